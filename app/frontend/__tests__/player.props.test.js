@@ -22,6 +22,7 @@ global.window = {
       invoke: vi.fn((cmd, args) => {
         if (cmd === 'audio_seek') return Promise.resolve();
         if (cmd === 'audio_set_volume') return Promise.resolve();
+        if (cmd === 'audio_set_muted') return Promise.resolve();
         if (cmd === 'audio_stop') return Promise.resolve();
         if (cmd === 'audio_play') return Promise.resolve();
         if (cmd === 'audio_pause') return Promise.resolve();
@@ -117,22 +118,22 @@ describe('Player Store - Property-Based Tests', () => {
       expect(store.volume).toBe(0);
     });
 
-    test.prop([fc.integer({ min: 1, max: 100 })])('setVolume with positive value unmutes', async (volume) => {
+    test.prop([fc.integer({ min: 0, max: 100 })])('setVolume while muted keeps mute', async (volume) => {
       store.muted = true;
 
       await store.setVolume(volume);
 
-      expect(store.muted).toBe(false);
+      expect(store.muted).toBe(true);
       expect(store.volume).toBe(volume);
     });
 
-    it('toggleMute twice restores original volume', async () => {
+    it('toggleMute twice keeps the volume level', async () => {
       const originalVolume = 75;
       await store.setVolume(originalVolume);
 
       await store.toggleMute();
       expect(store.muted).toBe(true);
-      expect(store.volume).toBe(0);
+      expect(store.volume).toBe(originalVolume);
 
       await store.toggleMute();
       expect(store.muted).toBe(false);
@@ -28,8 +28,13 @@ vi.mock('../js/api.js', () => ({
       add: vi.fn().mockResolvedValue({}),
       remove: vi.fn().mockResolvedValue({}),
       clear: vi.fn().mockResolvedValue({}),
+      replace: vi.fn().mockResolvedValue({}),
+      shuffle: vi.fn().mockResolvedValue({}),
+      reshuffle: vi.fn().mockResolvedValue({}),
+      reshuffleCycle: vi.fn().mockResolvedValue({}),
       move: vi.fn().mockResolvedValue({}),
       setShuffle: vi.fn().mockResolvedValue({}),
+      setShuffleMode: vi.fn().mockResolvedValue({}),
       setLoop: vi.fn().mockResolvedValue({}),
       setCurrentIndex: vi.fn().mockResolvedValue({}),
     }
@@ -42,6 +47,7 @@ global.window = {
 };
 
 // Import after mocks
+import { api } from '../js/api.js';
 import { createQueueStore } from '../js/stores/queue.js';
 
 // Arbitraries for generating test data
@@ -74,52 +80,45 @@ describe('Queue Store - Property-Based Tests', () => {
   });
 
   describe('Shuffle Invariants', () => {
-    test.prop([trackListArbitrary])('shuffle preserves all tracks (current track stored separately)', async (tracks) => {
+    test.prop([trackListArbitrary])('moving the current track to the front preserves all tracks', async (tracks) => {
       // Need at least 2 tracks to shuffle
       fc.pre(tracks.length >= 2);
 
       // Setup
       store.items = [...tracks];
       store._originalOrder = [...tracks];
-      store.currentIndex = 0;
+      store.currentIndex = tracks.length - 1;
 
       // Get original track IDs
       const originalIds = new Set(tracks.map(t => t.id));
 
-      // Shuffle
-      store._shuffleItems();
+      store._moveCurrentToFront();
 
       // Verify same tracks are preserved (all tracks still in queue)
-      const shuffledIds = new Set(store.items.map(t => t.id));
-      expect(shuffledIds).toEqual(originalIds);
+      const movedIds = new Set(store.items.map(t => t.id));
+      expect(movedIds).toEqual(originalIds);
 
       // Queue should have same length (current track stays at index 0)
       expect(store.items.length).toBe(tracks.length);
     });
 
-    test.prop([trackListArbitrary])('shuffle twice produces different order (probabilistic)', async (tracks) => {
-      // Skip if too few tracks to shuffle meaningfully
-      fc.pre(tracks.length >= 3);
-
+    it('toggling shuffle on shuffles on the backend and reloads the queue', async () => {
+      const tracks = [1, 2, 3, 4].map(id => ({ id, title: `Track ${id}` }));
+      const shuffled = [tracks[2], tracks[0], tracks[3], tracks[1]];
       store.items = [...tracks];
       store._originalOrder = [...tracks];
-      store.currentIndex = 0;
-
-      // First shuffle - current track at index 0
-      store._shuffleItems();
-      const firstShuffle = store.items.map(t => t.id);
-
-      // Queue should have same length after shuffle
-      expect(store.items.length).toBe(tracks.length);
+      store.currentIndex = 2;
+      store.shuffle = false;
+      api.queue.reshuffle.mockClear();
+      api.queue.get.mockResolvedValueOnce({ items: shuffled.map(track => ({ track })) });
 
-      // Second shuffle (simulating re-shuffle at end of queue with loop)
-      store._shuffleItems();
-      const secondShuffle = store.items.map(t => t.id);
+      await store.toggleShuffle();
 
-      // With 3+ tracks, probability of identical shuffle is low
-      // (we accept some false negatives for simplicity)
-      // Both shuffles should maintain all tracks
-      expect(store.items.length).toBe(tracks.length);
+      // Current track goes first, then the backend shuffles the rest
+      expect(api.queue.replace).toHaveBeenLastCalledWith([3, 1, 2, 4], 0);
+      expect(api.queue.reshuffle).toHaveBeenCalledTimes(1);
+      expect(store.items.map(t => t.id)).toEqual([3, 1, 4, 2]);
+      expect(store.currentIndex).toBe(0);
     });
 
     test.prop([trackListArbitrary])('shuffle keeps current track at index 0 (task-213)', async (tracks) => {
@@ -131,7 +130,7 @@ describe('Queue Store - Property-Based Tests', () => {
       store.currentIndex = currentIdx;
       const currentTrack = tracks[currentIdx];
 
-      store._shuffleItems();
+      store._moveCurrentToFront();
 
       // Current track should be at index 0
       expect(store.items[0].id).toBe(currentTrack.id);
@@ -155,8 +154,8 @@ describe('Queue Store - Property-Based Tests', () => {
       store._originalOrder = [trackA1, trackB, trackA2, trackC];
       store.currentIndex = 2; // Playing second occurrence of A (trackA2)
 
-      // Shuffle the queue
-      store._shuffleItems();
+      // Move the current track ahead of the shuffle
+      store._moveCurrentToFront();
 
       // Queue should have 4 items (current track stays at index 0)
       expect(store.items.length).toBe(4);
@@ -194,7 +193,7 @@ describe('Queue Store - Property-Based Tests', () => {
       expect(restoredOrder).toEqual(originalOrder);
     });
 
-    test.prop([trackListArbitrary])('_reshuffleForLoopRestart does not put just-played track first (task-222)', async (tracks) => {
+    test.prop([trackListArbitrary])('_reshuffleForLoopRestart takes the backend order with just-played track last (task-222)', async (tracks) => {
       // Need at least 2 tracks for reshuffle to be meaningful
       fc.pre(tracks.length >= 2);
 
@@ -203,12 +202,14 @@ describe('Queue Store - Property-Based Tests', () => {
       // Set current index to last track (simulating end of queue)
       store.currentIndex = tracks.length - 1;
       const justPlayedTrack = tracks[tracks.length - 1];
+      const nextCycle = [...tracks.slice(0, -1).reverse(), justPlayedTrack];
+      api.queue.get.mockResolvedValueOnce({ items: nextCycle.map(track => ({ track })) });
 
       // Reshuffle for loop restart
-      store._reshuffleForLoopRestart();
+      await store._reshuffleForLoopRestart();
 
-      // Just-played track should NOT be at index 0
-      expect(store.items[0].id).not.toBe(justPlayedTrack.id);
+      // The backend is told which track ended the cycle
+      expect(api.queue.reshuffleCycle).toHaveBeenLastCalledWith(tracks.length - 1);
 
       // Just-played track should be at the END
       expect(store.items[store.items.length - 1].id).toBe(justPlayedTrack.id);
@@ -217,15 +218,33 @@ describe('Queue Store - Property-Based Tests', () => {
       expect(store.items.length).toBe(tracks.length);
 
       // Original order should be updated to new shuffle
-      expect(store._originalOrder.length).toBe(tracks.length);
+      expect(store._originalOrder.map(t => t.id)).toEqual(nextCycle.map(t => t.id));
+    });
+
+    it('setShuffleMode leaves the reshuffle to the backend and reloads', async () => {
+      const tracks = [1, 2, 3].map(id => ({ id, title: `Track ${id}` }));
+      store.items = [...tracks];
+      store.currentIndex = 0;
+      store.shuffle = true;
+      store.shuffleMode = 'tracks';
+      api.queue.replace.mockClear();
+      api.queue.get.mockResolvedValueOnce({
+        items: [tracks[0], tracks[2], tracks[1]].map(track => ({ track })),
+      });
+
+      await store.setShuffleMode('albums');
+
+      expect(api.queue.setShuffleMode).toHaveBeenLastCalledWith('albums');
+      expect(api.queue.replace).not.toHaveBeenCalled();
+      expect(store.items.map(t => t.id)).toEqual([1, 3, 2]);
     });
 
-    it('_reshuffleForLoopRestart with single track does nothing', () => {
+    it('_reshuffleForLoopRestart with single track does nothing', async () => {
       const trackA = { id: 1, title: 'Track A', artist: 'Artist', album: 'Album' };
       store.items = [trackA];
       store.currentIndex = 0;
 
-      store._reshuffleForLoopRestart();
+      await store._reshuffleForLoopRestart();
 
       // Single track - nothing should change
       expect(store.items.length).toBe(1);
@@ -511,7 +530,7 @@ describe('Queue Store - Property-Based Tests', () => {
       await expect(store.remove(0)).resolves.not.toThrow();
       await expect(store.reorder(0, 1)).resolves.not.toThrow();
 
-      store._shuffleItems();
+      store._moveCurrentToFront();
       expect(store.items.length).toBe(0);
     });
 
@@ -528,8 +547,8 @@ describe('Queue Store - Property-Based Tests', () => {
       store.items = [track];
       store.currentIndex = 0;
 
-      // Shuffle should not change anything
-      store._shuffleItems();
+      // Moving to the front should not change anything
+      store._moveCurrentToFront();
       expect(store.items[0].id).toBe(track.id);
 
       // Remove should empty queue
@@ -51,13 +51,45 @@ async function request(endpoint, options = {}) {
  * Custom API error class
  */
 export class ApiError extends Error {
-  constructor(status, message) {
+  constructor(status, message, code = null, details = null) {
     super(message);
     this.name = 'ApiError';
     this.status = status;
+    this.code = code;
+    this.details = details;
+  }
+
+  /** Whether the same call may succeed if retried shortly */
+  get retryable() {
+    return this.code === 'db_busy';
   }
 }
 
+// HTTP-style status for each structured command error code
+const ERROR_CODE_STATUS = {
+  not_found: 404,
+  invalid_input: 400,
+  conflict: 409,
+  permission_denied: 403,
+  db_busy: 503,
+};
+
+/**
+ * Convert a rejected Tauri invoke into an ApiError
+ *
+ * Commands reject with either a plain string or a structured
+ * `{ code, message, details }` error.
+ * @param {string|object} error - Rejection value from invoke
+ * @returns {ApiError}
+ */
+function tauriError(error) {
+  if (error && typeof error === 'object' && 'code' in error) {
+    const status = ERROR_CODE_STATUS[error.code] || 500;
+    return new ApiError(status, error.message, error.code, error.details || null);
+  }
+  return new ApiError(500, error.toString());
+}
+
 /**
  * API client object with all endpoints
  */
@@ -79,6 +111,13 @@ export const api = {
      * Get all tracks in library (uses Tauri command)
      * @param {object} params - Query parameters
      * @param {string} [params.search] - Search query
+     * @param {string[]} [params.tags] - Custom tags to filter on
+     * @param {string} [params.tagMatch] - Whether tracks need 'any' (default) or 'all' of the tags
+     * @param {boolean} [params.favoritesOnly] - Only tracks in a favorites list
+     * @param {number} [params.minRating] - Only tracks rated at least this many stars (1-5)
+     * @param {Array<{field: string, op: string, value: string}>} [params.dateFilters] - Date
+     *   conditions on 'added_date', 'last_played' or 'last_seen', with op 'before', 'after' or
+     *   'within' and a date or relative value like 'now-14d' (units d, w, M, y)
      * @param {string} [params.sort] - Sort field
      * @param {string} [params.order] - Sort order ('asc' or 'desc')
      * @param {number} [params.limit] - Max results
@@ -92,6 +131,11 @@ export const api = {
             search: params.search || null,
             artist: params.artist || null,
             album: params.album || null,
+            tags: params.tags?.length ? params.tags : null,
+            tagMatch: params.tagMatch || null,
+            favoritesOnly: params.favoritesOnly || null,
+            minRating: params.minRating || null,
+            dateFilters: params.dateFilters?.length ? params.dateFilters : null,
             sortBy: params.sort || null,
             sortOrder: params.order || null,
             limit: params.limit || null,
@@ -99,7 +143,7 @@ export const api = {
           });
         } catch (error) {
           console.error('[api.library.getTracks] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // Fallback to HTTP
@@ -124,7 +168,7 @@ export const api = {
           return await invoke('library_get_track', { trackId: id });
         } catch (error) {
           console.error('[api.library.getTrack] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}`);
@@ -149,7 +193,7 @@ export const api = {
           };
         } catch (error) {
           console.error('[api.library.scan] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/library/scan', {
@@ -168,12 +212,78 @@ export const api = {
           return await invoke('library_get_stats');
         } catch (error) {
           console.error('[api.library.getStats] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/library/stats');
     },
 
+    /**
+     * Recount library statistics, bypassing the cache (uses Tauri command)
+     * @returns {Promise<{total_tracks: number, total_duration: number, total_size: number, total_artists: number, total_albums: number}>}
+     */
+    async recomputeStats() {
+      if (invoke) {
+        try {
+          return await invoke('library_recompute_stats');
+        } catch (error) {
+          console.error('[api.library.recomputeStats] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Recomputing stats not available in browser mode');
+    },
+
+    /**
+     * Get disk usage by artist, album, or folder, largest first (uses Tauri command)
+     * @param {string} groupBy - 'artist', 'album', or 'folder'
+     * @returns {Promise<Array<{name: string|null, artist: string|null, total_size: number, track_count: number}>>}
+     */
+    async getSizeBreakdown(groupBy) {
+      if (invoke) {
+        try {
+          return await invoke('library_get_size_breakdown', { groupBy });
+        } catch (error) {
+          console.error('[api.library.getSizeBreakdown] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Size breakdown not available in browser mode');
+    },
+
+    /**
+     * Find album discs with gaps in their track numbers (uses Tauri command)
+     * @returns {Promise<Array<{album_key: string, album: string, artist: string|null, disc_number: number, expected_total: number, present_count: number, missing_numbers: number[]}>>}
+     */
+    async findIncompleteAlbums() {
+      if (invoke) {
+        try {
+          return await invoke('library_find_incomplete_albums');
+        } catch (error) {
+          console.error('[api.library.findIncompleteAlbums] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Incomplete album search not available in browser mode');
+    },
+
+    /**
+     * Get tracks changed or deleted since a library change sequence (uses Tauri command)
+     * @param {number} seq - change_seq from the last library:updated event or call
+     * @returns {Promise<{seq: number, changed: number[], deleted: number[]}>}
+     */
+    async getChangesSince(seq) {
+      if (invoke) {
+        try {
+          return await invoke('library_get_changes_since', { seq });
+        } catch (error) {
+          console.error('[api.library.getChangesSince] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Library change tracking not available in browser mode');
+    },
+
     /**
      * Delete a track from library (uses Tauri command)
      * @param {number} id - Track ID
@@ -185,7 +295,7 @@ export const api = {
           return await invoke('library_delete_track', { trackId: id });
         } catch (error) {
           console.error('[api.library.deleteTrack] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}`, {
@@ -204,7 +314,7 @@ export const api = {
           return await invoke('library_update_play_count', { trackId: id });
         } catch (error) {
           console.error('[api.library.updatePlayCount] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/play-count`, {
@@ -212,6 +322,94 @@ export const api = {
       });
     },
 
+    /**
+     * Correct when a track was added to the library (uses Tauri command)
+     * @param {number} id - Track ID
+     * @param {string} date - RFC 3339 timestamp, 'YYYY-MM-DD HH:MM:SS' (UTC), or 'YYYY-MM-DD'
+     * @returns {Promise<object>} Updated track object
+     */
+    async setAddedDate(id, date) {
+      if (invoke) {
+        try {
+          return await invoke('library_set_added_date', { trackId: id, date });
+        } catch (error) {
+          console.error('[api.library.setAddedDate] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Setting added dates not available in browser mode');
+    },
+
+    /**
+     * Add a custom tag to a track (uses Tauri command)
+     * Tags are trimmed and lowercased
+     * @param {number} id - Track ID
+     * @param {string} tag - Tag to add
+     * @returns {Promise<string[]>} The track's tags
+     */
+    async addTag(id, tag) {
+      if (invoke) {
+        try {
+          return await invoke('library_add_tag', { trackId: id, tag });
+        } catch (error) {
+          console.error('[api.library.addTag] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Tags not available in browser mode');
+    },
+
+    /**
+     * Remove a custom tag from a track (uses Tauri command)
+     * @param {number} id - Track ID
+     * @param {string} tag - Tag to remove
+     * @returns {Promise<string[]>} The track's tags
+     */
+    async removeTag(id, tag) {
+      if (invoke) {
+        try {
+          return await invoke('library_remove_tag', { trackId: id, tag });
+        } catch (error) {
+          console.error('[api.library.removeTag] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Tags not available in browser mode');
+    },
+
+    /**
+     * Get the custom tags of a track (uses Tauri command)
+     * @param {number} id - Track ID
+     * @returns {Promise<string[]>} Tags, alphabetically
+     */
+    async getTags(id) {
+      if (invoke) {
+        try {
+          return await invoke('library_get_tags', { trackId: id });
+        } catch (error) {
+          console.error('[api.library.getTags] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Tags not available in browser mode');
+    },
+
+    /**
+     * Get every custom tag in use (uses Tauri command)
+     * @returns {Promise<Array<{tag: string, track_count: number}>>} Tags, alphabetically
+     */
+    async getAllTags() {
+      if (invoke) {
+        try {
+          return await invoke('library_get_all_tags');
+        } catch (error) {
+          console.error('[api.library.getAllTags] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Tags not available in browser mode');
+    },
+
     /**
      * Rescan a track's metadata from its file (uses Tauri command)
      * @param {number} id - Track ID
@@ -223,7 +421,7 @@ export const api = {
           return await invoke('library_rescan_track', { trackId: id });
         } catch (error) {
           console.error('[api.library.rescanTrack] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/rescan`, {
@@ -242,11 +440,11 @@ export const api = {
           return await invoke('library_get_artwork', { trackId: id });
         } catch (error) {
           // Not found is returned as null, not an error
-          if (error.toString().includes('not found')) {
+          if (error?.code === 'not_found') {
             return null;
           }
           console.error('[api.library.getArtwork] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       try {
@@ -269,11 +467,11 @@ export const api = {
         try {
           return await invoke('library_get_artwork_url', { trackId: id });
         } catch (error) {
-          if (error.toString().includes('not found')) {
+          if (error?.code === 'not_found') {
             return null;
           }
           console.error('[api.library.getArtworkUrl] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // HTTP fallback - get artwork and convert to data URL
@@ -284,22 +482,272 @@ export const api = {
       return null;
     },
 
+    /**
+     * Get one artwork data URL for a whole album (uses Tauri command)
+     * @param {string} albumKey - Album key: lowercased album artist (or artist) and album
+     * @returns {Promise<string|null>} Data URL, or null if no track of the album has artwork
+     */
+    async getAlbumArtworkUrl(albumKey) {
+      if (invoke) {
+        try {
+          return await invoke('library_get_album_artwork', { albumKey });
+        } catch (error) {
+          console.error('[api.library.getAlbumArtworkUrl] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Album artwork not available in browser mode');
+    },
+
+    /**
+     * Get every album, with album overrides applied (uses Tauri command)
+     * @returns {Promise<Array<{album_key: string, album: string, artist: string|null, year: string|null, track_count: number, total_duration: number, overridden: boolean}>>}
+     */
+    async getAlbums() {
+      if (invoke) {
+        try {
+          return await invoke('library_get_albums');
+        } catch (error) {
+          console.error('[api.library.getAlbums] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Album list not available in browser mode');
+    },
+
+    /**
+     * Override how an album is shown, without changing its files (uses Tauri command)
+     * @param {string} albumKey - Album key: lowercased album artist (or artist) and album
+     * @param {{album?: string, albumArtist?: string, year?: string, artworkPath?: string}} fields - Unset fields fall back to the tags
+     * @returns {Promise<{album_key: string, album: string|null, album_artist: string|null, year: string|null, artwork_path: string|null}>}
+     */
+    async setAlbumOverride(albumKey, fields = {}) {
+      if (invoke) {
+        try {
+          return await invoke('library_set_album_override', {
+            albumKey,
+            album: fields.album ?? null,
+            albumArtist: fields.albumArtist ?? null,
+            year: fields.year ?? null,
+            artworkPath: fields.artworkPath ?? null,
+          });
+        } catch (error) {
+          console.error('[api.library.setAlbumOverride] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Album overrides not available in browser mode');
+    },
+
+    /**
+     * Remove an album's override (uses Tauri command)
+     * @param {string} albumKey - Album key: lowercased album artist (or artist) and album
+     * @returns {Promise<boolean>} Whether the album had an override
+     */
+    async clearAlbumOverride(albumKey) {
+      if (invoke) {
+        try {
+          return await invoke('library_clear_album_override', { albumKey });
+        } catch (error) {
+          console.error('[api.library.clearAlbumOverride] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Album overrides not available in browser mode');
+    },
+
+    /**
+     * Forget which albums had no online artwork so they're looked up again (uses Tauri command)
+     * @returns {Promise<number>} Number of albums cleared
+     */
+    async clearArtworkMisses() {
+      if (invoke) {
+        try {
+          return await invoke('library_clear_artwork_misses');
+        } catch (error) {
+          console.error('[api.library.clearArtworkMisses] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Clearing artwork misses not available in browser mode');
+    },
+
+    /**
+     * Re-read a track's artwork from its file, replacing the cached copy (uses Tauri command)
+     * @param {number} id - Track ID
+     * @returns {Promise<{data: string, mime_type: string, source: string}|null>}
+     */
+    async refreshArtwork(id) {
+      if (invoke) {
+        try {
+          return await invoke('library_refresh_artwork', { trackId: id });
+        } catch (error) {
+          console.error('[api.library.refreshArtwork] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Refreshing artwork not available in browser mode');
+    },
+
+    /**
+     * Check that tracks decode, flagging unplayable files (uses Tauri command)
+     * Progress is emitted as 'library:verify-progress' events.
+     * @param {number[]} trackIds - Track IDs to check
+     * @returns {Promise<Array<{track_id: number, filepath: string, error: string}>>}
+     */
+    async verifyPlayable(trackIds) {
+      if (invoke) {
+        try {
+          return await invoke('library_verify_playable', { trackIds });
+        } catch (error) {
+          console.error('[api.library.verifyPlayable] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playability checks not available in browser mode');
+    },
+
+    /**
+     * Check that every library file exists, updating missing flags (uses Tauri command)
+     * @returns {Promise<{checked: number, marked_missing: number[], recovered: number[]}>}
+     */
+    async verifyAll() {
+      if (invoke) {
+        try {
+          return await invoke('library_verify_all');
+        } catch (error) {
+          console.error('[api.library.verifyAll] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Library checks not available in browser mode');
+    },
+
+    /**
+     * Get the IDs of tracks whose last decode check failed (uses Tauri command)
+     * @returns {Promise<number[]>}
+     */
+    async getUnplayable() {
+      if (invoke) {
+        try {
+          return await invoke('library_get_unplayable');
+        } catch (error) {
+          console.error('[api.library.getUnplayable] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return [];
+    },
+
+    /**
+     * Import play counts, ratings, loved tracks, and playlists from an iTunes or
+     * Music.app Library.xml export (uses Tauri command). Only tracks already in
+     * the library are updated. Emits library:import-progress while running.
+     * @param {string} path - Path to the exported Library.xml
+     * @returns {Promise<object>} Matched/unmatched counts and what was imported
+     */
+    async importItunes(path) {
+      if (invoke) {
+        try {
+          return await invoke('library_import_itunes', { path });
+        } catch (error) {
+          console.error('[api.library.importItunes] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'iTunes import not available in browser mode');
+    },
+
+    /**
+     * Export playlists as .m3u8 files into a folder (uses Tauri command).
+     * Folders export the playlists inside them; an empty list exports every
+     * playlist plus the whole library.
+     * @param {string} dir - Absolute path of the export folder
+     * @param {number[]} playlistIds - Playlists or folders to export
+     * @param {boolean} relative - Write track paths relative to the folder
+     * @returns {Promise<string[]>} Paths of the written files
+     */
+    async exportPlaylists(dir, playlistIds = [], relative = false) {
+      if (invoke) {
+        try {
+          return await invoke('library_export_playlists', { dir, playlistIds, relative });
+        } catch (error) {
+          console.error('[api.library.exportPlaylists] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playlist export not available in browser mode');
+    },
+
+    /**
+     * Export play history to a CSV listening log (uses Tauri command)
+     * @param {string} path - Absolute path of the CSV file to write
+     * @param {{from?: string, to?: string}} [range] - Inclusive dates or timestamps
+     * @returns {Promise<number>} Number of plays written
+     */
+    async exportListeningLog(path, range = null) {
+      if (invoke) {
+        try {
+          return await invoke('listening_log_export', { path, range });
+        } catch (error) {
+          console.error('[api.library.exportListeningLog] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Listening log export not available in browser mode');
+    },
+
+    /**
+     * Turn appending eligible plays to the local listening log on or off (uses Tauri command)
+     * @param {boolean} enabled - Whether to append plays
+     * @returns {Promise<void>}
+     */
+    async setListeningLogEnabled(enabled) {
+      if (invoke) {
+        try {
+          return await invoke('listening_log_set_enabled', { enabled });
+        } catch (error) {
+          console.error('[api.library.setListeningLogEnabled] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Listening log not available in browser mode');
+    },
+
     /**
      * Get all tracks marked as missing (uses Tauri command)
+     * @param {number|null} olderThanDays - Only tracks missing for longer than this
      * @returns {Promise<{tracks: Array, total: number}>}
      */
-    async getMissing() {
+    async getMissing(olderThanDays = null) {
       if (invoke) {
         try {
-          return await invoke('library_get_missing');
+          return await invoke('library_get_missing', { olderThanDays });
         } catch (error) {
           console.error('[api.library.getMissing] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/library/missing');
     },
 
+    /**
+     * Delete tracks missing for longer than a number of days (uses Tauri command)
+     * @param {number} olderThanDays - Cutoff in days; required
+     * @returns {Promise<number>} Number of tracks deleted
+     */
+    async purgeMissing(olderThanDays) {
+      if (invoke) {
+        try {
+          return await invoke('library_purge_missing', { olderThanDays });
+        } catch (error) {
+          console.error('[api.library.purgeMissing] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Purging missing tracks not available in browser mode');
+    },
+
     /**
      * Locate a missing track by providing a new file path (uses Tauri command)
      * @param {number} id - Track ID
@@ -312,7 +760,7 @@ export const api = {
           return await invoke('library_locate_track', { trackId: id, newPath });
         } catch (error) {
           console.error('[api.library.locate] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/locate`, {
@@ -321,6 +769,44 @@ export const api = {
       });
     },
 
+    /**
+     * Point a track at a different file of the same song, keeping its play history (uses Tauri command)
+     * @param {number} id - Track ID
+     * @param {string} newPath - Path of the replacement file
+     * @returns {Promise<object>} Updated track object
+     */
+    async replaceFile(id, newPath) {
+      if (invoke) {
+        try {
+          return await invoke('library_replace_track_file', { trackId: id, newPath });
+        } catch (error) {
+          console.error('[api.library.replaceFile] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Replacing track files not available in browser mode');
+    },
+
+    /**
+     * Read a byte range of a track's file (uses Tauri command)
+     * @param {number} id - Track ID
+     * @param {number} start - Offset of the first byte
+     * @param {number} len - Number of bytes wanted; clamped to the file and a 4 MiB cap
+     * @returns {Promise<{data: string, start: number, len: number, total_size: number, content_type: string}>}
+     *   Base64 bytes with the file's total size and content type
+     */
+    async readRange(id, start, len) {
+      if (invoke) {
+        try {
+          return await invoke('media_read_range', { trackId: id, start, len });
+        } catch (error) {
+          console.error('[api.library.readRange] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Reading media ranges not available in browser mode');
+    },
+
     /**
      * Check if a track's file exists and update its missing status (uses Tauri command)
      * @param {number} id - Track ID
@@ -332,7 +818,7 @@ export const api = {
           return await invoke('library_check_status', { trackId: id });
         } catch (error) {
           console.error('[api.library.checkStatus] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/check-status`, {
@@ -340,6 +826,23 @@ export const api = {
       });
     },
 
+    /**
+     * Check whether a file or folder is already in the library or watched (uses Tauri command)
+     * @param {string} path - File or folder path
+     * @returns {Promise<{path: string, is_directory: boolean, in_library: boolean, missing: boolean, watched_folder: string|null, indexed_files: number, new_files: number}>}
+     */
+    async pathStatus(path) {
+      if (invoke) {
+        try {
+          return await invoke('library_path_status', { path });
+        } catch (error) {
+          console.error('[api.library.pathStatus] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Path status not available in browser mode');
+    },
+
     /**
      * Mark a track as missing (uses Tauri command)
      * @param {number} id - Track ID
@@ -351,7 +854,7 @@ export const api = {
           return await invoke('library_mark_missing', { trackId: id });
         } catch (error) {
           console.error('[api.library.markMissing] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/mark-missing`, {
@@ -370,7 +873,7 @@ export const api = {
           return await invoke('library_mark_present', { trackId: id });
         } catch (error) {
           console.error('[api.library.markPresent] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/library/${encodeURIComponent(id)}/mark-present`, {
@@ -388,166 +891,430 @@ export const api = {
      * Get current queue (uses Tauri command)
      * @returns {Promise<{items: Array, count: number}>} Queue response
      */
-    async get() {
+    async get() {
+      if (invoke) {
+        try {
+          return await invoke('queue_get');
+        } catch (error) {
+          console.error('[api.queue.get] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue');
+    },
+
+    /**
+     * Add track(s) to queue by track IDs (uses Tauri command)
+     * @param {number|number[]} trackIds - Track ID(s) to add
+     * @param {number} [position] - Position to insert at (end if omitted)
+     * @param {string} [source] - Where the tracks come from, e.g. "playlist:12" or "album:Abbey Road"
+     * @returns {Promise<{added: number, queue_length: number}>}
+     */
+    async add(trackIds, position, source) {
+      const ids = Array.isArray(trackIds) ? trackIds : [trackIds];
+      if (invoke) {
+        try {
+          return await invoke('queue_add', {
+            trackIds: ids,
+            position: position ?? null,
+            source: source ?? null,
+          });
+        } catch (error) {
+          console.error('[api.queue.add] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/add', {
+        method: 'POST',
+        body: JSON.stringify({ track_ids: ids, position, source }),
+      });
+    },
+
+    /**
+     * Add files directly to queue (for drag-and-drop) (uses Tauri command)
+     * @param {string[]} filepaths - File paths to add
+     * @param {number} [position] - Position to insert at (end if omitted)
+     * @param {string} [source] - Where the files come from
+     * @returns {Promise<{added: number, queue_length: number, tracks: Array}>}
+     */
+    async addFiles(filepaths, position, source) {
+      if (invoke) {
+        try {
+          return await invoke('queue_add_files', {
+            filepaths,
+            position: position ?? null,
+            source: source ?? null,
+          });
+        } catch (error) {
+          console.error('[api.queue.addFiles] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/add-files', {
+        method: 'POST',
+        body: JSON.stringify({ filepaths, position, source }),
+      });
+    },
+
+    /**
+     * Get what the queue is playing from (uses Tauri command)
+     * @returns {Promise<{source: string|null, item_count: number}>}
+     */
+    async getContext() {
+      if (invoke) {
+        try {
+          return await invoke('queue_get_context');
+        } catch (error) {
+          console.error('[api.queue.getContext] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/context');
+    },
+
+    /**
+     * Remove track from queue (uses Tauri command)
+     * @param {number} position - Position in queue to remove
+     * @returns {Promise<void>}
+     */
+    async remove(position) {
+      if (invoke) {
+        try {
+          return await invoke('queue_remove', { position });
+        } catch (error) {
+          console.error('[api.queue.remove] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request(`/queue/${position}`, {
+        method: 'DELETE',
+      });
+    },
+
+    /**
+     * Clear the entire queue (uses Tauri command)
+     * @returns {Promise<void>}
+     */
+    async clear() {
+      if (invoke) {
+        try {
+          return await invoke('queue_clear');
+        } catch (error) {
+          console.error('[api.queue.clear] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/clear', {
+        method: 'POST',
+      });
+    },
+
+    /**
+     * Replace the whole queue in one step (uses Tauri command)
+     * @param {number[]} trackIds - Track IDs in play order
+     * @param {number} startIndex - Index into trackIds to start at
+     * @param {string} [source] - Where the tracks came from, e.g. "album:Abbey Road"
+     * @returns {Promise<{queue_length: number, current_index: number, skipped: number[]}>}
+     */
+    async replace(trackIds, startIndex = 0, source = null) {
+      if (invoke) {
+        try {
+          return await invoke('queue_replace', { trackIds, startIndex, source });
+        } catch (error) {
+          console.error('[api.queue.replace] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Queue replace not available in browser mode');
+    },
+
+    /**
+     * Re-take the metadata snapshots of queued tracks from the library
+     * (uses Tauri command)
+     * @returns {Promise<number>} Number of snapshots taken
+     */
+    async refreshMetadata() {
+      if (invoke) {
+        try {
+          return await invoke('queue_refresh_metadata');
+        } catch (error) {
+          console.error('[api.queue.refreshMetadata] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Queue snapshots not available in browser mode');
+    },
+
+    /**
+     * Move track within queue (reorder) (uses Tauri command)
+     * @param {number} from - Current position
+     * @param {number} to - New position
+     * @returns {Promise<{success: boolean, queue_length: number}>}
+     */
+    async move(from, to) {
+      if (invoke) {
+        try {
+          return await invoke('queue_reorder', {
+            fromPosition: from,
+            toPosition: to,
+          });
+        } catch (error) {
+          console.error('[api.queue.move] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/reorder', {
+        method: 'POST',
+        body: JSON.stringify({ from_position: from, to_position: to }),
+      });
+    },
+
+    /**
+     * Move several queue items to a drop target at once (uses Tauri command)
+     * @param {number[]} positions - Current positions of the items to move
+     * @param {number} toPosition - Position of the item to drop before, or the queue length to append
+     * @returns {Promise<{success: boolean, queue_length: number}>}
+     */
+    async reorderMany(positions, toPosition) {
+      if (invoke) {
+        try {
+          return await invoke('queue_reorder_many', { positions, toPosition });
+        } catch (error) {
+          console.error('[api.queue.reorderMany] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Multi-item queue reorder not available in browser mode');
+    },
+
+    /**
+     * Shuffle the queue (uses Tauri command)
+     * @param {boolean} [keepCurrent=true] - Keep currently playing track at position 0
+     * @returns {Promise<{success: boolean, queue_length: number}>}
+     */
+    async shuffle(keepCurrent = true) {
+      if (invoke) {
+        try {
+          return await invoke('queue_shuffle', { keepCurrent });
+        } catch (error) {
+          console.error('[api.queue.shuffle] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/queue/shuffle', {
+        method: 'POST',
+        body: JSON.stringify({ keep_current: keepCurrent }),
+      });
+    },
+
+    /**
+     * Reshuffle the tracks after the current one, seeded and persisted (uses Tauri command)
+     * Follows the shuffle mode and keeps repeats of a track apart
+     * @param {number} [seed] - Seed to reuse; a new one is picked when omitted
+     * @returns {Promise<{seed: number, queue_length: number}>}
+     */
+    async reshuffle(seed = null) {
+      if (invoke) {
+        try {
+          return await invoke('queue_reshuffle', { seed });
+        } catch (error) {
+          console.error('[api.queue.reshuffle] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Reshuffle not available in browser mode');
+    },
+
+    /**
+     * Reshuffle the queue for a new loop-all cycle (uses Tauri command)
+     * The ended track, or its album in album shuffle mode, goes last
+     * @param {number} ended - Index of the track that finished the cycle
+     * @returns {Promise<{seed: number, queue_length: number}>}
+     */
+    async reshuffleCycle(ended) {
+      if (invoke) {
+        try {
+          return await invoke('queue_reshuffle_cycle', { ended });
+        } catch (error) {
+          console.error('[api.queue.reshuffleCycle] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Reshuffle not available in browser mode');
+    },
+
+    /**
+     * Queue a folder's audio files in album order and play the first (uses Tauri command)
+     * @param {string} path - Absolute folder path
+     * @param {object} [options]
+     * @param {boolean} [options.recursive=true] - Include subfolders
+     * @param {boolean} [options.replace=true] - Replace the queue instead of appending
+     * @returns {Promise<{queued: number, queue_length: number, skipped: string[]}>}
+     */
+    async playFolder(path, { recursive = true, replace = true } = {}) {
+      if (invoke) {
+        try {
+          return await invoke('queue_play_folder', { path, recursive, replace });
+        } catch (error) {
+          console.error('[api.queue.playFolder] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playing folders not available in browser mode');
+    },
+
+    /**
+     * Replace the queue with a radio of the track's artist catalog (uses Tauri command)
+     * @param {number} trackId - Seed track ID
+     * @returns {Promise<{queued: number, unmatched: number, source: string}>}
+     */
+    async startArtistRadio(trackId) {
       if (invoke) {
         try {
-          return await invoke('queue_get');
+          return await invoke('queue_start_artist_radio', { trackId });
         } catch (error) {
-          console.error('[api.queue.get] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.startArtistRadio] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue');
+      throw new ApiError(500, 'Radio not available in browser mode');
     },
 
     /**
-     * Add track(s) to queue by track IDs (uses Tauri command)
-     * @param {number|number[]} trackIds - Track ID(s) to add
-     * @param {number} [position] - Position to insert at (end if omitted)
-     * @returns {Promise<{added: number, queue_length: number}>}
+     * Replace the queue with a radio of similar tracks (uses Tauri command)
+     * @param {number} trackId - Seed track ID
+     * @returns {Promise<{queued: number, unmatched: number, source: string}>}
      */
-    async add(trackIds, position) {
-      const ids = Array.isArray(trackIds) ? trackIds : [trackIds];
+    async startSimilarRadio(trackId) {
       if (invoke) {
         try {
-          return await invoke('queue_add', {
-            trackIds: ids,
-            position: position ?? null,
-          });
+          return await invoke('queue_start_similar_radio', { trackId });
         } catch (error) {
-          console.error('[api.queue.add] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.startSimilarRadio] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue/add', {
-        method: 'POST',
-        body: JSON.stringify({ track_ids: ids, position }),
-      });
+      throw new ApiError(500, 'Radio not available in browser mode');
     },
 
     /**
-     * Add files directly to queue (for drag-and-drop) (uses Tauri command)
-     * @param {string[]} filepaths - File paths to add
-     * @param {number} [position] - Position to insert at (end if omitted)
-     * @returns {Promise<{added: number, queue_length: number, tracks: Array}>}
+     * Replace the queue with the tracks that sound most like a track, from
+     * locally analyzed audio features (uses Tauri command)
+     * @param {number} trackId - Seed track ID
+     * @param {number} count - Most tracks to queue after the seed
+     * @returns {Promise<{queued: number, unmatched: number, source: string}>}
      */
-    async addFiles(filepaths, position) {
+    async playSimilarLocal(trackId, count) {
       if (invoke) {
         try {
-          return await invoke('queue_add_files', {
-            filepaths,
-            position: position ?? null,
-          });
+          return await invoke('queue_play_similar_local', { trackId, count });
         } catch (error) {
-          console.error('[api.queue.addFiles] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.playSimilarLocal] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue/add-files', {
-        method: 'POST',
-        body: JSON.stringify({ filepaths, position }),
-      });
+      throw new ApiError(500, 'Radio not available in browser mode');
+    },
+
+    save(state) {
+      console.debug('Queue save (local only):', state);
     },
 
     /**
-     * Remove track from queue (uses Tauri command)
-     * @param {number} position - Position in queue to remove
-     * @returns {Promise<void>}
+     * Get queue playback state (uses Tauri command)
+     * @returns {Promise<{current_index: number, shuffle_enabled: boolean, loop_mode: string, original_order_json: string|null}>}
      */
-    async remove(position) {
+    async getPlaybackState() {
       if (invoke) {
         try {
-          return await invoke('queue_remove', { position });
+          return await invoke('queue_get_playback_state');
         } catch (error) {
-          console.error('[api.queue.remove] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.getPlaybackState] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request(`/queue/${position}`, {
-        method: 'DELETE',
-      });
+      throw new ApiError(500, 'Queue playback state not available in browser mode');
     },
 
     /**
-     * Clear the entire queue (uses Tauri command)
-     * @returns {Promise<void>}
+     * Preview the tracks that play next, following shuffle and loop mode (uses Tauri command)
+     * @param {number} count - Number of upcoming items to return
+     * @returns {Promise<Array<{position: number, track: Object}>>}
      */
-    async clear() {
+    async peekUpcoming(count) {
       if (invoke) {
         try {
-          return await invoke('queue_clear');
+          return await invoke('queue_peek_upcoming', { count });
         } catch (error) {
-          console.error('[api.queue.clear] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.peekUpcoming] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue/clear', {
-        method: 'POST',
-      });
+      throw new ApiError(500, 'Upcoming queue preview not available in browser mode');
     },
 
     /**
-     * Move track within queue (reorder) (uses Tauri command)
-     * @param {number} from - Current position
-     * @param {number} to - New position
-     * @returns {Promise<{success: boolean, queue_length: number}>}
+     * Get the track that plays next, without changing queue state (uses Tauri command)
+     * @returns {Promise<Object|null>} Track, or null when nothing plays next
      */
-    async move(from, to) {
+    async peekNext() {
       if (invoke) {
         try {
-          return await invoke('queue_reorder', {
-            fromPosition: from,
-            toPosition: to,
-          });
+          return await invoke('queue_peek_next');
         } catch (error) {
-          console.error('[api.queue.move] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.peekNext] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue/reorder', {
-        method: 'POST',
-        body: JSON.stringify({ from_position: from, to_position: to }),
-      });
+      throw new ApiError(500, 'Queue preview not available in browser mode');
     },
 
     /**
-     * Shuffle the queue (uses Tauri command)
-     * @param {boolean} [keepCurrent=true] - Keep currently playing track at position 0
-     * @returns {Promise<{success: boolean, queue_length: number}>}
+     * Get the track "previous" goes to, without changing queue state (uses Tauri command)
+     * @returns {Promise<Object|null>} Track, or null at the start of the queue
      */
-    async shuffle(keepCurrent = true) {
+    async peekPrevious() {
       if (invoke) {
         try {
-          return await invoke('queue_shuffle', { keepCurrent });
+          return await invoke('queue_peek_previous');
         } catch (error) {
-          console.error('[api.queue.shuffle] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.peekPrevious] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      return request('/queue/shuffle', {
-        method: 'POST',
-        body: JSON.stringify({ keep_current: keepCurrent }),
-      });
+      throw new ApiError(500, 'Queue preview not available in browser mode');
     },
 
-    save(state) {
-      console.debug('Queue save (local only):', state);
+    /**
+     * Get queue item count and durations in play order (uses Tauri command)
+     * @returns {Promise<{count: number, current_index: number, total_duration: number, elapsed_duration: number, remaining_duration: number, unknown_durations: number}>}
+     */
+    async getTotals() {
+      if (invoke) {
+        try {
+          return await invoke('queue_get_totals');
+        } catch (error) {
+          console.error('[api.queue.getTotals] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Queue totals not available in browser mode');
     },
 
     /**
-     * Get queue playback state (uses Tauri command)
-     * @returns {Promise<{current_index: number, shuffle_enabled: boolean, loop_mode: string, original_order_json: string|null}>}
+     * Get the effective play order while shuffle is on (uses Tauri command)
+     * @returns {Promise<{order: number[], current: number, seed: number|null, shuffle_mode: string}>} Empty order when shuffle is off
      */
-    async getPlaybackState() {
+    async getShuffleOrder() {
       if (invoke) {
         try {
-          return await invoke('queue_get_playback_state');
+          return await invoke('queue_get_shuffle_order');
         } catch (error) {
-          console.error('[api.queue.getPlaybackState] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          console.error('[api.queue.getShuffleOrder] Tauri error:', error);
+          throw tauriError(error);
         }
       }
-      throw new ApiError(500, 'Queue playback state not available in browser mode');
+      throw new ApiError(500, 'Shuffle order not available in browser mode');
     },
 
     /**
@@ -561,7 +1328,7 @@ export const api = {
           return await invoke('queue_set_current_index', { index });
         } catch (error) {
           console.error('[api.queue.setCurrentIndex] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       console.debug('Queue setCurrentIndex (no-op in browser):', index);
@@ -578,12 +1345,30 @@ export const api = {
           return await invoke('queue_set_shuffle', { enabled });
         } catch (error) {
           console.error('[api.queue.setShuffle] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       console.debug('Queue setShuffle (no-op in browser):', enabled);
     },
 
+    /**
+     * Set shuffle mode in queue (uses Tauri command)
+     * While shuffle is on, the unplayed tracks are reshuffled in the new mode
+     * @param {string} mode - Shuffle mode ('tracks', 'albums')
+     * @returns {Promise<void>}
+     */
+    async setShuffleMode(mode) {
+      if (invoke) {
+        try {
+          return await invoke('queue_set_shuffle_mode', { mode });
+        } catch (error) {
+          console.error('[api.queue.setShuffleMode] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      console.debug('Queue setShuffleMode (no-op in browser):', mode);
+    },
+
     /**
      * Set loop mode in queue (uses Tauri command)
      * @param {string} mode - Loop mode ('none', 'all', 'one')
@@ -595,11 +1380,29 @@ export const api = {
           return await invoke('queue_set_loop', { mode });
         } catch (error) {
           console.error('[api.queue.setLoop] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       console.debug('Queue setLoop (no-op in browser):', mode);
     },
+
+    /**
+     * Set consume mode in queue (uses Tauri command)
+     * Played tracks are removed from the queue; takes precedence over loop 'all'
+     * @param {boolean} enabled - Whether consume is enabled
+     * @returns {Promise<void>}
+     */
+    async setConsume(enabled) {
+      if (invoke) {
+        try {
+          return await invoke('queue_set_consume', { enabled });
+        } catch (error) {
+          console.error('[api.queue.setConsume] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      console.debug('Queue setConsume (no-op in browser):', enabled);
+    },
   },
 
   // ============================================
@@ -623,7 +1426,7 @@ export const api = {
           });
         } catch (error) {
           console.error('[api.favorites.get] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // Fallback to HTTP
@@ -645,7 +1448,7 @@ export const api = {
           return await invoke('favorites_check', { trackId });
         } catch (error) {
           console.error('[api.favorites.check] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/favorites/${encodeURIComponent(trackId)}`);
@@ -669,7 +1472,7 @@ export const api = {
           if (error.toString().includes('not found')) {
             throw new ApiError(404, error.toString());
           }
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/favorites/${encodeURIComponent(trackId)}`, {
@@ -691,7 +1494,7 @@ export const api = {
           if (error.toString().includes('not in favorites')) {
             throw new ApiError(404, error.toString());
           }
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/favorites/${encodeURIComponent(trackId)}`, {
@@ -699,17 +1502,71 @@ export const api = {
       });
     },
 
+    /**
+     * Check which of several tracks are favorited (uses Tauri command)
+     * @param {number[]} trackIds - Track IDs
+     * @returns {Promise<Object<string, boolean>>} Favorite state keyed by track ID
+     */
+    async checkMany(trackIds) {
+      if (invoke) {
+        try {
+          return await invoke('favorites_check_many', { trackIds });
+        } catch (error) {
+          console.error('[api.favorites.checkMany] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Batch favorites not available in browser mode');
+    },
+
+    /**
+     * Add several tracks to favorites in one transaction (uses Tauri command)
+     * Fails without adding anything if a track doesn't exist
+     * @param {number[]} trackIds - Track IDs
+     * @returns {Promise<number[]>} IDs that were added (already favorited ones are skipped)
+     */
+    async addMany(trackIds) {
+      if (invoke) {
+        try {
+          return await invoke('favorites_add_many', { trackIds });
+        } catch (error) {
+          console.error('[api.favorites.addMany] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Batch favorites not available in browser mode');
+    },
+
+    /**
+     * Remove several tracks from favorites in one transaction (uses Tauri command)
+     * @param {number[]} trackIds - Track IDs
+     * @returns {Promise<number[]>} IDs that were removed
+     */
+    async removeMany(trackIds) {
+      if (invoke) {
+        try {
+          return await invoke('favorites_remove_many', { trackIds });
+        } catch (error) {
+          console.error('[api.favorites.removeMany] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Batch favorites not available in browser mode');
+    },
+
     /**
      * Get top 25 most played tracks (uses Tauri command)
+     * @param {object} [params] - Query parameters
+     * @param {number} [params.limit] - Number of tracks, default 25, max 500
      * @returns {Promise<{tracks: Array}>}
      */
-    async getTop25() {
+    async getTop25(params = {}) {
       if (invoke) {
         try {
-          return await invoke('favorites_get_top25');
+          return await invoke('favorites_get_top25', { limit: params.limit ?? null });
         } catch (error) {
           console.error('[api.favorites.getTop25] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/favorites/top25');
@@ -720,7 +1577,8 @@ export const api = {
      * @param {object} params - Query parameters
      * @param {number} [params.days] - Number of days to look back (default 14)
      * @param {number} [params.limit] - Max results (default 100)
-     * @returns {Promise<{tracks: Array, days: number}>}
+     * @param {string} [params.since] - Only plays at or after this timestamp; overrides days
+     * @returns {Promise<{tracks: Array, days: number}>} One entry per play, with played_at
      */
     async getRecentlyPlayed(params = {}) {
       if (invoke) {
@@ -728,10 +1586,11 @@ export const api = {
           return await invoke('favorites_get_recently_played', {
             days: params.days ?? null,
             limit: params.limit ?? null,
+            since: params.since ?? null,
           });
         } catch (error) {
           console.error('[api.favorites.getRecentlyPlayed] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // Fallback to HTTP
@@ -758,7 +1617,7 @@ export const api = {
           });
         } catch (error) {
           console.error('[api.favorites.getRecentlyAdded] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // Fallback to HTTP
@@ -860,7 +1719,7 @@ export const api = {
           return response.playlists || [];
         } catch (error) {
           console.error('[api.playlists.getAll] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       const response = await request('/playlists');
@@ -878,7 +1737,7 @@ export const api = {
           return await invoke('playlist_generate_name', { base });
         } catch (error) {
           console.error('[api.playlists.generateName] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       const query = new URLSearchParams({ base });
@@ -897,7 +1756,7 @@ export const api = {
           return response.playlist;
         } catch (error) {
           console.error('[api.playlists.create] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/playlists', {
@@ -909,20 +1768,50 @@ export const api = {
     /**
      * Get a playlist with its tracks (uses Tauri command)
      * @param {number} playlistId - Playlist ID
-     * @returns {Promise<object|null>}
+     * @param {object} [params] - Page and sort options; omit for every track in manual order
+     * @param {string} [params.sort] - Library sort field, or manual order when unset
+     * @param {string} [params.order] - Sort order ('asc' or 'desc')
+     * @param {number} [params.limit] - Max tracks
+     * @param {number} [params.offset] - Offset for pagination
+     * @returns {Promise<object|null>} Playlist whose track_count covers all its tracks
      */
-    async get(playlistId) {
+    async get(playlistId, params = {}) {
       if (invoke) {
         try {
-          return await invoke('playlist_get', { playlistId });
+          return await invoke('playlist_get', {
+            playlistId,
+            sortBy: params.sort || null,
+            sortOrder: params.order || null,
+            limit: params.limit ?? null,
+            offset: params.offset ?? null,
+          });
         } catch (error) {
           console.error('[api.playlists.get] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}`);
     },
 
+    /**
+     * Get the current tracks of a built-in auto-playlist (uses Tauri command)
+     * @param {string} kind - 'decade:1980', 'genre:Jazz', 'most_played', 'never_played',
+     *   or 'recently_added'
+     * @param {number} [limit] - Maximum number of tracks (default 100)
+     * @returns {Promise<Array>} Array of tracks
+     */
+    async getVirtual(kind, limit = null) {
+      if (invoke) {
+        try {
+          return await invoke('playlist_get_virtual', { kind, limit });
+        } catch (error) {
+          console.error('[api.playlists.getVirtual] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Virtual playlists not available in browser mode');
+    },
+
     /**
      * Rename a playlist (uses Tauri command)
      * @param {number} playlistId - Playlist ID
@@ -935,7 +1824,7 @@ export const api = {
           return await invoke('playlist_update', { playlistId, name });
         } catch (error) {
           console.error('[api.playlists.rename] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}`, {
@@ -945,17 +1834,61 @@ export const api = {
     },
 
     /**
-     * Delete a playlist (uses Tauri command)
+     * Create a playlist folder (uses Tauri command)
+     * @param {string} name - Folder name
+     * @param {number} [parentId] - Containing folder (top level if omitted)
+     * @returns {Promise<object|null>}
+     */
+    async createFolder(name, parentId) {
+      if (invoke) {
+        try {
+          const response = await invoke('playlist_create_folder', {
+            name,
+            parentId: parentId ?? null,
+          });
+          return response.playlist;
+        } catch (error) {
+          console.error('[api.playlists.createFolder] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playlist folders not available in browser mode');
+    },
+
+    /**
+     * Move a playlist or folder into a folder (uses Tauri command)
+     * @param {number} playlistId - Playlist or folder ID
+     * @param {number} [newParentId] - Target folder (top level if omitted)
+     * @returns {Promise<{success: boolean}>}
+     */
+    async move(playlistId, newParentId) {
+      if (invoke) {
+        try {
+          return await invoke('playlist_move', {
+            playlistId,
+            newParentId: newParentId ?? null,
+          });
+        } catch (error) {
+          console.error('[api.playlists.move] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playlist folders not available in browser mode');
+    },
+
+    /**
+     * Delete a playlist or folder (uses Tauri command)
      * @param {number} playlistId - Playlist ID
+     * @param {boolean} [recursive=false] - Also delete a non-empty folder's contents
      * @returns {Promise<{success: boolean}>}
      */
-    async delete(playlistId) {
+    async delete(playlistId, recursive = false) {
       if (invoke) {
         try {
-          return await invoke('playlist_delete', { playlistId });
+          return await invoke('playlist_delete', { playlistId, recursive });
         } catch (error) {
           console.error('[api.playlists.delete] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}`, {
@@ -980,7 +1913,7 @@ export const api = {
           });
         } catch (error) {
           console.error('[api.playlists.addTracks] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}/tracks`, {
@@ -1001,7 +1934,7 @@ export const api = {
           return await invoke('playlist_remove_track', { playlistId, position });
         } catch (error) {
           console.error('[api.playlists.removeTrack] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}/tracks/${position}`, {
@@ -1026,7 +1959,7 @@ export const api = {
           });
         } catch (error) {
           console.error('[api.playlists.reorder] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/playlists/${playlistId}/tracks/reorder`, {
@@ -1035,6 +1968,39 @@ export const api = {
       });
     },
 
+    /**
+     * Renumber a playlist's track positions to close gaps and duplicates (uses Tauri command)
+     * @param {number} playlistId - Playlist ID
+     * @returns {Promise<boolean>} Whether anything needed repairing
+     */
+    async repair(playlistId) {
+      if (invoke) {
+        try {
+          return await invoke('playlist_repair', { playlistId });
+        } catch (error) {
+          console.error('[api.playlists.repair] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playlist repair not available in browser mode');
+    },
+
+    /**
+     * Repair track positions in every playlist (uses Tauri command)
+     * @returns {Promise<number[]>} IDs of the playlists that were repaired
+     */
+    async repairAll() {
+      if (invoke) {
+        try {
+          return await invoke('playlist_repair_all');
+        } catch (error) {
+          console.error('[api.playlists.repairAll] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Playlist repair not available in browser mode');
+    },
+
     /**
      * Reorder playlists in sidebar (uses Tauri command)
      * @param {number} fromPosition - Current position
@@ -1047,7 +2013,7 @@ export const api = {
           return await invoke('playlists_reorder', { fromPosition, toPosition });
         } catch (error) {
           console.error('[api.playlists.reorderPlaylists] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/playlists/reorder', {
@@ -1055,6 +2021,24 @@ export const api = {
         body: JSON.stringify({ from_position: fromPosition, to_position: toPosition }),
       });
     },
+
+    /**
+     * Move several playlists in one folder to a position at once (uses Tauri command)
+     * @param {number[]} playlistIds - Playlists to move, all in the same folder
+     * @param {number} toPosition - Target position among that folder's children
+     * @returns {Promise<{success: boolean}>}
+     */
+    async reorderPlaylistsMany(playlistIds, toPosition) {
+      if (invoke) {
+        try {
+          return await invoke('playlists_reorder_many', { playlistIds, toPosition });
+        } catch (error) {
+          console.error('[api.playlists.reorderPlaylistsMany] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Multi-playlist reorder not available in browser mode');
+    },
   },
 
   // ============================================
@@ -1072,7 +2056,7 @@ export const api = {
           return await invoke('lastfm_get_settings');
         } catch (error) {
           console.error('[api.lastfm.getSettings] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/settings');
@@ -1091,7 +2075,7 @@ export const api = {
           return await invoke('lastfm_update_settings', { settingsUpdate: settings });
         } catch (error) {
           console.error('[api.lastfm.updateSettings] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/settings', {
@@ -1110,7 +2094,7 @@ export const api = {
           return await invoke('lastfm_get_auth_url');
         } catch (error) {
           console.error('[api.lastfm.getAuthUrl] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/auth-url');
@@ -1127,7 +2111,7 @@ export const api = {
           return await invoke('lastfm_auth_callback', { token });
         } catch (error) {
           console.error('[api.lastfm.completeAuth] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       const query = new URLSearchParams({ token });
@@ -1151,7 +2135,7 @@ export const api = {
           return await invoke('lastfm_scrobble', { request: scrobbleData });
         } catch (error) {
           console.error('[api.lastfm.scrobble] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/scrobble', {
@@ -1175,7 +2159,7 @@ export const api = {
           return await invoke('lastfm_now_playing', { request: nowPlayingData });
         } catch (error) {
           console.error('[api.lastfm.updateNowPlaying] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/now-playing', {
@@ -1194,7 +2178,7 @@ export const api = {
           return await invoke('lastfm_import_loved_tracks');
         } catch (error) {
           console.error('[api.lastfm.importLovedTracks] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/import-loved-tracks', {
@@ -1212,7 +2196,7 @@ export const api = {
           return await invoke('lastfm_disconnect');
         } catch (error) {
           console.error('[api.lastfm.disconnect] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/disconnect', {
@@ -1220,6 +2204,22 @@ export const api = {
       });
     },
 
+    /**
+     * Check Last.fm is reachable and the stored session still works (uses Tauri command)
+     * @returns {Promise<{status: string, reachable: boolean, authenticated: boolean, username: string|null, message?: string}>}
+     */
+    async testConnection() {
+      if (invoke) {
+        try {
+          return await invoke('lastfm_test_connection');
+        } catch (error) {
+          console.error('[api.lastfm.testConnection] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      throw new ApiError(500, 'Last.fm connection test not available in browser mode');
+    },
+
     /**
      * Get scrobble queue status (uses Tauri command)
      * @returns {Promise<{queued_scrobbles: number}>}
@@ -1230,12 +2230,28 @@ export const api = {
           return await invoke('lastfm_queue_status');
         } catch (error) {
           console.error('[api.lastfm.getQueueStatus] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/queue/status');
     },
 
+    /**
+     * Get the Last.fm rate limiter state (uses Tauri command)
+     * @returns {Promise<{wait_ms: number, recent_requests: number, daily_requests: number, per_second_limit: number, daily_limit: number}>}
+     */
+    async getRateLimitStatus() {
+      if (invoke) {
+        try {
+          return await invoke('lastfm_rate_limit_status');
+        } catch (error) {
+          console.error('[api.lastfm.getRateLimitStatus] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return request('/lastfm/rate-limit');
+    },
+
     /**
      * Manually retry queued scrobbles (uses Tauri command)
      * @returns {Promise<{status: string, remaining_queued: number}>}
@@ -1246,7 +2262,7 @@ export const api = {
           return await invoke('lastfm_queue_retry');
         } catch (error) {
           console.error('[api.lastfm.retryQueuedScrobbles] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/lastfm/queue/retry', {
@@ -1311,7 +2327,7 @@ export const api = {
           return await invoke('settings_get_all');
         } catch (error) {
           console.error('[api.settings.getAll] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       // Fallback to HTTP (for backwards compatibility)
@@ -1329,7 +2345,7 @@ export const api = {
           return await invoke('settings_get', { key });
         } catch (error) {
           console.error('[api.settings.get] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/settings/${encodeURIComponent(key)}`);
@@ -1347,7 +2363,7 @@ export const api = {
           return await invoke('settings_set', { key, value });
         } catch (error) {
           console.error('[api.settings.set] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request(`/settings/${encodeURIComponent(key)}`, {
@@ -1373,7 +2389,7 @@ export const api = {
           return await invoke('settings_update', { settings });
         } catch (error) {
           console.error('[api.settings.update] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/settings', {
@@ -1392,7 +2408,7 @@ export const api = {
           return await invoke('settings_reset');
         } catch (error) {
           console.error('[api.settings.reset] Tauri error:', error);
-          throw new ApiError(500, error.toString());
+          throw tauriError(error);
         }
       }
       return request('/settings/reset', {
@@ -1400,6 +2416,43 @@ export const api = {
       });
     },
   },
+
+  // ============================================
+  // App lifecycle (uses Tauri commands)
+  // ============================================
+
+  app: {
+    /**
+     * Stop background tasks and flush the database before closing (uses Tauri command)
+     * @returns {Promise<void>}
+     */
+    async shutdown() {
+      if (invoke) {
+        try {
+          return await invoke('app_shutdown');
+        } catch (error) {
+          console.error('[api.app.shutdown] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+    },
+
+    /**
+     * Get the background thread budget and its running and waiting jobs (uses Tauri command)
+     * @returns {Promise<{max_threads: number, active: number, queued: number}>}
+     */
+    async getBackgroundStatus() {
+      if (invoke) {
+        try {
+          return await invoke('background_status');
+        } catch (error) {
+          console.error('[api.app.getBackgroundStatus] Tauri error:', error);
+          throw tauriError(error);
+        }
+      }
+      return { max_threads: 0, active: 0, queued: 0 };
+    },
+  },
 };
 
 export default api;
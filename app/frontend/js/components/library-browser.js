@@ -923,8 +923,7 @@ export function createLibraryBrowser(Alpine) {
           await this.queue.add(this.library.filteredTracks, false);
           if (index >= 0 && index < this.queue.items.length) {
             this.queue.currentIndex = index;
-            this.queue._shuffleItems();
-            await this.queue._syncQueueToBackend();
+            await this.queue._shuffleOnBackend();
             await this.queue.playIndex(0);
           } else {
             await this.player.playTrack(track);
@@ -6,6 +6,7 @@ export function createNowPlayingView(Alpine) {
     dragY: 0,
     dragStartY: 0,
     dragItemHeight: 0,
+    selectedIndices: [],
 
     startDrag(originalIdx, event) {
       event.preventDefault();
@@ -44,7 +45,13 @@ export function createNowPlayingView(Alpine) {
           this.draggingOriginalIdx !== null && this.dragOverOriginalIdx !== null &&
           this.draggingOriginalIdx !== this.dragOverOriginalIdx
         ) {
-          this.reorder(this.draggingOriginalIdx, this.dragOverOriginalIdx);
+          // Dragging one of several selected tracks moves them all together
+          if (this.selectedIndices.length > 1 && this.isSelected(this.draggingOriginalIdx)) {
+            this.$store.queue.reorderMany(this.selectedIndices, this.dragOverOriginalIdx);
+            this.selectedIndices = [];
+          } else {
+            this.reorder(this.draggingOriginalIdx, this.dragOverOriginalIdx);
+          }
         }
 
         this.draggingOriginalIdx = null;
@@ -153,6 +160,20 @@ export function createNowPlayingView(Alpine) {
       queue.save();
     },
 
+    toggleSelection(originalIdx, event) {
+      if (event.metaKey || event.ctrlKey) {
+        this.selectedIndices = this.isSelected(originalIdx)
+          ? this.selectedIndices.filter((i) => i !== originalIdx)
+          : [...this.selectedIndices, originalIdx];
+      } else {
+        this.selectedIndices = [originalIdx];
+      }
+    },
+
+    isSelected(originalIdx) {
+      return this.selectedIndices.includes(originalIdx);
+    },
+
     isDragging(originalIdx) {
       return this.draggingOriginalIdx === originalIdx;
     },
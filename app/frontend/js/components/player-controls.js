@@ -208,6 +208,7 @@ export function createPlayerControls(Alpine) {
 
       if (!isNaN(position) && position >= 0) {
         this.dragPosition = position;
+        this.player.seekPreview(position);
       }
     },
 
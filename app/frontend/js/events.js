@@ -163,10 +163,11 @@ export async function initEventListeners(Alpine) {
   // Favorites updated event
   await subscribe(Events.FAVORITES_UPDATED, (payload) => {
     const { action, track_id } = payload;
+    const trackIds = payload.track_ids ?? [track_id];
     const library = Alpine.store('library');
     const player = Alpine.store('player');
 
-    console.log(`[events] Favorites ${action}: track ${track_id}`);
+    console.log(`[events] Favorites ${action}: tracks ${trackIds.join(', ')}`);
 
     // Refresh library if showing liked songs
     if (library.refreshIfLikedSongs) {
@@ -174,7 +175,7 @@ export async function initEventListeners(Alpine) {
     }
 
     // Update player favorite status if currently playing this track
-    if (player.currentTrack?.id === track_id) {
+    if (trackIds.includes(player.currentTrack?.id)) {
       player.isFavorite = action === 'added';
     }
   });
@@ -317,13 +317,19 @@ export function createLibraryStore(Alpine) {
             if (aAlbum > bAlbum) return 1;
           }
 
-          // Tiebreaker 2: Track Number
+          // Tiebreaker 2: Disc Number (untagged discs count as disc 1)
+          const aDisc = parseInt(a.disc_number, 10) || 1;
+          const bDisc = parseInt(b.disc_number, 10) || 1;
+          if (aDisc < bDisc) return -1;
+          if (aDisc > bDisc) return 1;
+
+          // Tiebreaker 3: Track Number
           const aTrack = parseInt(String(a.track_number || '').split('/')[0], 10) || 999999;
           const bTrack = parseInt(String(b.track_number || '').split('/')[0], 10) || 999999;
           if (aTrack < bTrack) return -1;
           if (aTrack > bTrack) return 1;
 
-          // Tiebreaker 3: Artist (if not primary sort key)
+          // Tiebreaker 4: Artist (if not primary sort key)
           if (sortKey !== 'artist') {
             const aArtist = this._stripIgnoredPrefix(a.artist || '', ignoreWords).toLowerCase();
             const bArtist = this._stripIgnoredPrefix(b.artist || '', ignoreWords).toLowerCase();
@@ -17,11 +17,13 @@ export function createPlayerStore(Alpine) {
     isSeeking: false,
     isFavorite: false,
     artwork: null,
+    xrunCount: 0,
+    streamError: null,
 
     _progressListener: null,
     _trackEndedListener: null,
     _mediaKeyListeners: [],
-    _previousVolume: 100,
+    _streamListeners: [],
     _seekDebounce: null,
     _playRequestId: 0, // Guard against concurrent playTrack calls
 
@@ -40,6 +42,7 @@ export function createPlayerStore(Alpine) {
         this.isPlaying = state === 'Playing';
       });
 
+      // Only sent for tracks loaded here; the backend advances its own tracks
       this._trackEndedListener = await listen('audio://track-ended', () => {
         this.isPlaying = false;
         Alpine.store('queue').playNext();
@@ -54,9 +57,26 @@ export function createPlayerStore(Alpine) {
         listen('mediakey://stop', () => this.stop()),
       ]);
 
+      // Frequent underruns suggest raising the output buffer size
+      this._streamListeners = await Promise.all([
+        listen('playback:stream-warning', (event) => {
+          this.xrunCount = event.payload.xrun_count;
+          console.warn(
+            `[player] ${event.payload.new_xruns} audio underruns, consider a larger buffer`,
+          );
+        }),
+        listen('playback:stream-error', (event) => {
+          this.streamError = event.payload.message;
+          this.isPlaying = false;
+          console.error('[player] Audio stream error:', event.payload.message);
+        }),
+      ]);
+
       try {
         const status = await invoke('audio_get_status');
         this.volume = Math.round(status.volume * 100);
+        this.muted = !!status.muted;
+        this.xrunCount = status.xrun_count ?? 0;
       } catch (e) {
         console.warn('Could not get initial audio status:', e);
       }
@@ -66,6 +86,7 @@ export function createPlayerStore(Alpine) {
       if (this._progressListener) this._progressListener();
       if (this._trackEndedListener) this._trackEndedListener();
       this._mediaKeyListeners.forEach((unlisten) => unlisten());
+      this._streamListeners.forEach((unlisten) => unlisten());
     },
 
     async playTrack(track) {
@@ -268,7 +289,7 @@ export function createPlayerStore(Alpine) {
 
       this._seekDebounce = setTimeout(async () => {
         try {
-          await invoke('audio_seek', { positionMs: pos });
+          await invoke('audio_seek_commit', { positionMs: pos });
         } catch (error) {
           console.error('[playback]', 'seek_error', { error: error.message, positionMs: pos });
         } finally {
@@ -278,6 +299,19 @@ export function createPlayerStore(Alpine) {
       }, 50);
     },
 
+    /**
+     * Preview a seek while the progress bar is dragged, without moving playback
+     * The backend reports it as preview_position_ms until seek() commits
+     * @param {number} positionMs - Position under the pointer
+     */
+    seekPreview(positionMs) {
+      if (!Number.isFinite(positionMs) || !this.currentTrack) return;
+      const pos = Math.max(0, Math.min(Math.round(positionMs), this.duration));
+      invoke('audio_seek_preview', { positionMs: pos }).catch((error) => {
+        console.debug('[playback]', 'seek_preview_error', { error: error?.message ?? error });
+      });
+    },
+
     async seekPercent(percent) {
       if (!Number.isFinite(percent)) return;
       if (!this.duration || this.duration <= 0) return;
@@ -285,6 +319,39 @@ export function createPlayerStore(Alpine) {
       await this.seek(positionMs);
     },
 
+    async seekRelative(deltaMs) {
+      if (!Number.isFinite(deltaMs) || !this.currentTrack) return;
+      try {
+        // When the seek reaches the end, Rust advances the queue like a finished track
+        const result = await invoke('audio_seek_relative', { deltaMs: Math.round(deltaMs) });
+        if (result.position_ms !== null) {
+          this.currentTime = result.position_ms;
+        }
+      } catch (error) {
+        console.error('[playback]', 'seek_relative_error', { error: error.message, deltaMs });
+      }
+    },
+
+    async nextChapter() {
+      await this._seekChapter('audio_next_chapter');
+    },
+
+    async prevChapter() {
+      await this._seekChapter('audio_prev_chapter');
+    },
+
+    async _seekChapter(command) {
+      if (!this.currentTrack) return;
+      try {
+        const positionMs = await invoke(command);
+        if (positionMs !== null) {
+          this.currentTime = positionMs;
+        }
+      } catch (error) {
+        console.error('[playback]', 'chapter_seek_error', { error: error.message, command });
+      }
+    },
+
     async setVolume(vol) {
       const clampedVol = Math.max(0, Math.min(100, vol));
 
@@ -298,9 +365,6 @@ export function createPlayerStore(Alpine) {
 
       try {
         await invoke('audio_set_volume', { volume: clampedVol / 100 });
-        if (clampedVol > 0) {
-          this.muted = false;
-        }
       } catch (error) {
         console.error('[playback]', 'set_volume_error', {
           error: error.message,
@@ -315,13 +379,15 @@ export function createPlayerStore(Alpine) {
         currentVolume: this.volume,
       });
 
-      if (this.muted) {
-        await this.setVolume(this._previousVolume || 100);
-        this.muted = false;
-      } else {
-        this._previousVolume = this.volume;
-        await this.setVolume(0);
-        this.muted = true;
+      const muted = !this.muted;
+      try {
+        await invoke('audio_set_muted', { muted });
+        this.muted = muted;
+      } catch (error) {
+        console.error('[playback]', 'toggle_mute_error', {
+          error: error.message,
+          muted,
+        });
       }
     },
 
@@ -399,35 +465,7 @@ export function createPlayerStore(Alpine) {
       } catch (error) {
         console.warn('Failed to update Now Playing metadata:', error);
       }
-
-      // Update Last.fm Now Playing in background
-      this._updateLastfmNowPlaying();
-    },
-
-    _updateLastfmNowPlaying() {
-      if (!this.currentTrack) return;
-
-      try {
-        const nowPlayingData = {
-          artist: this.currentTrack.artist || 'Unknown Artist',
-          track: this.currentTrack.title || 'Unknown Track',
-          album: this.currentTrack.album || undefined,
-          duration: Math.floor(this.duration / 1000), // Convert ms to seconds
-        };
-
-        api.lastfm.updateNowPlaying(nowPlayingData).then((result) => {
-          if (result.status === 'disabled' || result.status === 'not_authenticated') {
-            // Silently ignore if not configured
-            return;
-          }
-          console.debug('[lastfm] Now Playing updated:', nowPlayingData.track);
-        }).catch((error) => {
-          console.warn('[lastfm] Failed to update Now Playing:', error);
-        });
-      } catch (error) {
-        // Silently ignore Now Playing errors
-        console.warn('[lastfm] Error preparing Now Playing data:', error);
-      }
+      // Last.fm Now Playing is sent by the backend when the track is loaded
     },
 
     async _updateNowPlayingState() {
@@ -16,6 +16,7 @@ export function createQueueStore(Alpine) {
 
     // Playback modes
     shuffle: false,
+    shuffleMode: 'tracks', // 'tracks', 'albums'
     loop: 'none', // 'none', 'all', 'one'
 
     // Repeat-one "play once more" state
@@ -61,6 +62,7 @@ export function createQueueStore(Alpine) {
       // Reset to defaults - shuffle, loop, and currentIndex are session-only
       this.currentIndex = -1;
       this.shuffle = false;
+      this.shuffleMode = 'tracks';
       this.loop = 'none';
       this._originalOrder = [...this.items];
       this._repeatOnePending = false;
@@ -70,6 +72,7 @@ export function createQueueStore(Alpine) {
       try {
         await api.queue.setCurrentIndex(this.currentIndex);
         await api.queue.setShuffle(this.shuffle);
+        await api.queue.setShuffleMode(this.shuffleMode);
         await api.queue.setLoop(this.loop);
       } catch (error) {
         console.error('Failed to initialize playback state:', error);
@@ -163,16 +166,15 @@ export function createQueueStore(Alpine) {
     },
 
     /**
-     * Sync full queue state to backend (clear and rebuild)
+     * Sync full queue state to backend, replacing it in one step
      * Used when queue order changes in ways that can't be expressed as incremental operations
      */
     async _syncQueueToBackend() {
       try {
-        await api.queue.clear();
-        if (this.items.length > 0) {
-          const trackIds = this.items.map((t) => t.id);
-          await api.queue.add(trackIds);
-        }
+        const trackIds = this.items.map((t) => t.id);
+        const lastIndex = Math.max(trackIds.length - 1, 0);
+        const startIndex = Math.min(Math.max(this.currentIndex, 0), lastIndex);
+        await api.queue.replace(trackIds, startIndex);
       } catch (error) {
         console.error('[queue] Failed to sync to backend:', error);
       }
@@ -375,6 +377,45 @@ export function createQueueStore(Alpine) {
       }
     },
 
+    /**
+     * Move several tracks to a drop target at once (multi-select drag)
+     * @param {number[]} positions - Source indices
+     * @param {number} to - Index of the track to drop before, or items.length to append
+     */
+    async reorderMany(positions, to) {
+      const moved = [...new Set(positions)].sort((a, b) => a - b);
+      if (moved.length === 0) return;
+      if (moved.some((i) => i < 0 || i >= this.items.length)) return;
+
+      // Same block move as the backend: the moved tracks keep their order and
+      // land before the first unmoved track at or after the target
+      const target = Math.min(Math.max(to, 0), this.items.length);
+      const movedSet = new Set(moved);
+      const rest = this.items.map((_, i) => i).filter((i) => !movedSet.has(i));
+      const insertAt = rest.filter((i) => i < target).length;
+      const order = [...rest.slice(0, insertAt), ...moved, ...rest.slice(insertAt)];
+      if (order.every((i, position) => i === position)) return;
+
+      console.log('[queue]', 'reorder_tracks', {
+        positions: moved,
+        to: target,
+        movedCurrentTrack: movedSet.has(this.currentIndex),
+      });
+
+      // Update local state
+      this.items = order.map((i) => this.items[i]);
+      if (this.currentIndex >= 0) {
+        this.currentIndex = order.indexOf(this.currentIndex);
+      }
+
+      // Persist to backend in one call
+      try {
+        await api.queue.reorderMany(moved, target);
+      } catch (error) {
+        console.error('[queue] Failed to persist reorder:', error);
+      }
+    },
+
     /**
      * Play track at specific index
      * @param {number} index - Index to play
@@ -402,7 +443,8 @@ export function createQueueStore(Alpine) {
         if (this._repeatOnePending) {
           this._repeatOnePending = false;
           this.loop = 'none';
-          // Loop state is session-only, no persistence needed
+          // Keep the backend in step so its ended handler stops repeating too
+          await api.queue.setLoop(this.loop);
         } else {
           this._repeatOnePending = true;
           await this.playIndex(this.currentIndex, true);
@@ -421,7 +463,7 @@ export function createQueueStore(Alpine) {
         if (this.loop === 'all') {
           if (this.shuffle) {
             // Use special reshuffle that puts just-played track at END (task-222)
-            this._reshuffleForLoopRestart();
+            await this._reshuffleForLoopRestart();
           }
           nextIndex = 0;
         } else {
@@ -517,9 +559,7 @@ export function createQueueStore(Alpine) {
         if (this.shuffle) {
           // Save original order before shuffling
           this._originalOrder = [...this.items];
-          this._shuffleItems();
           // Don't clear history - user can still go back to previously played tracks
-          // Don't call setCurrentIndex - same track is playing, just at index 0 now
         } else {
           // Restore original order
           const currentTrack = this.items[this.currentIndex];
@@ -535,13 +575,12 @@ export function createQueueStore(Alpine) {
         // Persist shuffle state to backend
         await api.queue.setShuffle(this.shuffle);
 
-        // Only update backend index when disabling shuffle (index changed in original order)
-        if (!this.shuffle) {
-          await api.queue.setCurrentIndex(this.currentIndex);
+        if (this.shuffle) {
+          await this._shuffleOnBackend();
+        } else {
+          // Put the original order back on the backend
+          await this._syncQueueToBackend();
         }
-
-        // Sync queue order to backend
-        await this._syncQueueToBackend();
       } finally {
         // Delay reset to let pending backend events pass
         setTimeout(() => {
@@ -550,54 +589,72 @@ export function createQueueStore(Alpine) {
       }
     },
 
-    _shuffleItems() {
-      if (this.items.length < 2) return;
+    /**
+     * Move the current track to index 0 ahead of a shuffle (task-213)
+     * The tracks after it are shuffled by the backend in _shuffleOnBackend
+     */
+    _moveCurrentToFront() {
+      if (this.currentIndex <= 0 || this.currentIndex >= this.items.length) return;
+
+      // Filter out current track by index, so a duplicate of it stays queued
+      const currentTrack = this.items[this.currentIndex];
+      const otherTracks = this.items.filter((_, i) => i !== this.currentIndex);
+      this.items = [currentTrack, ...otherTracks];
+      this.currentIndex = 0;
 
-      const currentTrack = this.currentIndex >= 0 ? this.items[this.currentIndex] : null;
+      this._validateQueueIntegrity();
+    },
 
-      // Filter out current track by index to get tracks to shuffle
-      const otherTracks = currentTrack
-        ? this.items.filter((_, i) => i !== this.currentIndex)
-        : [...this.items];
+    /**
+     * Shuffle the queue on the backend and reload it
+     * The backend owns the shuffle order: it is seeded and persisted, follows
+     * shuffleMode, and keeps repeats of a track apart. The current track goes
+     * to index 0 first and stays there.
+     */
+    async _shuffleOnBackend() {
+      if (this.items.length < 2) return;
 
-      // Fisher-Yates shuffle of other tracks
-      for (let i = otherTracks.length - 1; i > 0; i--) {
-        const j = Math.floor(Math.random() * (i + 1));
-        [otherTracks[i], otherTracks[j]] = [otherTracks[j], otherTracks[i]];
-      }
+      const keepCurrent = this.currentIndex >= 0;
+      this._moveCurrentToFront();
+      await this._syncQueueToBackend();
 
-      if (currentTrack) {
-        // Current track goes to index 0, shuffled tracks follow (task-213)
-        this.items = [currentTrack, ...otherTracks];
-        this.currentIndex = 0;
-      } else {
-        this.items = otherTracks;
+      try {
+        if (keepCurrent) {
+          await api.queue.reshuffle();
+        } else {
+          await api.queue.shuffle(false);
+        }
+        await this._reloadItems();
+      } catch (error) {
+        console.error('[queue] Failed to shuffle on backend:', error);
       }
+    },
 
+    /**
+     * Reload the queue's tracks from the backend, keeping the current index
+     * Used after the backend reordered the queue itself
+     */
+    async _reloadItems() {
+      const data = await api.queue.get();
+      this.items = (data.items || []).map((item) => item.track || item);
       this._validateQueueIntegrity();
     },
 
     /**
      * Reshuffle for loop restart - ensures just-played track is NOT at index 0 (task-222)
      * Called when loop=all and reaching end of queue with shuffle enabled.
+     * The backend puts the just-played track, or in album mode its album, last.
      */
-    _reshuffleForLoopRestart() {
+    async _reshuffleForLoopRestart() {
       if (this.items.length < 2) return;
 
-      const justPlayedTrack = this.items[this.currentIndex];
-      const otherTracks = this.items.filter((_, i) => i !== this.currentIndex);
-
-      // Fisher-Yates shuffle of other tracks
-      for (let i = otherTracks.length - 1; i > 0; i--) {
-        const j = Math.floor(Math.random() * (i + 1));
-        [otherTracks[i], otherTracks[j]] = [otherTracks[j], otherTracks[i]];
+      try {
+        await api.queue.reshuffleCycle(this.currentIndex);
+        await this._reloadItems();
+        this._originalOrder = [...this.items];
+      } catch (error) {
+        console.error('[queue] Failed to reshuffle for loop restart:', error);
       }
-
-      // Put just-played track at END (plays last in next cycle)
-      this.items = [...otherTracks, justPlayedTrack];
-      this._originalOrder = [...this.items];
-
-      this._validateQueueIntegrity();
     },
 
     /**
@@ -645,12 +702,37 @@ export function createQueueStore(Alpine) {
         currentIndex: this.currentIndex,
       });
 
-      // Update local state
-      this._shuffleItems();
+      await this._shuffleOnBackend();
       this._originalOrder = [...this.items];
+    },
 
-      // Sync shuffled order to backend
-      await this._syncQueueToBackend();
+    /**
+     * Set shuffle mode; the backend reshuffles the queue when shuffle is on
+     * @param {string} mode - 'tracks' or 'albums'
+     */
+    async setShuffleMode(mode) {
+      if (!['tracks', 'albums'].includes(mode) || mode === this.shuffleMode) return;
+
+      console.log('[queue]', 'set_shuffle_mode', {
+        previousMode: this.shuffleMode,
+        newMode: mode,
+      });
+
+      this.shuffleMode = mode;
+      this._updating = true;
+      try {
+        // The backend reshuffles the unplayed tracks in the new mode itself
+        await api.queue.setShuffleMode(mode);
+        if (this.shuffle) {
+          await this._reloadItems();
+        }
+      } catch (error) {
+        console.error('[queue] Failed to set shuffle mode:', error);
+      } finally {
+        setTimeout(() => {
+          this._updating = false;
+        }, 200);
+      }
     },
 
     async cycleLoop() {
@@ -289,152 +289,8 @@ test.describe('Last.fm Integration', () => {
     });
   });
 
-  test.describe('Now Playing Updates', () => {
-    test.beforeEach(async ({ page }) => {
-      await page.setViewportSize({ width: 1624, height: 1057 });
-      await page.goto('/');
-      await waitForAlpine(page);
-    });
-
-    test('should update Now Playing when track starts playing', async ({ page }) => {
-      let nowPlayingCalled = false;
-      let nowPlayingData = null;
-
-      await page.route('**/lastfm/now-playing', async (route) => {
-        nowPlayingCalled = true;
-        nowPlayingData = route.request().postDataJSON();
-        await route.fulfill({
-          status: 200,
-          contentType: 'application/json',
-          body: JSON.stringify({
-            status: 'success',
-            message: 'Now playing updated',
-          }),
-        });
-      });
-
-      // Simulate track playing (mock player state)
-      await page.evaluate(() => {
-        const player = window.Alpine.store('player');
-        player.currentTrack = {
-          id: '1',
-          title: 'Test Track',
-          artist: 'Test Artist',
-          album: 'Test Album',
-        };
-        player.duration = 240000; // 4 minutes in ms
-        player.isPlaying = true;
-
-        // Trigger Now Playing update
-        player._updateLastfmNowPlaying();
-      });
-
-      // Wait for API call
-      await page.waitForTimeout(1000);
-
-      // Verify Now Playing was called
-      expect(nowPlayingCalled).toBe(true);
-      expect(nowPlayingData).toMatchObject({
-        artist: 'Test Artist',
-        track: 'Test Track',
-        album: 'Test Album',
-        duration: 240, // Should be in seconds
-      });
-    });
-
-    test('should handle Now Playing errors silently', async ({ page }) => {
-      // Mock failed Now Playing update
-      await page.route('**/lastfm/now-playing', async (route) => {
-        await route.fulfill({
-          status: 500,
-          contentType: 'application/json',
-          body: JSON.stringify({
-            detail: 'Internal server error',
-          }),
-        });
-      });
-
-      // Simulate track playing
-      await page.evaluate(() => {
-        const player = window.Alpine.store('player');
-        player.currentTrack = {
-          id: '1',
-          title: 'Test Track',
-          artist: 'Test Artist',
-        };
-        player.duration = 180000;
-        player.isPlaying = true;
-
-        // Trigger Now Playing update
-        player._updateLastfmNowPlaying();
-      });
-
-      // Wait and verify app doesn't crash
-      await page.waitForTimeout(1000);
-
-      // App should still be functional
-      const player = await getAlpineStore(page, 'player');
-      expect(player.currentTrack.title).toBe('Test Track');
-    });
-
-    test('should include album in Now Playing if available', async ({ page }) => {
-      let nowPlayingData = null;
-
-      await page.route('**/lastfm/now-playing', async (route) => {
-        nowPlayingData = route.request().postDataJSON();
-        await route.fulfill({
-          status: 200,
-          contentType: 'application/json',
-          body: JSON.stringify({ status: 'success' }),
-        });
-      });
-
-      await page.evaluate(() => {
-        const player = window.Alpine.store('player');
-        player.currentTrack = {
-          id: '1',
-          title: 'Track With Album',
-          artist: 'Artist Name',
-          album: 'Album Name',
-        };
-        player.duration = 200000;
-        player._updateLastfmNowPlaying();
-      });
-
-      await page.waitForTimeout(1000);
-
-      expect(nowPlayingData.album).toBe('Album Name');
-    });
-
-    test('should omit album from Now Playing if not available', async ({ page }) => {
-      let nowPlayingData = null;
-
-      await page.route('**/lastfm/now-playing', async (route) => {
-        nowPlayingData = route.request().postDataJSON();
-        await route.fulfill({
-          status: 200,
-          contentType: 'application/json',
-          body: JSON.stringify({ status: 'success' }),
-        });
-      });
-
-      await page.evaluate(() => {
-        const player = window.Alpine.store('player');
-        player.currentTrack = {
-          id: '1',
-          title: 'Track Without Album',
-          artist: 'Artist Name',
-          // No album field
-        };
-        player.duration = 200000;
-        player._updateLastfmNowPlaying();
-      });
-
-      await page.waitForTimeout(1000);
-
-      expect(nowPlayingData.album).toBeUndefined();
-    });
-  });
+  // NOTE: Now Playing updates are sent by the Rust backend when a track is
+  // loaded, after a short delay so skipped tracks aren't reported.
 
   test.describe('Scrobble API (task-007)', () => {
     // NOTE: Scrobble threshold checking was moved to Rust backend (task-197).
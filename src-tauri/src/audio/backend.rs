@@ -0,0 +1,521 @@
+//! Playback backends for the audio engine.
+//!
+//! `AudioEngine` owns the playback state machine (play/pause/stop state,
+//! volume, finish detection, listen time) and delegates decoding and output to
+//! a `PlaybackBackend`. `RodioBackend` is the real implementation; tests use a
+//! mock so the state machine can be exercised without audio hardware.
+
+use crate::audio::engine::TrackInfo;
+use crate::audio::error::AudioError;
+use crate::audio::silence::{MAX_TRIM_MS, SilenceBounds, leading_silence_ms, trailing_silence_ms};
+use crate::audio::xrun::{StreamReporter, stream_monitor};
+use crate::scanner::is_scan_only_file;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::{self, BufferSize};
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source, StreamError};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+use tracing::warn;
+
+/// Default output buffer length
+///
+/// The buffer is the tradeoff between dropouts and latency. A larger buffer
+/// rides out scheduling hiccups on slow or busy machines, so it drops out
+/// less, but play, pause, seek, and volume changes are heard up to one buffer
+/// later. A smaller buffer responds sooner but underruns more easily.
+pub const DEFAULT_BUFFER_MS: u64 = 50;
+
+/// Shortest accepted output buffer length
+pub const MIN_BUFFER_MS: u64 = 5;
+
+/// Longest accepted output buffer length
+pub const MAX_BUFFER_MS: u64 = 500;
+
+/// Samples decoded by `RodioBackend::probe`, a fraction of a second of audio
+const PROBE_SAMPLES: usize = 8192;
+
+/// Decoding and output for a single loaded track
+///
+/// A backend holds at most one track. `load` replaces any current track and
+/// leaves it paused at the start.
+pub trait PlaybackBackend {
+    /// Open and decode a file, replacing the current track, paused at 0
+    fn load(&mut self, path: &str) -> Result<TrackInfo, AudioError>;
+
+    /// Resume output of the loaded track
+    fn play(&mut self) -> Result<(), AudioError>;
+
+    /// Pause output of the loaded track
+    fn pause(&mut self) -> Result<(), AudioError>;
+
+    /// Stop output and unload the track
+    fn stop(&mut self);
+
+    /// Move the playback position, keeping the play/pause state
+    fn seek(&mut self, position_ms: u64) -> Result<(), AudioError>;
+
+    /// Set output gain (0.0 to 1.0)
+    fn set_volume(&mut self, volume: f32);
+
+    /// Current output buffer length
+    fn buffer_ms(&self) -> u64;
+
+    /// Rebuild the output with a new buffer length
+    ///
+    /// The loaded track continues from the same position and play/pause state.
+    /// On error the previous output is kept.
+    fn set_buffer_ms(&mut self, buffer_ms: u64) -> Result<(), AudioError>;
+
+    /// Measure the trimmable silence at each end of the loaded track
+    fn scan_silence(&self, threshold_db: f32) -> Result<SilenceBounds, AudioError>;
+
+    /// Current playback position of the loaded track
+    fn position_ms(&self) -> u64;
+
+    /// Whether a track is loaded
+    fn is_loaded(&self) -> bool;
+
+    /// Whether the loaded track has been fully output
+    fn is_drained(&self) -> bool;
+}
+
+/// Backend using rodio for decoding and the default output device
+pub struct RodioBackend {
+    stream: OutputStream,
+    sink: Option<Sink>,
+    path: Option<String>,
+    volume: f32,
+    buffer_ms: u64,
+    reporter: StreamReporter,
+}
+
+impl RodioBackend {
+    pub fn new() -> Result<Self, AudioError> {
+        // Nobody watches for stream faults here
+        let (reporter, _monitor) = stream_monitor();
+        Self::with_buffer_ms(DEFAULT_BUFFER_MS, reporter)
+    }
+
+    /// Create a backend whose output buffer holds about `buffer_ms` of audio
+    ///
+    /// Underruns and stream errors are sent to `reporter`.
+    pub fn with_buffer_ms(buffer_ms: u64, reporter: StreamReporter) -> Result<Self, AudioError> {
+        let stream = Self::open_stream(buffer_ms, &reporter)?;
+        Ok(Self {
+            stream,
+            sink: None,
+            path: None,
+            volume: 1.0,
+            buffer_ms,
+            reporter,
+        })
+    }
+
+    /// Open the default output device with a fixed buffer of `buffer_ms`
+    ///
+    /// Falls back to the device's default buffer if it refuses the fixed size.
+    fn open_stream(buffer_ms: u64, reporter: &StreamReporter) -> Result<OutputStream, AudioError> {
+        let on_error = {
+            let reporter = reporter.clone();
+            move |err: cpal::StreamError| reporter.report(&err)
+        };
+
+        let fixed = cpal::default_host()
+            .default_output_device()
+            .ok_or(StreamError::NoDevice)
+            .and_then(|device| {
+                let sample_rate = device
+                    .default_output_config()
+                    .map_err(StreamError::DefaultStreamConfigError)?
+                    .sample_rate()
+                    .0;
+                let frames = (u64::from(sample_rate) * buffer_ms / 1000).max(1) as u32;
+                OutputStreamBuilder::from_device(device)?
+                    .with_buffer_size(BufferSize::Fixed(frames))
+                    .with_error_callback(on_error.clone())
+                    .open_stream()
+            });
+
+        match fixed {
+            Ok(stream) => Ok(stream),
+            Err(e) => {
+                warn!(
+                    "{} ms output buffer unavailable ({}), using the device default",
+                    buffer_ms, e
+                );
+                OutputStreamBuilder::from_default_device()
+                    .and_then(|builder| {
+                        builder
+                            .with_error_callback(on_error)
+                            .open_stream_or_fallback()
+                    })
+                    .map_err(|e| AudioError::Stream(e.to_string()))
+            }
+        }
+    }
+
+    /// Check that a file decodes, without opening an output
+    ///
+    /// Opens the file with the same decoder used for playback and decodes its
+    /// first frames. A file that opens but yields no audio is an error.
+    pub fn probe(path: &str) -> Result<(), AudioError> {
+        let source = Self::open_decoder(path)?;
+        if source.take(PROBE_SAMPLES).count() == 0 {
+            return Err(AudioError::Decode(
+                "No audio frames could be decoded".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Measure the trimmable silence at each end of a file
+    ///
+    /// Only the first and last `MAX_TRIM_MS` are decoded, since longer runs
+    /// aren't trimmed anyway. Trailing silence is reported as 0 when the
+    /// length is unknown or the decoder can't seek near the end.
+    fn scan_file_silence(path: &str, threshold_db: f32) -> Result<SilenceBounds, AudioError> {
+        let mut source = Self::open_decoder(path)?;
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        let total = source.total_duration();
+        let window = (u64::from(sample_rate) * u64::from(channels) * MAX_TRIM_MS / 1000) as usize;
+
+        let head: Vec<f32> = source.by_ref().take(window).collect();
+        let lead_ms = leading_silence_ms(&head, channels, sample_rate, threshold_db);
+
+        let cap = Duration::from_millis(MAX_TRIM_MS);
+        let tail: Option<Vec<f32>> = match total {
+            // Short tracks are decoded whole
+            Some(total) if total <= cap * 2 => Some(head.into_iter().chain(source).collect()),
+            Some(total) => match source.try_seek(total - cap) {
+                Ok(()) => Some(source.collect()),
+                Err(e) => {
+                    warn!("Can't seek to the end of {}: {:?}", path, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let trail_ms = tail
+            .map(|tail| trailing_silence_ms(&tail, channels, sample_rate, threshold_db))
+            .unwrap_or(0);
+
+        Ok(SilenceBounds { lead_ms, trail_ms })
+    }
+
+    fn open_decoder(path: &str) -> Result<Decoder<BufReader<File>>, AudioError> {
+        // DSD, Musepack, WavPack, and TTA are scanned into the library, but
+        // rodio has no decoder for them
+        if is_scan_only_file(Path::new(path)) {
+            return Err(AudioError::UnsupportedFormat(format!(
+                "{} can be added to the library but not played",
+                path
+            )));
+        }
+        let file = File::open(path)?;
+        let reader = BufReader::new(file);
+        Decoder::new(reader).map_err(|e| AudioError::Decode(e.to_string()))
+    }
+
+    fn new_sink(&self, source: Decoder<BufReader<File>>) -> Sink {
+        let sink = Sink::connect_new(self.stream.mixer());
+        sink.set_volume(self.volume);
+        sink.append(source);
+        sink
+    }
+
+    fn seek_forward(&mut self, position_ms: u64) -> Result<(), AudioError> {
+        let sink = self.sink.as_ref().ok_or(AudioError::NoTrack)?;
+        sink.try_seek(Duration::from_millis(position_ms))
+            .map_err(|e| AudioError::Seek(format!("{:?}", e)))
+    }
+
+    /// Seek backward by reopening the file, since not every decoder can rewind
+    fn seek_by_reload(&mut self, position_ms: u64) -> Result<(), AudioError> {
+        let path = self.path.clone().ok_or(AudioError::NoTrack)?;
+        let was_playing = self.sink.as_ref().is_some_and(|s| !s.is_paused());
+
+        let source = Self::open_decoder(&path)?;
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+
+        let sink = self.new_sink(source);
+        sink.try_seek(Duration::from_millis(position_ms))
+            .map_err(|e| AudioError::Seek(format!("{:?}", e)))?;
+
+        if was_playing {
+            sink.play();
+        } else {
+            sink.pause();
+        }
+
+        self.sink = Some(sink);
+        Ok(())
+    }
+}
+
+impl PlaybackBackend for RodioBackend {
+    fn load(&mut self, path: &str) -> Result<TrackInfo, AudioError> {
+        self.stop();
+
+        if !Path::new(path).exists() {
+            return Err(AudioError::FileOpen(format!("File not found: {}", path)));
+        }
+
+        let source = Self::open_decoder(path)?;
+        let sample_rate = source.sample_rate();
+        let channels = source.channels();
+        let duration = source.total_duration().unwrap_or(Duration::ZERO);
+
+        let sink = self.new_sink(source);
+        sink.pause();
+
+        self.sink = Some(sink);
+        self.path = Some(path.to_string());
+
+        Ok(TrackInfo {
+            path: path.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            sample_rate,
+            channels,
+        })
+    }
+
+    fn play(&mut self) -> Result<(), AudioError> {
+        let sink = self.sink.as_ref().ok_or(AudioError::NoTrack)?;
+        sink.play();
+        Ok(())
+    }
+
+    fn pause(&mut self) -> Result<(), AudioError> {
+        let sink = self.sink.as_ref().ok_or(AudioError::NoTrack)?;
+        sink.pause();
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+        self.path = None;
+    }
+
+    fn seek(&mut self, position_ms: u64) -> Result<(), AudioError> {
+        if position_ms < self.position_ms() {
+            self.seek_by_reload(position_ms)
+        } else {
+            self.seek_forward(position_ms)
+        }
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        if let Some(ref sink) = self.sink {
+            sink.set_volume(volume);
+        }
+    }
+
+    fn buffer_ms(&self) -> u64 {
+        self.buffer_ms
+    }
+
+    fn set_buffer_ms(&mut self, buffer_ms: u64) -> Result<(), AudioError> {
+        let stream = Self::open_stream(buffer_ms, &self.reporter)?;
+
+        // Sinks are tied to their stream's mixer, so the track is reopened on
+        // the new stream at the same position
+        let resume = self.path.clone().map(|path| {
+            let playing = self.sink.as_ref().is_some_and(|s| !s.is_paused());
+            (path, self.position_ms(), playing)
+        });
+        if let Some(sink) = self.sink.take() {
+            sink.stop();
+        }
+        let mut old_stream = std::mem::replace(&mut self.stream, stream);
+        old_stream.log_on_drop(false);
+        self.buffer_ms = buffer_ms;
+
+        if let Some((path, position_ms, playing)) = resume {
+            let sink = match Self::open_decoder(&path) {
+                Ok(source) => self.new_sink(source),
+                Err(e) => {
+                    self.path = None;
+                    return Err(e);
+                }
+            };
+            sink.pause();
+            if position_ms > 0 {
+                sink.try_seek(Duration::from_millis(position_ms))
+                    .map_err(|e| AudioError::Seek(format!("{:?}", e)))?;
+            }
+            if playing {
+                sink.play();
+            }
+            self.sink = Some(sink);
+        }
+        Ok(())
+    }
+
+    fn scan_silence(&self, threshold_db: f32) -> Result<SilenceBounds, AudioError> {
+        let path = self.path.as_deref().ok_or(AudioError::NoTrack)?;
+        Self::scan_file_silence(path, threshold_db)
+    }
+
+    fn position_ms(&self) -> u64 {
+        self.sink
+            .as_ref()
+            .map(|s| s.get_pos().as_millis() as u64)
+            .unwrap_or(0)
+    }
+
+    fn is_loaded(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    fn is_drained(&self) -> bool {
+        // The sink is empty once all appended sources have been consumed
+        self.sink.as_ref().is_some_and(|s| s.empty())
+    }
+}
+
+/// In-memory backend for tests
+///
+/// Shares its state through `MockHandle` so a test can move the position or
+/// drain the track after the backend has been boxed into an engine.
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    pub struct MockState {
+        pub track: Option<TrackInfo>,
+        pub playing: bool,
+        pub position_ms: u64,
+        pub volume: f32,
+        pub drained: bool,
+        pub seeks: Vec<u64>,
+        pub buffer_ms: u64,
+        /// Number of times the output was rebuilt
+        pub rebuilds: u32,
+        /// Reported by `scan_silence` for every track
+        pub silence: SilenceBounds,
+        /// Thresholds passed to `scan_silence`
+        pub silence_scans: Vec<f32>,
+    }
+
+    pub type MockHandle = Arc<Mutex<MockState>>;
+
+    #[derive(Default)]
+    pub struct MockBackend {
+        state: MockHandle,
+    }
+
+    impl MockBackend {
+        /// Create a backend along with a handle to its shared state
+        pub fn with_handle() -> (Self, MockHandle) {
+            let backend = Self::default();
+            backend.state.lock().buffer_ms = DEFAULT_BUFFER_MS;
+            let handle = backend.state.clone();
+            (backend, handle)
+        }
+    }
+
+    impl PlaybackBackend for MockBackend {
+        fn load(&mut self, path: &str) -> Result<TrackInfo, AudioError> {
+            if path.is_empty() {
+                return Err(AudioError::FileOpen("File not found: ".to_string()));
+            }
+            let track = TrackInfo {
+                path: path.to_string(),
+                duration_ms: 180_000,
+                sample_rate: 44100,
+                channels: 2,
+            };
+            let mut state = self.state.lock();
+            state.track = Some(track.clone());
+            state.playing = false;
+            state.position_ms = 0;
+            state.drained = false;
+            Ok(track)
+        }
+
+        fn play(&mut self) -> Result<(), AudioError> {
+            let mut state = self.state.lock();
+            if state.track.is_none() {
+                return Err(AudioError::NoTrack);
+            }
+            state.playing = true;
+            Ok(())
+        }
+
+        fn pause(&mut self) -> Result<(), AudioError> {
+            let mut state = self.state.lock();
+            if state.track.is_none() {
+                return Err(AudioError::NoTrack);
+            }
+            state.playing = false;
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            let mut state = self.state.lock();
+            state.track = None;
+            state.playing = false;
+            state.position_ms = 0;
+            state.drained = false;
+        }
+
+        fn seek(&mut self, position_ms: u64) -> Result<(), AudioError> {
+            let mut state = self.state.lock();
+            if state.track.is_none() {
+                return Err(AudioError::NoTrack);
+            }
+            state.position_ms = position_ms;
+            state.seeks.push(position_ms);
+            Ok(())
+        }
+
+        fn set_volume(&mut self, volume: f32) {
+            self.state.lock().volume = volume;
+        }
+
+        fn buffer_ms(&self) -> u64 {
+            self.state.lock().buffer_ms
+        }
+
+        fn set_buffer_ms(&mut self, buffer_ms: u64) -> Result<(), AudioError> {
+            // The track, position, and play state carry over unchanged
+            let mut state = self.state.lock();
+            state.buffer_ms = buffer_ms;
+            state.rebuilds += 1;
+            Ok(())
+        }
+
+        fn scan_silence(&self, threshold_db: f32) -> Result<SilenceBounds, AudioError> {
+            let mut state = self.state.lock();
+            if state.track.is_none() {
+                return Err(AudioError::NoTrack);
+            }
+            state.silence_scans.push(threshold_db);
+            Ok(state.silence)
+        }
+
+        fn position_ms(&self) -> u64 {
+            self.state.lock().position_ms
+        }
+
+        fn is_loaded(&self) -> bool {
+            self.state.lock().track.is_some()
+        }
+
+        fn is_drained(&self) -> bool {
+            let state = self.state.lock();
+            state.track.is_some() && state.drained
+        }
+    }
+}
@@ -1,10 +1,11 @@
+use crate::audio::backend::{PlaybackBackend, RodioBackend, MAX_BUFFER_MS, MIN_BUFFER_MS};
 use crate::audio::error::AudioError;
-use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source};
+use crate::audio::fade::{Fade, FadeEnd, MAX_FADE_MS};
+use crate::audio::listen::ListenTracker;
+use crate::audio::silence::{SilenceBounds, MAX_SILENCE_THRESHOLD_DB, MIN_SILENCE_THRESHOLD_DB};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tracing::warn;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlaybackState {
@@ -21,192 +22,440 @@ pub struct TrackInfo {
     pub channels: u16,
 }
 
+/// Going to the previous chapter restarts the current one once this far into it
+pub const PREV_CHAPTER_RESTART_MS: u64 = 3000;
+
+/// Outcome of a relative seek
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeSeek {
+    /// Seeked to this position
+    Seeked(u64),
+    /// The target is past, or within the advance threshold of, the end; the
+    /// caller should move on to the next track
+    Advance,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Progress {
+    /// Position playback has actually reached
     pub position_ms: u64,
     pub duration_ms: u64,
     pub state: PlaybackState,
-}
-
-struct PlayerHandle {
-    sink: Sink,
+    /// Where the seek bar is being dragged to, until the seek is committed
+    #[serde(default)]
+    pub preview_position_ms: Option<u64>,
 }
 
 pub struct AudioEngine {
-    stream: OutputStream,
-    player_handle: Option<PlayerHandle>,
+    backend: Box<dyn PlaybackBackend>,
     state: PlaybackState,
+    /// User volume level, kept while muted so unmuting restores it
     volume: f32,
+    muted: bool,
+    /// Gain last applied to the backend
+    gain: f32,
+    /// Length of play/pause/stop fades, 0 to disable
+    fade_ms: u64,
+    fade: Option<Fade>,
     current_track: Option<TrackInfo>,
+    /// Chapter start offsets of the current track, ascending
+    chapters: Vec<u64>,
+    /// Threshold for trimming leading and trailing silence, `None` when off
+    trim_silence_db: Option<f32>,
+    /// Silence skipped at each end of the current track
+    silence: SilenceBounds,
+    listen: ListenTracker,
+    /// Seek bar position shown while dragging; decoding carries on unmoved
+    preview_ms: Option<u64>,
 }
 
 impl AudioEngine {
+    /// Create an engine on the default output device
     pub fn new() -> Result<Self, AudioError> {
-        let stream = OutputStreamBuilder::open_default_stream()
-            .map_err(|e| AudioError::Stream(e.to_string()))?;
-        Ok(Self {
-            stream,
-            player_handle: None,
+        Ok(Self::with_backend(Box::new(RodioBackend::new()?)))
+    }
+
+    /// Create an engine on top of a specific backend
+    pub fn with_backend(backend: Box<dyn PlaybackBackend>) -> Self {
+        Self {
+            backend,
             state: PlaybackState::Stopped,
             volume: 1.0,
+            muted: false,
+            gain: 1.0,
+            fade_ms: 0,
+            fade: None,
             current_track: None,
-        })
+            chapters: Vec::new(),
+            trim_silence_db: None,
+            silence: SilenceBounds::default(),
+            listen: ListenTracker::new(),
+            preview_ms: None,
+        }
     }
 
+    /// Load a track, paused at its start
+    ///
+    /// With silence trimming on, the track is scanned and positioned at its
+    /// first audible frame. A failed scan only disables trimming for the track.
     pub fn load(&mut self, path: &str) -> Result<TrackInfo, AudioError> {
-        self.stop();
+        self.stop_now();
 
-        let path_obj = Path::new(path);
-        if !path_obj.exists() {
-            return Err(AudioError::FileOpen(format!("File not found: {}", path)));
-        }
-
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let source = Decoder::new(reader)
-            .map_err(|e| AudioError::Decode(e.to_string()))?;
-
-        let sample_rate = source.sample_rate();
-        let channels = source.channels();
-        let duration = source.total_duration().unwrap_or(Duration::ZERO);
-        let duration_ms = duration.as_millis() as u64;
-
-        let sink = Sink::connect_new(self.stream.mixer());
-        sink.set_volume(self.volume);
-        sink.append(source);
-        sink.pause();
+        let track_info = self.backend.load(path)?;
 
-        let track_info = TrackInfo {
-            path: path.to_string(),
-            duration_ms,
-            sample_rate,
-            channels,
-        };
+        if let Some(threshold_db) = self.trim_silence_db {
+            self.silence = self.backend.scan_silence(threshold_db).unwrap_or_else(|e| {
+                warn!("Silence scan failed for {}: {}", path, e);
+                SilenceBounds::default()
+            });
+            if self.silence.lead_ms > 0 && self.backend.seek(self.silence.lead_ms).is_err() {
+                self.silence.lead_ms = 0;
+            }
+        }
 
-        self.player_handle = Some(PlayerHandle { sink });
         self.current_track = Some(track_info.clone());
         self.state = PlaybackState::Paused;
 
         Ok(track_info)
     }
 
+    /// Start or resume output, fading in
+    ///
+    /// Resuming during a pause fade-out cancels it and ramps back up from the
+    /// current gain.
     pub fn play(&mut self) -> Result<(), AudioError> {
-        if let Some(ref handle) = self.player_handle {
-            handle.sink.play();
-            self.state = PlaybackState::Playing;
-            Ok(())
-        } else {
-            Err(AudioError::NoTrack)
+        if self.current_track.is_none() {
+            return Err(AudioError::NoTrack);
         }
+        let now = Instant::now();
+        let from = if self.is_outputting() { self.current_gain(now) } else { 0.0 };
+        self.fade = None;
+        self.start_fade(from, self.output_gain(), now, FadeEnd::Continue);
+        self.backend.play()?;
+        self.state = PlaybackState::Playing;
+        Ok(())
     }
 
+    /// Pause output after fading out
+    ///
+    /// The state is reported as paused immediately; the backend keeps running
+    /// until the fade completes so the fade is audible.
     pub fn pause(&mut self) -> Result<(), AudioError> {
-        if let Some(ref handle) = self.player_handle {
-            handle.sink.pause();
-            self.state = PlaybackState::Paused;
-            Ok(())
-        } else {
-            Err(AudioError::NoTrack)
+        self.update_listen_time();
+        if self.current_track.is_none() {
+            return Err(AudioError::NoTrack);
+        }
+        if self.state == PlaybackState::Playing {
+            let now = Instant::now();
+            let from = self.current_gain(now);
+            self.fade = None;
+            self.start_fade(from, 0.0, now, FadeEnd::Pause);
+        } else if self.fade.is_none() {
+            self.backend.pause()?;
         }
+        self.state = PlaybackState::Paused;
+        Ok(())
     }
 
+    /// Stop and unload the track after fading out
     pub fn stop(&mut self) {
-        if let Some(handle) = self.player_handle.take() {
-            handle.sink.stop();
+        if self.is_outputting() {
+            let now = Instant::now();
+            let from = self.current_gain(now);
+            self.fade = None;
+            self.start_fade(from, 0.0, now, FadeEnd::Stop);
+        } else {
+            self.fade = None;
+            self.backend.stop();
         }
+        self.reset_track();
+    }
+
+    /// Stop immediately, cutting any fade short
+    fn stop_now(&mut self) {
+        self.fade = None;
+        self.backend.stop();
+        self.reset_track();
+    }
+
+    fn reset_track(&mut self) {
         self.state = PlaybackState::Stopped;
         self.current_track = None;
+        self.chapters.clear();
+        self.silence = SilenceBounds::default();
+        self.listen.reset();
+        self.preview_ms = None;
     }
 
+    /// Set the fade length in milliseconds (0 disables fades)
+    pub fn set_fade_ms(&mut self, fade_ms: u64) {
+        self.fade_ms = fade_ms.min(MAX_FADE_MS);
+    }
+
+    pub fn fade_ms(&self) -> u64 {
+        self.fade_ms
+    }
+
+    /// Set the output buffer length in milliseconds
+    ///
+    /// Rebuilds the output stream; the current track resumes at the same
+    /// position. Values outside `MIN_BUFFER_MS..=MAX_BUFFER_MS` are rejected
+    /// and the previous buffer is kept.
+    pub fn set_buffer_ms(&mut self, buffer_ms: u64) -> Result<(), AudioError> {
+        if !(MIN_BUFFER_MS..=MAX_BUFFER_MS).contains(&buffer_ms) {
+            return Err(AudioError::InvalidBufferSize(buffer_ms));
+        }
+        if buffer_ms == self.backend.buffer_ms() {
+            return Ok(());
+        }
+        self.backend.set_buffer_ms(buffer_ms)
+    }
+
+    pub fn buffer_ms(&self) -> u64 {
+        self.backend.buffer_ms()
+    }
+
+    /// Turn leading and trailing silence trimming on or off
+    ///
+    /// Frames at or below `threshold_db` count as silence. Thresholds outside
+    /// `MIN_SILENCE_THRESHOLD_DB..=MAX_SILENCE_THRESHOLD_DB` are rejected, so
+    /// only near-zero audio is ever skipped. Takes effect from the next load.
+    pub fn set_trim_silence(&mut self, enabled: bool, threshold_db: f32) -> Result<(), AudioError> {
+        if !(MIN_SILENCE_THRESHOLD_DB..=MAX_SILENCE_THRESHOLD_DB).contains(&threshold_db) {
+            return Err(AudioError::InvalidSilenceThreshold(threshold_db));
+        }
+        self.trim_silence_db = enabled.then_some(threshold_db);
+        Ok(())
+    }
+
+    /// Silence trimming threshold, `None` when trimming is off
+    pub fn trim_silence_db(&self) -> Option<f32> {
+        self.trim_silence_db
+    }
+
+    /// Silence skipped at each end of the current track
+    pub fn silence_bounds(&self) -> SilenceBounds {
+        self.silence
+    }
+
+    /// Whether a fade is in progress and needs `tick_fade` calls
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Advance the current fade, pausing or stopping the backend once a
+    /// fade-out completes
+    pub fn tick_fade(&mut self, now: Instant) {
+        let Some(fade) = self.fade else {
+            return;
+        };
+        self.apply_gain(fade.gain_at(now));
+        if fade.is_done(now) {
+            self.fade = None;
+            self.finish_fade(fade.end);
+        }
+    }
+
+    fn start_fade(&mut self, from: f32, to: f32, now: Instant, end: FadeEnd) {
+        if self.fade_ms == 0 {
+            self.apply_gain(to);
+            self.finish_fade(end);
+            return;
+        }
+        self.apply_gain(from);
+        let duration = Duration::from_millis(self.fade_ms);
+        self.fade = Some(Fade::new(from, to, now, duration, end));
+    }
+
+    fn finish_fade(&mut self, end: FadeEnd) {
+        match end {
+            FadeEnd::Continue => {}
+            FadeEnd::Pause => {
+                let _ = self.backend.pause();
+            }
+            FadeEnd::Stop => self.backend.stop(),
+        }
+    }
+
+    /// Whether the backend is producing output, including during a fade-out
+    fn is_outputting(&self) -> bool {
+        self.state == PlaybackState::Playing
+            || self.fade.is_some_and(|f| f.end != FadeEnd::Continue)
+    }
+
+    fn current_gain(&self, now: Instant) -> f32 {
+        self.fade.map(|f| f.gain_at(now)).unwrap_or(self.gain)
+    }
+
+    fn apply_gain(&mut self, gain: f32) {
+        self.gain = gain;
+        self.backend.set_volume(gain);
+    }
+
+    /// Seek the decoder, dropping any seek preview
     pub fn seek(&mut self, position_ms: u64) -> Result<(), AudioError> {
-        let current_pos = self.player_handle
+        self.update_listen_time();
+        if self.current_track.is_none() {
+            return Err(AudioError::NoTrack);
+        }
+        self.backend.seek(position_ms)?;
+        self.listen.record_seek(position_ms);
+        self.preview_ms = None;
+        Ok(())
+    }
+
+    /// Show `position_ms` as the position while the seek bar is dragged,
+    /// without repositioning the decoder
+    ///
+    /// Returns the previewed position, clamped to the track. Playback carries
+    /// on from where it was until `seek_commit`, so a drag doesn't stutter
+    /// through a real seek per mouse move.
+    pub fn seek_preview(&mut self, position_ms: u64) -> Result<u64, AudioError> {
+        let duration_ms = self
+            .current_track
             .as_ref()
-            .map(|h| h.sink.get_pos().as_millis() as u64)
-            .unwrap_or(0);
-        
-        let is_backward = position_ms < current_pos;
-        
-        if is_backward {
-            self.seek_by_reload(position_ms)
-        } else {
-            self.seek_forward(position_ms)
+            .ok_or(AudioError::NoTrack)?
+            .duration_ms;
+        // An unknown duration (0) can't be clamped against
+        let position_ms = if duration_ms > 0 { position_ms.min(duration_ms) } else { position_ms };
+        self.preview_ms = Some(position_ms);
+        Ok(position_ms)
+    }
+
+    /// Finish a seek bar drag with a real seek to `position_ms`
+    pub fn seek_commit(&mut self, position_ms: u64) -> Result<(), AudioError> {
+        self.seek(position_ms)
+    }
+
+    /// Position being previewed, if a seek bar drag is in progress
+    pub fn preview_position_ms(&self) -> Option<u64> {
+        self.preview_ms
+    }
+
+    /// Seek by an offset from the current position, clamped to the track
+    ///
+    /// A forward seek landing past the end, or within `advance_threshold_ms`
+    /// of it, returns `RelativeSeek::Advance` without seeking. A threshold of
+    /// 0 disables advancing, so the seek clamps to the end instead.
+    pub fn seek_relative(
+        &mut self,
+        delta_ms: i64,
+        advance_threshold_ms: u64,
+    ) -> Result<RelativeSeek, AudioError> {
+        let duration_ms = self
+            .current_track
+            .as_ref()
+            .ok_or(AudioError::NoTrack)?
+            .duration_ms;
+        let mut target = self.backend.position_ms().saturating_add_signed(delta_ms);
+
+        // An unknown duration (0) can't be clamped or advanced against
+        if duration_ms > 0 {
+            if delta_ms > 0
+                && advance_threshold_ms > 0
+                && target.saturating_add(advance_threshold_ms) > duration_ms
+            {
+                return Ok(RelativeSeek::Advance);
+            }
+            target = target.min(duration_ms);
         }
+
+        self.seek(target)?;
+        Ok(RelativeSeek::Seeked(target))
     }
-    
-    fn seek_forward(&mut self, position_ms: u64) -> Result<(), AudioError> {
-        if let Some(ref handle) = self.player_handle {
-            let duration = Duration::from_millis(position_ms);
-            handle.sink.try_seek(duration)
-                .map_err(|e| AudioError::Seek(format!("{:?}", e)))?;
-            Ok(())
-        } else {
-            Err(AudioError::NoTrack)
-        }
-    }
-    
-    fn seek_by_reload(&mut self, position_ms: u64) -> Result<(), AudioError> {
-        let track_info = self.current_track.clone().ok_or(AudioError::NoTrack)?;
-        let was_playing = self.state == PlaybackState::Playing;
-        
-        let file = File::open(&track_info.path)?;
-        let reader = BufReader::new(file);
-        let source = Decoder::new(reader)
-            .map_err(|e| AudioError::Decode(e.to_string()))?;
-        
-        if let Some(handle) = self.player_handle.take() {
-            handle.sink.stop();
-        }
-        
-        let sink = Sink::connect_new(self.stream.mixer());
-        sink.set_volume(self.volume);
-        sink.append(source);
-        
-        let duration = Duration::from_millis(position_ms);
-        sink.try_seek(duration)
-            .map_err(|e| AudioError::Seek(format!("{:?}", e)))?;
-        
-        if was_playing {
-            sink.play();
-            self.state = PlaybackState::Playing;
-        } else {
-            sink.pause();
-            self.state = PlaybackState::Paused;
+
+    /// Set the chapter start offsets of the current track
+    pub fn set_chapters(&mut self, mut starts: Vec<u64>) {
+        starts.sort_unstable();
+        starts.dedup();
+        self.chapters = starts;
+    }
+
+    /// Seek to the start of the next chapter, returning the new position
+    ///
+    /// Returns `None` without seeking when there is no later chapter.
+    pub fn next_chapter(&mut self) -> Result<Option<u64>, AudioError> {
+        if self.current_track.is_none() {
+            return Err(AudioError::NoTrack);
         }
-        
-        self.player_handle = Some(PlayerHandle { sink });
-        Ok(())
+        let position = self.backend.position_ms();
+        let Some(&start) = self.chapters.iter().find(|&&start| start > position) else {
+            return Ok(None);
+        };
+        self.seek(start)?;
+        Ok(Some(start))
+    }
+
+    /// Seek to the start of the current chapter, or the previous one when
+    /// within `PREV_CHAPTER_RESTART_MS` of the current chapter's start
+    ///
+    /// Returns `None` without seeking when the track has no chapters.
+    pub fn prev_chapter(&mut self) -> Result<Option<u64>, AudioError> {
+        if self.current_track.is_none() {
+            return Err(AudioError::NoTrack);
+        }
+        if self.chapters.is_empty() {
+            return Ok(None);
+        }
+        let position = self.backend.position_ms();
+        let target = match self.chapters.iter().rposition(|&start| start <= position) {
+            Some(i) if position - self.chapters[i] >= PREV_CHAPTER_RESTART_MS => self.chapters[i],
+            Some(i) if i > 0 => self.chapters[i - 1],
+            _ => 0,
+        };
+        self.seek(target)?;
+        Ok(Some(target))
     }
 
+    /// Set the volume level; while muted this only changes the level restored on unmute
     pub fn set_volume(&mut self, volume: f32) {
         self.volume = volume.clamp(0.0, 1.0);
-        if let Some(ref handle) = self.player_handle {
-            handle.sink.set_volume(self.volume);
-        }
+        self.refresh_gain();
     }
 
     pub fn get_volume(&self) -> f32 {
         self.volume
     }
 
-    pub fn get_progress(&self) -> Progress {
-        let (position_ms, duration_ms) = if let Some(ref handle) = self.player_handle {
-            let pos = handle.sink.get_pos();
-            let dur = self.current_track.as_ref().map(|t| t.duration_ms).unwrap_or(0);
-            (pos.as_millis() as u64, dur)
-        } else {
-            (0, 0)
-        };
+    /// Silence output without changing the volume level
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.refresh_gain();
+    }
 
-        let state = if self.is_finished() {
-            PlaybackState::Stopped
-        } else {
-            self.state
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    fn output_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.volume }
+    }
+
+    /// Apply a volume or mute change, retargeting a fade-in in progress
+    fn refresh_gain(&mut self) {
+        let target = self.output_gain();
+        match self.fade.as_mut() {
+            Some(fade) if fade.end == FadeEnd::Continue => fade.to = target,
+            // A fade-out keeps ramping to silence
+            Some(_) => {}
+            None => self.apply_gain(target),
+        }
+    }
+
+    pub fn get_progress(&self) -> Progress {
+        // A stop fade-out keeps the backend loaded briefly after the track is cleared
+        let (position_ms, duration_ms) = match self.current_track {
+            Some(ref track) if self.backend.is_loaded() => {
+                (self.backend.position_ms(), track.duration_ms)
+            }
+            _ => (0, 0),
         };
 
         Progress {
             position_ms,
             duration_ms,
-            state,
+            state: self.get_state(),
+            preview_position_ms: self.preview_ms,
         }
     }
 
@@ -218,17 +467,44 @@ impl AudioEngine {
         }
     }
 
+    /// Accumulate listened time from the current position while playing.
+    ///
+    /// Called periodically by the audio thread; paused or finished playback
+    /// does not advance the listened time.
+    pub fn update_listen_time(&mut self) {
+        if self.state != PlaybackState::Playing || self.is_finished() {
+            return;
+        }
+        if self.backend.is_loaded() {
+            self.listen.record_progress(self.backend.position_ms());
+        }
+    }
+
+    /// Milliseconds of the current track actually listened to
+    pub fn listened_ms(&self) -> u64 {
+        self.listen.listened_ms()
+    }
+
     pub fn get_current_track(&self) -> Option<&TrackInfo> {
         self.current_track.as_ref()
     }
 
     pub fn is_finished(&self) -> bool {
-        if let Some(ref handle) = self.player_handle {
-            // Track is finished when sink is empty (all sources consumed)
-            // and we were previously playing
-            handle.sink.empty() && self.state == PlaybackState::Playing
-        } else {
-            false
+        // Track is finished when the backend has output everything, or only
+        // trimmed silence is left, and we were previously playing
+        (self.backend.is_drained() || self.in_trailing_silence())
+            && self.state == PlaybackState::Playing
+    }
+
+    /// Whether playback has reached the trimmed silence at the end
+    fn in_trailing_silence(&self) -> bool {
+        let trail_ms = self.silence.trail_ms;
+        match self.current_track {
+            Some(ref track) if trail_ms > 0 && track.duration_ms > trail_ms => {
+                self.backend.is_loaded()
+                    && self.backend.position_ms() >= track.duration_ms - trail_ms
+            }
+            _ => false,
         }
     }
 }
@@ -1,11 +1,22 @@
 //! Unit tests for audio engine types and serialization.
 //!
-//! These tests verify the correctness of audio-related types
-//! without requiring actual audio output.
+//! These tests verify the correctness of audio-related types, and the
+//! engine's state machine against a mock backend, without requiring actual
+//! audio output.
 
 #[cfg(test)]
 mod tests {
-    use crate::audio::{PlaybackState, Progress, TrackInfo};
+    use crate::audio::mock::{MockBackend, MockHandle};
+    use crate::audio::{
+        AudioEngine, AudioError, PlaybackState, Progress, RelativeSeek, SilenceBounds, TrackInfo,
+        DEFAULT_BUFFER_MS, MAX_BUFFER_MS, MAX_FADE_MS, MAX_SILENCE_THRESHOLD_DB, MIN_BUFFER_MS,
+    };
+    use std::time::{Duration, Instant};
+
+    fn mock_engine() -> (AudioEngine, MockHandle) {
+        let (backend, handle) = MockBackend::with_handle();
+        (AudioEngine::with_backend(Box::new(backend)), handle)
+    }
 
     // ==================== PlaybackState Tests ====================
 
@@ -194,6 +205,7 @@ mod tests {
             position_ms: 30000,
             duration_ms: 180000,
             state: PlaybackState::Playing,
+            preview_position_ms: None,
         };
 
         assert_eq!(progress.position_ms, 30000);
@@ -207,6 +219,7 @@ mod tests {
             position_ms: 60000,
             duration_ms: 300000,
             state: PlaybackState::Paused,
+            preview_position_ms: None,
         };
 
         let cloned = progress.clone();
@@ -221,6 +234,7 @@ mod tests {
             position_ms: 45000,
             duration_ms: 200000,
             state: PlaybackState::Playing,
+            preview_position_ms: None,
         };
 
         let json = serde_json::to_string(&progress).unwrap();
@@ -249,6 +263,7 @@ mod tests {
             position_ms: 0,
             duration_ms: 180000,
             state: PlaybackState::Stopped,
+            preview_position_ms: None,
         };
 
         assert_eq!(progress.position_ms, 0);
@@ -260,6 +275,7 @@ mod tests {
             position_ms: 180000,
             duration_ms: 180000,
             state: PlaybackState::Stopped,
+            preview_position_ms: None,
         };
 
         assert_eq!(progress.position_ms, progress.duration_ms);
@@ -271,6 +287,7 @@ mod tests {
             position_ms: 90000, // 1.5 minutes
             duration_ms: 180000, // 3 minutes
             state: PlaybackState::Playing,
+            preview_position_ms: None,
         };
 
         let percentage = if progress.duration_ms > 0 {
@@ -288,6 +305,7 @@ mod tests {
             position_ms: 0,
             duration_ms: 0,
             state: PlaybackState::Stopped,
+            preview_position_ms: None,
         };
 
         // Should handle zero duration gracefully
@@ -311,6 +329,7 @@ mod tests {
                 position_ms: 1000,
                 duration_ms: 2000,
                 state,
+                preview_position_ms: None,
             };
             assert_eq!(progress.state, state);
         }
@@ -364,6 +383,7 @@ mod tests {
             position_ms: u64::MAX,
             duration_ms: u64::MAX,
             state: PlaybackState::Playing,
+            preview_position_ms: None,
         };
 
         let json = serde_json::to_string(&progress).unwrap();
@@ -371,4 +391,381 @@ mod tests {
         assert_eq!(progress.position_ms, deserialized.position_ms);
         assert_eq!(progress.duration_ms, deserialized.duration_ms);
     }
+
+    // ==================== AudioEngine State Machine Tests ====================
+
+    #[test]
+    fn test_engine_starts_stopped() {
+        let (engine, _) = mock_engine();
+
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+        assert!(engine.get_current_track().is_none());
+        let progress = engine.get_progress();
+        assert_eq!(progress.position_ms, 0);
+        assert_eq!(progress.duration_ms, 0);
+    }
+
+    #[test]
+    fn test_engine_requires_track() {
+        let (mut engine, _) = mock_engine();
+
+        assert!(matches!(engine.play(), Err(AudioError::NoTrack)));
+        assert!(matches!(engine.pause(), Err(AudioError::NoTrack)));
+        assert!(matches!(engine.seek(1000), Err(AudioError::NoTrack)));
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+    }
+
+    #[test]
+    fn test_engine_load_play_pause_stop() {
+        let (mut engine, handle) = mock_engine();
+
+        let track = engine.load("/music/song.mp3").unwrap();
+        assert_eq!(track.duration_ms, 180_000);
+        assert_eq!(engine.get_state(), PlaybackState::Paused);
+        assert!(!handle.lock().playing);
+
+        engine.play().unwrap();
+        assert_eq!(engine.get_state(), PlaybackState::Playing);
+        assert!(handle.lock().playing);
+
+        engine.pause().unwrap();
+        assert_eq!(engine.get_state(), PlaybackState::Paused);
+        assert!(!handle.lock().playing);
+
+        engine.stop();
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+        assert!(engine.get_current_track().is_none());
+        assert!(handle.lock().track.is_none());
+    }
+
+    #[test]
+    fn test_engine_failed_load_stays_stopped() {
+        let (mut engine, _) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+
+        assert!(engine.load("").is_err());
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+        assert!(engine.get_current_track().is_none());
+    }
+
+    #[test]
+    fn test_engine_finishes_when_drained_while_playing() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+
+        // A drained but paused track is not finished
+        handle.lock().drained = true;
+        assert!(!engine.is_finished());
+
+        engine.play().unwrap();
+        assert!(engine.is_finished());
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+        assert_eq!(engine.get_progress().state, PlaybackState::Stopped);
+    }
+
+    #[test]
+    fn test_engine_volume_clamped_and_forwarded() {
+        let (mut engine, handle) = mock_engine();
+
+        engine.set_volume(1.5);
+        assert_eq!(engine.get_volume(), 1.0);
+        assert_eq!(handle.lock().volume, 1.0);
+
+        engine.set_volume(-0.5);
+        assert_eq!(engine.get_volume(), 0.0);
+        assert_eq!(handle.lock().volume, 0.0);
+    }
+
+    #[test]
+    fn test_engine_listen_time_skips_seeks() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+        engine.play().unwrap();
+
+        for position_ms in [0, 500, 1000, 1500] {
+            handle.lock().position_ms = position_ms;
+            engine.update_listen_time();
+        }
+        assert_eq!(engine.listened_ms(), 1500);
+
+        engine.seek(120_000).unwrap();
+        assert_eq!(handle.lock().seeks, vec![120_000]);
+        handle.lock().position_ms = 120_500;
+        engine.update_listen_time();
+        assert_eq!(engine.listened_ms(), 2000);
+
+        // Paused playback does not accumulate
+        engine.pause().unwrap();
+        handle.lock().position_ms = 121_000;
+        engine.update_listen_time();
+        assert_eq!(engine.listened_ms(), 2000);
+    }
+
+    #[test]
+    fn test_engine_mute_change_volume_unmute() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+        engine.set_volume(0.6);
+
+        engine.set_muted(true);
+        assert!(engine.is_muted());
+        assert_eq!(engine.get_volume(), 0.6);
+        assert_eq!(handle.lock().volume, 0.0);
+
+        // Changing volume while muted updates the stored level but stays silent
+        engine.set_volume(0.3);
+        assert_eq!(engine.get_volume(), 0.3);
+        assert_eq!(handle.lock().volume, 0.0);
+
+        engine.set_muted(false);
+        assert!(!engine.is_muted());
+        assert_eq!(handle.lock().volume, 0.3);
+    }
+
+    #[test]
+    fn test_engine_unmute_to_zero_volume() {
+        let (mut engine, handle) = mock_engine();
+
+        engine.set_muted(true);
+        engine.set_volume(0.0);
+        engine.set_muted(false);
+
+        assert!(!engine.is_muted());
+        assert_eq!(engine.get_volume(), 0.0);
+        assert_eq!(handle.lock().volume, 0.0);
+    }
+
+    #[test]
+    fn test_engine_pause_waits_for_fade_out() {
+        let (mut engine, handle) = mock_engine();
+        engine.set_fade_ms(40);
+        engine.load("/music/song.mp3").unwrap();
+
+        engine.play().unwrap();
+        assert!(engine.is_fading());
+        assert!(handle.lock().playing);
+        assert_eq!(handle.lock().volume, 0.0);
+        engine.tick_fade(Instant::now() + Duration::from_millis(100));
+        assert!(!engine.is_fading());
+        assert_eq!(handle.lock().volume, 1.0);
+
+        engine.pause().unwrap();
+        assert_eq!(engine.get_state(), PlaybackState::Paused);
+        // Output keeps running until the fade-out completes
+        assert!(handle.lock().playing);
+        engine.tick_fade(Instant::now() + Duration::from_millis(100));
+        assert!(!handle.lock().playing);
+        assert_eq!(handle.lock().volume, 0.0);
+    }
+
+    #[test]
+    fn test_engine_resume_during_fade_out_cancels_pause() {
+        let (mut engine, handle) = mock_engine();
+        engine.set_fade_ms(40);
+        engine.set_volume(0.8);
+        engine.load("/music/song.mp3").unwrap();
+        engine.play().unwrap();
+        engine.tick_fade(Instant::now() + Duration::from_millis(100));
+
+        engine.pause().unwrap();
+        engine.play().unwrap();
+        assert_eq!(engine.get_state(), PlaybackState::Playing);
+
+        engine.tick_fade(Instant::now() + Duration::from_millis(100));
+        assert!(!engine.is_fading());
+        assert!(handle.lock().playing);
+        assert_eq!(handle.lock().volume, 0.8);
+    }
+
+    #[test]
+    fn test_engine_fade_ms_clamped() {
+        let (mut engine, _handle) = mock_engine();
+        engine.set_fade_ms(10_000);
+        assert_eq!(engine.fade_ms(), MAX_FADE_MS);
+    }
+
+    #[test]
+    fn test_engine_rejects_invalid_buffer_size() {
+        let (mut engine, handle) = mock_engine();
+        engine.set_buffer_ms(120).unwrap();
+
+        for invalid in [0, MIN_BUFFER_MS - 1, MAX_BUFFER_MS + 1] {
+            assert!(matches!(
+                engine.set_buffer_ms(invalid),
+                Err(AudioError::InvalidBufferSize(ms)) if ms == invalid
+            ));
+            assert_eq!(engine.buffer_ms(), 120);
+        }
+        assert_eq!(handle.lock().rebuilds, 1);
+    }
+
+    #[test]
+    fn test_engine_buffer_change_keeps_position() {
+        let (mut engine, handle) = mock_engine();
+        assert_eq!(engine.buffer_ms(), DEFAULT_BUFFER_MS);
+
+        engine.load("/music/song.mp3").unwrap();
+        engine.play().unwrap();
+        engine.seek(42_000).unwrap();
+
+        engine.set_buffer_ms(200).unwrap();
+        assert_eq!(engine.buffer_ms(), 200);
+        assert_eq!(engine.get_state(), PlaybackState::Playing);
+        assert_eq!(engine.get_progress().position_ms, 42_000);
+
+        // Setting the same size again doesn't rebuild the output
+        engine.set_buffer_ms(200).unwrap();
+        assert_eq!(handle.lock().rebuilds, 1);
+    }
+
+    #[test]
+    fn test_engine_trims_silence_when_enabled() {
+        let (mut engine, handle) = mock_engine();
+        handle.lock().silence = SilenceBounds {
+            lead_ms: 1500,
+            trail_ms: 4000,
+        };
+
+        // Off by default: nothing is scanned or skipped
+        engine.load("/music/song.mp3").unwrap();
+        assert!(handle.lock().silence_scans.is_empty());
+        assert_eq!(engine.get_progress().position_ms, 0);
+
+        engine.set_trim_silence(true, -60.0).unwrap();
+        engine.load("/music/song.mp3").unwrap();
+        assert_eq!(handle.lock().silence_scans, vec![-60.0]);
+        assert_eq!(engine.get_progress().position_ms, 1500);
+
+        // The track ends where its trailing silence starts (180 s track)
+        engine.play().unwrap();
+        handle.lock().position_ms = 175_999;
+        assert!(!engine.is_finished());
+        handle.lock().position_ms = 176_000;
+        assert!(engine.is_finished());
+        assert_eq!(engine.get_state(), PlaybackState::Stopped);
+
+        engine.set_trim_silence(false, -60.0).unwrap();
+        engine.load("/music/song.mp3").unwrap();
+        assert_eq!(engine.silence_bounds(), SilenceBounds::default());
+        assert_eq!(handle.lock().silence_scans.len(), 1);
+    }
+
+    #[test]
+    fn test_engine_rejects_audible_silence_threshold() {
+        let (mut engine, _handle) = mock_engine();
+        engine.set_trim_silence(true, -70.0).unwrap();
+
+        let too_loud = MAX_SILENCE_THRESHOLD_DB + 1.0;
+        assert!(matches!(
+            engine.set_trim_silence(true, too_loud),
+            Err(AudioError::InvalidSilenceThreshold(db)) if db == too_loud
+        ));
+        assert_eq!(engine.trim_silence_db(), Some(-70.0));
+    }
+
+    #[test]
+    fn test_engine_seek_relative_clamps() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+        handle.lock().position_ms = 10_000;
+
+        assert_eq!(engine.seek_relative(-30_000, 0).unwrap(), RelativeSeek::Seeked(0));
+        assert_eq!(engine.seek_relative(45_000, 0).unwrap(), RelativeSeek::Seeked(45_000));
+
+        // Without an advance threshold, seeking past the end clamps to the duration
+        assert_eq!(
+            engine.seek_relative(600_000, 0).unwrap(),
+            RelativeSeek::Seeked(180_000)
+        );
+        assert_eq!(handle.lock().seeks, vec![0, 45_000, 180_000]);
+    }
+
+    #[test]
+    fn test_engine_seek_relative_advances_near_end() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+        handle.lock().position_ms = 160_000;
+
+        assert_eq!(engine.seek_relative(30_000, 5000).unwrap(), RelativeSeek::Advance);
+        assert_eq!(engine.seek_relative(16_000, 5000).unwrap(), RelativeSeek::Advance);
+        assert!(handle.lock().seeks.is_empty());
+
+        // Outside the threshold it seeks normally, and backward never advances
+        assert_eq!(
+            engine.seek_relative(10_000, 5000).unwrap(),
+            RelativeSeek::Seeked(170_000)
+        );
+        assert_eq!(
+            engine.seek_relative(-1000, 5000).unwrap(),
+            RelativeSeek::Seeked(169_000)
+        );
+    }
+
+    #[test]
+    fn test_engine_seek_preview_leaves_decoder_in_place() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/music/song.mp3").unwrap();
+        engine.play().unwrap();
+        handle.lock().position_ms = 10_000;
+
+        // Dragging: each preview only moves the reported preview position
+        for target in [20_000, 60_000, 95_000] {
+            assert_eq!(engine.seek_preview(target).unwrap(), target);
+        }
+        assert_eq!(engine.seek_preview(900_000).unwrap(), 180_000);
+        assert!(handle.lock().seeks.is_empty());
+        let progress = engine.get_progress();
+        assert_eq!(progress.position_ms, 10_000);
+        assert_eq!(progress.preview_position_ms, Some(180_000));
+        assert_eq!(engine.get_state(), PlaybackState::Playing);
+
+        // Releasing commits a single real seek and ends the preview
+        engine.seek_commit(95_000).unwrap();
+        assert_eq!(handle.lock().seeks, vec![95_000]);
+        let progress = engine.get_progress();
+        assert_eq!(progress.position_ms, 95_000);
+        assert_eq!(progress.preview_position_ms, None);
+    }
+
+    #[test]
+    fn test_engine_seek_preview_cleared_by_load() {
+        let (mut engine, _handle) = mock_engine();
+        assert!(matches!(engine.seek_preview(1000), Err(AudioError::NoTrack)));
+
+        engine.load("/music/song.mp3").unwrap();
+        engine.seek_preview(30_000).unwrap();
+        engine.load("/music/other.mp3").unwrap();
+        assert_eq!(engine.preview_position_ms(), None);
+        assert_eq!(engine.get_progress().preview_position_ms, None);
+    }
+
+    #[test]
+    fn test_engine_seek_relative_requires_track() {
+        let (mut engine, _handle) = mock_engine();
+        assert!(matches!(engine.seek_relative(1000, 0), Err(AudioError::NoTrack)));
+    }
+
+    #[test]
+    fn test_engine_chapter_navigation() {
+        let (mut engine, handle) = mock_engine();
+        engine.load("/books/book.m4b").unwrap();
+        engine.set_chapters(vec![60_000, 0, 120_000]);
+
+        handle.lock().position_ms = 30_000;
+        assert_eq!(engine.next_chapter().unwrap(), Some(60_000));
+        assert_eq!(engine.next_chapter().unwrap(), Some(120_000));
+        assert_eq!(engine.next_chapter().unwrap(), None);
+
+        // Well into a chapter, previous restarts it
+        handle.lock().position_ms = 150_000;
+        assert_eq!(engine.prev_chapter().unwrap(), Some(120_000));
+        // Near its start, previous goes back one chapter
+        handle.lock().position_ms = 121_000;
+        assert_eq!(engine.prev_chapter().unwrap(), Some(60_000));
+
+        // Loading another track clears the chapters
+        engine.load("/music/song.mp3").unwrap();
+        assert_eq!(engine.next_chapter().unwrap(), None);
+        assert_eq!(engine.prev_chapter().unwrap(), None);
+    }
 }
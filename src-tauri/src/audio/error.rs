@@ -22,6 +22,12 @@ pub enum AudioError {
 
     #[error("Stream error: {0}")]
     Stream(String),
+
+    #[error("Invalid buffer size: {0} ms")]
+    InvalidBufferSize(u64),
+
+    #[error("Invalid silence threshold: {0} dB")]
+    InvalidSilenceThreshold(f32),
 }
 
 impl From<std::io::Error> for AudioError {
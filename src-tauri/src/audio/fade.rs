@@ -0,0 +1,134 @@
+//! Short gain ramps around play, pause, and stop.
+//!
+//! Starting or halting output mid-waveform produces an audible click. Ramping
+//! the gain over a few tens of milliseconds avoids it. The ramp is advanced by
+//! the audio thread, which polls more often while a fade is in progress.
+
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+/// Default fade length applied by the audio thread
+pub const DEFAULT_FADE_MS: u64 = 30;
+
+/// Longest accepted fade length
+pub const MAX_FADE_MS: u64 = 200;
+
+/// Gain at `elapsed` into a ramp from `from` to `to` lasting `duration`
+///
+/// Uses a raised-cosine curve, which starts and ends with zero slope so the
+/// ramp itself doesn't introduce a click. Before the start the gain is `from`;
+/// at or after the end it is exactly `to`.
+pub fn fade_gain(from: f32, to: f32, elapsed: Duration, duration: Duration) -> f32 {
+    if duration.is_zero() || elapsed >= duration {
+        return to;
+    }
+    let t = elapsed.as_secs_f32() / duration.as_secs_f32();
+    let shaped = 0.5 - 0.5 * (PI * t).cos();
+    from + (to - from) * shaped
+}
+
+/// What to do to the output once a fade finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeEnd {
+    /// Leave output running (fade-in)
+    Continue,
+    /// Pause the output (fade-out before pause)
+    Pause,
+    /// Stop and unload the output (fade-out before stop)
+    Stop,
+}
+
+/// An in-progress gain ramp
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    pub from: f32,
+    pub to: f32,
+    pub start: Instant,
+    pub duration: Duration,
+    pub end: FadeEnd,
+}
+
+impl Fade {
+    pub fn new(from: f32, to: f32, start: Instant, duration: Duration, end: FadeEnd) -> Self {
+        Self {
+            from,
+            to,
+            start,
+            duration,
+            end,
+        }
+    }
+
+    /// Gain at `now`
+    pub fn gain_at(&self, now: Instant) -> f32 {
+        fade_gain(
+            self.from,
+            self.to,
+            now.saturating_duration_since(self.start),
+            self.duration,
+        )
+    }
+
+    /// Whether the ramp has reached its target at `now`
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS_50: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn test_fade_gain_endpoints() {
+        assert_eq!(fade_gain(0.0, 0.8, Duration::ZERO, MS_50), 0.0);
+        assert_eq!(fade_gain(0.0, 0.8, MS_50, MS_50), 0.8);
+        assert_eq!(fade_gain(0.0, 0.8, Duration::from_millis(80), MS_50), 0.8);
+    }
+
+    #[test]
+    fn test_fade_gain_midpoint_is_halfway() {
+        let mid = fade_gain(0.0, 1.0, Duration::from_millis(25), MS_50);
+        assert!((mid - 0.5).abs() < 1e-5, "midpoint was {}", mid);
+
+        let mid = fade_gain(1.0, 0.0, Duration::from_millis(25), MS_50);
+        assert!((mid - 0.5).abs() < 1e-5, "midpoint was {}", mid);
+    }
+
+    #[test]
+    fn test_fade_gain_is_monotonic_and_eased() {
+        let samples: Vec<f32> = (0..=50)
+            .map(|ms| fade_gain(0.0, 1.0, Duration::from_millis(ms), MS_50))
+            .collect();
+        assert!(samples.windows(2).all(|w| w[1] >= w[0]));
+
+        // Eased: the first and last steps are smaller than the middle step
+        let first = samples[1] - samples[0];
+        let middle = samples[26] - samples[25];
+        let last = samples[50] - samples[49];
+        assert!(first < middle);
+        assert!(last < middle);
+
+        let down: Vec<f32> = (0..=50)
+            .map(|ms| fade_gain(1.0, 0.0, Duration::from_millis(ms), MS_50))
+            .collect();
+        assert!(down.windows(2).all(|w| w[1] <= w[0]));
+    }
+
+    #[test]
+    fn test_fade_gain_zero_duration_jumps_to_target() {
+        assert_eq!(fade_gain(0.0, 0.6, Duration::ZERO, Duration::ZERO), 0.6);
+    }
+
+    #[test]
+    fn test_fade_done() {
+        let start = Instant::now();
+        let fade = Fade::new(1.0, 0.0, start, MS_50, FadeEnd::Pause);
+
+        assert!(!fade.is_done(start + Duration::from_millis(10)));
+        assert!(fade.is_done(start + MS_50));
+        assert_eq!(fade.gain_at(start + MS_50), 0.0);
+    }
+}
@@ -0,0 +1,127 @@
+//! Listen-time accounting for the current track.
+//!
+//! Tracks how much of a track was actually heard, so scrobbling is based on
+//! real listening rather than the playback position. Pauses stop the clock,
+//! and seeking forward skips the unheard section instead of counting it.
+
+/// Largest position jump treated as continuous playback between two samples.
+/// Anything bigger is an unreported seek and is not counted as listened time.
+const MAX_PROGRESS_STEP_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Default)]
+pub struct ListenTracker {
+    listened_ms: u64,
+    last_position_ms: Option<u64>,
+}
+
+impl ListenTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all accumulated time (new track loaded or playback stopped)
+    pub fn reset(&mut self) {
+        self.listened_ms = 0;
+        self.last_position_ms = None;
+    }
+
+    /// Record the playback position while the track is playing.
+    ///
+    /// Only forward movement within `MAX_PROGRESS_STEP_MS` of the previous
+    /// sample counts as listened time.
+    pub fn record_progress(&mut self, position_ms: u64) {
+        if let Some(last) = self.last_position_ms
+            && position_ms > last
+            && position_ms - last <= MAX_PROGRESS_STEP_MS
+        {
+            self.listened_ms += position_ms - last;
+        }
+        self.last_position_ms = Some(position_ms);
+    }
+
+    /// Record a seek: the next sample is measured from the new position
+    pub fn record_seek(&mut self, position_ms: u64) {
+        self.last_position_ms = Some(position_ms);
+    }
+
+    /// Total time actually listened to, in milliseconds
+    pub fn listened_ms(&self) -> u64 {
+        self.listened_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listen_tracker_accumulates_progress() {
+        let mut tracker = ListenTracker::new();
+        for position in (0..=10_000).step_by(250) {
+            tracker.record_progress(position);
+        }
+        assert_eq!(tracker.listened_ms(), 10_000);
+    }
+
+    #[test]
+    fn test_listen_tracker_pause_does_not_count() {
+        let mut tracker = ListenTracker::new();
+        tracker.record_progress(0);
+        tracker.record_progress(2_000);
+        tracker.record_progress(4_000);
+        tracker.record_progress(5_000);
+
+        // Paused: no samples are recorded and the position does not move.
+        // On resume the first sample is at the same position.
+        tracker.record_progress(5_000);
+        tracker.record_progress(6_000);
+
+        assert_eq!(tracker.listened_ms(), 6_000);
+    }
+
+    #[test]
+    fn test_listen_tracker_seek_forward_skips_unheard() {
+        let mut tracker = ListenTracker::new();
+        tracker.record_progress(0);
+        tracker.record_progress(1_000);
+
+        tracker.record_seek(120_000);
+        tracker.record_progress(121_000);
+
+        assert_eq!(tracker.listened_ms(), 2_000);
+    }
+
+    #[test]
+    fn test_listen_tracker_seek_back_counts_relistening() {
+        let mut tracker = ListenTracker::new();
+        tracker.record_progress(0);
+        tracker.record_progress(2_000);
+
+        tracker.record_seek(0);
+        tracker.record_progress(1_000);
+
+        assert_eq!(tracker.listened_ms(), 3_000);
+    }
+
+    #[test]
+    fn test_listen_tracker_ignores_unreported_jumps() {
+        let mut tracker = ListenTracker::new();
+        tracker.record_progress(0);
+        tracker.record_progress(60_000);
+        tracker.record_progress(61_000);
+
+        assert_eq!(tracker.listened_ms(), 1_000);
+    }
+
+    #[test]
+    fn test_listen_tracker_reset() {
+        let mut tracker = ListenTracker::new();
+        tracker.record_progress(0);
+        tracker.record_progress(1_500);
+        tracker.reset();
+
+        assert_eq!(tracker.listened_ms(), 0);
+        tracker.record_progress(10_000);
+        assert_eq!(tracker.listened_ms(), 0);
+    }
+}
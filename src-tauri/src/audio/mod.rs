@@ -1,8 +1,30 @@
+mod backend;
 mod engine;
 mod error;
+mod fade;
+mod listen;
+mod notify;
+mod silence;
+mod xrun;
 
-pub use engine::{AudioEngine, PlaybackState, Progress, TrackInfo};
+pub use backend::{
+    PlaybackBackend, RodioBackend, DEFAULT_BUFFER_MS, MAX_BUFFER_MS, MIN_BUFFER_MS,
+};
+pub use engine::{
+    AudioEngine, PlaybackState, Progress, RelativeSeek, TrackInfo, PREV_CHAPTER_RESTART_MS,
+};
 pub use error::AudioError;
+pub use fade::{DEFAULT_FADE_MS, MAX_FADE_MS};
+pub use listen::ListenTracker;
+pub use notify::{PlaybackNotice, PlaybackNotifier};
+pub use silence::{
+    SilenceBounds, DEFAULT_SILENCE_THRESHOLD_DB, MAX_SILENCE_THRESHOLD_DB, MAX_TRIM_MS,
+    MIN_SILENCE_THRESHOLD_DB,
+};
+pub use xrun::{stream_monitor, StreamFailure, StreamMonitor, StreamReporter, StreamWarning};
+
+#[cfg(test)]
+pub(crate) use backend::mock;
 
 #[cfg(test)]
 #[path = "engine_test.rs"]
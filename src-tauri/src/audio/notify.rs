@@ -0,0 +1,203 @@
+//! Playback state change notifications.
+//!
+//! The audio thread shows the notifier the engine after every command and
+//! poll tick. A change of state is reported straight away; while playing,
+//! position-only updates go out at most every `POSITION_INTERVAL`. A track
+//! that plays to its end is reported once as "ended", together with a
+//! separate track-ended notice for the queue.
+
+use std::time::{Duration, Instant};
+
+use crate::audio::engine::{AudioEngine, PlaybackState};
+use crate::events::PlaybackStateChangedEvent;
+
+/// Shortest time between two position-only updates while playing
+pub const POSITION_INTERVAL: Duration = Duration::from_millis(250);
+
+/// State reported to the frontend, a superset of `PlaybackState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaybackPhase {
+    Loading,
+    Playing,
+    Paused,
+    Stopped,
+    /// Played to the end and waiting for the next track
+    Ended,
+}
+
+impl PlaybackPhase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PlaybackPhase::Loading => "loading",
+            PlaybackPhase::Playing => "playing",
+            PlaybackPhase::Paused => "paused",
+            PlaybackPhase::Stopped => "stopped",
+            PlaybackPhase::Ended => "ended",
+        }
+    }
+
+    /// The phase the engine is in
+    pub fn of(engine: &AudioEngine) -> Self {
+        if engine.is_finished() {
+            return PlaybackPhase::Ended;
+        }
+        match engine.get_state() {
+            PlaybackState::Playing => PlaybackPhase::Playing,
+            PlaybackState::Paused => PlaybackPhase::Paused,
+            PlaybackState::Stopped => PlaybackPhase::Stopped,
+        }
+    }
+}
+
+/// Something the frontend should hear about
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackNotice {
+    StateChanged(PlaybackStateChangedEvent),
+    /// The current track finished; sent right after the "ended" state
+    TrackEnded,
+}
+
+/// Turns engine snapshots into state change notices
+#[derive(Debug, Default)]
+pub struct PlaybackNotifier {
+    /// Last phase reported, `None` before the first report
+    phase: Option<PlaybackPhase>,
+    last_sent: Option<Instant>,
+}
+
+impl PlaybackNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report that a new track is about to load
+    pub fn loading(&mut self, engine: &AudioEngine, now: Instant) -> PlaybackStateChangedEvent {
+        self.phase = Some(PlaybackPhase::Loading);
+        self.last_sent = Some(now);
+        PlaybackStateChangedEvent {
+            state: PlaybackPhase::Loading.as_str().to_string(),
+            position_ms: 0,
+            duration_ms: 0,
+            volume: engine.get_volume(),
+        }
+    }
+
+    /// Compare the engine against the last report
+    pub fn observe(&mut self, engine: &AudioEngine, now: Instant) -> Vec<PlaybackNotice> {
+        let phase = PlaybackPhase::of(engine);
+        let changed = self.phase != Some(phase);
+        let position_due = phase == PlaybackPhase::Playing
+            && self
+                .last_sent
+                .is_none_or(|last| now.saturating_duration_since(last) >= POSITION_INTERVAL);
+        if !changed && !position_due {
+            return Vec::new();
+        }
+
+        self.phase = Some(phase);
+        self.last_sent = Some(now);
+        let progress = engine.get_progress();
+        let mut notices = vec![PlaybackNotice::StateChanged(PlaybackStateChangedEvent {
+            state: phase.as_str().to_string(),
+            position_ms: progress.position_ms,
+            duration_ms: progress.duration_ms,
+            volume: engine.get_volume(),
+        })];
+        if changed && phase == PlaybackPhase::Ended {
+            notices.push(PlaybackNotice::TrackEnded);
+        }
+        notices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::mock::{MockBackend, MockHandle};
+
+    fn mock_engine() -> (AudioEngine, MockHandle) {
+        let (backend, handle) = MockBackend::with_handle();
+        (AudioEngine::with_backend(Box::new(backend)), handle)
+    }
+
+    fn states(notices: &[PlaybackNotice]) -> Vec<&str> {
+        notices
+            .iter()
+            .map(|notice| match notice {
+                PlaybackNotice::StateChanged(event) => event.state.as_str(),
+                PlaybackNotice::TrackEnded => "track-ended",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loading_playing_ended_sequence() {
+        let (mut engine, handle) = mock_engine();
+        let mut notifier = PlaybackNotifier::new();
+        let start = Instant::now();
+        let mut notices = Vec::new();
+
+        let loading = notifier.loading(&engine, start);
+        notices.push(PlaybackNotice::StateChanged(loading));
+        engine.load("/music/track.mp3").unwrap();
+        engine.play().unwrap();
+        notices.extend(notifier.observe(&engine, start));
+
+        handle.lock().position_ms = 179_900;
+        handle.lock().drained = true;
+        let end = start + Duration::from_millis(50);
+        notices.extend(notifier.observe(&engine, end));
+        // Nothing more until the next track
+        notices.extend(notifier.observe(&engine, end + Duration::from_secs(1)));
+
+        assert_eq!(
+            states(&notices),
+            ["loading", "playing", "ended", "track-ended"]
+        );
+        let PlaybackNotice::StateChanged(playing) = &notices[1] else {
+            panic!("expected a state change");
+        };
+        assert_eq!(playing.duration_ms, 180_000);
+        assert_eq!(playing.volume, 1.0);
+    }
+
+    #[test]
+    fn test_position_updates_are_throttled() {
+        let (mut engine, handle) = mock_engine();
+        let mut notifier = PlaybackNotifier::new();
+        let start = Instant::now();
+        engine.load("/music/track.mp3").unwrap();
+        engine.play().unwrap();
+        assert_eq!(states(&notifier.observe(&engine, start)), ["playing"]);
+
+        handle.lock().position_ms = 100;
+        assert!(
+            notifier
+                .observe(&engine, start + Duration::from_millis(100))
+                .is_empty()
+        );
+
+        handle.lock().position_ms = 300;
+        let notices = notifier.observe(&engine, start + POSITION_INTERVAL);
+        assert_eq!(
+            notices,
+            [PlaybackNotice::StateChanged(PlaybackStateChangedEvent {
+                state: "playing".to_string(),
+                position_ms: 300,
+                duration_ms: 180_000,
+                volume: 1.0,
+            })]
+        );
+
+        // A transition goes out at once, even inside the interval
+        engine.pause().unwrap();
+        let notices = notifier.observe(&engine, start + POSITION_INTERVAL);
+        assert_eq!(states(&notices), ["paused"]);
+        // Paused playback sends no position updates
+        assert!(
+            notifier
+                .observe(&engine, start + Duration::from_secs(5))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Leading and trailing silence detection.
+//!
+//! Some rips carry seconds of digital silence before the music starts or
+//! after it ends. With trimming on, the engine starts each track at its first
+//! audible frame and moves on once only trailing silence is left, which also
+//! tightens the gap between tracks. Only runs that are near-zero (no louder
+//! than `MAX_SILENCE_THRESHOLD_DB`) and shorter than `MAX_TRIM_MS` are
+//! trimmed, so quiet intros and deliberate pauses play as recorded.
+
+/// Default level at or below which a frame counts as silent
+pub const DEFAULT_SILENCE_THRESHOLD_DB: f32 = -60.0;
+
+/// Quietest accepted threshold, below the 16-bit noise floor
+pub const MIN_SILENCE_THRESHOLD_DB: f32 = -96.0;
+
+/// Loudest accepted threshold; anything louder may be quiet music
+pub const MAX_SILENCE_THRESHOLD_DB: f32 = -40.0;
+
+/// Runs of silence this long or longer are left alone
+pub const MAX_TRIM_MS: u64 = 10_000;
+
+/// Silence kept next to the audio so its first and last moments aren't clipped
+const TRIM_MARGIN_MS: u64 = 20;
+
+/// Silence to skip at each end of a track
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SilenceBounds {
+    pub lead_ms: u64,
+    pub trail_ms: u64,
+}
+
+/// Linear amplitude of a dBFS level
+fn db_to_amplitude(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Length of the silent run at the front of `frames`, once trimmable
+fn silent_run_ms<'a>(
+    mut frames: impl Iterator<Item = &'a [f32]>,
+    sample_rate: u32,
+    threshold_db: f32,
+) -> u64 {
+    let limit = db_to_amplitude(threshold_db);
+    let mut silent_frames: u64 = 0;
+    let heard = loop {
+        match frames.next() {
+            Some(frame) if frame.iter().all(|s| s.abs() <= limit) => silent_frames += 1,
+            Some(_) => break true,
+            None => break false,
+        }
+    };
+    // A buffer that never gets louder is all silence, or too short to tell
+    if !heard || sample_rate == 0 {
+        return 0;
+    }
+
+    let ms = silent_frames * 1000 / u64::from(sample_rate);
+    if ms >= MAX_TRIM_MS {
+        return 0;
+    }
+    ms.saturating_sub(TRIM_MARGIN_MS)
+}
+
+/// Trimmable silence at the start of interleaved `samples`
+///
+/// A frame is silent when every channel is at or below `threshold_db`.
+/// Returns 0 when no audible frame follows the silence, or when the silence
+/// lasts `MAX_TRIM_MS` or longer.
+pub fn leading_silence_ms(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    threshold_db: f32,
+) -> u64 {
+    let frames = samples.chunks_exact(usize::from(channels.max(1)));
+    silent_run_ms(frames, sample_rate, threshold_db)
+}
+
+/// Trimmable silence at the end of interleaved `samples`
+///
+/// The mirror of `leading_silence_ms`.
+pub fn trailing_silence_ms(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    threshold_db: f32,
+) -> u64 {
+    let frames = samples.chunks_exact(usize::from(channels.max(1)));
+    silent_run_ms(frames.rev(), sample_rate, threshold_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    /// Interleaved stereo: `lead` silent frames, `sound` frames at `level`,
+    /// then `trail` silent frames, with `noise` on every silent sample
+    fn buffer(lead: usize, sound: usize, trail: usize, level: f32, noise: f32) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for i in 0..lead + sound + trail {
+            let audible = (lead..lead + sound).contains(&i);
+            let value = if audible { level } else { noise };
+            // Alternate signs, like a real waveform around zero
+            let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+            samples.push(sign * value);
+            samples.push(-sign * value);
+        }
+        samples
+    }
+
+    #[test]
+    fn test_detects_digital_silence_at_both_ends() {
+        let samples = buffer(2000, 3000, 4000, 0.5, 0.0);
+        assert_eq!(
+            leading_silence_ms(&samples, 2, RATE, -60.0),
+            2000 - TRIM_MARGIN_MS
+        );
+        assert_eq!(
+            trailing_silence_ms(&samples, 2, RATE, -60.0),
+            4000 - TRIM_MARGIN_MS
+        );
+    }
+
+    #[test]
+    fn test_noise_floor_below_threshold_counts_as_silence() {
+        // About -80 dBFS of dither
+        let samples = buffer(1000, 1000, 1000, 0.5, 0.0001);
+        assert_eq!(
+            leading_silence_ms(&samples, 2, RATE, -60.0),
+            1000 - TRIM_MARGIN_MS
+        );
+        // A stricter threshold hears the dither as sound
+        assert_eq!(leading_silence_ms(&samples, 2, RATE, -90.0), 0);
+    }
+
+    #[test]
+    fn test_quiet_intro_is_not_trimmed() {
+        // A -50 dBFS fade-in is music, not silence
+        let mut samples = buffer(0, 2000, 0, 0.003, 0.0);
+        samples.extend(buffer(0, 1000, 500, 0.5, 0.0));
+        assert_eq!(leading_silence_ms(&samples, 2, RATE, -60.0), 0);
+        assert_eq!(
+            trailing_silence_ms(&samples, 2, RATE, -60.0),
+            500 - TRIM_MARGIN_MS
+        );
+    }
+
+    #[test]
+    fn test_long_or_total_silence_is_not_trimmed() {
+        let cap = MAX_TRIM_MS as usize;
+        let samples = buffer(cap, 1000, cap + 1, 0.5, 0.0);
+        assert_eq!(leading_silence_ms(&samples, 2, RATE, -60.0), 0);
+        assert_eq!(trailing_silence_ms(&samples, 2, RATE, -60.0), 0);
+
+        let just_under = buffer(cap - 1, 1000, 0, 0.5, 0.0);
+        assert!(leading_silence_ms(&just_under, 2, RATE, -60.0) > 0);
+
+        let silent = buffer(3000, 0, 0, 0.0, 0.0);
+        assert_eq!(leading_silence_ms(&silent, 2, RATE, -60.0), 0);
+        assert_eq!(trailing_silence_ms(&silent, 2, RATE, -60.0), 0);
+        assert_eq!(leading_silence_ms(&[], 2, RATE, -60.0), 0);
+    }
+
+    #[test]
+    fn test_one_loud_channel_is_enough() {
+        let mut samples = buffer(500, 500, 0, 0.0, 0.0);
+        // Sound on the right channel only
+        for frame in samples.chunks_exact_mut(2).skip(500) {
+            frame[1] = 0.5;
+        }
+        assert_eq!(
+            leading_silence_ms(&samples, 2, RATE, -60.0),
+            500 - TRIM_MARGIN_MS
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Output stream fault reporting.
+//!
+//! Stream errors are reported on the audio device's own thread, where
+//! allocating or taking a lock can itself cause a dropout. The reporter only
+//! bumps an atomic underrun counter and hands fatal errors to a preallocated
+//! channel; the audio command thread polls the monitor and turns both into UI
+//! events. Repeated underruns are a hint that the output buffer is too small.
+
+use rodio::cpal;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::time::{Duration, Instant};
+
+/// New underruns needed before a warning is raised
+pub const XRUN_WARNING_THRESHOLD: u64 = 3;
+
+/// Shortest time between two warnings
+pub const XRUN_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Fatal errors held until the monitor is polled; later ones are dropped
+const ERROR_CAPACITY: usize = 4;
+
+/// Payload of the `playback:stream-warning` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamWarning {
+    /// Underruns since the output was first opened
+    pub xrun_count: u64,
+    /// Underruns since the previous warning
+    pub new_xruns: u64,
+}
+
+/// Payload of the `playback:stream-error` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamFailure {
+    pub message: String,
+}
+
+/// Create a connected reporter and monitor
+pub fn stream_monitor() -> (StreamReporter, StreamMonitor) {
+    let xruns = Arc::new(AtomicU64::new(0));
+    let (errors_tx, errors_rx) = mpsc::sync_channel(ERROR_CAPACITY);
+    let reporter = StreamReporter {
+        xruns: xruns.clone(),
+        errors: errors_tx,
+    };
+    let monitor = StreamMonitor {
+        xruns,
+        errors: errors_rx,
+        warned_xruns: 0,
+        last_warning: None,
+    };
+    (reporter, monitor)
+}
+
+/// Device-thread half: records faults without allocating or blocking
+#[derive(Clone)]
+pub struct StreamReporter {
+    xruns: Arc<AtomicU64>,
+    errors: SyncSender<&'static str>,
+}
+
+impl StreamReporter {
+    /// Record a buffer underrun or overrun
+    pub fn xrun(&self) {
+        self.xruns.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that the stream stopped for good
+    pub fn stream_died(&self, message: &'static str) {
+        // A full channel already holds an error for the UI to show
+        let _ = self.errors.try_send(message);
+    }
+
+    /// Record an error from the output stream's error callback
+    ///
+    /// Backend-specific errors are glitches the stream recovers from, such as
+    /// a failed underrun recovery, and are counted as xruns. Only a lost
+    /// device ends the stream.
+    pub fn report(&self, err: &cpal::StreamError) {
+        match err {
+            cpal::StreamError::DeviceNotAvailable => {
+                self.stream_died("The audio output device is no longer available")
+            }
+            cpal::StreamError::BackendSpecific { .. } => self.xrun(),
+        }
+    }
+}
+
+/// Audio-thread half: turns recorded faults into throttled events
+pub struct StreamMonitor {
+    xruns: Arc<AtomicU64>,
+    errors: Receiver<&'static str>,
+    /// Underrun count at the last warning
+    warned_xruns: u64,
+    last_warning: Option<Instant>,
+}
+
+impl StreamMonitor {
+    /// Underruns since the output was first opened
+    pub fn xrun_count(&self) -> u64 {
+        self.xruns.load(Ordering::Relaxed)
+    }
+
+    /// A warning, once `XRUN_WARNING_THRESHOLD` new underruns have piled up
+    /// and `XRUN_WARNING_INTERVAL` has passed since the previous one
+    pub fn poll_warning(&mut self, now: Instant) -> Option<StreamWarning> {
+        let xrun_count = self.xrun_count();
+        let new_xruns = xrun_count - self.warned_xruns;
+        if new_xruns < XRUN_WARNING_THRESHOLD {
+            return None;
+        }
+        if let Some(last) = self.last_warning
+            && now.saturating_duration_since(last) < XRUN_WARNING_INTERVAL
+        {
+            return None;
+        }
+
+        self.warned_xruns = xrun_count;
+        self.last_warning = Some(now);
+        Some(StreamWarning {
+            xrun_count,
+            new_xruns,
+        })
+    }
+
+    /// Take the next fatal stream error, if any
+    pub fn take_failure(&self) -> Option<StreamFailure> {
+        self.errors.try_recv().ok().map(|message| StreamFailure {
+            message: message.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_xruns_are_counted_and_throttled() {
+        let (reporter, mut monitor) = stream_monitor();
+        let start = Instant::now();
+
+        reporter.xrun();
+        reporter.xrun();
+        assert_eq!(monitor.xrun_count(), 2);
+        assert_eq!(monitor.poll_warning(start), None);
+
+        // The reporter is moved to the device thread in practice
+        let device_thread = reporter.clone();
+        std::thread::spawn(move || device_thread.xrun())
+            .join()
+            .unwrap();
+        assert_eq!(
+            monitor.poll_warning(start),
+            Some(StreamWarning {
+                xrun_count: 3,
+                new_xruns: 3,
+            })
+        );
+
+        // Further underruns within the interval don't warn again
+        for _ in 0..5 {
+            reporter.xrun();
+        }
+        assert_eq!(monitor.poll_warning(start + Duration::from_secs(1)), None);
+
+        let later = start + XRUN_WARNING_INTERVAL;
+        assert_eq!(
+            monitor.poll_warning(later),
+            Some(StreamWarning {
+                xrun_count: 8,
+                new_xruns: 5,
+            })
+        );
+        assert_eq!(monitor.poll_warning(later + XRUN_WARNING_INTERVAL), None);
+    }
+
+    #[test]
+    fn test_stream_errors_classified() {
+        let (reporter, monitor) = stream_monitor();
+
+        reporter.report(&cpal::StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: "snd_pcm_prepare failed".to_string(),
+            },
+        });
+        assert_eq!(monitor.xrun_count(), 1);
+        assert_eq!(monitor.take_failure(), None);
+
+        reporter.report(&cpal::StreamError::DeviceNotAvailable);
+        let failure = monitor.take_failure().unwrap();
+        assert!(failure.message.contains("no longer available"));
+        assert_eq!(monitor.take_failure(), None);
+        assert_eq!(monitor.xrun_count(), 1);
+    }
+
+    #[test]
+    fn test_stream_errors_beyond_capacity_dropped() {
+        let (reporter, monitor) = stream_monitor();
+        for _ in 0..ERROR_CAPACITY + 3 {
+            reporter.stream_died("gone");
+        }
+        assert_eq!(
+            std::iter::from_fn(|| monitor.take_failure()).count(),
+            ERROR_CAPACITY
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Shared thread budget for background maintenance.
+//!
+//! Audio analysis and fingerprint backfill do their per-file work through the
+//! `BackgroundExecutor`, so together they keep at most `max_threads` jobs
+//! running however many passes are going at once; jobs beyond the budget wait
+//! their turn. The budget comes from the `background_threads` setting and a
+//! change applies to the next job that starts.
+
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use tauri::State;
+
+/// Settings key for the most background jobs run at once
+pub const BACKGROUND_THREADS_SETTING: &str = "background_threads";
+
+/// Budget when the setting is unset: half the cores, leaving room for playback
+pub fn default_max_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| (n.get() / 2).max(1))
+}
+
+/// Budget and current load of the background executor
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct BackgroundStatus {
+    pub max_threads: usize,
+    /// Jobs running now
+    pub active: usize,
+    /// Jobs waiting for a free slot
+    pub queued: usize,
+}
+
+/// Runs background jobs within a shared thread budget, managed as Tauri state
+#[derive(Clone)]
+pub struct BackgroundExecutor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    status: Mutex<BackgroundStatus>,
+    slot_freed: Condvar,
+}
+
+/// A running job's place in the budget, given back when dropped
+struct Slot<'a>(&'a BackgroundExecutor);
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.inner.status.lock().active -= 1;
+        self.0.inner.slot_freed.notify_one();
+    }
+}
+
+impl BackgroundExecutor {
+    pub fn new(max_threads: usize) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                status: Mutex::new(BackgroundStatus {
+                    max_threads: max_threads.max(1),
+                    ..Default::default()
+                }),
+                slot_freed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Change the budget; running jobs finish, queued ones start as it allows
+    pub fn set_max_threads(&self, max_threads: usize) {
+        self.inner.status.lock().max_threads = max_threads.max(1);
+        self.inner.slot_freed.notify_all();
+    }
+
+    pub fn status(&self) -> BackgroundStatus {
+        *self.inner.status.lock()
+    }
+
+    /// Run `job` on the calling thread once the budget has a free slot
+    pub fn run<T>(&self, job: impl FnOnce() -> T) -> T {
+        self.enqueue(1);
+        let _slot = self.start_queued();
+        job()
+    }
+
+    /// Run `job` on a new thread once the budget has a free slot
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) -> JoinHandle<()> {
+        self.enqueue(1);
+        let executor = self.clone();
+        std::thread::spawn(move || {
+            let _slot = executor.start_queued();
+            job();
+        })
+    }
+
+    /// Map each item through `f` as its own job, in parallel within the budget
+    ///
+    /// Results come back in the order of `items`. Every item counts as queued
+    /// until it starts. Don't call this from inside a job: with a budget of
+    /// one it would wait on its own slot.
+    pub fn map<I, T, F>(&self, items: Vec<I>, f: F) -> Vec<T>
+    where
+        I: Send,
+        T: Send,
+        F: Fn(I) -> T + Sync,
+    {
+        let workers = self.status().max_threads.min(items.len());
+        self.enqueue(items.len());
+        if workers <= 1 {
+            return items
+                .into_iter()
+                .map(|item| {
+                    let _slot = self.start_queued();
+                    f(item)
+                })
+                .collect();
+        }
+
+        let pending = Mutex::new(items.into_iter().enumerate());
+        let mut results: Vec<(usize, T)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let next = pending.lock().next();
+                            let Some((index, item)) = next else {
+                                break;
+                            };
+                            let _slot = self.start_queued();
+                            done.push((index, f(item)));
+                        }
+                        done
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                })
+                .collect()
+        });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    fn enqueue(&self, jobs: usize) {
+        self.inner.status.lock().queued += jobs;
+    }
+
+    /// Wait for a free slot and move one queued job into it
+    fn start_queued(&self) -> Slot<'_> {
+        let mut status = self.inner.status.lock();
+        while status.active >= status.max_threads {
+            self.inner.slot_freed.wait(&mut status);
+        }
+        status.queued -= 1;
+        status.active += 1;
+        Slot(self)
+    }
+}
+
+/// Get the background thread budget and how many jobs are running or waiting
+#[tauri::command]
+pub fn background_status(executor: State<'_, BackgroundExecutor>) -> BackgroundStatus {
+    executor.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    /// Poll until `done` holds, failing after a few seconds
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_jobs_over_budget_queue() {
+        let executor = BackgroundExecutor::new(2);
+        let (release, gate) = mpsc::channel::<()>();
+        let gate = Arc::new(Mutex::new(gate));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let (gate, running, peak) = (gate.clone(), running.clone(), peak.clone());
+                executor.spawn(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    gate.lock().recv().unwrap();
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        wait_for(|| executor.status().active == 2);
+        let status = executor.status();
+        assert_eq!((status.active, status.queued), (2, 3));
+
+        for _ in 0..5 {
+            release.send(()).unwrap();
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let status = executor.status();
+        assert_eq!((status.active, status.queued), (0, 0));
+    }
+
+    #[test]
+    fn test_map_keeps_order_within_budget() {
+        let executor = BackgroundExecutor::new(3);
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let doubled = executor.map((0..40).collect(), |n: i32| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(1));
+            running.fetch_sub(1, Ordering::SeqCst);
+            n * 2
+        });
+
+        assert_eq!(doubled, (0..40).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert_eq!(executor.status().queued, 0);
+    }
+
+    #[test]
+    fn test_map_shares_budget_with_running_jobs() {
+        let executor = BackgroundExecutor::new(2);
+        let (release, gate) = mpsc::channel::<()>();
+        let blocker = executor.spawn(move || gate.recv().unwrap());
+        wait_for(|| executor.status().active == 1);
+
+        // Only the one slot left over is used while the other job runs
+        let peak = AtomicUsize::new(0);
+        let results = executor.map(vec![1, 2, 3, 4], |n| {
+            peak.fetch_max(executor.status().active, Ordering::SeqCst);
+            n
+        });
+        assert_eq!(results, [1, 2, 3, 4]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+
+        release.send(()).unwrap();
+        blocker.join().unwrap();
+    }
+
+    #[test]
+    fn test_raising_budget_starts_queued_jobs() {
+        let executor = BackgroundExecutor::new(1);
+        let (release, gate) = mpsc::channel::<()>();
+        let gate = Arc::new(Mutex::new(gate));
+        let handles: Vec<_> = (0..3)
+            .map(|_| {
+                let gate = gate.clone();
+                executor.spawn(move || gate.lock().recv().unwrap())
+            })
+            .collect();
+        wait_for(|| executor.status().active == 1 && executor.status().queued == 2);
+
+        executor.set_max_threads(3);
+        wait_for(|| executor.status().active == 3);
+
+        for _ in 0..3 {
+            release.send(()).unwrap();
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
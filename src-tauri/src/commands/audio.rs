@@ -1,9 +1,43 @@
-use crate::audio::{AudioEngine, PlaybackState, TrackInfo};
+use crate::audio::{
+    AudioEngine, AudioError, PlaybackBackend, PlaybackNotice, PlaybackNotifier, PlaybackState,
+    RelativeSeek, RodioBackend, StreamMonitor, StreamReporter, TrackInfo, DEFAULT_BUFFER_MS,
+    DEFAULT_FADE_MS, DEFAULT_SILENCE_THRESHOLD_DB, MAX_BUFFER_MS, MAX_FADE_MS, MIN_BUFFER_MS,
+};
+use crate::commands::lastfm;
+use crate::db::{bookmarks, library, settings, Bookmark, Database};
+use crate::events::{EventEmitter, PlaybackTrackEndedEvent};
+use crate::playback::PlaybackManager;
 use serde::{Deserialize, Serialize};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::{error, warn};
+
+/// Settings key for the persisted mute state
+const MUTED_SETTING: &str = "audio_muted";
+
+/// Settings key for the play/pause/stop fade length in milliseconds
+const FADE_SETTING: &str = "audio_fade_ms";
+
+/// Settings key for the output buffer length in milliseconds
+const BUFFER_SETTING: &str = "audio_buffer_ms";
+
+/// Settings key for whether leading and trailing silence is trimmed
+const TRIM_SILENCE_SETTING: &str = "audio_trim_silence";
+
+/// Settings key for the level in dBFS at or below which audio counts as silence
+const TRIM_SILENCE_DB_SETTING: &str = "audio_trim_silence_db";
+
+/// Settings key for how close to the end a forward relative seek moves on to
+/// the next track, in milliseconds (0 disables)
+const SEEK_ADVANCE_SETTING: &str = "audio_seek_advance_ms";
+
+/// Default for `SEEK_ADVANCE_SETTING`
+const DEFAULT_SEEK_ADVANCE_MS: u64 = 5000;
+
+/// Shortest gap between `audio://seek-preview` events during a drag
+const SEEK_PREVIEW_EMIT_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaybackStatus {
@@ -11,7 +45,24 @@ pub struct PlaybackStatus {
     pub duration_ms: u64,
     pub state: PlaybackState,
     pub volume: f32,
+    #[serde(default)]
+    pub muted: bool,
     pub track: Option<TrackInfo>,
+    /// Output underruns since the audio thread started
+    #[serde(default)]
+    pub xrun_count: u64,
+    /// Seek bar position being previewed by `audio_seek_preview`; unlike
+    /// `position_ms`, playback has not moved there yet
+    #[serde(default)]
+    pub preview_position_ms: Option<u64>,
+}
+
+/// Result of a relative seek
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelativeSeekResponse {
+    /// New position, or None when playback advanced to the next track instead
+    pub position_ms: Option<u64>,
+    pub advanced: bool,
 }
 
 enum AudioCommand {
@@ -20,8 +71,20 @@ enum AudioCommand {
     Pause(Sender<Result<(), String>>),
     Stop(Sender<Result<(), String>>),
     Seek(u64, Sender<Result<(), String>>),
+    SeekPreview(u64, Sender<Result<u64, String>>),
+    SeekCommit(u64, Sender<Result<(), String>>),
+    /// Seek only if the given library track is the one loaded
+    SeekInTrack(i64, u64, Sender<Result<(), String>>),
+    SeekRelative(i64, u64, Sender<Result<RelativeSeek, String>>),
+    NextChapter(Sender<Result<Option<u64>, String>>),
+    PrevChapter(Sender<Result<Option<u64>, String>>),
     SetVolume(f32, Sender<Result<(), String>>),
     GetVolume(Sender<f32>),
+    SetMuted(bool, Sender<Result<(), String>>),
+    GetMuted(Sender<bool>),
+    SetFadeMs(u64, Sender<Result<(), String>>),
+    SetBufferMs(u64, Sender<Result<(), String>>),
+    SetTrimSilence(bool, f32, Sender<Result<(), String>>),
     GetStatus(Sender<PlaybackStatus>),
 }
 
@@ -32,20 +95,43 @@ struct PlayCountState {
 
 struct ScrobbleState {
     track_id: Option<i64>,
+    /// Set once the track has been scrobbled; only reset when a new track loads
     threshold_reached: bool,
-    threshold_percent: f64,
+    /// Percentage of the track that must actually be listened to (25-100)
+    threshold_percent: u8,
+    /// Unix timestamp of when playback of the track started
+    started_at: i64,
 }
 
 pub struct AudioState {
     sender: Sender<AudioCommand>,
 }
 
+/// Creates the playback backend on the audio thread, given where to report stream faults
+type BackendFactory =
+    Box<dyn FnOnce(StreamReporter) -> Result<Box<dyn PlaybackBackend>, AudioError> + Send>;
+
 impl AudioState {
     pub fn new(app: AppHandle) -> Self {
+        let buffer_ms = load_buffer_ms(&app);
+        Self::with_backend(app, move |reporter| {
+            Ok(Box::new(RodioBackend::with_buffer_ms(buffer_ms, reporter)?))
+        })
+    }
+
+    /// Start the audio thread with a specific playback backend
+    ///
+    /// The backend is created on the audio thread itself, so it does not need
+    /// to be `Send` (output streams usually aren't).
+    pub fn with_backend<F>(app: AppHandle, make_backend: F) -> Self
+    where
+        F: FnOnce(StreamReporter) -> Result<Box<dyn PlaybackBackend>, AudioError> + Send + 'static,
+    {
         let (tx, rx) = mpsc::channel::<AudioCommand>();
+        let make_backend: BackendFactory = Box::new(make_backend);
 
         thread::spawn(move || {
-            audio_thread(rx, app);
+            audio_thread(rx, app, make_backend);
         });
 
         Self { sender: tx }
@@ -54,19 +140,72 @@ impl AudioState {
     fn send_command(&self, cmd: AudioCommand) {
         let _ = self.sender.send(cmd);
     }
+
+    /// Load a track on the audio thread (used by the playback orchestrator)
+    pub(crate) fn load(&self, path: String, track_id: Option<i64>) -> Result<TrackInfo, String> {
+        let (tx, rx) = mpsc::channel();
+        self.send_command(AudioCommand::Load(path, track_id, tx));
+        rx.recv().map_err(|_| "Channel closed".to_string())?
+    }
+
+    /// Start playback of the loaded track (used by the playback orchestrator)
+    pub(crate) fn play(&self) -> Result<(), String> {
+        let (tx, rx) = mpsc::channel();
+        self.send_command(AudioCommand::Play(tx));
+        rx.recv().map_err(|_| "Channel closed".to_string())?
+    }
+
+    /// Stop playback and unload the track (used by the playback orchestrator)
+    pub(crate) fn stop(&self) -> Result<(), String> {
+        let (tx, rx) = mpsc::channel();
+        self.send_command(AudioCommand::Stop(tx));
+        rx.recv().map_err(|_| "Channel closed".to_string())?
+    }
+
+    /// Current playback status, stopped with nothing loaded if the audio
+    /// thread is gone
+    pub(crate) fn status(&self) -> PlaybackStatus {
+        let (tx, rx) = mpsc::channel();
+        self.send_command(AudioCommand::GetStatus(tx));
+        rx.recv().unwrap_or(PlaybackStatus {
+            position_ms: 0,
+            duration_ms: 0,
+            state: PlaybackState::Stopped,
+            volume: 1.0,
+            muted: false,
+            track: None,
+            xrun_count: 0,
+            preview_position_ms: None,
+        })
+    }
 }
 
-fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
-    let mut engine = match AudioEngine::new() {
-        Ok(e) => e,
+fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle, make_backend: BackendFactory) {
+    let (reporter, mut stream_monitor) = crate::audio::stream_monitor();
+    let mut engine = match make_backend(reporter) {
+        Ok(backend) => AudioEngine::with_backend(backend),
         Err(e) => {
             eprintln!("Failed to create audio engine: {}", e);
             return;
         }
     };
+    engine.set_muted(load_muted(&app));
+    engine.set_fade_ms(load_fade_ms(&app));
+    let (trim_silence, trim_silence_db) = load_trim_silence(&app);
+    if let Err(e) = engine.set_trim_silence(trim_silence, trim_silence_db) {
+        warn!("Ignoring stored silence trimming: {}", e);
+    }
 
-    let mut last_finished = false;
-    let mut last_emit = std::time::Instant::now();
+    let mut last_emit = Instant::now();
+    // A preview not yet emitted, held back by SEEK_PREVIEW_EMIT_INTERVAL
+    let mut preview_pending = false;
+    let mut last_preview_emit: Option<Instant> = None;
+    let mut notifier = PlaybackNotifier::new();
+    // Set right after a load, which a play usually follows at once
+    let mut just_loaded = false;
+    let mut loaded_track_id: Option<i64> = None;
+    // Counts successful loads, so each track end can be told apart
+    let mut load_seq: u64 = 0;
     let mut play_count_state = PlayCountState {
         track_id: None,
         threshold_reached: false,
@@ -74,14 +213,28 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
     let mut scrobble_state = ScrobbleState {
         track_id: None,
         threshold_reached: false,
-        threshold_percent: 0.9, // Default 90%
+        threshold_percent: 90, // Default 90%
+        started_at: 0,
     };
 
     loop {
-        match rx.recv_timeout(Duration::from_millis(100)) {
+        // Poll faster while a fade is ramping so the gain steps stay small
+        let timeout = if engine.is_fading() { 5 } else { 100 };
+        match rx.recv_timeout(Duration::from_millis(timeout)) {
             Ok(cmd) => match cmd {
                 AudioCommand::Load(path, track_id, reply) => {
+                    let _ = app.emit_playback_state_changed(
+                        notifier.loading(&engine, Instant::now()),
+                    );
                     let result = engine.load(&path).map_err(|e| e.to_string());
+                    just_loaded = result.is_ok();
+                    loaded_track_id = track_id.filter(|_| result.is_ok());
+                    if result.is_ok() {
+                        load_seq += 1;
+                    }
+                    if let Some(track_id) = loaded_track_id {
+                        engine.set_chapters(load_chapter_starts(&app, track_id));
+                    }
 
                     // Reset play count state for new track
                     play_count_state.track_id = track_id;
@@ -90,8 +243,8 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                     // Reset scrobble state for new track
                     scrobble_state.track_id = track_id;
                     scrobble_state.threshold_reached = false;
-
-                    last_finished = false;
+                    scrobble_state.threshold_percent = load_scrobble_threshold(&app);
+                    scrobble_state.started_at = unix_now();
 
                     let _ = reply.send(result);
                 }
@@ -111,6 +264,42 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                     let result = engine.seek(pos).map_err(|e| e.to_string());
                     let _ = reply.send(result);
                 }
+                AudioCommand::SeekPreview(pos, reply) => {
+                    let result = engine.seek_preview(pos).map_err(|e| e.to_string());
+                    preview_pending = result.is_ok();
+                    let _ = reply.send(result);
+                }
+                AudioCommand::SeekCommit(pos, reply) => {
+                    let result = engine.seek_commit(pos).map_err(|e| e.to_string());
+                    preview_pending = false;
+                    let _ = reply.send(result);
+                }
+                AudioCommand::SeekInTrack(track_id, pos, reply) => {
+                    let result = if engine.get_current_track().is_some()
+                        && loaded_track_id == Some(track_id)
+                    {
+                        engine.seek(pos).map_err(|e| e.to_string())
+                    } else {
+                        Err("Bookmarked track is not loaded".to_string())
+                    };
+                    let _ = reply.send(result);
+                }
+                AudioCommand::SeekRelative(delta, advance_threshold, reply) => {
+                    let result = engine
+                        .seek_relative(delta, advance_threshold)
+                        .map_err(|e| e.to_string());
+                    // Advance through the same path as a track finishing
+                    if let Ok(RelativeSeek::Advance) = result {
+                        report_track_ended(&app, &engine, loaded_track_id, load_seq);
+                    }
+                    let _ = reply.send(result);
+                }
+                AudioCommand::NextChapter(reply) => {
+                    let _ = reply.send(engine.next_chapter().map_err(|e| e.to_string()));
+                }
+                AudioCommand::PrevChapter(reply) => {
+                    let _ = reply.send(engine.prev_chapter().map_err(|e| e.to_string()));
+                }
                 AudioCommand::SetVolume(vol, reply) => {
                     engine.set_volume(vol);
                     let _ = reply.send(Ok(()));
@@ -118,6 +307,27 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                 AudioCommand::GetVolume(reply) => {
                     let _ = reply.send(engine.get_volume());
                 }
+                AudioCommand::SetMuted(muted, reply) => {
+                    engine.set_muted(muted);
+                    let _ = reply.send(Ok(()));
+                }
+                AudioCommand::GetMuted(reply) => {
+                    let _ = reply.send(engine.is_muted());
+                }
+                AudioCommand::SetFadeMs(fade_ms, reply) => {
+                    engine.set_fade_ms(fade_ms);
+                    let _ = reply.send(Ok(()));
+                }
+                AudioCommand::SetBufferMs(buffer_ms, reply) => {
+                    let result = engine.set_buffer_ms(buffer_ms).map_err(|e| e.to_string());
+                    let _ = reply.send(result);
+                }
+                AudioCommand::SetTrimSilence(enabled, threshold_db, reply) => {
+                    let result = engine
+                        .set_trim_silence(enabled, threshold_db)
+                        .map_err(|e| e.to_string());
+                    let _ = reply.send(result);
+                }
                 AudioCommand::GetStatus(reply) => {
                     let progress = engine.get_progress();
                     let track = engine.get_current_track().cloned();
@@ -126,7 +336,10 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                         duration_ms: progress.duration_ms,
                         state: progress.state,
                         volume: engine.get_volume(),
+                        muted: engine.is_muted(),
                         track,
+                        xrun_count: stream_monitor.xrun_count(),
+                        preview_position_ms: progress.preview_position_ms,
                     };
                     let _ = reply.send(status);
                 }
@@ -135,13 +348,45 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
             Err(mpsc::RecvTimeoutError::Disconnected) => break,
         }
 
+        engine.tick_fade(Instant::now());
+        engine.update_listen_time();
+
         let is_playing = engine.get_state() == PlaybackState::Playing;
-        let is_finished = engine.is_finished();
+
+        report_stream_faults(&app, &mut stream_monitor);
+
+        // Previews arrive with every mouse move of a drag; pass on at most one
+        // per interval, and the latest once the interval is up
+        if preview_pending
+            && last_preview_emit.is_none_or(|at| at.elapsed() >= SEEK_PREVIEW_EMIT_INTERVAL)
+        {
+            let progress = engine.get_progress();
+            if progress.preview_position_ms.is_some() {
+                let _ = app.emit("audio://seek-preview", &progress);
+            }
+            preview_pending = false;
+            last_preview_emit = Some(Instant::now());
+        }
+
+        // Hold the paused state after a load back for one pass, so a load and
+        // play read as loading then playing rather than flashing paused
+        if !std::mem::take(&mut just_loaded) {
+            for notice in notifier.observe(&engine, Instant::now()) {
+                match notice {
+                    PlaybackNotice::StateChanged(event) => {
+                        let _ = app.emit_playback_state_changed(event);
+                    }
+                    PlaybackNotice::TrackEnded => {
+                        report_track_ended(&app, &engine, loaded_track_id, load_seq);
+                    }
+                }
+            }
+        }
 
         if is_playing && last_emit.elapsed() >= Duration::from_millis(250) {
             let progress = engine.get_progress();
             let _ = app.emit("audio://progress", &progress);
-            last_emit = std::time::Instant::now();
+            last_emit = Instant::now();
 
             // Check play count threshold (75%)
             if !play_count_state.threshold_reached
@@ -154,9 +399,6 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                         // Spawn async task to avoid blocking audio thread
                         let app_handle = app.clone();
                         std::thread::spawn(move || {
-                            use crate::db::Database;
-                            use crate::db::library;
-
                             let db = app_handle.state::<Database>();
                             if let Ok(conn) = db.conn() {
                                 let _ = library::update_play_count(&conn, track_id);
@@ -167,46 +409,155 @@ fn audio_thread(rx: Receiver<AudioCommand>, app: AppHandle) {
                     }
             }
 
-            // Check scrobble threshold (90% default, configurable)
+            // Check scrobble eligibility against time actually listened, so
+            // seeking forward cannot skip to the threshold. Seeking back does
+            // not reset threshold_reached, so a play is scrobbled only once.
             if !scrobble_state.threshold_reached
                && progress.duration_ms > 0
-               && scrobble_state.track_id.is_some() {
-                let ratio = progress.position_ms as f64 / progress.duration_ms as f64;
-
-                if ratio >= scrobble_state.threshold_percent
-                    && let Some(track_id) = scrobble_state.track_id {
-                        // Spawn async task to avoid blocking audio thread
-                        let app_handle = app.clone();
-                        std::thread::spawn(move || {
-                            use crate::commands::lastfm;
-                            use crate::db::Database;
-
-                            let db = app_handle.state::<Database>();
-                            if let Ok(conn) = db.conn() {
-                                // Queue scrobble from audio thread
-                                match lastfm::scrobble_from_audio_thread(&app_handle, &conn, track_id) {
-                                    Ok(_) => println!("[audio] Scrobble queued for track_id={}", track_id),
-                                    Err(e) => eprintln!("[audio] Failed to queue scrobble: {}", e),
-                                }
-                            }
-                        });
-                        scrobble_state.threshold_reached = true;
-                    }
+               && let Some(track_id) = scrobble_state.track_id {
+                let duration_secs = progress.duration_ms as f64 / 1000.0;
+                let listened_secs = engine.listened_ms() as f64 / 1000.0;
+
+                if lastfm::should_scrobble(duration_secs, listened_secs, scrobble_state.threshold_percent) {
+                    tauri::async_runtime::spawn(lastfm::scrobble_listened_track(
+                        app.clone(),
+                        track_id,
+                        scrobble_state.started_at,
+                        duration_secs as u32,
+                        listened_secs as u32,
+                    ));
+                    scrobble_state.threshold_reached = true;
+                }
             }
         }
+    }
+}
 
-        if is_finished && !last_finished {
-            let _ = app.emit("audio://track-ended", ());
-        }
-        last_finished = is_finished;
+/// Emit `playback:track-ended` for the loaded track and let the playback
+/// orchestrator move the queue on, or hand the end to the frontend
+///
+/// The orchestrator loads the next track through this thread, so it runs on
+/// its own thread rather than blocking the loop.
+fn report_track_ended(
+    app: &AppHandle,
+    engine: &AudioEngine,
+    track_id: Option<i64>,
+    load_seq: u64,
+) {
+    let _ = app.emit_playback_track_ended(PlaybackTrackEndedEvent {
+        track_id,
+        path: engine.get_current_track().map(|track| track.path.clone()),
+    });
+
+    let app = app.clone();
+    thread::spawn(move || crate::playback::handle_track_ended(&app, track_id, load_seq));
+}
+
+/// Emit throttled underrun warnings and fatal stream errors to the UI
+fn report_stream_faults(app: &AppHandle, monitor: &mut StreamMonitor) {
+    if let Some(warning) = monitor.poll_warning(Instant::now()) {
+        warn!(
+            "{} output underruns ({} total), consider a larger buffer",
+            warning.new_xruns, warning.xrun_count
+        );
+        let _ = app.emit("playback:stream-warning", &warning);
+    }
+    while let Some(failure) = monitor.take_failure() {
+        error!("Output stream error: {}", failure.message);
+        let _ = app.emit("playback:stream-error", &failure);
     }
 }
 
+/// Read the scrobble threshold setting, falling back to the 90% default
+fn load_scrobble_threshold(app: &AppHandle) -> u8 {
+    app.try_state::<Database>()
+        .and_then(|db| db.with_conn(lastfm::scrobble_threshold).ok())
+        .unwrap_or(90)
+}
+
+/// Read the persisted mute state, defaulting to unmuted
+fn load_muted(app: &AppHandle) -> bool {
+    app.try_state::<Database>()
+        .and_then(|db| db.with_conn(|conn| settings::get_setting(conn, MUTED_SETTING)).ok())
+        .flatten()
+        .is_some_and(|value| value == "1" || value == "true")
+}
+
+/// Read the chapter starts of a library track
+fn load_chapter_starts(app: &AppHandle, track_id: i64) -> Vec<u64> {
+    app.try_state::<Database>()
+        .and_then(|db| db.with_conn(|conn| library::get_track_chapters(conn, track_id)).ok())
+        .map(|chapters| chapters.into_iter().map(|c| c.start_ms).collect())
+        .unwrap_or_default()
+}
+
+/// Read the persisted fade length, defaulting to `DEFAULT_FADE_MS`
+fn load_fade_ms(app: &AppHandle) -> u64 {
+    app.try_state::<Database>()
+        .and_then(|db| db.with_conn(|conn| settings::get_setting(conn, FADE_SETTING)).ok())
+        .flatten()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_FADE_MS)
+}
+
+/// Read the persisted output buffer length, defaulting to `DEFAULT_BUFFER_MS`
+fn load_buffer_ms(app: &AppHandle) -> u64 {
+    app.try_state::<Database>()
+        .and_then(|db| db.with_conn(|conn| settings::get_setting(conn, BUFFER_SETTING)).ok())
+        .flatten()
+        .and_then(|value| value.parse().ok())
+        .filter(|ms| (MIN_BUFFER_MS..=MAX_BUFFER_MS).contains(ms))
+        .unwrap_or(DEFAULT_BUFFER_MS)
+}
+
+/// Read the persisted silence trimming switch and threshold
+///
+/// Trimming defaults to off at `DEFAULT_SILENCE_THRESHOLD_DB`.
+fn load_trim_silence(app: &AppHandle) -> (bool, f32) {
+    let get = |key: &str| {
+        app.try_state::<Database>()
+            .and_then(|db| db.with_conn(|conn| settings::get_setting(conn, key)).ok())
+            .flatten()
+    };
+    let enabled = get(TRIM_SILENCE_SETTING).is_some_and(|value| value == "1" || value == "true");
+    let threshold_db = get(TRIM_SILENCE_DB_SETTING)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SILENCE_THRESHOLD_DB);
+    (enabled, threshold_db)
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Load a file into the audio engine
+///
+/// With a library `track_id`, Last.fm is told it's now playing once the
+/// track has stayed loaded for a moment. The frontend then advances the
+/// queue itself when the track ends.
 #[tauri::command]
-pub fn audio_load(path: String, track_id: Option<i64>, state: State<AudioState>) -> Result<TrackInfo, String> {
+pub fn audio_load(
+    app: AppHandle,
+    path: String,
+    track_id: Option<i64>,
+    state: State<AudioState>,
+) -> Result<TrackInfo, String> {
+    // The frontend owns what plays next from here on
+    let playback = app.state::<PlaybackManager>();
+    let mut session = playback.lock();
     let (tx, rx) = mpsc::channel();
     state.send_command(AudioCommand::Load(path, track_id, tx));
-    rx.recv().map_err(|_| "Channel closed".to_string())?
+    let result = rx.recv().map_err(|_| "Channel closed".to_string())?;
+    session.clear();
+    drop(session);
+    let info = result?;
+    if let Some(track_id) = track_id {
+        crate::playback::commands::request_now_playing_for(&app, track_id, &info);
+    }
+    Ok(info)
 }
 
 #[tauri::command]
@@ -237,6 +588,101 @@ pub fn audio_seek(position_ms: u64, state: State<AudioState>) -> Result<(), Stri
     rx.recv().map_err(|_| "Channel closed".to_string())?
 }
 
+/// Preview a seek while the seek bar is dragged
+///
+/// Reports `position_ms` as `preview_position_ms` in the status and in
+/// throttled `audio://seek-preview` events, without repositioning playback.
+/// Returns the previewed position, clamped to the track.
+#[tauri::command]
+pub fn audio_seek_preview(position_ms: u64, state: State<AudioState>) -> Result<u64, String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SeekPreview(position_ms, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())?
+}
+
+/// Seek to where a seek bar drag was released, ending the preview
+#[tauri::command]
+pub fn audio_seek_commit(position_ms: u64, state: State<AudioState>) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SeekCommit(position_ms, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())?
+}
+
+/// Seek by an offset from the current position, clamped to the track
+///
+/// A forward seek past the end, or within the `audio_seek_advance_ms` setting
+/// of it, ends the track so playback moves on to the next queued track.
+#[tauri::command]
+pub fn audio_seek_relative(
+    delta_ms: i64,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<RelativeSeekResponse, String> {
+    let advance_threshold = db
+        .with_conn(|conn| settings::get_setting(conn, SEEK_ADVANCE_SETTING))
+        .ok()
+        .flatten()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_SEEK_ADVANCE_MS);
+
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SeekRelative(delta_ms, advance_threshold, tx));
+    let outcome = rx.recv().map_err(|_| "Channel closed".to_string())??;
+
+    Ok(match outcome {
+        RelativeSeek::Seeked(position_ms) => RelativeSeekResponse {
+            position_ms: Some(position_ms),
+            advanced: false,
+        },
+        RelativeSeek::Advance => RelativeSeekResponse {
+            position_ms: None,
+            advanced: true,
+        },
+    })
+}
+
+/// Seek to a bookmark in the currently loaded track
+#[tauri::command]
+pub fn audio_seek_to_bookmark(
+    bookmark_id: i64,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<Bookmark, String> {
+    let bookmark = db
+        .with_conn(|conn| bookmarks::get_bookmark(conn, bookmark_id))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Bookmark with id {} not found", bookmark_id))?;
+
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SeekInTrack(
+        bookmark.track_id,
+        bookmark.position_ms,
+        tx,
+    ));
+    rx.recv().map_err(|_| "Channel closed".to_string())??;
+    Ok(bookmark)
+}
+
+/// Seek to the next chapter of the current track
+///
+/// Returns the new position, or None when there is no later chapter.
+#[tauri::command]
+pub fn audio_next_chapter(state: State<AudioState>) -> Result<Option<u64>, String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::NextChapter(tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())?
+}
+
+/// Seek to the start of the current chapter, or the previous one near its start
+///
+/// Returns the new position, or None when the track has no chapters.
+#[tauri::command]
+pub fn audio_prev_chapter(state: State<AudioState>) -> Result<Option<u64>, String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::PrevChapter(tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())?
+}
+
 #[tauri::command]
 pub fn audio_set_volume(volume: f32, state: State<AudioState>) -> Result<(), String> {
     let (tx, rx) = mpsc::channel();
@@ -251,17 +697,100 @@ pub fn audio_get_volume(state: State<AudioState>) -> f32 {
     rx.recv().unwrap_or(1.0)
 }
 
+/// Mute or unmute output, keeping the volume level for when it is unmuted
 #[tauri::command]
-pub fn audio_get_status(state: State<AudioState>) -> PlaybackStatus {
+pub fn audio_set_muted(
+    muted: bool,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SetMuted(muted, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())??;
+
+    db.with_conn(|conn| settings::set_setting(conn, MUTED_SETTING, &serde_json::json!(muted)))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn audio_get_muted(state: State<AudioState>) -> bool {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::GetMuted(tx));
+    rx.recv().unwrap_or(false)
+}
+
+/// Set the fade applied on play, pause, and stop (0 disables it)
+///
+/// Values above `MAX_FADE_MS` are clamped. Returns the stored length.
+#[tauri::command]
+pub fn audio_set_fade_ms(
+    fade_ms: u64,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<u64, String> {
+    let fade_ms = fade_ms.min(MAX_FADE_MS);
     let (tx, rx) = mpsc::channel();
-    state.send_command(AudioCommand::GetStatus(tx));
-    rx.recv().unwrap_or(PlaybackStatus {
-        position_ms: 0,
-        duration_ms: 0,
-        state: PlaybackState::Stopped,
-        volume: 1.0,
-        track: None,
+    state.send_command(AudioCommand::SetFadeMs(fade_ms, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())??;
+
+    db.with_conn(|conn| settings::set_setting(conn, FADE_SETTING, &serde_json::json!(fade_ms)))
+        .map_err(|e| e.to_string())?;
+    Ok(fade_ms)
+}
+
+/// Set the output buffer length in milliseconds
+///
+/// A larger buffer means fewer dropouts on slow or busy machines, at the cost
+/// of controls taking longer to be heard; a smaller buffer is more responsive
+/// but underruns more easily. The output is rebuilt and the current track
+/// resumes where it was. Values outside `MIN_BUFFER_MS..=MAX_BUFFER_MS` are
+/// rejected and the previous buffer is kept. Returns the stored length.
+#[tauri::command]
+pub fn audio_set_buffer_ms(
+    buffer_ms: u64,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<u64, String> {
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SetBufferMs(buffer_ms, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())??;
+
+    db.with_conn(|conn| settings::set_setting(conn, BUFFER_SETTING, &serde_json::json!(buffer_ms)))
+        .map_err(|e| e.to_string())?;
+    Ok(buffer_ms)
+}
+
+/// Turn trimming of leading and trailing silence on or off
+///
+/// With trimming on, each track starts at its first audible frame and playback
+/// moves on once only silence is left. Only near-zero runs shorter than
+/// `MAX_TRIM_MS` are skipped. `threshold_db` defaults to
+/// `DEFAULT_SILENCE_THRESHOLD_DB`; values outside
+/// `MIN_SILENCE_THRESHOLD_DB..=MAX_SILENCE_THRESHOLD_DB` are rejected. Applies
+/// from the next track. Returns the stored threshold.
+#[tauri::command]
+pub fn audio_set_trim_silence(
+    enabled: bool,
+    threshold_db: Option<f32>,
+    state: State<AudioState>,
+    db: State<Database>,
+) -> Result<f32, String> {
+    let threshold_db = threshold_db.unwrap_or(DEFAULT_SILENCE_THRESHOLD_DB);
+    let (tx, rx) = mpsc::channel();
+    state.send_command(AudioCommand::SetTrimSilence(enabled, threshold_db, tx));
+    rx.recv().map_err(|_| "Channel closed".to_string())??;
+
+    db.with_conn(|conn| {
+        settings::set_setting(conn, TRIM_SILENCE_SETTING, &serde_json::json!(enabled))?;
+        settings::set_setting(conn, TRIM_SILENCE_DB_SETTING, &serde_json::json!(threshold_db))
     })
+    .map_err(|e| e.to_string())?;
+    Ok(threshold_db)
+}
+
+#[tauri::command]
+pub fn audio_get_status(state: State<AudioState>) -> PlaybackStatus {
+    state.status()
 }
 
 #[cfg(test)]
@@ -277,6 +806,9 @@ mod tests {
             duration_ms: 0,
             state: PlaybackState::Stopped,
             volume: 1.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
 
@@ -301,6 +833,9 @@ mod tests {
             duration_ms: 180000,
             state: PlaybackState::Playing,
             volume: 0.8,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: Some(track),
         };
 
@@ -317,6 +852,9 @@ mod tests {
             duration_ms: 200000,
             state: PlaybackState::Playing,
             volume: 0.75,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
 
@@ -325,6 +863,8 @@ mod tests {
         assert!(json.contains("\"duration_ms\":200000"));
         assert!(json.contains("\"state\":\"Playing\""));
         assert!(json.contains("\"volume\":0.75"));
+        assert!(json.contains("\"muted\":false"));
+        assert!(json.contains("\"xrun_count\":0"));
         assert!(json.contains("\"track\":null"));
     }
 
@@ -342,6 +882,9 @@ mod tests {
             duration_ms: 60000,
             state: PlaybackState::Paused,
             volume: 1.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: Some(track),
         };
 
@@ -365,6 +908,7 @@ mod tests {
         assert_eq!(status.duration_ms, 240000);
         assert_eq!(status.state, PlaybackState::Paused);
         assert_eq!(status.volume, 0.5);
+        assert!(!status.muted);
         assert!(status.track.is_none());
     }
 
@@ -375,6 +919,9 @@ mod tests {
             duration_ms: 10000,
             state: PlaybackState::Playing,
             volume: 0.9,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
 
@@ -395,6 +942,9 @@ mod tests {
                 duration_ms: 1000,
                 state,
                 volume: 1.0,
+                muted: false,
+                xrun_count: 0,
+                preview_position_ms: None,
                 track: None,
             };
             assert_eq!(status.state, state);
@@ -409,6 +959,9 @@ mod tests {
             duration_ms: 0,
             state: PlaybackState::Stopped,
             volume: 0.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
         assert_eq!(min_vol.volume, 0.0);
@@ -419,6 +972,9 @@ mod tests {
             duration_ms: 0,
             state: PlaybackState::Stopped,
             volume: 1.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
         assert_eq!(max_vol.volume, 1.0);
@@ -429,6 +985,9 @@ mod tests {
             duration_ms: 0,
             state: PlaybackState::Stopped,
             volume: 0.5,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
         assert_eq!(mid_vol.volume, 0.5);
@@ -441,6 +1000,9 @@ mod tests {
             duration_ms: 180000,
             state: PlaybackState::Stopped,
             volume: 1.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
 
@@ -454,6 +1016,9 @@ mod tests {
             duration_ms: 0,
             state: PlaybackState::Stopped,
             volume: 1.0,
+            muted: false,
+            xrun_count: 0,
+            preview_position_ms: None,
             track: None,
         };
 
@@ -482,12 +1047,39 @@ mod tests {
         let (tx, _rx) = mpsc::channel::<Result<(), String>>();
         let _seek = AudioCommand::Seek(1000, tx);
 
+        let (tx, _rx) = mpsc::channel::<Result<(), String>>();
+        let _seek_in_track = AudioCommand::SeekInTrack(7, 1000, tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<RelativeSeek, String>>();
+        let _seek_relative = AudioCommand::SeekRelative(-30_000, 5000, tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<Option<u64>, String>>();
+        let _next_chapter = AudioCommand::NextChapter(tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<Option<u64>, String>>();
+        let _prev_chapter = AudioCommand::PrevChapter(tx);
+
         let (tx, _rx) = mpsc::channel::<Result<(), String>>();
         let _set_vol = AudioCommand::SetVolume(0.5, tx);
 
         let (tx, _rx) = mpsc::channel::<f32>();
         let _get_vol = AudioCommand::GetVolume(tx);
 
+        let (tx, _rx) = mpsc::channel::<Result<(), String>>();
+        let _set_muted = AudioCommand::SetMuted(true, tx);
+
+        let (tx, _rx) = mpsc::channel::<bool>();
+        let _get_muted = AudioCommand::GetMuted(tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<(), String>>();
+        let _set_fade = AudioCommand::SetFadeMs(30, tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<(), String>>();
+        let _set_buffer = AudioCommand::SetBufferMs(100, tx);
+
+        let (tx, _rx) = mpsc::channel::<Result<(), String>>();
+        let _set_trim = AudioCommand::SetTrimSilence(true, -60.0, tx);
+
         let (tx, _rx) = mpsc::channel::<PlaybackStatus>();
         let _get_status = AudioCommand::GetStatus(tx);
     }
@@ -603,12 +1195,13 @@ mod tests {
         let state = ScrobbleState {
             track_id: None,
             threshold_reached: false,
-            threshold_percent: 0.9,
+            threshold_percent: 90,
+            started_at: 0,
         };
 
         assert!(state.track_id.is_none());
         assert!(!state.threshold_reached);
-        assert_eq!(state.threshold_percent, 0.9);
+        assert_eq!(state.threshold_percent, 90);
     }
 
     #[test]
@@ -616,10 +1209,12 @@ mod tests {
         let state = ScrobbleState {
             track_id: Some(789),
             threshold_reached: false,
-            threshold_percent: 0.5, // 50% threshold
+            threshold_percent: 50, // 50% threshold
+            started_at: 1704067200,
         };
 
-        assert_eq!(state.threshold_percent, 0.5);
+        assert_eq!(state.threshold_percent, 50);
+        assert_eq!(state.started_at, 1704067200);
     }
 
     #[test]
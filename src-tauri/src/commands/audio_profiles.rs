@@ -0,0 +1,168 @@
+//! Tauri commands for audio profile rules.
+//!
+//! Rules choose an EQ preset and normalization mode by folder, genre, or
+//! artist. Tracks matching no rule use the active output device's profile, or
+//! the global one. The playback orchestrator resolves the profile when loading
+//! a track.
+
+use tauri::State;
+
+use crate::db::{
+    AudioProfile, AudioProfileRule, Database, NormalizationMode, ProfileCondition, audio_profiles,
+};
+
+/// Response for listing audio profile rules
+#[derive(Clone, serde::Serialize)]
+pub struct AudioProfileListResponse {
+    /// Rules in evaluation order
+    pub rules: Vec<AudioProfileRule>,
+    /// Profile used when no rule matches
+    pub global: AudioProfile,
+}
+
+/// Profile for one output device
+#[derive(Clone, serde::Serialize)]
+pub struct DeviceProfileResponse {
+    pub device_id: String,
+    pub profile: AudioProfile,
+    /// False when the device has no profile of its own and gets the global one
+    pub saved: bool,
+}
+
+fn parse_device_id(device_id: &str) -> Result<&str, String> {
+    let device_id = device_id.trim();
+    if device_id.is_empty() {
+        return Err("Device id cannot be empty".to_string());
+    }
+    Ok(device_id)
+}
+
+fn parse_profile(eq_preset: String, normalization: &str) -> Result<AudioProfile, String> {
+    let eq_preset = eq_preset.trim().to_string();
+    if eq_preset.is_empty() {
+        return Err("EQ preset cannot be empty".to_string());
+    }
+    let normalization: NormalizationMode = normalization.parse().map_err(|_| {
+        format!(
+            "Invalid normalization '{}', expected off, track, or album",
+            normalization
+        )
+    })?;
+    Ok(AudioProfile {
+        eq_preset,
+        normalization,
+    })
+}
+
+/// List audio profile rules in evaluation order, with the global profile
+#[tauri::command]
+pub fn audio_profile_list(db: State<'_, Database>) -> Result<AudioProfileListResponse, String> {
+    db.with_conn(|conn| {
+        Ok(AudioProfileListResponse {
+            rules: audio_profiles::get_rules(conn)?,
+            global: audio_profiles::get_global_profile(conn)?,
+        })
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Create an audio profile rule, or update one when `rule_id` is given
+///
+/// `condition` is folder/genre/artist and `normalization` is off/track/album.
+/// The first matching rule in `position` order wins; new rules are appended
+/// unless a position is given.
+#[tauri::command]
+pub fn audio_profile_set_rule(
+    db: State<'_, Database>,
+    rule_id: Option<i64>,
+    condition: String,
+    value: String,
+    eq_preset: String,
+    normalization: String,
+    position: Option<i64>,
+) -> Result<AudioProfileRule, String> {
+    let condition: ProfileCondition = condition.parse().map_err(|_| {
+        format!(
+            "Invalid condition '{}', expected folder, genre, or artist",
+            condition
+        )
+    })?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("Rule value cannot be empty".to_string());
+    }
+    let profile = parse_profile(eq_preset, &normalization)?;
+
+    db.with_conn(|conn| {
+        audio_profiles::set_rule(conn, rule_id, condition, value, &profile, position)
+    })
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Rule with id {} not found", rule_id.unwrap_or_default()))
+}
+
+/// Delete an audio profile rule
+#[tauri::command]
+pub fn audio_profile_delete_rule(db: State<'_, Database>, rule_id: i64) -> Result<(), String> {
+    let deleted = db
+        .with_conn(|conn| audio_profiles::delete_rule(conn, rule_id))
+        .map_err(|e| e.to_string())?;
+    if !deleted {
+        return Err(format!("Rule with id {} not found", rule_id));
+    }
+    Ok(())
+}
+
+/// Set the profile used for tracks matching no rule
+#[tauri::command]
+pub fn audio_profile_set_global(
+    db: State<'_, Database>,
+    eq_preset: String,
+    normalization: String,
+) -> Result<AudioProfile, String> {
+    let profile = parse_profile(eq_preset, &normalization)?;
+    db.with_conn(|conn| audio_profiles::set_global_profile(conn, &profile))
+        .map_err(|e| e.to_string())?;
+    Ok(profile)
+}
+
+/// Get the profile for an output device, or the global one if it has none
+#[tauri::command]
+pub fn audio_get_device_profile(
+    db: State<'_, Database>,
+    device_id: String,
+) -> Result<DeviceProfileResponse, String> {
+    let device_id = parse_device_id(&device_id)?;
+    db.with_conn(|conn| {
+        let saved = audio_profiles::get_device_profile(conn, device_id)?;
+        Ok(DeviceProfileResponse {
+            device_id: device_id.to_string(),
+            saved: saved.is_some(),
+            profile: match saved {
+                Some(profile) => profile,
+                None => audio_profiles::get_global_profile(conn)?,
+            },
+        })
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Save the profile for an output device
+///
+/// It applies to tracks matching no rule while that device is the active one.
+#[tauri::command]
+pub fn audio_set_device_profile(
+    db: State<'_, Database>,
+    device_id: String,
+    eq_preset: String,
+    normalization: String,
+) -> Result<DeviceProfileResponse, String> {
+    let device_id = parse_device_id(&device_id)?;
+    let profile = parse_profile(eq_preset, &normalization)?;
+    db.with_conn(|conn| audio_profiles::set_device_profile(conn, device_id, &profile))
+        .map_err(|e| e.to_string())?;
+    Ok(DeviceProfileResponse {
+        device_id: device_id.to_string(),
+        profile,
+        saved: true,
+    })
+}
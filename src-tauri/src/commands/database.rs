@@ -0,0 +1,90 @@
+//! Tauri commands for database maintenance.
+//!
+//! Each command runs on a blocking worker with a dedicated connection so a
+//! long VACUUM neither freezes the UI nor starves the connection pool.
+
+use rusqlite::Connection;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+use crate::db::maintenance::{self, CheckpointMode, CheckpointResult, MaintenanceResult};
+use crate::db::relocate::{self, RelocateResult};
+use crate::db::{Database, DbResult};
+
+/// Run a maintenance operation on a dedicated connection off the async runtime
+async fn run_maintenance(
+    db: &Database,
+    op: fn(&Connection) -> DbResult<MaintenanceResult>,
+) -> Result<MaintenanceResult, String> {
+    let db = db.clone();
+    tauri::async_runtime::spawn_blocking(move || db.with_maintenance_conn(op))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Rebuild the database file to reclaim space left by deleted rows
+#[tauri::command]
+pub async fn db_vacuum(db: State<'_, Database>) -> Result<MaintenanceResult, String> {
+    run_maintenance(&db, maintenance::vacuum).await
+}
+
+/// Check the database for corruption
+#[tauri::command]
+pub async fn db_integrity_check(db: State<'_, Database>) -> Result<MaintenanceResult, String> {
+    run_maintenance(&db, maintenance::integrity_check).await
+}
+
+/// Refresh query planner statistics
+#[tauri::command]
+pub async fn db_optimize(db: State<'_, Database>) -> Result<MaintenanceResult, String> {
+    run_maintenance(&db, maintenance::optimize).await
+}
+
+/// Copy the WAL back into the database file
+///
+/// `mode` is PASSIVE, FULL, or TRUNCATE (the default). TRUNCATE also shrinks
+/// the `-wal` file to zero bytes, freeing the disk space a large scan used.
+#[tauri::command]
+pub async fn db_checkpoint(
+    db: State<'_, Database>,
+    mode: Option<String>,
+) -> Result<CheckpointResult, String> {
+    let mode = match mode {
+        Some(mode) => mode.parse::<CheckpointMode>().map_err(|e| e.to_string())?,
+        None => CheckpointMode::Truncate,
+    };
+    let db = db.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.with_maintenance_conn(|conn| maintenance::checkpoint(conn, mode))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Move the database file to `new_path` and use it from now on
+///
+/// Refuses if a file already exists there, unless `use_existing` is set, in
+/// which case the app switches to that database instead of copying. The
+/// location is remembered for the next launch. The frontend should reload
+/// its data afterwards.
+#[tauri::command]
+pub async fn db_relocate(
+    app: AppHandle,
+    db: State<'_, Database>,
+    new_path: String,
+    use_existing: Option<bool>,
+) -> Result<RelocateResult, String> {
+    let data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let new_path = PathBuf::from(new_path);
+    let db = db.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.relocate(&new_path, use_existing.unwrap_or(false), |path| {
+            relocate::save_db_location(&data_dir, path)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,257 @@
+//! Structured errors returned across the command boundary.
+//!
+//! Commands used to return plain strings, leaving the frontend to guess what
+//! went wrong from the wording. `CommandError` pairs the human-readable
+//! message with a stable `code` the UI can branch on, e.g. retrying on
+//! `db_busy` or offering to locate a file on `not_found`. Internal errors map
+//! to codes through the `From` impls below; plain strings from helpers that
+//! still return `Result<_, String>` become `internal`.
+
+use std::fmt;
+use std::io;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::audio::AudioError;
+use crate::db::DbError;
+use crate::scanner::ScanError;
+
+/// An error a command returns to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandError {
+    /// Stable machine-readable code, one of the constants below
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl CommandError {
+    /// The track, playlist, file, or other record doesn't exist
+    pub const NOT_FOUND: &str = "not_found";
+    /// The arguments were rejected; retrying as-is won't help
+    pub const INVALID_INPUT: &str = "invalid_input";
+    /// The change clashes with existing data, e.g. a duplicate path
+    pub const CONFLICT: &str = "conflict";
+    pub const PERMISSION_DENIED: &str = "permission_denied";
+    /// The database was locked or the pool had no free connection; transient
+    pub const DB_BUSY: &str = "db_busy";
+    pub const DB_ERROR: &str = "db_error";
+    pub const IO_ERROR: &str = "io_error";
+    pub const METADATA_ERROR: &str = "metadata_error";
+    pub const UNSUPPORTED_FORMAT: &str = "unsupported_format";
+    pub const DECODE_ERROR: &str = "decode_error";
+    pub const NO_TRACK: &str = "no_track";
+    pub const PLAYBACK_ERROR: &str = "playback_error";
+    pub const INTERNAL: &str = "internal";
+
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(Self::NOT_FOUND, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_INPUT, message)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    /// Prefix the message with what was being done, keeping the code
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    /// Whether the same call may succeed if retried shortly
+    pub fn is_transient(&self) -> bool {
+        self.code == Self::DB_BUSY
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(Self::INTERNAL, message)
+    }
+}
+
+impl From<io::Error> for CommandError {
+    fn from(error: io::Error) -> Self {
+        let code = match error.kind() {
+            io::ErrorKind::NotFound => Self::NOT_FOUND,
+            io::ErrorKind::PermissionDenied => Self::PERMISSION_DENIED,
+            _ => Self::IO_ERROR,
+        };
+        let kind = format!("{:?}", error.kind());
+        Self::new(code, error.to_string()).with_details(json!({ "kind": kind }))
+    }
+}
+
+impl From<rusqlite::Error> for CommandError {
+    fn from(error: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+
+        if let rusqlite::Error::QueryReturnedNoRows = error {
+            return Self::not_found(error.to_string());
+        }
+        let Some(sqlite_code) = error.sqlite_error_code() else {
+            return Self::new(Self::DB_ERROR, error.to_string());
+        };
+        let code = match sqlite_code {
+            ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => Self::DB_BUSY,
+            ErrorCode::ConstraintViolation => Self::CONFLICT,
+            ErrorCode::ReadOnly | ErrorCode::PermissionDenied => Self::PERMISSION_DENIED,
+            _ => Self::DB_ERROR,
+        };
+        Self::new(code, error.to_string())
+            .with_details(json!({ "sqlite_code": format!("{:?}", sqlite_code) }))
+    }
+}
+
+impl From<DbError> for CommandError {
+    fn from(error: DbError) -> Self {
+        match error {
+            DbError::Sqlite(e) => e.into(),
+            // The pool only fails when no connection frees up in time
+            DbError::Pool(e) => Self::new(Self::DB_BUSY, e.to_string()),
+            DbError::NotFound(_) => Self::not_found(error.to_string()),
+            DbError::Constraint(_) => Self::invalid_input(error.to_string()),
+            DbError::Io(e) => e.into(),
+        }
+    }
+}
+
+impl From<ScanError> for CommandError {
+    fn from(error: ScanError) -> Self {
+        match error {
+            ScanError::Io(e) => e.into(),
+            ScanError::Metadata(_) => Self::new(Self::METADATA_ERROR, error.to_string()),
+            ScanError::Database(_) => Self::new(Self::DB_ERROR, error.to_string()),
+            ScanError::PathNotFound(_) => Self::not_found(error.to_string()),
+        }
+    }
+}
+
+impl From<AudioError> for CommandError {
+    fn from(error: AudioError) -> Self {
+        let code = match error {
+            AudioError::FileOpen(_) => Self::IO_ERROR,
+            AudioError::UnsupportedFormat(_) => Self::UNSUPPORTED_FORMAT,
+            AudioError::Decode(_) => Self::DECODE_ERROR,
+            AudioError::NoTrack => Self::NO_TRACK,
+            AudioError::InvalidBufferSize(_) | AudioError::InvalidSilenceThreshold(_) => {
+                Self::INVALID_INPUT
+            }
+            AudioError::Playback(_) | AudioError::Seek(_) | AudioError::Stream(_) => {
+                Self::PLAYBACK_ERROR
+            }
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        Self::new(Self::INTERNAL, error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new(Self::INTERNAL, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sqlite_failure(code: i32) -> DbError {
+        DbError::Sqlite(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(code),
+            None,
+        ))
+    }
+
+    #[test]
+    fn test_db_errors_map_to_codes() {
+        let busy = CommandError::from(sqlite_failure(rusqlite::ffi::SQLITE_BUSY));
+        assert_eq!(busy.code, CommandError::DB_BUSY);
+        assert!(busy.is_transient());
+        assert_eq!(busy.details, Some(json!({ "sqlite_code": "DatabaseBusy" })));
+
+        let locked = CommandError::from(sqlite_failure(rusqlite::ffi::SQLITE_LOCKED));
+        assert_eq!(locked.code, CommandError::DB_BUSY);
+
+        let unique = CommandError::from(sqlite_failure(rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE));
+        assert_eq!(unique.code, CommandError::CONFLICT);
+        assert!(!unique.is_transient());
+
+        let no_rows = CommandError::from(DbError::Sqlite(rusqlite::Error::QueryReturnedNoRows));
+        assert_eq!(no_rows.code, CommandError::NOT_FOUND);
+
+        let missing = CommandError::from(DbError::NotFound("track 7".to_string()));
+        assert_eq!(missing.code, CommandError::NOT_FOUND);
+        assert_eq!(missing.message, "Record not found: track 7");
+
+        let invalid = CommandError::from(DbError::Constraint("bad loop mode".to_string()));
+        assert_eq!(invalid.code, CommandError::INVALID_INPUT);
+    }
+
+    #[test]
+    fn test_io_scan_and_audio_errors_map_to_codes() {
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "read-only volume");
+        assert_eq!(
+            CommandError::from(denied).code,
+            CommandError::PERMISSION_DENIED
+        );
+
+        let gone = ScanError::Io(io::Error::new(io::ErrorKind::NotFound, "gone"));
+        assert_eq!(CommandError::from(gone).code, CommandError::NOT_FOUND);
+        let path = ScanError::PathNotFound("/music/old".to_string());
+        assert_eq!(CommandError::from(path).code, CommandError::NOT_FOUND);
+        let tags = ScanError::Metadata("bad frame".to_string());
+        assert_eq!(CommandError::from(tags).code, CommandError::METADATA_ERROR);
+
+        let format = AudioError::UnsupportedFormat("dsf".to_string());
+        assert_eq!(
+            CommandError::from(format).code,
+            CommandError::UNSUPPORTED_FORMAT
+        );
+        assert_eq!(
+            CommandError::from(AudioError::NoTrack).code,
+            CommandError::NO_TRACK
+        );
+        let buffer = AudioError::InvalidBufferSize(5);
+        assert_eq!(CommandError::from(buffer).code, CommandError::INVALID_INPUT);
+    }
+
+    #[test]
+    fn test_serializes_for_the_frontend() {
+        let error = CommandError::not_found("Track with id 3 not found");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "code": "not_found", "message": "Track with id 3 not found" })
+        );
+
+        let error = CommandError::from("Scan failed".to_string()).context("Rescan");
+        assert_eq!(error.code, CommandError::INTERNAL);
+        assert_eq!(error.message, "Rescan: Scan failed");
+    }
+}
@@ -3,10 +3,17 @@
 //! These commands expose favorites operations to the frontend,
 //! replacing the Python FastAPI favorites routes.
 
+use std::collections::HashMap;
+
 use tauri::{AppHandle, State};
 
-use crate::db::{favorites, library, Database, FavoriteTrack, PaginatedResult, Track};
-use crate::events::{EventEmitter, FavoritesUpdatedEvent};
+use rusqlite::Connection;
+
+use crate::db::{
+    favorites, library, Database, FavoriteList, FavoriteTrack, PaginatedResult, PlayedTrack,
+    Track,
+};
+use crate::events::{EventEmitter, FavoriteListsUpdatedEvent, FavoritesUpdatedEvent};
 
 /// Response for favorites get operations with pagination
 #[derive(Clone, serde::Serialize)]
@@ -44,19 +51,64 @@ pub struct RecentTracksResponse {
     pub days: i64,
 }
 
+/// Response for recently played tracks, one entry per play
+#[derive(Clone, serde::Serialize)]
+pub struct RecentlyPlayedResponse {
+    pub tracks: Vec<PlayedTrack>,
+    pub days: i64,
+}
+
+/// Format used for `play_history.played_at`
+const PLAYED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Most tracks `favorites_get_top25` returns
+const TOP_PLAYED_MAX_LIMIT: i64 = 500;
+
+/// Number of top played tracks to return, 25 unless asked otherwise
+fn top_played_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(25).clamp(1, TOP_PLAYED_MAX_LIMIT)
+}
+
+/// Normalize a `since` timestamp (RFC 3339 or `YYYY-MM-DD HH:MM:SS` UTC) to
+/// the stored `played_at` format
+fn parse_since(since: &str) -> Result<String, String> {
+    let utc = chrono::DateTime::parse_from_rfc3339(since)
+        .map(|dt| dt.with_timezone(&chrono::Utc).naive_utc())
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(since, PLAYED_AT_FORMAT))
+        .map_err(|_| format!("Invalid since timestamp: {}", since))?;
+    Ok(utc.format(PLAYED_AT_FORMAT).to_string())
+}
+
+/// Response for favorite lists
+#[derive(Clone, serde::Serialize)]
+pub struct FavoriteListsResponse {
+    pub lists: Vec<FavoriteList>,
+}
+
+/// Resolve an optional list ID to an existing list, defaulting to Liked Songs
+fn resolve_list_id(conn: &Connection, list_id: Option<i64>) -> Result<i64, String> {
+    let list_id = list_id.unwrap_or(favorites::DEFAULT_FAVORITE_LIST_ID);
+    if !favorites::favorite_list_exists(conn, list_id).map_err(|e| e.to_string())? {
+        return Err(format!("Favorite list with id {} not found", list_id));
+    }
+    Ok(list_id)
+}
+
 /// Get favorited tracks (Liked Songs) with pagination
 #[tauri::command]
 pub fn favorites_get(
     db: State<'_, Database>,
     limit: Option<i64>,
     offset: Option<i64>,
+    list_id: Option<i64>,
 ) -> Result<FavoritesResponse, String> {
     let limit = limit.unwrap_or(100).clamp(1, 1000);
     let offset = offset.unwrap_or(0).max(0);
 
     let conn = db.conn().map_err(|e| e.to_string())?;
+    let list_id = resolve_list_id(&conn, list_id)?;
     let result: PaginatedResult<FavoriteTrack> =
-        favorites::get_favorites(&conn, limit, offset).map_err(|e| e.to_string())?;
+        favorites::get_favorites(&conn, list_id, limit, offset).map_err(|e| e.to_string())?;
 
     Ok(FavoritesResponse {
         tracks: result.items,
@@ -68,10 +120,15 @@ pub fn favorites_get(
 
 /// Check if a track is favorited
 #[tauri::command]
-pub fn favorites_check(db: State<'_, Database>, track_id: i64) -> Result<FavoriteCheckResponse, String> {
+pub fn favorites_check(
+    db: State<'_, Database>,
+    track_id: i64,
+    list_id: Option<i64>,
+) -> Result<FavoriteCheckResponse, String> {
     let conn = db.conn().map_err(|e| e.to_string())?;
+    let list_id = resolve_list_id(&conn, list_id)?;
     let (is_favorite, favorited_date) =
-        favorites::is_favorite(&conn, track_id).map_err(|e| e.to_string())?;
+        favorites::is_favorite(&conn, list_id, track_id).map_err(|e| e.to_string())?;
 
     Ok(FavoriteCheckResponse {
         is_favorite,
@@ -85,8 +142,10 @@ pub fn favorites_add(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
+    list_id: Option<i64>,
 ) -> Result<FavoriteAddResponse, String> {
     let conn = db.conn().map_err(|e| e.to_string())?;
+    let list_id = resolve_list_id(&conn, list_id)?;
 
     // Check track exists
     let track = library::get_track_by_id(&conn, track_id).map_err(|e| e.to_string())?;
@@ -95,14 +154,15 @@ pub fn favorites_add(
     }
 
     // Add to favorites
-    let favorited_date = favorites::add_favorite(&conn, track_id).map_err(|e| e.to_string())?;
+    let favorited_date =
+        favorites::add_favorite(&conn, list_id, track_id).map_err(|e| e.to_string())?;
 
     if favorited_date.is_none() {
         return Err("Track is already favorited".to_string());
     }
 
     // Emit favorites updated event
-    let _ = app.emit_favorites_updated(FavoritesUpdatedEvent::added(track_id));
+    let _ = app.emit_favorites_updated(FavoritesUpdatedEvent::added(track_id).in_list(list_id));
 
     Ok(FavoriteAddResponse {
         success: true,
@@ -116,43 +176,122 @@ pub fn favorites_remove(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
+    list_id: Option<i64>,
 ) -> Result<(), String> {
     let conn = db.conn().map_err(|e| e.to_string())?;
-    let removed = favorites::remove_favorite(&conn, track_id).map_err(|e| e.to_string())?;
+    let list_id = resolve_list_id(&conn, list_id)?;
+    let removed =
+        favorites::remove_favorite(&conn, list_id, track_id).map_err(|e| e.to_string())?;
 
     if !removed {
         return Err(format!("Track with id {} not in favorites", track_id));
     }
 
     // Emit favorites updated event
-    let _ = app.emit_favorites_updated(FavoritesUpdatedEvent::removed(track_id));
+    let _ = app.emit_favorites_updated(FavoritesUpdatedEvent::removed(track_id).in_list(list_id));
 
     Ok(())
 }
 
-/// Get top 25 most played tracks
+/// Add several tracks to favorites at once, e.g. a whole album
+///
+/// Runs in one transaction: an unknown track fails the batch and nothing is
+/// added. Tracks already favorited are skipped. Emits a single
+/// `favorites:updated` event listing the added tracks, and returns them.
+#[tauri::command]
+pub fn favorites_add_many(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_ids: Vec<i64>,
+    list_id: Option<i64>,
+) -> Result<Vec<i64>, String> {
+    let list_id = resolve_list_id(&db.conn().map_err(|e| e.to_string())?, list_id)?;
+    let added = db
+        .transaction(|conn| favorites::add_favorites(conn, list_id, &track_ids))
+        .map_err(|e| e.to_string())?;
+
+    if let Some(event) = FavoritesUpdatedEvent::added_many(added.clone()) {
+        let _ = app.emit_favorites_updated(event.in_list(list_id));
+    }
+    Ok(added)
+}
+
+/// Remove several tracks from favorites at once
+///
+/// Tracks not in the list are skipped. Emits a single `favorites:updated`
+/// event listing the removed tracks, and returns them.
+#[tauri::command]
+pub fn favorites_remove_many(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_ids: Vec<i64>,
+    list_id: Option<i64>,
+) -> Result<Vec<i64>, String> {
+    let list_id = resolve_list_id(&db.conn().map_err(|e| e.to_string())?, list_id)?;
+    let removed = db
+        .transaction(|conn| favorites::remove_favorites(conn, list_id, &track_ids))
+        .map_err(|e| e.to_string())?;
+
+    if let Some(event) = FavoritesUpdatedEvent::removed_many(removed.clone()) {
+        let _ = app.emit_favorites_updated(event.in_list(list_id));
+    }
+    Ok(removed)
+}
+
+/// Check which of several tracks are favorited, keyed by track ID
+#[tauri::command]
+pub fn favorites_check_many(
+    db: State<'_, Database>,
+    track_ids: Vec<i64>,
+    list_id: Option<i64>,
+) -> Result<HashMap<i64, bool>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let list_id = resolve_list_id(&conn, list_id)?;
+    favorites::check_favorites(&conn, list_id, &track_ids).map_err(|e| e.to_string())
+}
+
+/// Get the most played tracks, optionally limited to one favorite list
+///
+/// Returns 25 tracks unless `limit` asks for another count, up to
+/// `TOP_PLAYED_MAX_LIMIT`.
 #[tauri::command]
-pub fn favorites_get_top25(db: State<'_, Database>) -> Result<TracksResponse, String> {
+pub fn favorites_get_top25(
+    db: State<'_, Database>,
+    list_id: Option<i64>,
+    limit: Option<i64>,
+) -> Result<TracksResponse, String> {
     let conn = db.conn().map_err(|e| e.to_string())?;
-    let tracks = favorites::get_top_25(&conn).map_err(|e| e.to_string())?;
+    let tracks = favorites::get_top_played(&conn, top_played_limit(limit), list_id)
+        .map_err(|e| e.to_string())?;
 
     Ok(TracksResponse { tracks })
 }
 
-/// Get tracks played within the last N days
+/// Get recent plays within the last N days, or since an explicit timestamp
+///
+/// `since` takes precedence over `days` when given.
 #[tauri::command]
 pub fn favorites_get_recently_played(
     db: State<'_, Database>,
     days: Option<i64>,
     limit: Option<i64>,
-) -> Result<RecentTracksResponse, String> {
+    list_id: Option<i64>,
+    since: Option<String>,
+) -> Result<RecentlyPlayedResponse, String> {
     let days = days.unwrap_or(14).clamp(1, 365);
     let limit = limit.unwrap_or(100).clamp(1, 1000);
+    let since = match since {
+        Some(since) => parse_since(&since)?,
+        None => (chrono::Utc::now() - chrono::Duration::days(days))
+            .format(PLAYED_AT_FORMAT)
+            .to_string(),
+    };
 
     let conn = db.conn().map_err(|e| e.to_string())?;
-    let tracks = favorites::get_recently_played(&conn, days, limit).map_err(|e| e.to_string())?;
+    let tracks =
+        favorites::get_recently_played(&conn, &since, limit, list_id).map_err(|e| e.to_string())?;
 
-    Ok(RecentTracksResponse { tracks, days })
+    Ok(RecentlyPlayedResponse { tracks, days })
 }
 
 /// Get tracks added within the last N days
@@ -161,16 +300,68 @@ pub fn favorites_get_recently_added(
     db: State<'_, Database>,
     days: Option<i64>,
     limit: Option<i64>,
+    list_id: Option<i64>,
 ) -> Result<RecentTracksResponse, String> {
     let days = days.unwrap_or(14).clamp(1, 365);
     let limit = limit.unwrap_or(100).clamp(1, 1000);
 
     let conn = db.conn().map_err(|e| e.to_string())?;
-    let tracks = favorites::get_recently_added(&conn, days, limit).map_err(|e| e.to_string())?;
+    let tracks =
+        favorites::get_recently_added(&conn, days, limit, list_id).map_err(|e| e.to_string())?;
 
     Ok(RecentTracksResponse { tracks, days })
 }
 
+/// Get all favorite lists with their track counts
+#[tauri::command]
+pub fn favorites_lists(db: State<'_, Database>) -> Result<FavoriteListsResponse, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let lists = favorites::get_favorite_lists(&conn).map_err(|e| e.to_string())?;
+
+    Ok(FavoriteListsResponse { lists })
+}
+
+/// Create a new named favorite list
+#[tauri::command]
+pub fn favorites_create_list(
+    app: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+) -> Result<FavoriteList, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Favorite list name cannot be empty".to_string());
+    }
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let list = favorites::create_favorite_list(&conn, name)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Favorite list '{}' already exists", name))?;
+
+    let _ = app.emit_favorite_lists_updated(FavoriteListsUpdatedEvent::created(list.id));
+
+    Ok(list)
+}
+
+/// Delete a favorite list and its entries
+#[tauri::command]
+pub fn favorites_delete_list(
+    app: AppHandle,
+    db: State<'_, Database>,
+    list_id: i64,
+) -> Result<(), String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let deleted = favorites::delete_favorite_list(&conn, list_id).map_err(|e| e.to_string())?;
+
+    if !deleted {
+        return Err(format!("Favorite list with id {} not found", list_id));
+    }
+
+    let _ = app.emit_favorite_lists_updated(FavoriteListsUpdatedEvent::deleted(list_id));
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,4 +402,25 @@ mod tests {
         let json = serde_json::to_string(&response).unwrap();
         assert!(json.contains("\"success\":true"));
     }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(
+            parse_since("2026-03-01T12:30:00+02:00").unwrap(),
+            "2026-03-01 10:30:00"
+        );
+        assert_eq!(
+            parse_since("2026-03-01 12:30:00").unwrap(),
+            "2026-03-01 12:30:00"
+        );
+        assert!(parse_since("last tuesday").is_err());
+    }
+
+    #[test]
+    fn test_top_played_limit() {
+        assert_eq!(top_played_limit(None), 25);
+        assert_eq!(top_played_limit(Some(100)), 100);
+        assert_eq!(top_played_limit(Some(5000)), TOP_PLAYED_MAX_LIMIT);
+        assert_eq!(top_played_limit(Some(0)), 1);
+    }
 }
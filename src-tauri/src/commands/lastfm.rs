@@ -2,15 +2,18 @@
 //!
 //! Provides OAuth authentication, scrobbling, now playing updates, and loved tracks import.
 
-use crate::db::{favorites, library, scrobble, settings, Database};
+use crate::db::{favorites, library, queue, scrobble, settings, Database, Track};
 use crate::events::{LastfmAuthEvent, ScrobbleStatusEvent};
+use crate::export::listening_log::{self, LogEntry};
 use crate::lastfm::{
-    AuthCallbackResponse, AuthUrlResponse, DisconnectResponse, ImportLovedTracksResponse,
-    LastFmClient, LastfmSettings, LastfmSettingsUpdate, NowPlayingRequest, QueueRetryResponse,
-    QueueStatusResponse, ScrobbleRequest, ScrobbleResponse,
+    AuthCallbackResponse, AuthUrlResponse, ConnectionTestResponse, DisconnectResponse,
+    ImportLovedTracksResponse, LastFmClient, LastFmError, LastfmSettings, LastfmSettingsUpdate,
+    NowPlayingRequest, QueueRetryResponse, QueueStatusResponse, RateLimitStatus, RateLimiter,
+    ScrobbleRequest, ScrobbleResponse,
 };
 use serde_json::json;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::{debug, error, info, warn};
 
 /// Helper to check if a setting is truthy
 fn is_setting_truthy(value: Option<String>) -> bool {
@@ -182,6 +185,37 @@ pub fn lastfm_disconnect(
     })
 }
 
+/// Check Last.fm can be reached and the stored session is still accepted
+///
+/// Makes one signed `user.getInfo` call, held back by the shared rate
+/// limiter like any other request. Failures come back as a response whose
+/// `status` tells network, auth, and other API errors apart, rather than as
+/// an `Err`.
+#[tauri::command]
+pub async fn lastfm_test_connection(
+    db: State<'_, Database>,
+) -> Result<ConnectionTestResponse, String> {
+    let client = LastFmClient::new();
+    if !client.is_configured() {
+        return Ok(ConnectionTestResponse::from_error(&LastFmError::NotConfigured));
+    }
+
+    let session_key = db
+        .with_conn(|conn| settings::get_setting(conn, "lastfm_session_key"))
+        .map_err(|e: crate::db::DbError| format!("Database error: {}", e))?;
+    let Some(session_key) = session_key.filter(|key| !key.is_empty()) else {
+        return Ok(ConnectionTestResponse::not_authenticated());
+    };
+
+    match client.get_user_info(&session_key).await {
+        Ok(username) => Ok(ConnectionTestResponse::ok(username)),
+        Err(e) => {
+            warn!("Last.fm connection test failed: {}", e);
+            Ok(ConnectionTestResponse::from_error(&e))
+        }
+    }
+}
+
 // ============================================
 // Scrobbling Commands
 // ============================================
@@ -192,7 +226,7 @@ pub fn lastfm_disconnect(
 /// 1. played_time >= 30 seconds (absolute minimum)
 /// 2. fraction_played >= threshold (percentage requirement)
 /// 3. played_time >= min(duration * threshold, 240 seconds) (4-minute max cap)
-fn should_scrobble(duration: f64, played_time: f64, threshold_percent: u8) -> bool {
+pub(crate) fn should_scrobble(duration: f64, played_time: f64, threshold_percent: u8) -> bool {
     if duration <= 0.0 {
         return false;
     }
@@ -251,7 +285,7 @@ pub async fn lastfm_now_playing(
         Ok(_) => Ok(json!({ "status": "success" })),
         Err(e) => {
             // Now Playing updates are not critical, just log and return success
-            eprintln!("[lastfm] Now Playing update failed: {}", e);
+            warn!("Now Playing update failed: {}", e);
             Ok(json!({ "status": "error", "message": e.to_string() }))
         }
     }
@@ -380,67 +414,96 @@ fn queue_scrobble_for_retry(
     Ok(())
 }
 
-/// Scrobble a track from the audio thread (synchronous, called at 90% threshold)
-///
-/// This function is called by the audio thread when playback reaches the scrobble
-/// threshold. It queues the scrobble for immediate processing by the retry mechanism.
-pub(crate) fn scrobble_from_audio_thread(
-    app: &AppHandle,
-    conn: &rusqlite::Connection,
-    track_id: i64,
-) -> Result<(), String> {
-    // Check if scrobbling is enabled
-    let enabled = is_setting_truthy(settings::get_setting(conn, "lastfm_scrobbling_enabled")
-        .map_err(|e| format!("Database error: {}", e))?);
-
-    if !enabled {
-        return Ok(());
-    }
+/// Read the configured scrobble threshold percentage (25-100, default 90)
+pub(crate) fn scrobble_threshold(conn: &rusqlite::Connection) -> Result<u8, crate::db::DbError> {
+    Ok(parse_threshold(settings::get_setting(conn, "lastfm_scrobble_threshold")?, 90))
+}
 
-    // Check if authenticated
-    let session_key = settings::get_setting(conn, "lastfm_session_key")
-        .map_err(|e| format!("Database error: {}", e))?;
+/// Append an eligible play to the local listening log when it's turned on
+fn log_listened_track(app: &AppHandle, track: &Track, timestamp: i64) {
+    let db = app.state::<Database>();
+    let source = db.with_conn(|conn| {
+        let enabled = settings::get_setting(conn, listening_log::LISTENING_LOG_SETTING)?;
+        if !is_setting_truthy(enabled) {
+            return Ok(None);
+        }
+        Ok(Some(queue::get_queue_context(conn)?.source))
+    });
+    let source = match source {
+        Ok(Some(source)) => source,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Failed to read listening log setting: {}", e);
+            return;
+        }
+    };
 
-    if session_key.is_none() || session_key.as_deref() == Some("") {
-        return Ok(());
+    let entry = LogEntry::for_track(track, timestamp, source);
+    let result = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())
+        .and_then(|dir| listening_log::append(&dir, &entry).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to append track_id={} to listening log: {}", track.id, e);
     }
+}
 
-    // Load track metadata
-    let track = library::get_track_by_id(conn, track_id)
-        .map_err(|e| format!("Failed to load track: {}", e))?
-        .ok_or_else(|| format!("Track {} not found", track_id))?;
-
-    // Check duration requirement (>=30s)
-    if track.duration.unwrap_or(0.0) < 30.0 {
-        return Ok(());
-    }
+/// Scrobble a track from the audio thread once its listened time is eligible
+///
+/// The audio thread calls this exactly once per play. Enabled/auth/threshold
+/// checks are delegated to `lastfm_scrobble`, which emits the scrobble status
+/// event on success or when the scrobble is queued for retry.
+///
+/// Every eligible play is also appended to the listening log, independently
+/// of Last.fm.
+pub(crate) async fn scrobble_listened_track(
+    app: AppHandle,
+    track_id: i64,
+    timestamp: i64,
+    duration: u32,
+    played_time: u32,
+) {
+    let track = {
+        let db = app.state::<Database>();
+        match db.with_conn(|conn| library::get_track_by_id(conn, track_id)) {
+            Ok(Some(track)) => track,
+            Ok(None) => {
+                warn!("Track {} not found, skipping scrobble", track_id);
+                return;
+            }
+            Err(e) => {
+                error!("Failed to load track {}: {}", track_id, e);
+                return;
+            }
+        }
+    };
 
-    // Queue for scrobbling (bypass threshold check since audio thread already validated)
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let artist = track.artist.as_deref().unwrap_or("Unknown Artist");
-    let title = track.title.as_deref().unwrap_or("Unknown Track");
-    let album = track.album.as_deref();
-
-    scrobble::queue_scrobble(
-        conn,
-        artist,
-        title,
-        album,
-        timestamp as i64,
-    )
-    .map_err(|e| format!("Failed to queue scrobble: {}", e))?;
+    log_listened_track(&app, &track, timestamp);
 
-    // Emit queued event
-    let _ = app.emit(
-        ScrobbleStatusEvent::EVENT_NAME,
-        ScrobbleStatusEvent::queued(artist.to_string(), title.to_string()),
-    );
+    let request = ScrobbleRequest {
+        artist: track.artist.unwrap_or_else(|| "Unknown Artist".to_string()),
+        track: track.title.unwrap_or_else(|| "Unknown Track".to_string()),
+        album: track.album,
+        timestamp,
+        duration,
+        played_time,
+    };
+    let (artist, title) = (request.artist.clone(), request.track.clone());
 
-    Ok(())
+    match lastfm_scrobble(app.clone(), app.state::<Database>(), request).await {
+        Ok(response) => info!(
+            "Scrobble for track_id={}: {}",
+            track_id, response.status
+        ),
+        Err(e) => {
+            warn!("Scrobble failed for track_id={}: {}", track_id, e);
+            let _ = app.emit(
+                ScrobbleStatusEvent::EVENT_NAME,
+                ScrobbleStatusEvent::failed(artist, title, e),
+            );
+        }
+    }
 }
 
 // ============================================
@@ -459,6 +522,15 @@ pub fn lastfm_queue_status(db: State<Database>) -> Result<QueueStatusResponse, S
     })
 }
 
+/// Get the state of the rate limiter shared by all Last.fm requests
+///
+/// A non-zero `wait_ms` means requests are being held back, e.g. during a
+/// loved tracks import.
+#[tauri::command]
+pub async fn lastfm_rate_limit_status() -> Result<RateLimitStatus, String> {
+    Ok(RateLimiter::shared().status().await)
+}
+
 /// Manually retry queued scrobbles
 #[tauri::command]
 pub async fn lastfm_queue_retry(
@@ -506,7 +578,7 @@ pub async fn lastfm_queue_retry(
                     if let Err(e) = db.with_conn(|conn| {
                         scrobble::remove_queued_scrobble(conn, queued_scrobble.id)
                     }) {
-                        eprintln!("[lastfm] Failed to remove scrobble from queue: {}", e);
+                        error!("Failed to remove scrobble from queue: {}", e);
                     }
 
                     // Emit success event
@@ -524,14 +596,14 @@ pub async fn lastfm_queue_retry(
                     if let Err(e) = db.with_conn(|conn| {
                         scrobble::increment_scrobble_retry(conn, queued_scrobble.id)
                     }) {
-                        eprintln!("[lastfm] Failed to increment retry count: {}", e);
+                        error!("Failed to increment retry count: {}", e);
                     }
                     failed += 1;
                 }
             }
             Err(e) => {
-                eprintln!(
-                    "[lastfm] Retry failed for {}/{}: {}",
+                warn!(
+                    "Retry failed for {}/{}: {}",
                     queued_scrobble.artist, queued_scrobble.track, e
                 );
 
@@ -539,7 +611,7 @@ pub async fn lastfm_queue_retry(
                 if let Err(e) = db
                     .with_conn(|conn| scrobble::increment_scrobble_retry(conn, queued_scrobble.id))
                 {
-                    eprintln!("[lastfm] Failed to increment retry count: {}", e);
+                    error!("Failed to increment retry count: {}", e);
                 }
                 failed += 1;
             }
@@ -629,8 +701,8 @@ pub async fn lastfm_import_loved_tracks(
     }
 
     let total_loved = all_loved_tracks.len();
-    println!(
-        "[lastfm] Fetched {} loved tracks from Last.fm",
+    info!(
+        "Fetched {} loved tracks from Last.fm",
         total_loved
     );
 
@@ -649,6 +721,10 @@ pub async fn lastfm_import_loved_tracks(
             search: Some(track_name.clone()),  // Search in title field
             artist: Some(artist_name.to_string()),  // Exact artist filter
             album: None,
+            tags: Vec::new(),
+            tag_match: Default::default(),
+            favorites_only: false,
+            min_rating: None,
             sort_by: LibrarySortColumn::Title,
             sort_order: SortOrder::Asc,
             limit: 5,  // Get top 5 matches to find best one
@@ -665,6 +741,10 @@ pub async fn lastfm_import_loved_tracks(
                 search: Some(format!("{} {}", artist_name, track_name)),
                 artist: None,
                 album: None,
+                tags: Vec::new(),
+                tag_match: Default::default(),
+                favorites_only: false,
+                min_rating: None,
                 sort_by: LibrarySortColumn::Title,
                 sort_order: SortOrder::Asc,
                 limit: 5,
@@ -679,28 +759,32 @@ pub async fn lastfm_import_loved_tracks(
         // Check if we found any matches
         if search_results.items.is_empty() {
             not_in_library += 1;
-            println!(
-                "[lastfm] No match found for: {} - {}",
+            debug!(
+                "No match found for: {} - {}",
                 artist_name, track_name
             );
             continue;
         }
 
         if let Some(first_track) = search_results.items.first() {
-            println!(
-                "[lastfm] Found match: {} - {} -> {} (ID: {})",
+            debug!(
+                "Found match: {} - {} -> {} (ID: {})",
                 artist_name, track_name, first_track.filepath, first_track.id
             );
             // Check if already favorited
             let (is_fav, _) = db
-                .with_conn(|conn| favorites::is_favorite(conn, first_track.id))
+                .with_conn(|conn| {
+                    favorites::is_favorite(conn, favorites::DEFAULT_FAVORITE_LIST_ID, first_track.id)
+                })
                 .map_err(|e: crate::db::DbError| format!("Favorites check error: {}", e))?;
 
             if is_fav {
                 already_favorited += 1;
             } else {
                 // Add to favorites
-                let add_result = db.with_conn(|conn| favorites::add_favorite(conn, first_track.id));
+                let add_result = db.with_conn(|conn| {
+                    favorites::add_favorite(conn, favorites::DEFAULT_FAVORITE_LIST_ID, first_track.id)
+                });
                 match add_result {
                     Ok(Some(_)) => {
                         imported += 1;
@@ -710,8 +794,8 @@ pub async fn lastfm_import_loved_tracks(
                         already_favorited += 1;
                     }
                     Err(e) => {
-                        eprintln!(
-                            "[lastfm] Failed to add {} - {} to favorites: {}",
+                        error!(
+                            "Failed to add {} - {} to favorites: {}",
                             artist_name, track_name, e
                         );
                     }
@@ -727,7 +811,7 @@ pub async fn lastfm_import_loved_tracks(
         imported, already_favorited, not_in_library
     );
 
-    println!("[lastfm] {}", message);
+    info!("{}", message);
 
     Ok(ImportLovedTracksResponse {
         status: "success".to_string(),
@@ -1,4 +1,7 @@
 mod audio;
+mod audio_profiles;
+mod database;
+mod error;
 mod favorites;
 mod lastfm;
 mod playlists;
@@ -6,32 +9,56 @@ mod queue;
 mod settings;
 
 pub use audio::{
-    audio_get_status, audio_get_volume, audio_load, audio_pause, audio_play, audio_seek,
-    audio_set_volume, audio_stop, AudioState, PlaybackStatus,
+    audio_get_muted, audio_get_status, audio_get_volume, audio_load, audio_next_chapter,
+    audio_pause, audio_play, audio_prev_chapter, audio_seek, audio_seek_commit,
+    audio_seek_preview, audio_seek_relative,
+    audio_seek_to_bookmark, audio_set_buffer_ms, audio_set_fade_ms, audio_set_muted,
+    audio_set_trim_silence, audio_set_volume, audio_stop, AudioState, PlaybackStatus,
 };
 
+pub use audio_profiles::{
+    audio_get_device_profile, audio_profile_delete_rule, audio_profile_list,
+    audio_profile_set_global, audio_profile_set_rule, audio_set_device_profile,
+};
+
+pub use error::CommandError;
+
+pub use database::{db_checkpoint, db_integrity_check, db_optimize, db_relocate, db_vacuum};
+
 pub use favorites::{
-    favorites_add, favorites_check, favorites_get, favorites_get_recently_added,
-    favorites_get_recently_played, favorites_get_top25, favorites_remove,
+    favorites_add, favorites_add_many, favorites_check, favorites_check_many,
+    favorites_create_list, favorites_delete_list, favorites_get,
+    favorites_get_recently_added, favorites_get_recently_played, favorites_get_top25,
+    favorites_lists, favorites_remove, favorites_remove_many,
 };
 
 pub use lastfm::{
     lastfm_auth_callback, lastfm_disconnect, lastfm_get_auth_url, lastfm_get_settings,
     lastfm_import_loved_tracks, lastfm_now_playing, lastfm_queue_retry, lastfm_queue_status,
-    lastfm_scrobble, lastfm_update_settings,
+    lastfm_rate_limit_status, lastfm_scrobble, lastfm_test_connection, lastfm_update_settings,
 };
 
 pub use playlists::{
-    playlist_add_tracks, playlist_create, playlist_delete, playlist_generate_name, playlist_get,
-    playlist_list, playlist_remove_track, playlist_reorder_tracks, playlist_update,
-    playlists_reorder,
+    playlist_add_tracks, playlist_create, playlist_create_folder, playlist_delete,
+    playlist_generate_name, playlist_get, playlist_get_virtual, playlist_list, playlist_move,
+    playlist_remove_track, playlist_reorder_tracks, playlist_repair, playlist_repair_all,
+    playlist_update, playlists_reorder,
+    playlists_reorder_many,
 };
 
 pub use queue::{
-    queue_add, queue_add_files, queue_clear, queue_get, queue_get_playback_state, queue_remove,
-    queue_reorder, queue_set_current_index, queue_set_loop, queue_set_shuffle, queue_shuffle,
+    queue_add, queue_add_files, queue_clear, queue_contains, queue_get, queue_get_context,
+    queue_get_playback_state, queue_get_shuffle_order, queue_get_totals, queue_jump_to_track,
+    queue_peek_next,
+    queue_peek_previous, queue_peek_upcoming, queue_play_folder, queue_play_similar_local,
+    queue_refresh_metadata, queue_remove, queue_reorder, queue_replace,
+    queue_reorder_many, queue_reshuffle, queue_reshuffle_cycle,
+    queue_set_consume, queue_set_current_index, queue_set_loop, queue_set_shuffle,
+    queue_set_shuffle_mode, queue_shuffle, queue_shuffle_library,
+    queue_start_artist_radio, queue_start_similar_radio,
 };
 
 pub use settings::{
     settings_get, settings_get_all, settings_reset, settings_set, settings_update,
+    stored_background_threads, stored_log_level, stored_verify_on_startup,
 };
@@ -4,10 +4,21 @@
 //! replacing the Python FastAPI playlist routes.
 
 use tauri::{AppHandle, State};
+use tracing::info;
 
-use crate::db::{playlists, Database, Playlist, PlaylistWithTracks};
+use crate::commands::CommandError;
+use crate::db::playlists::PlaylistTrackQuery;
+use crate::db::{
+    playlists, Database, Playlist, PlaylistWithTracks, SortOrder, Track, VirtualPlaylist,
+};
 use crate::events::{EventEmitter, PlaylistsUpdatedEvent};
 
+/// Tracks returned by a virtual playlist when no limit is given
+const DEFAULT_VIRTUAL_PLAYLIST_LIMIT: i64 = 100;
+
+/// Most tracks a virtual playlist returns
+const MAX_VIRTUAL_PLAYLIST_LIMIT: i64 = 5000;
+
 /// Response for playlist list operations
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct PlaylistListResponse {
@@ -42,9 +53,9 @@ pub struct PlaylistGenerateNameResponse {
 
 /// Get all playlists with track counts
 #[tauri::command]
-pub fn playlist_list(db: State<'_, Database>) -> Result<PlaylistListResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let playlists = playlists::get_playlists(&conn).map_err(|e| e.to_string())?;
+pub fn playlist_list(db: State<'_, Database>) -> Result<PlaylistListResponse, CommandError> {
+    let conn = db.conn()?;
+    let playlists = playlists::get_playlists(&conn)?;
     let count = playlists.len() as i64;
 
     Ok(PlaylistListResponse { playlists, count })
@@ -56,13 +67,35 @@ pub fn playlist_create(
     app: AppHandle,
     db: State<'_, Database>,
     name: String,
-) -> Result<PlaylistResponse, String> {
+) -> Result<PlaylistResponse, CommandError> {
+    if name.trim().is_empty() {
+        return Err(CommandError::invalid_input("Playlist name cannot be empty"));
+    }
+
+    let conn = db.conn()?;
+    let playlist = playlists::create_playlist(&conn, &name)?;
+
+    if let Some(ref p) = playlist {
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::created(p.id));
+    }
+
+    Ok(PlaylistResponse { playlist })
+}
+
+/// Create a playlist folder, optionally inside another folder
+#[tauri::command]
+pub fn playlist_create_folder(
+    app: AppHandle,
+    db: State<'_, Database>,
+    name: String,
+    parent_id: Option<i64>,
+) -> Result<PlaylistResponse, CommandError> {
     if name.trim().is_empty() {
-        return Err("Playlist name cannot be empty".to_string());
+        return Err(CommandError::invalid_input("Folder name cannot be empty"));
     }
 
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let playlist = playlists::create_playlist(&conn, &name).map_err(|e| e.to_string())?;
+    let conn = db.conn()?;
+    let playlist = playlists::create_playlist_folder(&conn, &name, parent_id)?;
 
     if let Some(ref p) = playlist {
         let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::created(p.id));
@@ -71,14 +104,85 @@ pub fn playlist_create(
     Ok(PlaylistResponse { playlist })
 }
 
+/// Move a playlist or folder into a folder, or to the top level when
+/// `new_parent_id` is omitted
+#[tauri::command]
+pub fn playlist_move(
+    app: AppHandle,
+    db: State<'_, Database>,
+    playlist_id: i64,
+    new_parent_id: Option<i64>,
+) -> Result<PlaylistOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = playlists::move_playlist(&conn, playlist_id, new_parent_id)?;
+
+    if success {
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::moved(playlist_id));
+    }
+
+    Ok(PlaylistOperationResponse { success })
+}
+
 /// Get a playlist with its tracks
+///
+/// Without `limit` every track is returned. Tracks come in manual order
+/// unless `sort_by` names a library sort column; `sort_order` defaults to
+/// ascending. `track_count` is always the whole playlist. Broken track
+/// positions are repaired first, unless the
+/// `playlist_auto_repair_positions` setting is off.
 #[tauri::command]
 pub fn playlist_get(
     db: State<'_, Database>,
     playlist_id: i64,
-) -> Result<Option<PlaylistWithTracks>, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    playlists::get_playlist(&conn, playlist_id).map_err(|e| e.to_string())
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Option<PlaylistWithTracks>, CommandError> {
+    let query = PlaylistTrackQuery {
+        sort_by: sort_by.as_ref().and_then(|s| s.parse().ok()),
+        sort_order: match sort_order.as_deref() {
+            Some(s) if s.eq_ignore_ascii_case("desc") => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        },
+        limit: limit.map(|l| l.max(0)),
+        offset: offset.unwrap_or(0),
+    };
+
+    // Gaps or duplicate positions from an interrupted reorder would misorder pages
+    let needs_repair = db
+        .with_conn(|conn| {
+            Ok(playlists::auto_repair_enabled(conn)?
+                && !playlists::positions_are_dense(conn, playlist_id)?)
+        })?;
+    if needs_repair {
+        info!("Repairing track positions of playlist {}", playlist_id);
+        db.transaction(|conn| playlists::repair_positions(conn, playlist_id))?;
+    }
+
+    let conn = db.conn()?;
+    playlists::get_playlist_page(&conn, playlist_id, &query).map_err(CommandError::from)
+}
+
+/// Get the current tracks of a built-in auto-playlist
+///
+/// `kind` is one of `decade:<year>`, `genre:<name>`, `most_played`,
+/// `never_played`, or `recently_added`. Tracks are queried on every call.
+#[tauri::command]
+pub fn playlist_get_virtual(
+    db: State<'_, Database>,
+    kind: String,
+    limit: Option<i64>,
+) -> Result<Vec<Track>, CommandError> {
+    let virtual_playlist: VirtualPlaylist = kind
+        .parse()
+        .map_err(|_| CommandError::invalid_input(format!("Unknown virtual playlist: {}", kind)))?;
+    let limit = limit
+        .unwrap_or(DEFAULT_VIRTUAL_PLAYLIST_LIMIT)
+        .clamp(1, MAX_VIRTUAL_PLAYLIST_LIMIT);
+
+    let conn = db.conn()?;
+    playlists::get_virtual_playlist(&conn, &virtual_playlist, limit).map_err(CommandError::from)
 }
 
 /// Update playlist metadata (name)
@@ -88,10 +192,9 @@ pub fn playlist_update(
     db: State<'_, Database>,
     playlist_id: i64,
     name: Option<String>,
-) -> Result<PlaylistResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let playlist =
-        playlists::update_playlist(&conn, playlist_id, name.as_deref()).map_err(|e| e.to_string())?;
+) -> Result<PlaylistResponse, CommandError> {
+    let conn = db.conn()?;
+    let playlist = playlists::update_playlist(&conn, playlist_id, name.as_deref())?;
 
     if playlist.is_some() && name.is_some() {
         let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::renamed(playlist_id));
@@ -100,15 +203,23 @@ pub fn playlist_update(
     Ok(PlaylistResponse { playlist })
 }
 
-/// Delete a playlist
+/// Delete a playlist or folder
+///
+/// A folder that still has children is only deleted when `recursive` is set,
+/// in which case everything nested inside it is deleted too.
 #[tauri::command]
 pub fn playlist_delete(
     app: AppHandle,
     db: State<'_, Database>,
     playlist_id: i64,
-) -> Result<PlaylistOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let success = playlists::delete_playlist(&conn, playlist_id).map_err(|e| e.to_string())?;
+    recursive: Option<bool>,
+) -> Result<PlaylistOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = if recursive.unwrap_or(false) {
+        playlists::delete_playlist_tree(&conn, playlist_id)
+    } else {
+        playlists::delete_playlist(&conn, playlist_id)
+    }?;
 
     if success {
         let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::deleted(playlist_id));
@@ -125,17 +236,14 @@ pub fn playlist_add_tracks(
     playlist_id: i64,
     track_ids: Vec<i64>,
     position: Option<i64>,
-) -> Result<PlaylistAddTracksResponse, String> {
+) -> Result<PlaylistAddTracksResponse, CommandError> {
     if track_ids.is_empty() {
-        return Err("track_ids must not be empty".to_string());
+        return Err(CommandError::invalid_input("track_ids must not be empty"));
     }
 
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let added =
-        playlists::add_tracks_to_playlist(&conn, playlist_id, &track_ids, position)
-            .map_err(|e| e.to_string())?;
-    let track_count =
-        playlists::get_playlist_track_count(&conn, playlist_id).map_err(|e| e.to_string())?;
+    let conn = db.conn()?;
+    let added = playlists::add_tracks_to_playlist(&conn, playlist_id, &track_ids, position)?;
+    let track_count = playlists::get_playlist_track_count(&conn, playlist_id)?;
 
     let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::tracks_added(playlist_id, track_ids));
 
@@ -152,11 +260,9 @@ pub fn playlist_remove_track(
     db: State<'_, Database>,
     playlist_id: i64,
     position: i64,
-) -> Result<PlaylistOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let success =
-        playlists::remove_track_from_playlist(&conn, playlist_id, position)
-            .map_err(|e| e.to_string())?;
+) -> Result<PlaylistOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = playlists::remove_track_from_playlist(&conn, playlist_id, position)?;
 
     if success {
         let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::tracks_removed(playlist_id, vec![]));
@@ -173,11 +279,9 @@ pub fn playlist_reorder_tracks(
     playlist_id: i64,
     from_position: i64,
     to_position: i64,
-) -> Result<PlaylistOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let success =
-        playlists::reorder_playlist(&conn, playlist_id, from_position, to_position)
-            .map_err(|e| e.to_string())?;
+) -> Result<PlaylistOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = playlists::reorder_playlist(&conn, playlist_id, from_position, to_position)?;
 
     if success {
         let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::reordered(playlist_id));
@@ -186,6 +290,42 @@ pub fn playlist_reorder_tracks(
     Ok(PlaylistOperationResponse { success })
 }
 
+/// Rewrite a playlist's track positions as 0, 1, 2, ... in their current order
+///
+/// Fixes gaps and duplicates left by interrupted reorders. Returns whether any
+/// position changed.
+#[tauri::command]
+pub fn playlist_repair(
+    app: AppHandle,
+    db: State<'_, Database>,
+    playlist_id: i64,
+) -> Result<bool, CommandError> {
+    let repaired = db
+        .transaction(|conn| playlists::repair_positions(conn, playlist_id))?;
+
+    if repaired {
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::reordered(playlist_id));
+    }
+
+    Ok(repaired)
+}
+
+/// Repair the track positions of every playlist, returning the IDs changed
+#[tauri::command]
+pub fn playlist_repair_all(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<Vec<i64>, CommandError> {
+    let repaired = db
+        .transaction(playlists::repair_all_positions)?;
+
+    for playlist_id in &repaired {
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::reordered(*playlist_id));
+    }
+
+    Ok(repaired)
+}
+
 /// Reorder playlists in the sidebar
 #[tauri::command]
 pub fn playlists_reorder(
@@ -193,11 +333,36 @@ pub fn playlists_reorder(
     db: State<'_, Database>,
     from_position: i64,
     to_position: i64,
-) -> Result<PlaylistOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let success =
-        playlists::reorder_playlists(&conn, from_position, to_position)
-            .map_err(|e| e.to_string())?;
+) -> Result<PlaylistOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = playlists::reorder_playlists(&conn, from_position, to_position)?;
+
+    if success {
+        // Use playlist_id=0 to indicate sidebar reorder (affects all playlists)
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::reordered(0));
+    }
+
+    Ok(PlaylistOperationResponse { success })
+}
+
+/// Move several playlists to a target position at once (multi-select drag)
+///
+/// The playlists must share a folder and keep their relative order;
+/// `to_position` counts among that folder's children. Use `playlist_move`
+/// to move between folders. Everything happens in one transaction.
+#[tauri::command]
+pub fn playlists_reorder_many(
+    app: AppHandle,
+    db: State<'_, Database>,
+    playlist_ids: Vec<i64>,
+    to_position: i64,
+) -> Result<PlaylistOperationResponse, CommandError> {
+    if playlist_ids.is_empty() {
+        return Err(CommandError::invalid_input("playlist_ids must not be empty"));
+    }
+
+    let success = db
+        .transaction(|conn| playlists::reorder_playlist_block(conn, &playlist_ids, to_position))?;
 
     if success {
         // Use playlist_id=0 to indicate sidebar reorder (affects all playlists)
@@ -212,11 +377,10 @@ pub fn playlists_reorder(
 pub fn playlist_generate_name(
     db: State<'_, Database>,
     base: Option<String>,
-) -> Result<PlaylistGenerateNameResponse, String> {
+) -> Result<PlaylistGenerateNameResponse, CommandError> {
     let base_name = base.as_deref().unwrap_or("New playlist");
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let name =
-        playlists::generate_unique_playlist_name(&conn, base_name).map_err(|e| e.to_string())?;
+    let conn = db.conn()?;
+    let name = playlists::generate_unique_playlist_name(&conn, base_name)?;
 
     Ok(PlaylistGenerateNameResponse { name })
 }
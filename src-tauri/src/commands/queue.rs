@@ -3,12 +3,23 @@
 //! These commands expose queue operations to the frontend,
 //! replacing the Python FastAPI queue routes.
 
-use rand::rng;
-use rand::seq::SliceRandom;
+use std::path::Path;
 use tauri::{AppHandle, State};
-
-use crate::db::{queue, Database, QueueItem, QueueState, Track};
-use crate::events::{EventEmitter, QueueStateChangedEvent, QueueUpdatedEvent};
+use tracing::warn;
+
+use crate::commands::{AudioState, CommandError};
+use crate::db::radio::{self, SimilarMatches, SimilarSuggestion};
+use crate::db::{
+    features, library, queue, settings, Database, DbError, DbResult, QueueContext, QueueItem,
+    QueueState, QueueTotals, ShuffleOrder, Track,
+};
+use crate::events::{
+    EventEmitter, LibraryUpdatedEvent, QueueStateChangedEvent, QueueUpdatedEvent,
+};
+use crate::lastfm::LastFmClient;
+use crate::library::audio_features::{extract_features, rank_similar};
+use crate::playback::{play_track, PlaybackManager};
+use crate::scanner::folder;
 
 /// Response for queue get operations
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -32,6 +43,58 @@ pub struct QueueAddFilesResponse {
     pub tracks: Vec<Track>,
 }
 
+/// Response for queue reshuffle operations
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueueReshuffleResponse {
+    pub seed: u64,
+    pub queue_length: i64,
+}
+
+/// Response for radio operations
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueueRadioResponse {
+    /// Tracks queued, including the seed
+    pub queued: i64,
+    /// Similar suggestions from Last.fm with no matching library track
+    pub unmatched: usize,
+    /// Where the tracks came from: "artist", "similar", "genre", or "features"
+    pub source: String,
+}
+
+/// Response for queue play-folder operations
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueuePlayFolderResponse {
+    /// Tracks queued from the folder
+    pub queued: i64,
+    pub queue_length: i64,
+    /// Audio files in the folder that couldn't be added to the library
+    pub skipped: Vec<String>,
+}
+
+/// Response for queue replace operations
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct QueueReplaceResponse {
+    pub queue_length: i64,
+    /// Index playback starts at, adjusted for skipped tracks; -1 when empty
+    pub current_index: i64,
+    /// Requested track IDs with no library track
+    pub skipped: Vec<i64>,
+}
+
+/// Library filter for queue operations that pull tracks from the library
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct QueueLibraryFilter {
+    pub search: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+}
+
+/// Setting that caps how many tracks `queue_shuffle_library` queues
+const SHUFFLE_LIBRARY_MAX_SETTING: &str = "queue_shuffle_library_max";
+
+/// Most items `queue_peek_upcoming` returns
+const MAX_PEEK_UPCOMING: usize = 100;
+
 /// Response for queue operations that return success status
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct QueueOperationResponse {
@@ -41,36 +104,37 @@ pub struct QueueOperationResponse {
 
 /// Get the current playback queue with track metadata
 #[tauri::command]
-pub fn queue_get(db: State<'_, Database>) -> Result<QueueResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let items = queue::get_queue(&conn).map_err(|e| e.to_string())?;
+pub fn queue_get(db: State<'_, Database>) -> Result<QueueResponse, CommandError> {
+    let conn = db.conn()?;
+    let items = queue::get_queue(&conn)?;
     let count = items.len() as i64;
 
     Ok(QueueResponse { items, count })
 }
 
 /// Add tracks to the queue by track IDs
+///
+/// `source` records where the tracks were queued from, e.g. "playlist:12",
+/// "album:Abbey Road", or "library".
 #[tauri::command]
 pub fn queue_add(
     app: AppHandle,
     db: State<'_, Database>,
     track_ids: Vec<i64>,
     position: Option<i64>,
-) -> Result<QueueAddResponse, String> {
+    source: Option<String>,
+) -> Result<QueueAddResponse, CommandError> {
     if track_ids.is_empty() {
-        return Err("track_ids must not be empty".to_string());
+        return Err(CommandError::invalid_input("track_ids must not be empty"));
     }
 
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let added = queue::add_to_queue(&conn, &track_ids, position).map_err(|e| e.to_string())?;
-    let queue_length = queue::get_queue_length(&conn).map_err(|e| e.to_string())?;
+    let (added, reshuffled, queue_length) = db.transaction(|conn| {
+        let added = queue::add_to_queue_from(conn, &track_ids, position, source.as_deref())?;
+        let reshuffled = position.is_none() && regenerate_shuffled_tail(conn)?;
+        Ok((added, reshuffled, queue::get_queue_length(conn)?))
+    })?;
 
-    // Calculate positions that were added
-    let start_pos = position.unwrap_or(queue_length - added);
-    let positions: Vec<i64> = (start_pos..start_pos + added).collect();
-
-    // Emit queue updated event with payload
-    let _ = app.emit_queue_updated(QueueUpdatedEvent::added(positions, queue_length));
+    let _ = app.emit_queue_updated(added_event(added, position, reshuffled, queue_length));
 
     Ok(QueueAddResponse {
         added,
@@ -85,22 +149,20 @@ pub fn queue_add_files(
     db: State<'_, Database>,
     filepaths: Vec<String>,
     position: Option<i64>,
-) -> Result<QueueAddFilesResponse, String> {
+    source: Option<String>,
+) -> Result<QueueAddFilesResponse, CommandError> {
     if filepaths.is_empty() {
-        return Err("filepaths must not be empty".to_string());
+        return Err(CommandError::invalid_input("filepaths must not be empty"));
     }
 
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let (added, tracks) =
-        queue::add_files_to_queue(&conn, &filepaths, position).map_err(|e| e.to_string())?;
-    let queue_length = queue::get_queue_length(&conn).map_err(|e| e.to_string())?;
-
-    // Calculate positions that were added
-    let start_pos = position.unwrap_or(queue_length - added);
-    let positions: Vec<i64> = (start_pos..start_pos + added).collect();
+    let (added, tracks, reshuffled, queue_length) = db.transaction(|conn| {
+        let (added, tracks) =
+            queue::add_files_to_queue_from(conn, &filepaths, position, source.as_deref())?;
+        let reshuffled = position.is_none() && regenerate_shuffled_tail(conn)?;
+        Ok((added, tracks, reshuffled, queue::get_queue_length(conn)?))
+    })?;
 
-    // Emit queue updated event with payload
-    let _ = app.emit_queue_updated(QueueUpdatedEvent::added(positions, queue_length));
+    let _ = app.emit_queue_updated(added_event(added, position, reshuffled, queue_length));
 
     Ok(QueueAddFilesResponse {
         added,
@@ -115,27 +177,38 @@ pub fn queue_remove(
     app: AppHandle,
     db: State<'_, Database>,
     position: i64,
-) -> Result<(), String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let removed = queue::remove_from_queue(&conn, position).map_err(|e| e.to_string())?;
-
-    if !removed {
-        return Err(format!("No track at position {}", position));
-    }
-
-    let queue_length = queue::get_queue_length(&conn).map_err(|e| e.to_string())?;
-
-    // Emit queue updated event with payload
-    let _ = app.emit_queue_updated(QueueUpdatedEvent::removed(position, queue_length));
+) -> Result<(), CommandError> {
+    let removed = db.transaction(|conn| {
+        if !queue::remove_from_queue(conn, position)? {
+            return Ok(None);
+        }
+        let reshuffled = regenerate_shuffled_tail(conn)?;
+        Ok(Some((reshuffled, queue::get_queue_length(conn)?)))
+    })?;
+
+    let Some((reshuffled, queue_length)) = removed else {
+        return Err(CommandError::not_found(format!(
+            "No track at position {}",
+            position
+        )));
+    };
+
+    // A regenerated tail moved every unplayed entry, not just the removed one
+    let event = if reshuffled {
+        QueueUpdatedEvent::shuffled(queue_length)
+    } else {
+        QueueUpdatedEvent::removed(position, queue_length)
+    };
+    let _ = app.emit_queue_updated(event);
 
     Ok(())
 }
 
 /// Clear the entire queue
 #[tauri::command]
-pub fn queue_clear(app: AppHandle, db: State<'_, Database>) -> Result<(), String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    queue::clear_queue(&conn).map_err(|e| e.to_string())?;
+pub fn queue_clear(app: AppHandle, db: State<'_, Database>) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    queue::clear_queue(&conn)?;
 
     // Emit queue updated event with payload
     let _ = app.emit_queue_updated(QueueUpdatedEvent::cleared());
@@ -143,6 +216,73 @@ pub fn queue_clear(app: AppHandle, db: State<'_, Database>) -> Result<(), String
     Ok(())
 }
 
+/// Replace the whole queue with tracks by ID and start at `start_index`
+///
+/// Clearing, refilling, and setting the current index happen in one
+/// transaction, followed by a single `queue:updated` and a single
+/// `queue:state-changed` event. IDs with no library track are skipped and
+/// reported; `start_index` points into `track_ids` and moves with the skips.
+#[tauri::command]
+pub fn queue_replace(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_ids: Vec<i64>,
+    start_index: i64,
+    source: Option<String>,
+) -> Result<QueueReplaceResponse, CommandError> {
+    replace_queue(&app, &db, &track_ids, start_index, source.as_deref())
+}
+
+/// `queue_replace` against any event emitter
+fn replace_queue(
+    emitter: &impl EventEmitter,
+    db: &Database,
+    track_ids: &[i64],
+    start_index: i64,
+    source: Option<&str>,
+) -> Result<QueueReplaceResponse, CommandError> {
+    let (skipped, queue_length, state) = db.transaction(|conn| {
+        let (_, skipped) = queue::replace_with_tracks(conn, track_ids, start_index, source)?;
+        Ok((
+            skipped,
+            queue::get_queue_length(conn)?,
+            queue::get_queue_state(conn)?,
+        ))
+    })?;
+
+    let current_index = state.current_index;
+    let _ = emitter.emit_queue_updated(QueueUpdatedEvent::replaced(queue_length));
+    let _ = emitter.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(QueueReplaceResponse {
+        queue_length,
+        current_index,
+        skipped,
+    })
+}
+
+/// Re-take the metadata snapshots of queued tracks from the library
+///
+/// Only matters with the `queue_metadata_snapshot` setting on. Returns how
+/// many snapshots were taken.
+#[tauri::command]
+pub fn queue_refresh_metadata(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<i64, CommandError> {
+    let conn = db.conn()?;
+    let refreshed = queue::refresh_snapshots(&conn)?;
+    let queue_length = queue::get_queue_length(&conn)?;
+
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::refreshed(queue_length));
+
+    Ok(refreshed)
+}
+
 /// Reorder tracks in the queue (move from one position to another)
 #[tauri::command]
 pub fn queue_reorder(
@@ -150,19 +290,22 @@ pub fn queue_reorder(
     db: State<'_, Database>,
     from_position: i64,
     to_position: i64,
-) -> Result<QueueOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let success =
-        queue::reorder_queue(&conn, from_position, to_position).map_err(|e| e.to_string())?;
+) -> Result<QueueOperationResponse, CommandError> {
+    let conn = db.conn()?;
+    let success = queue::reorder_queue(&conn, from_position, to_position)?;
 
     if !success {
-        return Err("Invalid positions".to_string());
+        return Err(CommandError::invalid_input("Invalid positions"));
     }
 
-    let queue_length = queue::get_queue_length(&conn).map_err(|e| e.to_string())?;
+    let queue_length = queue::get_queue_length(&conn)?;
 
     // Emit queue updated event with payload
-    let _ = app.emit_queue_updated(QueueUpdatedEvent::reordered(from_position, to_position, queue_length));
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::reordered(
+        from_position,
+        to_position,
+        queue_length,
+    ));
 
     Ok(QueueOperationResponse {
         success,
@@ -170,65 +313,496 @@ pub fn queue_reorder(
     })
 }
 
-/// Shuffle the queue using Fisher-Yates algorithm
+/// Move several queue items to a target position at once (multi-select drag).
+///
+/// The items keep their relative order and the whole move happens in a single
+/// transaction, so the frontend never observes intermediate states.
+///
+/// Items are identified by position rather than by queue item ID: every
+/// reorder rebuilds the queue rows through `replace_queue_entries`, so row
+/// IDs don't survive from one change to the next, and the same track may be
+/// queued more than once, so track IDs are ambiguous. Positions are what the
+/// frontend's queue holds, as with `queue_reorder` and `queue_remove`.
+#[tauri::command]
+pub fn queue_reorder_many(
+    app: AppHandle,
+    db: State<'_, Database>,
+    positions: Vec<i64>,
+    to_position: i64,
+) -> Result<QueueOperationResponse, CommandError> {
+    if positions.is_empty() {
+        return Err(CommandError::invalid_input("positions must not be empty"));
+    }
+
+    let (block_start, queue_length) = db.transaction(|conn| {
+        let block_start = queue::reorder_queue_block(conn, &positions, to_position)?;
+        let queue_length = queue::get_queue_length(conn)?;
+        Ok((block_start, queue_length))
+    })?;
+
+    let Some(block_start) = block_start else {
+        return Err(CommandError::invalid_input("Invalid positions"));
+    };
+
+    let from_position = positions.iter().min().copied().unwrap_or(0);
+
+    // Emit a single queue updated event for the whole block
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::reordered(
+        from_position,
+        block_start,
+        queue_length,
+    ));
+
+    Ok(QueueOperationResponse {
+        success: true,
+        queue_length,
+    })
+}
+
+/// Shuffle the queue with a new seed
+///
+/// With `keep_current` (the default) the first entry stays put and the rest
+/// is shuffled; otherwise every entry is. The shuffle follows the queue's
+/// shuffle mode and no-repeat window, and the seed is persisted like
+/// `queue_reshuffle` does.
 #[tauri::command]
 pub fn queue_shuffle(
     app: AppHandle,
     db: State<'_, Database>,
     keep_current: Option<bool>,
-) -> Result<QueueOperationResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let items = queue::get_queue(&conn).map_err(|e| e.to_string())?;
+) -> Result<QueueOperationResponse, CommandError> {
+    let start = if keep_current.unwrap_or(true) { 1 } else { 0 };
+    let queue_length = db.transaction(|conn| queue::reshuffle_from(conn, start, rand::random()))?;
 
-    if items.is_empty() {
-        return Ok(QueueOperationResponse {
-            success: true,
-            queue_length: 0,
-        });
+    if queue_length > 0 {
+        // Emit queue updated event with payload
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
     }
 
-    // Get filepaths from queue items
-    let mut filepaths: Vec<String> = items.iter().map(|item| item.track.filepath.clone()).collect();
+    Ok(QueueOperationResponse {
+        success: true,
+        queue_length,
+    })
+}
 
-    let keep_current = keep_current.unwrap_or(true);
+/// Reshuffle the unplayed part of the queue with a deterministic seed.
+///
+/// When no seed is given a new random one is generated. The seed is persisted
+/// in the queue state so the same order can be rebuilt after a restart.
+#[tauri::command]
+pub fn queue_reshuffle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    seed: Option<u64>,
+) -> Result<QueueReshuffleResponse, CommandError> {
+    let seed = seed.unwrap_or_else(rand::random);
 
-    if keep_current && !filepaths.is_empty() {
-        // Keep first item, shuffle rest using Fisher-Yates
-        let first = filepaths.remove(0);
-        filepaths.shuffle(&mut rng());
-        filepaths.insert(0, first);
-    } else {
-        // Shuffle all items
-        filepaths.shuffle(&mut rng());
-    }
+    let queue_length = db.transaction(|conn| queue::reshuffle_unplayed(conn, seed))?;
 
-    // Rebuild queue with shuffled order
-    queue::clear_queue(&conn).map_err(|e| e.to_string())?;
+    // Emit queue updated event with payload
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
 
-    for filepath in &filepaths {
-        conn.execute(
-            "INSERT INTO queue (filepath) VALUES (?)",
-            rusqlite::params![filepath],
-        )
-        .map_err(|e| e.to_string())?;
-    }
+    Ok(QueueReshuffleResponse { seed, queue_length })
+}
 
-    let queue_length = queue::get_queue_length(&conn).map_err(|e| e.to_string())?;
+/// Reshuffle the queue for a new loop-all cycle when the user skips past the end
+///
+/// The entry at `ended` goes last, with its album in album shuffle mode, so
+/// it doesn't play again straight away. The caller plays the first entry.
+#[tauri::command]
+pub fn queue_reshuffle_cycle(
+    app: AppHandle,
+    db: State<'_, Database>,
+    ended: usize,
+) -> Result<QueueReshuffleResponse, CommandError> {
+    let seed = rand::random();
+    let queue_length = db.transaction(|conn| queue::reshuffle_for_new_cycle(conn, ended, seed))?;
 
     // Emit queue updated event with payload
     let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
 
-    Ok(QueueOperationResponse {
-        success: true,
+    Ok(QueueReshuffleResponse { seed, queue_length })
+}
+
+/// Replace the queue with a seeded shuffle of the whole library ("Shuffle All").
+///
+/// An optional filter narrows the library the same way `library_get_all` does;
+/// missing tracks are always excluded. The queue is capped at the
+/// `queue_shuffle_library_max` setting (default 1000) and playback starts at
+/// index 0.
+#[tauri::command]
+pub fn queue_shuffle_library(
+    app: AppHandle,
+    db: State<'_, Database>,
+    filter: Option<QueueLibraryFilter>,
+    seed: Option<u64>,
+) -> Result<QueueReshuffleResponse, CommandError> {
+    let filter = filter.unwrap_or_default();
+    let seed = seed.unwrap_or_else(rand::random);
+    let query = library::LibraryQuery {
+        search: filter.search,
+        artist: filter.artist,
+        album: filter.album,
+        ..Default::default()
+    };
+
+    let (queue_length, state) = db.transaction(|conn| {
+        let max_size = settings::get_setting(conn, SHUFFLE_LIBRARY_MAX_SETTING)?
+            .and_then(|v| v.trim_matches('"').parse::<usize>().ok())
+            .filter(|&max| max > 0)
+            .unwrap_or(queue::SHUFFLE_LIBRARY_MAX_DEFAULT);
+        let queue_length = queue::replace_with_shuffled_library(conn, &query, seed, max_size)?;
+        Ok((queue_length, queue::get_queue_state(conn)?))
+    })?;
+
+    // Emit queue updated and state changed events
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(QueueReshuffleResponse { seed, queue_length })
+}
+
+/// Replace the queue with a radio of the seed track's artist catalog.
+///
+/// Queues the seed followed by a shuffled selection of the tracks whose artist
+/// or album artist matches the seed's, and starts playback at the seed.
+#[tauri::command]
+pub fn queue_start_artist_radio(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<QueueRadioResponse, CommandError> {
+    let seed = rand::random();
+    let (queued, state) = db.transaction(|conn| {
+        let track = find_seed_track(conn, track_id)?;
+        let catalog = radio::get_artist_catalog(conn, track_id)?;
+        let queued = queue::replace_with_radio(
+            conn,
+            &track.filepath,
+            &catalog,
+            seed,
+            radio::RADIO_MAX_TRACKS,
+        )?;
+        Ok((queued, queue::get_queue_state(conn)?))
+    })?;
+
+    emit_queue_replaced(&app, queued, state);
+
+    Ok(QueueRadioResponse {
+        queued,
+        unmatched: 0,
+        source: "artist".to_string(),
+    })
+}
+
+/// Replace the queue with a radio of tracks similar to the seed track.
+///
+/// When Last.fm is connected, similar tracks and artists are looked up there
+/// and matched against the library; suggestions with no local match are
+/// counted in `unmatched`. If Last.fm is unavailable or nothing matched, the
+/// radio falls back to tracks sharing the seed's genre.
+#[tauri::command]
+pub async fn queue_start_similar_radio(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<QueueRadioResponse, CommandError> {
+    let (track, session_key) = db.with_conn(|conn| {
+        Ok((
+            find_seed_track(conn, track_id)?,
+            settings::get_setting(conn, "lastfm_session_key")?,
+        ))
+    })?;
+
+    let connected = session_key.is_some_and(|key| !key.is_empty());
+    let suggestions = match track.artist.as_deref() {
+        Some(artist) if connected && !artist.is_empty() => {
+            fetch_similar_suggestions(artist, track.title.as_deref()).await
+        }
+        _ => None,
+    };
+
+    let seed = rand::random();
+    let (response, state) = db.transaction(|conn| {
+        let matches = match &suggestions {
+            Some(suggestions) => radio::match_similar(
+                conn,
+                track_id,
+                suggestions,
+                radio::SIMILAR_TRACKS_PER_ARTIST,
+            )?,
+            None => SimilarMatches::default(),
+        };
+        let (candidates, source) = if matches.filepaths.is_empty() {
+            (radio::get_genre_filepaths(conn, track_id)?, "genre")
+        } else {
+            (matches.filepaths, "similar")
+        };
+
+        let queued = queue::replace_with_radio(
+            conn,
+            &track.filepath,
+            &candidates,
+            seed,
+            radio::RADIO_MAX_TRACKS,
+        )?;
+        let response = QueueRadioResponse {
+            queued,
+            unmatched: matches.unmatched,
+            source: source.to_string(),
+        };
+        Ok((response, queue::get_queue_state(conn)?))
+    })?;
+
+    emit_queue_replaced(&app, response.queued, state);
+
+    Ok(response)
+}
+
+/// Replace the queue with the seed track and the tracks that sound most like it.
+///
+/// Tracks are ranked by distance to the seed in the space of the audio
+/// features the analyze pass stores, so this works without any online
+/// service. The seed's features are computed now if it hasn't been analyzed
+/// yet; tracks that haven't been, and missing tracks, are left out. Up to
+/// `count` tracks follow the seed, nearest first, and playback starts at the
+/// seed.
+#[tauri::command]
+pub async fn queue_play_similar_local(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+    count: usize,
+) -> Result<QueueRadioResponse, CommandError> {
+    let (track, stored) = db.with_conn(|conn| {
+        Ok((
+            find_seed_track(conn, track_id)?,
+            features::get_track_features(conn, track_id)?,
+        ))
+    })?;
+
+    let seed_features = match stored {
+        Some(stored) => stored,
+        None => {
+            let filepath = track.filepath.clone();
+            let extracted = tauri::async_runtime::spawn_blocking(move || {
+                extract_features(Path::new(&filepath))
+            })
+            .await?
+            .ok_or_else(|| {
+                let message = format!("Could not analyze {}", track.filepath);
+                CommandError::new(CommandError::DECODE_ERROR, message)
+            })?;
+            db.with_conn(|conn| features::set_track_features(conn, track_id, &extracted))?;
+            extracted
+        }
+    };
+
+    let count = count.min(radio::RADIO_MAX_TRACKS);
+    let (queued, state) = db.transaction(|conn| {
+        let library = features::get_all_track_features(conn)?;
+        let mut track_ids = vec![track_id];
+        track_ids.extend(rank_similar(track_id, &seed_features, &library, count));
+
+        queue::clear_queue(conn)?;
+        let queued = queue::add_to_queue_from(conn, &track_ids, None, Some(queue::SOURCE_RADIO))?;
+        queue::set_current_index(conn, 0)?;
+        Ok((queued, queue::get_queue_state(conn)?))
+    })?;
+
+    emit_queue_replaced(&app, queued, state);
+
+    Ok(QueueRadioResponse {
+        queued,
+        unmatched: 0,
+        source: "features".to_string(),
+    })
+}
+
+/// Look up a radio seed track, failing if it isn't in the library
+fn find_seed_track(conn: &rusqlite::Connection, track_id: i64) -> Result<Track, DbError> {
+    library::get_track_by_id(conn, track_id)?
+        .ok_or_else(|| DbError::NotFound(format!("Track with id {} not found", track_id)))
+}
+
+/// Fetch similar tracks and artists from Last.fm
+///
+/// Returns `None` when Last.fm isn't configured or neither lookup succeeded,
+/// so the caller can fall back to a local selection.
+async fn fetch_similar_suggestions(
+    artist: &str,
+    title: Option<&str>,
+) -> Option<Vec<SimilarSuggestion>> {
+    let client = LastFmClient::new();
+    if !client.is_configured() {
+        return None;
+    }
+
+    let mut suggestions = Vec::new();
+    let mut any_succeeded = false;
+
+    if let Some(title) = title {
+        match client.get_similar_tracks(artist, title, 50).await {
+            Ok(tracks) => {
+                any_succeeded = true;
+                suggestions.extend(tracks.into_iter().map(|t| SimilarSuggestion {
+                    artist: t.artist.name().to_string(),
+                    title: Some(t.name),
+                }));
+            }
+            Err(e) => warn!("Radio track.getSimilar failed: {}", e),
+        }
+    }
+
+    match client.get_similar_artists(artist, 20).await {
+        Ok(artists) => {
+            any_succeeded = true;
+            suggestions.extend(artists.into_iter().map(|a| SimilarSuggestion {
+                artist: a.name,
+                title: None,
+            }));
+        }
+        Err(e) => warn!("Radio artist.getSimilar failed: {}", e),
+    }
+
+    any_succeeded.then_some(suggestions)
+}
+
+/// Queue a folder's audio files in album order and play the first one.
+///
+/// Files not yet in the library are scanned into it first, so a folder that's
+/// only partly in the library plays in full. Tracks are ordered by disc and
+/// track number, then filename, and keep that order even with shuffle on.
+/// With `replace` (the default) the folder replaces the queue; otherwise it's
+/// appended and playback jumps to its first track.
+#[tauri::command]
+pub async fn queue_play_folder(
+    app: AppHandle,
+    db: State<'_, Database>,
+    audio: State<'_, AudioState>,
+    playback: State<'_, PlaybackManager>,
+    path: String,
+    recursive: Option<bool>,
+    replace: Option<bool>,
+) -> Result<QueuePlayFolderResponse, CommandError> {
+    let recursive = recursive.unwrap_or(true);
+    let replace = replace.unwrap_or(true);
+
+    let scan_db = db.inner().clone();
+    let scan_path = path.clone();
+    let folder = tauri::async_runtime::spawn_blocking(move || {
+        folder::resolve_folder(&scan_db, &scan_path, recursive)
+    })
+    .await??;
+
+    if folder.library_changed {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::added(vec![]));
+    }
+    let Some(first) = folder.tracks.first() else {
+        return Err(CommandError::not_found(format!(
+            "No playable audio files in {}",
+            path
+        )));
+    };
+
+    let track_ids: Vec<i64> = folder.tracks.iter().map(|t| t.id).collect();
+    let source = format!("folder:{}", path);
+    let (start, queued, state) = db.transaction(|conn| {
+        if replace {
+            queue::clear_queue(conn)?;
+        }
+        let start = queue::get_queue_length(conn)?;
+        let queued = queue::add_to_queue_from(conn, &track_ids, None, Some(&source))?;
+        queue::set_current_index(conn, start)?;
+        Ok((start, queued, queue::get_queue_state(conn)?))
+    })?;
+    let queue_length = start + queued;
+
+    if replace {
+        emit_queue_replaced(&app, queue_length, state);
+    } else {
+        let positions: Vec<i64> = (start..queue_length).collect();
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::added(positions, queue_length));
+        let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+            state.current_index,
+            state.shuffle_enabled,
+            state.loop_mode,
+        ));
+    }
+
+    let mut session = playback.lock();
+    play_track(&app, &db, &audio, &mut session, first.id)?;
+
+    Ok(QueuePlayFolderResponse {
+        queued,
         queue_length,
+        skipped: folder.skipped,
     })
 }
 
+/// Emit the events for a queue that was replaced and restarted at index 0
+fn emit_queue_replaced(app: &AppHandle, queue_length: i64, state: QueueState) {
+    let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+}
+
+/// Regenerate only the unplayed tail of the queue after it changed mid-shuffle,
+/// reusing the active seed so already-played entries keep their positions.
+///
+/// Inserts at an explicit position (e.g. "play next") skip this so the
+/// placement the user chose is preserved. Returns whether the tail was
+/// regenerated.
+fn regenerate_shuffled_tail(conn: &rusqlite::Connection) -> DbResult<bool> {
+    let state = queue::get_queue_state(conn)?;
+
+    if let (true, Some(seed)) = (state.shuffle_enabled, state.shuffle_seed) {
+        queue::reshuffle_unplayed(conn, seed)?;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Event for `added` tracks inserted at `position`, or appended without one
+///
+/// A regenerated shuffled tail moves every unplayed entry, so it is reported
+/// as a shuffle rather than as the added positions.
+fn added_event(
+    added: i64,
+    position: Option<i64>,
+    reshuffled: bool,
+    queue_length: i64,
+) -> QueueUpdatedEvent {
+    if reshuffled {
+        return QueueUpdatedEvent::shuffled(queue_length);
+    }
+    let start_pos = position.unwrap_or(queue_length - added);
+    QueueUpdatedEvent::added((start_pos..start_pos + added).collect(), queue_length)
+}
+
+/// Get what the queue is playing from, for "Playing from ..." labels
+///
+/// The current item's source wins; otherwise the source most items share.
+/// `source` is `None` when nothing in the queue has one.
+#[tauri::command]
+pub fn queue_get_context(db: State<'_, Database>) -> Result<QueueContext, CommandError> {
+    db.with_conn(queue::get_queue_context)
+        .map_err(CommandError::from)
+}
+
 /// Get queue playback state
 #[tauri::command]
-pub fn queue_get_playback_state(db: State<'_, Database>) -> Result<QueueState, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    let state: QueueState = queue::get_queue_state(&conn).map_err(|e| e.to_string())?;
+pub fn queue_get_playback_state(db: State<'_, Database>) -> Result<QueueState, CommandError> {
+    let conn = db.conn()?;
+    let state: QueueState = queue::get_queue_state(&conn)?;
     Ok(state)
 }
 
@@ -238,12 +812,12 @@ pub fn queue_set_current_index(
     app: AppHandle,
     db: State<'_, Database>,
     index: i64,
-) -> Result<(), String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    queue::set_current_index(&conn, index).map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    queue::set_current_index(&conn, index)?;
 
     // Emit state changed event
-    let state = queue::get_queue_state(&conn).map_err(|e| e.to_string())?;
+    let state = queue::get_queue_state(&conn)?;
     let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
         state.current_index,
         state.shuffle_enabled,
@@ -253,18 +827,141 @@ pub fn queue_set_current_index(
     Ok(())
 }
 
+/// Make a queued track current by track ID and return its new index.
+///
+/// When the track is queued more than once, the nearest occurrence after the
+/// current index is chosen, wrapping around to the start of the queue.
+#[tauri::command]
+pub fn queue_jump_to_track(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<i64, CommandError> {
+    let conn = db.conn()?;
+
+    let track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track {} not found", track_id)))?;
+
+    let position = queue::find_next_queue_position(&conn, &track.filepath)?.ok_or_else(|| {
+        CommandError::invalid_input(format!("Track {} is not in the queue", track_id))
+    })?;
+
+    queue::set_current_index(&conn, position)?;
+
+    // Emit state changed event
+    let state = queue::get_queue_state(&conn)?;
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(position)
+}
+
+/// Check whether a track is in the queue by track ID
+#[tauri::command]
+pub fn queue_contains(db: State<'_, Database>, track_id: i64) -> Result<bool, CommandError> {
+    let conn = db.conn()?;
+
+    let Some(track) = library::get_track_by_id(&conn, track_id)? else {
+        return Ok(false);
+    };
+
+    let position = queue::find_queue_position(&conn, &track.filepath)?;
+    Ok(position.is_some())
+}
+
+/// Preview the tracks that play next, in effective play order
+///
+/// Accounts for shuffle and loop mode without changing queue state. Returns
+/// fewer than `count` items when loop is off and the queue is nearly done.
+#[tauri::command]
+pub fn queue_peek_upcoming(
+    db: State<'_, Database>,
+    count: usize,
+) -> Result<Vec<QueueItem>, CommandError> {
+    let count = count.min(MAX_PEEK_UPCOMING);
+    let conn = db.conn()?;
+    queue::peek_upcoming(&conn, count).map_err(CommandError::from)
+}
+
+/// Get the track that plays next, without changing queue state
+///
+/// `None` when nothing is known to play next: loop is off and the queue is
+/// done, or a shuffled loop is about to start a reshuffled cycle.
+#[tauri::command]
+pub fn queue_peek_next(db: State<'_, Database>) -> Result<Option<Track>, CommandError> {
+    db.with_conn(queue::peek_next).map_err(CommandError::from)
+}
+
+/// Get the track "previous" goes to, without changing queue state
+#[tauri::command]
+pub fn queue_peek_previous(db: State<'_, Database>) -> Result<Option<Track>, CommandError> {
+    db.with_conn(queue::peek_previous)
+        .map_err(CommandError::from)
+}
+
+/// Get item counts and durations for the queue
+///
+/// Remaining time covers the current track and everything after it in
+/// effective play order. Tracks with no known duration count as zero and are
+/// reported in `unknown_durations`.
+#[tauri::command]
+pub fn queue_get_totals(db: State<'_, Database>) -> Result<QueueTotals, CommandError> {
+    let conn = db.conn()?;
+    queue::get_queue_totals(&conn).map_err(CommandError::from)
+}
+
+/// Get the effective play order while shuffle is on
+///
+/// Lists queue positions in play order with the current item's index in
+/// that order, plus the seed and shuffle mode. The order is empty when
+/// shuffle is off, meaning the queue plays in queue order.
+#[tauri::command]
+pub fn queue_get_shuffle_order(db: State<'_, Database>) -> Result<ShuffleOrder, CommandError> {
+    let conn = db.conn()?;
+    queue::get_shuffle_order(&conn).map_err(CommandError::from)
+}
+
 /// Set shuffle enabled in queue playback state
 #[tauri::command]
 pub fn queue_set_shuffle(
     app: AppHandle,
     db: State<'_, Database>,
     enabled: bool,
-) -> Result<(), String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    queue::set_shuffle_enabled(&conn, enabled).map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    queue::set_shuffle_enabled(&conn, enabled)?;
 
     // Emit state changed event
-    let state = queue::get_queue_state(&conn).map_err(|e| e.to_string())?;
+    let state = queue::get_queue_state(&conn)?;
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(())
+}
+
+/// Set consume mode in queue playback state
+///
+/// While consume is on, each entry the backend advances past is removed from
+/// the queue. It takes precedence over loop "all", so a consumed queue ends
+/// instead of wrapping. Ended tracks have already been counted in the play
+/// history, so removing them loses no record.
+#[tauri::command]
+pub fn queue_set_consume(
+    app: AppHandle,
+    db: State<'_, Database>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    queue::set_consume_enabled(&conn, enabled)?;
+
+    // Emit state changed event
+    let state = queue::get_queue_state(&conn)?;
     let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
         state.current_index,
         state.shuffle_enabled,
@@ -275,17 +972,54 @@ pub fn queue_set_shuffle(
 }
 
 /// Set loop mode in queue playback state
+///
+/// `mode` must be "none", "all" or "one"; anything else is rejected and the
+/// current mode is kept.
 #[tauri::command]
 pub fn queue_set_loop(
     app: AppHandle,
     db: State<'_, Database>,
     mode: String,
-) -> Result<(), String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    queue::set_loop_mode(&conn, &mode).map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    queue::set_loop_mode(&conn, &mode)?;
 
     // Emit state changed event
-    let state = queue::get_queue_state(&conn).map_err(|e| e.to_string())?;
+    let state = queue::get_queue_state(&conn)?;
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(())
+}
+
+/// Set shuffle mode in queue playback state
+///
+/// `mode` must be "tracks" or "albums". While shuffle is on, the unplayed
+/// part of the queue is reshuffled in the new mode straight away, reusing the
+/// active seed when there is one.
+#[tauri::command]
+pub fn queue_set_shuffle_mode(
+    app: AppHandle,
+    db: State<'_, Database>,
+    mode: String,
+) -> Result<(), CommandError> {
+    let (state, reshuffled) = db.transaction(|conn| {
+        queue::set_shuffle_mode(conn, &mode)?;
+        let state = queue::get_queue_state(conn)?;
+        if !state.shuffle_enabled {
+            return Ok((state, None));
+        }
+        let seed = state.shuffle_seed.unwrap_or_else(rand::random);
+        let queue_length = queue::reshuffle_unplayed(conn, seed)?;
+        Ok((state, Some(queue_length)))
+    })?;
+
+    if let Some(queue_length) = reshuffled {
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
+    }
     let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
         state.current_index,
         state.shuffle_enabled,
@@ -298,6 +1032,14 @@ pub fn queue_set_loop(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::TrackMetadata;
+    use crate::events::{
+        ExistenceProgressEvent, FavoriteListsUpdatedEvent, FavoritesUpdatedEvent,
+        ImportProgressEvent, PlaybackStateChangedEvent, PlaybackTrackEndedEvent,
+        PlaylistsUpdatedEvent, QueueSkippedMissingEvent, ScanCompleteEvent, ScanProgressEvent,
+        SettingsUpdatedEvent, VerifyProgressEvent,
+    };
+    use std::cell::RefCell;
 
     // ==================== QueueResponse Tests ====================
 
@@ -435,6 +1177,36 @@ mod tests {
         assert_eq!(response.tracks.len(), cloned.tracks.len());
     }
 
+    // ==================== QueueReshuffleResponse Tests ====================
+
+    #[test]
+    fn test_queue_reshuffle_response_serialization() {
+        let response = QueueReshuffleResponse {
+            seed: 42,
+            queue_length: 12,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"seed\":42"));
+        assert!(json.contains("\"queue_length\":12"));
+    }
+
+    // ==================== QueueRadioResponse Tests ====================
+
+    #[test]
+    fn test_queue_radio_response_serialization() {
+        let response = QueueRadioResponse {
+            queued: 25,
+            unmatched: 7,
+            source: "similar".to_string(),
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"queued\":25"));
+        assert!(json.contains("\"unmatched\":7"));
+        assert!(json.contains("\"source\":\"similar\""));
+    }
+
     // ==================== QueueOperationResponse Tests ====================
 
     #[test]
@@ -556,4 +1328,129 @@ mod tests {
         // (validation should happen at the command level, not response level)
         assert_eq!(response.added, 3);
     }
+
+    // ==================== queue_replace Tests ====================
+
+    /// Records the queue events it is asked to emit
+    #[derive(Default)]
+    struct RecordingEmitter {
+        queue_updated: RefCell<Vec<QueueUpdatedEvent>>,
+        queue_state_changed: RefCell<Vec<QueueStateChangedEvent>>,
+        other: RefCell<usize>,
+    }
+
+    impl RecordingEmitter {
+        fn other(&self) -> Result<(), String> {
+            *self.other.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    impl EventEmitter for RecordingEmitter {
+        fn emit_library_updated(&self, _: LibraryUpdatedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_scan_progress(&self, _: ScanProgressEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_scan_complete(&self, _: ScanCompleteEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_verify_progress(&self, _: VerifyProgressEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_existence_progress(&self, _: ExistenceProgressEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_import_progress(&self, _: ImportProgressEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_queue_updated(&self, event: QueueUpdatedEvent) -> Result<(), String> {
+            self.queue_updated.borrow_mut().push(event);
+            Ok(())
+        }
+        fn emit_queue_skipped_missing(&self, _: QueueSkippedMissingEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_queue_state_changed(&self, event: QueueStateChangedEvent) -> Result<(), String> {
+            self.queue_state_changed.borrow_mut().push(event);
+            Ok(())
+        }
+        fn emit_playback_state_changed(&self, _: PlaybackStateChangedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_playback_track_ended(&self, _: PlaybackTrackEndedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_favorites_updated(&self, _: FavoritesUpdatedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_favorite_lists_updated(&self, _: FavoriteListsUpdatedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_playlists_updated(&self, _: PlaylistsUpdatedEvent) -> Result<(), String> {
+            self.other()
+        }
+        fn emit_settings_updated(&self, _: SettingsUpdatedEvent) -> Result<(), String> {
+            self.other()
+        }
+    }
+
+    fn add_tracks(db: &Database, count: usize) -> Vec<i64> {
+        db.with_conn(|conn| {
+            (0..count)
+                .map(|i| {
+                    let path = format!("/music/{}.flac", i);
+                    library::add_track(conn, &path, &TrackMetadata::default())
+                })
+                .collect()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replace_queue_emits_one_batched_event() {
+        let db = Database::new_in_memory().unwrap();
+        let ids = add_tracks(&db, 5);
+        db.with_conn(|conn| queue::add_to_queue(conn, &ids[..2], None))
+            .unwrap();
+        let emitter = RecordingEmitter::default();
+
+        let requested = [ids[2], 404, ids[3], ids[4]];
+        let response = replace_queue(&emitter, &db, &requested, 2, Some("album:Hex")).unwrap();
+        assert_eq!(response.queue_length, 3);
+        assert_eq!(response.current_index, 1);
+        assert_eq!(response.skipped, [404]);
+
+        let updated = emitter.queue_updated.borrow();
+        assert_eq!(updated.len(), 1);
+        assert_eq!(updated[0].action, "replaced");
+        assert_eq!(updated[0].queue_length, 3);
+        let state_changed = emitter.queue_state_changed.borrow();
+        assert_eq!(state_changed.len(), 1);
+        assert_eq!(state_changed[0].current_index, 1);
+        assert_eq!(*emitter.other.borrow(), 0);
+
+        let queued: Vec<i64> = db
+            .with_conn(queue::get_queue)
+            .unwrap()
+            .iter()
+            .map(|item| item.track.id)
+            .collect();
+        assert_eq!(queued, [ids[2], ids[3], ids[4]]);
+    }
+
+    #[test]
+    fn test_replace_queue_failure_is_atomic() {
+        let db = Database::new_in_memory().unwrap();
+        let ids = add_tracks(&db, 3);
+        db.with_conn(|conn| queue::add_to_queue(conn, &ids, None))
+            .unwrap();
+        let emitter = RecordingEmitter::default();
+
+        assert!(replace_queue(&emitter, &db, &ids[..1], 5, None).is_err());
+        assert_eq!(db.with_conn(queue::get_queue_length).unwrap(), 3);
+        assert!(emitter.queue_updated.borrow().is_empty());
+        assert!(emitter.queue_state_changed.borrow().is_empty());
+    }
 }
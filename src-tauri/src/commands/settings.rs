@@ -5,8 +5,13 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
 use std::collections::HashMap;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_store::StoreExt;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::background::{default_max_threads, BackgroundExecutor, BACKGROUND_THREADS_SETTING};
+use crate::library::existence::VERIFY_ON_STARTUP_SETTING;
+use crate::logging::{self, Logging, DEFAULT_LOG_LEVEL, LOG_LEVEL_SETTING};
 
 /// Settings store filename
 const STORE_NAME: &str = "settings.json";
@@ -20,9 +25,69 @@ fn get_defaults() -> HashMap<&'static str, JsonValue> {
     defaults.insert("theme", json!("dark"));
     defaults.insert("sidebar_width", json!(250));
     defaults.insert("queue_panel_height", json!(300));
+    defaults.insert(LOG_LEVEL_SETTING, json!(DEFAULT_LOG_LEVEL));
+    defaults.insert(VERIFY_ON_STARTUP_SETTING, json!(false));
     defaults
 }
 
+/// Whether the library existence check runs on launch
+pub fn stored_verify_on_startup(app: &AppHandle) -> bool {
+    app.store(STORE_NAME)
+        .ok()
+        .and_then(|store| store.get(VERIFY_ON_STARTUP_SETTING))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// Log level from the settings store, the default if unset or invalid
+pub fn stored_log_level(app: &AppHandle) -> LevelFilter {
+    app.store(STORE_NAME)
+        .ok()
+        .and_then(|store| store.get(LOG_LEVEL_SETTING))
+        .and_then(|value| value.as_str().and_then(logging::parse_level))
+        .unwrap_or(LevelFilter::INFO)
+}
+
+/// Background thread budget from the settings store, the default if unset
+pub fn stored_background_threads(app: &AppHandle) -> usize {
+    app.store(STORE_NAME)
+        .ok()
+        .and_then(|store| store.get(BACKGROUND_THREADS_SETTING))
+        .and_then(|value| value.as_u64())
+        .filter(|&threads| threads > 0)
+        .map_or_else(default_max_threads, |threads| threads as usize)
+}
+
+/// Validate a `background_threads` value and apply it to the executor
+///
+/// `null` goes back to the default budget.
+fn apply_background_threads(app: &AppHandle, value: &JsonValue) -> Result<(), String> {
+    let threads = match value {
+        JsonValue::Null => default_max_threads(),
+        _ => value
+            .as_u64()
+            .filter(|&threads| threads > 0)
+            .map(|threads| threads as usize)
+            .ok_or_else(|| format!("Invalid background thread count: {}", value))?,
+    };
+    if let Some(executor) = app.try_state::<BackgroundExecutor>() {
+        executor.set_max_threads(threads);
+    }
+    Ok(())
+}
+
+/// Validate a `log_level` value and apply it to the running logger
+fn apply_log_level(app: &AppHandle, value: &JsonValue) -> Result<(), String> {
+    let level = value
+        .as_str()
+        .and_then(logging::parse_level)
+        .ok_or_else(|| format!("Invalid log level: {}", value))?;
+    match app.try_state::<Logging>() {
+        Some(logging) => logging.set_level(level),
+        None => Ok(()),
+    }
+}
+
 /// All settings response
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AllSettingsResponse {
@@ -118,6 +183,13 @@ pub fn settings_set(app: AppHandle, key: String, value: JsonValue) -> Result<Set
         .store(STORE_NAME)
         .map_err(|e| format!("Failed to open settings store: {}", e))?;
 
+    if key == LOG_LEVEL_SETTING {
+        apply_log_level(&app, &value)?;
+    }
+    if key == BACKGROUND_THREADS_SETTING {
+        apply_background_threads(&app, &value)?;
+    }
+
     store.set(key.clone(), value.clone());
     store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
 
@@ -233,6 +305,8 @@ pub fn settings_reset(app: AppHandle) -> Result<AllSettingsResponse, String> {
     }
 
     store.save().map_err(|e| format!("Failed to save settings: {}", e))?;
+    apply_log_level(&app, &json!(DEFAULT_LOG_LEVEL))?;
+    apply_background_threads(&app, &JsonValue::Null)?;
 
     // Emit reset event
     let _ = app.emit("settings://reset", ());
@@ -257,12 +331,14 @@ mod tests {
         assert_eq!(defaults.get("theme"), Some(&json!("dark")));
         assert_eq!(defaults.get("sidebar_width"), Some(&json!(250)));
         assert_eq!(defaults.get("queue_panel_height"), Some(&json!(300)));
+        assert_eq!(defaults.get("log_level"), Some(&json!("info")));
+        assert_eq!(defaults.get("verify_library_on_startup"), Some(&json!(false)));
     }
 
     #[test]
     fn test_defaults_count() {
         let defaults = get_defaults();
-        assert_eq!(defaults.len(), 6);
+        assert_eq!(defaults.len(), 8);
     }
 
     #[test]
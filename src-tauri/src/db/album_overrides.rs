@@ -0,0 +1,174 @@
+//! Album metadata override database operations.
+//!
+//! An override corrects how an album is shown (its name, album artist, year,
+//! or cover) without editing its files or its tracks' rows. Overrides are
+//! keyed by `artwork_misses::track_album_key`, so they apply to the album as
+//! the tags group it, and fields left unset fall back to the tags.
+
+use rusqlite::{Connection, Row, params};
+use std::collections::HashMap;
+
+use crate::db::{AlbumOverride, DbError, DbResult};
+
+fn row_to_override(row: &Row) -> rusqlite::Result<AlbumOverride> {
+    Ok(AlbumOverride {
+        album_key: row.get("album_key")?,
+        album: row.get("album")?,
+        album_artist: row.get("album_artist")?,
+        year: row.get("year")?,
+        artwork_path: row.get("artwork_path")?,
+    })
+}
+
+/// Trimmed value, `None` if it is blank
+fn non_blank(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Set the override for an album, replacing any existing one
+///
+/// Blank fields are stored as unset. An override with every field unset is
+/// rejected; use `clear_album_override` to remove one.
+pub fn set_album_override(conn: &Connection, value: &AlbumOverride) -> DbResult<AlbumOverride> {
+    let value = AlbumOverride {
+        album_key: value.album_key.clone(),
+        album: non_blank(&value.album),
+        album_artist: non_blank(&value.album_artist),
+        year: non_blank(&value.year),
+        artwork_path: non_blank(&value.artwork_path),
+    };
+    if value.album_key.is_empty() {
+        return Err(DbError::Constraint(
+            "Album key must not be empty".to_string(),
+        ));
+    }
+    if value.album.is_none()
+        && value.album_artist.is_none()
+        && value.year.is_none()
+        && value.artwork_path.is_none()
+    {
+        return Err(DbError::Constraint(
+            "Album override must set at least one field".to_string(),
+        ));
+    }
+
+    conn.execute(
+        "INSERT OR REPLACE INTO album_overrides
+         (album_key, album, album_artist, year, artwork_path)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            value.album_key,
+            value.album,
+            value.album_artist,
+            value.year,
+            value.artwork_path,
+        ],
+    )?;
+    Ok(value)
+}
+
+/// Get the override for an album
+pub fn get_album_override(conn: &Connection, album_key: &str) -> DbResult<Option<AlbumOverride>> {
+    match conn.query_row(
+        "SELECT * FROM album_overrides WHERE album_key = ?",
+        [album_key],
+        row_to_override,
+    ) {
+        Ok(value) => Ok(Some(value)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get every override, keyed by album key
+pub fn get_album_overrides(conn: &Connection) -> DbResult<HashMap<String, AlbumOverride>> {
+    let mut stmt = conn.prepare("SELECT * FROM album_overrides")?;
+    let overrides = stmt
+        .query_map([], row_to_override)?
+        .map(|r| r.map(|o| (o.album_key.clone(), o)))
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(overrides)
+}
+
+/// Remove the override for an album, returning whether there was one
+pub fn clear_album_override(conn: &Connection, album_key: &str) -> DbResult<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM album_overrides WHERE album_key = ?",
+        [album_key],
+    )?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn with_year(album_key: &str, year: &str) -> AlbumOverride {
+        AlbumOverride {
+            album_key: album_key.to_string(),
+            album: None,
+            album_artist: None,
+            year: Some(year.to_string()),
+            artwork_path: None,
+        }
+    }
+
+    #[test]
+    fn test_set_get_and_clear_override() {
+        let conn = setup_test_db();
+        assert!(get_album_override(&conn, "low\u{1f}hex").unwrap().is_none());
+
+        let stored = set_album_override(
+            &conn,
+            &AlbumOverride {
+                album: Some(" Hex (Remastered) ".to_string()),
+                album_artist: Some("  ".to_string()),
+                ..with_year("low\u{1f}hex", "1994")
+            },
+        )
+        .unwrap();
+        assert_eq!(stored.album.as_deref(), Some("Hex (Remastered)"));
+        assert_eq!(stored.album_artist, None);
+        assert_eq!(
+            get_album_override(&conn, "low\u{1f}hex").unwrap(),
+            Some(stored)
+        );
+
+        // Setting again replaces the whole override
+        set_album_override(&conn, &with_year("low\u{1f}hex", "1995")).unwrap();
+        let replaced = get_album_override(&conn, "low\u{1f}hex").unwrap().unwrap();
+        assert_eq!(replaced.album, None);
+        assert_eq!(replaced.year.as_deref(), Some("1995"));
+        assert_eq!(get_album_overrides(&conn).unwrap().len(), 1);
+
+        assert!(clear_album_override(&conn, "low\u{1f}hex").unwrap());
+        assert!(!clear_album_override(&conn, "low\u{1f}hex").unwrap());
+        assert!(get_album_overrides(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_empty_override_rejected() {
+        let conn = setup_test_db();
+        let blank = with_year("low\u{1f}hex", " ");
+        assert!(matches!(
+            set_album_override(&conn, &blank),
+            Err(DbError::Constraint(_))
+        ));
+        assert!(matches!(
+            set_album_override(&conn, &with_year("", "1994")),
+            Err(DbError::Constraint(_))
+        ));
+    }
+}
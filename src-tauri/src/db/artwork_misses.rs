@@ -0,0 +1,134 @@
+//! Negative cache for online artwork lookups.
+//!
+//! Many albums simply have no cover on Last.fm or the Cover Art Archive.
+//! A lookup that comes back empty is recorded here with a time to retry
+//! after, and lookups for that album are skipped until then. Times are Unix
+//! seconds.
+
+use rusqlite::{Connection, params};
+use std::time::Duration;
+
+use crate::db::DbResult;
+
+/// How long an album stays skipped after a lookup found no artwork
+pub const DEFAULT_MISS_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Cache key for an album, ignoring case and surrounding whitespace
+pub fn album_key(artist: &str, album: &str) -> String {
+    format!(
+        "{}\u{1f}{}",
+        artist.trim().to_lowercase(),
+        album.trim().to_lowercase()
+    )
+}
+
+/// Album key of a track: its album artist, or else its artist, with the album
+///
+/// Going by album artist keeps a compilation together when its tracks share
+/// one, such as "Various Artists".
+pub fn track_album_key(album_artist: Option<&str>, artist: Option<&str>, album: &str) -> String {
+    let artist = album_artist
+        .filter(|a| !a.trim().is_empty())
+        .or(artist)
+        .unwrap_or("");
+    album_key(artist, album)
+}
+
+/// Record that a lookup for `album_key` found nothing, skipping it for `ttl`
+pub fn record_miss(conn: &Connection, album_key: &str, now: i64, ttl: Duration) -> DbResult<()> {
+    let retry_after = now.saturating_add(ttl.as_secs() as i64);
+    conn.execute(
+        "INSERT OR REPLACE INTO artwork_misses (album_key, missed_at, retry_after)
+         VALUES (?1, ?2, ?3)",
+        params![album_key, now, retry_after],
+    )?;
+    Ok(())
+}
+
+/// When `album_key` may be looked up again, or `None` if it may be now
+pub fn get_retry_after(conn: &Connection, album_key: &str, now: i64) -> DbResult<Option<i64>> {
+    match conn.query_row(
+        "SELECT retry_after FROM artwork_misses WHERE album_key = ?1 AND retry_after > ?2",
+        params![album_key, now],
+        |row| row.get(0),
+    ) {
+        Ok(retry_after) => Ok(Some(retry_after)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Forget the miss for one album. Returns whether there was one.
+pub fn clear_miss(conn: &Connection, album_key: &str) -> DbResult<bool> {
+    let removed = conn.execute(
+        "DELETE FROM artwork_misses WHERE album_key = ?",
+        [album_key],
+    )?;
+    Ok(removed > 0)
+}
+
+/// Forget every miss so all albums are looked up again. Returns the count.
+pub fn clear_misses(conn: &Connection) -> DbResult<usize> {
+    Ok(conn.execute("DELETE FROM artwork_misses", [])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_album_key_normalizes() {
+        assert_eq!(
+            album_key(" Burial ", "Untrue"),
+            album_key("burial", "UNTRUE")
+        );
+        assert_ne!(album_key("a", "bc"), album_key("ab", "c"));
+        assert_eq!(
+            track_album_key(Some("Various Artists"), Some("Burial"), "Kompakt 100"),
+            track_album_key(Some("various artists"), Some("Kode9"), "Kompakt 100")
+        );
+        assert_eq!(
+            track_album_key(Some(" "), Some("Burial"), "Untrue"),
+            album_key("Burial", "Untrue")
+        );
+    }
+
+    #[test]
+    fn test_miss_expires_after_ttl() {
+        let conn = setup();
+        let key = album_key("Burial", "Untrue");
+        let ttl = Duration::from_secs(100);
+
+        assert_eq!(get_retry_after(&conn, &key, 1000).unwrap(), None);
+        record_miss(&conn, &key, 1000, ttl).unwrap();
+        assert_eq!(get_retry_after(&conn, &key, 1000).unwrap(), Some(1100));
+        assert_eq!(get_retry_after(&conn, &key, 1099).unwrap(), Some(1100));
+        assert_eq!(get_retry_after(&conn, &key, 1100).unwrap(), None);
+
+        // A new miss pushes the retry time out again
+        record_miss(&conn, &key, 1100, ttl).unwrap();
+        assert_eq!(get_retry_after(&conn, &key, 1150).unwrap(), Some(1200));
+    }
+
+    #[test]
+    fn test_clear_misses() {
+        let conn = setup();
+        let ttl = Duration::from_secs(100);
+        record_miss(&conn, "fresh", 500, ttl).unwrap();
+        record_miss(&conn, "other", 500, ttl).unwrap();
+
+        assert!(clear_miss(&conn, "fresh").unwrap());
+        assert!(!clear_miss(&conn, "fresh").unwrap());
+        assert_eq!(get_retry_after(&conn, "other", 550).unwrap(), Some(600));
+        assert_eq!(clear_misses(&conn).unwrap(), 1);
+        assert_eq!(get_retry_after(&conn, "other", 550).unwrap(), None);
+    }
+}
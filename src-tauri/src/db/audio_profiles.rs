@@ -0,0 +1,545 @@
+//! Audio profile rules database operations.
+//!
+//! Rules map a folder, genre, or artist to an audio profile (EQ preset and
+//! normalization mode). Rules are evaluated in position order and the first
+//! match wins; tracks matching no rule use the default profile: the one saved
+//! for the active output device, or else the global profile from settings.
+
+use rusqlite::{Connection, Row, params};
+
+use crate::db::{
+    AudioProfile, AudioProfileRule, DbResult, ProfileCondition, ResolvedProfile, settings,
+};
+
+/// Settings key for the global EQ preset
+pub const GLOBAL_EQ_PRESET_SETTING: &str = "audio_profile_eq_preset";
+
+/// Settings key for the global normalization mode
+pub const GLOBAL_NORMALIZATION_SETTING: &str = "audio_profile_normalization";
+
+/// Settings key for the id of the output device playback goes to
+pub const ACTIVE_DEVICE_SETTING: &str = "audio_output_device";
+
+/// Track attributes rules are matched against
+#[derive(Debug, Clone, Default)]
+pub struct ProfileSubject {
+    pub filepath: String,
+    pub genre: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+}
+
+fn row_to_rule(row: &Row) -> rusqlite::Result<AudioProfileRule> {
+    let condition: String = row.get("condition")?;
+    let normalization: String = row.get("normalization")?;
+    Ok(AudioProfileRule {
+        id: row.get("id")?,
+        position: row.get("position")?,
+        // Unknown values can only come from a newer schema; treat them as folder/off
+        condition: condition.parse().unwrap_or(ProfileCondition::Folder),
+        value: row.get("value")?,
+        profile: AudioProfile {
+            eq_preset: row.get("eq_preset")?,
+            normalization: normalization.parse().unwrap_or_default(),
+        },
+    })
+}
+
+/// Get all rules in evaluation order
+pub fn get_rules(conn: &Connection) -> DbResult<Vec<AudioProfileRule>> {
+    let mut stmt = conn.prepare("SELECT * FROM audio_profile_rules ORDER BY position, id")?;
+    let rules = stmt
+        .query_map([], row_to_rule)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(rules)
+}
+
+/// Get a rule by ID
+pub fn get_rule(conn: &Connection, rule_id: i64) -> DbResult<Option<AudioProfileRule>> {
+    match conn.query_row(
+        "SELECT * FROM audio_profile_rules WHERE id = ?",
+        [rule_id],
+        row_to_rule,
+    ) {
+        Ok(rule) => Ok(Some(rule)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Create a rule, or update it when `rule_id` is given
+///
+/// New rules go after every existing rule unless `position` is given.
+/// Returns `None` when `rule_id` doesn't exist.
+pub fn set_rule(
+    conn: &Connection,
+    rule_id: Option<i64>,
+    condition: ProfileCondition,
+    value: &str,
+    profile: &AudioProfile,
+    position: Option<i64>,
+) -> DbResult<Option<AudioProfileRule>> {
+    let Some(rule_id) = rule_id else {
+        let position = match position {
+            Some(position) => position,
+            None => conn.query_row(
+                "SELECT COALESCE(MAX(position), -1) + 1 FROM audio_profile_rules",
+                [],
+                |row| row.get(0),
+            )?,
+        };
+        conn.execute(
+            "INSERT INTO audio_profile_rules (position, condition, value, eq_preset, normalization)
+             VALUES (?, ?, ?, ?, ?)",
+            params![
+                position,
+                condition.as_str(),
+                value,
+                profile.eq_preset,
+                profile.normalization.as_str(),
+            ],
+        )?;
+        return get_rule(conn, conn.last_insert_rowid());
+    };
+
+    conn.execute(
+        "UPDATE audio_profile_rules SET
+            position = COALESCE(?, position),
+            condition = ?,
+            value = ?,
+            eq_preset = ?,
+            normalization = ?
+         WHERE id = ?",
+        params![
+            position,
+            condition.as_str(),
+            value,
+            profile.eq_preset,
+            profile.normalization.as_str(),
+            rule_id,
+        ],
+    )?;
+    get_rule(conn, rule_id)
+}
+
+/// Delete a rule
+pub fn delete_rule(conn: &Connection, rule_id: i64) -> DbResult<bool> {
+    let deleted = conn.execute("DELETE FROM audio_profile_rules WHERE id = ?", [rule_id])?;
+    Ok(deleted > 0)
+}
+
+/// Get the profile used when no rule matches and no device profile applies
+pub fn get_global_profile(conn: &Connection) -> DbResult<AudioProfile> {
+    let default = AudioProfile::default();
+    let eq_preset = settings::get_setting(conn, GLOBAL_EQ_PRESET_SETTING)?
+        .filter(|preset| !preset.is_empty())
+        .unwrap_or(default.eq_preset);
+    let normalization = settings::get_setting(conn, GLOBAL_NORMALIZATION_SETTING)?
+        .and_then(|mode| mode.parse().ok())
+        .unwrap_or(default.normalization);
+    Ok(AudioProfile {
+        eq_preset,
+        normalization,
+    })
+}
+
+/// Set the profile used when no rule matches
+pub fn set_global_profile(conn: &Connection, profile: &AudioProfile) -> DbResult<()> {
+    settings::set_setting(
+        conn,
+        GLOBAL_EQ_PRESET_SETTING,
+        &serde_json::json!(profile.eq_preset),
+    )?;
+    settings::set_setting(
+        conn,
+        GLOBAL_NORMALIZATION_SETTING,
+        &serde_json::json!(profile.normalization.as_str()),
+    )
+}
+
+/// Get the profile saved for an output device, `None` if it has none
+pub fn get_device_profile(conn: &Connection, device_id: &str) -> DbResult<Option<AudioProfile>> {
+    let key = |base| settings::device_setting_key(base, device_id);
+    let Some(eq_preset) = settings::get_setting(conn, &key(GLOBAL_EQ_PRESET_SETTING))?
+        .filter(|preset| !preset.is_empty())
+    else {
+        return Ok(None);
+    };
+    let normalization = settings::get_setting(conn, &key(GLOBAL_NORMALIZATION_SETTING))?
+        .and_then(|mode| mode.parse().ok())
+        .unwrap_or_default();
+    Ok(Some(AudioProfile {
+        eq_preset,
+        normalization,
+    }))
+}
+
+/// Save the profile for an output device
+pub fn set_device_profile(
+    conn: &Connection,
+    device_id: &str,
+    profile: &AudioProfile,
+) -> DbResult<()> {
+    let key = |base| settings::device_setting_key(base, device_id);
+    settings::set_setting(
+        conn,
+        &key(GLOBAL_EQ_PRESET_SETTING),
+        &serde_json::json!(profile.eq_preset),
+    )?;
+    settings::set_setting(
+        conn,
+        &key(GLOBAL_NORMALIZATION_SETTING),
+        &serde_json::json!(profile.normalization.as_str()),
+    )
+}
+
+/// Get the profile used when no rule matches
+///
+/// That is the active output device's saved profile, or the global profile
+/// when no device is active or it has no profile of its own.
+pub fn get_default_profile(conn: &Connection) -> DbResult<AudioProfile> {
+    if let Some(device_id) = settings::get_setting(conn, ACTIVE_DEVICE_SETTING)?
+        && !device_id.is_empty()
+        && let Some(profile) = get_device_profile(conn, &device_id)?
+    {
+        return Ok(profile);
+    }
+    get_global_profile(conn)
+}
+
+/// Whether a rule applies to a track
+pub fn rule_matches(rule: &AudioProfileRule, subject: &ProfileSubject) -> bool {
+    let value = rule.value.trim();
+    if value.is_empty() {
+        return false;
+    }
+
+    match rule.condition {
+        ProfileCondition::Folder => {
+            let normalize = |s: &str| s.replace('\\', "/");
+            let folder = normalize(value);
+            let folder = folder.trim_end_matches('/');
+            let path = normalize(&subject.filepath);
+            // Match whole path components so /music/pod doesn't match /music/podcasts
+            path.strip_prefix(folder)
+                .is_some_and(|rest| rest.starts_with('/'))
+        }
+        ProfileCondition::Genre => subject.genre.as_deref().is_some_and(|genre| {
+            genre
+                .split(';')
+                .any(|g| g.trim().to_lowercase() == value.to_lowercase())
+        }),
+        ProfileCondition::Artist => [&subject.artist, &subject.album_artist]
+            .into_iter()
+            .flatten()
+            .any(|artist| artist.trim().to_lowercase() == value.to_lowercase()),
+    }
+}
+
+/// Pick the profile for a track: the first matching rule, else the global profile
+pub fn select_profile(
+    rules: &[AudioProfileRule],
+    global: &AudioProfile,
+    subject: &ProfileSubject,
+) -> ResolvedProfile {
+    match rules.iter().find(|rule| rule_matches(rule, subject)) {
+        Some(rule) => ResolvedProfile {
+            profile: rule.profile.clone(),
+            rule_id: Some(rule.id),
+        },
+        None => ResolvedProfile {
+            profile: global.clone(),
+            rule_id: None,
+        },
+    }
+}
+
+/// Resolve the profile for a library track
+///
+/// Returns `None` if the track doesn't exist.
+pub fn resolve_profile(conn: &Connection, track_id: i64) -> DbResult<Option<ResolvedProfile>> {
+    let subject = match conn.query_row(
+        "SELECT filepath, genre, artist, album_artist FROM library WHERE id = ?",
+        [track_id],
+        |row| {
+            Ok(ProfileSubject {
+                filepath: row.get(0)?,
+                genre: row.get(1)?,
+                artist: row.get(2)?,
+                album_artist: row.get(3)?,
+            })
+        },
+    ) {
+        Ok(subject) => subject,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let rules = get_rules(conn)?;
+    let default = get_default_profile(conn)?;
+    Ok(Some(select_profile(&rules, &default, &subject)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::add_track;
+    use crate::db::{NormalizationMode, TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn profile(eq_preset: &str, normalization: NormalizationMode) -> AudioProfile {
+        AudioProfile {
+            eq_preset: eq_preset.to_string(),
+            normalization,
+        }
+    }
+
+    fn rule(
+        id: i64,
+        condition: ProfileCondition,
+        value: &str,
+        eq_preset: &str,
+    ) -> AudioProfileRule {
+        AudioProfileRule {
+            id,
+            position: id,
+            condition,
+            value: value.to_string(),
+            profile: profile(eq_preset, NormalizationMode::Track),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches_folder_on_component_boundary() {
+        let podcasts = rule(1, ProfileCondition::Folder, "/music/Podcasts/", "podcast");
+        let subject = |path: &str| ProfileSubject {
+            filepath: path.to_string(),
+            ..Default::default()
+        };
+
+        assert!(rule_matches(
+            &podcasts,
+            &subject("/music/Podcasts/show/ep1.mp3")
+        ));
+        assert!(!rule_matches(
+            &podcasts,
+            &subject("/music/PodcastsOld/ep1.mp3")
+        ));
+        assert!(!rule_matches(
+            &podcasts,
+            &subject("/music/podcasts/ep1.mp3")
+        ));
+
+        // Windows separators are normalized
+        let windows = rule(2, ProfileCondition::Folder, "C:\\Music\\Talk", "podcast");
+        assert!(rule_matches(&windows, &subject("C:\\Music\\Talk\\ep.mp3")));
+    }
+
+    #[test]
+    fn test_rule_matches_genre_and_artist_case_insensitively() {
+        let subject = ProfileSubject {
+            filepath: "/music/a.mp3".to_string(),
+            genre: Some("Rock; Spoken Word".to_string()),
+            artist: Some("Guest".to_string()),
+            album_artist: Some("Various Artists".to_string()),
+        };
+
+        assert!(rule_matches(
+            &rule(1, ProfileCondition::Genre, "spoken word", "x"),
+            &subject
+        ));
+        assert!(!rule_matches(
+            &rule(1, ProfileCondition::Genre, "Spoken", "x"),
+            &subject
+        ));
+        assert!(rule_matches(
+            &rule(1, ProfileCondition::Artist, "guest", "x"),
+            &subject
+        ));
+        assert!(rule_matches(
+            &rule(1, ProfileCondition::Artist, "various artists", "x"),
+            &subject
+        ));
+        assert!(!rule_matches(
+            &rule(1, ProfileCondition::Artist, "", "x"),
+            &subject
+        ));
+    }
+
+    #[test]
+    fn test_select_profile_first_match_wins() {
+        let rules = vec![
+            rule(1, ProfileCondition::Folder, "/music/Podcasts", "podcast"),
+            rule(2, ProfileCondition::Genre, "Podcast", "speech"),
+            rule(3, ProfileCondition::Artist, "Host", "bass-boost"),
+        ];
+        let global = profile("flat", NormalizationMode::Off);
+
+        // Matches rules 2 and 3; the earlier rule wins
+        let subject = ProfileSubject {
+            filepath: "/music/Other/ep.mp3".to_string(),
+            genre: Some("Podcast".to_string()),
+            artist: Some("Host".to_string()),
+            album_artist: None,
+        };
+        let resolved = select_profile(&rules, &global, &subject);
+        assert_eq!(resolved.rule_id, Some(2));
+        assert_eq!(resolved.profile.eq_preset, "speech");
+
+        // Matches all three; the folder rule is first
+        let in_folder = ProfileSubject {
+            filepath: "/music/Podcasts/ep.mp3".to_string(),
+            ..subject.clone()
+        };
+        assert_eq!(select_profile(&rules, &global, &in_folder).rule_id, Some(1));
+
+        // Matches nothing; the global profile applies
+        let music = ProfileSubject {
+            filepath: "/music/Album/01.flac".to_string(),
+            ..Default::default()
+        };
+        let resolved = select_profile(&rules, &global, &music);
+        assert_eq!(resolved.rule_id, None);
+        assert_eq!(resolved.profile, global);
+    }
+
+    #[test]
+    fn test_set_rule_orders_and_updates() {
+        let conn = setup_test_db();
+        let podcast = profile("podcast", NormalizationMode::Track);
+
+        let first = set_rule(&conn, None, ProfileCondition::Genre, "Jazz", &podcast, None)
+            .unwrap()
+            .unwrap();
+        let second = set_rule(
+            &conn,
+            None,
+            ProfileCondition::Artist,
+            "Host",
+            &podcast,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!((first.position, second.position), (0, 1));
+
+        // Moving the second rule ahead of the first changes precedence
+        let moved = set_rule(
+            &conn,
+            Some(second.id),
+            ProfileCondition::Artist,
+            "Host",
+            &profile("speech", NormalizationMode::Album),
+            Some(-1),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(moved.profile.normalization, NormalizationMode::Album);
+
+        let ids: Vec<i64> = get_rules(&conn).unwrap().iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+
+        assert!(
+            set_rule(
+                &conn,
+                Some(9999),
+                ProfileCondition::Genre,
+                "x",
+                &podcast,
+                None
+            )
+            .unwrap()
+            .is_none()
+        );
+        assert!(delete_rule(&conn, first.id).unwrap());
+        assert!(!delete_rule(&conn, first.id).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_profile_falls_back_to_global() {
+        let conn = setup_test_db();
+        let metadata = TrackMetadata {
+            genre: Some("Audiobook".to_string()),
+            ..Default::default()
+        };
+        let book = add_track(&conn, "/books/book.m4b", &metadata).unwrap();
+        let song = add_track(&conn, "/music/song.mp3", &TrackMetadata::default()).unwrap();
+
+        assert_eq!(get_global_profile(&conn).unwrap(), AudioProfile::default());
+        let global = profile("loudness", NormalizationMode::Album);
+        set_global_profile(&conn, &global).unwrap();
+
+        let spoken = profile("podcast", NormalizationMode::Track);
+        let rule = set_rule(
+            &conn,
+            None,
+            ProfileCondition::Genre,
+            "audiobook",
+            &spoken,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+
+        let resolved = resolve_profile(&conn, book).unwrap().unwrap();
+        assert_eq!(resolved.rule_id, Some(rule.id));
+        assert_eq!(resolved.profile, spoken);
+
+        let resolved = resolve_profile(&conn, song).unwrap().unwrap();
+        assert_eq!(resolved.rule_id, None);
+        assert_eq!(resolved.profile, global);
+
+        assert!(resolve_profile(&conn, 9999).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_switching_device_loads_its_profile() {
+        let conn = setup_test_db();
+        let song = add_track(&conn, "/music/song.mp3", &TrackMetadata::default()).unwrap();
+        let global = profile("loudness", NormalizationMode::Album);
+        set_global_profile(&conn, &global).unwrap();
+
+        let headphones = profile("bass_boost", NormalizationMode::Track);
+        let speakers = profile("treble_boost", NormalizationMode::Off);
+        set_device_profile(&conn, "Headphones", &headphones).unwrap();
+        set_device_profile(&conn, "Built-in Speakers", &speakers).unwrap();
+        assert_eq!(
+            get_device_profile(&conn, "Headphones").unwrap(),
+            Some(headphones.clone())
+        );
+        assert!(get_device_profile(&conn, "HDMI").unwrap().is_none());
+
+        let switch_to = |device_id: &str| {
+            settings::set_setting(&conn, ACTIVE_DEVICE_SETTING, &serde_json::json!(device_id))
+                .unwrap();
+            resolve_profile(&conn, song).unwrap().unwrap().profile
+        };
+        assert_eq!(switch_to("Headphones"), headphones);
+        assert_eq!(switch_to("Built-in Speakers"), speakers);
+        // Devices without a saved profile use the global one
+        assert_eq!(switch_to("HDMI"), global);
+        assert_eq!(switch_to(""), global);
+
+        // Rules still win over the device profile
+        let spoken = profile("podcast", NormalizationMode::Track);
+        set_rule(
+            &conn,
+            None,
+            ProfileCondition::Folder,
+            "/music",
+            &spoken,
+            None,
+        )
+        .unwrap();
+        assert_eq!(switch_to("Headphones"), spoken);
+        // Saving a device profile leaves the global one alone
+        assert_eq!(get_global_profile(&conn).unwrap(), global);
+    }
+}
@@ -17,12 +17,17 @@ mod tests {
             album_artist: Some(format!("Artist {}", i % 100)),
             track_number: Some((i % 20 + 1).to_string()),
             track_total: Some("20".to_string()),
+            disc_number: None,
+            disc_total: None,
             date: Some("2024".to_string()),
+            genre: None,
             duration: Some((180 + (i % 120)) as f64),
             file_size: Some(5_000_000 + (i * 100) as i64),
             file_mtime_ns: None,
             file_inode: None,
             content_hash: None,
+            added_date: None,
+            chapters: Vec::new(),
         };
         (filepath, metadata)
     }
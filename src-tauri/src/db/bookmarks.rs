@@ -0,0 +1,152 @@
+//! Bookmark database operations.
+//!
+//! Bookmarks are named positions within a track, keyed by track ID so they
+//! follow the track through moves and relocation. They are deleted with the
+//! track through the foreign key cascade.
+
+use rusqlite::{Connection, Row, params};
+
+use crate::db::{Bookmark, DbResult};
+
+fn row_to_bookmark(row: &Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        id: row.get("id")?,
+        track_id: row.get("track_id")?,
+        position_ms: row.get::<_, i64>("position_ms")?.max(0) as u64,
+        label: row.get("label")?,
+        created_at: row.get("created_at")?,
+    })
+}
+
+/// Add a bookmark to a track
+///
+/// Returns `None` if the track doesn't exist.
+pub fn add_bookmark(
+    conn: &Connection,
+    track_id: i64,
+    position_ms: u64,
+    label: &str,
+) -> DbResult<Option<Bookmark>> {
+    let inserted = conn.execute(
+        "INSERT INTO bookmarks (track_id, position_ms, label)
+         SELECT id, ?2, ?3 FROM library WHERE id = ?1",
+        params![track_id, position_ms as i64, label],
+    )?;
+    if inserted == 0 {
+        return Ok(None);
+    }
+    get_bookmark(conn, conn.last_insert_rowid())
+}
+
+/// Get a bookmark by ID
+pub fn get_bookmark(conn: &Connection, bookmark_id: i64) -> DbResult<Option<Bookmark>> {
+    match conn.query_row(
+        "SELECT * FROM bookmarks WHERE id = ?",
+        [bookmark_id],
+        row_to_bookmark,
+    ) {
+        Ok(bookmark) => Ok(Some(bookmark)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the bookmarks of a track, ordered by position
+pub fn get_bookmarks(conn: &Connection, track_id: i64) -> DbResult<Vec<Bookmark>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM bookmarks
+         WHERE track_id = ?
+         ORDER BY position_ms, id",
+    )?;
+    let bookmarks = stmt
+        .query_map([track_id], row_to_bookmark)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(bookmarks)
+}
+
+/// Delete a bookmark
+pub fn delete_bookmark(conn: &Connection, bookmark_id: i64) -> DbResult<bool> {
+    let deleted = conn.execute("DELETE FROM bookmarks WHERE id = ?", [bookmark_id])?;
+    Ok(deleted > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, delete_track, delete_tracks_bulk, merge_duplicate_tracks};
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn positions(bookmarks: &[Bookmark]) -> Vec<(u64, &str)> {
+        bookmarks
+            .iter()
+            .map(|b| (b.position_ms, b.label.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bookmarks_ordered_by_position() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/sets/mix.mp3", &TrackMetadata::default()).unwrap();
+
+        add_bookmark(&conn, id, 1_800_000, "Peak").unwrap();
+        add_bookmark(&conn, id, 30_000, "Intro").unwrap();
+        let drop = add_bookmark(&conn, id, 600_000, "Drop").unwrap().unwrap();
+
+        let bookmarks = get_bookmarks(&conn, id).unwrap();
+        assert_eq!(
+            positions(&bookmarks),
+            vec![(30_000, "Intro"), (600_000, "Drop"), (1_800_000, "Peak")]
+        );
+        assert!(drop.created_at.is_some());
+
+        assert!(delete_bookmark(&conn, drop.id).unwrap());
+        assert!(!delete_bookmark(&conn, drop.id).unwrap());
+        assert_eq!(get_bookmarks(&conn, id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_add_bookmark_requires_track() {
+        let conn = setup_test_db();
+        assert!(add_bookmark(&conn, 9999, 0, "Nowhere").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_deleting_track_cascades_bookmarks() {
+        let conn = setup_test_db();
+        let a = add_track(&conn, "/books/a.m4b", &TrackMetadata::default()).unwrap();
+        let b = add_track(&conn, "/books/b.m4b", &TrackMetadata::default()).unwrap();
+        let mark = add_bookmark(&conn, a, 5000, "Chapter 2").unwrap().unwrap();
+        add_bookmark(&conn, b, 7000, "Chapter 3").unwrap();
+
+        delete_track(&conn, a).unwrap();
+        assert!(get_bookmark(&conn, mark.id).unwrap().is_none());
+        assert!(get_bookmarks(&conn, a).unwrap().is_empty());
+
+        delete_tracks_bulk(&conn, &["/books/b.m4b".to_string()]).unwrap();
+        assert!(get_bookmarks(&conn, b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_duplicates_keeps_bookmarks() {
+        let conn = setup_test_db();
+        let keep = add_track(&conn, "/music/keep.mp3", &TrackMetadata::default()).unwrap();
+        let dup = add_track(&conn, "/music/dup.mp3", &TrackMetadata::default()).unwrap();
+        add_bookmark(&conn, dup, 42_000, "Solo").unwrap();
+
+        merge_duplicate_tracks(&conn, keep, dup).unwrap();
+
+        assert_eq!(
+            positions(&get_bookmarks(&conn, keep).unwrap()),
+            vec![(42_000, "Solo")]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Library change sequence.
+//!
+//! Triggers on the library table bump a counter in `meta` with every insert,
+//! update, and delete, and record the sequence of each track's latest change
+//! in `library_changes`. A client that remembers the sequence it last saw can
+//! then fetch only the tracks that changed since, instead of reloading.
+
+use rusqlite::Connection;
+
+use crate::db::{DbResult, LibraryChanges};
+
+/// Key of the library change sequence in the `meta` table
+pub const LIBRARY_CHANGE_SEQ_KEY: &str = "library_change_seq";
+
+/// Get the current library change sequence
+pub fn get_change_seq(conn: &Connection) -> DbResult<i64> {
+    match conn.query_row(
+        "SELECT value FROM meta WHERE key = ?",
+        [LIBRARY_CHANGE_SEQ_KEY],
+        |row| row.get(0),
+    ) {
+        Ok(seq) => Ok(seq),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the tracks changed or deleted after `since`
+///
+/// A track is listed once, by its latest change: a track added and then
+/// deleted since `since` is only reported as deleted.
+pub fn get_changes_since(conn: &Connection, since: i64) -> DbResult<LibraryChanges> {
+    // Read the sequence first: a write landing in between is reported again
+    // on the next query rather than missed
+    let seq = get_change_seq(conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT track_id, deleted FROM library_changes
+         WHERE seq > ?
+         ORDER BY seq",
+    )?;
+    let rows: Vec<(i64, bool)> = stmt
+        .query_map([since], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let (deleted, changed): (Vec<_>, Vec<_>) = rows.into_iter().partition(|(_, deleted)| *deleted);
+    Ok(LibraryChanges {
+        seq,
+        changed: changed.into_iter().map(|(id, _)| id).collect(),
+        deleted: deleted.into_iter().map(|(id, _)| id).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, add_tracks_bulk, delete_track, update_play_count};
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_seq_advances_on_add_update_delete() {
+        let conn = setup_test_db();
+        assert_eq!(get_change_seq(&conn).unwrap(), 0);
+
+        let id = add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+        let after_add = get_change_seq(&conn).unwrap();
+        assert!(after_add > 0);
+
+        update_play_count(&conn, id).unwrap();
+        let after_update = get_change_seq(&conn).unwrap();
+        assert!(after_update > after_add);
+
+        delete_track(&conn, id).unwrap();
+        assert!(get_change_seq(&conn).unwrap() > after_update);
+    }
+
+    #[test]
+    fn test_changes_since_returns_delta() {
+        let conn = setup_test_db();
+        let a = add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+        let b = add_track(&conn, "/music/b.mp3", &TrackMetadata::default()).unwrap();
+        let seen = get_change_seq(&conn).unwrap();
+
+        assert_eq!(
+            get_changes_since(&conn, 0).unwrap(),
+            LibraryChanges {
+                seq: seen,
+                changed: vec![a, b],
+                deleted: vec![],
+            }
+        );
+
+        update_play_count(&conn, b).unwrap();
+        delete_track(&conn, a).unwrap();
+        let bulk = add_tracks_bulk(
+            &conn,
+            &[("/music/c.mp3".to_string(), TrackMetadata::default())],
+        )
+        .unwrap();
+        assert_eq!(bulk, 1);
+        let c = conn
+            .query_row(
+                "SELECT id FROM library WHERE filepath = '/music/c.mp3'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap();
+
+        let changes = get_changes_since(&conn, seen).unwrap();
+        assert_eq!(changes.changed, vec![b, c]);
+        assert_eq!(changes.deleted, vec![a]);
+        assert!(changes.seq > seen);
+
+        // Nothing new since the latest sequence
+        let latest = get_changes_since(&conn, changes.seq).unwrap();
+        assert!(latest.changed.is_empty() && latest.deleted.is_empty());
+        assert_eq!(latest.seq, changes.seq);
+    }
+
+    #[test]
+    fn test_track_added_then_deleted_reports_deleted_only() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/brief.mp3", &TrackMetadata::default()).unwrap();
+        delete_track(&conn, id).unwrap();
+
+        let changes = get_changes_since(&conn, 0).unwrap();
+        assert!(changes.changed.is_empty());
+        assert_eq!(changes.deleted, vec![id]);
+    }
+}
@@ -118,7 +118,8 @@ mod tests {
         let db = Database::new(db_path.to_str().unwrap()).expect("Failed to open database");
         let conn = db.conn().expect("Failed to get connection");
 
-        let result = favorites::get_favorites(&conn, 100, 0).expect("Failed to get favorites");
+        let result = favorites::get_favorites(&conn, favorites::DEFAULT_FAVORITE_LIST_ID, 100, 0)
+            .expect("Failed to get favorites");
         println!("Found {} favorites", result.total);
     }
 
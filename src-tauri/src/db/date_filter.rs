@@ -0,0 +1,334 @@
+//! Date filters for library queries.
+//!
+//! A filter compares one of a track's dates against a point in time, given
+//! either as a date (anything `library::normalize_added_date` accepts) or as
+//! an offset from now: `now-14d`, `now-2w`, `now-6M`, `now-1y`, or plain
+//! `now`. Offsets resolve against the current time when the query runs.
+//!
+//! `added_date` and `last_played` are stored as text, `last_seen_at` as Unix
+//! seconds. Every field is compared through SQLite's `datetime()`, so they
+//! all behave the same whatever the storage.
+
+use chrono::{Duration, Months, NaiveDateTime};
+use serde::Deserialize;
+use std::str::FromStr;
+
+use crate::db::library::normalize_added_date;
+
+/// Text format `datetime()` produces
+const SQL_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Unit of a relative date offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateUnit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// A point in time in a date filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum DateExpr {
+    /// Now, moved by `amount` units; negative amounts are in the past
+    Relative { amount: i32, unit: DateUnit },
+    /// A fixed UTC time
+    Absolute(NaiveDateTime),
+}
+
+impl DateExpr {
+    /// The concrete UTC time this stands for, given the current time
+    ///
+    /// Offsets past the range of dates chrono handles clamp to its limits.
+    pub fn resolve(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let (amount, unit) = match *self {
+            DateExpr::Absolute(time) => return time,
+            DateExpr::Relative { amount, unit } => (amount, unit),
+        };
+        let limit = if amount < 0 {
+            NaiveDateTime::MIN
+        } else {
+            NaiveDateTime::MAX
+        };
+        let months = |count: u32| {
+            let months = Months::new(count);
+            if amount < 0 {
+                now.checked_sub_months(months)
+            } else {
+                now.checked_add_months(months)
+            }
+        };
+
+        let size = amount.unsigned_abs();
+        let moved = match unit {
+            DateUnit::Day => now.checked_add_signed(Duration::days(amount.into())),
+            DateUnit::Week => now.checked_add_signed(Duration::weeks(amount.into())),
+            DateUnit::Month => months(size),
+            DateUnit::Year => size.checked_mul(12).and_then(months),
+        };
+        moved.unwrap_or(limit)
+    }
+}
+
+impl FromStr for DateExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let Some(offset) = s.strip_prefix("now") else {
+            let normalized = normalize_added_date(s).map_err(|_| format!("Invalid date: {}", s))?;
+            let time = NaiveDateTime::parse_from_str(&normalized, SQL_DATETIME_FORMAT)
+                .map_err(|_| format!("Invalid date: {}", s))?;
+            return Ok(DateExpr::Absolute(time));
+        };
+        if offset.is_empty() {
+            return Ok(DateExpr::Relative {
+                amount: 0,
+                unit: DateUnit::Day,
+            });
+        }
+
+        let invalid = || format!("Invalid relative date: {} (expected e.g. now-14d)", s);
+        let (sign, rest) = if let Some(rest) = offset.strip_prefix('-') {
+            (-1, rest)
+        } else if let Some(rest) = offset.strip_prefix('+') {
+            (1, rest)
+        } else {
+            return Err(invalid());
+        };
+        let unit = match rest.chars().last() {
+            Some('d') => DateUnit::Day,
+            Some('w') => DateUnit::Week,
+            Some('M') => DateUnit::Month,
+            Some('y') => DateUnit::Year,
+            _ => return Err(invalid()),
+        };
+        let digits = &rest[..rest.len() - 1];
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let amount: i32 = digits.parse().map_err(|_| invalid())?;
+        Ok(DateExpr::Relative {
+            amount: sign * amount,
+            unit,
+        })
+    }
+}
+
+impl TryFrom<String> for DateExpr {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Track date a filter looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateField {
+    AddedDate,
+    LastPlayed,
+    LastSeen,
+}
+
+impl DateField {
+    fn column(&self) -> &'static str {
+        match self {
+            DateField::AddedDate => "added_date",
+            DateField::LastPlayed => "last_played",
+            DateField::LastSeen => "last_seen_at",
+        }
+    }
+
+    /// The column as `datetime()` text
+    fn as_datetime_sql(&self) -> &'static str {
+        match self {
+            DateField::AddedDate => "datetime(added_date)",
+            DateField::LastPlayed => "datetime(last_played)",
+            DateField::LastSeen => "datetime(last_seen_at, 'unixepoch')",
+        }
+    }
+}
+
+/// How a date filter compares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateOp {
+    /// Strictly earlier, or never set: a never-played track counts as "not
+    /// played since" any date
+    Before,
+    /// Strictly later
+    After,
+    /// Between the point and now, either way round, inclusive
+    Within,
+}
+
+/// A date condition on library tracks, e.g. last played before `now-6M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct DateFilter {
+    pub field: DateField,
+    pub op: DateOp,
+    pub value: DateExpr,
+}
+
+impl DateFilter {
+    /// SQL condition and its parameters, with offsets resolved against `now`
+    pub fn to_sql(&self, now: NaiveDateTime) -> (String, Vec<String>) {
+        let format = |time: NaiveDateTime| time.format(SQL_DATETIME_FORMAT).to_string();
+        let point = self.value.resolve(now);
+        let date = self.field.as_datetime_sql();
+
+        match self.op {
+            DateOp::Before => (
+                format!("({} IS NULL OR {} < ?)", self.field.column(), date),
+                vec![format(point)],
+            ),
+            DateOp::After => (format!("{} > ?", date), vec![format(point)]),
+            DateOp::Within => (
+                format!("{} BETWEEN ? AND ?", date),
+                vec![format(point.min(now)), format(point.max(now))],
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use crate::db::library::{LibraryQuery, add_track, get_all_tracks};
+    use crate::db::schema::{create_tables, run_migrations};
+    use rusqlite::Connection;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, SQL_DATETIME_FORMAT).unwrap()
+    }
+
+    fn resolve(expr: &str) -> NaiveDateTime {
+        expr.parse::<DateExpr>()
+            .unwrap()
+            .resolve(at("2026-03-31 12:00:00"))
+    }
+
+    #[test]
+    fn test_relative_units() {
+        assert_eq!(resolve("now"), at("2026-03-31 12:00:00"));
+        assert_eq!(resolve("now-14d"), at("2026-03-17 12:00:00"));
+        assert_eq!(resolve("now+1d"), at("2026-04-01 12:00:00"));
+        assert_eq!(resolve("now-2w"), at("2026-03-17 12:00:00"));
+        // Month steps clamp to the end of shorter months
+        assert_eq!(resolve("now-1M"), at("2026-02-28 12:00:00"));
+        assert_eq!(resolve("now-6M"), at("2025-09-30 12:00:00"));
+        assert_eq!(resolve("now-1y"), at("2025-03-31 12:00:00"));
+        assert_eq!(resolve(" now-10y "), at("2016-03-31 12:00:00"));
+    }
+
+    #[test]
+    fn test_absolute_and_invalid_expressions() {
+        assert_eq!(resolve("2024-05-01"), at("2024-05-01 00:00:00"));
+        assert_eq!(resolve("2024-05-01T10:30:00Z"), at("2024-05-01 10:30:00"));
+
+        for bad in [
+            "now-",
+            "now-d",
+            "now-14",
+            "now-3m",
+            "now*2d",
+            "now--2d",
+            "yesterday",
+        ] {
+            assert!(bad.parse::<DateExpr>().is_err(), "{} should not parse", bad);
+        }
+        assert_eq!(resolve("now-99999999y"), NaiveDateTime::MIN);
+    }
+
+    #[test]
+    fn test_filter_deserializes_from_json() {
+        let filter: DateFilter =
+            serde_json::from_str(r#"{"field": "last_played", "op": "before", "value": "now-6M"}"#)
+                .unwrap();
+        assert_eq!(filter.field, DateField::LastPlayed);
+        assert_eq!(filter.op, DateOp::Before);
+        assert_eq!(
+            filter.value,
+            DateExpr::Relative {
+                amount: -6,
+                unit: DateUnit::Month
+            }
+        );
+
+        let bad = r#"{"field": "last_played", "op": "before", "value": "now-6q"}"#;
+        assert!(serde_json::from_str::<DateFilter>(bad).is_err());
+    }
+
+    #[test]
+    fn test_library_query_date_filters() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let add = |name: &str, added: &str, played: Option<&str>, seen: i64| {
+            let metadata = TrackMetadata {
+                added_date: Some(added.to_string()),
+                ..Default::default()
+            };
+            let id = add_track(&conn, &format!("/music/{}.mp3", name), &metadata).unwrap();
+            conn.execute(
+                "UPDATE library SET last_played = ?, last_seen_at = ? WHERE id = ?",
+                rusqlite::params![played, seen, id],
+            )
+            .unwrap();
+        };
+        let now = chrono::Utc::now();
+        let days_ago = |days: i64| (now - Duration::days(days)).format(SQL_DATETIME_FORMAT);
+        let recent = days_ago(3).to_string();
+        let old = days_ago(400).to_string();
+        add("fresh", &recent, Some(&recent), now.timestamp());
+        add("stale", &old, Some(&old), now.timestamp() - 400 * 86400);
+        add("unplayed", &old, None, now.timestamp() - 30 * 86400);
+
+        let names = |field, op, value: &str| -> Vec<String> {
+            let query = LibraryQuery {
+                date_filters: vec![DateFilter {
+                    field,
+                    op,
+                    value: value.parse().unwrap(),
+                }],
+                ..LibraryQuery::new()
+            };
+            let mut names: Vec<String> = get_all_tracks(&conn, &query)
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|t| t.filepath.trim_start_matches("/music/").to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            names(DateField::AddedDate, DateOp::Within, "now-2w"),
+            ["fresh.mp3"]
+        );
+        assert_eq!(
+            names(DateField::AddedDate, DateOp::Before, "now-1y"),
+            ["stale.mp3", "unplayed.mp3"]
+        );
+        // Never played counts as not played in the last 6 months
+        assert_eq!(
+            names(DateField::LastPlayed, DateOp::Before, "now-6M"),
+            ["stale.mp3", "unplayed.mp3"]
+        );
+        assert_eq!(
+            names(DateField::LastPlayed, DateOp::After, "now-6M"),
+            ["fresh.mp3"]
+        );
+        // Unix-second columns compare the same way as text dates
+        assert_eq!(
+            names(DateField::LastSeen, DateOp::Within, "now-6w"),
+            ["fresh.mp3", "unplayed.mp3"]
+        );
+    }
+}
@@ -1,32 +1,131 @@
 //! Favorites database operations.
 //!
 //! Operations for favorited tracks, top played, and recently played.
+//!
+//! Favorites live in named lists; list 1 is the default "Favorites" list that
+//! existing single-list favorites were migrated into.
 
 use rusqlite::{params, Connection};
+use std::collections::HashMap;
+
+use crate::db::{
+    library, DbError, DbResult, FavoriteList, FavoriteTrack, PaginatedResult, PlayedTrack, Track,
+};
+
+/// ID of the default "Favorites" list
+pub const DEFAULT_FAVORITE_LIST_ID: i64 = 1;
 
-use crate::db::{DbResult, FavoriteTrack, PaginatedResult, Track};
+/// Get all favorite lists with their track counts, default list first
+pub fn get_favorite_lists(conn: &Connection) -> DbResult<Vec<FavoriteList>> {
+    let mut stmt = conn.prepare(
+        "SELECT fl.id, fl.name, fl.created_at, COUNT(f.id) as track_count
+         FROM favorite_lists fl
+         LEFT JOIN favorites f ON f.list_id = fl.id
+         GROUP BY fl.id
+         ORDER BY fl.id",
+    )?;
 
-/// Get favorited tracks with pagination
+    let lists: Vec<FavoriteList> = stmt
+        .query_map([], |row| {
+            Ok(FavoriteList {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                created_at: row.get("created_at")?,
+                track_count: row.get("track_count")?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(lists)
+}
+
+/// Check whether a favorite list exists
+pub fn favorite_list_exists(conn: &Connection, list_id: i64) -> DbResult<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM favorite_lists WHERE id = ?",
+        [list_id],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+/// Create a named favorite list
+///
+/// Returns `None` if a list with that name already exists.
+pub fn create_favorite_list(conn: &Connection, name: &str) -> DbResult<Option<FavoriteList>> {
+    match conn.execute("INSERT INTO favorite_lists (name) VALUES (?)", [name]) {
+        Ok(_) => {
+            let id = conn.last_insert_rowid();
+            let list = conn
+                .query_row(
+                    "SELECT id, name, created_at FROM favorite_lists WHERE id = ?",
+                    [id],
+                    |row| {
+                        Ok(FavoriteList {
+                            id: row.get("id")?,
+                            name: row.get("name")?,
+                            created_at: row.get("created_at")?,
+                            track_count: 0,
+                        })
+                    },
+                )
+                .ok();
+            Ok(list)
+        }
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            Ok(None) // Name already exists
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Delete a favorite list and its entries
+///
+/// The default list can't be deleted.
+pub fn delete_favorite_list(conn: &Connection, list_id: i64) -> DbResult<bool> {
+    if list_id == DEFAULT_FAVORITE_LIST_ID {
+        return Err(DbError::Constraint(
+            "The default favorites list cannot be deleted".to_string(),
+        ));
+    }
+
+    // Delete entries explicitly in case foreign keys are off for this connection
+    conn.execute("DELETE FROM favorites WHERE list_id = ?", [list_id])?;
+    let deleted = conn.execute("DELETE FROM favorite_lists WHERE id = ?", [list_id])?;
+    Ok(deleted > 0)
+}
+
+/// Get the tracks in a favorite list with pagination
 pub fn get_favorites(
     conn: &Connection,
+    list_id: i64,
     limit: i64,
     offset: i64,
 ) -> DbResult<PaginatedResult<FavoriteTrack>> {
-    let total: i64 = conn.query_row("SELECT COUNT(*) FROM favorites", [], |row| row.get(0))?;
+    let total: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM favorites WHERE list_id = ?",
+        [list_id],
+        |row| row.get(0),
+    )?;
 
     let mut stmt = conn.prepare(
         "SELECT l.id, l.filepath, l.title, l.artist, l.album, l.album_artist,
-                l.track_number, l.track_total, l.date, l.duration, l.file_size,
+                l.track_number, l.track_total, l.disc_number, l.disc_total,
+                l.date, l.duration, l.file_size,
                 l.play_count, l.last_played, l.added_date, l.missing, l.last_seen_at,
                 l.file_mtime_ns, l.file_inode, l.content_hash, f.timestamp as favorited_date
          FROM favorites f
          JOIN library l ON f.track_id = l.id
+         WHERE f.list_id = ?
          ORDER BY f.timestamp ASC
          LIMIT ? OFFSET ?",
     )?;
 
     let tracks: Vec<FavoriteTrack> = stmt
-        .query_map([limit, offset], |row| {
+        .query_map([list_id, limit, offset], |row| {
             Ok(FavoriteTrack {
                 track: Track {
                     id: row.get("id")?,
@@ -37,6 +136,8 @@ pub fn get_favorites(
                     album_artist: row.get("album_artist")?,
                     track_number: row.get("track_number")?,
                     track_total: row.get("track_total")?,
+                    disc_number: row.get("disc_number")?,
+                    disc_total: row.get("disc_total")?,
                     date: row.get("date")?,
                     duration: row.get("duration")?,
                     file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
@@ -61,21 +162,33 @@ pub fn get_favorites(
     })
 }
 
-/// Get top 25 most played tracks
-pub fn get_top_25(conn: &Connection) -> DbResult<Vec<Track>> {
+/// Get top 25 most played tracks, optionally limited to a favorite list
+pub fn get_top_25(conn: &Connection, list_id: Option<i64>) -> DbResult<Vec<Track>> {
+    get_top_played(conn, 25, list_id)
+}
+
+/// Get up to `limit` most played tracks, optionally limited to a favorite list
+///
+/// Ties on play count go to the most recently played, then the oldest track.
+pub fn get_top_played(
+    conn: &Connection,
+    limit: i64,
+    list_id: Option<i64>,
+) -> DbResult<Vec<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library
          WHERE play_count > 0
-         ORDER BY play_count DESC, last_played DESC
-         LIMIT 25",
+           AND (?1 IS NULL OR id IN (SELECT track_id FROM favorites WHERE list_id = ?1))
+         ORDER BY play_count DESC, last_played DESC, id ASC
+         LIMIT ?2",
     )?;
 
     let tracks: Vec<Track> = stmt
-        .query_map([], |row| {
+        .query_map(params![list_id, limit], |row| {
             Ok(Track {
                 id: row.get("id")?,
                 filepath: row.get("filepath")?,
@@ -85,6 +198,8 @@ pub fn get_top_25(conn: &Connection) -> DbResult<Vec<Track>> {
                 album_artist: row.get("album_artist")?,
                 track_number: row.get("track_number")?,
                 track_total: row.get("track_total")?,
+                disc_number: row.get("disc_number")?,
+                disc_total: row.get("disc_total")?,
                 date: row.get("date")?,
                 duration: row.get("duration")?,
                 file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
@@ -104,70 +219,74 @@ pub fn get_top_25(conn: &Connection) -> DbResult<Vec<Track>> {
     Ok(tracks)
 }
 
-/// Get tracks played within the last N days
-pub fn get_recently_played(conn: &Connection, days: i64, limit: i64) -> DbResult<Vec<Track>> {
-    let modifier = format!("-{} days", days);
-
+/// Get recent plays since a UTC timestamp, optionally limited to a favorite list
+///
+/// Reads `play_history`, newest play first, so a track appears once per
+/// listening session rather than only at its latest play. Back-to-back plays
+/// of the same track collapse into the most recent one. `since` uses the
+/// `YYYY-MM-DD HH:MM:SS` format that `played_at` is stored in.
+pub fn get_recently_played(
+    conn: &Connection,
+    since: &str,
+    limit: i64,
+    list_id: Option<i64>,
+) -> DbResult<Vec<PlayedTrack>> {
     let mut stmt = conn.prepare(
-        "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
-                play_count, last_played, added_date, missing, last_seen_at,
-                file_mtime_ns, file_inode, content_hash
-         FROM library
-         WHERE last_played IS NOT NULL
-           AND last_played >= datetime('now', ?)
-         ORDER BY last_played DESC
-         LIMIT ?",
+        "SELECT h.played_at, l.id, l.filepath, l.title, l.artist, l.album, l.album_artist,
+                l.track_number, l.track_total, l.disc_number, l.disc_total,
+                l.date, l.duration, l.file_size,
+                l.play_count, l.last_played, l.added_date, l.missing, l.last_seen_at,
+                l.file_mtime_ns, l.file_inode, l.content_hash
+         FROM play_history h
+         JOIN library l ON l.id = h.track_id
+         WHERE h.played_at >= ?1
+           AND (?2 IS NULL OR h.track_id IN (SELECT track_id FROM favorites WHERE list_id = ?2))
+         ORDER BY h.played_at DESC, h.id DESC",
     )?;
 
-    let tracks: Vec<Track> = stmt
-        .query_map(params![modifier, limit], |row| {
-            Ok(Track {
-                id: row.get("id")?,
-                filepath: row.get("filepath")?,
-                title: row.get("title")?,
-                artist: row.get("artist")?,
-                album: row.get("album")?,
-                album_artist: row.get("album_artist")?,
-                track_number: row.get("track_number")?,
-                track_total: row.get("track_total")?,
-                date: row.get("date")?,
-                duration: row.get("duration")?,
-                file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
-                file_mtime_ns: row.get("file_mtime_ns")?,
-                file_inode: row.get("file_inode")?,
-                content_hash: row.get("content_hash")?,
-                added_date: row.get("added_date")?,
-                last_played: row.get("last_played")?,
-                play_count: row.get::<_, Option<i64>>("play_count")?.unwrap_or(0),
-                missing: row.get::<_, Option<i64>>("missing")?.unwrap_or(0) != 0,
-                last_seen_at: row.get("last_seen_at")?,
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let mut rows = stmt.query(params![since, list_id])?;
+    let mut plays: Vec<PlayedTrack> = Vec::new();
+    while let Some(row) = rows.next()? {
+        if plays.len() as i64 >= limit {
+            break;
+        }
+        let track = library::row_to_track(row)?;
+        if plays.last().is_some_and(|prev| prev.track.id == track.id) {
+            continue;
+        }
+        plays.push(PlayedTrack {
+            track,
+            played_at: row.get("played_at")?,
+        });
+    }
 
-    Ok(tracks)
+    Ok(plays)
 }
 
-/// Get tracks added within the last N days
-pub fn get_recently_added(conn: &Connection, days: i64, limit: i64) -> DbResult<Vec<Track>> {
+/// Get tracks added within the last N days, optionally limited to a favorite list
+pub fn get_recently_added(
+    conn: &Connection,
+    days: i64,
+    limit: i64,
+    list_id: Option<i64>,
+) -> DbResult<Vec<Track>> {
     let modifier = format!("-{} days", days);
 
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library
          WHERE added_date IS NOT NULL
-           AND added_date >= datetime('now', ?)
-         ORDER BY added_date DESC
-         LIMIT ?",
+           AND added_date >= datetime('now', ?1)
+           AND (?3 IS NULL OR id IN (SELECT track_id FROM favorites WHERE list_id = ?3))
+         ORDER BY added_date DESC, id DESC
+         LIMIT ?2",
     )?;
 
     let tracks: Vec<Track> = stmt
-        .query_map(params![modifier, limit], |row| {
+        .query_map(params![modifier, limit, list_id], |row| {
             Ok(Track {
                 id: row.get("id")?,
                 filepath: row.get("filepath")?,
@@ -177,6 +296,8 @@ pub fn get_recently_added(conn: &Connection, days: i64, limit: i64) -> DbResult<
                 album_artist: row.get("album_artist")?,
                 track_number: row.get("track_number")?,
                 track_total: row.get("track_total")?,
+                disc_number: row.get("disc_number")?,
+                disc_total: row.get("disc_total")?,
                 date: row.get("date")?,
                 duration: row.get("duration")?,
                 file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
@@ -196,11 +317,15 @@ pub fn get_recently_added(conn: &Connection, days: i64, limit: i64) -> DbResult<
     Ok(tracks)
 }
 
-/// Check if a track is favorited
-pub fn is_favorite(conn: &Connection, track_id: i64) -> DbResult<(bool, Option<String>)> {
+/// Check if a track is in a favorite list
+pub fn is_favorite(
+    conn: &Connection,
+    list_id: i64,
+    track_id: i64,
+) -> DbResult<(bool, Option<String>)> {
     match conn.query_row(
-        "SELECT timestamp FROM favorites WHERE track_id = ?",
-        [track_id],
+        "SELECT timestamp FROM favorites WHERE list_id = ? AND track_id = ?",
+        [list_id, track_id],
         |row| row.get::<_, String>(0),
     ) {
         Ok(timestamp) => Ok((true, Some(timestamp))),
@@ -209,13 +334,16 @@ pub fn is_favorite(conn: &Connection, track_id: i64) -> DbResult<(bool, Option<S
     }
 }
 
-/// Add a track to favorites
-pub fn add_favorite(conn: &Connection, track_id: i64) -> DbResult<Option<String>> {
-    match conn.execute("INSERT INTO favorites (track_id) VALUES (?)", [track_id]) {
+/// Add a track to a favorite list
+pub fn add_favorite(conn: &Connection, list_id: i64, track_id: i64) -> DbResult<Option<String>> {
+    match conn.execute(
+        "INSERT INTO favorites (list_id, track_id) VALUES (?, ?)",
+        [list_id, track_id],
+    ) {
         Ok(_) => {
             let timestamp: String = conn.query_row(
-                "SELECT timestamp FROM favorites WHERE track_id = ?",
-                [track_id],
+                "SELECT timestamp FROM favorites WHERE list_id = ? AND track_id = ?",
+                [list_id, track_id],
                 |row| row.get(0),
             )?;
             Ok(Some(timestamp))
@@ -229,21 +357,85 @@ pub fn add_favorite(conn: &Connection, track_id: i64) -> DbResult<Option<String>
     }
 }
 
-/// Remove a track from favorites
-pub fn remove_favorite(conn: &Connection, track_id: i64) -> DbResult<bool> {
-    let deleted = conn.execute("DELETE FROM favorites WHERE track_id = ?", [track_id])?;
+/// Remove a track from a favorite list
+pub fn remove_favorite(conn: &Connection, list_id: i64, track_id: i64) -> DbResult<bool> {
+    let deleted = conn.execute(
+        "DELETE FROM favorites WHERE list_id = ? AND track_id = ?",
+        [list_id, track_id],
+    )?;
     Ok(deleted > 0)
 }
 
+/// Add several tracks to a favorite list
+///
+/// Every track is checked before anything is written, so an unknown track
+/// fails the whole batch with `DbError::NotFound`. Tracks already in the list
+/// are skipped. Returns the tracks that were added, in the order given; run
+/// inside a transaction to keep a batch that fails mid-way from half applying.
+pub fn add_favorites(conn: &Connection, list_id: i64, track_ids: &[i64]) -> DbResult<Vec<i64>> {
+    let mut exists = conn.prepare("SELECT 1 FROM library WHERE id = ?")?;
+    for &track_id in track_ids {
+        if !exists.exists([track_id])? {
+            return Err(DbError::NotFound(format!("Track with id {}", track_id)));
+        }
+    }
+
+    let mut insert =
+        conn.prepare("INSERT OR IGNORE INTO favorites (list_id, track_id) VALUES (?, ?)")?;
+    let mut added = Vec::new();
+    for &track_id in track_ids {
+        if insert.execute([list_id, track_id])? > 0 {
+            added.push(track_id);
+        }
+    }
+    Ok(added)
+}
+
+/// Remove several tracks from a favorite list
+///
+/// Returns the tracks that were in the list and got removed.
+pub fn remove_favorites(
+    conn: &Connection,
+    list_id: i64,
+    track_ids: &[i64],
+) -> DbResult<Vec<i64>> {
+    let mut delete = conn.prepare("DELETE FROM favorites WHERE list_id = ? AND track_id = ?")?;
+    let mut removed = Vec::new();
+    for &track_id in track_ids {
+        if delete.execute([list_id, track_id])? > 0 {
+            removed.push(track_id);
+        }
+    }
+    Ok(removed)
+}
+
+/// Whether each track is in a favorite list, for rendering a page of stars
+///
+/// Unknown tracks map to `false`.
+pub fn check_favorites(
+    conn: &Connection,
+    list_id: i64,
+    track_ids: &[i64],
+) -> DbResult<HashMap<i64, bool>> {
+    let mut stmt = conn.prepare("SELECT 1 FROM favorites WHERE list_id = ? AND track_id = ?")?;
+    track_ids
+        .iter()
+        .map(|&track_id| Ok((track_id, stmt.exists([list_id, track_id])?)))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::{
-        library::{add_track, update_play_count},
+        history::record_play_at,
+        library::{add_track, set_play_count, update_play_count},
         schema::{create_tables, run_migrations},
         TrackMetadata,
     };
 
+    const ALL_TIME: &str = "1970-01-01 00:00:00";
+
     fn setup_test_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
         create_tables(&conn).unwrap();
@@ -262,26 +454,69 @@ mod tests {
         let id = add_track(&conn, "/music/test.mp3", &metadata).unwrap();
 
         // Add favorite
-        let timestamp = add_favorite(&conn, id).unwrap();
+        let timestamp = add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         assert!(timestamp.is_some());
 
         // Check is favorite
-        let (is_fav, _) = is_favorite(&conn, id).unwrap();
+        let (is_fav, _) = is_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         assert!(is_fav);
 
         // Adding again should return None
-        let timestamp = add_favorite(&conn, id).unwrap();
+        let timestamp = add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         assert!(timestamp.is_none());
 
         // Remove favorite
-        let removed = remove_favorite(&conn, id).unwrap();
+        let removed = remove_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         assert!(removed);
 
         // Check is not favorite
-        let (is_fav, _) = is_favorite(&conn, id).unwrap();
+        let (is_fav, _) = is_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         assert!(!is_fav);
     }
 
+    #[test]
+    fn test_add_favorites_is_all_or_nothing() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = (1..=3)
+            .map(|i| add_track(&conn, &format!("/music/{}.mp3", i), &TrackMetadata::default()))
+            .collect::<DbResult<_>>()
+            .unwrap();
+
+        // One unknown track rejects the whole batch
+        let result = add_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &[ids[0], 999, ids[1]]);
+        assert!(matches!(result, Err(DbError::NotFound(_))));
+        let checked = check_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &ids).unwrap();
+        assert!(checked.values().all(|favorite| !favorite));
+
+        // Tracks already in the list are skipped rather than failing the batch
+        add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, ids[1]).unwrap();
+        let added = add_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &ids).unwrap();
+        assert_eq!(added, vec![ids[0], ids[2]]);
+        assert_eq!(get_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, 10, 0).unwrap().total, 3);
+    }
+
+    #[test]
+    fn test_remove_and_check_favorites_batch() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = (1..=4)
+            .map(|i| add_track(&conn, &format!("/music/{}.mp3", i), &TrackMetadata::default()))
+            .collect::<DbResult<_>>()
+            .unwrap();
+        let list = create_favorite_list(&conn, "Road trip").unwrap().unwrap();
+        add_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &ids[..3]).unwrap();
+        add_favorites(&conn, list.id, &[ids[3]]).unwrap();
+
+        let removed = remove_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &[ids[0], ids[3]]).unwrap();
+        assert_eq!(removed, vec![ids[0]]);
+
+        let asked = [ids[0], ids[1], ids[2], ids[3], 999];
+        let checked = check_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, &asked).unwrap();
+        let expected: HashMap<i64, bool> =
+            [(ids[0], false), (ids[1], true), (ids[2], true), (ids[3], false), (999, false)].into();
+        assert_eq!(checked, expected);
+        assert!(check_favorites(&conn, list.id, &[ids[3]]).unwrap()[&ids[3]]);
+    }
+
     #[test]
     fn test_get_favorites() {
         let conn = setup_test_db();
@@ -292,10 +527,10 @@ mod tests {
                 ..Default::default()
             };
             let id = add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap();
-            add_favorite(&conn, id).unwrap();
+            add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         }
 
-        let result = get_favorites(&conn, 10, 0).unwrap();
+        let result = get_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, 10, 0).unwrap();
         assert_eq!(result.total, 5);
         assert_eq!(result.items.len(), 5);
     }
@@ -317,7 +552,7 @@ mod tests {
             }
         }
 
-        let top = get_top_25(&conn).unwrap();
+        let top = get_top_25(&conn, None).unwrap();
         assert_eq!(top.len(), 25);
         assert_eq!(top[0].play_count, 30); // Most played first
     }
@@ -327,7 +562,7 @@ mod tests {
         let conn = setup_test_db();
 
         // No tracks played yet
-        let recent = get_recently_played(&conn, 7, 10).unwrap();
+        let recent = get_recently_played(&conn, ALL_TIME, 10, None).unwrap();
         assert!(recent.is_empty());
     }
 
@@ -344,9 +579,46 @@ mod tests {
         update_play_count(&conn, id).unwrap();
 
         // Should find the recently played track
-        let recent = get_recently_played(&conn, 7, 10).unwrap();
+        let recent = get_recently_played(&conn, ALL_TIME, 10, None).unwrap();
         assert_eq!(recent.len(), 1);
-        assert_eq!(recent[0].title, Some("Recently Played".to_string()));
+        assert_eq!(recent[0].track.title, Some("Recently Played".to_string()));
+        assert!(!recent[0].played_at.is_empty());
+    }
+
+    #[test]
+    fn test_get_recently_played_orders_and_dedupes_history() {
+        let conn = setup_test_db();
+        let a = add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+        let b = add_track(&conn, "/music/b.mp3", &TrackMetadata::default()).unwrap();
+
+        // Inserted out of order: a, a, b, a by time
+        record_play_at(&conn, a, "2026-01-01 10:08:00").unwrap();
+        record_play_at(&conn, b, "2026-01-01 10:04:00").unwrap();
+        record_play_at(&conn, a, "2026-01-01 10:00:00").unwrap();
+        record_play_at(&conn, a, "2026-01-01 10:02:00").unwrap();
+
+        let recent = get_recently_played(&conn, ALL_TIME, 10, None).unwrap();
+        let plays: Vec<(i64, &str)> = recent
+            .iter()
+            .map(|p| (p.track.id, p.played_at.as_str()))
+            .collect();
+        assert_eq!(
+            plays,
+            vec![
+                (a, "2026-01-01 10:08:00"),
+                (b, "2026-01-01 10:04:00"),
+                (a, "2026-01-01 10:02:00"),
+            ]
+        );
+
+        // Limit applies after collapsing repeats
+        let recent = get_recently_played(&conn, ALL_TIME, 2, None).unwrap();
+        assert_eq!(recent.len(), 2);
+
+        // Plays before `since` are excluded
+        let recent = get_recently_played(&conn, "2026-01-01 10:03:00", 10, None).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[1].track.id, b);
     }
 
     #[test]
@@ -364,7 +636,7 @@ mod tests {
         }
 
         // Request only 3
-        let recent = get_recently_played(&conn, 7, 3).unwrap();
+        let recent = get_recently_played(&conn, ALL_TIME, 3, None).unwrap();
         assert_eq!(recent.len(), 3);
     }
 
@@ -373,7 +645,7 @@ mod tests {
         let conn = setup_test_db();
 
         // No tracks added
-        let recent = get_recently_added(&conn, 7, 10).unwrap();
+        let recent = get_recently_added(&conn, 7, 10, None).unwrap();
         assert!(recent.is_empty());
     }
 
@@ -389,7 +661,7 @@ mod tests {
         add_track(&conn, "/music/new.mp3", &metadata).unwrap();
 
         // Should find the recently added track
-        let recent = get_recently_added(&conn, 7, 10).unwrap();
+        let recent = get_recently_added(&conn, 7, 10, None).unwrap();
         assert_eq!(recent.len(), 1);
         assert_eq!(recent[0].title, Some("New Track".to_string()));
     }
@@ -408,7 +680,7 @@ mod tests {
         }
 
         // Request only 2
-        let recent = get_recently_added(&conn, 7, 2).unwrap();
+        let recent = get_recently_added(&conn, 7, 2, None).unwrap();
         assert_eq!(recent.len(), 2);
     }
 
@@ -417,7 +689,7 @@ mod tests {
         let conn = setup_test_db();
 
         // Try to remove a favorite that doesn't exist
-        let removed = remove_favorite(&conn, 999).unwrap();
+        let removed = remove_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, 999).unwrap();
         assert!(!removed);
     }
 
@@ -426,7 +698,7 @@ mod tests {
         let conn = setup_test_db();
 
         // Check if nonexistent track is favorite
-        let (is_fav, timestamp) = is_favorite(&conn, 999).unwrap();
+        let (is_fav, timestamp) = is_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, 999).unwrap();
         assert!(!is_fav);
         assert!(timestamp.is_none());
     }
@@ -442,16 +714,16 @@ mod tests {
                 ..Default::default()
             };
             let id = add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap();
-            add_favorite(&conn, id).unwrap();
+            add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
         }
 
         // Get first page
-        let page1 = get_favorites(&conn, 5, 0).unwrap();
+        let page1 = get_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, 5, 0).unwrap();
         assert_eq!(page1.total, 10);
         assert_eq!(page1.items.len(), 5);
 
         // Get second page
-        let page2 = get_favorites(&conn, 5, 5).unwrap();
+        let page2 = get_favorites(&conn, DEFAULT_FAVORITE_LIST_ID, 5, 5).unwrap();
         assert_eq!(page2.total, 10);
         assert_eq!(page2.items.len(), 5);
 
@@ -473,7 +745,7 @@ mod tests {
         }
 
         // Should return empty since no tracks have been played
-        let top = get_top_25(&conn).unwrap();
+        let top = get_top_25(&conn, None).unwrap();
         assert!(top.is_empty());
     }
 
@@ -507,10 +779,116 @@ mod tests {
             update_play_count(&conn, id3).unwrap(); // 5 plays
         }
 
-        let top = get_top_25(&conn).unwrap();
+        let top = get_top_25(&conn, None).unwrap();
         assert_eq!(top.len(), 3);
         assert_eq!(top[0].title, Some("High Plays".to_string()));
         assert_eq!(top[1].title, Some("Medium Plays".to_string()));
         assert_eq!(top[2].title, Some("Low Plays".to_string()));
     }
+
+    #[test]
+    fn test_get_top_played_honors_limit_with_stable_ties() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = (1..=5)
+            .map(|i| {
+                let path = format!("/music/track{}.mp3", i);
+                let id = add_track(&conn, &path, &TrackMetadata::default()).unwrap();
+                set_play_count(&conn, id, 3, false).unwrap();
+                id
+            })
+            .collect();
+        set_play_count(&conn, ids[4], 9, false).unwrap();
+
+        let top: Vec<i64> = get_top_played(&conn, 3, None)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        // Equal play counts with no last play fall back to library order
+        assert_eq!(top, [ids[4], ids[0], ids[1]]);
+        assert_eq!(get_top_played(&conn, 100, None).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_get_recently_added_breaks_ties_newest_first() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = (1..=4)
+            .map(|i| {
+                let path = format!("/music/track{}.mp3", i);
+                add_track(&conn, &path, &TrackMetadata::default()).unwrap()
+            })
+            .collect();
+        conn.execute("UPDATE library SET added_date = datetime('now', '-1 day')", []).unwrap();
+
+        let recent: Vec<i64> = get_recently_added(&conn, 14, 2, None)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(recent, [ids[3], ids[2]]);
+    }
+
+    #[test]
+    fn test_default_favorite_list_exists() {
+        let conn = setup_test_db();
+
+        let lists = get_favorite_lists(&conn).unwrap();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].id, DEFAULT_FAVORITE_LIST_ID);
+        assert_eq!(lists[0].name, "Favorites");
+    }
+
+    #[test]
+    fn test_named_lists_are_independent() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/song.mp3", &TrackMetadata::default()).unwrap();
+
+        let workout = create_favorite_list(&conn, "Workout").unwrap().unwrap();
+        assert!(create_favorite_list(&conn, "Workout").unwrap().is_none());
+
+        add_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
+        assert!(add_favorite(&conn, workout.id, id).unwrap().is_some());
+
+        remove_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap();
+        assert!(!is_favorite(&conn, DEFAULT_FAVORITE_LIST_ID, id).unwrap().0);
+        assert!(is_favorite(&conn, workout.id, id).unwrap().0);
+
+        let result = get_favorites(&conn, workout.id, 10, 0).unwrap();
+        assert_eq!(result.total, 1);
+
+        let lists = get_favorite_lists(&conn).unwrap();
+        assert_eq!(lists[1].name, "Workout");
+        assert_eq!(lists[1].track_count, 1);
+    }
+
+    #[test]
+    fn test_delete_favorite_list() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/song.mp3", &TrackMetadata::default()).unwrap();
+        let chill = create_favorite_list(&conn, "Chill").unwrap().unwrap();
+        add_favorite(&conn, chill.id, id).unwrap();
+
+        assert!(delete_favorite_list(&conn, chill.id).unwrap());
+        assert!(!favorite_list_exists(&conn, chill.id).unwrap());
+        assert!(!is_favorite(&conn, chill.id, id).unwrap().0);
+
+        assert!(delete_favorite_list(&conn, DEFAULT_FAVORITE_LIST_ID).is_err());
+    }
+
+    #[test]
+    fn test_get_top_25_filtered_by_list() {
+        let conn = setup_test_db();
+        let listed = add_track(&conn, "/music/listed.mp3", &TrackMetadata::default()).unwrap();
+        let other = add_track(&conn, "/music/other.mp3", &TrackMetadata::default()).unwrap();
+        update_play_count(&conn, listed).unwrap();
+        update_play_count(&conn, other).unwrap();
+
+        let gym = create_favorite_list(&conn, "Gym").unwrap().unwrap();
+        add_favorite(&conn, gym.id, listed).unwrap();
+
+        let top = get_top_25(&conn, Some(gym.id)).unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].id, listed);
+        assert_eq!(get_top_25(&conn, None).unwrap().len(), 2);
+    }
 }
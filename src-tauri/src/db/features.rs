@@ -0,0 +1,182 @@
+//! Audio feature database operations.
+//!
+//! Features are filled in by the analyze pass and read back to rank tracks
+//! for "play similar". They are keyed by track ID and deleted with the track
+//! through the foreign key cascade.
+
+use rusqlite::{Connection, Row, params};
+
+use crate::db::{DbResult, TrackFeatures};
+
+fn row_to_features(row: &Row) -> rusqlite::Result<TrackFeatures> {
+    Ok(TrackFeatures {
+        bpm: row.get("bpm")?,
+        key: row
+            .get::<_, Option<i64>>("musical_key")?
+            .and_then(|key| u8::try_from(key).ok()),
+        loudness_db: row.get("loudness_db")?,
+        centroid_hz: row.get("centroid_hz")?,
+    })
+}
+
+/// Store a track's features, replacing any earlier ones
+///
+/// Returns false if the track doesn't exist.
+pub fn set_track_features(
+    conn: &Connection,
+    track_id: i64,
+    features: &TrackFeatures,
+) -> DbResult<bool> {
+    let stored = conn.execute(
+        "INSERT OR REPLACE INTO track_features
+             (track_id, bpm, musical_key, loudness_db, centroid_hz)
+         SELECT id, ?2, ?3, ?4, ?5 FROM library WHERE id = ?1",
+        params![
+            track_id,
+            features.bpm,
+            features.key,
+            features.loudness_db,
+            features.centroid_hz
+        ],
+    )?;
+    Ok(stored > 0)
+}
+
+/// Get a track's features, `None` if it hasn't been analyzed
+pub fn get_track_features(conn: &Connection, track_id: i64) -> DbResult<Option<TrackFeatures>> {
+    match conn.query_row(
+        "SELECT * FROM track_features WHERE track_id = ?",
+        [track_id],
+        row_to_features,
+    ) {
+        Ok(features) => Ok(Some(features)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the features of every analyzed track that isn't missing, by track ID
+pub fn get_all_track_features(conn: &Connection) -> DbResult<Vec<(i64, TrackFeatures)>> {
+    let mut stmt = conn.prepare(
+        "SELECT f.* FROM track_features f
+         JOIN library l ON l.id = f.track_id
+         WHERE l.missing = 0 OR l.missing IS NULL
+         ORDER BY f.track_id",
+    )?;
+    let features = stmt
+        .query_map([], |row| Ok((row.get("track_id")?, row_to_features(row)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(features)
+}
+
+/// Count present tracks with no stored features
+pub fn count_tracks_missing_features(conn: &Connection) -> DbResult<i64> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM library l
+         WHERE (l.missing = 0 OR l.missing IS NULL)
+           AND NOT EXISTS (SELECT 1 FROM track_features f WHERE f.track_id = l.id)",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Get the next batch of present tracks with no stored features, by ID after `after_id`
+///
+/// Paging by ID means tracks that can't be analyzed aren't fetched again.
+pub fn get_tracks_missing_features(
+    conn: &Connection,
+    after_id: i64,
+    limit: i64,
+) -> DbResult<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT l.id, l.filepath FROM library l
+         WHERE (l.missing = 0 OR l.missing IS NULL) AND l.id > ?
+           AND NOT EXISTS (SELECT 1 FROM track_features f WHERE f.track_id = l.id)
+         ORDER BY l.id
+         LIMIT ?",
+    )?;
+    let tracks = stmt
+        .query_map(params![after_id, limit], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(tracks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, delete_track, mark_track_missing};
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn features(bpm: Option<f64>, key: Option<u8>) -> TrackFeatures {
+        TrackFeatures {
+            bpm,
+            key,
+            loudness_db: -12.5,
+            centroid_hz: 1800.0,
+        }
+    }
+
+    #[test]
+    fn test_features_round_trip() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/a.flac", &TrackMetadata::default()).unwrap();
+        assert_eq!(get_track_features(&conn, id).unwrap(), None);
+
+        let first = features(Some(124.5), Some(21));
+        assert!(set_track_features(&conn, id, &first).unwrap());
+        assert_eq!(get_track_features(&conn, id).unwrap(), Some(first));
+
+        // Re-analysis replaces, and unknown tempo and key stay unknown
+        let second = features(None, None);
+        assert!(set_track_features(&conn, id, &second).unwrap());
+        assert_eq!(get_track_features(&conn, id).unwrap(), Some(second));
+
+        assert!(!set_track_features(&conn, 9999, &first).unwrap());
+    }
+
+    #[test]
+    fn test_missing_features_and_missing_tracks() {
+        let conn = setup_test_db();
+        let done = add_track(&conn, "/music/done.flac", &TrackMetadata::default()).unwrap();
+        let todo = add_track(&conn, "/music/todo.flac", &TrackMetadata::default()).unwrap();
+        let gone = add_track(&conn, "/music/gone.flac", &TrackMetadata::default()).unwrap();
+        set_track_features(&conn, done, &features(Some(100.0), Some(0))).unwrap();
+        set_track_features(&conn, gone, &features(Some(140.0), Some(7))).unwrap();
+        mark_track_missing(&conn, gone).unwrap();
+
+        assert_eq!(count_tracks_missing_features(&conn).unwrap(), 1);
+        assert_eq!(
+            get_tracks_missing_features(&conn, 0, 10).unwrap(),
+            vec![(todo, "/music/todo.flac".to_string())]
+        );
+        assert!(
+            get_tracks_missing_features(&conn, todo, 10)
+                .unwrap()
+                .is_empty()
+        );
+
+        let all: Vec<i64> = get_all_track_features(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        assert_eq!(all, vec![done]);
+
+        delete_track(&conn, done).unwrap();
+        assert!(get_all_track_features(&conn).unwrap().is_empty());
+        assert_eq!(get_track_features(&conn, done).unwrap(), None);
+    }
+}
@@ -0,0 +1,315 @@
+//! Play history database operations.
+//!
+//! Every play is recorded as its own row, so listening can be queried by time.
+//! `library.last_played` only keeps the latest play per track.
+
+use rusqlite::{Connection, params};
+
+use crate::db::{DbResult, PlayRecord, TopEntry, TopKind, TopList, TopRange};
+
+/// Record a play of a track at the current time
+pub fn record_play(conn: &Connection, track_id: i64) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO play_history (track_id)
+         SELECT id FROM library WHERE id = ?",
+        [track_id],
+    )?;
+    Ok(())
+}
+
+/// Record a play of a track at an explicit UTC time (`YYYY-MM-DD HH:MM:SS`)
+pub fn record_play_at(conn: &Connection, track_id: i64, played_at: &str) -> DbResult<()> {
+    conn.execute(
+        "INSERT INTO play_history (track_id, played_at)
+         SELECT id, ?2 FROM library WHERE id = ?1",
+        params![track_id, played_at],
+    )?;
+    Ok(())
+}
+
+/// Forget every recorded play of a track
+pub fn clear_track_history(conn: &Connection, track_id: i64) -> DbResult<usize> {
+    Ok(conn.execute("DELETE FROM play_history WHERE track_id = ?", [track_id])?)
+}
+
+/// Forget all recorded plays
+pub fn clear_all_history(conn: &Connection) -> DbResult<usize> {
+    Ok(conn.execute("DELETE FROM play_history", [])?)
+}
+
+/// Count recorded plays of a track
+pub fn get_track_play_history_count(conn: &Connection, track_id: i64) -> DbResult<i64> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) FROM play_history WHERE track_id = ?",
+        [track_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Recorded plays between two UTC times (`YYYY-MM-DD HH:MM:SS`), oldest first
+///
+/// Both ends are inclusive and either can be left open.
+pub fn get_plays_between(
+    conn: &Connection,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> DbResult<Vec<PlayRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT h.played_at, h.track_id, l.artist, l.title, l.album, l.duration
+         FROM play_history h
+         JOIN library l ON l.id = h.track_id
+         WHERE (?1 IS NULL OR h.played_at >= ?1) AND (?2 IS NULL OR h.played_at <= ?2)
+         ORDER BY h.played_at, h.id",
+    )?;
+    let plays = stmt
+        .query_map(params![from, to], |row| {
+            Ok(PlayRecord {
+                played_at: row.get(0)?,
+                track_id: row.get(1)?,
+                artist: row.get(2)?,
+                title: row.get(3)?,
+                album: row.get(4)?,
+                duration: row.get(5)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(plays)
+}
+
+/// Most played artists, albums, or genres within a range
+///
+/// Counts plays from history in the range. If the range has no history at all,
+/// falls back to the all-time `play_count` column so imported counts still
+/// show up. Ties are broken by name so the order is stable.
+pub fn get_top(conn: &Connection, kind: TopKind, range: TopRange, limit: i64) -> DbResult<TopList> {
+    let modifier = range.as_modifier();
+    let history_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM play_history
+         WHERE ?1 IS NULL OR played_at >= datetime('now', ?1)",
+        [modifier],
+        |row| row.get(0),
+    )?;
+
+    let fallback = history_count == 0;
+    let plays = if fallback {
+        "SELECT id AS track_id, play_count AS plays FROM library WHERE play_count > 0"
+    } else {
+        "SELECT track_id, COUNT(*) AS plays FROM play_history
+         WHERE ?1 IS NULL OR played_at >= datetime('now', ?1)
+         GROUP BY track_id"
+    };
+    let (name, artist) = match kind {
+        TopKind::Artist => ("l.artist", "NULL"),
+        TopKind::Album => ("l.album", "COALESCE(NULLIF(l.album_artist, ''), l.artist)"),
+        TopKind::Genre => ("l.genre", "NULL"),
+    };
+
+    // The bare p.track_id is taken from the row holding MAX(p.plays)
+    let sql = format!(
+        "WITH plays AS ({plays})
+         SELECT {name} AS name, {artist} AS artist, SUM(p.plays) AS play_count,
+                p.track_id, MAX(p.plays)
+         FROM plays p
+         JOIN library l ON l.id = p.track_id
+         WHERE {name} IS NOT NULL AND {name} != ''
+         GROUP BY 1, 2
+         ORDER BY play_count DESC, name COLLATE NOCASE ASC, artist COLLATE NOCASE ASC
+         LIMIT ?2"
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let items: Vec<TopEntry> = stmt
+        .query_map(params![modifier, limit], |row| {
+            Ok(TopEntry {
+                name: row.get("name")?,
+                artist: row.get("artist")?,
+                play_count: row.get("play_count")?,
+                track_id: row.get("track_id")?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(TopList { items, fallback })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, set_play_count};
+    use crate::db::{TrackMetadata, schema};
+
+    fn add(conn: &Connection, path: &str, artist: &str, album: &str, genre: &str) -> i64 {
+        let metadata = TrackMetadata {
+            artist: Some(artist.to_string()),
+            album: Some(album.to_string()),
+            genre: Some(genre.to_string()),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    /// UTC timestamp `days` ago in the stored `played_at` format
+    fn days_ago(days: i64) -> String {
+        (chrono::Utc::now() - chrono::Duration::days(days))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+
+    fn names(list: &TopList) -> Vec<(&str, i64)> {
+        list.items
+            .iter()
+            .map(|e| (e.name.as_str(), e.play_count))
+            .collect()
+    }
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_record_play_ignores_unknown_track() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/song.mp3", &TrackMetadata::default()).unwrap();
+
+        record_play(&conn, id).unwrap();
+        record_play_at(&conn, id, "2026-01-01 12:00:00").unwrap();
+        record_play(&conn, 9999).unwrap();
+
+        assert_eq!(get_track_play_history_count(&conn, id).unwrap(), 2);
+        assert_eq!(get_track_play_history_count(&conn, 9999).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_clear_history() {
+        let conn = setup_test_db();
+        let a = add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+        let b = add_track(&conn, "/music/b.mp3", &TrackMetadata::default()).unwrap();
+        record_play(&conn, a).unwrap();
+        record_play(&conn, b).unwrap();
+
+        assert_eq!(clear_track_history(&conn, a).unwrap(), 1);
+        assert_eq!(get_track_play_history_count(&conn, b).unwrap(), 1);
+
+        assert_eq!(clear_all_history(&conn).unwrap(), 1);
+        assert_eq!(get_track_play_history_count(&conn, b).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_plays_between_includes_only_the_range() {
+        let conn = setup_test_db();
+        let a = add(
+            &conn,
+            "/a.mp3",
+            "Low",
+            "Things We Lost in the Fire",
+            "Slowcore",
+        );
+        let b = add(&conn, "/b.mp3", "Codeine", "Frigid Stars", "Slowcore");
+        record_play_at(&conn, a, "2026-01-31 23:59:59").unwrap();
+        record_play_at(&conn, b, "2026-02-01 00:00:00").unwrap();
+        record_play_at(&conn, a, "2026-02-14 20:30:00").unwrap();
+        record_play_at(&conn, b, "2026-03-01 00:00:00").unwrap();
+
+        let plays = get_plays_between(
+            &conn,
+            Some("2026-02-01 00:00:00"),
+            Some("2026-02-28 23:59:59"),
+        )
+        .unwrap();
+        let found: Vec<(&str, i64)> = plays
+            .iter()
+            .map(|p| (p.played_at.as_str(), p.track_id))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("2026-02-01 00:00:00", b), ("2026-02-14 20:30:00", a)]
+        );
+        assert_eq!(plays[1].artist.as_deref(), Some("Low"));
+
+        assert_eq!(get_plays_between(&conn, None, None).unwrap().len(), 4);
+        assert_eq!(
+            get_plays_between(&conn, Some("2026-02-15 00:00:00"), None)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_get_top_by_kind() {
+        let conn = setup_test_db();
+        let a1 = add(&conn, "/a1.mp3", "Aphex Twin", "Drukqs", "Electronic");
+        let a2 = add(&conn, "/a2.mp3", "Aphex Twin", "Syro", "Electronic");
+        let b1 = add(&conn, "/b1.mp3", "Boards of Canada", "Geogaddi", "Ambient");
+        for _ in 0..3 {
+            record_play(&conn, a2).unwrap();
+        }
+        record_play(&conn, a1).unwrap();
+        record_play(&conn, b1).unwrap();
+        record_play(&conn, b1).unwrap();
+
+        let artists = get_top(&conn, TopKind::Artist, TopRange::All, 10).unwrap();
+        assert!(!artists.fallback);
+        assert_eq!(
+            names(&artists),
+            vec![("Aphex Twin", 4), ("Boards of Canada", 2)]
+        );
+        // Representative track is the most played in the group
+        assert_eq!(artists.items[0].track_id, a2);
+
+        let albums = get_top(&conn, TopKind::Album, TopRange::All, 10).unwrap();
+        assert_eq!(
+            names(&albums),
+            vec![("Syro", 3), ("Geogaddi", 2), ("Drukqs", 1)]
+        );
+        assert_eq!(albums.items[0].artist.as_deref(), Some("Aphex Twin"));
+
+        let genres = get_top(&conn, TopKind::Genre, TopRange::All, 1).unwrap();
+        assert_eq!(names(&genres), vec![("Electronic", 4)]);
+    }
+
+    #[test]
+    fn test_get_top_ties_sorted_by_name() {
+        let conn = setup_test_db();
+        let b = add(&conn, "/b.mp3", "beta", "B", "Rock");
+        let a = add(&conn, "/a.mp3", "Alpha", "A", "Rock");
+        record_play(&conn, b).unwrap();
+        record_play(&conn, a).unwrap();
+
+        let artists = get_top(&conn, TopKind::Artist, TopRange::All, 10).unwrap();
+        assert_eq!(names(&artists), vec![("Alpha", 1), ("beta", 1)]);
+    }
+
+    #[test]
+    fn test_get_top_windowed_range() {
+        let conn = setup_test_db();
+        let old = add(&conn, "/old.mp3", "Old Favorite", "Then", "Jazz");
+        let new = add(&conn, "/new.mp3", "New Favorite", "Now", "Pop");
+        for _ in 0..5 {
+            record_play_at(&conn, old, &days_ago(60)).unwrap();
+        }
+        record_play_at(&conn, new, &days_ago(2)).unwrap();
+
+        let week = get_top(&conn, TopKind::Artist, TopRange::Week, 10).unwrap();
+        assert_eq!(names(&week), vec![("New Favorite", 1)]);
+
+        let year = get_top(&conn, TopKind::Artist, TopRange::Year, 10).unwrap();
+        assert_eq!(names(&year), vec![("Old Favorite", 5), ("New Favorite", 1)]);
+    }
+
+    #[test]
+    fn test_get_top_falls_back_to_play_count() {
+        let conn = setup_test_db();
+        let id = add(&conn, "/imported.mp3", "Imported", "Counts", "Folk");
+        set_play_count(&conn, id, 12, false).unwrap();
+
+        let top = get_top(&conn, TopKind::Artist, TopRange::Month, 10).unwrap();
+        assert!(top.fallback);
+        assert_eq!(names(&top), vec![("Imported", 12)]);
+    }
+}
@@ -2,17 +2,21 @@
 //!
 //! CRUD operations for the music library (tracks table).
 
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rusqlite::{params, Connection, Row};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::Path;
 
+use crate::db::date_filter::DateFilter;
 use crate::db::{
-    DbResult, FileFingerprint, LibrarySortColumn, LibraryStats, PaginatedResult, SortOrder, Track,
-    TrackMetadata,
+    album_overrides, artwork_misses, changes, history, tags, watched, Album, Chapter, DbError,
+    DbResult, FileFingerprint, IncompleteAlbum, LibrarySortColumn, LibraryStats, PaginatedResult,
+    SizeEntry, SizeGroup, SortOrder, TagMatch, Track, TrackMetadata,
 };
 
 /// Map a database row to a Track struct
-fn row_to_track(row: &Row) -> rusqlite::Result<Track> {
+pub(crate) fn row_to_track(row: &Row) -> rusqlite::Result<Track> {
     Ok(Track {
         id: row.get("id")?,
         filepath: row.get("filepath")?,
@@ -22,6 +26,8 @@ fn row_to_track(row: &Row) -> rusqlite::Result<Track> {
         album_artist: row.get("album_artist")?,
         track_number: row.get("track_number")?,
         track_total: row.get("track_total")?,
+        disc_number: row.get("disc_number")?,
+        disc_total: row.get("disc_total")?,
         date: row.get("date")?,
         duration: row.get("duration")?,
         file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
@@ -42,6 +48,16 @@ pub struct LibraryQuery {
     pub search: Option<String>,
     pub artist: Option<String>,
     pub album: Option<String>,
+    /// Custom tags to filter on; blank and repeated tags are ignored
+    pub tags: Vec<String>,
+    /// Whether a track needs any or all of `tags`
+    pub tag_match: TagMatch,
+    /// Only tracks in at least one favorites list
+    pub favorites_only: bool,
+    /// Only tracks rated at least this many stars
+    pub min_rating: Option<u8>,
+    /// Date conditions, all of which must hold
+    pub date_filters: Vec<DateFilter>,
     pub sort_by: LibrarySortColumn,
     pub sort_order: SortOrder,
     pub limit: i64,
@@ -57,13 +73,13 @@ impl LibraryQuery {
     }
 }
 
-/// Get tracks from the library with filtering and pagination
-pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<PaginatedResult<Track>> {
-    let mut conditions = Vec::new();
+/// Build the WHERE clause and parameters for a library query's filters
+fn library_filter(query: &LibraryQuery) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions: Vec<String> = Vec::new();
     let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
 
     if let Some(search) = &query.search {
-        conditions.push("(title LIKE ? OR artist LIKE ? OR album LIKE ?)");
+        conditions.push("(title LIKE ? OR artist LIKE ? OR album LIKE ?)".to_string());
         let search_term = format!("%{}%", search);
         params_vec.push(Box::new(search_term.clone()));
         params_vec.push(Box::new(search_term.clone()));
@@ -71,17 +87,51 @@ pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<Pagin
     }
 
     if let Some(artist) = &query.artist {
-        conditions.push("artist = ?");
+        conditions.push("artist = ?".to_string());
         params_vec.push(Box::new(artist.clone()));
     }
 
     if let Some(album) = &query.album {
-        conditions.push("album = ?");
+        conditions.push("album = ?".to_string());
         params_vec.push(Box::new(album.clone()));
     }
 
+    let tags = tags::normalize_tags(&query.tags);
+    if !tags.is_empty() {
+        let placeholders = vec!["?"; tags.len()].join(",");
+        let having = match query.tag_match {
+            TagMatch::Any => String::new(),
+            TagMatch::All => format!(" GROUP BY track_id HAVING COUNT(*) = {}", tags.len()),
+        };
+        conditions.push(format!(
+            "id IN (SELECT track_id FROM tags WHERE tag IN ({}){})",
+            placeholders, having
+        ));
+        for tag in tags {
+            params_vec.push(Box::new(tag));
+        }
+    }
+
+    if query.favorites_only {
+        conditions.push("id IN (SELECT track_id FROM favorites)".to_string());
+    }
+
+    if let Some(stars) = query.min_rating {
+        conditions.push("rating >= ?".to_string());
+        params_vec.push(Box::new(stars));
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    for filter in &query.date_filters {
+        let (condition, values) = filter.to_sql(now);
+        conditions.push(condition);
+        for value in values {
+            params_vec.push(Box::new(value));
+        }
+    }
+
     // Always filter out missing tracks from library view
-    conditions.push("(missing = 0 OR missing IS NULL)");
+    conditions.push("(missing = 0 OR missing IS NULL)".to_string());
 
     let where_clause = if conditions.is_empty() {
         String::new()
@@ -89,6 +139,13 @@ pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<Pagin
         format!("WHERE {}", conditions.join(" AND "))
     };
 
+    (where_clause, params_vec)
+}
+
+/// Get tracks from the library with filtering and pagination
+pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<PaginatedResult<Track>> {
+    let (where_clause, params_vec) = library_filter(query);
+
     // Get total count
     let count_sql = format!("SELECT COUNT(*) FROM library {}", where_clause);
     let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
@@ -97,16 +154,15 @@ pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<Pagin
     // Get tracks
     let sql = format!(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library
          {}
-         ORDER BY {} {}
+         ORDER BY {}
          LIMIT ? OFFSET ?",
         where_clause,
-        query.sort_by.as_sql(),
-        query.sort_order.as_sql()
+        query.sort_by.order_by_sql(query.sort_order)
     );
 
     let mut all_params: Vec<&dyn rusqlite::ToSql> = params_refs;
@@ -125,11 +181,127 @@ pub fn get_all_tracks(conn: &Connection, query: &LibraryQuery) -> DbResult<Pagin
     })
 }
 
+/// Get the filepaths of all non-missing tracks matching a query's filters
+///
+/// Sorting and pagination are ignored; paths are returned in insertion order.
+pub fn get_filtered_filepaths(conn: &Connection, query: &LibraryQuery) -> DbResult<Vec<String>> {
+    let (where_clause, params_vec) = library_filter(query);
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    let sql = format!("SELECT filepath FROM library {} ORDER BY id", where_clause);
+    let mut stmt = conn.prepare(&sql)?;
+    let filepaths: Vec<String> = stmt
+        .query_map(params_refs.as_slice(), |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(filepaths)
+}
+
+/// Modulus of the seeded sort key in `get_sampled_filepaths`, the prime 2^31 - 1
+const SAMPLE_PRIME: i64 = 2_147_483_647;
+
+/// Get a seeded random sample of up to `limit` filepaths matching a query's filters
+///
+/// The sample is picked in SQL by sorting on `(id * a + b) mod SAMPLE_PRIME`,
+/// with `a` and `b` drawn from `seed`, so only the sampled paths are loaded.
+/// With `whole_albums`, each album sorts by the key of its first track, so
+/// albums are sampled whole, except that the last one may be cut short.
+/// Paths come back in sample order.
+pub fn get_sampled_filepaths(
+    conn: &Connection,
+    query: &LibraryQuery,
+    seed: u64,
+    limit: usize,
+    whole_albums: bool,
+) -> DbResult<Vec<String>> {
+    let (where_clause, params_vec) = library_filter(query);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let multiplier: i64 = rng.random_range(1..SAMPLE_PRIME);
+    let offset: i64 = rng.random_range(0..SAMPLE_PRIME);
+    let limit = limit as i64;
+
+    // Tracks without an album are albums of their own
+    let pick_id = if whole_albums {
+        "CASE WHEN album IS NULL OR album = '' THEN id
+              ELSE MIN(id) OVER (PARTITION BY COALESCE(NULLIF(album_artist, ''), artist, ''), album)
+         END"
+    } else {
+        "id"
+    };
+    let sql = format!(
+        "SELECT filepath FROM (
+             SELECT filepath, id, {} AS pick_id FROM library {}
+         )
+         ORDER BY (pick_id % ? * ? + ?) % ?, pick_id, id
+         LIMIT ?",
+        pick_id, where_clause
+    );
+
+    let mut all_params: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+    all_params.extend([
+        &SAMPLE_PRIME as &dyn rusqlite::ToSql,
+        &multiplier,
+        &offset,
+        &SAMPLE_PRIME,
+        &limit,
+    ]);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let filepaths = stmt
+        .query_map(all_params.as_slice(), |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+
+    Ok(filepaths)
+}
+
+/// Get non-missing tracks whose title, artist, or album contains any fragment
+///
+/// Used as a cheap prefilter before fuzzy scoring.
+pub fn get_search_candidates(
+    conn: &Connection,
+    fragments: &[String],
+    limit: i64,
+) -> DbResult<Vec<Track>> {
+    if fragments.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conditions = vec!["(title LIKE ? OR artist LIKE ? OR album LIKE ?)"; fragments.len()];
+    let sql = format!(
+        "SELECT id, filepath, title, artist, album, album_artist,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
+                play_count, last_played, added_date, missing, last_seen_at,
+                file_mtime_ns, file_inode, content_hash
+         FROM library
+         WHERE (missing = 0 OR missing IS NULL) AND ({})
+         LIMIT ?",
+        conditions.join(" OR ")
+    );
+
+    let patterns: Vec<String> = fragments.iter().map(|f| format!("%{}%", f)).collect();
+    let mut params_vec: Vec<&dyn rusqlite::ToSql> = Vec::new();
+    for pattern in &patterns {
+        params_vec.push(pattern);
+        params_vec.push(pattern);
+        params_vec.push(pattern);
+    }
+    params_vec.push(&limit);
+
+    let mut stmt = conn.prepare(&sql)?;
+    let tracks: Vec<Track> = stmt
+        .query_map(params_vec.as_slice(), row_to_track)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(tracks)
+}
+
 /// Get a single track by ID
 pub fn get_track_by_id(conn: &Connection, track_id: i64) -> DbResult<Option<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library WHERE id = ?",
@@ -147,7 +319,7 @@ pub fn get_track_by_id(conn: &Connection, track_id: i64) -> DbResult<Option<Trac
 pub fn get_track_by_filepath(conn: &Connection, filepath: &str) -> DbResult<Option<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library WHERE filepath = ?",
@@ -209,14 +381,44 @@ pub fn get_all_fingerprints(conn: &Connection) -> DbResult<HashMap<String, FileF
     Ok(fingerprints)
 }
 
+/// Normalize an added date to SQLite's `CURRENT_TIMESTAMP` format
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD HH:MM:SS` (taken as UTC), and bare
+/// `YYYY-MM-DD` dates, and returns UTC `YYYY-MM-DD HH:MM:SS`. Storing one
+/// format keeps imported and scanned dates sorting together as text.
+pub fn normalize_added_date(date: &str) -> DbResult<String> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+    let date = date.trim();
+    let parsed = DateTime::parse_from_rfc3339(date)
+        .map(|dt| dt.with_timezone(&Utc).naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| DbError::Constraint(format!("Invalid added date: {}", date)))?;
+    Ok(parsed.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// The normalized added date to insert for `metadata`, or `None` for now
+fn insert_added_date(metadata: &TrackMetadata) -> DbResult<Option<String>> {
+    metadata.added_date.as_deref().map(normalize_added_date).transpose()
+}
+
 /// Add a track to the library
+///
+/// `metadata.added_date` sets when the track counts as added; it defaults to now.
 pub fn add_track(conn: &Connection, filepath: &str, metadata: &TrackMetadata) -> DbResult<i64> {
+    let added_date = insert_added_date(metadata)?;
     conn.execute(
         "INSERT INTO library
          (filepath, title, artist, album, album_artist,
-          track_number, track_total, date, duration, file_size, file_mtime_ns,
-          file_inode, content_hash, missing)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
+          track_number, track_total, disc_number, disc_total, date, genre, duration,
+          file_size, file_mtime_ns, file_inode, content_hash, added_date, missing)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                 COALESCE(?, CURRENT_TIMESTAMP), 0)",
         params![
             filepath,
             metadata.title,
@@ -225,16 +427,25 @@ pub fn add_track(conn: &Connection, filepath: &str, metadata: &TrackMetadata) ->
             metadata.album_artist,
             metadata.track_number,
             metadata.track_total,
+            metadata.disc_number,
+            metadata.disc_total,
             metadata.date,
+            metadata.genre,
             metadata.duration,
             metadata.file_size.unwrap_or(0),
             metadata.file_mtime_ns,
             metadata.file_inode.map(|v| v as i64),
             metadata.content_hash,
+            added_date,
         ],
     )?;
 
-    Ok(conn.last_insert_rowid())
+    let track_id = conn.last_insert_rowid();
+    if !metadata.chapters.is_empty() {
+        set_track_chapters(conn, track_id, &metadata.chapters)?;
+    }
+
+    Ok(track_id)
 }
 
 /// Add multiple tracks in a single transaction
@@ -246,13 +457,15 @@ pub fn add_tracks_bulk(conn: &Connection, tracks: &[(String, TrackMetadata)]) ->
     let mut stmt = conn.prepare(
         "INSERT INTO library
          (filepath, title, artist, album, album_artist,
-          track_number, track_total, date, duration, file_size, file_mtime_ns,
-          file_inode, content_hash, missing)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0)",
+          track_number, track_total, disc_number, disc_total, date, genre, duration,
+          file_size, file_mtime_ns, file_inode, content_hash, added_date, missing)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                 COALESCE(?, CURRENT_TIMESTAMP), 0)",
     )?;
 
     let mut count = 0;
     for (filepath, metadata) in tracks {
+        let added_date = insert_added_date(metadata)?;
         stmt.execute(params![
             filepath,
             metadata.title,
@@ -261,13 +474,20 @@ pub fn add_tracks_bulk(conn: &Connection, tracks: &[(String, TrackMetadata)]) ->
             metadata.album_artist,
             metadata.track_number,
             metadata.track_total,
+            metadata.disc_number,
+            metadata.disc_total,
             metadata.date,
+            metadata.genre,
             metadata.duration,
             metadata.file_size.unwrap_or(0),
             metadata.file_mtime_ns,
             metadata.file_inode.map(|v| v as i64),
             metadata.content_hash,
+            added_date,
         ])?;
+        if !metadata.chapters.is_empty() {
+            set_track_chapters(conn, conn.last_insert_rowid(), &metadata.chapters)?;
+        }
         count += 1;
     }
 
@@ -288,7 +508,10 @@ pub fn update_tracks_bulk(conn: &Connection, tracks: &[(String, TrackMetadata)])
             album_artist = ?,
             track_number = ?,
             track_total = ?,
+            disc_number = ?,
+            disc_total = ?,
             date = ?,
+            genre = ?,
             duration = ?,
             file_size = ?,
             file_mtime_ns = ?
@@ -304,12 +527,22 @@ pub fn update_tracks_bulk(conn: &Connection, tracks: &[(String, TrackMetadata)])
             metadata.album_artist,
             metadata.track_number,
             metadata.track_total,
+            metadata.disc_number,
+            metadata.disc_total,
             metadata.date,
+            metadata.genre,
             metadata.duration,
             metadata.file_size.unwrap_or(0),
             metadata.file_mtime_ns,
             filepath,
         ])?;
+        if rows > 0 {
+            let track_id: i64 =
+                conn.query_row("SELECT id FROM library WHERE filepath = ?", [filepath], |row| {
+                    row.get(0)
+                })?;
+            set_track_chapters(conn, track_id, &metadata.chapters)?;
+        }
         count += rows as i64;
     }
 
@@ -373,7 +606,10 @@ pub fn update_track_metadata(
             album_artist = ?,
             track_number = ?,
             track_total = ?,
+            disc_number = ?,
+            disc_total = ?,
             date = ?,
+            genre = ?,
             duration = ?,
             file_size = ?,
             file_mtime_ns = ?
@@ -385,7 +621,10 @@ pub fn update_track_metadata(
             metadata.album_artist,
             metadata.track_number,
             metadata.track_total,
+            metadata.disc_number,
+            metadata.disc_total,
             metadata.date,
+            metadata.genre,
             metadata.duration,
             metadata.file_size.unwrap_or(0),
             metadata.file_mtime_ns,
@@ -393,10 +632,47 @@ pub fn update_track_metadata(
         ],
     )?;
 
+    if updated > 0 {
+        set_track_chapters(conn, track_id, &metadata.chapters)?;
+    }
+
     Ok(updated > 0)
 }
 
-/// Increment play count for a track
+/// Replace the chapter markers of a track
+pub fn set_track_chapters(conn: &Connection, track_id: i64, chapters: &[Chapter]) -> DbResult<()> {
+    conn.execute("DELETE FROM track_chapters WHERE track_id = ?", [track_id])?;
+
+    let mut stmt =
+        conn.prepare("INSERT INTO track_chapters (track_id, start_ms, title) VALUES (?, ?, ?)")?;
+    for chapter in chapters {
+        stmt.execute(params![track_id, chapter.start_ms as i64, chapter.title])?;
+    }
+
+    Ok(())
+}
+
+/// Get the chapter markers of a track, ordered by start
+pub fn get_track_chapters(conn: &Connection, track_id: i64) -> DbResult<Vec<Chapter>> {
+    let mut stmt = conn.prepare(
+        "SELECT start_ms, title FROM track_chapters
+         WHERE track_id = ?
+         ORDER BY start_ms, id",
+    )?;
+    let chapters = stmt
+        .query_map([track_id], |row| {
+            Ok(Chapter {
+                start_ms: row.get::<_, i64>(0)?.max(0) as u64,
+                title: row.get(1)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(chapters)
+}
+
+/// Increment play count for a track and record the play in history
 pub fn update_play_count(conn: &Connection, track_id: i64) -> DbResult<Option<Track>> {
     conn.execute(
         "UPDATE library SET
@@ -405,12 +681,134 @@ pub fn update_play_count(conn: &Connection, track_id: i64) -> DbResult<Option<Tr
          WHERE id = ?",
         [track_id],
     )?;
+    history::record_play(conn, track_id)?;
 
     get_track_by_id(conn, track_id)
 }
 
+/// Set a track's play count to an explicit value
+///
+/// A count of zero (or `clear_last_played`) also clears `last_played` and the
+/// track's play history, so a track is never reported as played without any
+/// plays.
+pub fn set_play_count(
+    conn: &Connection,
+    track_id: i64,
+    count: i64,
+    clear_last_played: bool,
+) -> DbResult<Option<Track>> {
+    let clear = clear_last_played || count == 0;
+    conn.execute(
+        "UPDATE library SET
+            play_count = ?1,
+            last_played = CASE WHEN ?2 THEN NULL ELSE last_played END
+         WHERE id = ?3",
+        params![count, clear, track_id],
+    )?;
+    if clear {
+        history::clear_track_history(conn, track_id)?;
+    }
+
+    get_track_by_id(conn, track_id)
+}
+
+/// Correct when a track was added to the library
+pub fn set_added_date(conn: &Connection, track_id: i64, date: &str) -> DbResult<Option<Track>> {
+    let date = normalize_added_date(date)?;
+    conn.execute(
+        "UPDATE library SET added_date = ? WHERE id = ?",
+        params![date, track_id],
+    )?;
+
+    get_track_by_id(conn, track_id)
+}
+
+/// Reset play counts, last played timestamps, and play history for every track
+///
+/// Returns the IDs of tracks that had plays recorded.
+pub fn reset_all_play_counts(conn: &Connection) -> DbResult<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM library WHERE play_count != 0 OR last_played IS NOT NULL",
+    )?;
+    let ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    conn.execute(
+        "UPDATE library SET play_count = 0, last_played = NULL
+         WHERE play_count != 0 OR last_played IS NOT NULL",
+        [],
+    )?;
+    history::clear_all_history(conn)?;
+
+    Ok(ids)
+}
+
 /// Get library statistics
+///
+/// Served from the `library_stats` cache while it was computed at the current
+/// library change sequence; any library write since makes it stale, and the
+/// stats are then recounted and cached again.
 pub fn get_library_stats(conn: &Connection) -> DbResult<LibraryStats> {
+    let seq = changes::get_change_seq(conn)?;
+    let cached = conn.query_row(
+        "SELECT total_tracks, total_duration, total_size, total_artists, total_albums,
+                missing_tracks, tracks_without_artwork, tracks_missing_fingerprints,
+                potential_duplicates
+         FROM library_stats WHERE id = 1 AND change_seq = ?",
+        [seq],
+        |row| {
+            Ok(LibraryStats {
+                total_tracks: row.get(0)?,
+                total_duration: row.get(1)?,
+                total_size: row.get(2)?,
+                total_artists: row.get(3)?,
+                total_albums: row.get(4)?,
+                missing_tracks: row.get(5)?,
+                tracks_without_artwork: row.get(6)?,
+                tracks_missing_fingerprints: row.get(7)?,
+                potential_duplicates: row.get(8)?,
+            })
+        },
+    );
+    match cached {
+        Ok(stats) => Ok(stats),
+        Err(rusqlite::Error::QueryReturnedNoRows) => recompute_library_stats(conn),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Recount library statistics and cache them
+///
+/// The cache is stamped with the change sequence read before counting, so a
+/// write that lands mid-count leaves it stale rather than wrong.
+pub fn recompute_library_stats(conn: &Connection) -> DbResult<LibraryStats> {
+    let seq = changes::get_change_seq(conn)?;
+    let stats = count_library_stats(conn)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO library_stats (id, change_seq, total_tracks, total_duration,
+            total_size, total_artists, total_albums, missing_tracks, tracks_without_artwork,
+            tracks_missing_fingerprints, potential_duplicates)
+         VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            seq,
+            stats.total_tracks,
+            stats.total_duration,
+            stats.total_size,
+            stats.total_artists,
+            stats.total_albums,
+            stats.missing_tracks,
+            stats.tracks_without_artwork,
+            stats.tracks_missing_fingerprints,
+            stats.potential_duplicates,
+        ],
+    )?;
+    Ok(stats)
+}
+
+/// Count library statistics straight from the library table
+fn count_library_stats(conn: &Connection) -> DbResult<LibraryStats> {
     // Only count non-missing tracks
     let total_tracks: i64 = conn.query_row(
         "SELECT COUNT(*) FROM library WHERE (missing = 0 OR missing IS NULL)",
@@ -443,15 +841,417 @@ pub fn get_library_stats(conn: &Connection) -> DbResult<LibraryStats> {
         |row| row.get(0),
     )?;
 
+    // Health indicators in a single pass; missing tracks are excluded from the
+    // other counts so they line up with the main library view
+    let (missing_tracks, tracks_without_artwork, tracks_missing_fingerprints): (i64, i64, i64) =
+        conn.query_row(
+            "SELECT
+                COALESCE(SUM(CASE WHEN missing != 0 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN (missing = 0 OR missing IS NULL)
+                    AND has_artwork = 0 THEN 1 ELSE 0 END), 0),
+                COALESCE(SUM(CASE WHEN (missing = 0 OR missing IS NULL)
+                    AND (file_inode IS NULL OR content_hash IS NULL) THEN 1 ELSE 0 END), 0)
+             FROM library",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+
+    let potential_duplicates = count_potential_duplicates(conn)?;
+
     Ok(LibraryStats {
         total_tracks,
         total_duration,
         total_size,
         total_artists,
         total_albums,
+        missing_tracks,
+        tracks_without_artwork,
+        tracks_missing_fingerprints,
+        potential_duplicates,
     })
 }
 
+/// Get the disk usage of present tracks grouped by artist, album, or folder
+///
+/// Largest groups first, ties broken by name. Tracks with no known size count
+/// as zero bytes; `update_file_sizes` fills those in from disk.
+pub fn get_size_breakdown(conn: &Connection, group: SizeGroup) -> DbResult<Vec<SizeEntry>> {
+    let (name, artist) = match group {
+        SizeGroup::Artist => ("NULLIF(artist, '')", "NULL"),
+        SizeGroup::Album => ("NULLIF(album, '')", "COALESCE(NULLIF(album_artist, ''), artist)"),
+        SizeGroup::Folder => return get_folder_size_breakdown(conn),
+    };
+
+    let sql = format!(
+        "SELECT {name} AS name, {artist} AS artist,
+                COALESCE(SUM(file_size), 0) AS total_size, COUNT(*) AS track_count
+         FROM library
+         WHERE (missing = 0 OR missing IS NULL)
+         GROUP BY 1, 2
+         ORDER BY total_size DESC, name COLLATE NOCASE ASC, artist COLLATE NOCASE ASC"
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok(SizeEntry {
+                name: row.get("name")?,
+                artist: row.get("artist")?,
+                total_size: row.get("total_size")?,
+                track_count: row.get("track_count")?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(entries)
+}
+
+/// Folder breakdown, grouping each file by `size_folder`
+fn get_folder_size_breakdown(conn: &Connection) -> DbResult<Vec<SizeEntry>> {
+    let roots: Vec<String> = watched::get_watched_folders(conn)?
+        .into_iter()
+        .map(|folder| folder.path)
+        .collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT filepath, COALESCE(file_size, 0) FROM library
+         WHERE (missing = 0 OR missing IS NULL)",
+    )?;
+    let mut folders: HashMap<String, (i64, i64)> = HashMap::new();
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))? {
+        let (filepath, size) = row?;
+        let totals = folders.entry(size_folder(&filepath, &roots)).or_default();
+        totals.0 += size;
+        totals.1 += 1;
+    }
+
+    let mut entries: Vec<SizeEntry> = folders
+        .into_iter()
+        .map(|(folder, (total_size, track_count))| SizeEntry {
+            name: Some(folder),
+            artist: None,
+            total_size,
+            track_count,
+        })
+        .collect();
+    entries.sort_by(|a, b| b.total_size.cmp(&a.total_size).then_with(|| a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// The folder a file's size counts towards
+///
+/// Under a watched folder that is the top-level directory below it (the
+/// watched folder itself for files directly inside); elsewhere it is the
+/// file's parent directory. The deepest matching watched folder wins.
+fn size_folder(filepath: &str, roots: &[String]) -> String {
+    let path = Path::new(filepath);
+    let root = roots
+        .iter()
+        .map(Path::new)
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count());
+
+    let folder = match root {
+        Some(root) => {
+            let mut rest = path.strip_prefix(root).unwrap_or(path).components();
+            match (rest.next(), rest.next()) {
+                (Some(top), Some(_)) => root.join(top),
+                _ => root.to_path_buf(),
+            }
+        }
+        None => path.parent().unwrap_or(path).to_path_buf(),
+    };
+    folder.to_string_lossy().into_owned()
+}
+
+/// Count non-missing tracks that share an inode or content hash with an
+/// earlier non-missing track (the rows a duplicate merge would remove).
+///
+/// Uses the same grouping keys as `find_duplicates_by_inode` and
+/// `find_duplicates_by_content_hash`, but counts in one query.
+fn count_potential_duplicates(conn: &Connection) -> DbResult<i64> {
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM library l
+         WHERE (l.missing = 0 OR l.missing IS NULL)
+           AND (
+             (l.file_inode IS NOT NULL AND EXISTS (
+                SELECT 1 FROM library o
+                WHERE o.file_inode = l.file_inode AND o.id < l.id
+                  AND (o.missing = 0 OR o.missing IS NULL)))
+             OR (l.content_hash IS NOT NULL AND EXISTS (
+                SELECT 1 FROM library o
+                WHERE o.content_hash = l.content_hash AND o.id < l.id
+                  AND (o.missing = 0 OR o.missing IS NULL)))
+           )",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Record whether artwork was found for a track (feeds library health stats)
+pub fn set_track_has_artwork(conn: &Connection, track_id: i64, has_artwork: bool) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET has_artwork = ?1
+         WHERE id = ?2 AND (has_artwork IS NULL OR has_artwork != ?1)",
+        params![has_artwork as i64, track_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Present tracks of an album that may have artwork, as `(id, filepath)`
+///
+/// Albums are matched by `artwork_misses::track_album_key`. Tracks known to
+/// have artwork come first, then unchecked ones; tracks already found to have
+/// none are left out.
+pub fn get_album_artwork_candidates(
+    conn: &Connection,
+    album_key: &str,
+) -> DbResult<Vec<(i64, String)>> {
+    // Keys are lowercased in Rust, which SQLite's ASCII-only lower() can't match
+    let mut stmt = conn.prepare(
+        "SELECT id, filepath, album_artist, artist, album FROM library
+         WHERE (missing = 0 OR missing IS NULL) AND TRIM(album) != ''
+           AND (has_artwork IS NULL OR has_artwork = 1)
+         ORDER BY has_artwork IS NULL, filepath",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        let album_artist: Option<String> = row.get(2)?;
+        let artist: Option<String> = row.get(3)?;
+        let album: String = row.get(4)?;
+        let key = artwork_misses::track_album_key(
+            album_artist.as_deref(),
+            artist.as_deref(),
+            &album,
+        );
+        Ok((row.get(0)?, row.get(1)?, key))
+    })?;
+
+    let mut tracks = Vec::new();
+    for row in rows {
+        let (id, filepath, key): (i64, String, String) = row?;
+        if key == album_key {
+            tracks.push((id, filepath));
+        }
+    }
+    Ok(tracks)
+}
+
+/// Highest track number or total `find_incomplete_albums` takes as real
+///
+/// Larger values are usually dates or other junk in the tag, and would
+/// report thousands of missing tracks.
+const MAX_TRACK_NUMBER: i64 = 999;
+
+/// Find album discs with tracks missing from their track-number sequence
+///
+/// Tracks are grouped by `artwork_misses::track_album_key` and disc number.
+/// A disc is expected to run from 1 to its tagged track total, or to its
+/// highest track number when the total is untagged. Tracks without a usable
+/// track number and discs with none at all are left out; tracks marked
+/// missing count as absent. Numbers and totals above `MAX_TRACK_NUMBER` are
+/// ignored.
+pub fn find_incomplete_albums(conn: &Connection) -> DbResult<Vec<IncompleteAlbum>> {
+    struct Disc {
+        album: String,
+        artist: Option<String>,
+        track_total: i64,
+        numbers: BTreeSet<i64>,
+    }
+
+    // CAST reads the leading number, so "03/12" is 3 and untagged values are 0
+    let mut stmt = conn.prepare(
+        "SELECT album_artist, artist, album, COALESCE(disc_number, 1),
+                CAST(track_number AS INTEGER), COALESCE(CAST(track_total AS INTEGER), 0)
+         FROM library
+         WHERE (missing = 0 OR missing IS NULL) AND TRIM(album) != ''",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<i64>>(4)?,
+            row.get::<_, i64>(5)?,
+        ))
+    })?;
+
+    let mut discs: BTreeMap<(String, i64), Disc> = BTreeMap::new();
+    for row in rows {
+        let (album_artist, artist, album, disc_number, track_number, track_total) = row?;
+        let key = artwork_misses::track_album_key(
+            album_artist.as_deref(),
+            artist.as_deref(),
+            &album,
+        );
+        let disc = discs.entry((key, disc_number)).or_insert_with(|| Disc {
+            artist: album_artist.filter(|a| !a.trim().is_empty()).or(artist),
+            album,
+            track_total: 0,
+            numbers: BTreeSet::new(),
+        });
+        if track_total <= MAX_TRACK_NUMBER {
+            disc.track_total = disc.track_total.max(track_total);
+        }
+        if let Some(number) = track_number.filter(|n| (1..=MAX_TRACK_NUMBER).contains(n)) {
+            disc.numbers.insert(number);
+        }
+    }
+
+    let incomplete = discs
+        .into_iter()
+        .filter_map(|((album_key, disc_number), disc)| {
+            let highest = *disc.numbers.last()?;
+            let expected_total = disc.track_total.max(highest);
+            let missing_numbers: Vec<i64> = (1..=expected_total)
+                .filter(|n| !disc.numbers.contains(n))
+                .collect();
+            (!missing_numbers.is_empty()).then_some(IncompleteAlbum {
+                album_key,
+                album: disc.album,
+                artist: disc.artist,
+                disc_number,
+                expected_total,
+                present_count: disc.numbers.len() as i64,
+                missing_numbers,
+            })
+        })
+        .collect();
+    Ok(incomplete)
+}
+
+/// Get every album of present tracks, with album overrides applied
+///
+/// Tracks are grouped by `artwork_misses::track_album_key`. An album's year
+/// is the earliest year in its tracks' dates. Overrides only change what is
+/// shown; the album key stays that of the tracks. Sorted by artist, then album.
+pub fn get_albums(conn: &Connection) -> DbResult<Vec<Album>> {
+    let mut stmt = conn.prepare(
+        "SELECT album_artist, artist, album, date, COALESCE(duration, 0)
+         FROM library
+         WHERE (missing = 0 OR missing IS NULL) AND TRIM(album) != ''",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, f64>(4)?,
+        ))
+    })?;
+
+    let mut albums: HashMap<String, Album> = HashMap::new();
+    for row in rows {
+        let (album_artist, artist, album, date, duration) = row?;
+        let album_key = artwork_misses::track_album_key(
+            album_artist.as_deref(),
+            artist.as_deref(),
+            &album,
+        );
+        let year = date
+            .as_deref()
+            .and_then(|d| d.trim().get(..4))
+            .filter(|y| y.chars().all(|c| c.is_ascii_digit()))
+            .map(str::to_string);
+
+        let entry = albums.entry(album_key.clone()).or_insert_with(|| Album {
+            album_key,
+            album,
+            artist: album_artist.filter(|a| !a.trim().is_empty()).or(artist),
+            year: None,
+            track_count: 0,
+            total_duration: 0.0,
+            overridden: false,
+        });
+        entry.track_count += 1;
+        entry.total_duration += duration;
+        if let Some(year) = year
+            && entry.year.as_ref().is_none_or(|current| &year < current)
+        {
+            entry.year = Some(year);
+        }
+    }
+
+    for (album_key, value) in album_overrides::get_album_overrides(conn)? {
+        if let Some(album) = albums.get_mut(&album_key) {
+            album.overridden = true;
+            if let Some(name) = value.album {
+                album.album = name;
+            }
+            if let Some(artist) = value.album_artist {
+                album.artist = Some(artist);
+            }
+            if let Some(year) = value.year {
+                album.year = Some(year);
+            }
+        }
+    }
+
+    let mut albums: Vec<Album> = albums.into_values().collect();
+    albums.sort_by_cached_key(|a| {
+        (
+            a.artist.as_deref().unwrap_or("").to_lowercase(),
+            a.album.to_lowercase(),
+            a.album_key.clone(),
+        )
+    });
+    Ok(albums)
+}
+
+/// Record the outcome of a decode check for a track
+pub fn set_track_verify_failed(conn: &Connection, track_id: i64, failed: bool) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET last_verify_failed = ? WHERE id = ?",
+        params![failed as i64, track_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Raise a track's play count to `count`, leaving higher counts alone
+///
+/// Returns `true` if the count changed.
+pub fn raise_play_count(conn: &Connection, track_id: i64, count: i64) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET play_count = ?1 WHERE id = ?2 AND COALESCE(play_count, 0) < ?1",
+        params![count, track_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Set a track's star rating, 1-5, or clear it with `None`
+///
+/// Returns `true` if the rating changed.
+pub fn set_track_rating(conn: &Connection, track_id: i64, stars: Option<u8>) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET rating = ?1 WHERE id = ?2 AND rating IS NOT ?1",
+        params![stars, track_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Get a track's star rating, `None` if unrated or unknown
+pub fn get_track_rating(conn: &Connection, track_id: i64) -> DbResult<Option<u8>> {
+    match conn.query_row("SELECT rating FROM library WHERE id = ?", [track_id], |row| {
+        row.get(0)
+    }) {
+        Ok(stars) => Ok(stars),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get the IDs of tracks whose last decode check failed
+pub fn get_verify_failed_track_ids(conn: &Connection) -> DbResult<Vec<i64>> {
+    let mut stmt =
+        conn.prepare("SELECT id FROM library WHERE last_verify_failed = 1 ORDER BY id")?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(ids)
+}
+
 /// Update file sizes for tracks with file_size = 0
 pub fn update_file_sizes(conn: &Connection) -> DbResult<i64> {
     let mut stmt =
@@ -474,6 +1274,48 @@ pub fn update_file_sizes(conn: &Connection) -> DbResult<i64> {
     Ok(updated)
 }
 
+/// Count present tracks with no stored duration
+pub fn count_tracks_missing_audio_props(conn: &Connection) -> DbResult<i64> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM library WHERE duration IS NULL AND (missing = 0 OR missing IS NULL)",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Get the next batch of present tracks with no stored duration, by ID after `after_id`
+///
+/// Paging by ID means tracks whose properties can't be read aren't fetched again.
+pub fn get_tracks_missing_audio_props(
+    conn: &Connection,
+    after_id: i64,
+    limit: i64,
+) -> DbResult<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, filepath FROM library
+         WHERE duration IS NULL AND (missing = 0 OR missing IS NULL) AND id > ?
+         ORDER BY id
+         LIMIT ?",
+    )?;
+
+    let tracks: Vec<(i64, String)> = stmt
+        .query_map(params![after_id, limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(tracks)
+}
+
+/// Set a track's duration
+pub fn update_track_duration(conn: &Connection, track_id: i64, duration: f64) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET duration = ? WHERE id = ?",
+        params![duration, track_id],
+    )?;
+    Ok(updated > 0)
+}
+
 /// Mark a track as missing by ID
 pub fn mark_track_missing(conn: &Connection, track_id: i64) -> DbResult<bool> {
     let updated = conn.execute("UPDATE library SET missing = 1 WHERE id = ?", [track_id])?;
@@ -539,11 +1381,47 @@ pub fn update_track_filepath(conn: &Connection, track_id: i64, new_path: &str) -
     Ok(updated > 0)
 }
 
+/// Move a track to a new filepath after the file was found there
+///
+/// Clears the missing flag, refreshes last_seen_at, and records the new
+/// inode when one is known.
+pub fn relocate_track(
+    conn: &Connection,
+    track_id: i64,
+    new_path: &str,
+    inode: Option<u64>,
+) -> DbResult<bool> {
+    let updated = conn.execute(
+        "UPDATE library SET filepath = ?, missing = 0, last_seen_at = strftime('%s','now'),
+                file_inode = COALESCE(?, file_inode)
+         WHERE id = ?",
+        params![new_path, inode.map(|v| v as i64), track_id],
+    )?;
+    Ok(updated > 0)
+}
+
+/// Get (id, filepath) for all tracks (including missing) whose filepath starts with `prefix`
+pub fn get_track_paths_with_prefix(conn: &Connection, prefix: &str) -> DbResult<Vec<(i64, String)>> {
+    // substr comparison avoids LIKE wildcard escaping for '%' and '_' in paths
+    let mut stmt = conn.prepare(
+        "SELECT id, filepath FROM library
+         WHERE substr(filepath, 1, length(?1)) = ?1
+         ORDER BY id",
+    )?;
+
+    let paths: Vec<(i64, String)> = stmt
+        .query_map([prefix], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(paths)
+}
+
 /// Get all missing tracks
 pub fn get_missing_tracks(conn: &Connection) -> DbResult<Vec<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library WHERE missing = 1 ORDER BY title ASC",
@@ -557,6 +1435,86 @@ pub fn get_missing_tracks(conn: &Connection) -> DbResult<Vec<Track>> {
     Ok(tracks)
 }
 
+/// Condition matching missing tracks last seen more than `?1` days ago
+///
+/// Tracks never marked present again have no `last_seen_at`, so their
+/// `added_date` stands in for it.
+const MISSING_OLDER_THAN_SQL: &str = "missing = 1
+    AND COALESCE(last_seen_at, CAST(strftime('%s', added_date) AS INTEGER))
+        < CAST(strftime('%s', 'now') AS INTEGER) - ?1 * 86400";
+
+/// Get tracks that have been missing for more than `days` days
+pub fn get_missing_tracks_older_than(conn: &Connection, days: i64) -> DbResult<Vec<Track>> {
+    let sql = format!(
+        "SELECT id, filepath, title, artist, album, album_artist,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
+                play_count, last_played, added_date, missing, last_seen_at,
+                file_mtime_ns, file_inode, content_hash
+         FROM library WHERE {} ORDER BY title ASC",
+        MISSING_OLDER_THAN_SQL
+    );
+    let mut stmt = conn.prepare(&sql)?;
+
+    let tracks: Vec<Track> = stmt
+        .query_map([days], row_to_track)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(tracks)
+}
+
+/// Delete tracks that have been missing for more than `days` days
+///
+/// Their favorites, playlist entries, and play history go with them;
+/// chapters, bookmarks, tags, and features follow through `ON DELETE CASCADE`.
+/// Returns the IDs deleted.
+pub fn purge_missing_tracks(conn: &Connection, days: i64) -> DbResult<Vec<i64>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id FROM library WHERE {} ORDER BY id",
+        MISSING_OLDER_THAN_SQL
+    ))?;
+    let ids: Vec<i64> = stmt
+        .query_map([days], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    for &track_id in &ids {
+        conn.execute("DELETE FROM favorites WHERE track_id = ?", [track_id])?;
+        conn.execute("DELETE FROM playlist_items WHERE track_id = ?", [track_id])?;
+        history::clear_track_history(conn, track_id)?;
+        conn.execute("DELETE FROM library WHERE id = ?", [track_id])?;
+    }
+
+    Ok(ids)
+}
+
+/// Get (id, filepath) for every track not already marked missing
+pub fn get_present_track_paths(conn: &Connection) -> DbResult<Vec<(i64, String)>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, filepath FROM library WHERE (missing = 0 OR missing IS NULL) ORDER BY id",
+    )?;
+
+    let paths: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(paths)
+}
+
+/// Get every track's ID, filepath, and missing flag, ordered by ID
+pub fn get_track_paths_with_status(conn: &Connection) -> DbResult<Vec<(i64, String, bool)>> {
+    let mut stmt =
+        conn.prepare("SELECT id, filepath, COALESCE(missing, 0) FROM library ORDER BY id")?;
+
+    let paths: Vec<(i64, String, bool)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(paths)
+}
+
 /// Check and update track status based on file existence
 pub fn check_and_update_track_status(conn: &Connection, track_id: i64) -> DbResult<Option<Track>> {
     let track = get_track_by_id(conn, track_id)?;
@@ -576,7 +1534,7 @@ pub fn check_and_update_track_status(conn: &Connection, track_id: i64) -> DbResu
 pub fn find_missing_track_by_inode(conn: &Connection, inode: u64) -> DbResult<Option<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library WHERE file_inode = ? AND missing = 1 LIMIT 1",
@@ -596,7 +1554,7 @@ pub fn find_missing_track_by_content_hash(
 ) -> DbResult<Option<Track>> {
     let mut stmt = conn.prepare(
         "SELECT id, filepath, title, artist, album, album_artist,
-                track_number, track_total, date, duration, file_size,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
                 play_count, last_played, added_date, missing, last_seen_at,
                 file_mtime_ns, file_inode, content_hash
          FROM library WHERE content_hash = ? AND missing = 1 LIMIT 1",
@@ -772,8 +1730,48 @@ pub fn find_duplicates_by_content_hash(
     Ok(result)
 }
 
+/// A track with its stored fingerprints, for planning duplicate merges
+#[derive(Debug)]
+pub struct FingerprintedTrack {
+    pub candidate: DuplicateCandidate,
+    pub file_inode: Option<i64>,
+    pub content_hash: Option<String>,
+}
+
+/// Get every track with its stored fingerprints, by ID
+///
+/// For callers that group duplicates the way `find_duplicates_by_inode` and
+/// `find_duplicates_by_content_hash` do, but with fingerprints that aren't
+/// stored yet.
+pub fn get_fingerprinted_tracks(conn: &Connection) -> DbResult<Vec<FingerprintedTrack>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, filepath, missing, play_count, added_date, file_inode, content_hash
+         FROM library
+         ORDER BY id",
+    )?;
+
+    let tracks: Vec<FingerprintedTrack> = stmt
+        .query_map([], |row| {
+            Ok(FingerprintedTrack {
+                candidate: DuplicateCandidate {
+                    id: row.get(0)?,
+                    filepath: row.get(1)?,
+                    missing: row.get::<_, Option<i64>>(2)?.unwrap_or(0) != 0,
+                    play_count: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+                    added_date: row.get(4)?,
+                },
+                file_inode: row.get(5)?,
+                content_hash: row.get(6)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(tracks)
+}
+
 /// Merge duplicate tracks: transfer metadata from source to target, then delete source
-/// Preserves: play_count (summed), favorites, playlist memberships
+/// Preserves: play_count (summed), play history, bookmarks, tags, favorites, playlist memberships
 pub fn merge_duplicate_tracks(conn: &Connection, keep_id: i64, delete_id: i64) -> DbResult<bool> {
     // Sum play counts
     conn.execute(
@@ -783,10 +1781,29 @@ pub fn merge_duplicate_tracks(conn: &Connection, keep_id: i64, delete_id: i64) -
         params![delete_id, keep_id],
     )?;
 
-    // Transfer favorites (ignore if already exists)
+    // Transfer play history
+    conn.execute(
+        "UPDATE play_history SET track_id = ? WHERE track_id = ?",
+        params![keep_id, delete_id],
+    )?;
+
+    // Transfer bookmarks
+    conn.execute(
+        "UPDATE bookmarks SET track_id = ? WHERE track_id = ?",
+        params![keep_id, delete_id],
+    )?;
+
+    // Transfer custom tags (ignore ones the kept track already has)
     conn.execute(
-        "INSERT OR IGNORE INTO favorites (track_id, timestamp)
-         SELECT ?, timestamp FROM favorites WHERE track_id = ?",
+        "INSERT OR IGNORE INTO tags (track_id, tag)
+         SELECT ?, tag FROM tags WHERE track_id = ?",
+        params![keep_id, delete_id],
+    )?;
+
+    // Transfer favorites in every list (ignore if already exists)
+    conn.execute(
+        "INSERT OR IGNORE INTO favorites (list_id, track_id, timestamp)
+         SELECT list_id, ?, timestamp FROM favorites WHERE track_id = ?",
         params![keep_id, delete_id],
     )?;
 
@@ -860,6 +1877,34 @@ mod tests {
         assert!(not_found.is_none());
     }
 
+    #[test]
+    fn test_track_chapters_stored_and_replaced() {
+        let conn = setup_test_db();
+
+        let chapter = |start_ms: u64, title: &str| Chapter {
+            start_ms,
+            title: Some(title.to_string()),
+        };
+        let metadata = TrackMetadata {
+            title: Some("Audiobook".to_string()),
+            chapters: vec![chapter(0, "Opening"), chapter(90_000, "Part One")],
+            ..Default::default()
+        };
+        let id = add_track(&conn, "/books/book.m4b", &metadata).unwrap();
+        assert_eq!(get_track_chapters(&conn, id).unwrap(), metadata.chapters);
+
+        // A rescan replaces the chapters rather than appending
+        let rescanned = TrackMetadata {
+            chapters: vec![chapter(0, "Prologue")],
+            ..metadata.clone()
+        };
+        update_tracks_bulk(&conn, &[("/books/book.m4b".to_string(), rescanned)]).unwrap();
+        assert_eq!(get_track_chapters(&conn, id).unwrap(), vec![chapter(0, "Prologue")]);
+
+        update_track_metadata(&conn, id, &TrackMetadata::default()).unwrap();
+        assert!(get_track_chapters(&conn, id).unwrap().is_empty());
+    }
+
     #[test]
     fn test_bulk_operations() {
         let conn = setup_test_db();
@@ -894,12 +1939,49 @@ mod tests {
         };
         let id = add_track(&conn, "/music/test.mp3", &metadata).unwrap();
 
-        let track = update_play_count(&conn, id).unwrap().unwrap();
-        assert_eq!(track.play_count, 1);
-        assert!(track.last_played.is_some());
+        let track = update_play_count(&conn, id).unwrap().unwrap();
+        assert_eq!(track.play_count, 1);
+        assert!(track.last_played.is_some());
+
+        let track = update_play_count(&conn, id).unwrap().unwrap();
+        assert_eq!(track.play_count, 2);
+        assert_eq!(history::get_track_play_history_count(&conn, id).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_set_play_count_explicit_value() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let id = add_track(&conn, "/music/track.mp3", &metadata).unwrap();
+        update_play_count(&conn, id).unwrap();
+
+        let track = set_play_count(&conn, id, 42, false).unwrap().unwrap();
+        assert_eq!(track.play_count, 42);
+        assert!(track.last_played.is_some());
+
+        let track = set_play_count(&conn, id, 0, false).unwrap().unwrap();
+        assert_eq!(track.play_count, 0);
+        assert!(track.last_played.is_none());
+        assert_eq!(history::get_track_play_history_count(&conn, id).unwrap(), 0);
+
+        assert!(set_play_count(&conn, 9999, 1, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reset_all_play_counts() {
+        let conn = setup_test_db();
 
-        let track = update_play_count(&conn, id).unwrap().unwrap();
-        assert_eq!(track.play_count, 2);
+        let metadata = TrackMetadata::default();
+        let played = add_track(&conn, "/music/played.mp3", &metadata).unwrap();
+        add_track(&conn, "/music/unplayed.mp3", &metadata).unwrap();
+        update_play_count(&conn, played).unwrap();
+
+        assert_eq!(reset_all_play_counts(&conn).unwrap(), vec![played]);
+
+        let track = get_track_by_id(&conn, played).unwrap().unwrap();
+        assert_eq!(track.play_count, 0);
+        assert!(track.last_played.is_none());
     }
 
     #[test]
@@ -1732,8 +2814,128 @@ mod tests {
         }
     }
 
+    /// Add a missing track last seen `days_ago` days ago
+    fn add_missing_track(conn: &Connection, filepath: &str, days_ago: i64) -> i64 {
+        let id = add_track(conn, filepath, &TrackMetadata::default()).unwrap();
+        conn.execute(
+            "UPDATE library SET missing = 1,
+                last_seen_at = CAST(strftime('%s', 'now') AS INTEGER) - ?1 * 86400
+             WHERE id = ?2",
+            [days_ago, id],
+        )
+        .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_purge_missing_tracks_respects_cutoff() {
+        let conn = setup_test_db();
+
+        let long_gone = add_missing_track(&conn, "/music/long_gone.mp3", 40);
+        let recently_gone = add_missing_track(&conn, "/music/recently_gone.mp3", 2);
+        let present = add_track(&conn, "/music/present.mp3", &TrackMetadata::default()).unwrap();
+        // Never marked present again, so its added date counts
+        let old_added = add_track(&conn, "/music/old.mp3", &TrackMetadata::default()).unwrap();
+        conn.execute(
+            "UPDATE library SET missing = 1, added_date = '2020-01-01 00:00:00' WHERE id = ?",
+            [old_added],
+        )
+        .unwrap();
+
+        let preview: Vec<i64> = get_missing_tracks_older_than(&conn, 30)
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(preview.len(), 2);
+        assert!(preview.contains(&long_gone) && preview.contains(&old_added));
+
+        assert_eq!(purge_missing_tracks(&conn, 30).unwrap(), vec![long_gone, old_added]);
+        assert!(get_track_by_id(&conn, long_gone).unwrap().is_none());
+        assert!(get_track_by_id(&conn, recently_gone).unwrap().is_some());
+        assert!(get_track_by_id(&conn, present).unwrap().is_some());
+
+        // A zero-day cutoff takes every missing track, never a present one
+        assert_eq!(purge_missing_tracks(&conn, 0).unwrap(), vec![recently_gone]);
+        assert!(get_track_by_id(&conn, present).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_purge_missing_tracks_removes_dependent_rows() {
+        let conn = setup_test_db();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+
+        let gone = add_missing_track(&conn, "/music/gone.mp3", 90);
+        let kept = add_track(&conn, "/music/kept.mp3", &TrackMetadata::default()).unwrap();
+        let playlist = crate::db::playlists::create_playlist(&conn, "Mix").unwrap().unwrap();
+        crate::db::playlists::add_tracks_to_playlist(&conn, playlist.id, &[gone, kept], None)
+            .unwrap();
+        for id in [gone, kept] {
+            crate::db::favorites::add_favorite(&conn, 1, id).unwrap();
+            history::record_play(&conn, id).unwrap();
+            crate::db::bookmarks::add_bookmark(&conn, id, 1_000, "Intro").unwrap();
+        }
+
+        assert_eq!(purge_missing_tracks(&conn, 30).unwrap(), vec![gone]);
+
+        let count = |table: &str, id: i64| -> i64 {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {} WHERE track_id = ?", table),
+                [id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        for table in ["favorites", "playlist_items", "play_history", "bookmarks"] {
+            assert_eq!(count(table, gone), 0, "{} rows left for purged track", table);
+            assert_eq!(count(table, kept), 1, "{} rows lost for kept track", table);
+        }
+    }
+
     // ===== Fingerprint Backfill Tests =====
 
+    #[test]
+    fn test_get_present_track_paths_skips_missing() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let present = add_track(&conn, "/music/present.mp3", &metadata).unwrap();
+        let missing = add_track(&conn, "/music/missing.mp3", &metadata).unwrap();
+        mark_track_missing(&conn, missing).unwrap();
+
+        let paths = get_present_track_paths(&conn).unwrap();
+        assert_eq!(paths, vec![(present, "/music/present.mp3".to_string())]);
+    }
+
+    #[test]
+    fn test_get_track_paths_with_prefix_treats_wildcards_literally() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let matching = add_track(&conn, "/music/100%_hits/a.mp3", &metadata).unwrap();
+        add_track(&conn, "/music/100x-hits/b.mp3", &metadata).unwrap();
+
+        let paths = get_track_paths_with_prefix(&conn, "/music/100%_").unwrap();
+        assert_eq!(paths, vec![(matching, "/music/100%_hits/a.mp3".to_string())]);
+    }
+
+    #[test]
+    fn test_relocate_track_clears_missing() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let id = add_track(&conn, "/old/song.mp3", &metadata).unwrap();
+        mark_track_missing(&conn, id).unwrap();
+
+        assert!(relocate_track(&conn, id, "/new/song.mp3", Some(42)).unwrap());
+
+        let track = get_track_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!(track.filepath, "/new/song.mp3");
+        assert!(!track.missing);
+        assert!(track.last_seen_at.is_some());
+        assert_eq!(track.file_inode, Some(42));
+    }
+
     #[test]
     fn test_get_tracks_needing_fingerprints_empty() {
         let conn = setup_test_db();
@@ -2045,6 +3247,62 @@ mod tests {
         assert_eq!(result.total, 2);
     }
 
+    #[test]
+    fn test_library_query_favorites_and_rating_filters() {
+        use crate::db::favorites::{add_favorite, create_favorite_list};
+
+        let conn = setup_test_db();
+        let mut ids = Vec::new();
+        for (i, artist) in ["Artist A", "Artist A", "Artist A", "Artist B"].iter().enumerate() {
+            let metadata = TrackMetadata {
+                title: Some(format!("Track {}", i)),
+                artist: Some(artist.to_string()),
+                ..Default::default()
+            };
+            ids.push(add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap());
+        }
+        add_favorite(&conn, 1, ids[0]).unwrap();
+        // A track in another list counts as a favorite too
+        let list = create_favorite_list(&conn, "Road").unwrap().unwrap();
+        add_favorite(&conn, list.id, ids[1]).unwrap();
+        add_favorite(&conn, list.id, ids[0]).unwrap();
+        add_favorite(&conn, 1, ids[3]).unwrap();
+        set_track_rating(&conn, ids[1], Some(4)).unwrap();
+        set_track_rating(&conn, ids[2], Some(5)).unwrap();
+        set_track_rating(&conn, ids[3], Some(5)).unwrap();
+
+        let query = LibraryQuery {
+            artist: Some("Artist A".to_string()),
+            favorites_only: true,
+            sort_by: LibrarySortColumn::Title,
+            sort_order: SortOrder::Asc,
+            limit: 1,
+            ..Default::default()
+        };
+        let result = get_all_tracks(&conn, &query).unwrap();
+        // Each favorite is counted once, however many lists it is in
+        assert_eq!(result.total, 2);
+        assert_eq!(result.items[0].id, ids[0]);
+        let next = get_all_tracks(&conn, &LibraryQuery { offset: 1, ..query }).unwrap();
+        assert_eq!(next.items[0].id, ids[1]);
+
+        let rated = |favorites_only, min_rating| {
+            let query = LibraryQuery {
+                artist: Some("Artist A".to_string()),
+                favorites_only,
+                min_rating,
+                limit: 100,
+                ..Default::default()
+            };
+            get_all_tracks(&conn, &query).unwrap().total
+        };
+        assert_eq!(rated(false, Some(4)), 2);
+        assert_eq!(rated(false, Some(5)), 1);
+        assert_eq!(rated(true, Some(4)), 1);
+        assert_eq!(rated(true, Some(5)), 0);
+        assert_eq!(rated(false, None), 3);
+    }
+
     // ===== DuplicateCandidate and TrackForBackfill Struct Tests =====
 
     #[test]
@@ -2074,4 +3332,523 @@ mod tests {
         assert!(debug_str.contains("42"));
         assert!(debug_str.contains("backfill.mp3"));
     }
+
+    // ===== Library Health Stats Tests =====
+
+    #[test]
+    fn test_library_stats_health_counts_empty() {
+        let conn = setup_test_db();
+        let stats = get_library_stats(&conn).unwrap();
+
+        assert_eq!(stats.missing_tracks, 0);
+        assert_eq!(stats.tracks_without_artwork, 0);
+        assert_eq!(stats.tracks_missing_fingerprints, 0);
+        assert_eq!(stats.potential_duplicates, 0);
+    }
+
+    #[test]
+    fn test_library_stats_health_counts() {
+        let conn = setup_test_db();
+
+        let mut ids = Vec::new();
+        for i in 1..=5 {
+            let metadata = TrackMetadata {
+                title: Some(format!("Track {}", i)),
+                ..Default::default()
+            };
+            ids.push(add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap());
+        }
+
+        // Tracks 1 and 2 share an inode, tracks 3 and 4 share a content hash
+        update_track_fingerprints(&conn, ids[0], Some(100), Some("hash-a")).unwrap();
+        update_track_fingerprints(&conn, ids[1], Some(100), Some("hash-b")).unwrap();
+        update_track_fingerprints(&conn, ids[2], Some(200), Some("hash-c")).unwrap();
+        update_track_fingerprints(&conn, ids[3], Some(300), Some("hash-c")).unwrap();
+
+        // Track 5 has no fingerprints and is missing
+        mark_track_missing(&conn, ids[4]).unwrap();
+
+        set_track_has_artwork(&conn, ids[0], true).unwrap();
+        set_track_has_artwork(&conn, ids[1], false).unwrap();
+        set_track_has_artwork(&conn, ids[4], false).unwrap();
+
+        let stats = get_library_stats(&conn).unwrap();
+        assert_eq!(stats.total_tracks, 4);
+        assert_eq!(stats.missing_tracks, 1);
+        assert_eq!(stats.tracks_without_artwork, 1);
+        assert_eq!(stats.tracks_missing_fingerprints, 0);
+        assert_eq!(stats.potential_duplicates, 2);
+    }
+
+    #[test]
+    fn test_library_stats_null_missing_counts_as_present() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let id = add_track(&conn, "/music/track.mp3", &metadata).unwrap();
+        conn.execute("UPDATE library SET missing = NULL WHERE id = ?", [id]).unwrap();
+
+        let stats = get_library_stats(&conn).unwrap();
+        assert_eq!(stats.total_tracks, 1);
+        assert_eq!(stats.missing_tracks, 0);
+        assert_eq!(stats.tracks_missing_fingerprints, 1);
+    }
+
+    #[test]
+    fn test_cached_library_stats_follow_adds_and_deletes() {
+        let conn = setup_test_db();
+        assert_eq!(get_library_stats(&conn).unwrap(), count_library_stats(&conn).unwrap());
+
+        let mut ids = Vec::new();
+        for i in 1..=4 {
+            let metadata = TrackMetadata {
+                artist: Some(format!("Artist {}", i % 2)),
+                album: Some(format!("Album {}", i)),
+                duration: Some(100.0),
+                file_size: Some(1000),
+                ..Default::default()
+            };
+            ids.push(add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap());
+            assert_eq!(get_library_stats(&conn).unwrap(), count_library_stats(&conn).unwrap());
+        }
+        let stats = get_library_stats(&conn).unwrap();
+        assert_eq!((stats.total_tracks, stats.total_artists, stats.total_albums), (4, 2, 4));
+        assert_eq!((stats.total_duration, stats.total_size), (400, 4000));
+
+        delete_track(&conn, ids[0]).unwrap();
+        mark_track_missing(&conn, ids[1]).unwrap();
+        let stats = get_library_stats(&conn).unwrap();
+        assert_eq!(stats, count_library_stats(&conn).unwrap());
+        assert_eq!((stats.total_tracks, stats.missing_tracks), (2, 1));
+
+        let rest: Vec<String> = (2..=4).map(|i| format!("/music/track{}.mp3", i)).collect();
+        delete_tracks_bulk(&conn, &rest).unwrap();
+        assert_eq!(get_library_stats(&conn).unwrap(), count_library_stats(&conn).unwrap());
+        assert_eq!(get_library_stats(&conn).unwrap().total_tracks, 0);
+    }
+
+    #[test]
+    fn test_library_stats_served_from_cache_until_library_changes() {
+        let conn = setup_test_db();
+        add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+        assert_eq!(get_library_stats(&conn).unwrap().total_tracks, 1);
+
+        // Tamper with the cache: reads keep returning it while nothing changes
+        conn.execute("UPDATE library_stats SET total_tracks = 99", []).unwrap();
+        assert_eq!(get_library_stats(&conn).unwrap().total_tracks, 99);
+
+        // A forced recount repairs it
+        assert_eq!(recompute_library_stats(&conn).unwrap().total_tracks, 1);
+        assert_eq!(get_library_stats(&conn).unwrap().total_tracks, 1);
+
+        // Any library write makes the cache stale
+        conn.execute("UPDATE library_stats SET total_tracks = 99", []).unwrap();
+        add_track(&conn, "/music/b.mp3", &TrackMetadata::default()).unwrap();
+        assert_eq!(get_library_stats(&conn).unwrap().total_tracks, 2);
+    }
+
+    #[test]
+    fn test_set_track_has_artwork_only_updates_on_change() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let id = add_track(&conn, "/music/track.mp3", &metadata).unwrap();
+
+        assert!(set_track_has_artwork(&conn, id, true).unwrap());
+        assert!(!set_track_has_artwork(&conn, id, true).unwrap());
+        assert!(set_track_has_artwork(&conn, id, false).unwrap());
+    }
+
+    // ===== Added Date Tests =====
+
+    fn added(conn: &Connection, path: &str, added_date: Option<&str>) -> DbResult<i64> {
+        let metadata = TrackMetadata {
+            title: Some(path.to_string()),
+            added_date: added_date.map(String::from),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata)
+    }
+
+    #[test]
+    fn test_custom_added_date_is_stored_normalized() {
+        let conn = setup_test_db();
+
+        let offset = added(&conn, "/music/a.mp3", Some("2009-06-01T12:00:00+02:00")).unwrap();
+        let track = get_track_by_id(&conn, offset).unwrap().unwrap();
+        assert_eq!(track.added_date.as_deref(), Some("2009-06-01 10:00:00"));
+
+        let now = added(&conn, "/music/b.mp3", None).unwrap();
+        let track = get_track_by_id(&conn, now).unwrap().unwrap();
+        assert!(track.added_date.unwrap().as_str() > "2020-01-01");
+
+        let bulk = vec![(
+            "/music/c.mp3".to_string(),
+            TrackMetadata {
+                added_date: Some("2012-03-04".to_string()),
+                ..Default::default()
+            },
+        )];
+        add_tracks_bulk(&conn, &bulk).unwrap();
+        let track = get_track_by_filepath(&conn, "/music/c.mp3").unwrap().unwrap();
+        assert_eq!(track.added_date.as_deref(), Some("2012-03-04 00:00:00"));
+
+        // Unparseable dates are rejected instead of stored
+        assert!(added(&conn, "/music/d.mp3", Some("last tuesday")).is_err());
+        assert!(get_track_by_filepath(&conn, "/music/d.mp3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_set_added_date() {
+        let conn = setup_test_db();
+        let id = added(&conn, "/music/a.mp3", None).unwrap();
+
+        let track = set_added_date(&conn, id, "1999-12-31 23:59:59").unwrap().unwrap();
+        assert_eq!(track.added_date.as_deref(), Some("1999-12-31 23:59:59"));
+        assert!(set_added_date(&conn, id, "31/12/1999").is_err());
+        assert!(set_added_date(&conn, 99999, "1999-12-31").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_sort_by_added_date_puts_nulls_last() {
+        let conn = setup_test_db();
+        added(&conn, "/music/old.mp3", Some("2009-06-01")).unwrap();
+        added(&conn, "/music/new.mp3", None).unwrap();
+        added(&conn, "/music/older.mp3", Some("2001-01-01")).unwrap();
+        let unknown = added(&conn, "/music/unknown.mp3", None).unwrap();
+        conn.execute("UPDATE library SET added_date = NULL WHERE id = ?", [unknown]).unwrap();
+
+        let order = |sort_order| -> Vec<String> {
+            let query = LibraryQuery {
+                sort_by: LibrarySortColumn::AddedDate,
+                sort_order,
+                limit: 100,
+                ..Default::default()
+            };
+            get_all_tracks(&conn, &query)
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|t| t.filepath)
+                .collect()
+        };
+
+        assert_eq!(
+            order(SortOrder::Desc),
+            ["/music/new.mp3", "/music/old.mp3", "/music/older.mp3", "/music/unknown.mp3"]
+        );
+        assert_eq!(
+            order(SortOrder::Asc),
+            ["/music/older.mp3", "/music/old.mp3", "/music/new.mp3", "/music/unknown.mp3"]
+        );
+    }
+
+    #[test]
+    fn test_album_sort_puts_disc_one_before_disc_two() {
+        let conn = setup_test_db();
+        let add = |name: &str, album: &str, disc: Option<u32>, track: &str| {
+            let metadata = TrackMetadata {
+                album: Some(album.to_string()),
+                track_number: Some(track.to_string()),
+                disc_number: disc,
+                disc_total: disc.map(|_| 2),
+                ..Default::default()
+            };
+            add_track(&conn, &format!("/music/{}.flac", name), &metadata).unwrap()
+        };
+        // Inserted out of order, with track numbers that overlap across discs
+        add("d2t1", "Double", Some(2), "1");
+        add("d1t2", "Double", Some(1), "2/9");
+        add("d2t3", "Double", Some(2), "3");
+        add("untagged", "Double", None, "5");
+        add("d1t1", "Double", Some(1), "1");
+        add("d2t2", "Double", Some(2), "2");
+        add("single", "Another", None, "1");
+
+        let order = |sort_order| -> Vec<String> {
+            let query = LibraryQuery {
+                sort_by: LibrarySortColumn::Album,
+                sort_order,
+                limit: 100,
+                ..Default::default()
+            };
+            get_all_tracks(&conn, &query)
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|t| t.filepath.trim_start_matches("/music/").replace(".flac", ""))
+                .collect()
+        };
+
+        // A file lacking a disc tag counts as disc 1
+        let double = ["d1t1", "d1t2", "untagged", "d2t1", "d2t2", "d2t3"];
+        assert_eq!(order(SortOrder::Asc)[0], "single");
+        assert_eq!(order(SortOrder::Asc)[1..], double);
+        // Descending flips the albums, not the tracks within them
+        assert_eq!(order(SortOrder::Desc)[..6], double);
+    }
+
+    #[test]
+    fn test_disc_numbers_round_trip() {
+        let conn = setup_test_db();
+        let metadata = TrackMetadata {
+            disc_number: Some(2),
+            disc_total: Some(3),
+            ..Default::default()
+        };
+        let id = add_track(&conn, "/music/disc.flac", &metadata).unwrap();
+        let track = get_track_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!((track.disc_number, track.disc_total), (Some(2), Some(3)));
+
+        update_track_metadata(&conn, id, &TrackMetadata::default()).unwrap();
+        let track = get_track_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!((track.disc_number, track.disc_total), (None, None));
+    }
+
+    fn add_sized(conn: &Connection, path: &str, artist: &str, album: &str, size: i64) -> i64 {
+        let metadata = TrackMetadata {
+            artist: Some(artist.to_string()).filter(|a| !a.is_empty()),
+            album: Some(album.to_string()),
+            file_size: Some(size),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    #[test]
+    fn test_sampled_filepaths_are_seeded_and_capped() {
+        let conn = setup_test_db();
+        for album in 0..6 {
+            for n in 0..5 {
+                let path = format!("/music/{}-{}.flac", album, n);
+                add_sized(&conn, &path, "Low", &format!("Album {}", album), 1);
+            }
+        }
+        let query = LibraryQuery::new();
+
+        let sample = get_sampled_filepaths(&conn, &query, 7, 10, false).unwrap();
+        assert_eq!(sample.len(), 10);
+        assert_eq!(sample.iter().collect::<HashSet<_>>().len(), 10);
+        assert_eq!(sample, get_sampled_filepaths(&conn, &query, 7, 10, false).unwrap());
+        let samples: HashSet<Vec<String>> = (0..5)
+            .map(|seed| get_sampled_filepaths(&conn, &query, seed, 10, false).unwrap())
+            .collect();
+        assert!(samples.len() > 1);
+
+        // Album sampling takes two whole albums
+        let sample = get_sampled_filepaths(&conn, &query, 7, 10, true).unwrap();
+        let mut albums: BTreeMap<&str, usize> = BTreeMap::new();
+        for path in &sample {
+            let album = path.trim_start_matches("/music/").split('-').next().unwrap();
+            *albums.entry(album).or_default() += 1;
+        }
+        assert_eq!(albums.into_values().collect::<Vec<_>>(), [5, 5]);
+    }
+
+    #[test]
+    fn test_size_breakdown_by_artist_and_album() {
+        let conn = setup_test_db();
+        add_sized(&conn, "/music/a1.flac", "Low", "Things We Lost", 300);
+        add_sized(&conn, "/music/a2.flac", "Low", "Things We Lost", 200);
+        add_sized(&conn, "/music/a3.flac", "Low", "Double Negative", 100);
+        add_sized(&conn, "/music/b1.flac", "Bark Psychosis", "Hex", 700);
+        add_sized(&conn, "/music/u1.flac", "", "Hex", 50);
+        let gone = add_sized(&conn, "/music/gone.flac", "Low", "Things We Lost", 5000);
+        mark_track_missing(&conn, gone).unwrap();
+        // Unknown sizes count as nothing but still count as tracks
+        add_sized(&conn, "/music/a4.flac", "Low", "Double Negative", 0);
+        let no_size = add_sized(&conn, "/music/a5.flac", "Low", "Double Negative", 0);
+        conn.execute("UPDATE library SET file_size = NULL WHERE id = ?", [no_size]).unwrap();
+
+        let artists = get_size_breakdown(&conn, SizeGroup::Artist).unwrap();
+        let totals: Vec<_> = artists
+            .iter()
+            .map(|e| (e.name.as_deref(), e.total_size, e.track_count))
+            .collect();
+        assert_eq!(
+            totals,
+            [(Some("Bark Psychosis"), 700, 1), (Some("Low"), 600, 5), (None, 50, 1)]
+        );
+
+        let albums = get_size_breakdown(&conn, SizeGroup::Album).unwrap();
+        let totals: Vec<_> = albums
+            .iter()
+            .map(|e| (e.name.as_deref(), e.artist.as_deref(), e.total_size, e.track_count))
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                (Some("Hex"), Some("Bark Psychosis"), 700, 1),
+                (Some("Things We Lost"), Some("Low"), 500, 2),
+                (Some("Double Negative"), Some("Low"), 100, 3),
+                (Some("Hex"), None, 50, 1),
+            ]
+        );
+    }
+
+    fn add_numbered(conn: &Connection, path: &str, album: &str, disc: u32, number: &str) -> i64 {
+        let metadata = TrackMetadata {
+            artist: Some("Low".to_string()),
+            album: Some(album.to_string()),
+            track_number: Some(number.to_string()),
+            track_total: Some("5".to_string()).filter(|_| album != "Untotalled"),
+            disc_number: Some(disc),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    #[test]
+    fn test_find_incomplete_albums_reports_gap() {
+        let conn = setup_test_db();
+        for n in ["1", "2", "04/5", "5"] {
+            add_numbered(&conn, &format!("/music/{}.flac", n.replace('/', "_")), "Hex", 1, n);
+        }
+
+        let incomplete = find_incomplete_albums(&conn).unwrap();
+        assert_eq!(incomplete.len(), 1);
+        let album = &incomplete[0];
+        assert_eq!(album.album_key, artwork_misses::album_key("Low", "Hex"));
+        assert_eq!(album.album, "Hex");
+        assert_eq!(album.artist.as_deref(), Some("Low"));
+        assert_eq!(album.disc_number, 1);
+        assert_eq!(album.expected_total, 5);
+        assert_eq!(album.present_count, 4);
+        assert_eq!(album.missing_numbers, [3]);
+    }
+
+    #[test]
+    fn test_find_incomplete_albums_skips_complete_albums() {
+        let conn = setup_test_db();
+        for n in 1..=5 {
+            add_numbered(&conn, &format!("/music/hex/{}.flac", n), "Hex", 1, &n.to_string());
+        }
+        // Two full discs, and an album without a track total whose highest number ends it
+        for disc in 1..=2 {
+            for n in 1..=5 {
+                let path = format!("/music/multi/{}-{}.flac", disc, n);
+                add_numbered(&conn, &path, "Multi", disc, &n.to_string());
+            }
+        }
+        for n in 1..=3 {
+            add_numbered(&conn, &format!("/music/u/{}.flac", n), "Untotalled", 1, &n.to_string());
+        }
+        assert!(find_incomplete_albums(&conn).unwrap().is_empty());
+
+        // A missing file leaves a gap on its disc only
+        let gone = get_track_by_filepath(&conn, "/music/multi/2-3.flac").unwrap().unwrap();
+        mark_track_missing(&conn, gone.id).unwrap();
+        let incomplete = find_incomplete_albums(&conn).unwrap();
+        let gaps: Vec<_> = incomplete
+            .iter()
+            .map(|a| (a.album.as_str(), a.disc_number, a.missing_numbers.clone()))
+            .collect();
+        assert_eq!(gaps, [("Multi", 2, vec![3])]);
+    }
+
+    #[test]
+    fn test_find_incomplete_albums_ignores_implausible_numbers() {
+        let conn = setup_test_db();
+        for n in 1..=5 {
+            add_numbered(&conn, &format!("/music/hex/{}.flac", n), "Hex", 1, &n.to_string());
+        }
+        // A date in the track number tag and junk in the total
+        add_numbered(&conn, "/music/hex/date.flac", "Hex", 1, "2021");
+        let junk = get_track_by_filepath(&conn, "/music/hex/1.flac").unwrap().unwrap();
+        conn.execute(
+            "UPDATE library SET track_total = ?1 WHERE id = ?2",
+            params!["9223372036854775807", junk.id],
+        )
+        .unwrap();
+        assert!(find_incomplete_albums(&conn).unwrap().is_empty());
+
+        let gone = get_track_by_filepath(&conn, "/music/hex/4.flac").unwrap().unwrap();
+        mark_track_missing(&conn, gone.id).unwrap();
+        let incomplete = find_incomplete_albums(&conn).unwrap();
+        assert_eq!(incomplete.len(), 1);
+        assert_eq!(incomplete[0].expected_total, 5);
+        assert_eq!(incomplete[0].missing_numbers, [4]);
+    }
+
+    #[test]
+    fn test_album_override_changes_display_only() {
+        let conn = setup_test_db();
+        for (path, date) in [("/music/hex/01.flac", "1994-03-01"), ("/music/hex/02.flac", "1993")] {
+            let metadata = TrackMetadata {
+                artist: Some("Bark Psychosis".to_string()),
+                album: Some("Hex".to_string()),
+                date: Some(date.to_string()),
+                duration: Some(100.0),
+                ..Default::default()
+            };
+            add_track(&conn, path, &metadata).unwrap();
+        }
+        add_sized(&conn, "/music/other.flac", "Autechre", "Amber", 0);
+
+        let albums = get_albums(&conn).unwrap();
+        let names: Vec<_> = albums.iter().map(|a| a.album.as_str()).collect();
+        assert_eq!(names, ["Amber", "Hex"]);
+        let hex = &albums[1];
+        assert_eq!(hex.album_key, artwork_misses::album_key("Bark Psychosis", "Hex"));
+        assert_eq!(hex.year.as_deref(), Some("1993"));
+        assert_eq!(hex.track_count, 2);
+        assert_eq!(hex.total_duration, 200.0);
+        assert!(!hex.overridden);
+
+        album_overrides::set_album_override(
+            &conn,
+            &crate::db::AlbumOverride {
+                album_key: hex.album_key.clone(),
+                album: Some("Hex (Remastered)".to_string()),
+                album_artist: None,
+                year: Some("2019".to_string()),
+                artwork_path: None,
+            },
+        )
+        .unwrap();
+
+        let albums = get_albums(&conn).unwrap();
+        let hex = albums.iter().find(|a| a.overridden).unwrap();
+        assert_eq!(hex.album, "Hex (Remastered)");
+        assert_eq!(hex.artist.as_deref(), Some("Bark Psychosis"));
+        assert_eq!(hex.year.as_deref(), Some("2019"));
+        assert_eq!(hex.album_key, artwork_misses::album_key("Bark Psychosis", "Hex"));
+
+        // The tracks keep their tags
+        let track = get_track_by_filepath(&conn, "/music/hex/01.flac").unwrap().unwrap();
+        assert_eq!(track.album.as_deref(), Some("Hex"));
+        assert_eq!(track.date.as_deref(), Some("1994-03-01"));
+
+        album_overrides::clear_album_override(&conn, &hex.album_key).unwrap();
+        let albums = get_albums(&conn).unwrap();
+        assert_eq!(albums[1].album, "Hex");
+        assert_eq!(albums[1].year.as_deref(), Some("1993"));
+    }
+
+    #[test]
+    fn test_size_breakdown_by_folder() {
+        let conn = setup_test_db();
+        watched::add_watched_folder(&conn, "/music", "continuous", 10, true).unwrap();
+        watched::add_watched_folder(&conn, "/music/podcasts", "continuous", 10, true).unwrap();
+        add_sized(&conn, "/music/Low/Hex/01.flac", "Low", "Hex", 300);
+        add_sized(&conn, "/music/Low/Hex/02.flac", "Low", "Hex", 200);
+        add_sized(&conn, "/music/loose.flac", "Low", "Hex", 10);
+        add_sized(&conn, "/music/podcasts/show/ep1.mp3", "Host", "Show", 400);
+        add_sized(&conn, "/downloads/new/song.mp3", "Someone", "Else", 0);
+
+        let folders = get_size_breakdown(&conn, SizeGroup::Folder).unwrap();
+        let totals: Vec<_> = folders
+            .iter()
+            .map(|e| (e.name.as_deref().unwrap(), e.total_size, e.track_count))
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                ("/music/Low", 500, 2),
+                ("/music/podcasts/show", 400, 1),
+                ("/music", 10, 1),
+                ("/downloads/new", 0, 1),
+            ]
+        );
+    }
 }
@@ -0,0 +1,235 @@
+//! Database maintenance operations.
+//!
+//! VACUUM, integrity checks, WAL checkpoints, and query planner optimization.
+//! These can take a while on large libraries, so callers should run them on a dedicated
+//! connection (see `Database::with_maintenance_conn`) off the main thread.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::str::FromStr;
+
+use crate::db::{DbError, DbResult};
+
+/// Outcome of a maintenance operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceResult {
+    pub ok: bool,
+    pub messages: Vec<String>,
+}
+
+impl MaintenanceResult {
+    fn ok() -> Self {
+        Self {
+            ok: true,
+            messages: vec!["ok".to_string()],
+        }
+    }
+}
+
+/// Rebuild the database file to reclaim free pages
+pub fn vacuum(conn: &Connection) -> DbResult<MaintenanceResult> {
+    conn.execute_batch("VACUUM")?;
+    // Fold the rebuilt pages back into the main file so the WAL doesn't stay bloated
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
+    Ok(MaintenanceResult::ok())
+}
+
+/// Run `PRAGMA integrity_check` and collect its report
+///
+/// SQLite returns a single "ok" row for a healthy database, otherwise one row
+/// per problem found.
+pub fn integrity_check(conn: &Connection) -> DbResult<MaintenanceResult> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let messages: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    Ok(MaintenanceResult { ok, messages })
+}
+
+/// Refresh query planner statistics
+pub fn optimize(conn: &Connection) -> DbResult<MaintenanceResult> {
+    conn.execute_batch("ANALYZE; PRAGMA optimize;")?;
+    Ok(MaintenanceResult::ok())
+}
+
+/// How hard `PRAGMA wal_checkpoint` tries to empty the WAL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// Copy what it can without waiting on readers or writers
+    Passive,
+    /// Wait for writers, then copy every frame
+    Full,
+    /// Like `Full`, then truncate the WAL file to zero bytes
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+impl FromStr for CheckpointMode {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "PASSIVE" => Ok(CheckpointMode::Passive),
+            "FULL" => Ok(CheckpointMode::Full),
+            "TRUNCATE" => Ok(CheckpointMode::Truncate),
+            _ => Err(DbError::Constraint(format!(
+                "Unknown checkpoint mode '{}', expected PASSIVE, FULL, or TRUNCATE",
+                s
+            ))),
+        }
+    }
+}
+
+/// Outcome of a WAL checkpoint, as reported by SQLite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CheckpointResult {
+    /// A `Full` or `Truncate` checkpoint was blocked by another connection
+    pub busy: bool,
+    /// Frames in the WAL, or -1 when the database isn't in WAL mode
+    pub log_frames: i64,
+    /// Frames copied back into the database file, or -1 outside WAL mode
+    pub checkpointed_frames: i64,
+}
+
+/// Run `PRAGMA wal_checkpoint` with the given mode
+pub fn checkpoint(conn: &Connection, mode: CheckpointMode) -> DbResult<CheckpointResult> {
+    let sql = format!("PRAGMA wal_checkpoint({})", mode.as_sql());
+    Ok(conn.query_row(&sql, [], |row| {
+        Ok(CheckpointResult {
+            busy: row.get::<_, i64>(0)? != 0,
+            log_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })?)
+}
+
+/// Lightweight optimize for shutdown
+///
+/// Only `PRAGMA optimize`, which lets SQLite analyze just the tables whose
+/// statistics are stale, so quitting stays fast on large libraries.
+pub fn optimize_on_close(conn: &Connection) -> DbResult<()> {
+    conn.execute_batch("PRAGMA optimize")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, DatabaseConfig, TrackMetadata, library};
+
+    #[test]
+    fn test_integrity_check_fresh_in_memory_db() {
+        let db = Database::new_in_memory().unwrap();
+        let result = db.with_maintenance_conn(integrity_check).unwrap();
+
+        assert!(result.ok);
+        assert_eq!(result.messages, vec!["ok".to_string()]);
+    }
+
+    #[test]
+    fn test_integrity_check_fresh_file_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("mt.db")).unwrap();
+        let result = db.with_maintenance_conn(integrity_check).unwrap();
+
+        assert!(result.ok);
+        assert_eq!(result.messages, vec!["ok".to_string()]);
+    }
+
+    #[test]
+    fn test_vacuum_and_optimize_with_pooled_connection_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("mt.db")).unwrap();
+
+        // Holding a pooled connection must not block the dedicated maintenance connection
+        let _held = db.conn().unwrap();
+
+        assert!(db.with_maintenance_conn(vacuum).unwrap().ok);
+        assert!(db.with_maintenance_conn(optimize).unwrap().ok);
+        assert!(db.with_maintenance_conn(integrity_check).unwrap().ok);
+    }
+
+    #[test]
+    fn test_checkpoint_mode_parsing() {
+        assert_eq!(
+            "passive".parse::<CheckpointMode>().unwrap(),
+            CheckpointMode::Passive
+        );
+        assert_eq!(
+            " FULL ".parse::<CheckpointMode>().unwrap(),
+            CheckpointMode::Full
+        );
+        assert_eq!(
+            "Truncate".parse::<CheckpointMode>().unwrap(),
+            CheckpointMode::Truncate
+        );
+        assert!("RESTART".parse::<CheckpointMode>().is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_truncates_wal_after_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mt.db");
+        // Automatic checkpoints off, so the WAL only shrinks when asked
+        let config = DatabaseConfig {
+            wal_autocheckpoint: 0,
+            ..DatabaseConfig::default()
+        };
+        let db = Database::with_config(&path, &config).unwrap();
+        let wal_size = || {
+            std::fs::metadata(dir.path().join("mt.db-wal"))
+                .unwrap()
+                .len()
+        };
+
+        let tracks: Vec<(String, TrackMetadata)> = (0..500)
+            .map(|i| (format!("/music/{}.mp3", i), TrackMetadata::default()))
+            .collect();
+        db.with_conn(|conn| library::add_tracks_bulk(conn, &tracks))
+            .unwrap();
+        let before = wal_size();
+        assert!(before > 0);
+
+        let passive = db
+            .with_maintenance_conn(|conn| checkpoint(conn, CheckpointMode::Passive))
+            .unwrap();
+        assert!(!passive.busy);
+        assert!(passive.log_frames > 0);
+        assert_eq!(passive.checkpointed_frames, passive.log_frames);
+        // A passive checkpoint leaves the file at its size for reuse
+        assert_eq!(wal_size(), before);
+
+        let truncate = db
+            .with_maintenance_conn(|conn| checkpoint(conn, CheckpointMode::Truncate))
+            .unwrap();
+        assert!(!truncate.busy);
+        assert_eq!(wal_size(), 0);
+
+        // The data made it into the main file
+        let count: i64 = db
+            .with_conn(|conn| Ok(conn.query_row("SELECT COUNT(*) FROM library", [], |r| r.get(0))?))
+            .unwrap();
+        assert_eq!(count, 500);
+    }
+
+    #[test]
+    fn test_checkpoint_outside_wal_mode() {
+        let db = Database::new_in_memory().unwrap();
+        let result = db
+            .with_maintenance_conn(|conn| checkpoint(conn, CheckpointMode::Truncate))
+            .unwrap();
+        assert_eq!((result.log_frames, result.checkpointed_frames), (-1, -1));
+    }
+}
@@ -3,14 +3,26 @@
 //! This module provides SQLite database access with connection pooling,
 //! matching the schema and functionality of the Python backend.
 
+pub mod album_overrides;
+pub mod artwork_misses;
+pub mod audio_profiles;
+pub mod bookmarks;
+pub mod changes;
+pub mod date_filter;
 pub mod favorites;
+pub mod features;
+pub mod history;
 pub mod library;
+pub mod maintenance;
 pub mod models;
 pub mod playlists;
 pub mod queue;
+pub mod radio;
+pub mod relocate;
 pub mod schema;
 pub mod scrobble;
 pub mod settings;
+pub mod tags;
 pub mod watched;
 
 #[cfg(test)]
@@ -18,11 +30,14 @@ mod benchmarks;
 #[cfg(test)]
 mod compat_test;
 
+use parking_lot::RwLock;
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::Connection;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 pub use models::*;
@@ -52,14 +67,163 @@ pub type DbResult<T> = Result<T, DbError>;
 pub type DbPool = Pool<SqliteConnectionManager>;
 pub type DbConnection = PooledConnection<SqliteConnectionManager>;
 
+/// How long a maintenance connection waits for locks held by pooled connections
+const MAINTENANCE_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Snapshot of connection pool usage
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct PoolStatus {
+    pub max_size: u32,
+    pub connections: u32,
+    pub idle_connections: u32,
+}
+
+/// How SQLite journals writes to the database file
+///
+/// `Wal` is fastest and lets readers run alongside a writer, but keeps `-wal`
+/// and `-shm` files next to the database. `Delete` and `Truncate` keep
+/// everything in the single database file, which cloud-sync folders handle
+/// safely, at the cost of slower writes and readers blocking on the writer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JournalMode {
+    /// Write-ahead log (the default)
+    #[default]
+    Wal,
+    /// Rollback journal, deleted after each transaction
+    Delete,
+    /// Rollback journal, truncated to zero bytes after each transaction
+    Truncate,
+}
+
+impl JournalMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            JournalMode::Wal => "WAL",
+            JournalMode::Delete => "DELETE",
+            JournalMode::Truncate => "TRUNCATE",
+        }
+    }
+
+    /// `NORMAL` is only crash-safe under WAL; rollback journals need `FULL`
+    fn synchronous(self) -> &'static str {
+        match self {
+            JournalMode::Wal => "NORMAL",
+            JournalMode::Delete | JournalMode::Truncate => "FULL",
+        }
+    }
+
+    /// Apply the journal mode and its matching `synchronous` level to a connection
+    fn apply(self, conn: &Connection) -> rusqlite::Result<()> {
+        conn.execute_batch(&format!(
+            "PRAGMA journal_mode = {}; PRAGMA synchronous = {};",
+            self.as_sql(),
+            self.synchronous()
+        ))
+    }
+}
+
+impl FromStr for JournalMode {
+    type Err = DbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "WAL" => Ok(JournalMode::Wal),
+            "DELETE" => Ok(JournalMode::Delete),
+            "TRUNCATE" => Ok(JournalMode::Truncate),
+            _ => Err(DbError::Constraint(format!(
+                "Unknown journal mode '{}', expected WAL, DELETE, or TRUNCATE",
+                s
+            ))),
+        }
+    }
+}
+
+/// Connection pool configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatabaseConfig {
+    /// Maximum number of pooled connections
+    pub max_size: u32,
+    /// Connections kept open while idle
+    pub min_idle: Option<u32>,
+    /// How long a connection waits on a lock before returning "database is locked"
+    pub busy_timeout: Duration,
+    /// WAL size in pages that triggers an automatic checkpoint; 0 turns them off
+    pub wal_autocheckpoint: u32,
+    /// Journal mode; use `Delete` or `Truncate` when the database lives in a
+    /// cloud-synced folder
+    pub journal_mode: JournalMode,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 10,
+            min_idle: Some(2),
+            busy_timeout: Duration::from_secs(5),
+            wal_autocheckpoint: 1000,
+            journal_mode: JournalMode::Wal,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Build a config from `MT_DB_POOL_MAX_SIZE`, `MT_DB_POOL_MIN_IDLE`,
+    /// `MT_DB_BUSY_TIMEOUT_MS`, `MT_DB_WAL_AUTOCHECKPOINT`, and
+    /// `MT_DB_JOURNAL_MODE`, falling back to the defaults for unset or invalid
+    /// values
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+        let parse = |key: &str| lookup(key).and_then(|v| v.trim().parse::<u64>().ok());
+
+        let max_size = parse("MT_DB_POOL_MAX_SIZE")
+            .filter(|&n| n > 0)
+            .map(|n| n as u32)
+            .unwrap_or(defaults.max_size);
+        let min_idle = parse("MT_DB_POOL_MIN_IDLE")
+            .map(|n| Some((n as u32).min(max_size)))
+            .unwrap_or(defaults.min_idle.map(|n| n.min(max_size)));
+        let busy_timeout = parse("MT_DB_BUSY_TIMEOUT_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.busy_timeout);
+        let wal_autocheckpoint = parse("MT_DB_WAL_AUTOCHECKPOINT")
+            .and_then(|n| u32::try_from(n).ok())
+            .unwrap_or(defaults.wal_autocheckpoint);
+        let journal_mode = lookup("MT_DB_JOURNAL_MODE")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(defaults.journal_mode);
+
+        Self {
+            max_size,
+            min_idle,
+            busy_timeout,
+            wal_autocheckpoint,
+            journal_mode,
+        }
+    }
+}
+
 /// Main database interface with connection pooling
+///
+/// Clones share the pool, so a relocation is seen by every clone.
 #[derive(Clone)]
 pub struct Database {
-    pool: Arc<DbPool>,
+    inner: Arc<RwLock<DatabaseInner>>,
+}
+
+/// The pool and the file behind it, replaced together on relocation
+struct DatabaseInner {
+    pool: DbPool,
+    /// Database file path, or `None` for in-memory databases
+    path: Option<PathBuf>,
+    config: DatabaseConfig,
 }
 
 impl Database {
-    /// Create a new database connection pool
+    /// Create a new database connection pool with the default configuration
     ///
     /// # Arguments
     /// * `db_path` - Path to the SQLite database file
@@ -67,20 +231,19 @@ impl Database {
     /// # Returns
     /// A new Database instance with initialized schema
     pub fn new<P: AsRef<Path>>(db_path: P) -> DbResult<Self> {
-        let manager = SqliteConnectionManager::file(db_path);
-        let pool = Pool::builder()
-            .max_size(10)
-            .min_idle(Some(2))
-            .build(manager)?;
-
-        let db = Self {
-            pool: Arc::new(pool),
-        };
+        Self::with_config(db_path, &DatabaseConfig::default())
+    }
 
-        // Initialize schema and run migrations
-        db.init()?;
+    /// Create a new database connection pool with a custom configuration
+    pub fn with_config<P: AsRef<Path>>(db_path: P, config: &DatabaseConfig) -> DbResult<Self> {
+        let path = db_path.as_ref().to_path_buf();
+        let pool = Self::open_pool(&path, config)?;
 
-        Ok(db)
+        Ok(Self::from_inner(DatabaseInner {
+            pool,
+            path: Some(path),
+            config: config.clone(),
+        }))
     }
 
     /// Create an in-memory database (useful for testing)
@@ -88,24 +251,53 @@ impl Database {
         let manager = SqliteConnectionManager::memory();
         let pool = Pool::builder().max_size(1).build(manager)?;
 
-        let db = Self {
-            pool: Arc::new(pool),
-        };
+        Self::init(&pool, JournalMode::default())?;
+
+        Ok(Self::from_inner(DatabaseInner {
+            pool,
+            path: None,
+            config: DatabaseConfig::default(),
+        }))
+    }
+
+    fn from_inner(inner: DatabaseInner) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+        }
+    }
+
+    /// Open a pool on a database file, initializing its schema
+    fn open_pool(path: &Path, config: &DatabaseConfig) -> DbResult<DbPool> {
+        let busy_timeout = config.busy_timeout;
+        let wal_autocheckpoint = config.wal_autocheckpoint;
+        let journal_mode = config.journal_mode;
+        // Every pooled connection waits on locks instead of failing with "database is locked".
+        // The autocheckpoint threshold and rollback journal modes are per connection,
+        // so they're set on each one too.
+        let manager = SqliteConnectionManager::file(path).with_init(move |conn| {
+            conn.busy_timeout(busy_timeout)?;
+            conn.pragma_update(None, "wal_autocheckpoint", wal_autocheckpoint)?;
+            journal_mode.apply(conn)
+        });
+        let pool = Pool::builder()
+            .max_size(config.max_size)
+            .min_idle(config.min_idle)
+            .build(manager)?;
 
-        db.init()?;
+        // Initialize schema and run migrations
+        Self::init(&pool, journal_mode)?;
 
-        Ok(db)
+        Ok(pool)
     }
 
     /// Initialize the database schema and run migrations
-    fn init(&self) -> DbResult<()> {
-        let conn = self.pool.get()?;
+    fn init(pool: &DbPool, journal_mode: JournalMode) -> DbResult<()> {
+        let conn = pool.get()?;
 
         // Enable performance optimizations
+        journal_mode.apply(&conn)?;
         conn.execute_batch(
             "
-            PRAGMA journal_mode = WAL;
-            PRAGMA synchronous = NORMAL;
             PRAGMA foreign_keys = ON;
             PRAGMA cache_size = -64000;
             ",
@@ -122,7 +314,25 @@ impl Database {
 
     /// Get a connection from the pool
     pub fn conn(&self) -> DbResult<DbConnection> {
-        Ok(self.pool.get()?)
+        // Don't hold the lock while waiting for a free connection
+        let pool = self.inner.read().pool.clone();
+        Ok(pool.get()?)
+    }
+
+    /// Database file path, or `None` for in-memory databases
+    pub fn path(&self) -> Option<PathBuf> {
+        self.inner.read().path.clone()
+    }
+
+    /// Get the current connection pool usage
+    pub fn pool_status(&self) -> PoolStatus {
+        let pool = self.inner.read().pool.clone();
+        let state = pool.state();
+        PoolStatus {
+            max_size: pool.max_size(),
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        }
     }
 
     /// Execute a function with a connection, enabling foreign keys
@@ -135,6 +345,28 @@ impl Database {
         f(&conn)
     }
 
+    /// Execute a function on a dedicated connection outside the pool
+    ///
+    /// Used for long-running maintenance (VACUUM, integrity checks) so it never
+    /// holds a pooled connection that other commands are waiting on. In-memory
+    /// databases can't be reopened, so they fall back to a pooled connection.
+    pub fn with_maintenance_conn<F, T>(&self, f: F) -> DbResult<T>
+    where
+        F: FnOnce(&Connection) -> DbResult<T>,
+    {
+        match self.path() {
+            Some(path) => {
+                let conn = Connection::open(path)?;
+                conn.busy_timeout(MAINTENANCE_BUSY_TIMEOUT)?;
+                f(&conn)
+            }
+            None => {
+                let conn = self.conn()?;
+                f(&conn)
+            }
+        }
+    }
+
     /// Execute a function within a transaction
     pub fn transaction<F, T>(&self, f: F) -> DbResult<T>
     where
@@ -195,4 +427,92 @@ mod tests {
             .unwrap();
         assert_eq!(fk_enabled, 1);
     }
+
+    #[test]
+    fn test_custom_config_honored_by_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DatabaseConfig {
+            max_size: 3,
+            min_idle: Some(1),
+            busy_timeout: Duration::from_millis(1234),
+            wal_autocheckpoint: 250,
+            journal_mode: JournalMode::Wal,
+        };
+        let db = Database::with_config(dir.path().join("mt.db"), &config)
+            .expect("Failed to create database");
+
+        let status = db.pool_status();
+        assert_eq!(status.max_size, 3);
+        assert!(status.connections <= 3);
+
+        let conn = db.conn().unwrap();
+        let busy_timeout: i64 = conn
+            .query_row("PRAGMA busy_timeout", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(busy_timeout, 1234);
+        let wal_autocheckpoint: i64 = conn
+            .query_row("PRAGMA wal_autocheckpoint", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(wal_autocheckpoint, 250);
+    }
+
+    #[test]
+    fn test_default_config_preserves_pool_size() {
+        let config = DatabaseConfig::default();
+        assert_eq!(config.max_size, 10);
+        assert_eq!(config.min_idle, Some(2));
+    }
+
+    #[test]
+    fn test_config_from_lookup() {
+        let config = DatabaseConfig::from_lookup(|key| match key {
+            "MT_DB_POOL_MAX_SIZE" => Some("4".to_string()),
+            "MT_DB_POOL_MIN_IDLE" => Some("8".to_string()),
+            "MT_DB_BUSY_TIMEOUT_MS" => Some("not a number".to_string()),
+            "MT_DB_WAL_AUTOCHECKPOINT" => Some("0".to_string()),
+            "MT_DB_JOURNAL_MODE" => Some("sideways".to_string()),
+            _ => None,
+        });
+
+        assert_eq!(config.max_size, 4);
+        // min_idle is clamped to max_size
+        assert_eq!(config.min_idle, Some(4));
+        assert_eq!(config.busy_timeout, DatabaseConfig::default().busy_timeout);
+        assert_eq!(config.wal_autocheckpoint, 0);
+        assert_eq!(config.journal_mode, JournalMode::Wal);
+    }
+
+    #[test]
+    fn test_journal_mode_parsing() {
+        assert_eq!("wal".parse::<JournalMode>().unwrap(), JournalMode::Wal);
+        assert_eq!(" Delete ".parse::<JournalMode>().unwrap(), JournalMode::Delete);
+        assert_eq!("TRUNCATE".parse::<JournalMode>().unwrap(), JournalMode::Truncate);
+        assert!("MEMORY".parse::<JournalMode>().is_err());
+    }
+
+    #[test]
+    fn test_delete_journal_mode_keeps_single_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mt.db");
+        let config = DatabaseConfig {
+            journal_mode: JournalMode::Delete,
+            ..DatabaseConfig::default()
+        };
+        let db = Database::with_config(&path, &config).expect("Failed to create database");
+
+        let conn = db.conn().unwrap();
+        let journal_mode: String = conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(journal_mode, "delete");
+        // FULL is 2
+        let synchronous: i64 = conn
+            .query_row("PRAGMA synchronous", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(synchronous, 2);
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('k', 'v')", [])
+            .unwrap();
+        assert!(!dir.path().join("mt.db-wal").exists());
+    }
 }
@@ -16,6 +16,9 @@ pub struct Track {
     pub album_artist: Option<String>,
     pub track_number: Option<String>,
     pub track_total: Option<String>,
+    /// Disc of a multi-disc release; `None` if untagged, which sorts as disc 1
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
     pub date: Option<String>,
     pub duration: Option<f64>,
     pub file_size: i64,
@@ -38,12 +41,59 @@ pub struct TrackMetadata {
     pub album_artist: Option<String>,
     pub track_number: Option<String>,
     pub track_total: Option<String>,
+    pub disc_number: Option<u32>,
+    pub disc_total: Option<u32>,
     pub date: Option<String>,
+    pub genre: Option<String>,
     pub duration: Option<f64>,
     pub file_size: Option<i64>,
     pub file_mtime_ns: Option<i64>,
     pub file_inode: Option<u64>,
     pub content_hash: Option<String>,
+    /// When the track joined the library, for imports that know the real
+    /// date. `None` means now.
+    #[serde(default)]
+    pub added_date: Option<String>,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
+}
+
+/// A chapter marker within a track
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Chapter {
+    /// Chapter start offset from the beginning of the track
+    pub start_ms: u64,
+    pub title: Option<String>,
+}
+
+/// Named position within a track
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: i64,
+    pub track_id: i64,
+    pub position_ms: u64,
+    pub label: String,
+    pub created_at: Option<String>,
+}
+
+/// A custom tag and how many tracks carry it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub track_count: i64,
+}
+
+/// Audio descriptors computed from a track's samples, for local similarity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrackFeatures {
+    /// Estimated tempo, `None` if nothing rhythmic stood out
+    pub bpm: Option<f64>,
+    /// Estimated key: 0-11 are C to B major, 12-23 C to B minor
+    pub key: Option<u8>,
+    /// Average loudness in dBFS
+    pub loudness_db: f64,
+    /// Mean spectral centroid in Hz, a measure of brightness
+    pub centroid_hz: f64,
 }
 
 /// Queue item with track metadata
@@ -51,6 +101,19 @@ pub struct TrackMetadata {
 pub struct QueueItem {
     pub position: i64,
     pub track: Track,
+    /// Where the item was queued from, e.g. "playlist:12" or "album:Abbey Road"
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// What the queue is playing from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueContext {
+    /// Source of the current item, or the most common source when the
+    /// current item has none
+    pub source: Option<String>,
+    /// Queue items with that source
+    pub item_count: i64,
 }
 
 /// Queue playback state
@@ -58,8 +121,49 @@ pub struct QueueItem {
 pub struct QueueState {
     pub current_index: i64,
     pub shuffle_enabled: bool,
+    /// "none", "all" or "one", see `queue::LOOP_MODES`
     pub loop_mode: String,
     pub original_order_json: Option<String>,
+    pub shuffle_seed: Option<u64>,
+    /// Remove entries from the queue once they have played
+    #[serde(default)]
+    pub consume_enabled: bool,
+    /// "tracks" or "albums", see `queue::SHUFFLE_MODES`
+    #[serde(default = "default_shuffle_mode")]
+    pub shuffle_mode: String,
+}
+
+fn default_shuffle_mode() -> String {
+    "tracks".to_string()
+}
+
+/// The queue's play order while shuffle is on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShuffleOrder {
+    /// Queue positions in play order; empty when shuffle is off, meaning queue order
+    pub order: Vec<i64>,
+    /// Index of the current item within `order`, or -1 when nothing is current
+    pub current: i64,
+    /// Seed the unplayed part was shuffled with, if any
+    pub seed: Option<u64>,
+    /// "tracks" or "albums", see `queue::SHUFFLE_MODES`
+    pub shuffle_mode: String,
+}
+
+/// Item counts and durations for the queue, in play order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueTotals {
+    pub count: i64,
+    /// Index of the current item, or -1 when nothing is current
+    pub current_index: i64,
+    /// Seconds of audio in the whole queue
+    pub total_duration: f64,
+    /// Seconds of the items before the current one
+    pub elapsed_duration: f64,
+    /// Seconds of the current item and everything after it
+    pub remaining_duration: f64,
+    /// Items with no known duration, counted as zero in the sums
+    pub unknown_durations: i64,
 }
 
 /// Playlist metadata
@@ -70,6 +174,10 @@ pub struct Playlist {
     pub position: i64,
     pub created_at: Option<String>,
     pub track_count: i64,
+    /// Folder containing this playlist, `None` at the top level
+    pub parent_id: Option<i64>,
+    /// Folders hold other playlists and folders instead of tracks
+    pub is_folder: bool,
 }
 
 /// Playlist with tracks
@@ -80,6 +188,8 @@ pub struct PlaylistWithTracks {
     pub position: i64,
     pub created_at: Option<String>,
     pub track_count: i64,
+    pub parent_id: Option<i64>,
+    pub is_folder: bool,
     pub tracks: Vec<PlaylistTrack>,
 }
 
@@ -107,6 +217,23 @@ pub struct FavoriteTrack {
     pub favorited_date: Option<String>,
 }
 
+/// Track with the time of one recorded play
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayedTrack {
+    #[serde(flatten)]
+    pub track: Track,
+    pub played_at: String,
+}
+
+/// Named favorites list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FavoriteList {
+    pub id: i64,
+    pub name: String,
+    pub created_at: Option<String>,
+    pub track_count: i64,
+}
+
 /// Setting entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Setting {
@@ -152,13 +279,32 @@ pub struct LyricsCache {
 }
 
 /// Library statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LibraryStats {
     pub total_tracks: i64,
     pub total_duration: i64,
     pub total_size: i64,
     pub total_artists: i64,
     pub total_albums: i64,
+    /// Tracks flagged as missing from disk
+    pub missing_tracks: i64,
+    /// Tracks whose artwork lookup found nothing (unchecked tracks are not counted)
+    pub tracks_without_artwork: i64,
+    /// Tracks lacking an inode or content hash for move/duplicate detection
+    pub tracks_missing_fingerprints: i64,
+    /// Tracks sharing an inode or content hash with an earlier track
+    pub potential_duplicates: i64,
+}
+
+/// Tracks changed or deleted since a library change sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryChanges {
+    /// Current change sequence, to pass to the next query
+    pub seq: i64,
+    /// Tracks added or modified since the given sequence
+    pub changed: Vec<i64>,
+    /// Tracks deleted since the given sequence
+    pub deleted: Vec<i64>,
 }
 
 /// File fingerprint for change detection
@@ -222,6 +368,26 @@ impl LibrarySortColumn {
             LibrarySortColumn::LastPlayed => "last_played",
         }
     }
+
+    /// `ORDER BY` terms for this column in `order`
+    ///
+    /// Tracks without an added date sort last either way, and tracks added
+    /// together keep their insertion order. Within an album, tracks always
+    /// run in disc then track order, with untagged discs counting as disc 1,
+    /// so a multi-disc album plays disc 1 through before disc 2.
+    pub fn order_by_sql(&self, order: SortOrder) -> String {
+        match self {
+            LibrarySortColumn::AddedDate => format!(
+                "added_date IS NULL, added_date {0}, id {0}",
+                order.as_sql()
+            ),
+            LibrarySortColumn::Album => format!(
+                "album {}, COALESCE(disc_number, 1), CAST(track_number AS INTEGER), id",
+                order.as_sql()
+            ),
+            _ => format!("{} {}", self.as_sql(), order.as_sql()),
+        }
+    }
 }
 
 impl std::str::FromStr for LibrarySortColumn {
@@ -241,6 +407,338 @@ impl std::str::FromStr for LibrarySortColumn {
     }
 }
 
+/// How a library tag filter combines several tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagMatch {
+    /// Tracks with at least one of the tags
+    #[default]
+    Any,
+    /// Tracks with every one of the tags
+    All,
+}
+
+impl std::str::FromStr for TagMatch {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(TagMatch::Any),
+            "all" => Ok(TagMatch::All),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Dimension for top-played aggregation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopKind {
+    Artist,
+    Album,
+    Genre,
+}
+
+impl std::str::FromStr for TopKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "artist" => Ok(TopKind::Artist),
+            "album" => Ok(TopKind::Album),
+            "genre" => Ok(TopKind::Genre),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Relative time window for top-played aggregation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TopRange {
+    Week,
+    Month,
+    Year,
+    #[default]
+    All,
+}
+
+impl TopRange {
+    /// SQLite `datetime('now', ...)` modifier for the window start, `None` for all time
+    pub fn as_modifier(&self) -> Option<&'static str> {
+        match self {
+            TopRange::Week => Some("-7 days"),
+            TopRange::Month => Some("-30 days"),
+            TopRange::Year => Some("-1 year"),
+            TopRange::All => None,
+        }
+    }
+}
+
+impl std::str::FromStr for TopRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "7d" => Ok(TopRange::Week),
+            "30d" => Ok(TopRange::Month),
+            "year" => Ok(TopRange::Year),
+            "all" => Ok(TopRange::All),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Artist, album, or genre with its play count
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopEntry {
+    pub name: String,
+    /// Album artist, only set for albums
+    pub artist: Option<String>,
+    pub play_count: i64,
+    /// Most played track in the group, for artwork
+    pub track_id: i64,
+}
+
+/// Top-played entries for a range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopList {
+    pub items: Vec<TopEntry>,
+    /// True when the range had no play history and all-time play counts were used
+    pub fallback: bool,
+}
+
+/// One recorded play with the track's current details
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayRecord {
+    /// UTC time of the play (`YYYY-MM-DD HH:MM:SS`)
+    pub played_at: String,
+    pub track_id: i64,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<f64>,
+}
+
+/// Grouping for the library disk usage breakdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeGroup {
+    Artist,
+    Album,
+    Folder,
+}
+
+impl std::str::FromStr for SizeGroup {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "artist" => Ok(SizeGroup::Artist),
+            "album" => Ok(SizeGroup::Album),
+            "folder" => Ok(SizeGroup::Folder),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Disk usage of an artist, album, or folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeEntry {
+    /// Artist, album, or folder path; `None` for tracks with no artist or album
+    pub name: Option<String>,
+    /// Album artist, only set for albums
+    pub artist: Option<String>,
+    pub total_size: i64,
+    pub track_count: i64,
+}
+
+/// Display corrections for an album, applied in views without touching its tracks
+///
+/// Unset fields fall back to the album's tags.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlbumOverride {
+    /// Album key, as built by `artwork_misses::track_album_key`
+    pub album_key: String,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub year: Option<String>,
+    /// Image file shown as the album's cover instead of its tracks' artwork
+    pub artwork_path: Option<String>,
+}
+
+/// An album in the library, with any override applied
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Album {
+    /// Album key of its tracks, which stays the same under an override
+    pub album_key: String,
+    pub album: String,
+    /// Album artist, or else artist
+    pub artist: Option<String>,
+    pub year: Option<String>,
+    pub track_count: i64,
+    /// Total duration in seconds
+    pub total_duration: f64,
+    /// Whether an override changed how the album is shown
+    pub overridden: bool,
+}
+
+/// A disc of an album with gaps in its track numbers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncompleteAlbum {
+    /// Album key, as built by `artwork_misses::track_album_key`
+    pub album_key: String,
+    pub album: String,
+    /// Album artist, or else artist
+    pub artist: Option<String>,
+    /// Disc number; untagged tracks count as disc 1
+    pub disc_number: i64,
+    /// Tagged track total, or the highest track number when it's untagged or lower
+    pub expected_total: i64,
+    /// Distinct track numbers present in the library
+    pub present_count: i64,
+    pub missing_numbers: Vec<i64>,
+}
+
+/// Loudness normalization mode of an audio profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationMode {
+    #[default]
+    Off,
+    Track,
+    Album,
+}
+
+impl NormalizationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NormalizationMode::Off => "off",
+            NormalizationMode::Track => "track",
+            NormalizationMode::Album => "album",
+        }
+    }
+}
+
+impl std::str::FromStr for NormalizationMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(NormalizationMode::Off),
+            "track" => Ok(NormalizationMode::Track),
+            "album" => Ok(NormalizationMode::Album),
+            _ => Err(()),
+        }
+    }
+}
+
+/// EQ preset and normalization mode selected for playback
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioProfile {
+    pub eq_preset: String,
+    pub normalization: NormalizationMode,
+}
+
+impl Default for AudioProfile {
+    fn default() -> Self {
+        Self {
+            eq_preset: "flat".to_string(),
+            normalization: NormalizationMode::Off,
+        }
+    }
+}
+
+/// Built-in auto-playlist whose tracks are queried on every access
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VirtualPlaylist {
+    /// Tracks dated in the ten years starting at the given year
+    Decade(i32),
+    /// Tracks with the genre (any of `;`-separated genres)
+    Genre(String),
+    MostPlayed,
+    NeverPlayed,
+    RecentlyAdded,
+}
+
+impl std::str::FromStr for VirtualPlaylist {
+    type Err = ();
+
+    /// Parse a kind such as `decade:1980`, `genre:Jazz`, or `most_played`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = match s.split_once(':') {
+            Some((kind, value)) => (kind, Some(value.trim())),
+            None => (s, None),
+        };
+        match (kind.trim().to_lowercase().as_str(), value) {
+            ("decade", Some(year)) => {
+                let year: i32 = year.trim_end_matches('s').parse().map_err(|_| ())?;
+                // "decade:1984" means the 1980s
+                Ok(VirtualPlaylist::Decade(year - year.rem_euclid(10)))
+            }
+            ("genre", Some(genre)) if !genre.is_empty() => {
+                Ok(VirtualPlaylist::Genre(genre.to_string()))
+            }
+            ("most_played", None) => Ok(VirtualPlaylist::MostPlayed),
+            ("never_played", None) => Ok(VirtualPlaylist::NeverPlayed),
+            ("recently_added", None) => Ok(VirtualPlaylist::RecentlyAdded),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Track attribute an audio profile rule matches on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileCondition {
+    /// Track path is inside the folder
+    Folder,
+    /// Track genre equals the value (any of `;`-separated genres)
+    Genre,
+    /// Track artist or album artist equals the value
+    Artist,
+}
+
+impl ProfileCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProfileCondition::Folder => "folder",
+            ProfileCondition::Genre => "genre",
+            ProfileCondition::Artist => "artist",
+        }
+    }
+}
+
+impl std::str::FromStr for ProfileCondition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "folder" => Ok(ProfileCondition::Folder),
+            "genre" => Ok(ProfileCondition::Genre),
+            "artist" => Ok(ProfileCondition::Artist),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Rule selecting an audio profile for matching tracks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioProfileRule {
+    pub id: i64,
+    /// Evaluation order; the first matching rule wins
+    pub position: i64,
+    pub condition: ProfileCondition,
+    pub value: String,
+    #[serde(flatten)]
+    pub profile: AudioProfile,
+}
+
+/// Profile chosen for a track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedProfile {
+    #[serde(flatten)]
+    pub profile: AudioProfile,
+    /// Rule that matched, or None when the global profile applies
+    pub rule_id: Option<i64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,15 +1,38 @@
 //! Playlist database operations.
 //!
-//! Operations for playlists and playlist items.
-
-use rusqlite::{params, Connection};
-
-use crate::db::{DbResult, Playlist, PlaylistTrack, PlaylistWithTracks, Track};
+//! Operations for playlists and playlist items. Playlists can be grouped into
+//! folders: a folder is a playlist row with `is_folder` set, and any playlist
+//! or folder points at its folder through `parent_id`. Positions order the
+//! children of each folder.
+
+use rusqlite::{params, Connection, Row};
+
+use crate::db::library::row_to_track;
+use crate::db::queue::calculate_block_move;
+use crate::db::{
+    settings, DbError, DbResult, LibrarySortColumn, Playlist, PlaylistTrack, PlaylistWithTracks,
+    SortOrder, Track, VirtualPlaylist,
+};
+
+/// Settings key for repairing broken track positions when a playlist is read
+pub const AUTO_REPAIR_POSITIONS_SETTING: &str = "playlist_auto_repair_positions";
+
+fn row_to_playlist(row: &Row, track_count: i64) -> rusqlite::Result<Playlist> {
+    Ok(Playlist {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        position: row.get::<_, Option<i64>>("position")?.unwrap_or(0),
+        created_at: row.get("created_at")?,
+        track_count,
+        parent_id: row.get("parent_id")?,
+        is_folder: row.get::<_, Option<i64>>("is_folder")?.unwrap_or(0) != 0,
+    })
+}
 
 /// Get all playlists with track counts
 pub fn get_playlists(conn: &Connection) -> DbResult<Vec<Playlist>> {
     let mut stmt = conn.prepare(
-        "SELECT p.id, p.name, p.position, p.created_at,
+        "SELECT p.id, p.name, p.position, p.created_at, p.parent_id, p.is_folder,
                 COUNT(pi.id) as track_count
          FROM playlists p
          LEFT JOIN playlist_items pi ON p.id = pi.playlist_id
@@ -18,15 +41,7 @@ pub fn get_playlists(conn: &Connection) -> DbResult<Vec<Playlist>> {
     )?;
 
     let playlists: Vec<Playlist> = stmt
-        .query_map([], |row| {
-            Ok(Playlist {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                position: row.get::<_, Option<i64>>("position")?.unwrap_or(0),
-                created_at: row.get("created_at")?,
-                track_count: row.get("track_count")?,
-            })
-        })?
+        .query_map([], |row| row_to_playlist(row, row.get("track_count")?))?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -40,17 +55,7 @@ pub fn create_playlist(conn: &Connection, name: &str) -> DbResult<Option<Playlis
             let id = conn.last_insert_rowid();
 
             let mut stmt = conn.prepare("SELECT * FROM playlists WHERE id = ?")?;
-            let playlist = stmt
-                .query_row([id], |row| {
-                    Ok(Playlist {
-                        id: row.get("id")?,
-                        name: row.get("name")?,
-                        position: row.get::<_, Option<i64>>("position")?.unwrap_or(0),
-                        created_at: row.get("created_at")?,
-                        track_count: 0,
-                    })
-                })
-                .ok();
+            let playlist = stmt.query_row([id], |row| row_to_playlist(row, 0)).ok();
 
             Ok(playlist)
         }
@@ -63,67 +68,85 @@ pub fn create_playlist(conn: &Connection, name: &str) -> DbResult<Option<Playlis
     }
 }
 
-/// Get a playlist with its tracks
+/// Which of a playlist's tracks to return, and in what order
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistTrackQuery {
+    /// Column to sort on, `None` for the playlist's manual order
+    pub sort_by: Option<LibrarySortColumn>,
+    pub sort_order: SortOrder,
+    /// Most tracks to return, `None` for all of them
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
+/// Get a playlist with all its tracks, in manual order
 pub fn get_playlist(conn: &Connection, playlist_id: i64) -> DbResult<Option<PlaylistWithTracks>> {
+    get_playlist_page(conn, playlist_id, &PlaylistTrackQuery::default())
+}
+
+/// Get a playlist with one page of its tracks
+///
+/// `track_count` is the whole playlist, not the page. Missing tracks are
+/// included, flagged, so positions line up from page to page. Sorting by a
+/// column keeps manual order among equal values.
+pub fn get_playlist_page(
+    conn: &Connection,
+    playlist_id: i64,
+    query: &PlaylistTrackQuery,
+) -> DbResult<Option<PlaylistWithTracks>> {
     // Get playlist metadata
     let playlist = match conn.query_row(
         "SELECT * FROM playlists WHERE id = ?",
         [playlist_id],
-        |row| {
-            Ok(Playlist {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                position: row.get::<_, Option<i64>>("position")?.unwrap_or(0),
-                created_at: row.get("created_at")?,
-                track_count: 0,
-            })
-        },
+        |row| row_to_playlist(row, 0),
     ) {
         Ok(p) => p,
         Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
         Err(e) => return Err(e.into()),
     };
 
-    // Get tracks
-    let mut stmt = conn.prepare(
-        "SELECT l.id, l.filepath, l.title, l.artist, l.album, l.album_artist,
-                l.track_number, l.track_total, l.date, l.duration, l.file_size,
-                l.play_count, l.last_played, l.added_date, l.missing, l.last_seen_at,
-                l.file_mtime_ns, l.file_inode, l.content_hash, pi.position, pi.added_at
+    let track_count: i64 = conn.query_row(
+        "SELECT COUNT(*)
          FROM playlist_items pi
          JOIN library l ON pi.track_id = l.id
-         WHERE pi.playlist_id = ?
-         ORDER BY pi.position ASC",
+         WHERE pi.playlist_id = ?",
+        [playlist_id],
+        |row| row.get(0),
     )?;
 
+    // The inner query leaves one `id` column, so library sort terms apply as is
+    let order_by = match query.sort_by {
+        Some(column) => format!("{}, position ASC", column.order_by_sql(query.sort_order)),
+        None => "position ASC".to_string(),
+    };
+    let sql = format!(
+        "SELECT * FROM (
+             SELECT l.id, l.filepath, l.title, l.artist, l.album, l.album_artist,
+                    l.track_number, l.track_total, l.disc_number, l.disc_total,
+                    l.date, l.duration, l.file_size,
+                    l.play_count, l.last_played, l.added_date, l.missing, l.last_seen_at,
+                    l.file_mtime_ns, l.file_inode, l.content_hash, pi.position, pi.added_at
+             FROM playlist_items pi
+             JOIN library l ON pi.track_id = l.id
+             WHERE pi.playlist_id = ?
+         )
+         ORDER BY {}
+         LIMIT ? OFFSET ?",
+        order_by
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
     let tracks: Vec<PlaylistTrack> = stmt
-        .query_map([playlist_id], |row| {
-            Ok(PlaylistTrack {
-                position: row.get("position")?,
-                added_date: row.get("added_at")?,
-                track: Track {
-                    id: row.get("id")?,
-                    filepath: row.get("filepath")?,
-                    title: row.get("title")?,
-                    artist: row.get("artist")?,
-                    album: row.get("album")?,
-                    album_artist: row.get("album_artist")?,
-                    track_number: row.get("track_number")?,
-                    track_total: row.get("track_total")?,
-                    date: row.get("date")?,
-                    duration: row.get("duration")?,
-                    file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
-                    file_mtime_ns: row.get("file_mtime_ns")?,
-                    file_inode: row.get("file_inode")?,
-                    content_hash: row.get("content_hash")?,
-                    added_date: row.get("added_date")?,
-                    last_played: row.get("last_played")?,
-                    play_count: row.get::<_, Option<i64>>("play_count")?.unwrap_or(0),
-                    missing: row.get::<_, Option<i64>>("missing")?.unwrap_or(0) != 0,
-                    last_seen_at: row.get("last_seen_at")?,
-                },
-            })
-        })?
+        .query_map(
+            params![playlist_id, query.limit.unwrap_or(-1), query.offset.max(0)],
+            |row| {
+                Ok(PlaylistTrack {
+                    position: row.get("position")?,
+                    added_date: row.get("added_at")?,
+                    track: row_to_track(row)?,
+                })
+            },
+        )?
         .filter_map(|r| r.ok())
         .collect();
 
@@ -132,7 +155,9 @@ pub fn get_playlist(conn: &Connection, playlist_id: i64) -> DbResult<Option<Play
         name: playlist.name,
         position: playlist.position,
         created_at: playlist.created_at,
-        track_count: tracks.len() as i64,
+        track_count,
+        parent_id: playlist.parent_id,
+        is_folder: playlist.is_folder,
         tracks,
     }))
 }
@@ -167,15 +192,7 @@ pub fn update_playlist(
     match conn.query_row(
         "SELECT * FROM playlists WHERE id = ?",
         [playlist_id],
-        |row| {
-            Ok(Playlist {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                position: row.get::<_, Option<i64>>("position")?.unwrap_or(0),
-                created_at: row.get("created_at")?,
-                track_count,
-            })
-        },
+        |row| row_to_playlist(row, track_count),
     ) {
         Ok(p) => Ok(Some(p)),
         Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -184,11 +201,172 @@ pub fn update_playlist(
 }
 
 /// Delete a playlist
+///
+/// Refuses to delete a folder that still has children; use
+/// `delete_playlist_tree` to delete a folder with its contents.
 pub fn delete_playlist(conn: &Connection, playlist_id: i64) -> DbResult<bool> {
+    let child_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM playlists WHERE parent_id = ?",
+        [playlist_id],
+        |row| row.get(0),
+    )?;
+    if child_count > 0 {
+        return Err(DbError::Constraint("Folder is not empty".to_string()));
+    }
+
     let deleted = conn.execute("DELETE FROM playlists WHERE id = ?", [playlist_id])?;
     Ok(deleted > 0)
 }
 
+/// Delete a playlist or folder along with everything nested inside it
+pub fn delete_playlist_tree(conn: &Connection, playlist_id: i64) -> DbResult<bool> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM playlists WHERE id = ?",
+        [playlist_id],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(false);
+    }
+
+    let mut ids = get_descendant_ids(conn, playlist_id)?;
+    ids.push(playlist_id);
+
+    let placeholders = ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+    // Delete items explicitly in case foreign keys are off for this connection
+    conn.execute(
+        &format!("DELETE FROM playlist_items WHERE playlist_id IN ({})", placeholders),
+        rusqlite::params_from_iter(ids.iter()),
+    )?;
+    conn.execute(
+        &format!("DELETE FROM playlists WHERE id IN ({})", placeholders),
+        rusqlite::params_from_iter(ids.iter()),
+    )?;
+    Ok(true)
+}
+
+/// IDs of every playlist and folder nested below `playlist_id`
+pub fn get_descendant_ids(conn: &Connection, playlist_id: i64) -> DbResult<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE descendants(id) AS (
+            SELECT id FROM playlists WHERE parent_id = ?1
+            UNION
+            SELECT p.id FROM playlists p JOIN descendants d ON p.parent_id = d.id
+         )
+         SELECT id FROM descendants",
+    )?;
+    let ids = stmt
+        .query_map([playlist_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(ids)
+}
+
+/// Check that `parent_id`, if set, names an existing folder
+fn ensure_folder(conn: &Connection, parent_id: Option<i64>) -> DbResult<()> {
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    match conn.query_row(
+        "SELECT is_folder FROM playlists WHERE id = ?",
+        [parent_id],
+        |row| row.get::<_, Option<i64>>(0),
+    ) {
+        Ok(Some(is_folder)) if is_folder != 0 => Ok(()),
+        Ok(_) => Err(DbError::Constraint(format!(
+            "Playlist {} is not a folder",
+            parent_id
+        ))),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(DbError::NotFound(format!(
+            "Folder with id {} not found",
+            parent_id
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Position after the last child of a folder (or of the top level)
+fn next_child_position(conn: &Connection, parent_id: Option<i64>) -> DbResult<i64> {
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(position), -1) + 1 FROM playlists WHERE parent_id IS ?",
+        [parent_id],
+        |row| row.get(0),
+    )?)
+}
+
+/// Create a playlist folder, at the end of `parent_id` or the top level
+///
+/// Returns `None` if the name is already taken by a playlist or folder.
+pub fn create_playlist_folder(
+    conn: &Connection,
+    name: &str,
+    parent_id: Option<i64>,
+) -> DbResult<Option<Playlist>> {
+    ensure_folder(conn, parent_id)?;
+    let position = next_child_position(conn, parent_id)?;
+
+    match conn.execute(
+        "INSERT INTO playlists (name, parent_id, is_folder, position) VALUES (?, ?, 1, ?)",
+        params![name, parent_id, position],
+    ) {
+        Ok(_) => {
+            let id = conn.last_insert_rowid();
+            let folder = conn
+                .query_row("SELECT * FROM playlists WHERE id = ?", [id], |row| {
+                    row_to_playlist(row, 0)
+                })
+                .ok();
+            Ok(folder)
+        }
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            Ok(None) // Name already exists
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Move a playlist or folder to the end of another folder, or to the top level
+///
+/// Returns `false` if the playlist doesn't exist. Moving a folder into itself
+/// or one of its descendants is refused, since it would detach the subtree.
+pub fn move_playlist(
+    conn: &Connection,
+    playlist_id: i64,
+    new_parent_id: Option<i64>,
+) -> DbResult<bool> {
+    let current_parent: Option<i64> = match conn.query_row(
+        "SELECT parent_id FROM playlists WHERE id = ?",
+        [playlist_id],
+        |row| row.get(0),
+    ) {
+        Ok(parent) => parent,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    if current_parent == new_parent_id {
+        return Ok(true);
+    }
+
+    ensure_folder(conn, new_parent_id)?;
+    if let Some(new_parent_id) = new_parent_id
+        && (new_parent_id == playlist_id
+            || get_descendant_ids(conn, playlist_id)?.contains(&new_parent_id))
+    {
+        return Err(DbError::Constraint(
+            "Cannot move a folder into itself or one of its subfolders".to_string(),
+        ));
+    }
+
+    let position = next_child_position(conn, new_parent_id)?;
+    conn.execute(
+        "UPDATE playlists SET parent_id = ?, position = ? WHERE id = ?",
+        params![new_parent_id, position, playlist_id],
+    )?;
+    Ok(true)
+}
+
 /// Add tracks to a playlist
 pub fn add_tracks_to_playlist(
     conn: &Connection,
@@ -250,7 +428,7 @@ pub fn remove_track_from_playlist(
 
     // Reindex positions
     let mut stmt =
-        conn.prepare("SELECT id FROM playlist_items WHERE playlist_id = ? ORDER BY position")?;
+        conn.prepare("SELECT id FROM playlist_items WHERE playlist_id = ? ORDER BY position, id")?;
     let items: Vec<i64> = stmt
         .query_map([playlist_id], |row| row.get(0))?
         .filter_map(|r| r.ok())
@@ -274,7 +452,7 @@ pub fn reorder_playlist(
     to_position: i64,
 ) -> DbResult<bool> {
     let mut stmt = conn.prepare(
-        "SELECT id, track_id FROM playlist_items WHERE playlist_id = ? ORDER BY position",
+        "SELECT id, track_id FROM playlist_items WHERE playlist_id = ? ORDER BY position, id",
     )?;
     let items: Vec<i64> = stmt
         .query_map([playlist_id], |row| row.get::<_, i64>(0))?
@@ -304,6 +482,70 @@ pub fn reorder_playlist(
     Ok(true)
 }
 
+/// Whether a playlist's track positions run 0, 1, 2, ... with no gaps or
+/// duplicates
+pub fn positions_are_dense(conn: &Connection, playlist_id: i64) -> DbResult<bool> {
+    let (count, distinct, min, max): (i64, i64, i64, i64) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT position),
+                COALESCE(MIN(position), 0), COALESCE(MAX(position), -1)
+         FROM playlist_items WHERE playlist_id = ?",
+        [playlist_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    Ok(count == 0 || (distinct == count && min == 0 && max == count - 1))
+}
+
+/// Rewrite a playlist's track positions as 0, 1, 2, ... in their current order
+///
+/// Left behind by interrupted reorders, tracks sharing a position keep the
+/// order they were added in. Returns whether any position changed.
+pub fn repair_positions(conn: &Connection, playlist_id: i64) -> DbResult<bool> {
+    let mut stmt = conn.prepare(
+        "SELECT id, position FROM playlist_items WHERE playlist_id = ? ORDER BY position, id",
+    )?;
+    let items: Vec<(i64, i64)> = stmt
+        .query_map([playlist_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut changed = false;
+    for (pos, (item_id, position)) in items.into_iter().enumerate() {
+        if position != pos as i64 {
+            conn.execute(
+                "UPDATE playlist_items SET position = ? WHERE id = ?",
+                params![pos as i64, item_id],
+            )?;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+/// Repair the track positions of every playlist, returning the IDs changed
+pub fn repair_all_positions(conn: &Connection) -> DbResult<Vec<i64>> {
+    let mut stmt =
+        conn.prepare("SELECT DISTINCT playlist_id FROM playlist_items ORDER BY playlist_id")?;
+    let playlist_ids: Vec<i64> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut repaired = Vec::new();
+    for playlist_id in playlist_ids {
+        if repair_positions(conn, playlist_id)? {
+            repaired.push(playlist_id);
+        }
+    }
+    Ok(repaired)
+}
+
+/// Whether broken positions are repaired when a playlist is read; on unless
+/// turned off
+pub fn auto_repair_enabled(conn: &Connection) -> DbResult<bool> {
+    Ok(settings::get_setting(conn, AUTO_REPAIR_POSITIONS_SETTING)?
+        .is_none_or(|value| value != "0" && value != "false"))
+}
+
 /// Get the number of tracks in a playlist
 pub fn get_playlist_track_count(conn: &Connection, playlist_id: i64) -> DbResult<i64> {
     let count: i64 = conn.query_row(
@@ -348,6 +590,64 @@ pub fn reorder_playlists(
     Ok(true)
 }
 
+/// Move several playlists or folders to `to_position` in one go
+///
+/// They must all share a folder (or all be at the top level); moving between
+/// folders is `move_playlist`'s job. `to_position` counts among that folder's
+/// children, and the moved playlists keep their relative order, following
+/// `queue::calculate_block_move`. Returns `false` if any playlist doesn't
+/// exist.
+pub fn reorder_playlist_block(
+    conn: &Connection,
+    playlist_ids: &[i64],
+    to_position: i64,
+) -> DbResult<bool> {
+    let mut parent_id = None;
+    for (i, &playlist_id) in playlist_ids.iter().enumerate() {
+        let parent: Option<i64> = match conn.query_row(
+            "SELECT parent_id FROM playlists WHERE id = ?",
+            [playlist_id],
+            |row| row.get(0),
+        ) {
+            Ok(parent) => parent,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        if i > 0 && parent != parent_id {
+            return Err(DbError::Constraint(
+                "Playlists in different folders can't be reordered together".to_string(),
+            ));
+        }
+        parent_id = parent;
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT id FROM playlists WHERE parent_id IS ?
+         ORDER BY position ASC, created_at ASC, id ASC",
+    )?;
+    let siblings: Vec<i64> = stmt
+        .query_map([parent_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let positions: Vec<i64> = playlist_ids
+        .iter()
+        .filter_map(|id| siblings.iter().position(|s| s == id))
+        .map(|i| i as i64)
+        .collect();
+    let Some(order) = calculate_block_move(siblings.len(), &positions, to_position) else {
+        return Ok(false);
+    };
+
+    for (pos, &i) in order.iter().enumerate() {
+        conn.execute(
+            "UPDATE playlists SET position = ? WHERE id = ?",
+            params![pos as i64, siblings[i]],
+        )?;
+    }
+    Ok(true)
+}
+
 /// Generate a unique playlist name
 pub fn generate_unique_playlist_name(conn: &Connection, base: &str) -> DbResult<String> {
     let exists: bool = conn
@@ -380,11 +680,74 @@ pub fn generate_unique_playlist_name(conn: &Connection, base: &str) -> DbResult<
     }
 }
 
+/// Resolve a virtual playlist into its current tracks
+///
+/// Nothing is stored: membership is recomputed from the library each time.
+/// Missing tracks are left out. A track's year is the leading four digits of
+/// its date tag, so `1984`, `1984-05-01`, and `1984/05` all count as 1984.
+pub fn get_virtual_playlist(
+    conn: &Connection,
+    kind: &VirtualPlaylist,
+    limit: i64,
+) -> DbResult<Vec<Track>> {
+    let (condition, order, value): (&str, &str, Option<Box<dyn rusqlite::ToSql>>) = match kind {
+        VirtualPlaylist::Decade(start) => (
+            "trim(date) GLOB '[0-9][0-9][0-9][0-9]*'
+             AND CAST(substr(trim(date), 1, 4) AS INTEGER) BETWEEN ?2 AND ?2 + 9",
+            "substr(trim(date), 1, 4), artist, album, track_number, id",
+            Some(Box::new(*start)),
+        ),
+        VirtualPlaylist::Genre(genre) => (
+            "';' || lower(replace(replace(genre, '; ', ';'), ' ;', ';')) || ';'
+                 LIKE '%;' || lower(trim(?2)) || ';%'",
+            "artist, album, track_number, id",
+            Some(Box::new(genre.clone())),
+        ),
+        VirtualPlaylist::MostPlayed => (
+            "play_count > 0",
+            "play_count DESC, last_played DESC, id",
+            None,
+        ),
+        VirtualPlaylist::NeverPlayed => (
+            "(play_count = 0 OR play_count IS NULL)",
+            "added_date DESC, id",
+            None,
+        ),
+        VirtualPlaylist::RecentlyAdded => (
+            "added_date IS NOT NULL",
+            "added_date DESC, id DESC",
+            None,
+        ),
+    };
+
+    let sql = format!(
+        "SELECT id, filepath, title, artist, album, album_artist,
+                track_number, track_total, disc_number, disc_total, date, duration, file_size,
+                play_count, last_played, added_date, missing, last_seen_at,
+                file_mtime_ns, file_inode, content_hash
+         FROM library
+         WHERE (missing = 0 OR missing IS NULL) AND {}
+         ORDER BY {}
+         LIMIT ?1",
+        condition, order
+    );
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(limit)];
+    params_vec.extend(value);
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    let mut stmt = conn.prepare(&sql)?;
+    let tracks = stmt
+        .query_map(params_refs.as_slice(), row_to_track)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(tracks)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::{
-        library::add_track,
+        library::{add_track, mark_track_missing, set_play_count},
         schema::{create_tables, run_migrations},
         TrackMetadata,
     };
@@ -434,6 +797,59 @@ mod tests {
         assert_eq!(playlist.tracks.len(), 5);
     }
 
+    #[test]
+    fn test_playlist_pages_keep_manual_order() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 7);
+        let order: Vec<i64> = [6, 2, 4, 0, 5, 1, 3].iter().map(|&i| ids[i]).collect();
+        let playlist = create_playlist(&conn, "Big").unwrap().unwrap();
+        add_tracks_to_playlist(&conn, playlist.id, &order, None).unwrap();
+        mark_track_missing(&conn, ids[4]).unwrap();
+
+        let mut paged = Vec::new();
+        for offset in [0, 3, 6, 9] {
+            let query = PlaylistTrackQuery {
+                limit: Some(3),
+                offset,
+                ..Default::default()
+            };
+            let page = get_playlist_page(&conn, playlist.id, &query).unwrap().unwrap();
+            assert_eq!(page.track_count, 7);
+            assert_eq!(page.tracks.len(), [3, 3, 1, 0][offset as usize / 3]);
+            paged.extend(page.tracks);
+        }
+
+        let positions: Vec<i64> = paged.iter().map(|t| t.position).collect();
+        assert_eq!(positions, (0..7).collect::<Vec<_>>());
+        let paged_ids: Vec<i64> = paged.iter().map(|t| t.track.id).collect();
+        assert_eq!(paged_ids, order);
+        // The missing track keeps its place, flagged
+        assert!(paged[2].track.missing);
+        assert_eq!(paged.iter().filter(|t| t.track.missing).count(), 1);
+    }
+
+    #[test]
+    fn test_playlist_page_sorted_by_column() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 5);
+        let playlist = create_playlist(&conn, "Sorted").unwrap().unwrap();
+        let order: Vec<i64> = ids.iter().rev().copied().collect();
+        add_tracks_to_playlist(&conn, playlist.id, &order, None).unwrap();
+
+        let query = PlaylistTrackQuery {
+            sort_by: Some(LibrarySortColumn::Title),
+            sort_order: SortOrder::Asc,
+            limit: Some(2),
+            offset: 1,
+        };
+        let page = get_playlist_page(&conn, playlist.id, &query).unwrap().unwrap();
+        let titles: Vec<_> = page.tracks.iter().map(|t| t.track.title.clone().unwrap()).collect();
+        assert_eq!(titles, ["Track 2", "Track 3"]);
+        // Positions still report the manual order
+        assert_eq!(page.tracks[0].position, 3);
+        assert_eq!(page.track_count, 5);
+    }
+
     #[test]
     fn test_remove_track_from_playlist() {
         let conn = setup_test_db();
@@ -636,6 +1052,74 @@ mod tests {
         assert_eq!(playlists[2].name, "First");
     }
 
+    /// Names of the playlists in `parent_id`, in sidebar order
+    fn child_names(conn: &Connection, parent_id: Option<i64>) -> Vec<String> {
+        get_playlists(conn)
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.parent_id == parent_id)
+            .map(|p| p.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_reorder_playlist_block_to_start_and_end() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|name| create_playlist(&conn, name).unwrap().unwrap().id)
+            .collect();
+
+        assert!(reorder_playlist_block(&conn, &[ids[4], ids[2]], 0).unwrap());
+        assert_eq!(child_names(&conn, None), ["C", "E", "A", "B", "D"]);
+
+        assert!(reorder_playlist_block(&conn, &[ids[0], ids[2]], 99).unwrap());
+        assert_eq!(child_names(&conn, None), ["E", "B", "D", "C", "A"]);
+
+        assert!(!reorder_playlist_block(&conn, &[ids[0], 999], 0).unwrap());
+        assert_eq!(child_names(&conn, None), ["E", "B", "D", "C", "A"]);
+    }
+
+    #[test]
+    fn test_reorder_playlist_block_target_inside_block() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = ["A", "B", "C", "D", "E"]
+            .iter()
+            .map(|name| create_playlist(&conn, name).unwrap().unwrap().id)
+            .collect();
+
+        // Dropping a block onto itself leaves it in place
+        assert!(reorder_playlist_block(&conn, &[ids[1], ids[2], ids[3]], 2).unwrap());
+        assert_eq!(child_names(&conn, None), ["A", "B", "C", "D", "E"]);
+
+        // Negative targets clamp to the start
+        assert!(reorder_playlist_block(&conn, &[ids[3], ids[4]], -5).unwrap());
+        assert_eq!(child_names(&conn, None), ["D", "E", "A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_reorder_playlist_block_stays_within_folder() {
+        let conn = setup_test_db();
+        let folder = create_playlist_folder(&conn, "Folder", None).unwrap().unwrap();
+        let top = create_playlist(&conn, "Top").unwrap().unwrap();
+        let children: Vec<i64> = ["X", "Y", "Z"]
+            .iter()
+            .map(|name| {
+                let playlist = create_playlist(&conn, name).unwrap().unwrap();
+                move_playlist(&conn, playlist.id, Some(folder.id)).unwrap();
+                playlist.id
+            })
+            .collect();
+
+        assert!(reorder_playlist_block(&conn, &[children[2], children[1]], 0).unwrap());
+        assert_eq!(child_names(&conn, Some(folder.id)), ["Y", "Z", "X"]);
+
+        let result = reorder_playlist_block(&conn, &[top.id, children[0]], 0);
+        assert!(matches!(result, Err(DbError::Constraint(_))));
+        assert_eq!(child_names(&conn, Some(folder.id)), ["Y", "Z", "X"]);
+        assert_eq!(child_names(&conn, None), ["Folder", "Top"]);
+    }
+
     #[test]
     fn test_reorder_playlists_invalid_positions() {
         let conn = setup_test_db();
@@ -702,4 +1186,247 @@ mod tests {
         assert_eq!(playlist.tracks[0].track.artist, Some("Test Artist".to_string()));
         assert_eq!(playlist.tracks[0].position, 0);
     }
+
+    /// Names of the children of a folder (or the top level), in display order
+    fn children(conn: &Connection, parent_id: Option<i64>) -> Vec<String> {
+        get_playlists(conn)
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.parent_id == parent_id)
+            .map(|p| p.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_create_folder() {
+        let conn = setup_test_db();
+
+        let folder = create_playlist_folder(&conn, "Moods", None).unwrap().unwrap();
+        assert!(folder.is_folder);
+        assert_eq!(folder.parent_id, None);
+
+        let nested = create_playlist_folder(&conn, "Calm", Some(folder.id)).unwrap().unwrap();
+        assert_eq!(nested.parent_id, Some(folder.id));
+
+        // Names are shared with playlists
+        assert!(create_playlist_folder(&conn, "Moods", None).unwrap().is_none());
+
+        // The parent must be an existing folder
+        let playlist = create_playlist(&conn, "Plain").unwrap().unwrap();
+        assert!(create_playlist_folder(&conn, "Inside", Some(playlist.id)).is_err());
+        assert!(create_playlist_folder(&conn, "Nowhere", Some(9999)).is_err());
+    }
+
+    #[test]
+    fn test_move_playlist_orders_within_folder() {
+        let conn = setup_test_db();
+        let folder = create_playlist_folder(&conn, "Workout", None).unwrap().unwrap();
+        let run = create_playlist(&conn, "Run").unwrap().unwrap();
+        let lift = create_playlist(&conn, "Lift").unwrap().unwrap();
+        let stretch = create_playlist(&conn, "Stretch").unwrap().unwrap();
+
+        // Moved playlists are appended to the end of the folder
+        assert!(move_playlist(&conn, lift.id, Some(folder.id)).unwrap());
+        assert!(move_playlist(&conn, stretch.id, Some(folder.id)).unwrap());
+        assert!(move_playlist(&conn, run.id, Some(folder.id)).unwrap());
+        assert_eq!(children(&conn, Some(folder.id)), vec!["Lift", "Stretch", "Run"]);
+        assert_eq!(children(&conn, None), vec!["Workout"]);
+
+        // Moving back out appends to the top level
+        assert!(move_playlist(&conn, stretch.id, None).unwrap());
+        assert_eq!(children(&conn, None), vec!["Workout", "Stretch"]);
+        assert_eq!(children(&conn, Some(folder.id)), vec!["Lift", "Run"]);
+
+        // Sidebar reordering keeps the relative order inside the folder
+        assert!(reorder_playlists(&conn, 0, 3).unwrap());
+        assert_eq!(children(&conn, Some(folder.id)), vec!["Lift", "Run"]);
+
+        assert!(!move_playlist(&conn, 9999, None).unwrap());
+    }
+
+    #[test]
+    fn test_move_folder_into_descendant_refused() {
+        let conn = setup_test_db();
+        let top = create_playlist_folder(&conn, "Top", None).unwrap().unwrap();
+        let middle = create_playlist_folder(&conn, "Middle", Some(top.id)).unwrap().unwrap();
+        let bottom = create_playlist_folder(&conn, "Bottom", Some(middle.id)).unwrap().unwrap();
+
+        assert!(move_playlist(&conn, top.id, Some(top.id)).is_err());
+        assert!(move_playlist(&conn, top.id, Some(middle.id)).is_err());
+        assert!(move_playlist(&conn, top.id, Some(bottom.id)).is_err());
+        assert_eq!(children(&conn, None), vec!["Top"]);
+
+        // Moving a subfolder up or sideways is fine
+        assert!(move_playlist(&conn, bottom.id, Some(top.id)).unwrap());
+        assert!(move_playlist(&conn, middle.id, Some(bottom.id)).unwrap());
+        assert_eq!(get_descendant_ids(&conn, top.id).unwrap().len(), 2);
+
+        // Playlists can't hold other playlists
+        let playlist = create_playlist(&conn, "Leaf").unwrap().unwrap();
+        assert!(move_playlist(&conn, middle.id, Some(playlist.id)).is_err());
+    }
+
+    #[test]
+    fn test_delete_folder_refuses_or_cascades() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 2);
+        let folder = create_playlist_folder(&conn, "Archive", None).unwrap().unwrap();
+        let sub = create_playlist_folder(&conn, "2019", Some(folder.id)).unwrap().unwrap();
+        let playlist = create_playlist(&conn, "Summer").unwrap().unwrap();
+        add_tracks_to_playlist(&conn, playlist.id, &track_ids, None).unwrap();
+        move_playlist(&conn, playlist.id, Some(sub.id)).unwrap();
+
+        assert!(delete_playlist(&conn, folder.id).is_err());
+        assert_eq!(get_playlists(&conn).unwrap().len(), 3);
+
+        assert!(delete_playlist_tree(&conn, folder.id).unwrap());
+        assert!(get_playlists(&conn).unwrap().is_empty());
+        assert_eq!(get_playlist_track_count(&conn, playlist.id).unwrap(), 0);
+        assert!(!delete_playlist_tree(&conn, folder.id).unwrap());
+
+        // An empty folder deletes like a playlist
+        let empty = create_playlist_folder(&conn, "Empty", None).unwrap().unwrap();
+        assert!(delete_playlist(&conn, empty.id).unwrap());
+    }
+
+    fn add_dated_track(conn: &Connection, path: &str, date: &str, genre: &str) -> i64 {
+        let metadata = TrackMetadata {
+            date: Some(date.to_string()),
+            genre: Some(genre.to_string()),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    fn ids(tracks: &[Track]) -> Vec<i64> {
+        tracks.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_parse_virtual_playlist_kind() {
+        assert_eq!("decade:1980".parse(), Ok(VirtualPlaylist::Decade(1980)));
+        assert_eq!("decade:1987".parse(), Ok(VirtualPlaylist::Decade(1980)));
+        assert_eq!("decade:1990s".parse(), Ok(VirtualPlaylist::Decade(1990)));
+        assert_eq!("genre:Jazz".parse(), Ok(VirtualPlaylist::Genre("Jazz".to_string())));
+        assert_eq!("Most_Played".parse(), Ok(VirtualPlaylist::MostPlayed));
+        assert_eq!("never_played".parse(), Ok(VirtualPlaylist::NeverPlayed));
+        assert_eq!("recently_added".parse(), Ok(VirtualPlaylist::RecentlyAdded));
+
+        for invalid in ["decade", "decade:eighties", "genre:", "most_played:5", "top"] {
+            assert_eq!(invalid.parse::<VirtualPlaylist>(), Err(()), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_virtual_decade_selects_years_in_range() {
+        let conn = setup_test_db();
+        add_dated_track(&conn, "/m/1979.mp3", "1979-12-31", "Disco");
+        let first = add_dated_track(&conn, "/m/1980.mp3", "1980", "Pop");
+        let full = add_dated_track(&conn, "/m/1984.mp3", "1984-05-01", "Pop");
+        let slashed = add_dated_track(&conn, "/m/1989.mp3", " 1989/11", "Rock");
+        add_dated_track(&conn, "/m/1990.mp3", "1990", "Rock");
+        add_dated_track(&conn, "/m/undated.mp3", "", "Rock");
+        add_dated_track(&conn, "/m/bad.mp3", "c. 1985", "Rock");
+        let gone = add_dated_track(&conn, "/m/gone.mp3", "1985", "Pop");
+        mark_track_missing(&conn, gone).unwrap();
+
+        let tracks = get_virtual_playlist(&conn, &VirtualPlaylist::Decade(1980), 100).unwrap();
+        assert_eq!(ids(&tracks), vec![first, full, slashed]);
+
+        let limited = get_virtual_playlist(&conn, &VirtualPlaylist::Decade(1980), 2).unwrap();
+        assert_eq!(ids(&limited), vec![first, full]);
+    }
+
+    #[test]
+    fn test_virtual_never_played_and_most_played() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 4);
+        set_play_count(&conn, track_ids[0], 3, false).unwrap();
+        set_play_count(&conn, track_ids[2], 7, false).unwrap();
+
+        let never = get_virtual_playlist(&conn, &VirtualPlaylist::NeverPlayed, 100).unwrap();
+        let mut never = ids(&never);
+        never.sort();
+        assert_eq!(never, vec![track_ids[1], track_ids[3]]);
+
+        let most = get_virtual_playlist(&conn, &VirtualPlaylist::MostPlayed, 100).unwrap();
+        assert_eq!(ids(&most), vec![track_ids[2], track_ids[0]]);
+    }
+
+    #[test]
+    fn test_virtual_genre_matches_any_listed_genre() {
+        let conn = setup_test_db();
+        let jazz = add_dated_track(&conn, "/m/1.mp3", "1959", "Jazz");
+        let fusion = add_dated_track(&conn, "/m/2.mp3", "1970", "Rock; jazz");
+        add_dated_track(&conn, "/m/3.mp3", "2001", "Acid Jazz");
+
+        let genre = VirtualPlaylist::Genre("Jazz".to_string());
+        let tracks = get_virtual_playlist(&conn, &genre, 100).unwrap();
+        let mut found = ids(&tracks);
+        found.sort();
+        assert_eq!(found, vec![jazz, fusion]);
+    }
+
+    fn item_positions(conn: &Connection, playlist_id: i64) -> Vec<(i64, i64)> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT track_id, position FROM playlist_items
+                 WHERE playlist_id = ? ORDER BY position",
+            )
+            .unwrap();
+        stmt.query_map([playlist_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_repair_positions_closes_gaps_and_duplicates() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 4);
+        let playlist = create_playlist(&conn, "Broken").unwrap().unwrap();
+        add_tracks_to_playlist(&conn, playlist.id, &ids, None).unwrap();
+        assert!(positions_are_dense(&conn, playlist.id).unwrap());
+
+        // As an interrupted reorder could leave them: [0, 0, 2, 5]
+        for (track_id, position) in [(ids[0], 0), (ids[1], 0), (ids[2], 2), (ids[3], 5)] {
+            conn.execute(
+                "UPDATE playlist_items SET position = ? WHERE playlist_id = ? AND track_id = ?",
+                params![position, playlist.id, track_id],
+            )
+            .unwrap();
+        }
+        assert!(!positions_are_dense(&conn, playlist.id).unwrap());
+
+        assert!(repair_positions(&conn, playlist.id).unwrap());
+        // The tie at 0 goes to the track added first
+        assert_eq!(
+            item_positions(&conn, playlist.id),
+            [(ids[0], 0), (ids[1], 1), (ids[2], 2), (ids[3], 3)]
+        );
+        assert!(positions_are_dense(&conn, playlist.id).unwrap());
+        assert!(!repair_positions(&conn, playlist.id).unwrap());
+    }
+
+    #[test]
+    fn test_repair_all_positions_reports_changed_playlists() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 3);
+        let fine = create_playlist(&conn, "Fine").unwrap().unwrap();
+        let broken = create_playlist(&conn, "Broken").unwrap().unwrap();
+        add_tracks_to_playlist(&conn, fine.id, &ids, None).unwrap();
+        add_tracks_to_playlist(&conn, broken.id, &ids, None).unwrap();
+        conn.execute(
+            "UPDATE playlist_items SET position = position + 1 WHERE playlist_id = ?",
+            [broken.id],
+        )
+        .unwrap();
+
+        assert_eq!(repair_all_positions(&conn).unwrap(), [broken.id]);
+        assert!(positions_are_dense(&conn, broken.id).unwrap());
+        assert!(auto_repair_enabled(&conn).unwrap());
+        settings::set_setting(&conn, AUTO_REPAIR_POSITIONS_SETTING, &serde_json::json!(false))
+            .unwrap();
+        assert!(!auto_repair_enabled(&conn).unwrap());
+    }
 }
@@ -2,22 +2,183 @@
 //!
 //! Operations for the playback queue.
 
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::db::{library::get_track_by_filepath, DbResult, QueueItem, QueueState, Track};
+use crate::db::library::{get_sampled_filepaths, get_track_by_filepath, LibraryQuery};
+use crate::db::{
+    radio, settings, DbError, DbResult, QueueContext, QueueItem, QueueState, QueueTotals,
+    ShuffleOrder, Track,
+};
+
+/// Number of most recently placed tracks a shuffled track must not repeat within
+pub const SHUFFLE_NO_REPEAT_WINDOW: usize = 5;
+
+/// Default cap on how many tracks a library shuffle puts in the queue
+pub const SHUFFLE_LIBRARY_MAX_DEFAULT: usize = 1000;
+
+/// Source stamped on tracks queued by a library shuffle
+pub const SOURCE_LIBRARY: &str = "library";
+
+/// Source stamped on tracks queued by a radio or a radio top-up
+pub const SOURCE_RADIO: &str = "radio";
+
+/// Settings key for passing over queue entries whose files are missing
+pub const SKIP_MISSING_SETTING: &str = "skip_missing_in_queue";
+
+/// Settings key for showing queued tracks as they were when queued
+pub const SNAPSHOT_SETTING: &str = "queue_metadata_snapshot";
+
+/// Loop modes, in the order the player's loop button cycles through them
+///
+/// Stored as-is in `queue_state.loop_mode`: "none" stops at the end of the
+/// queue, "all" wraps to the start and "one" repeats the current entry.
+pub const LOOP_MODES: [&str; 3] = ["none", "all", "one"];
+
+/// Shuffle modes
+///
+/// Stored as-is in `queue_state.shuffle_mode`: "tracks" shuffles every entry
+/// and "albums" shuffles whole albums, playing each one's tracks in order.
+pub const SHUFFLE_MODES: [&str; 2] = ["tracks", "albums"];
+
+/// A queue row: the file and where it was queued from
+pub type QueueEntry = (String, Option<String>);
+
+/// Trim a queue source, `None` if it is blank
+pub fn normalize_source(source: Option<&str>) -> Option<String> {
+    source.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// Get the queue's files and their sources, in queue order
+pub fn get_queue_entries(conn: &Connection) -> DbResult<Vec<QueueEntry>> {
+    let mut stmt = conn.prepare("SELECT filepath, source FROM queue ORDER BY id")?;
+    let entries = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(entries)
+}
+
+/// Append entries to the end of the queue
+fn insert_entries<'a>(
+    conn: &Connection,
+    entries: impl IntoIterator<Item = &'a QueueEntry>,
+) -> DbResult<()> {
+    let mut stmt = conn.prepare("INSERT INTO queue (filepath, source) VALUES (?, ?)")?;
+    for (filepath, source) in entries {
+        stmt.execute(params![filepath, source])?;
+    }
+    capture_snapshots(conn)
+}
+
+/// Whether the queue shows the metadata tracks had when they were queued
+pub fn snapshot_enabled(conn: &Connection) -> DbResult<bool> {
+    Ok(settings::get_setting(conn, SNAPSHOT_SETTING)?
+        .is_some_and(|value| value == "1" || value == "true"))
+}
+
+/// Snapshot newly queued files, keeping the snapshots already taken
+///
+/// Snapshots are keyed by file, so rebuilding the queue in a new order keeps
+/// them; a file removed and queued again gets a fresh one.
+fn capture_snapshots(conn: &Connection) -> DbResult<()> {
+    prune_snapshots(conn)?;
+    if snapshot_enabled(conn)? {
+        conn.execute(
+            "INSERT OR IGNORE INTO queue_snapshots
+                 (filepath, track_id, title, artist, album, duration)
+             SELECT filepath, id, title, artist, album, duration FROM library
+             WHERE filepath IN (SELECT filepath FROM queue)",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Drop snapshots of files no longer in the queue
+fn prune_snapshots(conn: &Connection) -> DbResult<()> {
+    conn.execute(
+        "DELETE FROM queue_snapshots WHERE filepath NOT IN (SELECT filepath FROM queue)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Re-take the snapshot of every queued file from the library
+///
+/// Returns how many snapshots were taken. Files the library no longer has
+/// keep their old snapshot.
+pub fn refresh_snapshots(conn: &Connection) -> DbResult<i64> {
+    prune_snapshots(conn)?;
+    let refreshed = conn.execute(
+        "INSERT OR REPLACE INTO queue_snapshots
+             (filepath, track_id, title, artist, album, duration)
+         SELECT filepath, id, title, artist, album, duration FROM library
+         WHERE filepath IN (SELECT filepath FROM queue)",
+        [],
+    )?;
+    Ok(refreshed as i64)
+}
+
+/// Replace the whole queue with `entries`
+pub fn replace_queue_entries(conn: &Connection, entries: &[QueueEntry]) -> DbResult<()> {
+    conn.execute("DELETE FROM queue", [])?;
+    insert_entries(conn, entries)
+}
+
+/// Pair a reordering of the entries' filepaths back up with their sources
+///
+/// A file queued more than once hands out its sources in queue order.
+fn with_sources(order: Vec<String>, entries: &[QueueEntry]) -> Vec<QueueEntry> {
+    let mut sources: HashMap<&str, VecDeque<Option<String>>> = HashMap::new();
+    for (filepath, source) in entries {
+        sources.entry(filepath.as_str()).or_default().push_back(source.clone());
+    }
+    order
+        .into_iter()
+        .map(|filepath| {
+            let source = sources
+                .get_mut(filepath.as_str())
+                .and_then(VecDeque::pop_front)
+                .flatten();
+            (filepath, source)
+        })
+        .collect()
+}
+
+/// Stamp each filepath with the same source
+fn stamped(filepaths: impl IntoIterator<Item = String>, source: &str) -> Vec<QueueEntry> {
+    filepaths
+        .into_iter()
+        .map(|filepath| (filepath, Some(source.to_string())))
+        .collect()
+}
 
 /// Get all items in the queue with track metadata
+///
+/// With the `SNAPSHOT_SETTING` on, title, artist, album and duration come
+/// from the snapshot taken when the file was queued, so library edits don't
+/// show until `refresh_snapshots`.
 pub fn get_queue(conn: &Connection) -> DbResult<Vec<QueueItem>> {
     let mut stmt = conn.prepare(
-        "SELECT q.id as queue_id, q.filepath,
+        "SELECT q.id as queue_id, q.filepath, q.source,
                 l.id, l.title, l.artist, l.album, l.album_artist,
-                l.track_number, l.track_total, l.date, l.duration, l.file_size,
+                l.track_number, l.track_total, l.disc_number, l.disc_total,
+                l.date, l.duration, l.file_size,
                 l.play_count, l.last_played, l.added_date, l.missing, l.last_seen_at,
-                l.file_mtime_ns, l.file_inode, l.content_hash
+                l.file_mtime_ns, l.file_inode, l.content_hash,
+                s.filepath AS snapshot_filepath, s.track_id AS snapshot_track_id,
+                s.title AS snapshot_title, s.artist AS snapshot_artist,
+                s.album AS snapshot_album, s.duration AS snapshot_duration
          FROM queue q
          LEFT JOIN library l ON q.filepath = l.filepath
+         LEFT JOIN queue_snapshots s ON q.filepath = s.filepath
          ORDER BY q.id",
     )?;
+    let use_snapshots = snapshot_enabled(conn)?;
 
     let mut items = Vec::new();
     let mut rows = stmt.query([])?;
@@ -25,7 +186,7 @@ pub fn get_queue(conn: &Connection) -> DbResult<Vec<QueueItem>> {
 
     while let Some(row) = rows.next()? {
         let filepath: String = row.get("filepath")?;
-        let track = Track {
+        let mut track = Track {
             id: row.get::<_, Option<i64>>("id")?.unwrap_or(0),
             filepath: filepath.clone(),
             title: row.get("title")?,
@@ -34,6 +195,8 @@ pub fn get_queue(conn: &Connection) -> DbResult<Vec<QueueItem>> {
             album_artist: row.get("album_artist")?,
             track_number: row.get("track_number")?,
             track_total: row.get("track_total")?,
+            disc_number: row.get("disc_number")?,
+            disc_total: row.get("disc_total")?,
             date: row.get("date")?,
             duration: row.get("duration")?,
             file_size: row.get::<_, Option<i64>>("file_size")?.unwrap_or(0),
@@ -46,8 +209,21 @@ pub fn get_queue(conn: &Connection) -> DbResult<Vec<QueueItem>> {
             missing: row.get::<_, Option<i64>>("missing")?.unwrap_or(0) != 0,
             last_seen_at: row.get("last_seen_at")?,
         };
+        if use_snapshots && row.get::<_, Option<String>>("snapshot_filepath")?.is_some() {
+            if track.id == 0 {
+                track.id = row.get::<_, Option<i64>>("snapshot_track_id")?.unwrap_or(0);
+            }
+            track.title = row.get("snapshot_title")?;
+            track.artist = row.get("snapshot_artist")?;
+            track.album = row.get("snapshot_album")?;
+            track.duration = row.get("snapshot_duration")?;
+        }
 
-        items.push(QueueItem { position, track });
+        items.push(QueueItem {
+            position,
+            track,
+            source: row.get("source")?,
+        });
         position += 1;
     }
 
@@ -56,7 +232,31 @@ pub fn get_queue(conn: &Connection) -> DbResult<Vec<QueueItem>> {
 
 /// Add tracks to the queue by track IDs
 pub fn add_to_queue(conn: &Connection, track_ids: &[i64], position: Option<i64>) -> DbResult<i64> {
-    // Get filepaths for track IDs
+    add_to_queue_from(conn, track_ids, position, None)
+}
+
+/// Add tracks to the queue by track IDs, recording where they were queued from
+pub fn add_to_queue_from(
+    conn: &Connection,
+    track_ids: &[i64],
+    position: Option<i64>,
+    source: Option<&str>,
+) -> DbResult<i64> {
+    let track_map = track_filepaths(conn, track_ids)?;
+
+    let source = normalize_source(source);
+    let new_entries: Vec<QueueEntry> = track_ids
+        .iter()
+        .filter_map(|track_id| track_map.get(track_id))
+        .map(|filepath| (filepath.clone(), source.clone()))
+        .collect();
+    insert_entries_at(conn, &new_entries, position)?;
+
+    Ok(new_entries.len() as i64)
+}
+
+/// Filepaths of the library tracks among `track_ids`, keyed by ID
+fn track_filepaths(conn: &Connection, track_ids: &[i64]) -> DbResult<HashMap<i64, String>> {
     let placeholders = track_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
     let sql = format!(
         "SELECT id, filepath FROM library WHERE id IN ({})",
@@ -69,55 +269,34 @@ pub fn add_to_queue(conn: &Connection, track_ids: &[i64], position: Option<i64>)
         .map(|id| id as &dyn rusqlite::ToSql)
         .collect();
 
-    let tracks: Vec<(i64, String)> = stmt
+    let tracks = stmt
         .query_map(params.as_slice(), |row| Ok((row.get(0)?, row.get(1)?)))?
         .filter_map(|r| r.ok())
         .collect();
+    Ok(tracks)
+}
 
-    let track_map: std::collections::HashMap<i64, String> = tracks.into_iter().collect();
-
-    if let Some(pos) = position {
-        // Get current queue
-        let mut stmt = conn.prepare("SELECT id, filepath FROM queue ORDER BY id")?;
-        let current_queue: Vec<(i64, String)> = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        // Clear and rebuild queue
-        conn.execute("DELETE FROM queue", [])?;
-
-        let pos = pos as usize;
-
-        // Insert items before position
-        for (_, filepath) in current_queue.iter().take(pos) {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-        }
-
-        // Insert new items
-        for track_id in track_ids {
-            if let Some(filepath) = track_map.get(track_id) {
-                conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-            }
-        }
-
-        // Insert items after position
-        for (_, filepath) in current_queue.iter().skip(pos) {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-        }
-    } else {
-        // Append to end
-        for track_id in track_ids {
-            if let Some(filepath) = track_map.get(track_id) {
-                conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-            }
-        }
-    }
+/// Insert entries before `position`, or append them when it is `None`
+fn insert_entries_at(
+    conn: &Connection,
+    new_entries: &[QueueEntry],
+    position: Option<i64>,
+) -> DbResult<()> {
+    let Some(pos) = position else {
+        return insert_entries(conn, new_entries);
+    };
 
-    Ok(track_ids
-        .iter()
-        .filter(|id| track_map.contains_key(id))
-        .count() as i64)
+    // Clear and rebuild queue
+    let current_queue = get_queue_entries(conn)?;
+    let pos = (pos as usize).min(current_queue.len());
+    conn.execute("DELETE FROM queue", [])?;
+    insert_entries(
+        conn,
+        current_queue[..pos]
+            .iter()
+            .chain(new_entries)
+            .chain(&current_queue[pos..]),
+    )
 }
 
 /// Add files directly to the queue
@@ -125,6 +304,16 @@ pub fn add_files_to_queue(
     conn: &Connection,
     filepaths: &[String],
     position: Option<i64>,
+) -> DbResult<(i64, Vec<Track>)> {
+    add_files_to_queue_from(conn, filepaths, position, None)
+}
+
+/// Add files directly to the queue, recording where they were queued from
+pub fn add_files_to_queue_from(
+    conn: &Connection,
+    filepaths: &[String],
+    position: Option<i64>,
+    source: Option<&str>,
 ) -> DbResult<(i64, Vec<Track>)> {
     let mut added_tracks = Vec::new();
 
@@ -155,33 +344,12 @@ pub fn add_files_to_queue(
     }
 
     // Add to queue
-    if let Some(pos) = position {
-        let mut stmt = conn.prepare("SELECT id, filepath FROM queue ORDER BY id")?;
-        let current_queue: Vec<(i64, String)> = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        conn.execute("DELETE FROM queue", [])?;
-
-        let pos = pos as usize;
-
-        for (_, filepath) in current_queue.iter().take(pos) {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-        }
-
-        for track in &added_tracks {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [&track.filepath])?;
-        }
-
-        for (_, filepath) in current_queue.iter().skip(pos) {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-        }
-    } else {
-        for track in &added_tracks {
-            conn.execute("INSERT INTO queue (filepath) VALUES (?)", [&track.filepath])?;
-        }
-    }
+    let source = normalize_source(source);
+    let new_entries: Vec<QueueEntry> = added_tracks
+        .iter()
+        .map(|track| (track.filepath.clone(), source.clone()))
+        .collect();
+    insert_entries_at(conn, &new_entries, position)?;
 
     Ok((added_tracks.len() as i64, added_tracks))
 }
@@ -200,459 +368,2667 @@ pub fn remove_from_queue(conn: &Connection, position: i64) -> DbResult<bool> {
 
     let queue_id = items[position as usize];
     let deleted = conn.execute("DELETE FROM queue WHERE id = ?", [queue_id])?;
+    prune_snapshots(conn)?;
     Ok(deleted > 0)
 }
 
 /// Clear the entire queue
 pub fn clear_queue(conn: &Connection) -> DbResult<()> {
     conn.execute("DELETE FROM queue", [])?;
-    Ok(())
+    prune_snapshots(conn)
 }
 
 /// Reorder tracks in the queue
 pub fn reorder_queue(conn: &Connection, from_position: i64, to_position: i64) -> DbResult<bool> {
-    let mut stmt = conn.prepare("SELECT id, filepath FROM queue ORDER BY id")?;
-    let items: Vec<(i64, String)> = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
-        .collect();
+    let mut entries = get_queue_entries(conn)?;
 
     let from = from_position as usize;
     let to = to_position as usize;
 
-    if from >= items.len() || to >= items.len() {
+    if from >= entries.len() || to >= entries.len() {
         return Ok(false);
     }
 
     // Reorder in memory
-    let mut filepaths: Vec<String> = items.into_iter().map(|(_, fp)| fp).collect();
-    let item = filepaths.remove(from);
-    filepaths.insert(to, item);
+    let item = entries.remove(from);
+    entries.insert(to, item);
 
     // Rebuild queue
-    conn.execute("DELETE FROM queue", [])?;
-    for filepath in filepaths {
-        conn.execute("INSERT INTO queue (filepath) VALUES (?)", [filepath])?;
-    }
+    replace_queue_entries(conn, &entries)?;
 
     Ok(true)
 }
 
-/// Get the number of items in the queue
-pub fn get_queue_length(conn: &Connection) -> DbResult<i64> {
-    let count: i64 = conn.query_row("SELECT COUNT(*) FROM queue", [], |row| row.get(0))?;
-    Ok(count)
-}
-
-/// Get queue playback state
-pub fn get_queue_state(conn: &Connection) -> DbResult<QueueState> {
-    let result = conn.query_row(
-        "SELECT current_index, shuffle_enabled, loop_mode, original_order_json
-         FROM queue_state WHERE id = 1",
-        [],
-        |row| {
-            Ok(QueueState {
-                current_index: row.get(0)?,
-                shuffle_enabled: row.get::<_, i64>(1)? != 0,
-                loop_mode: row.get(2)?,
-                original_order_json: row.get(3)?,
-            })
-        },
-    );
-
-    match result {
-        Ok(state) => Ok(state),
-        Err(rusqlite::Error::QueryReturnedNoRows) => {
-            // Initialize default state if not exists
-            let default_state = QueueState {
-                current_index: -1,
-                shuffle_enabled: false,
-                loop_mode: "none".to_string(),
-                original_order_json: None,
-            };
-            set_queue_state(conn, &default_state)?;
-            Ok(default_state)
-        }
-        Err(e) => Err(e.into()),
+/// Calculate the new order for moving a block of positions to a target index.
+///
+/// `to_position` is the drop target in the current order: the block is inserted
+/// before whatever non-moved item sits there. Targets past the end append the
+/// block, and targets inside the block itself leave it where it is. The moved
+/// items keep their relative order.
+///
+/// Returns the original indices in their new order, or `None` if the block is
+/// empty or contains an out-of-range position.
+pub fn calculate_block_move(len: usize, positions: &[i64], to_position: i64) -> Option<Vec<usize>> {
+    if positions.is_empty() || positions.iter().any(|&p| p < 0 || p as usize >= len) {
+        return None;
     }
-}
-
-/// Set queue playback state
-pub fn set_queue_state(conn: &Connection, state: &QueueState) -> DbResult<()> {
-    conn.execute(
-        "INSERT OR REPLACE INTO queue_state (id, current_index, shuffle_enabled, loop_mode, original_order_json)
-         VALUES (1, ?, ?, ?, ?)",
-        params![
-            state.current_index,
-            if state.shuffle_enabled { 1 } else { 0 },
-            &state.loop_mode,
-            &state.original_order_json
-        ],
-    )?;
-    Ok(())
-}
 
-/// Update current index in queue state
-pub fn set_current_index(conn: &Connection, index: i64) -> DbResult<()> {
-    // Ensure state exists
-    let _ = get_queue_state(conn)?;
+    let mut block: Vec<usize> = positions.iter().map(|&p| p as usize).collect();
+    block.sort_unstable();
+    block.dedup();
 
-    conn.execute(
-        "UPDATE queue_state SET current_index = ? WHERE id = 1",
-        params![index],
-    )?;
-    Ok(())
-}
+    let target = to_position.clamp(0, len as i64) as usize;
+    let rest: Vec<usize> = (0..len).filter(|i| block.binary_search(i).is_err()).collect();
 
-/// Update shuffle enabled in queue state
-pub fn set_shuffle_enabled(conn: &Connection, enabled: bool) -> DbResult<()> {
-    // Ensure state exists
-    let _ = get_queue_state(conn)?;
+    // Insertion point counts only the non-moved items before the target
+    let insert_at = rest.iter().take_while(|&&i| i < target).count();
 
-    conn.execute(
-        "UPDATE queue_state SET shuffle_enabled = ? WHERE id = 1",
-        params![if enabled { 1 } else { 0 }],
-    )?;
-    Ok(())
+    let mut order = Vec::with_capacity(len);
+    order.extend_from_slice(&rest[..insert_at]);
+    order.extend_from_slice(&block);
+    order.extend_from_slice(&rest[insert_at..]);
+    Some(order)
 }
 
-/// Update loop mode in queue state
-pub fn set_loop_mode(conn: &Connection, mode: &str) -> DbResult<()> {
-    // Ensure state exists
-    let _ = get_queue_state(conn)?;
+/// Move several queue items to a target position in one operation
+///
+/// Returns the position the block starts at after the move, or `None` if the
+/// positions were invalid.
+pub fn reorder_queue_block(
+    conn: &Connection,
+    positions: &[i64],
+    to_position: i64,
+) -> DbResult<Option<i64>> {
+    let entries = get_queue_entries(conn)?;
 
-    conn.execute(
-        "UPDATE queue_state SET loop_mode = ? WHERE id = 1",
-        params![mode],
-    )?;
-    Ok(())
-}
+    let Some(order) = calculate_block_move(entries.len(), positions, to_position) else {
+        return Ok(None);
+    };
 
-/// Update original order JSON in queue state
-pub fn set_original_order_json(conn: &Connection, json: Option<String>) -> DbResult<()> {
-    // Ensure state exists
-    let _ = get_queue_state(conn)?;
+    let first_moved = positions.iter().min().copied().unwrap_or(0) as usize;
+    let block_start = order.iter().position(|&i| i == first_moved).unwrap_or(0);
 
-    conn.execute(
-        "UPDATE queue_state SET original_order_json = ? WHERE id = 1",
-        params![json],
-    )?;
-    Ok(())
+    insert_entries_reordered(conn, &entries, &order)?;
+
+    Ok(Some(block_start as i64))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::db::{
-        library::add_track,
-        schema::{create_tables, run_migrations},
-        TrackMetadata,
-    };
+/// Rebuild the queue as `entries` taken in `order`
+fn insert_entries_reordered(
+    conn: &Connection,
+    entries: &[QueueEntry],
+    order: &[usize],
+) -> DbResult<()> {
+    conn.execute("DELETE FROM queue", [])?;
+    insert_entries(conn, order.iter().map(|&i| &entries[i]))
+}
 
-    fn setup_test_db() -> Connection {
-        let conn = Connection::open_in_memory().unwrap();
-        create_tables(&conn).unwrap();
-        run_migrations(&conn).unwrap();
-        conn
+/// Build a deterministic shuffle order for the queue.
+///
+/// Entries before `start` (already played) are kept in place; the tail is
+/// shuffled with a seeded RNG so the same seed always yields the same order.
+/// A filepath is never placed within `no_repeat_window` positions of a previous
+/// occurrence unless the remaining entries make that unavoidable.
+pub fn build_shuffle_order(
+    filepaths: &[String],
+    start: usize,
+    seed: u64,
+    no_repeat_window: usize,
+) -> Vec<String> {
+    let start = start.min(filepaths.len());
+    let mut order: Vec<String> = filepaths[..start].to_vec();
+
+    let mut tail: Vec<&String> = filepaths[start..].iter().collect();
+    tail.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    // Collapse duplicates into (filepath, remaining) in shuffled first-seen order
+    let mut pending: VecDeque<(&String, usize)> = VecDeque::new();
+    let mut seen: HashMap<&String, usize> = HashMap::new();
+    for filepath in tail {
+        match seen.get(filepath) {
+            Some(&i) => pending[i].1 += 1,
+            None => {
+                seen.insert(filepath, pending.len());
+                pending.push_back((filepath, 1));
+            }
+        }
     }
 
-    fn add_test_tracks(conn: &Connection, count: i32) -> Vec<i64> {
-        (1..=count)
-            .map(|i| {
-                let metadata = TrackMetadata {
-                    title: Some(format!("Track {}", i)),
-                    ..Default::default()
-                };
-                add_track(conn, &format!("/music/track{}.mp3", i), &metadata).unwrap()
-            })
-            .collect()
+    // Histogram of remaining counts so the current maximum is cheap to find
+    let max_initial = pending.iter().map(|(_, c)| *c).max().unwrap_or(0);
+    let mut keys_with_count = vec![0usize; max_initial + 1];
+    for (_, count) in &pending {
+        keys_with_count[*count] += 1;
     }
+    let mut max_count = max_initial;
 
-    #[test]
-    fn test_add_to_queue() {
-        let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
+    while !pending.is_empty() {
+        while max_count > 0 && keys_with_count[max_count] == 0 {
+            max_count -= 1;
+        }
 
-        let added = add_to_queue(&conn, &track_ids, None).unwrap();
-        assert_eq!(added, 3);
+        let recent = &order[order.len().saturating_sub(no_repeat_window)..];
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue.len(), 3);
-        assert_eq!(queue[0].position, 0);
-        assert_eq!(queue[1].position, 1);
-        assert_eq!(queue[2].position, 2);
-    }
+        // Prefer the allowed entry with the most copies left (ties keep shuffled order),
+        // which spreads duplicates out far enough to satisfy the window when possible
+        let mut best: Option<(usize, usize)> = None;
+        for (i, (filepath, count)) in pending.iter().enumerate() {
+            if recent.iter().any(|r| r == *filepath) {
+                continue;
+            }
+            if best.is_none_or(|(_, c)| *count > c) {
+                best = Some((i, *count));
+            }
+            if *count == max_count {
+                break;
+            }
+        }
 
-    #[test]
-    fn test_add_to_queue_at_position() {
-        let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 5);
+        // Every candidate is inside the window: a repeat is unavoidable
+        let pick = best.map(|(i, _)| i).unwrap_or(0);
 
-        // Add first 3 tracks
-        add_to_queue(&conn, &track_ids[0..3], None).unwrap();
+        let (filepath, count) = pending[pick];
+        order.push(filepath.clone());
+        keys_with_count[count] -= 1;
+        if count > 1 {
+            keys_with_count[count - 1] += 1;
+            pending[pick].1 = count - 1;
+        } else {
+            pending.remove(pick);
+        }
+    }
 
-        // Add remaining 2 at position 1
-        add_to_queue(&conn, &track_ids[3..5], Some(1)).unwrap();
+    order
+}
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue.len(), 5);
+/// A file's album and its place in it, for album shuffle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlbumSlot {
+    /// Album artist, or the track artist without one, and album title
+    pub album: (String, String),
+    pub disc: i64,
+    pub track: Option<i64>,
+}
 
-        // Order should be: track1, track4, track5, track2, track3
-        assert_eq!(queue[0].track.title, Some("Track 1".to_string()));
-        assert_eq!(queue[1].track.title, Some("Track 4".to_string()));
-        assert_eq!(queue[2].track.title, Some("Track 5".to_string()));
+/// Build a deterministic album shuffle order for the queue.
+///
+/// Entries before `start` are kept in place, as in `build_shuffle_order`.
+/// The tail is grouped by album, the groups are shuffled with a seeded RNG,
+/// and each group plays in disc and track order. A file missing from
+/// `albums` is a group of its own. The album the entry before `start` belongs
+/// to goes first, so reshuffling mid-album doesn't cut it short. Groups are
+/// never split up, so there is no no-repeat window: a file queued twice
+/// plays twice within its album.
+pub fn build_album_shuffle_order(
+    filepaths: &[String],
+    start: usize,
+    seed: u64,
+    albums: &HashMap<String, AlbumSlot>,
+) -> Vec<String> {
+    let start = start.min(filepaths.len());
+    let mut order: Vec<String> = filepaths[..start].to_vec();
+
+    let mut groups: Vec<Vec<&String>> = Vec::new();
+    let mut group_index: HashMap<&(String, String), usize> = HashMap::new();
+    for filepath in &filepaths[start..] {
+        let Some(slot) = albums.get(filepath) else {
+            groups.push(vec![filepath]);
+            continue;
+        };
+        let i = *group_index.entry(&slot.album).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[i].push(filepath);
+    }
+    for group in &mut groups {
+        group.sort_by_key(|filepath| albums.get(*filepath).map(|slot| (slot.disc, slot.track)));
+    }
+    groups.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let album_of = |filepath: &String| albums.get(filepath).map(|slot| &slot.album);
+    if let Some(playing) = order.last().and_then(album_of)
+        && let Some(i) = groups.iter().position(|g| album_of(g[0]) == Some(playing))
+    {
+        let group = groups.remove(i);
+        groups.insert(0, group);
+    }
+
+    order.extend(groups.into_iter().flatten().cloned());
+    order
+}
+
+/// Look up the album of each file for `build_album_shuffle_order`
+///
+/// Files the library doesn't have, or that have no album title, are left out.
+fn album_slots(conn: &Connection, filepaths: &[String]) -> DbResult<HashMap<String, AlbumSlot>> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(NULLIF(album_artist, ''), artist, ''), album,
+                COALESCE(disc_number, 1), CAST(track_number AS INTEGER)
+         FROM library WHERE filepath = ? AND album IS NOT NULL AND album != ''",
+    )?;
+    let mut slots = HashMap::new();
+    for filepath in filepaths {
+        if slots.contains_key(filepath) {
+            continue;
+        }
+        let mut rows = stmt.query([filepath])?;
+        if let Some(row) = rows.next()? {
+            let slot = AlbumSlot {
+                album: (row.get(0)?, row.get(1)?),
+                disc: row.get(2)?,
+                track: row.get(3)?,
+            };
+            slots.insert(filepath.clone(), slot);
+        }
+    }
+    Ok(slots)
+}
+
+/// Shuffle `filepaths` from `start` on the way `mode` says
+fn shuffle_order(
+    conn: &Connection,
+    filepaths: &[String],
+    start: usize,
+    seed: u64,
+    mode: &str,
+) -> DbResult<Vec<String>> {
+    if mode == "albums" {
+        let albums = album_slots(conn, filepaths)?;
+        return Ok(build_album_shuffle_order(filepaths, start, seed, &albums));
+    }
+    Ok(build_shuffle_order(filepaths, start, seed, SHUFFLE_NO_REPEAT_WINDOW))
+}
+
+/// New cycle order for a loop-all wrap in album shuffle mode
+///
+/// The other albums are reshuffled and the album that just finished goes
+/// last, in track order, so it doesn't play twice back to back.
+fn album_wrap_order(
+    conn: &Connection,
+    entries: &[QueueEntry],
+    ended: usize,
+    seed: u64,
+) -> DbResult<Vec<QueueEntry>> {
+    let filepaths: Vec<String> = entries.iter().map(|(fp, _)| fp.clone()).collect();
+    let albums = album_slots(conn, &filepaths)?;
+    let ended_album = albums.get(&filepaths[ended]).map(|slot| &slot.album);
+
+    let mut rest = Vec::new();
+    let mut last = Vec::new();
+    for (i, filepath) in filepaths.iter().enumerate() {
+        let album = albums.get(filepath).map(|slot| &slot.album);
+        if i == ended || (ended_album.is_some() && album == ended_album) {
+            last.push(filepath.clone());
+        } else {
+            rest.push(filepath.clone());
+        }
+    }
+    let mut order = build_album_shuffle_order(&rest, 0, seed, &albums);
+    order.extend(build_album_shuffle_order(&last, 0, seed, &albums));
+    Ok(with_sources(order, entries))
+}
+
+/// Shuffled order for the next cycle of a loop-all queue
+///
+/// The entry at `ended` just finished the cycle, so it goes last, and in
+/// album mode its whole album does, so nothing plays twice back to back.
+fn new_cycle_order(
+    conn: &Connection,
+    mut entries: Vec<QueueEntry>,
+    ended: usize,
+    seed: u64,
+    mode: &str,
+) -> DbResult<Vec<QueueEntry>> {
+    if mode == "albums" {
+        return album_wrap_order(conn, &entries, ended, seed);
+    }
+    let last = entries.remove(ended);
+    let filepaths: Vec<String> = entries.iter().map(|(fp, _)| fp.clone()).collect();
+    let order = build_shuffle_order(&filepaths, 0, seed, SHUFFLE_NO_REPEAT_WINDOW);
+    let mut order = with_sources(order, &entries);
+    order.push(last);
+    Ok(order)
+}
+
+/// Reshuffle the whole queue for a new loop-all cycle after the entry at
+/// `ended` finished it, and persist the seed as the active shuffle seed
+///
+/// Used when the user skips past the end, where `advance_after_ended` isn't
+/// involved. The current index is left for the caller, which plays the first
+/// entry. Returns the new queue length.
+pub fn reshuffle_for_new_cycle(conn: &Connection, ended: usize, seed: u64) -> DbResult<i64> {
+    let entries = get_queue_entries(conn)?;
+    if ended >= entries.len() {
+        return Err(DbError::Constraint(format!(
+            "Position {} is outside the queue of {}",
+            ended,
+            entries.len()
+        )));
+    }
+
+    let mode = get_queue_state(conn)?.shuffle_mode;
+    let order = new_cycle_order(conn, entries, ended, seed, &mode)?;
+    replace_queue_entries(conn, &order)?;
+    set_shuffle_seed(conn, Some(seed))?;
+
+    Ok(order.len() as i64)
+}
+
+/// Reshuffle the unplayed part of the queue (everything after the current index)
+/// using the given seed, and persist the seed as the active shuffle seed.
+///
+/// Follows the queue's shuffle mode. Returns the new queue length.
+pub fn reshuffle_unplayed(conn: &Connection, seed: u64) -> DbResult<i64> {
+    let start = (get_queue_state(conn)?.current_index + 1).max(0) as usize;
+    reshuffle_from(conn, start, seed)
+}
+
+/// Reshuffle the queue from position `start` on using the given seed, and
+/// persist the seed as the active shuffle seed.
+///
+/// Entries before `start` keep their positions. Follows the queue's shuffle
+/// mode. Returns the new queue length.
+pub fn reshuffle_from(conn: &Connection, start: usize, seed: u64) -> DbResult<i64> {
+    let state = get_queue_state(conn)?;
+
+    let entries = get_queue_entries(conn)?;
+    let filepaths: Vec<String> = entries.iter().map(|(fp, _)| fp.clone()).collect();
+
+    let order = shuffle_order(conn, &filepaths, start, seed, &state.shuffle_mode)?;
+    let order = with_sources(order, &entries);
+
+    replace_queue_entries(conn, &order)?;
+
+    set_shuffle_seed(conn, Some(seed))?;
+
+    Ok(order.len() as i64)
+}
+
+/// Replace the queue with a seeded shuffle of the library tracks matching `query`.
+///
+/// Missing tracks are excluded and at most `max_size` tracks are queued,
+/// sampled in SQL so the rest of the library is never loaded. The queue's
+/// shuffle mode decides between shuffling tracks and albums. The current
+/// index is reset to the first entry and the seed is persisted as the active
+/// shuffle seed.
+///
+/// Returns the new queue length.
+pub fn replace_with_shuffled_library(
+    conn: &Connection,
+    query: &LibraryQuery,
+    seed: u64,
+    max_size: usize,
+) -> DbResult<i64> {
+    let mode = get_queue_state(conn)?.shuffle_mode;
+    let filepaths = get_sampled_filepaths(conn, query, seed, max_size, mode == "albums")?;
+    let order = shuffle_order(conn, &filepaths, 0, seed, &mode)?;
+
+    let entries = stamped(order, SOURCE_LIBRARY);
+    replace_queue_entries(conn, &entries)?;
+
+    set_current_index(conn, if entries.is_empty() { -1 } else { 0 })?;
+    set_shuffle_seed(conn, Some(seed))?;
+
+    Ok(entries.len() as i64)
+}
+
+/// Replace the queue with a radio: the seed track followed by a seeded
+/// shuffle of `candidates`, capped at `max_size` entries in total.
+///
+/// Playback starts at the seed (index 0).
+pub fn replace_with_radio(
+    conn: &Connection,
+    seed_filepath: &str,
+    candidates: &[String],
+    seed: u64,
+    max_size: usize,
+) -> DbResult<i64> {
+    let mut filepaths = Vec::with_capacity(candidates.len() + 1);
+    filepaths.push(seed_filepath.to_string());
+    filepaths.extend(candidates.iter().cloned());
+
+    let mut order = build_shuffle_order(&filepaths, 1, seed, SHUFFLE_NO_REPEAT_WINDOW);
+    order.truncate(max_size.max(1));
+
+    let entries = stamped(order, SOURCE_RADIO);
+    replace_queue_entries(conn, &entries)?;
+
+    set_current_index(conn, 0)?;
+    set_shuffle_seed(conn, Some(seed))?;
+
+    Ok(entries.len() as i64)
+}
+
+/// Replace the queue with library tracks by ID and start at `start_index`
+///
+/// `start_index` points into `track_ids`. IDs with no library track are
+/// skipped and returned; the start then moves to the first kept track at or
+/// after it, or the last kept track when none follow. The current index is
+/// -1 when nothing was kept. Run it in a transaction so the queue is never
+/// seen half replaced.
+///
+/// Returns the new current index and the skipped IDs.
+pub fn replace_with_tracks(
+    conn: &Connection,
+    track_ids: &[i64],
+    start_index: i64,
+    source: Option<&str>,
+) -> DbResult<(i64, Vec<i64>)> {
+    if !track_ids.is_empty() && !(0..track_ids.len() as i64).contains(&start_index) {
+        return Err(DbError::Constraint(format!(
+            "Start index {} is out of range for {} tracks",
+            start_index,
+            track_ids.len()
+        )));
+    }
+
+    let track_map = if track_ids.is_empty() {
+        HashMap::new()
+    } else {
+        track_filepaths(conn, track_ids)?
+    };
+    let source = normalize_source(source);
+
+    let mut entries = Vec::with_capacity(track_ids.len());
+    let mut skipped = Vec::new();
+    let mut current_index = -1;
+    for (index, track_id) in track_ids.iter().enumerate() {
+        if index as i64 == start_index {
+            current_index = entries.len() as i64;
+        }
+        match track_map.get(track_id) {
+            Some(filepath) => entries.push((filepath.clone(), source.clone())),
+            None => skipped.push(*track_id),
+        }
+    }
+    let current_index = current_index.min(entries.len() as i64 - 1);
+
+    replace_queue_entries(conn, &entries)?;
+    set_current_index(conn, current_index)?;
+
+    Ok((current_index, skipped))
+}
+
+/// Find the queue position of a filepath.
+///
+/// When the file is queued more than once, the entry at the current index wins,
+/// otherwise the first occurrence is returned.
+pub fn find_queue_position(conn: &Connection, filepath: &str) -> DbResult<Option<i64>> {
+    let mut stmt = conn.prepare("SELECT filepath FROM queue ORDER BY id")?;
+    let filepaths: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let current_index = get_queue_state(conn)?.current_index;
+    if current_index >= 0
+        && filepaths.get(current_index as usize).map(String::as_str) == Some(filepath)
+    {
+        return Ok(Some(current_index));
+    }
+
+    Ok(filepaths
+        .iter()
+        .position(|fp| fp == filepath)
+        .map(|i| i as i64))
+}
+
+/// Find the queue entry for a filepath nearest after the current index.
+///
+/// Searches forward from the entry after the current index, wrapping around
+/// to the start, so the current entry itself is only chosen last.
+pub fn find_next_queue_position(conn: &Connection, filepath: &str) -> DbResult<Option<i64>> {
+    let mut stmt = conn.prepare("SELECT filepath FROM queue ORDER BY id")?;
+    let filepaths: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let len = filepaths.len() as i64;
+    if len == 0 {
+        return Ok(None);
+    }
+
+    let current_index = get_queue_state(conn)?.current_index.clamp(-1, len - 1);
+    Ok((1..=len)
+        .map(|offset| (current_index + offset).rem_euclid(len))
+        .find(|&position| filepaths[position as usize] == filepath))
+}
+
+/// Queue positions that play after the current entry, in play order
+///
+/// Shuffle reorders the queue itself, so play order is queue order. Loop
+/// "one" repeats the current entry. Loop "all" wraps to the start, except with
+/// shuffle on: the next cycle is reshuffled when it starts, so it can't be
+/// previewed. Loop "none" stops at the end of the queue.
+pub fn upcoming_positions(
+    len: usize,
+    current_index: i64,
+    loop_mode: &str,
+    shuffle: bool,
+    count: usize,
+) -> Vec<usize> {
+    upcoming_available_positions(len, current_index, loop_mode, shuffle, count, |_| false)
+}
+
+/// Like `upcoming_positions`, leaving out the entries `is_missing` flags
+///
+/// Matches what `next_available_index` plays: a missing current entry isn't
+/// repeated under loop "one", and a loop "all" queue with nothing playable
+/// has nothing upcoming.
+pub fn upcoming_available_positions(
+    len: usize,
+    current_index: i64,
+    loop_mode: &str,
+    shuffle: bool,
+    count: usize,
+    is_missing: impl Fn(usize) -> bool,
+) -> Vec<usize> {
+    let current = usize::try_from(current_index).ok().filter(|&i| i < len);
+
+    if loop_mode == "one"
+        && let Some(current) = current
+        && !is_missing(current)
+    {
+        return vec![current; count];
+    }
+
+    let start = current.map_or(0, |i| i + 1);
+    if loop_mode == "all" && !shuffle && len > 0 {
+        if (0..len).all(&is_missing) {
+            return Vec::new();
+        }
+        (start..)
+            .map(|position| position % len)
+            .filter(|&i| !is_missing(i))
+            .take(count)
+            .collect()
+    } else {
+        (start..len).filter(|&i| !is_missing(i)).take(count).collect()
+    }
+}
+
+/// Queue position to play once the current entry finishes
+///
+/// Loop "one" repeats the current entry, loop "all" wraps to the start, and
+/// loop "none" returns `None` after the last entry. With nothing current the
+/// queue starts from the top.
+pub fn next_index(len: usize, current_index: i64, loop_mode: &str) -> Option<usize> {
+    let current = usize::try_from(current_index).ok().filter(|&i| i < len);
+
+    if loop_mode == "one" && current.is_some() {
+        return current;
+    }
+
+    let next = current.map_or(0, |i| i + 1);
+    if next < len {
+        Some(next)
+    } else if loop_mode == "all" && len > 0 {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Like `next_index`, but passing over the entries `is_missing` flags
+///
+/// Returns the position to play and the positions skipped on the way there.
+/// Once an entry is skipped loop "one" has nothing to repeat, so it moves on
+/// as loop "none" would. Loop "all" wraps around at most once: when every
+/// entry is missing there is nothing to play.
+pub fn next_available_index(
+    len: usize,
+    current_index: i64,
+    loop_mode: &str,
+    is_missing: impl Fn(usize) -> bool,
+) -> (Option<usize>, Vec<usize>) {
+    let mut skipped = Vec::new();
+    let mut next = next_index(len, current_index, loop_mode);
+
+    while let Some(index) = next
+        && is_missing(index)
+    {
+        if skipped.len() == len {
+            return (None, skipped);
+        }
+        skipped.push(index);
+        let loop_mode = if loop_mode == "one" { "none" } else { loop_mode };
+        next = next_index(len, index as i64, loop_mode);
+    }
+    (next, skipped)
+}
+
+/// Whether advancing the queue skips entries whose files are missing
+pub fn skip_missing_enabled(conn: &Connection) -> DbResult<bool> {
+    Ok(settings::get_setting(conn, SKIP_MISSING_SETTING)?
+        .is_some_and(|value| value == "1" || value == "true"))
+}
+
+/// Queued files the library has flagged missing
+fn missing_queue_files(conn: &Connection) -> DbResult<HashSet<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT q.filepath FROM queue q
+         JOIN library l ON q.filepath = l.filepath
+         WHERE l.missing = 1",
+    )?;
+    let filepaths = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(filepaths)
+}
+
+/// Queue position "previous" goes to from the current entry
+///
+/// Steps back one entry. At the start, loop "all" wraps to the last entry and
+/// the other modes return `None`, as does a queue with nothing current.
+pub fn prev_index(len: usize, current_index: i64, loop_mode: &str) -> Option<usize> {
+    let current = usize::try_from(current_index).ok().filter(|&i| i < len)?;
+
+    match current.checked_sub(1) {
+        Some(prev) => Some(prev),
+        None if loop_mode == "all" => Some(len - 1),
+        None => None,
+    }
+}
+
+/// Step taken after the current queue entry plays to its end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueAdvance {
+    /// Play the entry at `index`, which is now the current index
+    Play {
+        index: i64,
+        filepath: String,
+        /// The queue was reshuffled for a new loop cycle
+        reshuffled: bool,
+        /// Entries appended by radio top-up
+        topped_up: usize,
+        /// Position the ended entry was consumed from
+        consumed: Option<i64>,
+        /// Missing files passed over on the way, in the order reached
+        skipped: Vec<String>,
+    },
+    /// The queue ran out, or everything left in it is missing
+    ///
+    /// The current index is left on the last entry, or reset to -1 when that
+    /// entry was consumed.
+    End {
+        consumed: Option<i64>,
+        skipped: Vec<String>,
+    },
+}
+
+/// Move the queue past the entry that just ended
+///
+/// With shuffle on, wrapping around under loop "all" reshuffles the queue for
+/// the next cycle with `seed`, and the entry that just ended goes to the back
+/// so it doesn't play twice in a row. When the queue runs out and
+/// `radio_top_up` is set, a shuffled selection from the artist catalog of
+/// the last track queued from outside the radio is appended, stamped with
+/// `SOURCE_RADIO`, and playback carries on into it.
+///
+/// In consume mode the ended entry is removed and the one after it takes its
+/// place. Consume wins over loop "all": a consumed queue never wraps, it just
+/// shrinks until it is empty. Loop "one" never moves past the entry, so
+/// nothing is consumed while it repeats.
+///
+/// With the `SKIP_MISSING_SETTING` on, entries whose files the library has
+/// flagged missing are passed over as `next_available_index` does, and
+/// listed in the step. Skipped entries stay in the queue, even in consume
+/// mode, since they never played.
+pub fn advance_after_ended(
+    conn: &Connection,
+    seed: u64,
+    radio_top_up: bool,
+) -> DbResult<QueueAdvance> {
+    let mut entries = get_queue_entries(conn)?;
+    let state = get_queue_state(conn)?;
+    let current = usize::try_from(state.current_index)
+        .ok()
+        .filter(|&i| i < entries.len());
+
+    let missing = if skip_missing_enabled(conn)? {
+        missing_queue_files(conn)?
+    } else {
+        HashSet::new()
+    };
+    let mut skipped: Vec<String> = Vec::new();
+    // Next available position after `from` in `entries`, noting what it passes
+    let mut step = |entries: &[QueueEntry], from: i64, loop_mode: &str| {
+        let (next, passed) = next_available_index(entries.len(), from, loop_mode, |i| {
+            missing.contains(&entries[i].0)
+        });
+        for i in passed {
+            if !skipped.contains(&entries[i].0) {
+                skipped.push(entries[i].0.clone());
+            }
+        }
+        next
+    };
+
+    let mut next;
+    let mut reshuffled = false;
+    let mut topped_up = 0;
+    let mut consumed = None;
+    let mut last_played = entries.last().cloned();
+
+    if state.consume_enabled
+        && state.loop_mode != "one"
+        && let Some(ended) = current
+    {
+        remove_from_queue(conn, ended as i64)?;
+        last_played = Some(entries.remove(ended));
+        consumed = Some(ended as i64);
+        // The entry after the ended one has slid into its place
+        next = step(&entries, ended as i64 - 1, "none");
+    } else if state.loop_mode == "all"
+        && state.shuffle_enabled
+        && let Some(ended) = current
+        && entries.len() > 1
+    {
+        // Each cycle starts with a reshuffle, so only look as far as the end
+        // of this one before wrapping
+        next = step(&entries, state.current_index, "none");
+        if next.is_none() {
+            let order = new_cycle_order(conn, entries, ended, seed, &state.shuffle_mode)?;
+
+            replace_queue_entries(conn, &order)?;
+            set_shuffle_seed(conn, Some(seed))?;
+            entries = order;
+            reshuffled = true;
+            next = step(&entries, -1, "all");
+        }
+    } else {
+        next = step(&entries, state.current_index, &state.loop_mode);
+    }
+
+    if next.is_none()
+        && radio_top_up
+        && let Some(seed_filepath) = top_up_seed(&entries, last_played)
+        && let Some(track) = get_track_by_filepath(conn, &seed_filepath)?
+    {
+        let queued: HashSet<&String> = entries.iter().map(|(fp, _)| fp).collect();
+        let candidates: Vec<String> = radio::get_artist_catalog(conn, track.id)?
+            .into_iter()
+            .filter(|filepath| !queued.contains(filepath))
+            .collect();
+        let mut order = build_shuffle_order(&candidates, 0, seed, SHUFFLE_NO_REPEAT_WINDOW);
+        order.truncate(radio::RADIO_MAX_TRACKS);
+
+        let added = stamped(order, SOURCE_RADIO);
+        insert_entries(conn, &added)?;
+        // The radio catalog leaves out missing tracks, so none are skipped here
+        if !added.is_empty() {
+            next = Some(entries.len());
+            topped_up = added.len();
+            entries.extend(added);
+        }
+    }
+
+    let Some(index) = next else {
+        if consumed.is_some() {
+            set_current_index(conn, -1)?;
+        }
+        return Ok(QueueAdvance::End { consumed, skipped });
+    };
+
+    set_current_index(conn, index as i64)?;
+    Ok(QueueAdvance::Play {
+        index: index as i64,
+        filepath: entries[index].0.clone(),
+        reshuffled,
+        topped_up,
+        consumed,
+        skipped,
+    })
+}
+
+/// Track whose artist seeds a radio top-up
+///
+/// The last track played seeds it, unless that came from an earlier top-up:
+/// then the last track the user queued does, so the radio keeps following
+/// their album or playlist instead of drifting from one top-up to the next.
+fn top_up_seed(entries: &[QueueEntry], last_played: Option<QueueEntry>) -> Option<String> {
+    let (last_filepath, last_source) = last_played?;
+    if last_source.as_deref() != Some(SOURCE_RADIO) {
+        return Some(last_filepath);
+    }
+    entries
+        .iter()
+        .rev()
+        .find(|(_, source)| source.as_deref() != Some(SOURCE_RADIO))
+        .map(|(filepath, _)| filepath.clone())
+        .or(Some(last_filepath))
+}
+
+/// What the queue is playing from
+///
+/// The current item's source wins. Without one, the source most items share
+/// is used, the earliest in the queue on a tie.
+pub fn get_queue_context(conn: &Connection) -> DbResult<QueueContext> {
+    let entries = get_queue_entries(conn)?;
+    let current_index = get_queue_state(conn)?.current_index;
+    let current_source = usize::try_from(current_index)
+        .ok()
+        .and_then(|i| entries.get(i))
+        .and_then(|(_, source)| source.clone());
+
+    let count = |source: &str| {
+        entries
+            .iter()
+            .filter(|(_, s)| s.as_deref() == Some(source))
+            .count() as i64
+    };
+    let source = current_source.or_else(|| {
+        let mut best: Option<(&str, i64)> = None;
+        for source in entries.iter().filter_map(|(_, s)| s.as_deref()) {
+            let n = count(source);
+            if best.is_none_or(|(_, most)| n > most) {
+                best = Some((source, n));
+            }
+        }
+        best.map(|(source, _)| source.to_string())
+    });
+
+    Ok(QueueContext {
+        item_count: source.as_deref().map_or(0, count),
+        source,
+    })
+}
+
+/// Loop mode auto-advance actually follows
+///
+/// Consume wins over loop "all" (see `advance_after_ended`), so a consumed
+/// queue plays on as if loop were off.
+fn effective_loop_mode(state: &QueueState) -> &str {
+    if state.consume_enabled && state.loop_mode == "all" {
+        "none"
+    } else {
+        &state.loop_mode
+    }
+}
+
+/// Preview the next `count` queue items without changing playback state
+///
+/// With the `SKIP_MISSING_SETTING` on, missing items are left out, as
+/// advancing would pass over them.
+pub fn peek_upcoming(conn: &Connection, count: usize) -> DbResult<Vec<QueueItem>> {
+    let items = get_queue(conn)?;
+    let state = get_queue_state(conn)?;
+    let skip_missing = skip_missing_enabled(conn)?;
+
+    let positions = upcoming_available_positions(
+        items.len(),
+        state.current_index,
+        effective_loop_mode(&state),
+        state.shuffle_enabled,
+        count,
+        |i| skip_missing && items[i].track.missing,
+    );
+    Ok(positions.into_iter().map(|i| items[i].clone()).collect())
+}
+
+/// The effective play order while shuffle is on
+///
+/// Shuffling writes the seeded, no-repeat order into the queue itself, and
+/// every later change reshuffles the unplayed tail the same way. The played
+/// entries, the current one, and the upcoming tail are therefore already in
+/// play order, so the order lists every position in turn. A shuffled loop
+/// "all" queue reshuffles when it wraps, so the next cycle isn't included.
+pub fn get_shuffle_order(conn: &Connection) -> DbResult<ShuffleOrder> {
+    let state = get_queue_state(conn)?;
+    let len = get_queue_length(conn)?;
+
+    let (order, current) = if state.shuffle_enabled {
+        let current = if (0..len).contains(&state.current_index) {
+            state.current_index
+        } else {
+            -1
+        };
+        ((0..len).collect(), current)
+    } else {
+        (Vec::new(), -1)
+    };
+
+    Ok(ShuffleOrder {
+        order,
+        current,
+        seed: state.shuffle_seed,
+        shuffle_mode: state.shuffle_mode,
+    })
+}
+
+/// The track that plays once the current one ends, without changing state
+///
+/// Follows `upcoming_positions`, so it is `None` where the next track can't
+/// be known yet: after the last entry with loop off, and when a shuffled
+/// loop "all" queue wraps into a reshuffled cycle. A radio top-up isn't
+/// previewed either.
+pub fn peek_next(conn: &Connection) -> DbResult<Option<Track>> {
+    Ok(peek_upcoming(conn, 1)?.into_iter().next().map(|item| item.track))
+}
+
+/// The track "previous" goes to, without changing state
+pub fn peek_previous(conn: &Connection) -> DbResult<Option<Track>> {
+    let mut items = get_queue(conn)?;
+    let state = get_queue_state(conn)?;
+
+    Ok(prev_index(items.len(), state.current_index, &state.loop_mode)
+        .map(|i| items.swap_remove(i).track))
+}
+
+/// Sum item durations around the current index
+///
+/// `durations` are in play order. Items before `current_index` count as
+/// elapsed; the current item and the rest count as remaining. An index past
+/// the end counts everything as elapsed; -1 counts everything as remaining.
+pub fn compute_totals(durations: &[Option<f64>], current_index: i64) -> QueueTotals {
+    let split = usize::try_from(current_index).map_or(0, |i| i.min(durations.len()));
+    let seconds = |items: &[Option<f64>]| items.iter().map(|d| d.unwrap_or(0.0)).sum::<f64>();
+
+    let (elapsed, remaining) = durations.split_at(split);
+    QueueTotals {
+        count: durations.len() as i64,
+        current_index,
+        total_duration: seconds(durations),
+        elapsed_duration: seconds(elapsed),
+        remaining_duration: seconds(remaining),
+        unknown_durations: durations.iter().filter(|d| d.is_none()).count() as i64,
+    }
+}
+
+/// Get item counts and durations for the queue
+///
+/// Shuffle reorders the queue itself, so remaining time follows the shuffled
+/// play order. Loop modes are ignored: only the current pass is counted.
+pub fn get_queue_totals(conn: &Connection) -> DbResult<QueueTotals> {
+    let mut stmt = conn.prepare(
+        "SELECT l.duration
+         FROM queue q
+         LEFT JOIN library l ON q.filepath = l.filepath
+         ORDER BY q.id",
+    )?;
+    let durations: Vec<Option<f64>> = stmt
+        .query_map([], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .map(|d: Option<f64>| d.filter(|seconds| *seconds > 0.0))
+        .collect();
+
+    let state = get_queue_state(conn)?;
+    Ok(compute_totals(&durations, state.current_index))
+}
+
+/// Get the number of items in the queue
+pub fn get_queue_length(conn: &Connection) -> DbResult<i64> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM queue", [], |row| row.get(0))?;
+    Ok(count)
+}
+
+/// Get queue playback state
+pub fn get_queue_state(conn: &Connection) -> DbResult<QueueState> {
+    let result = conn.query_row(
+        "SELECT current_index, shuffle_enabled, loop_mode, original_order_json, shuffle_seed,
+                consume_enabled, shuffle_mode
+         FROM queue_state WHERE id = 1",
+        [],
+        |row| {
+            Ok(QueueState {
+                current_index: row.get(0)?,
+                shuffle_enabled: row.get::<_, i64>(1)? != 0,
+                loop_mode: effective_stored_loop_mode(row.get(2)?),
+                original_order_json: row.get(3)?,
+                shuffle_seed: row.get::<_, Option<i64>>(4)?.map(|s| s as u64),
+                consume_enabled: row.get::<_, Option<i64>>(5)?.unwrap_or(0) != 0,
+                shuffle_mode: effective_stored_shuffle_mode(row.get(6)?),
+            })
+        },
+    );
+
+    match result {
+        Ok(state) => Ok(state),
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            // Initialize default state if not exists
+            let default_state = QueueState {
+                current_index: -1,
+                shuffle_enabled: false,
+                loop_mode: "none".to_string(),
+                original_order_json: None,
+                shuffle_seed: None,
+                consume_enabled: false,
+                shuffle_mode: "tracks".to_string(),
+            };
+            set_queue_state(conn, &default_state)?;
+            Ok(default_state)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Set queue playback state
+pub fn set_queue_state(conn: &Connection, state: &QueueState) -> DbResult<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO queue_state (id, current_index, shuffle_enabled, loop_mode, original_order_json, shuffle_seed, consume_enabled, shuffle_mode)
+         VALUES (1, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            state.current_index,
+            if state.shuffle_enabled { 1 } else { 0 },
+            &state.loop_mode,
+            &state.original_order_json,
+            state.shuffle_seed.map(|s| s as i64),
+            if state.consume_enabled { 1 } else { 0 },
+            &state.shuffle_mode
+        ],
+    )?;
+    Ok(())
+}
+
+/// Update current index in queue state
+pub fn set_current_index(conn: &Connection, index: i64) -> DbResult<()> {
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET current_index = ? WHERE id = 1",
+        params![index],
+    )?;
+    Ok(())
+}
+
+/// Update shuffle enabled in queue state
+pub fn set_shuffle_enabled(conn: &Connection, enabled: bool) -> DbResult<()> {
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET shuffle_enabled = ? WHERE id = 1",
+        params![if enabled { 1 } else { 0 }],
+    )?;
+    Ok(())
+}
+
+/// Update consume mode in queue state
+pub fn set_consume_enabled(conn: &Connection, enabled: bool) -> DbResult<()> {
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET consume_enabled = ? WHERE id = 1",
+        params![if enabled { 1 } else { 0 }],
+    )?;
+    Ok(())
+}
+
+/// Loop mode a stored value stands for; anything unknown plays as "none"
+fn effective_stored_loop_mode(value: Option<String>) -> String {
+    value
+        .filter(|mode| LOOP_MODES.contains(&mode.as_str()))
+        .unwrap_or_else(|| "none".to_string())
+}
+
+/// Update loop mode in queue state
+///
+/// Fails with `DbError::Constraint` unless `mode` is one of `LOOP_MODES`.
+pub fn set_loop_mode(conn: &Connection, mode: &str) -> DbResult<()> {
+    if !LOOP_MODES.contains(&mode) {
+        return Err(DbError::Constraint(format!(
+            "Unknown loop mode '{}', expected one of: {}",
+            mode,
+            LOOP_MODES.join(", ")
+        )));
+    }
+
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET loop_mode = ? WHERE id = 1",
+        params![mode],
+    )?;
+    Ok(())
+}
+
+/// Shuffle mode a stored value stands for; anything unknown shuffles tracks
+fn effective_stored_shuffle_mode(value: Option<String>) -> String {
+    value
+        .filter(|mode| SHUFFLE_MODES.contains(&mode.as_str()))
+        .unwrap_or_else(|| "tracks".to_string())
+}
+
+/// Update shuffle mode in queue state
+///
+/// Fails with `DbError::Constraint` unless `mode` is one of `SHUFFLE_MODES`.
+/// Only the stored mode changes; callers reshuffle the queue if they want the
+/// new mode to apply straight away.
+pub fn set_shuffle_mode(conn: &Connection, mode: &str) -> DbResult<()> {
+    if !SHUFFLE_MODES.contains(&mode) {
+        return Err(DbError::Constraint(format!(
+            "Unknown shuffle mode '{}', expected one of: {}",
+            mode,
+            SHUFFLE_MODES.join(", ")
+        )));
+    }
+
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET shuffle_mode = ? WHERE id = 1",
+        params![mode],
+    )?;
+    Ok(())
+}
+
+/// Update original order JSON in queue state
+pub fn set_original_order_json(conn: &Connection, json: Option<String>) -> DbResult<()> {
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET original_order_json = ? WHERE id = 1",
+        params![json],
+    )?;
+    Ok(())
+}
+
+/// Update the active shuffle seed in queue state
+pub fn set_shuffle_seed(conn: &Connection, seed: Option<u64>) -> DbResult<()> {
+    // Ensure state exists
+    let _ = get_queue_state(conn)?;
+
+    conn.execute(
+        "UPDATE queue_state SET shuffle_seed = ? WHERE id = 1",
+        params![seed.map(|s| s as i64)],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        library::{add_track, delete_track, mark_track_missing, update_track_metadata},
+        schema::{create_tables, run_migrations},
+        TrackMetadata,
+    };
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add_test_tracks(conn: &Connection, count: i32) -> Vec<i64> {
+        (1..=count)
+            .map(|i| {
+                let metadata = TrackMetadata {
+                    title: Some(format!("Track {}", i)),
+                    ..Default::default()
+                };
+                add_track(conn, &format!("/music/track{}.mp3", i), &metadata).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_add_to_queue() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+
+        let added = add_to_queue(&conn, &track_ids, None).unwrap();
+        assert_eq!(added, 3);
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue[0].position, 0);
+        assert_eq!(queue[1].position, 1);
+        assert_eq!(queue[2].position, 2);
+    }
+
+    fn sources(conn: &Connection) -> Vec<Option<String>> {
+        get_queue(conn).unwrap().into_iter().map(|item| item.source).collect()
+    }
+
+    #[test]
+    fn test_enqueue_from_playlist_stamps_source() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 5);
+
+        add_to_queue_from(&conn, &track_ids[0..3], None, Some("playlist:12")).unwrap();
+        assert_eq!(sources(&conn), vec![Some("playlist:12".to_string()); 3]);
+
+        // Items queued without a source keep none, and inserting or moving
+        // items doesn't lose the others' sources
+        add_to_queue(&conn, &track_ids[3..4], Some(1)).unwrap();
+        add_to_queue_from(&conn, &track_ids[4..5], None, Some("  ")).unwrap();
+        reorder_queue(&conn, 0, 4).unwrap();
+        let playlist = Some("playlist:12".to_string());
+        assert_eq!(
+            sources(&conn),
+            [None, playlist.clone(), playlist.clone(), None, playlist.clone()]
+        );
+
+        // Shuffling keeps each file's source
+        reshuffle_unplayed(&conn, 3).unwrap();
+        for item in get_queue(&conn).unwrap() {
+            let expected = match item.track.id {
+                id if id == track_ids[3] || id == track_ids[4] => None,
+                _ => playlist.clone(),
+            };
+            assert_eq!(item.source, expected);
+        }
+    }
+
+    #[test]
+    fn test_queue_context_prefers_current_item() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 5);
+        let context = get_queue_context(&conn).unwrap();
+        assert_eq!((context.source, context.item_count), (None, 0));
+
+        add_to_queue(&conn, &track_ids[0..1], None).unwrap();
+        add_to_queue_from(&conn, &track_ids[1..2], None, Some("album:Abbey Road")).unwrap();
+        add_to_queue_from(&conn, &track_ids[2..5], None, Some("playlist:12")).unwrap();
+
+        // The current item has no source, so the most common one is used
+        set_current_index(&conn, 0).unwrap();
+        let context = get_queue_context(&conn).unwrap();
+        assert_eq!(context.source.as_deref(), Some("playlist:12"));
+        assert_eq!(context.item_count, 3);
+
+        set_current_index(&conn, 1).unwrap();
+        let context = get_queue_context(&conn).unwrap();
+        assert_eq!(context.source.as_deref(), Some("album:Abbey Road"));
+        assert_eq!(context.item_count, 1);
+    }
+
+    #[test]
+    fn test_add_to_queue_at_position() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 5);
+
+        // Add first 3 tracks
+        add_to_queue(&conn, &track_ids[0..3], None).unwrap();
+
+        // Add remaining 2 at position 1
+        add_to_queue(&conn, &track_ids[3..5], Some(1)).unwrap();
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 5);
+
+        // Order should be: track1, track4, track5, track2, track3
+        assert_eq!(queue[0].track.title, Some("Track 1".to_string()));
+        assert_eq!(queue[1].track.title, Some("Track 4".to_string()));
+        assert_eq!(queue[2].track.title, Some("Track 5".to_string()));
+    }
+
+    #[test]
+    fn test_remove_from_queue() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        let removed = remove_from_queue(&conn, 1).unwrap();
+        assert!(removed);
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_reorder_queue() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        // Move track at position 2 to position 0
+        let success = reorder_queue(&conn, 2, 0).unwrap();
+        assert!(success);
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue[0].track.title, Some("Track 3".to_string()));
+        assert_eq!(queue[1].track.title, Some("Track 1".to_string()));
+        assert_eq!(queue[2].track.title, Some("Track 2".to_string()));
+    }
+
+    #[test]
+    fn test_clear_queue() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        clear_queue(&conn).unwrap();
+
+        let length = get_queue_length(&conn).unwrap();
+        assert_eq!(length, 0);
+    }
+
+    #[test]
+    fn test_remove_from_queue_invalid_position() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        // Invalid positive position
+        let removed = remove_from_queue(&conn, 999).unwrap();
+        assert!(!removed);
+
+        // Invalid negative position
+        let removed = remove_from_queue(&conn, -1).unwrap();
+        assert!(!removed);
+    }
+
+    #[test]
+    fn test_reorder_queue_invalid_from() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        let success = reorder_queue(&conn, 999, 0).unwrap();
+        assert!(!success);
+    }
+
+    #[test]
+    fn test_reorder_queue_invalid_to() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        let success = reorder_queue(&conn, 0, 999).unwrap();
+        assert!(!success);
+    }
+
+    #[test]
+    fn test_get_queue_state_default() {
+        let conn = setup_test_db();
+
+        // First call should initialize default state
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.current_index, -1);
+        assert!(!state.shuffle_enabled);
+        assert_eq!(state.loop_mode, "none");
+        assert!(state.original_order_json.is_none());
+        assert!(state.shuffle_seed.is_none());
+    }
+
+    #[test]
+    fn test_set_queue_state() {
+        let conn = setup_test_db();
+
+        let state = QueueState {
+            current_index: 5,
+            shuffle_enabled: true,
+            loop_mode: "all".to_string(),
+            original_order_json: Some("[1,2,3]".to_string()),
+            shuffle_seed: Some(42),
+            consume_enabled: true,
+            shuffle_mode: "albums".to_string(),
+        };
+
+        set_queue_state(&conn, &state).unwrap();
+
+        let retrieved = get_queue_state(&conn).unwrap();
+        assert_eq!(retrieved.current_index, 5);
+        assert!(retrieved.shuffle_enabled);
+        assert_eq!(retrieved.loop_mode, "all");
+        assert_eq!(retrieved.original_order_json, Some("[1,2,3]".to_string()));
+        assert_eq!(retrieved.shuffle_seed, Some(42));
+        assert!(retrieved.consume_enabled);
+        assert_eq!(retrieved.shuffle_mode, "albums");
+    }
+
+    #[test]
+    fn test_set_current_index() {
+        let conn = setup_test_db();
+
+        set_current_index(&conn, 10).unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.current_index, 10);
+    }
+
+    #[test]
+    fn test_set_shuffle_enabled() {
+        let conn = setup_test_db();
+
+        set_shuffle_enabled(&conn, true).unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert!(state.shuffle_enabled);
+
+        set_shuffle_enabled(&conn, false).unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert!(!state.shuffle_enabled);
+    }
+
+    #[test]
+    fn test_set_loop_mode() {
+        let conn = setup_test_db();
+
+        set_loop_mode(&conn, "one").unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.loop_mode, "one");
+
+        set_loop_mode(&conn, "all").unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.loop_mode, "all");
+    }
+
+    #[test]
+    fn test_loop_mode_round_trips_each_mode() {
+        let conn = setup_test_db();
+
+        for mode in LOOP_MODES {
+            set_loop_mode(&conn, mode).unwrap();
+            assert_eq!(get_queue_state(&conn).unwrap().loop_mode, mode);
+        }
+    }
+
+    #[test]
+    fn test_set_loop_mode_rejects_unknown_modes() {
+        let conn = setup_test_db();
+        set_loop_mode(&conn, "all").unwrap();
+
+        for mode in ["", "off", "One", "repeat"] {
+            let result = set_loop_mode(&conn, mode);
+            assert!(matches!(result, Err(DbError::Constraint(_))), "{mode:?} accepted");
+        }
+        assert_eq!(get_queue_state(&conn).unwrap().loop_mode, "all");
+    }
+
+    #[test]
+    fn test_unknown_stored_loop_mode_reads_as_none() {
+        let conn = setup_test_db();
+        let _ = get_queue_state(&conn).unwrap();
+        conn.execute("UPDATE queue_state SET loop_mode = 'shuffle' WHERE id = 1", []).unwrap();
+
+        assert_eq!(get_queue_state(&conn).unwrap().loop_mode, "none");
+    }
+
+    #[test]
+    fn test_replace_with_tracks_skips_missing_ids() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 4);
+        add_to_queue(&conn, &[ids[3]], None).unwrap();
+
+        // Starting on an unknown ID moves the start to the next kept track
+        let requested = [ids[0], 999, 998, ids[1], ids[2]];
+        let (current_index, skipped) =
+            replace_with_tracks(&conn, &requested, 2, Some("album:Hex")).unwrap();
+        assert_eq!(skipped, [999, 998]);
+        assert_eq!(current_index, 1);
+
+        let queue = get_queue(&conn).unwrap();
+        let queued: Vec<i64> = queue.iter().map(|item| item.track.id).collect();
+        assert_eq!(queued, [ids[0], ids[1], ids[2]]);
+        assert!(queue.iter().all(|item| item.source.as_deref() == Some("album:Hex")));
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 1);
+
+        // Nothing kept after the start falls back to the last kept track
+        let (current_index, _) = replace_with_tracks(&conn, &[ids[0], 999], 1, None).unwrap();
+        assert_eq!(current_index, 0);
+
+        let (current_index, skipped) = replace_with_tracks(&conn, &[999], 0, None).unwrap();
+        assert_eq!((current_index, skipped), (-1, vec![999]));
+        assert!(get_queue(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replace_with_tracks_rejects_bad_start_without_changes() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 2);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 1).unwrap();
+
+        assert!(matches!(
+            replace_with_tracks(&conn, &[ids[0]], 3, None),
+            Err(DbError::Constraint(_))
+        ));
+        assert_eq!(get_queue_length(&conn).unwrap(), 2);
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 1);
+    }
+
+    #[test]
+    fn test_get_shuffle_order_follows_seeded_queue() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 8);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 2).unwrap();
+
+        // Off means queue order
+        let order = get_shuffle_order(&conn).unwrap();
+        assert!(order.order.is_empty());
+        assert_eq!(order.current, -1);
+
+        set_shuffle_enabled(&conn, true).unwrap();
+        reshuffle_unplayed(&conn, 42).unwrap();
+        let order = get_shuffle_order(&conn).unwrap();
+        assert_eq!(order.seed, Some(42));
+        assert_eq!(order.shuffle_mode, "tracks");
+
+        let mut sorted = order.order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..8).collect::<Vec<i64>>());
+        assert_eq!(order.order[order.current as usize], 2);
+
+        // What follows the current item is what plays next
+        let upcoming: Vec<i64> = peek_upcoming(&conn, 8)
+            .unwrap()
+            .iter()
+            .map(|item| item.position)
+            .collect();
+        assert_eq!(upcoming, order.order[order.current as usize + 1..]);
+    }
+
+    #[test]
+    fn test_set_original_order_json() {
+        let conn = setup_test_db();
+
+        set_original_order_json(&conn, Some("[5,4,3,2,1]".to_string())).unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.original_order_json, Some("[5,4,3,2,1]".to_string()));
+
+        // Clear it
+        set_original_order_json(&conn, None).unwrap();
+
+        let state = get_queue_state(&conn).unwrap();
+        assert!(state.original_order_json.is_none());
+    }
+
+    #[test]
+    fn test_add_files_to_queue_new_files() {
+        let conn = setup_test_db();
+
+        // Add files not in library
+        let filepaths = vec![
+            "/music/new1.mp3".to_string(),
+            "/music/new2.mp3".to_string(),
+        ];
+
+        let (count, tracks) = add_files_to_queue(&conn, &filepaths, None).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(tracks.len(), 2);
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_add_files_to_queue_existing_files() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 2);
+
+        // Get filepaths from library
+        let filepaths = vec![
+            "/music/track1.mp3".to_string(),
+            "/music/track2.mp3".to_string(),
+        ];
+
+        let (count, tracks) = add_files_to_queue(&conn, &filepaths, None).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].id, track_ids[0]);
+        assert_eq!(tracks[1].id, track_ids[1]);
+    }
+
+    #[test]
+    fn test_add_files_to_queue_at_position() {
+        let conn = setup_test_db();
+
+        // Add initial files
+        let initial = vec!["/music/first.mp3".to_string(), "/music/second.mp3".to_string()];
+        add_files_to_queue(&conn, &initial, None).unwrap();
+
+        // Add new files at position 1
+        let new_files = vec!["/music/inserted.mp3".to_string()];
+        add_files_to_queue(&conn, &new_files, Some(1)).unwrap();
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue[0].track.filepath, "/music/first.mp3");
+        assert_eq!(queue[1].track.filepath, "/music/inserted.mp3");
+        assert_eq!(queue[2].track.filepath, "/music/second.mp3");
+    }
+
+    #[test]
+    fn test_get_queue_empty() {
+        let conn = setup_test_db();
+
+        let queue = get_queue(&conn).unwrap();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_get_queue_length() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 5);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        let length = get_queue_length(&conn).unwrap();
+        assert_eq!(length, 5);
+    }
+
+    #[test]
+    fn test_add_to_queue_nonexistent_tracks() {
+        let conn = setup_test_db();
+
+        // Try to add nonexistent track IDs
+        let added = add_to_queue(&conn, &[9999, 9998], None).unwrap();
+        assert_eq!(added, 0);
+
+        let queue = get_queue(&conn).unwrap();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_calculate_block_move_forward() {
+        // Move [1, 3] before index 5: 0 2 4 | 1 3 | 5
+        let order = calculate_block_move(6, &[1, 3], 5).unwrap();
+        assert_eq!(order, vec![0, 2, 4, 1, 3, 5]);
+    }
+
+    #[test]
+    fn test_calculate_block_move_backward() {
+        let order = calculate_block_move(6, &[4, 2], 0).unwrap();
+        assert_eq!(order, vec![2, 4, 0, 1, 3, 5]);
+    }
+
+    #[test]
+    fn test_calculate_block_move_past_end() {
+        let order = calculate_block_move(5, &[0, 1], 99).unwrap();
+        assert_eq!(order, vec![2, 3, 4, 0, 1]);
+    }
+
+    #[test]
+    fn test_calculate_block_move_target_inside_block() {
+        // Dropping a contiguous block onto itself leaves the order unchanged
+        let order = calculate_block_move(5, &[1, 2, 3], 2).unwrap();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+
+        // Non-contiguous block gathers around the first non-moved item before the target
+        let order = calculate_block_move(6, &[1, 4], 4).unwrap();
+        assert_eq!(order, vec![0, 2, 3, 1, 4, 5]);
+    }
+
+    #[test]
+    fn test_calculate_block_move_invalid() {
+        assert!(calculate_block_move(3, &[], 0).is_none());
+        assert!(calculate_block_move(3, &[3], 0).is_none());
+        assert!(calculate_block_move(3, &[-1], 0).is_none());
+    }
+
+    #[test]
+    fn test_reorder_queue_block() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 5);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        // Move tracks 4 and 2 (positions 3 and 1) to the front
+        let start = reorder_queue_block(&conn, &[3, 1], 0).unwrap();
+        assert_eq!(start, Some(0));
+
+        let titles: Vec<String> = get_queue(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.track.title.unwrap())
+            .collect();
+        assert_eq!(
+            titles,
+            vec!["Track 2", "Track 4", "Track 1", "Track 3", "Track 5"]
+        );
+    }
+
+    #[test]
+    fn test_reorder_queue_block_invalid_positions() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+
+        let result = reorder_queue_block(&conn, &[0, 7], 1).unwrap();
+        assert!(result.is_none());
+        assert_eq!(get_queue_length(&conn).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_find_queue_position_prefers_current_index() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+        add_to_queue(&conn, &track_ids[..1], None).unwrap();
+
+        // First occurrence when the current index points elsewhere
+        let position = find_queue_position(&conn, "/music/track1.mp3").unwrap();
+        assert_eq!(position, Some(0));
+
+        set_current_index(&conn, 3).unwrap();
+        let position = find_queue_position(&conn, "/music/track1.mp3").unwrap();
+        assert_eq!(position, Some(3));
+
+        let missing = find_queue_position(&conn, "/music/none.mp3").unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn test_find_next_queue_position_wraps() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 3);
+        // Queue: track1, track2, track1, track3
+        add_to_queue(&conn, &track_ids[..2], None).unwrap();
+        add_to_queue(&conn, &[track_ids[0], track_ids[2]], None).unwrap();
+
+        set_current_index(&conn, 0).unwrap();
+        assert_eq!(find_next_queue_position(&conn, "/music/track1.mp3").unwrap(), Some(2));
+
+        set_current_index(&conn, 3).unwrap();
+        assert_eq!(find_next_queue_position(&conn, "/music/track1.mp3").unwrap(), Some(0));
+
+        // The current entry is only chosen when it is the sole occurrence
+        set_current_index(&conn, 1).unwrap();
+        assert_eq!(find_next_queue_position(&conn, "/music/track2.mp3").unwrap(), Some(1));
+
+        assert!(find_next_queue_position(&conn, "/music/none.mp3").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_find_next_queue_position_no_current() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 2);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+        add_to_queue(&conn, &track_ids[..1], None).unwrap();
+
+        set_current_index(&conn, -1).unwrap();
+        assert_eq!(find_next_queue_position(&conn, "/music/track1.mp3").unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_upcoming_positions_loop_none_stops_at_end() {
+        assert_eq!(upcoming_positions(5, 1, "none", false, 3), vec![2, 3, 4]);
+        assert_eq!(upcoming_positions(5, 3, "none", false, 3), vec![4]);
+        assert!(upcoming_positions(5, 4, "none", false, 3).is_empty());
+        // Nothing playing yet: the queue starts from the top
+        assert_eq!(upcoming_positions(5, -1, "none", false, 2), vec![0, 1]);
+        assert!(upcoming_positions(0, -1, "all", false, 3).is_empty());
+    }
+
+    #[test]
+    fn test_upcoming_positions_loop_all_wraps() {
+        assert_eq!(upcoming_positions(5, 3, "all", false, 3), vec![4, 0, 1]);
+        assert_eq!(upcoming_positions(5, 4, "all", false, 2), vec![0, 1]);
+        // A short queue repeats in the preview, as it will during playback
+        assert_eq!(upcoming_positions(2, 0, "all", false, 3), vec![1, 0, 1]);
+    }
+
+    #[test]
+    fn test_upcoming_positions_loop_one_repeats_current() {
+        assert_eq!(upcoming_positions(5, 4, "one", false, 3), vec![4, 4, 4]);
+        assert_eq!(upcoming_positions(5, 2, "one", true, 2), vec![2, 2]);
+        // Without a current entry there is nothing to repeat
+        assert_eq!(upcoming_positions(5, -1, "one", false, 2), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_upcoming_positions_shuffle_does_not_preview_next_cycle() {
+        assert_eq!(upcoming_positions(5, 2, "all", true, 3), vec![3, 4]);
+        assert_eq!(upcoming_positions(5, 2, "none", true, 3), vec![3, 4]);
+    }
+
+    #[test]
+    fn test_next_index_follows_loop_mode() {
+        assert_eq!(next_index(3, 0, "none"), Some(1));
+        assert_eq!(next_index(3, 2, "none"), None);
+        assert_eq!(next_index(3, 2, "all"), Some(0));
+        assert_eq!(next_index(3, 1, "one"), Some(1));
+        // Nothing current: start from the top, whatever the mode
+        assert_eq!(next_index(3, -1, "one"), Some(0));
+        assert_eq!(next_index(0, -1, "all"), None);
+        // A stale index past the end is treated as nothing current
+        assert_eq!(next_index(3, 7, "none"), Some(0));
+    }
+
+    #[test]
+    fn test_advance_after_ended_reshuffles_on_loop_restart() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 4);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_queue_state(
+            &conn,
+            &QueueState {
+                current_index: 3,
+                shuffle_enabled: true,
+                loop_mode: "all".to_string(),
+                original_order_json: None,
+                shuffle_seed: Some(1),
+                consume_enabled: false,
+                shuffle_mode: "tracks".to_string(),
+            },
+        )
+        .unwrap();
+
+        let QueueAdvance::Play {
+            index,
+            filepath,
+            reshuffled,
+            ..
+        } = advance_after_ended(&conn, 42, false).unwrap()
+        else {
+            panic!("loop all should wrap around");
+        };
+        assert_eq!(index, 0);
+        assert!(reshuffled);
+        assert_ne!(filepath, "/music/track4.mp3");
+
+        // The track that just ended plays last in the new cycle
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue[3].track.filepath, "/music/track4.mp3");
+        assert_eq!(queue[0].track.filepath, filepath);
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!((state.current_index, state.shuffle_seed), (0, Some(42)));
+    }
+
+    #[test]
+    fn test_reshuffle_for_new_cycle_puts_ended_entry_last() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 6);
+        add_to_queue(&conn, &ids, None).unwrap();
+
+        assert_eq!(reshuffle_for_new_cycle(&conn, 2, 9).unwrap(), 6);
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue[5].track.filepath, "/music/track3.mp3");
+        let mut queued: Vec<i64> = queue.iter().map(|item| item.track.id).collect();
+        queued.sort();
+        assert_eq!(queued, ids);
+        assert_eq!(get_queue_state(&conn).unwrap().shuffle_seed, Some(9));
+
+        assert!(matches!(reshuffle_for_new_cycle(&conn, 6, 9), Err(DbError::Constraint(_))));
+    }
+
+    #[test]
+    fn test_advance_after_ended_radio_top_up() {
+        let conn = setup_test_db();
+        let ids: Vec<i64> = (1..=4)
+            .map(|i| {
+                let metadata = TrackMetadata {
+                    artist: Some("Burial".to_string()),
+                    ..Default::default()
+                };
+                add_track(&conn, &format!("/music/track{}.mp3", i), &metadata).unwrap()
+            })
+            .collect();
+        add_to_queue(&conn, &ids[..2], None).unwrap();
+        set_current_index(&conn, 1).unwrap();
+
+        // Without top-up the queue just ends, leaving the index in place
+        assert_eq!(
+            advance_after_ended(&conn, 5, false).unwrap(),
+            QueueAdvance::End {
+                consumed: None,
+                skipped: Vec::new(),
+            }
+        );
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 1);
+
+        let QueueAdvance::Play {
+            index,
+            filepath,
+            topped_up,
+            ..
+        } = advance_after_ended(&conn, 5, true).unwrap()
+        else {
+            panic!("radio top-up should continue playback");
+        };
+        assert_eq!((index, topped_up), (2, 2));
+        // Only tracks not already queued are added
+        let queue = get_queue(&conn).unwrap();
+        let mut added: Vec<&str> = queue[2..].iter().map(|i| i.track.filepath.as_str()).collect();
+        added.sort();
+        assert_eq!(added, ["/music/track3.mp3", "/music/track4.mp3"]);
+        assert!(queue[2..].iter().all(|i| i.source.as_deref() == Some(SOURCE_RADIO)));
+        assert_eq!(queue[2].track.filepath, filepath);
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 2);
     }
 
     #[test]
-    fn test_remove_from_queue() {
+    fn test_advance_after_ended_consumes_played_entries() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
-
-        let removed = remove_from_queue(&conn, 1).unwrap();
-        assert!(removed);
+        let ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 0).unwrap();
+        set_loop_mode(&conn, "all").unwrap();
+        set_consume_enabled(&conn, true).unwrap();
+
+        for (remaining, expected) in [(2, "/music/track2.mp3"), (1, "/music/track3.mp3")] {
+            let QueueAdvance::Play {
+                index,
+                filepath,
+                consumed,
+                ..
+            } = advance_after_ended(&conn, 1, false).unwrap()
+            else {
+                panic!("consume should move on to the next entry");
+            };
+            // The next entry slides into the consumed one's place
+            assert_eq!((index, consumed), (0, Some(0)));
+            assert_eq!(filepath, expected);
+            assert_eq!(get_queue_length(&conn).unwrap(), remaining);
+        }
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue.len(), 2);
+        assert_eq!(
+            advance_after_ended(&conn, 1, false).unwrap(),
+            QueueAdvance::End {
+                consumed: Some(0),
+                skipped: Vec::new(),
+            }
+        );
+        assert_eq!(get_queue_length(&conn).unwrap(), 0);
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, -1);
     }
 
     #[test]
-    fn test_reorder_queue() {
+    fn test_advance_after_ended_loop_one_does_not_consume() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
+        let ids = add_test_tracks(&conn, 2);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 1).unwrap();
+        set_loop_mode(&conn, "one").unwrap();
+        set_consume_enabled(&conn, true).unwrap();
 
-        // Move track at position 2 to position 0
-        let success = reorder_queue(&conn, 2, 0).unwrap();
-        assert!(success);
+        let step = advance_after_ended(&conn, 1, false).unwrap();
+        assert!(matches!(step, QueueAdvance::Play { index: 1, consumed: None, .. }));
+        assert_eq!(get_queue_length(&conn).unwrap(), 2);
+    }
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue[0].track.title, Some("Track 3".to_string()));
-        assert_eq!(queue[1].track.title, Some("Track 1".to_string()));
-        assert_eq!(queue[2].track.title, Some("Track 2".to_string()));
+    #[test]
+    fn test_next_available_index_skips_missing() {
+        let odd = |i: usize| i % 2 == 1;
+        assert_eq!(next_available_index(5, 0, "none", odd), (Some(2), vec![1]));
+        assert_eq!(next_available_index(5, 2, "none", odd), (Some(4), vec![3]));
+        assert_eq!(next_available_index(4, 2, "none", odd), (None, vec![3]));
+        // Loop all wraps past missing entries at the end
+        assert_eq!(next_available_index(4, 2, "all", odd), (Some(0), vec![3]));
+        // Loop one moves on once its entry is missing
+        assert_eq!(next_available_index(4, 1, "one", odd), (Some(2), vec![1]));
+        assert_eq!(next_available_index(4, 2, "one", odd), (Some(2), vec![]));
+        // With everything missing, loop all gives up after one lap
+        let (next, skipped) = next_available_index(3, 0, "all", |_| true);
+        assert_eq!(next, None);
+        assert_eq!(skipped, [1, 2, 0]);
     }
 
     #[test]
-    fn test_clear_queue() {
+    fn test_advance_after_ended_skips_missing_entries() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
+        let ids = add_test_tracks(&conn, 6);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 0).unwrap();
+        // Every other entry is missing
+        for id in [ids[1], ids[3], ids[5]] {
+            mark_track_missing(&conn, id).unwrap();
+        }
 
-        clear_queue(&conn).unwrap();
+        // Off by default: the missing entry is next
+        let step = advance_after_ended(&conn, 1, false).unwrap();
+        assert!(matches!(step, QueueAdvance::Play { index: 1, .. }));
 
-        let length = get_queue_length(&conn).unwrap();
-        assert_eq!(length, 0);
+        set_current_index(&conn, 0).unwrap();
+        settings::set_setting(&conn, SKIP_MISSING_SETTING, &serde_json::json!(true)).unwrap();
+        let upcoming: Vec<String> = peek_upcoming(&conn, 10)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.track.filepath)
+            .collect();
+        assert_eq!(upcoming, ["/music/track3.mp3", "/music/track5.mp3"]);
+
+        let mut played = Vec::new();
+        loop {
+            match advance_after_ended(&conn, 1, false).unwrap() {
+                QueueAdvance::Play {
+                    filepath,
+                    skipped,
+                    ..
+                } => {
+                    assert_eq!(skipped.len(), 1);
+                    played.push(filepath);
+                }
+                QueueAdvance::End { skipped, .. } => {
+                    // Only missing entries were left, so playback stops
+                    assert_eq!(skipped, ["/music/track6.mp3"]);
+                    break;
+                }
+            }
+        }
+        assert_eq!(played, ["/music/track3.mp3", "/music/track5.mp3"]);
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 4);
     }
 
     #[test]
-    fn test_remove_from_queue_invalid_position() {
+    fn test_advance_after_ended_skips_missing_in_consume_mode() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
-
-        // Invalid positive position
-        let removed = remove_from_queue(&conn, 999).unwrap();
-        assert!(!removed);
+        let ids = add_test_tracks(&conn, 4);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 0).unwrap();
+        set_consume_enabled(&conn, true).unwrap();
+        mark_track_missing(&conn, ids[1]).unwrap();
+        settings::set_setting(&conn, SKIP_MISSING_SETTING, &serde_json::json!(true)).unwrap();
+
+        let QueueAdvance::Play {
+            index,
+            filepath,
+            consumed,
+            skipped,
+            ..
+        } = advance_after_ended(&conn, 1, false).unwrap()
+        else {
+            panic!("an available entry is left");
+        };
+        assert_eq!((index, consumed), (1, Some(0)));
+        assert_eq!(filepath, "/music/track3.mp3");
+        // The skipped entry never played, so it isn't consumed
+        assert_eq!(skipped, ["/music/track2.mp3"]);
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue[0].track.filepath, "/music/track2.mp3");
+    }
 
-        // Invalid negative position
-        let removed = remove_from_queue(&conn, -1).unwrap();
-        assert!(!removed);
+    #[test]
+    fn test_peek_upcoming_follows_shuffled_order_without_mutating() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 4);
+        // Shuffled queue order: 3, 1, 4, 2
+        add_to_queue(&conn, &[ids[2], ids[0], ids[3], ids[1]], None).unwrap();
+        set_queue_state(
+            &conn,
+            &QueueState {
+                current_index: 1,
+                shuffle_enabled: true,
+                loop_mode: "all".to_string(),
+                original_order_json: None,
+                shuffle_seed: Some(7),
+                consume_enabled: false,
+                shuffle_mode: "tracks".to_string(),
+            },
+        )
+        .unwrap();
+
+        let upcoming = peek_upcoming(&conn, 3).unwrap();
+        let upcoming_ids: Vec<i64> = upcoming.iter().map(|item| item.track.id).collect();
+        assert_eq!(upcoming_ids, vec![ids[3], ids[1]]);
+        assert_eq!(upcoming[0].position, 2);
+        assert_eq!(upcoming[0].track.title.as_deref(), Some("Track 4"));
+
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 1);
+        assert_eq!(get_queue_length(&conn).unwrap(), 4);
     }
 
     #[test]
-    fn test_reorder_queue_invalid_from() {
+    fn test_replace_with_radio_starts_at_seed() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
+        add_test_tracks(&conn, 5);
+        add_to_queue(&conn, &[1], None).unwrap();
+        let candidates: Vec<String> = (2..=5).map(|i| format!("/music/track{}.mp3", i)).collect();
 
-        let success = reorder_queue(&conn, 999, 0).unwrap();
-        assert!(!success);
+        let len = replace_with_radio(&conn, "/music/track1.mp3", &candidates, 9, 100).unwrap();
+        assert_eq!(len, 5);
+
+        let queue = get_queue(&conn).unwrap();
+        assert_eq!(queue[0].track.filepath, "/music/track1.mp3");
+        let mut rest: Vec<&str> = queue[1..].iter().map(|i| i.track.filepath.as_str()).collect();
+        rest.sort();
+        assert_eq!(rest, candidates.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.current_index, 0);
+        assert_eq!(state.shuffle_seed, Some(9));
+
+        // The cap includes the seed
+        let len = replace_with_radio(&conn, "/music/track1.mp3", &candidates, 9, 3).unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(get_queue(&conn).unwrap()[0].track.filepath, "/music/track1.mp3");
     }
 
     #[test]
-    fn test_reorder_queue_invalid_to() {
+    fn test_replace_with_shuffled_library_excludes_missing() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 3);
-        add_to_queue(&conn, &track_ids, None).unwrap();
+        let track_ids = add_test_tracks(&conn, 5);
+        mark_track_missing(&conn, track_ids[1]).unwrap();
+        mark_track_missing(&conn, track_ids[3]).unwrap();
+        add_to_queue(&conn, &track_ids[..1], None).unwrap();
 
-        let success = reorder_queue(&conn, 0, 999).unwrap();
-        assert!(!success);
+        let len = replace_with_shuffled_library(&conn, &LibraryQuery::new(), 42, 1000).unwrap();
+        assert_eq!(len, 3);
+
+        let mut queued: Vec<String> = get_queue(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|item| item.track.filepath)
+            .collect();
+        queued.sort();
+        assert_eq!(queued, paths(&["track1", "track3", "track5"]));
+
+        let state = get_queue_state(&conn).unwrap();
+        assert_eq!(state.current_index, 0);
+        assert_eq!(state.shuffle_seed, Some(42));
     }
 
     #[test]
-    fn test_get_queue_state_default() {
+    fn test_replace_with_shuffled_library_respects_cap() {
         let conn = setup_test_db();
+        add_test_tracks(&conn, 20);
 
-        // First call should initialize default state
-        let state = get_queue_state(&conn).unwrap();
-        assert_eq!(state.current_index, -1);
-        assert!(!state.shuffle_enabled);
-        assert_eq!(state.loop_mode, "none");
-        assert!(state.original_order_json.is_none());
+        let len = replace_with_shuffled_library(&conn, &LibraryQuery::new(), 7, 8).unwrap();
+        assert_eq!(len, 8);
+        assert_eq!(get_queue_length(&conn).unwrap(), 8);
+
+        // Same seed yields the same selection
+        let first: Vec<String> = get_queue(&conn).unwrap().into_iter().map(|i| i.track.filepath).collect();
+        replace_with_shuffled_library(&conn, &LibraryQuery::new(), 7, 8).unwrap();
+        let second: Vec<String> = get_queue(&conn).unwrap().into_iter().map(|i| i.track.filepath).collect();
+        assert_eq!(first, second);
     }
 
     #[test]
-    fn test_set_queue_state() {
+    fn test_replace_with_shuffled_library_empty_match() {
         let conn = setup_test_db();
+        add_test_tracks(&conn, 3);
 
-        let state = QueueState {
-            current_index: 5,
-            shuffle_enabled: true,
-            loop_mode: "all".to_string(),
-            original_order_json: Some("[1,2,3]".to_string()),
+        let query = LibraryQuery {
+            artist: Some("Nobody".to_string()),
+            ..LibraryQuery::new()
         };
+        let len = replace_with_shuffled_library(&conn, &query, 1, 1000).unwrap();
+        assert_eq!(len, 0);
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, -1);
+    }
 
-        set_queue_state(&conn, &state).unwrap();
+    fn paths(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| format!("/music/{}.mp3", n)).collect()
+    }
 
-        let retrieved = get_queue_state(&conn).unwrap();
-        assert_eq!(retrieved.current_index, 5);
-        assert!(retrieved.shuffle_enabled);
-        assert_eq!(retrieved.loop_mode, "all");
-        assert_eq!(retrieved.original_order_json, Some("[1,2,3]".to_string()));
+    #[test]
+    fn test_build_shuffle_order_same_seed_same_order() {
+        let filepaths: Vec<String> = (0..50).map(|i| format!("/music/{}.mp3", i)).collect();
+
+        let first = build_shuffle_order(&filepaths, 0, 1234, SHUFFLE_NO_REPEAT_WINDOW);
+        let second = build_shuffle_order(&filepaths, 0, 1234, SHUFFLE_NO_REPEAT_WINDOW);
+        assert_eq!(first, second);
+
+        let other = build_shuffle_order(&filepaths, 0, 4321, SHUFFLE_NO_REPEAT_WINDOW);
+        assert_ne!(first, other);
+
+        // Still a permutation of the input
+        let mut sorted = first.clone();
+        sorted.sort();
+        let mut expected = filepaths.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
     }
 
     #[test]
-    fn test_set_current_index() {
-        let conn = setup_test_db();
+    fn test_build_shuffle_order_no_repeat_window() {
+        // Four tracks queued three times each: spacing of 4 is achievable with window 3
+        let mut filepaths = Vec::new();
+        for _ in 0..3 {
+            filepaths.extend(paths(&["a", "b", "c", "d"]));
+        }
 
-        set_current_index(&conn, 10).unwrap();
+        for seed in 0..20 {
+            let order = build_shuffle_order(&filepaths, 0, seed, 3);
+            assert_eq!(order.len(), filepaths.len());
+            for (i, filepath) in order.iter().enumerate() {
+                let window_start = i.saturating_sub(3);
+                assert!(
+                    !order[window_start..i].contains(filepath),
+                    "seed {} repeated {} within window at {}",
+                    seed,
+                    filepath,
+                    i
+                );
+            }
+        }
+    }
 
-        let state = get_queue_state(&conn).unwrap();
-        assert_eq!(state.current_index, 10);
+    #[test]
+    fn test_build_shuffle_order_window_includes_played_prefix() {
+        let mut filepaths = paths(&["a"]);
+        filepaths.extend(paths(&["a", "b", "c"]));
+
+        for seed in 0..20 {
+            let order = build_shuffle_order(&filepaths, 1, seed, 1);
+            assert_eq!(order[0], "/music/a.mp3");
+            assert_ne!(order[1], "/music/a.mp3");
+        }
     }
 
     #[test]
-    fn test_set_shuffle_enabled() {
-        let conn = setup_test_db();
+    fn test_build_shuffle_order_keeps_played_prefix() {
+        let filepaths = paths(&["a", "b", "c", "d", "e", "f"]);
 
-        set_shuffle_enabled(&conn, true).unwrap();
+        let order = build_shuffle_order(&filepaths, 2, 99, SHUFFLE_NO_REPEAT_WINDOW);
+        assert_eq!(&order[..2], &filepaths[..2]);
+        assert_eq!(order.len(), filepaths.len());
+    }
 
-        let state = get_queue_state(&conn).unwrap();
-        assert!(state.shuffle_enabled);
+    #[test]
+    fn test_reshuffle_unplayed_persists_seed() {
+        let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 10);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+        set_current_index(&conn, 2).unwrap();
 
-        set_shuffle_enabled(&conn, false).unwrap();
+        let before = get_queue(&conn).unwrap();
+        let length = reshuffle_unplayed(&conn, 7).unwrap();
+        assert_eq!(length, 10);
+
+        let after = get_queue(&conn).unwrap();
+        for (a, b) in after.iter().zip(&before).take(3) {
+            assert_eq!(a.track.filepath, b.track.filepath);
+        }
 
         let state = get_queue_state(&conn).unwrap();
-        assert!(!state.shuffle_enabled);
+        assert_eq!(state.shuffle_seed, Some(7));
     }
 
     #[test]
-    fn test_set_loop_mode() {
+    fn test_reshuffle_from_keeps_prefix_and_is_seeded() {
         let conn = setup_test_db();
+        let track_ids = add_test_tracks(&conn, 10);
+        add_to_queue(&conn, &track_ids, None).unwrap();
+        let before = get_queue(&conn).unwrap();
+
+        assert_eq!(reshuffle_from(&conn, 1, 3).unwrap(), 10);
+        let first: Vec<i64> = get_queue(&conn).unwrap().iter().map(|i| i.track.id).collect();
+        assert_eq!(first[0], before[0].track.id);
+        assert_eq!(get_queue_state(&conn).unwrap().shuffle_seed, Some(3));
+
+        // Rebuilding from the same order and seed gives the same shuffle
+        replace_with_tracks(&conn, &track_ids, 0, None).unwrap();
+        reshuffle_from(&conn, 1, 3).unwrap();
+        let again: Vec<i64> = get_queue(&conn).unwrap().iter().map(|i| i.track.id).collect();
+        assert_eq!(first, again);
+    }
 
-        set_loop_mode(&conn, "one").unwrap();
-
-        let state = get_queue_state(&conn).unwrap();
-        assert_eq!(state.loop_mode, "one");
+    /// Album runs in `order`, asserting each album is one contiguous block
+    fn album_blocks(order: &[String], albums: &HashMap<String, AlbumSlot>) -> Vec<String> {
+        let mut blocks: Vec<String> = Vec::new();
+        for filepath in order {
+            let album = albums[filepath].album.1.clone();
+            if blocks.last() != Some(&album) {
+                assert!(!blocks.contains(&album), "album {} split up in {:?}", album, order);
+                blocks.push(album);
+            }
+        }
+        blocks
+    }
 
-        set_loop_mode(&conn, "all").unwrap();
+    #[test]
+    fn test_build_album_shuffle_order_keeps_albums_together() {
+        // Queued out of track order and interleaved across three albums
+        let filepaths = paths(&["a2", "b1", "a1", "c1", "b2", "a3", "c2", "b3"]);
+        let albums: HashMap<String, AlbumSlot> = filepaths
+            .iter()
+            .map(|filepath| {
+                let name = &filepath["/music/".len()..filepath.len() - ".mp3".len()];
+                let slot = AlbumSlot {
+                    album: ("Artist".to_string(), name[..1].to_string()),
+                    disc: 1,
+                    track: name[1..].parse().ok(),
+                };
+                (filepath.clone(), slot)
+            })
+            .collect();
 
-        let state = get_queue_state(&conn).unwrap();
-        assert_eq!(state.loop_mode, "all");
+        let mut block_orders = HashSet::new();
+        for seed in 0..20 {
+            let order = build_album_shuffle_order(&filepaths, 0, seed, &albums);
+            assert_eq!(order, build_album_shuffle_order(&filepaths, 0, seed, &albums));
+            assert_eq!(order.len(), filepaths.len());
+
+            // Each album plays in track order
+            for album in ["a", "b", "c"] {
+                let tracks: Vec<&String> =
+                    order.iter().filter(|fp| albums[*fp].album.1 == album).collect();
+                let mut sorted = tracks.clone();
+                sorted.sort_by_key(|fp| albums[*fp].track);
+                assert_eq!(tracks, sorted);
+            }
+            block_orders.insert(album_blocks(&order, &albums));
+        }
+        // The albums themselves are reordered from seed to seed
+        assert!(block_orders.len() > 1);
     }
 
     #[test]
-    fn test_set_original_order_json() {
-        let conn = setup_test_db();
+    fn test_build_album_shuffle_order_finishes_playing_album_first() {
+        let filepaths = paths(&["a1", "a2", "b1", "c1", "a3", "b2"]);
+        let slot = |album: &str, track: i64| AlbumSlot {
+            album: (String::new(), album.to_string()),
+            disc: 1,
+            track: Some(track),
+        };
+        let slots = [
+            slot("a", 1),
+            slot("a", 2),
+            slot("b", 1),
+            slot("c", 1),
+            slot("a", 3),
+            slot("b", 2),
+        ];
+        let albums: HashMap<String, AlbumSlot> = filepaths.iter().cloned().zip(slots).collect();
 
-        set_original_order_json(&conn, Some("[5,4,3,2,1]".to_string())).unwrap();
+        for seed in 0..10 {
+            let order = build_album_shuffle_order(&filepaths, 2, seed, &albums);
+            assert_eq!(&order[..3], &paths(&["a1", "a2", "a3"])[..]);
+            album_blocks(&order[2..], &albums);
+        }
+    }
 
-        let state = get_queue_state(&conn).unwrap();
-        assert_eq!(state.original_order_json, Some("[5,4,3,2,1]".to_string()));
+    #[test]
+    fn test_reshuffle_unplayed_in_album_mode() {
+        let conn = setup_test_db();
+        let mut ids = Vec::new();
+        for album in ["Red", "Blue", "Green"] {
+            for (disc, track) in [(2, "1"), (1, "2/3"), (1, "1/3")] {
+                let metadata = TrackMetadata {
+                    artist: Some("Band".to_string()),
+                    album: Some(album.to_string()),
+                    disc_number: Some(disc),
+                    track_number: Some(track.to_string()),
+                    ..Default::default()
+                };
+                let filepath = format!("/music/{}-{}-{}.mp3", album, disc, &track[..1]);
+                ids.push(add_track(&conn, &filepath, &metadata).unwrap());
+            }
+        }
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_shuffle_mode(&conn, "albums").unwrap();
+
+        let mut block_orders = HashSet::new();
+        for seed in 0..10 {
+            set_current_index(&conn, -1).unwrap();
+            assert_eq!(reshuffle_unplayed(&conn, seed).unwrap(), 9);
+
+            let queue = get_queue(&conn).unwrap();
+            let albums: Vec<&str> =
+                queue.iter().map(|item| item.track.album.as_deref().unwrap()).collect();
+            for block in albums.chunks(3) {
+                assert!(block.iter().all(|album| *album == block[0]), "{:?}", albums);
+            }
+            let filepaths: Vec<&str> =
+                queue.iter().map(|item| item.track.filepath.as_str()).collect();
+            for block in filepaths.chunks(3) {
+                let names: Vec<&str> = block.iter().map(|fp| &fp[fp.len() - 7..]).collect();
+                assert_eq!(names, ["1-1.mp3", "1-2.mp3", "2-1.mp3"]);
+            }
+            block_orders.insert(albums.join(","));
+        }
+        assert!(block_orders.len() > 1);
+    }
 
-        // Clear it
-        set_original_order_json(&conn, None).unwrap();
+    #[test]
+    fn test_advance_after_ended_album_mode_plays_ended_album_last() {
+        let conn = setup_test_db();
+        let mut ids = Vec::new();
+        for album in ["Red", "Blue", "Green"] {
+            for track in 1..=2 {
+                let metadata = TrackMetadata {
+                    album: Some(album.to_string()),
+                    track_number: Some(track.to_string()),
+                    ..Default::default()
+                };
+                let filepath = format!("/music/{}-{}.mp3", album, track);
+                ids.push(add_track(&conn, &filepath, &metadata).unwrap());
+            }
+        }
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_queue_state(
+            &conn,
+            &QueueState {
+                current_index: 5,
+                shuffle_enabled: true,
+                loop_mode: "all".to_string(),
+                original_order_json: None,
+                shuffle_seed: Some(1),
+                consume_enabled: false,
+                shuffle_mode: "albums".to_string(),
+            },
+        )
+        .unwrap();
+
+        let QueueAdvance::Play { index, reshuffled, .. } =
+            advance_after_ended(&conn, 42, false).unwrap()
+        else {
+            panic!("loop all should wrap around");
+        };
+        assert_eq!(index, 0);
+        assert!(reshuffled);
 
-        let state = get_queue_state(&conn).unwrap();
-        assert!(state.original_order_json.is_none());
+        let queue = get_queue(&conn).unwrap();
+        let filepaths: Vec<&str> = queue.iter().map(|item| item.track.filepath.as_str()).collect();
+        assert_eq!(&filepaths[4..], ["/music/Green-1.mp3", "/music/Green-2.mp3"]);
+        assert_ne!(filepaths[0], "/music/Green-1.mp3");
     }
 
     #[test]
-    fn test_add_files_to_queue_new_files() {
+    fn test_set_shuffle_mode_rejects_unknown_modes() {
         let conn = setup_test_db();
+        assert_eq!(get_queue_state(&conn).unwrap().shuffle_mode, "tracks");
+        set_shuffle_mode(&conn, "albums").unwrap();
 
-        // Add files not in library
-        let filepaths = vec![
-            "/music/new1.mp3".to_string(),
-            "/music/new2.mp3".to_string(),
-        ];
+        for mode in ["", "album", "Tracks", "artists"] {
+            let result = set_shuffle_mode(&conn, mode);
+            assert!(matches!(result, Err(DbError::Constraint(_))), "{mode:?} accepted");
+        }
+        assert_eq!(get_queue_state(&conn).unwrap().shuffle_mode, "albums");
 
-        let (count, tracks) = add_files_to_queue(&conn, &filepaths, None).unwrap();
-        assert_eq!(count, 2);
-        assert_eq!(tracks.len(), 2);
+        conn.execute("UPDATE queue_state SET shuffle_mode = 'genres' WHERE id = 1", []).unwrap();
+        assert_eq!(get_queue_state(&conn).unwrap().shuffle_mode, "tracks");
+    }
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue.len(), 2);
+    #[test]
+    fn test_compute_totals_sequential_vs_shuffled() {
+        let sequential = [Some(60.0), Some(120.0), Some(180.0), Some(240.0)];
+        let totals = compute_totals(&sequential, 1);
+        assert_eq!(totals.total_duration, 600.0);
+        assert_eq!(totals.elapsed_duration, 60.0);
+        assert_eq!(totals.remaining_duration, 540.0);
+
+        // The same tracks shuffled, still on the 120s track: only what plays
+        // after it in shuffled order remains
+        let shuffled = [Some(240.0), Some(120.0), Some(60.0), Some(180.0)];
+        let totals = compute_totals(&shuffled, 1);
+        assert_eq!(totals.total_duration, 600.0);
+        assert_eq!(totals.elapsed_duration, 240.0);
+        assert_eq!(totals.remaining_duration, 360.0);
+
+        assert_eq!(compute_totals(&sequential, -1).remaining_duration, 600.0);
+        assert_eq!(compute_totals(&sequential, 9).elapsed_duration, 600.0);
+        assert_eq!(compute_totals(&[], 0), QueueTotals::default());
     }
 
     #[test]
-    fn test_add_files_to_queue_existing_files() {
+    fn test_queue_totals_follow_shuffled_order() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 2);
+        let ids: Vec<i64> = [60.0, 120.0, 180.0, 240.0, 300.0]
+            .iter()
+            .enumerate()
+            .map(|(i, seconds)| {
+                let metadata = TrackMetadata {
+                    duration: Some(*seconds),
+                    ..Default::default()
+                };
+                add_track(&conn, &format!("/music/{}.mp3", i), &metadata).unwrap()
+            })
+            .collect();
+        // Queued without a known duration
+        add_files_to_queue(&conn, &["/music/unknown.mp3".to_string()], None).unwrap();
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 2).unwrap();
+
+        let totals = get_queue_totals(&conn).unwrap();
+        assert_eq!(totals.count, 6);
+        assert_eq!(totals.current_index, 2);
+        assert_eq!(totals.total_duration, 900.0);
+        assert_eq!(totals.elapsed_duration, 60.0);
+        assert_eq!(totals.remaining_duration, 840.0);
+        assert_eq!(totals.unknown_durations, 1);
+
+        reshuffle_unplayed(&conn, 11).unwrap();
+        set_current_index(&conn, 4).unwrap();
+        let expected_remaining: f64 = get_queue(&conn).unwrap()[4..]
+            .iter()
+            .map(|item| item.track.duration.unwrap_or(0.0))
+            .sum();
+
+        let totals = get_queue_totals(&conn).unwrap();
+        assert_eq!(totals.total_duration, 900.0);
+        assert_eq!(totals.remaining_duration, expected_remaining);
+        assert_eq!(totals.elapsed_duration, 900.0 - expected_remaining);
+    }
 
-        // Get filepaths from library
-        let filepaths = vec![
-            "/music/track1.mp3".to_string(),
-            "/music/track2.mp3".to_string(),
-        ];
+    #[test]
+    fn test_prev_index_edges() {
+        assert_eq!(prev_index(3, 2, "none"), Some(1));
+        assert_eq!(prev_index(3, 0, "none"), None);
+        assert_eq!(prev_index(3, 0, "one"), None);
+        assert_eq!(prev_index(3, 0, "all"), Some(2));
+        assert_eq!(prev_index(3, -1, "all"), None);
+        assert_eq!(prev_index(0, 0, "all"), None);
+    }
 
-        let (count, tracks) = add_files_to_queue(&conn, &filepaths, None).unwrap();
-        assert_eq!(count, 2);
-        assert_eq!(tracks.len(), 2);
-        assert_eq!(tracks[0].id, track_ids[0]);
-        assert_eq!(tracks[1].id, track_ids[1]);
+    #[test]
+    fn test_peek_next_matches_shuffled_playback() {
+        let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 6);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_current_index(&conn, 0).unwrap();
+        set_shuffle_enabled(&conn, true).unwrap();
+        reshuffle_unplayed(&conn, 11).unwrap();
+
+        loop {
+            let peeked = peek_next(&conn).unwrap();
+            let before = get_queue(&conn).unwrap();
+            match advance_after_ended(&conn, 11, false).unwrap() {
+                QueueAdvance::Play { filepath, .. } => {
+                    assert_eq!(peeked.map(|t| t.filepath), Some(filepath));
+                }
+                QueueAdvance::End { .. } => {
+                    assert!(peeked.is_none());
+                    break;
+                }
+            }
+            // Peeking never changes the queue
+            let order = |items: &[QueueItem]| -> Vec<String> {
+                items.iter().map(|i| i.track.filepath.clone()).collect()
+            };
+            assert_eq!(order(&before), order(&get_queue(&conn).unwrap()));
+        }
     }
 
     #[test]
-    fn test_add_files_to_queue_at_position() {
+    fn test_peek_next_and_previous_wrap_with_loop_all() {
         let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 3);
+        add_to_queue(&conn, &ids, None).unwrap();
+        set_loop_mode(&conn, "all").unwrap();
+        let filepath = |track: Option<Track>| track.map(|t| t.filepath);
+
+        set_current_index(&conn, 0).unwrap();
+        assert_eq!(filepath(peek_previous(&conn).unwrap()).as_deref(), Some("/music/track3.mp3"));
+
+        set_current_index(&conn, 2).unwrap();
+        let peeked = filepath(peek_next(&conn).unwrap());
+        assert_eq!(peeked.as_deref(), Some("/music/track1.mp3"));
+        let QueueAdvance::Play { filepath: played, .. } =
+            advance_after_ended(&conn, 1, false).unwrap()
+        else {
+            panic!("loop all should wrap");
+        };
+        assert_eq!(peeked, Some(played));
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 0);
 
-        // Add initial files
-        let initial = vec!["/music/first.mp3".to_string(), "/music/second.mp3".to_string()];
-        add_files_to_queue(&conn, &initial, None).unwrap();
+        // Without loop the edges have nothing to peek
+        set_loop_mode(&conn, "none").unwrap();
+        assert!(peek_previous(&conn).unwrap().is_none());
+        set_current_index(&conn, 2).unwrap();
+        assert!(peek_next(&conn).unwrap().is_none());
+        assert_eq!(get_queue_state(&conn).unwrap().current_index, 2);
 
-        // Add new files at position 1
-        let new_files = vec!["/music/inserted.mp3".to_string()];
-        add_files_to_queue(&conn, &new_files, Some(1)).unwrap();
+        // A shuffled loop reshuffles at the wrap, so the next track is unknown
+        set_loop_mode(&conn, "all").unwrap();
+        set_shuffle_enabled(&conn, true).unwrap();
+        assert!(peek_next(&conn).unwrap().is_none());
+    }
 
-        let queue = get_queue(&conn).unwrap();
-        assert_eq!(queue.len(), 3);
-        assert_eq!(queue[0].track.filepath, "/music/first.mp3");
-        assert_eq!(queue[1].track.filepath, "/music/inserted.mp3");
-        assert_eq!(queue[2].track.filepath, "/music/second.mp3");
+    fn retitle(conn: &Connection, track_id: i64, title: &str) {
+        let metadata = TrackMetadata {
+            title: Some(title.to_string()),
+            ..Default::default()
+        };
+        update_track_metadata(conn, track_id, &metadata).unwrap();
+    }
+
+    fn queued_titles(conn: &Connection) -> Vec<Option<String>> {
+        get_queue(conn).unwrap().into_iter().map(|item| item.track.title).collect()
     }
 
     #[test]
-    fn test_get_queue_empty() {
+    fn test_snapshot_keeps_queued_title_until_refreshed() {
         let conn = setup_test_db();
-
-        let queue = get_queue(&conn).unwrap();
-        assert!(queue.is_empty());
+        let ids = add_test_tracks(&conn, 2);
+        settings::set_setting(&conn, SNAPSHOT_SETTING, &serde_json::json!(true)).unwrap();
+        add_to_queue(&conn, &ids, None).unwrap();
+
+        retitle(&conn, ids[0], "Renamed");
+        assert_eq!(
+            queued_titles(&conn),
+            [Some("Track 1".to_string()), Some("Track 2".to_string())]
+        );
+
+        // Rebuilding the queue in a new order keeps the snapshots
+        reorder_queue(&conn, 0, 1).unwrap();
+        assert_eq!(
+            queued_titles(&conn),
+            [Some("Track 2".to_string()), Some("Track 1".to_string())]
+        );
+
+        assert_eq!(refresh_snapshots(&conn).unwrap(), 2);
+        assert_eq!(
+            queued_titles(&conn),
+            [Some("Track 2".to_string()), Some("Renamed".to_string())]
+        );
     }
 
     #[test]
-    fn test_get_queue_length() {
+    fn test_snapshot_off_shows_live_metadata() {
         let conn = setup_test_db();
-        let track_ids = add_test_tracks(&conn, 5);
-        add_to_queue(&conn, &track_ids, None).unwrap();
+        let ids = add_test_tracks(&conn, 1);
+        add_to_queue(&conn, &ids, None).unwrap();
 
-        let length = get_queue_length(&conn).unwrap();
-        assert_eq!(length, 5);
+        retitle(&conn, ids[0], "Renamed");
+        assert_eq!(queued_titles(&conn), [Some("Renamed".to_string())]);
     }
 
     #[test]
-    fn test_add_to_queue_nonexistent_tracks() {
+    fn test_snapshot_retaken_when_file_queued_again() {
         let conn = setup_test_db();
+        let ids = add_test_tracks(&conn, 1);
+        settings::set_setting(&conn, SNAPSHOT_SETTING, &serde_json::json!(true)).unwrap();
+        add_to_queue(&conn, &ids, None).unwrap();
 
-        // Try to add nonexistent track IDs
-        let added = add_to_queue(&conn, &[9999, 9998], None).unwrap();
-        assert_eq!(added, 0);
+        retitle(&conn, ids[0], "Renamed");
+        remove_from_queue(&conn, 0).unwrap();
+        add_to_queue(&conn, &ids, None).unwrap();
+        assert_eq!(queued_titles(&conn), [Some("Renamed".to_string())]);
 
+        // A deleted track still shows what was queued
+        delete_track(&conn, ids[0]).unwrap();
         let queue = get_queue(&conn).unwrap();
-        assert!(queue.is_empty());
+        assert_eq!(queue[0].track.id, ids[0]);
+        assert_eq!(queue[0].track.title.as_deref(), Some("Renamed"));
     }
 }
-
-#[cfg(test)]
-#[path = "queue_props_test.rs"]
-mod queue_props_test;
@@ -0,0 +1,242 @@
+//! Radio track selection.
+//!
+//! A radio queue starts with a seed track followed by related tracks from the
+//! library: the seed's artist catalog, local matches for Last.fm similarity
+//! suggestions, or tracks sharing the seed's genre. Missing tracks are never
+//! selected.
+
+use std::collections::HashSet;
+
+use rusqlite::{Connection, params};
+
+use crate::db::DbResult;
+
+/// Most tracks a radio queue holds, including the seed
+pub const RADIO_MAX_TRACKS: usize = 100;
+
+/// Tracks queued for each similar artist suggestion
+pub const SIMILAR_TRACKS_PER_ARTIST: usize = 3;
+
+/// A similar artist, or a similar track when `title` is set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimilarSuggestion {
+    pub artist: String,
+    pub title: Option<String>,
+}
+
+/// Library tracks found for a list of similar suggestions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimilarMatches {
+    pub filepaths: Vec<String>,
+    /// Suggestions with no matching library track
+    pub unmatched: usize,
+}
+
+fn query_filepaths(conn: &Connection, sql: &str, track_id: i64) -> DbResult<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let filepaths = stmt
+        .query_map([track_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(filepaths)
+}
+
+/// Filepaths of the other tracks by the seed's artist or album artist
+pub fn get_artist_catalog(conn: &Connection, track_id: i64) -> DbResult<Vec<String>> {
+    query_filepaths(
+        conn,
+        "SELECT l.filepath FROM library l, library s
+         WHERE s.id = ?1 AND l.id != s.id
+           AND (l.missing = 0 OR l.missing IS NULL)
+           AND (l.artist = NULLIF(s.artist, '') COLLATE NOCASE
+                OR l.album_artist = NULLIF(s.artist, '') COLLATE NOCASE
+                OR l.artist = NULLIF(s.album_artist, '') COLLATE NOCASE
+                OR l.album_artist = NULLIF(s.album_artist, '') COLLATE NOCASE)
+         ORDER BY l.id",
+        track_id,
+    )
+}
+
+/// Filepaths of the other tracks sharing the seed's genre
+pub fn get_genre_filepaths(conn: &Connection, track_id: i64) -> DbResult<Vec<String>> {
+    query_filepaths(
+        conn,
+        "SELECT l.filepath FROM library l, library s
+         WHERE s.id = ?1 AND l.id != s.id
+           AND (l.missing = 0 OR l.missing IS NULL)
+           AND l.genre = NULLIF(s.genre, '') COLLATE NOCASE
+         ORDER BY l.id",
+        track_id,
+    )
+}
+
+/// Map similar suggestions to library tracks, in suggestion order
+///
+/// A track suggestion matches on artist and title. An artist suggestion
+/// contributes up to `per_artist` of that artist's most played tracks. The
+/// seed track and tracks already matched are skipped.
+pub fn match_similar(
+    conn: &Connection,
+    seed_track_id: i64,
+    suggestions: &[SimilarSuggestion],
+    per_artist: usize,
+) -> DbResult<SimilarMatches> {
+    let mut track_stmt = conn.prepare(
+        "SELECT filepath FROM library
+         WHERE artist = ?1 COLLATE NOCASE AND title = ?2 COLLATE NOCASE
+           AND id != ?3 AND (missing = 0 OR missing IS NULL)
+         ORDER BY id",
+    )?;
+    let mut artist_stmt = conn.prepare(
+        "SELECT filepath FROM library
+         WHERE (artist = ?1 COLLATE NOCASE OR album_artist = ?1 COLLATE NOCASE)
+           AND id != ?2 AND (missing = 0 OR missing IS NULL)
+         ORDER BY play_count DESC, id
+         LIMIT ?3",
+    )?;
+
+    let mut seen = HashSet::new();
+    let mut matches = SimilarMatches::default();
+    for suggestion in suggestions {
+        let found: Vec<String> = match &suggestion.title {
+            Some(title) => track_stmt
+                .query_map(params![suggestion.artist, title, seed_track_id], |row| {
+                    row.get(0)
+                })?
+                .filter_map(|r| r.ok())
+                .take(1)
+                .collect(),
+            None => artist_stmt
+                .query_map(
+                    params![suggestion.artist, seed_track_id, per_artist as i64],
+                    |row| row.get(0),
+                )?
+                .filter_map(|r| r.ok())
+                .collect(),
+        };
+
+        if found.is_empty() {
+            matches.unmatched += 1;
+        }
+        for filepath in found {
+            if seen.insert(filepath.clone()) {
+                matches.filepaths.push(filepath);
+            }
+        }
+    }
+
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, mark_track_missing, set_play_count};
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add(conn: &Connection, path: &str, title: &str, artist: &str, genre: &str) -> i64 {
+        let metadata = TrackMetadata {
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            genre: Some(genre.to_string()),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    fn suggest(artist: &str, title: Option<&str>) -> SimilarSuggestion {
+        SimilarSuggestion {
+            artist: artist.to_string(),
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_artist_catalog() {
+        let conn = setup_test_db();
+        let seed = add(&conn, "/m/1.mp3", "Roads", "Portishead", "Trip Hop");
+        add(&conn, "/m/2.mp3", "Sour Times", "portishead", "Trip Hop");
+        let gone = add(&conn, "/m/3.mp3", "Mysterons", "Portishead", "Trip Hop");
+        add(&conn, "/m/4.mp3", "Teardrop", "Massive Attack", "Trip Hop");
+        let compilation = TrackMetadata {
+            title: Some("Only You".to_string()),
+            artist: Some("Various".to_string()),
+            album_artist: Some("Portishead".to_string()),
+            ..Default::default()
+        };
+        add_track(&conn, "/m/5.mp3", &compilation).unwrap();
+        mark_track_missing(&conn, gone).unwrap();
+
+        assert_eq!(
+            get_artist_catalog(&conn, seed).unwrap(),
+            vec!["/m/2.mp3", "/m/5.mp3"]
+        );
+        assert!(get_artist_catalog(&conn, 9999).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_genre_filepaths() {
+        let conn = setup_test_db();
+        let seed = add(&conn, "/m/1.mp3", "Roads", "Portishead", "Trip Hop");
+        add(&conn, "/m/2.mp3", "Teardrop", "Massive Attack", "trip hop");
+        add(&conn, "/m/3.mp3", "Windowlicker", "Aphex Twin", "IDM");
+        let untagged = add(&conn, "/m/4.mp3", "Untitled", "Unknown", "");
+        add(&conn, "/m/5.mp3", "Also Untitled", "Unknown", "");
+
+        assert_eq!(get_genre_filepaths(&conn, seed).unwrap(), vec!["/m/2.mp3"]);
+        assert!(get_genre_filepaths(&conn, untagged).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_match_similar_counts_unmatched() {
+        let conn = setup_test_db();
+        let seed = add(&conn, "/m/1.mp3", "Roads", "Portishead", "Trip Hop");
+        add(&conn, "/m/2.mp3", "Teardrop", "Massive Attack", "Trip Hop");
+        let hit = add(&conn, "/m/3.mp3", "Black Steel", "Tricky", "Trip Hop");
+        add(&conn, "/m/4.mp3", "Overcome", "Tricky", "Trip Hop");
+        add(
+            &conn,
+            "/m/5.mp3",
+            "Hell Is Round the Corner",
+            "Tricky",
+            "Trip Hop",
+        );
+        set_play_count(&conn, hit, 10, false).unwrap();
+
+        let suggestions = vec![
+            suggest("massive attack", Some("TEARDROP")),
+            suggest("Morcheeba", Some("The Sea")),
+            suggest("Tricky", None),
+            suggest("Portishead", Some("Roads")),
+            suggest("Sneaker Pimps", None),
+        ];
+        let matches = match_similar(&conn, seed, &suggestions, 2).unwrap();
+
+        // The most played tracks of a similar artist come first
+        assert_eq!(matches.filepaths, vec!["/m/2.mp3", "/m/3.mp3", "/m/4.mp3"]);
+        // The seed itself doesn't count as a match
+        assert_eq!(matches.unmatched, 3);
+    }
+
+    #[test]
+    fn test_match_similar_skips_duplicates() {
+        let conn = setup_test_db();
+        let seed = add(&conn, "/m/1.mp3", "Roads", "Portishead", "Trip Hop");
+        add(&conn, "/m/2.mp3", "Teardrop", "Massive Attack", "Trip Hop");
+
+        let suggestions = vec![
+            suggest("Massive Attack", Some("Teardrop")),
+            suggest("Massive Attack", None),
+        ];
+        let matches = match_similar(&conn, seed, &suggestions, 3).unwrap();
+        assert_eq!(matches.filepaths, vec!["/m/2.mp3"]);
+        assert_eq!(matches.unmatched, 0);
+    }
+}
@@ -0,0 +1,331 @@
+//! Moving the database to another location.
+//!
+//! The live database is copied with SQLite's online backup API, which
+//! produces a consistent file even while other connections are writing, and
+//! folds in anything still sitting in the WAL. The copy is written next to
+//! the target as `<name>.partial`, checked, and only then renamed into place,
+//! so a failure at any step leaves the current database in use and nothing
+//! half-written at the target. The old file is kept as a fallback.
+//!
+//! The chosen location is saved to a small file in the app data directory
+//! and read back at launch by `resolve_db_path`.
+
+use rusqlite::Connection;
+use rusqlite::backup::Backup;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::{Database, DbError, DbResult};
+
+/// Name of the database file in the app data directory
+pub const DEFAULT_DB_FILE: &str = "mt.db";
+
+/// File in the app data directory holding a relocated database's path
+const LOCATION_FILE: &str = "db_location";
+
+/// Pages copied per backup step; writers get the lock back between steps
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 256;
+
+/// Pause between backup steps
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(1);
+
+/// Outcome of a relocation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelocateResult {
+    pub old_path: String,
+    pub new_path: String,
+    /// False when switching to a database that already existed at the target
+    pub copied: bool,
+}
+
+/// Database file to open at launch
+///
+/// The saved location wins when its file still exists. If it's gone, for
+/// example on an unmounted drive, this falls back to `mt.db` in `data_dir`.
+pub fn resolve_db_path(data_dir: &Path) -> PathBuf {
+    let default = data_dir.join(DEFAULT_DB_FILE);
+    let Ok(saved) = fs::read_to_string(data_dir.join(LOCATION_FILE)) else {
+        return default;
+    };
+    let saved = PathBuf::from(saved.trim());
+    if saved.is_absolute() && saved.is_file() {
+        saved
+    } else {
+        warn!(
+            "Saved database location {} is unavailable, using {}",
+            saved.display(),
+            default.display()
+        );
+        default
+    }
+}
+
+/// Save `db_path` as the database location for the next launch
+pub fn save_db_location(data_dir: &Path, db_path: &Path) -> DbResult<()> {
+    fs::create_dir_all(data_dir)?;
+    let target = data_dir.join(LOCATION_FILE);
+    let tmp = data_dir.join(format!("{}.tmp", LOCATION_FILE));
+    fs::write(&tmp, db_path.to_string_lossy().as_bytes())?;
+    fs::rename(&tmp, &target)?;
+    Ok(())
+}
+
+/// Path of `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Remove a database file and the journal files SQLite keeps beside it
+fn remove_db_files(path: &Path) {
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let _ = fs::remove_file(with_suffix(path, suffix));
+    }
+}
+
+/// Fail early, with a clear message, if files can't be created in `dir`
+fn check_writable(dir: &Path) -> DbResult<()> {
+    let probe = dir.join(".mt-write-test");
+    fs::write(&probe, b"")
+        .map_err(|e| DbError::Constraint(format!("Can't write to {}: {}", dir.display(), e)))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Copy the database at `src` into a new file at `dst` and verify the copy
+///
+/// Running out of space surfaces here as SQLite's "database or disk is full".
+fn copy_database(src: &Path, dst: &Path, busy_timeout: Duration) -> DbResult<()> {
+    let src = Connection::open(src)?;
+    src.busy_timeout(busy_timeout)?;
+    let mut copy = Connection::open(dst)?;
+    {
+        let backup = Backup::new(&src, &mut copy)?;
+        backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)?;
+    }
+
+    let check: String = copy.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(DbError::Constraint(format!(
+            "Copied database failed its integrity check: {}",
+            check
+        )));
+    }
+    Ok(())
+}
+
+impl Database {
+    /// Move the database to `new_path` and switch every clone over to it
+    ///
+    /// The parent directory is created if needed. If a file already exists at
+    /// `new_path` this refuses, unless `use_existing` is set, in which case
+    /// that database is opened as-is and nothing is copied. `save_location` is
+    /// called once the new database is open and ready; if it fails, the
+    /// relocation is rolled back. New connections wait until the switch is
+    /// done. Connections already checked out finish on the old file.
+    pub fn relocate(
+        &self,
+        new_path: &Path,
+        use_existing: bool,
+        save_location: impl FnOnce(&Path) -> DbResult<()>,
+    ) -> DbResult<RelocateResult> {
+        let mut inner = self.inner.write();
+        let old_path = inner.path.clone().ok_or_else(|| {
+            DbError::Constraint("In-memory databases can't be relocated".to_string())
+        })?;
+
+        if !new_path.is_absolute() {
+            return Err(DbError::Constraint(format!(
+                "Database path must be absolute: {}",
+                new_path.display()
+            )));
+        }
+        let Some(dir) = new_path.parent().filter(|_| new_path.file_name().is_some()) else {
+            return Err(DbError::Constraint(format!(
+                "Not a file path: {}",
+                new_path.display()
+            )));
+        };
+        fs::create_dir_all(dir)?;
+        if new_path.exists() && fs::canonicalize(new_path)? == fs::canonicalize(&old_path)? {
+            return Err(DbError::Constraint(format!(
+                "The database is already at {}",
+                new_path.display()
+            )));
+        }
+
+        let copied = !new_path.exists();
+        if !copied && !use_existing {
+            return Err(DbError::Constraint(format!(
+                "A database already exists at {}",
+                new_path.display()
+            )));
+        }
+        check_writable(dir)?;
+
+        if copied {
+            let partial = with_suffix(new_path, ".partial");
+            remove_db_files(&partial);
+            let copy = copy_database(&old_path, &partial, inner.config.busy_timeout)
+                .and_then(|_| fs::rename(&partial, new_path).map_err(DbError::from));
+            if let Err(e) = copy {
+                remove_db_files(&partial);
+                return Err(e);
+            }
+        }
+
+        let switched = Self::open_pool(new_path, &inner.config)
+            .and_then(|pool| save_location(new_path).map(|_| pool));
+        let pool = match switched {
+            Ok(pool) => pool,
+            Err(e) => {
+                // Only clean up what this call created
+                if copied {
+                    remove_db_files(new_path);
+                }
+                return Err(e);
+            }
+        };
+
+        inner.pool = pool;
+        inner.path = Some(new_path.to_path_buf());
+        info!(
+            "Relocated database from {} to {}",
+            old_path.display(),
+            new_path.display()
+        );
+
+        Ok(RelocateResult {
+            old_path: old_path.to_string_lossy().into_owned(),
+            new_path: new_path.to_string_lossy().into_owned(),
+            copied,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TrackMetadata, library, settings};
+
+    fn track_count(db: &Database) -> i64 {
+        db.with_conn(|conn| {
+            Ok(conn.query_row("SELECT COUNT(*) FROM library", [], |row| row.get(0))?)
+        })
+        .unwrap()
+    }
+
+    fn add_track(db: &Database, path: &str) {
+        db.with_conn(|conn| library::add_track(conn, path, &TrackMetadata::default()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_relocate_copies_and_switches_all_clones() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("mt.db");
+        let new = dir.path().join("elsewhere/nested/library.db");
+        let db = Database::new(&old).unwrap();
+        let clone = db.clone();
+        add_track(&db, "/music/a.mp3");
+        // Leave a write in the WAL to check it's carried over
+        let conn = db.conn().unwrap();
+        settings::set_setting(&conn, "volume", &serde_json::json!(40)).unwrap();
+        drop(conn);
+
+        let mut saved = None;
+        let result = db
+            .relocate(&new, false, |path| {
+                saved = Some(path.to_path_buf());
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(result.copied);
+        assert_eq!(saved.as_deref(), Some(new.as_path()));
+        assert_eq!(clone.path().as_deref(), Some(new.as_path()));
+        assert!(!with_suffix(&new, ".partial").exists());
+
+        // Writes now land in the new file, and the old one is left alone
+        add_track(&clone, "/music/b.mp3");
+        assert_eq!(track_count(&db), 2);
+        assert_eq!(track_count(&Database::new(&old).unwrap()), 1);
+        let volume = db
+            .with_conn(|conn| settings::get_setting(conn, "volume"))
+            .unwrap();
+        assert_eq!(volume.as_deref(), Some("40"));
+    }
+
+    #[test]
+    fn test_relocate_refuses_existing_target_unless_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let other_path = dir.path().join("other.db");
+        let other = Database::new(&other_path).unwrap();
+        add_track(&other, "/music/x.mp3");
+        add_track(&other, "/music/y.mp3");
+        drop(other);
+
+        let db = Database::new(dir.path().join("mt.db")).unwrap();
+        add_track(&db, "/music/a.mp3");
+
+        let err = db.relocate(&other_path, false, |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(track_count(&db), 1);
+
+        let result = db.relocate(&other_path, true, |_| Ok(())).unwrap();
+        assert!(!result.copied);
+        assert_eq!(track_count(&db), 2);
+
+        // Moving onto the file in use is refused even with `use_existing`
+        let err = db.relocate(&other_path, true, |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("already at"));
+    }
+
+    #[test]
+    fn test_failed_relocation_keeps_current_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("mt.db");
+        let new = dir.path().join("new/mt.db");
+        let db = Database::new(&old).unwrap();
+        add_track(&db, "/music/a.mp3");
+
+        let err = db
+            .relocate(&new, false, |_| {
+                Err(DbError::Constraint("disk went away".to_string()))
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("disk went away"));
+        assert!(!new.exists());
+        assert!(!with_suffix(&new, ".partial").exists());
+        assert_eq!(db.path().as_deref(), Some(old.as_path()));
+        add_track(&db, "/music/b.mp3");
+        assert_eq!(track_count(&db), 2);
+
+        let err = db
+            .relocate(Path::new("relative.db"), false, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("absolute"));
+
+        let memory = Database::new_in_memory().unwrap();
+        assert!(memory.relocate(&new, false, |_| Ok(())).is_err());
+    }
+
+    #[test]
+    fn test_saved_location_round_trip() {
+        let data = tempfile::tempdir().unwrap();
+        let default = data.path().join(DEFAULT_DB_FILE);
+        assert_eq!(resolve_db_path(data.path()), default);
+
+        let moved = data.path().join("moved.db");
+        save_db_location(data.path(), &moved).unwrap();
+        // Not there yet, so the default is used
+        assert_eq!(resolve_db_path(data.path()), default);
+
+        fs::write(&moved, b"").unwrap();
+        assert_eq!(resolve_db_path(data.path()), moved);
+    }
+}
@@ -43,14 +43,24 @@ pub const CREATE_TABLES: &[(&str, &str)] = &[
             value TEXT
         )",
     ),
+    (
+        "favorite_lists",
+        "CREATE TABLE IF NOT EXISTS favorite_lists (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+    ),
     (
         "favorites",
         "CREATE TABLE IF NOT EXISTS favorites (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
+            list_id INTEGER NOT NULL DEFAULT 1,
             track_id INTEGER NOT NULL,
             timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
             FOREIGN KEY (track_id) REFERENCES library(id),
-            UNIQUE(track_id)
+            FOREIGN KEY (list_id) REFERENCES favorite_lists(id) ON DELETE CASCADE,
+            UNIQUE(list_id, track_id)
         )",
     ),
     (
@@ -72,7 +82,9 @@ pub const CREATE_TABLES: &[(&str, &str)] = &[
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             name TEXT NOT NULL UNIQUE,
             position INTEGER DEFAULT 0,
-            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            parent_id INTEGER,
+            is_folder INTEGER DEFAULT 0
         )",
     ),
     (
@@ -123,6 +135,130 @@ pub const CREATE_TABLES: &[(&str, &str)] = &[
             original_order_json TEXT
         )",
     ),
+    (
+        "play_history",
+        "CREATE TABLE IF NOT EXISTS play_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            track_id INTEGER NOT NULL,
+            played_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (track_id) REFERENCES library(id) ON DELETE CASCADE
+        )",
+    ),
+    (
+        "track_chapters",
+        "CREATE TABLE IF NOT EXISTS track_chapters (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            track_id INTEGER NOT NULL,
+            start_ms INTEGER NOT NULL,
+            title TEXT,
+            FOREIGN KEY (track_id) REFERENCES library(id) ON DELETE CASCADE
+        )",
+    ),
+    (
+        "audio_profile_rules",
+        "CREATE TABLE IF NOT EXISTS audio_profile_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            position INTEGER NOT NULL,
+            condition TEXT NOT NULL,
+            value TEXT NOT NULL,
+            eq_preset TEXT NOT NULL DEFAULT 'flat',
+            normalization TEXT NOT NULL DEFAULT 'off',
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        )",
+    ),
+    (
+        "bookmarks",
+        "CREATE TABLE IF NOT EXISTS bookmarks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            track_id INTEGER NOT NULL,
+            position_ms INTEGER NOT NULL,
+            label TEXT NOT NULL DEFAULT '',
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (track_id) REFERENCES library(id) ON DELETE CASCADE
+        )",
+    ),
+    (
+        "meta",
+        "CREATE TABLE IF NOT EXISTS meta (
+            key TEXT PRIMARY KEY,
+            value INTEGER NOT NULL DEFAULT 0
+        )",
+    ),
+    (
+        "library_changes",
+        "CREATE TABLE IF NOT EXISTS library_changes (
+            track_id INTEGER PRIMARY KEY,
+            seq INTEGER NOT NULL,
+            deleted INTEGER NOT NULL DEFAULT 0
+        )",
+    ),
+    (
+        "artwork_misses",
+        "CREATE TABLE IF NOT EXISTS artwork_misses (
+            album_key TEXT PRIMARY KEY,
+            missed_at INTEGER NOT NULL,
+            retry_after INTEGER NOT NULL
+        )",
+    ),
+    (
+        "album_overrides",
+        "CREATE TABLE IF NOT EXISTS album_overrides (
+            album_key TEXT PRIMARY KEY,
+            album TEXT,
+            album_artist TEXT,
+            year TEXT,
+            artwork_path TEXT
+        )",
+    ),
+    (
+        "tags",
+        "CREATE TABLE IF NOT EXISTS tags (
+            track_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (track_id, tag),
+            FOREIGN KEY (track_id) REFERENCES library(id) ON DELETE CASCADE
+        )",
+    ),
+    (
+        "track_features",
+        "CREATE TABLE IF NOT EXISTS track_features (
+            track_id INTEGER PRIMARY KEY,
+            bpm REAL,
+            musical_key INTEGER,
+            loudness_db REAL NOT NULL,
+            centroid_hz REAL NOT NULL,
+            analyzed_at INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+            FOREIGN KEY (track_id) REFERENCES library(id) ON DELETE CASCADE
+        )",
+    ),
+    (
+        "library_stats",
+        "CREATE TABLE IF NOT EXISTS library_stats (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            change_seq INTEGER NOT NULL,
+            total_tracks INTEGER NOT NULL,
+            total_duration INTEGER NOT NULL,
+            total_size INTEGER NOT NULL,
+            total_artists INTEGER NOT NULL,
+            total_albums INTEGER NOT NULL,
+            missing_tracks INTEGER NOT NULL,
+            tracks_without_artwork INTEGER NOT NULL,
+            tracks_missing_fingerprints INTEGER NOT NULL,
+            potential_duplicates INTEGER NOT NULL
+        )",
+    ),
+    (
+        "queue_snapshots",
+        "CREATE TABLE IF NOT EXISTS queue_snapshots (
+            filepath TEXT PRIMARY KEY,
+            track_id INTEGER,
+            title TEXT,
+            artist TEXT,
+            album TEXT,
+            duration REAL,
+            captured_at INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        )",
+    ),
 ];
 
 /// Create all database tables
@@ -252,6 +388,205 @@ pub fn run_migrations(conn: &Connection) -> DbResult<()> {
         println!("[migration] content_hash index created successfully");
     }
 
+    // Migration: Add has_artwork column for library health stats (NULL = not yet checked)
+    let library_columns = get_table_columns(conn, "library")?;
+    if !library_columns.contains(&"has_artwork".to_string()) {
+        println!("[migration] Adding has_artwork column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN has_artwork INTEGER", [])?;
+        println!("[migration] has_artwork column added successfully");
+    }
+
+    // Migration: Add genre column for genre-based stats
+    if !library_columns.contains(&"genre".to_string()) {
+        println!("[migration] Adding genre column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN genre TEXT", [])?;
+        println!("[migration] genre column added successfully");
+    }
+
+    // Migration: Add last_verify_failed column for decode checks (NULL = not yet checked)
+    if !library_columns.contains(&"last_verify_failed".to_string()) {
+        println!("[migration] Adding last_verify_failed column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN last_verify_failed INTEGER", [])?;
+        println!("[migration] last_verify_failed column added successfully");
+    }
+
+    // Migration: Add rating column for imported star ratings (NULL = unrated, 1-5 stars)
+    if !library_columns.contains(&"rating".to_string()) {
+        println!("[migration] Adding rating column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN rating INTEGER", [])?;
+        println!("[migration] rating column added successfully");
+    }
+
+    // Migration: Add disc_number and disc_total columns for multi-disc album order
+    if !library_columns.contains(&"disc_number".to_string()) {
+        println!("[migration] Adding disc_number column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN disc_number INTEGER", [])?;
+        println!("[migration] disc_number column added successfully");
+    }
+
+    if !library_columns.contains(&"disc_total".to_string()) {
+        println!("[migration] Adding disc_total column to library table...");
+        conn.execute("ALTER TABLE library ADD COLUMN disc_total INTEGER", [])?;
+        println!("[migration] disc_total column added successfully");
+    }
+
+    // Migration: Add shuffle_seed column for deterministic shuffle order
+    let queue_state_columns = get_table_columns(conn, "queue_state")?;
+    if !queue_state_columns.contains(&"shuffle_seed".to_string()) {
+        println!("[migration] Adding shuffle_seed column to queue_state table...");
+        conn.execute("ALTER TABLE queue_state ADD COLUMN shuffle_seed INTEGER", [])?;
+        println!("[migration] shuffle_seed column added successfully");
+    }
+
+    // Migration: Add consume_enabled column for consume mode
+    if !queue_state_columns.contains(&"consume_enabled".to_string()) {
+        println!("[migration] Adding consume_enabled column to queue_state table...");
+        conn.execute(
+            "ALTER TABLE queue_state ADD COLUMN consume_enabled INTEGER DEFAULT 0",
+            [],
+        )?;
+        println!("[migration] consume_enabled column added successfully");
+    }
+
+    // Migration: Add shuffle_mode column for album shuffle
+    if !queue_state_columns.contains(&"shuffle_mode".to_string()) {
+        println!("[migration] Adding shuffle_mode column to queue_state table...");
+        conn.execute(
+            "ALTER TABLE queue_state ADD COLUMN shuffle_mode TEXT DEFAULT 'tracks'",
+            [],
+        )?;
+        println!("[migration] shuffle_mode column added successfully");
+    }
+
+    // Migration: Named favorite lists. Existing favorites move into the default
+    // "Favorites" list; the table is rebuilt because the UNIQUE(track_id)
+    // constraint must become UNIQUE(list_id, track_id).
+    conn.execute(
+        "INSERT OR IGNORE INTO favorite_lists (id, name) VALUES (1, 'Favorites')",
+        [],
+    )?;
+    let favorites_columns = get_table_columns(conn, "favorites")?;
+    if !favorites_columns.contains(&"list_id".to_string()) {
+        println!("[migration] Moving favorites into the default favorite list...");
+        conn.execute_batch(
+            "CREATE TABLE favorites_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                list_id INTEGER NOT NULL DEFAULT 1,
+                track_id INTEGER NOT NULL,
+                timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (track_id) REFERENCES library(id),
+                FOREIGN KEY (list_id) REFERENCES favorite_lists(id) ON DELETE CASCADE,
+                UNIQUE(list_id, track_id)
+            );
+            -- Favorites of deleted tracks would fail the new foreign key
+            INSERT INTO favorites_new (id, list_id, track_id, timestamp)
+                SELECT id, 1, track_id, timestamp FROM favorites
+                WHERE track_id IN (SELECT id FROM library);
+            DROP TABLE favorites;
+            ALTER TABLE favorites_new RENAME TO favorites;",
+        )?;
+        println!("[migration] favorites migrated successfully");
+    }
+
+    // Migration: Play history. Seed an empty history with each track's last
+    // play so recently played isn't blank right after upgrading.
+    if !index_exists(conn, "idx_play_history_played_at")? {
+        println!("[migration] Adding play history index...");
+        conn.execute(
+            "CREATE INDEX idx_play_history_played_at ON play_history(played_at)",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO play_history (track_id, played_at)
+             SELECT id, last_played FROM library
+             WHERE last_played IS NOT NULL
+               AND NOT EXISTS (SELECT 1 FROM play_history)",
+            [],
+        )?;
+        println!("[migration] play history index added successfully");
+    }
+
+    // Migration: Per-track chapter markers
+    if !index_exists(conn, "idx_track_chapters_track_id")? {
+        println!("[migration] Adding track chapters index...");
+        conn.execute(
+            "CREATE INDEX idx_track_chapters_track_id ON track_chapters(track_id, start_ms)",
+            [],
+        )?;
+        println!("[migration] track chapters index added successfully");
+    }
+
+    // Migration: Bookmarks within a track
+    if !index_exists(conn, "idx_bookmarks_track_id")? {
+        println!("[migration] Adding bookmarks index...");
+        conn.execute(
+            "CREATE INDEX idx_bookmarks_track_id ON bookmarks(track_id, position_ms)",
+            [],
+        )?;
+        println!("[migration] bookmarks index added successfully");
+    }
+
+    // Migration: Playlist folders
+    let playlist_columns = get_table_columns(conn, "playlists")?;
+    if !playlist_columns.contains(&"parent_id".to_string()) {
+        println!("[migration] Adding playlist folder columns...");
+        conn.execute("ALTER TABLE playlists ADD COLUMN parent_id INTEGER", [])?;
+        conn.execute(
+            "ALTER TABLE playlists ADD COLUMN is_folder INTEGER DEFAULT 0",
+            [],
+        )?;
+        println!("[migration] playlist folder columns added successfully");
+    }
+
+    // Migration: Library change sequence. Triggers bump the sequence in the
+    // same transaction as each library write and record the latest sequence
+    // per track, so clients can fetch only what changed since they last looked.
+    conn.execute(
+        "INSERT OR IGNORE INTO meta (key, value) VALUES ('library_change_seq', 0)",
+        [],
+    )?;
+    if !index_exists(conn, "idx_library_changes_seq")? {
+        println!("[migration] Adding library change tracking...");
+        conn.execute_batch(
+            "CREATE INDEX idx_library_changes_seq ON library_changes(seq);
+            CREATE TRIGGER IF NOT EXISTS library_change_insert AFTER INSERT ON library
+            BEGIN
+                UPDATE meta SET value = value + 1 WHERE key = 'library_change_seq';
+                INSERT OR REPLACE INTO library_changes (track_id, seq, deleted)
+                VALUES (NEW.id, (SELECT value FROM meta WHERE key = 'library_change_seq'), 0);
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_change_update AFTER UPDATE ON library
+            BEGIN
+                UPDATE meta SET value = value + 1 WHERE key = 'library_change_seq';
+                INSERT OR REPLACE INTO library_changes (track_id, seq, deleted)
+                VALUES (NEW.id, (SELECT value FROM meta WHERE key = 'library_change_seq'), 0);
+            END;
+            CREATE TRIGGER IF NOT EXISTS library_change_delete AFTER DELETE ON library
+            BEGIN
+                UPDATE meta SET value = value + 1 WHERE key = 'library_change_seq';
+                INSERT OR REPLACE INTO library_changes (track_id, seq, deleted)
+                VALUES (OLD.id, (SELECT value FROM meta WHERE key = 'library_change_seq'), 1);
+            END;",
+        )?;
+        println!("[migration] library change tracking added successfully");
+    }
+
+    // Migration: Custom tags. The primary key covers lookups by track; this
+    // index covers filtering the library by tag.
+    if !index_exists(conn, "idx_tags_tag_track_id")? {
+        println!("[migration] Adding tags index...");
+        conn.execute("CREATE INDEX idx_tags_tag_track_id ON tags(tag, track_id)", [])?;
+        println!("[migration] tags index added successfully");
+    }
+
+    // Migration: Where each queue entry was queued from
+    let queue_columns = get_table_columns(conn, "queue")?;
+    if !queue_columns.contains(&"source".to_string()) {
+        println!("[migration] Adding source column to queue table...");
+        conn.execute("ALTER TABLE queue ADD COLUMN source TEXT", [])?;
+        println!("[migration] source column added successfully");
+    }
+
     Ok(())
 }
 
@@ -284,7 +619,7 @@ mod tests {
         let conn = Connection::open_in_memory().unwrap();
         create_tables(&conn).expect("Failed to create tables");
 
-        // Verify all 10 tables exist
+        // Verify all tables exist
         let mut stmt = conn
             .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
             .unwrap();
@@ -294,17 +629,30 @@ mod tests {
             .filter_map(|r| r.ok())
             .collect();
 
-        assert_eq!(tables.len(), 10);
+        assert_eq!(tables.len(), 23);
         assert!(tables.contains(&"library".to_string()));
         assert!(tables.contains(&"queue".to_string()));
         assert!(tables.contains(&"queue_state".to_string()));
         assert!(tables.contains(&"playlists".to_string()));
         assert!(tables.contains(&"playlist_items".to_string()));
         assert!(tables.contains(&"favorites".to_string()));
+        assert!(tables.contains(&"favorite_lists".to_string()));
         assert!(tables.contains(&"settings".to_string()));
         assert!(tables.contains(&"scrobble_queue".to_string()));
         assert!(tables.contains(&"watched_folders".to_string()));
         assert!(tables.contains(&"lyrics_cache".to_string()));
+        assert!(tables.contains(&"play_history".to_string()));
+        assert!(tables.contains(&"track_chapters".to_string()));
+        assert!(tables.contains(&"audio_profile_rules".to_string()));
+        assert!(tables.contains(&"bookmarks".to_string()));
+        assert!(tables.contains(&"meta".to_string()));
+        assert!(tables.contains(&"library_changes".to_string()));
+        assert!(tables.contains(&"artwork_misses".to_string()));
+        assert!(tables.contains(&"tags".to_string()));
+        assert!(tables.contains(&"track_features".to_string()));
+        assert!(tables.contains(&"queue_snapshots".to_string()));
+        assert!(tables.contains(&"library_stats".to_string()));
+        assert!(tables.contains(&"album_overrides".to_string()));
     }
 
     #[test]
@@ -324,5 +672,105 @@ mod tests {
         assert!(columns.contains(&"last_seen_at".to_string()));
         assert!(columns.contains(&"file_inode".to_string()));
         assert!(columns.contains(&"content_hash".to_string()));
+        assert!(columns.contains(&"has_artwork".to_string()));
+        assert!(columns.contains(&"genre".to_string()));
+        assert!(columns.contains(&"last_verify_failed".to_string()));
+        assert!(columns.contains(&"rating".to_string()));
+        assert!(columns.contains(&"disc_number".to_string()));
+        assert!(columns.contains(&"disc_total".to_string()));
+
+        let playlist_columns = get_table_columns(&conn, "playlists").unwrap();
+        assert!(playlist_columns.contains(&"parent_id".to_string()));
+        assert!(playlist_columns.contains(&"is_folder".to_string()));
+
+        let queue_columns = get_table_columns(&conn, "queue").unwrap();
+        assert!(queue_columns.contains(&"source".to_string()));
+
+        let queue_state_columns = get_table_columns(&conn, "queue_state").unwrap();
+        assert!(queue_state_columns.contains(&"shuffle_seed".to_string()));
+        assert!(queue_state_columns.contains(&"consume_enabled".to_string()));
+        assert!(queue_state_columns.contains(&"shuffle_mode".to_string()));
+
+        let favorites_columns = get_table_columns(&conn, "favorites").unwrap();
+        assert!(favorites_columns.contains(&"list_id".to_string()));
+
+        assert!(index_exists(&conn, "idx_play_history_played_at").unwrap());
+        assert!(index_exists(&conn, "idx_track_chapters_track_id").unwrap());
+        assert!(index_exists(&conn, "idx_bookmarks_track_id").unwrap());
+    }
+
+    #[test]
+    fn test_migrate_single_favorites_into_default_list() {
+        let conn = Connection::open_in_memory().unwrap();
+        // Pre-list favorites table
+        conn.execute_batch(
+            "CREATE TABLE favorites (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                track_id INTEGER NOT NULL,
+                timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(track_id)
+            );
+            INSERT INTO favorites (track_id, timestamp) VALUES (7, '2024-01-01 00:00:00');
+            INSERT INTO favorites (track_id, timestamp) VALUES (9, '2024-01-02 00:00:00');
+            INSERT INTO favorites (track_id, timestamp) VALUES (12, '2024-01-03 00:00:00');",
+        )
+        .unwrap();
+        create_tables(&conn).unwrap();
+        // Track 12 was deleted without its favorite being removed
+        conn.execute_batch(
+            "INSERT INTO library (id, filepath) VALUES (7, '/music/7.mp3');
+            INSERT INTO library (id, filepath) VALUES (9, '/music/9.mp3');",
+        )
+        .unwrap();
+        run_migrations(&conn).unwrap();
+
+        let name: String = conn
+            .query_row("SELECT name FROM favorite_lists WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(name, "Favorites");
+
+        let rows: Vec<(i64, i64, String)> = conn
+            .prepare("SELECT list_id, track_id, timestamp FROM favorites ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, 7, "2024-01-01 00:00:00".to_string()),
+                (1, 9, "2024-01-02 00:00:00".to_string()),
+            ]
+        );
+
+        // The same track may now be favorited in another list
+        conn.execute("INSERT INTO favorite_lists (id, name) VALUES (2, 'Workout')", [])
+            .unwrap();
+        conn.execute("INSERT INTO favorites (list_id, track_id) VALUES (2, 7)", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_migrate_seeds_play_history_from_last_played() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO library (id, filepath, last_played) VALUES (1, '/a.mp3', '2024-03-01 10:00:00');
+             INSERT INTO library (id, filepath) VALUES (2, '/b.mp3');",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let rows: Vec<(i64, String)> = conn
+            .prepare("SELECT track_id, played_at FROM play_history")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .filter_map(|r| r.ok())
+            .collect();
+        assert_eq!(rows, vec![(1, "2024-03-01 10:00:00".to_string())]);
     }
 }
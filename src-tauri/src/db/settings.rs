@@ -63,6 +63,13 @@ pub fn get_all_settings(conn: &Connection) -> DbResult<HashMap<String, JsonValue
     Ok(settings)
 }
 
+/// Key under which `key` is stored for one output device
+///
+/// The device id goes last, so ids containing any character stay unambiguous.
+pub fn device_setting_key(key: &str, device_id: &str) -> String {
+    format!("{}@device:{}", key, device_id)
+}
+
 /// Get a single setting value
 pub fn get_setting(conn: &Connection, key: &str) -> DbResult<Option<String>> {
     match conn.query_row(
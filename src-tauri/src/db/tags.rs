@@ -0,0 +1,224 @@
+//! Custom track tag database operations.
+//!
+//! Tags are free-form labels such as "roadtrip" or "focus", kept apart from
+//! the genre read from the file. They are stored trimmed and lowercased, so
+//! "Focus " and "focus" are the same tag. They are deleted with the track
+//! through the foreign key cascade.
+
+use rusqlite::{Connection, params};
+
+use crate::db::{DbError, DbResult, TagCount};
+
+/// Normalize a tag for storage and matching, `None` if it is blank
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Normalize a list of tags, dropping blanks and duplicates
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = tags.iter().filter_map(|t| normalize_tag(t)).collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Tag a track. Adding a tag it already has is a no-op.
+///
+/// Returns the track's tags afterwards, or `None` if the track doesn't exist.
+pub fn add_tag(conn: &Connection, track_id: i64, tag: &str) -> DbResult<Option<Vec<String>>> {
+    let tag =
+        normalize_tag(tag).ok_or_else(|| DbError::Constraint("Tag cannot be empty".to_string()))?;
+
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM library WHERE id = ?)",
+        [track_id],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        return Ok(None);
+    }
+
+    conn.execute(
+        "INSERT OR IGNORE INTO tags (track_id, tag) VALUES (?, ?)",
+        params![track_id, tag],
+    )?;
+    get_tags(conn, track_id).map(Some)
+}
+
+/// Remove a tag from a track. Returns whether the track had it.
+pub fn remove_tag(conn: &Connection, track_id: i64, tag: &str) -> DbResult<bool> {
+    let Some(tag) = normalize_tag(tag) else {
+        return Ok(false);
+    };
+    let removed = conn.execute(
+        "DELETE FROM tags WHERE track_id = ? AND tag = ?",
+        params![track_id, tag],
+    )?;
+    Ok(removed > 0)
+}
+
+/// Get the tags of a track, alphabetically
+pub fn get_tags(conn: &Connection, track_id: i64) -> DbResult<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT tag FROM tags WHERE track_id = ? ORDER BY tag")?;
+    let tags = stmt
+        .query_map([track_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(tags)
+}
+
+/// Get every tag in use with its track count, alphabetically
+pub fn get_all_tags(conn: &Connection) -> DbResult<Vec<TagCount>> {
+    let mut stmt = conn.prepare(
+        "SELECT tag, COUNT(*) FROM tags
+         GROUP BY tag
+         ORDER BY tag",
+    )?;
+    let tags = stmt
+        .query_map([], |row| {
+            Ok(TagCount {
+                tag: row.get(0)?,
+                track_count: row.get(1)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{
+        LibraryQuery, add_track, delete_track, delete_tracks_bulk, get_all_tracks,
+        merge_duplicate_tracks,
+    };
+    use crate::db::{TagMatch, TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn tagged_track(conn: &Connection, filepath: &str, tags: &[&str]) -> i64 {
+        let id = add_track(conn, filepath, &TrackMetadata::default()).unwrap();
+        for tag in tags {
+            add_tag(conn, id, tag).unwrap();
+        }
+        id
+    }
+
+    fn filter(conn: &Connection, tags: &[&str], tag_match: TagMatch) -> Vec<String> {
+        let query = LibraryQuery {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            tag_match,
+            limit: -1,
+            ..Default::default()
+        };
+        let mut paths: Vec<String> = get_all_tracks(conn, &query)
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|t| t.filepath)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_tags_are_normalized() {
+        let conn = setup_test_db();
+        let id = add_track(&conn, "/music/a.mp3", &TrackMetadata::default()).unwrap();
+
+        add_tag(&conn, id, "  Focus ").unwrap();
+        let tags = add_tag(&conn, id, "focus").unwrap().unwrap();
+        assert_eq!(tags, ["focus"]);
+        assert!(add_tag(&conn, id, "   ").is_err());
+        assert!(add_tag(&conn, 9999, "focus").unwrap().is_none());
+
+        assert!(remove_tag(&conn, id, "FOCUS").unwrap());
+        assert!(!remove_tag(&conn, id, "focus").unwrap());
+        assert_eq!(
+            normalize_tags(&[
+                "B".to_string(),
+                " a".to_string(),
+                "b ".to_string(),
+                "".to_string()
+            ]),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn test_filter_matches_any_or_all_tags() {
+        let conn = setup_test_db();
+        tagged_track(&conn, "/music/both.mp3", &["roadtrip", "focus"]);
+        tagged_track(&conn, "/music/road.mp3", &["roadtrip"]);
+        tagged_track(&conn, "/music/focus.mp3", &["focus", "night"]);
+        tagged_track(&conn, "/music/none.mp3", &[]);
+
+        assert_eq!(
+            filter(&conn, &["Roadtrip", "focus"], TagMatch::Any),
+            ["/music/both.mp3", "/music/focus.mp3", "/music/road.mp3"]
+        );
+        assert_eq!(
+            filter(&conn, &["roadtrip", "FOCUS"], TagMatch::All),
+            ["/music/both.mp3"]
+        );
+        // A repeated tag doesn't make "all" impossible to satisfy
+        assert_eq!(
+            filter(&conn, &["night", "Night "], TagMatch::All),
+            ["/music/focus.mp3"]
+        );
+        // Blank tags mean no tag filter at all
+        assert_eq!(filter(&conn, &[" "], TagMatch::All).len(), 4);
+        assert!(filter(&conn, &["unused"], TagMatch::Any).is_empty());
+    }
+
+    #[test]
+    fn test_get_all_tags_counts_tracks() {
+        let conn = setup_test_db();
+        tagged_track(&conn, "/music/a.mp3", &["roadtrip", "focus"]);
+        tagged_track(&conn, "/music/b.mp3", &["roadtrip"]);
+
+        let counts: Vec<(String, i64)> = get_all_tags(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.tag, t.track_count))
+            .collect();
+        assert_eq!(
+            counts,
+            [("focus".to_string(), 1), ("roadtrip".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_deleting_track_cascades_tags() {
+        let conn = setup_test_db();
+        let a = tagged_track(&conn, "/music/a.mp3", &["focus"]);
+        let b = tagged_track(&conn, "/music/b.mp3", &["focus", "night"]);
+
+        delete_track(&conn, a).unwrap();
+        assert!(get_tags(&conn, a).unwrap().is_empty());
+        assert_eq!(get_all_tags(&conn).unwrap().len(), 2);
+
+        delete_tracks_bulk(&conn, &["/music/b.mp3".to_string()]).unwrap();
+        assert!(get_tags(&conn, b).unwrap().is_empty());
+        assert!(get_all_tags(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_duplicates_keeps_tags() {
+        let conn = setup_test_db();
+        let keep = tagged_track(&conn, "/music/keep.mp3", &["focus"]);
+        let dup = tagged_track(&conn, "/music/dup.mp3", &["focus", "roadtrip"]);
+
+        merge_duplicate_tracks(&conn, keep, dup).unwrap();
+
+        assert_eq!(get_tags(&conn, keep).unwrap(), ["focus", "roadtrip"]);
+    }
+}
@@ -0,0 +1,305 @@
+//! Structured diagnostics for bug reports.
+//!
+//! Gathers app, database, and subsystem status into a single struct that the
+//! UI can display and `export_diagnostics` writes out as text. Every field is
+//! collected independently so a subsystem that failed to initialize only
+//! degrades its own fields instead of failing the whole report.
+
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use crate::db::{Database, PoolStatus, library, playlists, scrobble, settings};
+use crate::logging::{self, Logging};
+use crate::media_keys::MediaKeyManager;
+use crate::scanner::artwork_cache::ArtworkCache;
+use crate::watcher::WatcherManager;
+
+/// Text shown in the export for fields that could not be collected
+const UNAVAILABLE: &str = "unavailable";
+
+/// Most of the current log file included in an export
+const MAX_EXPORTED_LOG_BYTES: u64 = 512 * 1024;
+
+/// App version, build, and platform
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub version: String,
+    pub build: String,
+    pub platform: String,
+}
+
+impl AppInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            build: option_env!("MT_BUILD_ID").unwrap_or("dev").to_string(),
+            platform: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+}
+
+/// Database file and content statistics
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DatabaseDiagnostics {
+    pub file_size_bytes: Option<i64>,
+    pub track_count: Option<i64>,
+    pub playlist_count: Option<i64>,
+    pub pool: Option<PoolStatus>,
+}
+
+impl DatabaseDiagnostics {
+    pub fn collect(db: &Database) -> Self {
+        Self {
+            file_size_bytes: db
+                .with_conn(|conn| {
+                    Ok(conn.query_row(
+                        "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+                        [],
+                        |row| row.get(0),
+                    )?)
+                })
+                .ok(),
+            track_count: db
+                .with_conn(|conn| Ok(library::get_library_stats(conn)?.total_tracks))
+                .ok(),
+            playlist_count: db
+                .with_conn(|conn| Ok(playlists::get_playlists(conn)?.len() as i64))
+                .ok(),
+            pool: Some(db.pool_status()),
+        }
+    }
+}
+
+/// Last.fm connection and scrobble queue status
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LastfmDiagnostics {
+    pub authenticated: Option<bool>,
+    pub queued_scrobbles: Option<i64>,
+}
+
+impl LastfmDiagnostics {
+    pub fn collect(db: &Database) -> Self {
+        Self {
+            authenticated: db
+                .with_conn(|conn| settings::get_setting(conn, "lastfm_session_key"))
+                .ok()
+                .map(|key| key.is_some_and(|k| !k.is_empty())),
+            queued_scrobbles: db.with_conn(scrobble::get_scrobble_queue_count).ok(),
+        }
+    }
+}
+
+/// Full diagnostics report
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    #[serde(flatten)]
+    pub app: AppInfo,
+    pub timestamp: String,
+    pub rust_version: String,
+    pub working_directory: Option<String>,
+    pub database: DatabaseDiagnostics,
+    pub artwork_cache_size: Option<usize>,
+    pub active_watchers: Option<usize>,
+    pub media_keys_available: bool,
+    pub lastfm: LastfmDiagnostics,
+    /// Log file currently written to
+    pub log_file: Option<String>,
+}
+
+impl Diagnostics {
+    /// Collect diagnostics from whatever subsystems are managed by the app
+    pub fn collect(app: &AppHandle) -> Self {
+        let db = app.try_state::<Database>();
+
+        Self {
+            app: AppInfo::current(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            rust_version: env!("CARGO_PKG_RUST_VERSION").to_string(),
+            working_directory: std::env::current_dir()
+                .ok()
+                .map(|cwd| cwd.display().to_string()),
+            database: db
+                .as_ref()
+                .map(|db| DatabaseDiagnostics::collect(db))
+                .unwrap_or_default(),
+            artwork_cache_size: app.try_state::<ArtworkCache>().map(|cache| cache.len()),
+            active_watchers: app
+                .try_state::<WatcherManager>()
+                .map(|watcher| watcher.active_watcher_count()),
+            media_keys_available: app.try_state::<MediaKeyManager>().is_some(),
+            lastfm: db
+                .as_ref()
+                .map(|db| LastfmDiagnostics::collect(db))
+                .unwrap_or_default(),
+            log_file: app
+                .try_state::<Logging>()
+                .and_then(|logging| logging.current_file())
+                .map(|path| path.display().to_string()),
+        }
+    }
+
+    /// Render the report as plain text for export
+    pub fn to_text(&self) -> String {
+        let mut content = String::new();
+
+        content.push_str("=== mt Diagnostics ===\n\n");
+        content.push_str(&format!("Version: {}\n", self.app.version));
+        content.push_str(&format!("Build: {}\n", self.app.build));
+        content.push_str(&format!("Platform: {}\n", self.app.platform));
+        content.push_str(&format!("Timestamp: {}\n", self.timestamp));
+
+        content.push_str("\n=== Environment ===\n\n");
+        content.push_str(&format!("Rust version: {}\n", self.rust_version));
+        content.push_str(&format!(
+            "Working directory: {}\n",
+            display(self.working_directory.as_ref())
+        ));
+
+        content.push_str("\n=== Database ===\n\n");
+        content.push_str(&format!(
+            "File size: {}\n",
+            display(
+                self.database
+                    .file_size_bytes
+                    .map(|b| format!("{} bytes", b))
+            )
+        ));
+        content.push_str(&format!("Tracks: {}\n", display(self.database.track_count)));
+        content.push_str(&format!(
+            "Playlists: {}\n",
+            display(self.database.playlist_count)
+        ));
+        content.push_str(&format!(
+            "Pool: {}\n",
+            display(self.database.pool.map(|p| format!(
+                "{} connections ({} idle), max {}",
+                p.connections, p.idle_connections, p.max_size
+            )))
+        ));
+
+        content.push_str("\n=== Subsystems ===\n\n");
+        content.push_str(&format!(
+            "Artwork cache entries: {}\n",
+            display(self.artwork_cache_size)
+        ));
+        content.push_str(&format!(
+            "Active watchers: {}\n",
+            display(self.active_watchers)
+        ));
+        content.push_str(&format!("Media keys: {}\n", self.media_keys_available));
+        content.push_str(&format!(
+            "Last.fm authenticated: {}\n",
+            display(self.lastfm.authenticated)
+        ));
+        content.push_str(&format!(
+            "Queued scrobbles: {}\n",
+            display(self.lastfm.queued_scrobbles)
+        ));
+        content.push_str(&format!("Log file: {}\n", display(self.log_file.as_ref())));
+
+        content
+    }
+
+    /// The text report followed by the end of the current log file
+    pub fn to_text_with_log(&self) -> String {
+        let mut content = self.to_text();
+        let Some(log_file) = &self.log_file else {
+            return content;
+        };
+
+        content.push_str("\n=== Log ===\n\n");
+        match logging::read_tail(Path::new(log_file), MAX_EXPORTED_LOG_BYTES) {
+            Ok(log) => content.push_str(&log),
+            Err(e) => content.push_str(&format!("Failed to read log file: {}\n", e)),
+        }
+        content
+    }
+}
+
+/// Format an optional value, falling back to the unavailable sentinel
+fn display<T: std::fmt::Display>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| UNAVAILABLE.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_diagnostics() -> Diagnostics {
+        Diagnostics {
+            app: AppInfo::current(),
+            timestamp: "2026-01-01T00:00:00+00:00".to_string(),
+            rust_version: "1.80".to_string(),
+            working_directory: None,
+            database: DatabaseDiagnostics::default(),
+            artwork_cache_size: None,
+            active_watchers: None,
+            media_keys_available: false,
+            lastfm: LastfmDiagnostics::default(),
+            log_file: None,
+        }
+    }
+
+    #[test]
+    fn test_database_diagnostics_collect() {
+        let db = Database::new_in_memory().unwrap();
+        let diagnostics = DatabaseDiagnostics::collect(&db);
+
+        assert_eq!(diagnostics.track_count, Some(0));
+        assert_eq!(diagnostics.playlist_count, Some(0));
+        assert!(diagnostics.file_size_bytes.unwrap() > 0);
+        assert_eq!(diagnostics.pool.unwrap().max_size, 1);
+    }
+
+    #[test]
+    fn test_lastfm_diagnostics_collect() {
+        let db = Database::new_in_memory().unwrap();
+        let diagnostics = LastfmDiagnostics::collect(&db);
+
+        assert_eq!(diagnostics.authenticated, Some(false));
+        assert_eq!(diagnostics.queued_scrobbles, Some(0));
+    }
+
+    #[test]
+    fn test_to_text_degrades_missing_fields() {
+        let text = empty_diagnostics().to_text();
+
+        assert!(text.contains("Tracks: unavailable"));
+        assert!(text.contains("Active watchers: unavailable"));
+        assert!(text.contains("Media keys: false"));
+        assert!(text.contains("Log file: unavailable"));
+        assert!(text.contains(&format!("Version: {}", env!("CARGO_PKG_VERSION"))));
+    }
+
+    #[test]
+    fn test_to_text_with_log_appends_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mt.2026-10-16.log");
+        std::fs::write(&path, "INFO mt_lib: Database initialized\n").unwrap();
+
+        assert_eq!(
+            empty_diagnostics().to_text_with_log(),
+            empty_diagnostics().to_text()
+        );
+
+        let diagnostics = Diagnostics {
+            log_file: Some(path.display().to_string()),
+            ..empty_diagnostics()
+        };
+        let text = diagnostics.to_text_with_log();
+        assert!(text.contains(&format!("Log file: {}", path.display())));
+        assert!(text.ends_with("=== Log ===\n\nINFO mt_lib: Database initialized\n"));
+    }
+
+    #[test]
+    fn test_serialization_flattens_app_info() {
+        let json = serde_json::to_value(empty_diagnostics()).unwrap();
+
+        assert!(json.get("version").is_some());
+        assert!(json.get("platform").is_some());
+        assert!(json["database"]["track_count"].is_null());
+    }
+}
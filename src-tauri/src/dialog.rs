@@ -1,13 +1,20 @@
 use tauri_plugin_dialog::DialogExt;
 use tokio::sync::oneshot;
 
+use crate::scanner::AUDIO_EXTENSIONS;
+
 #[tauri::command]
 pub async fn open_file_dialog(app: tauri::AppHandle) -> Result<Vec<String>, String> {
     let (tx, rx) = oneshot::channel();
     
+    let extensions: Vec<&str> = AUDIO_EXTENSIONS
+        .iter()
+        .map(|ext| ext.trim_start_matches('.'))
+        .collect();
+
     app.dialog()
         .file()
-        .add_filter("Audio Files", &["mp3", "m4a", "flac", "ogg", "wav", "aac", "wma", "opus"])
+        .add_filter("Audio Files", &extensions)
         .add_filter("All Files", &["*"])
         .set_title("Select audio files to add to your library")
         .pick_files(move |paths| {
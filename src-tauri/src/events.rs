@@ -7,6 +7,9 @@
 
 use serde::Serialize;
 
+use crate::db::favorites::DEFAULT_FAVORITE_LIST_ID;
+use crate::db::{changes, Database};
+
 // ============================================
 // Library Events
 // ============================================
@@ -18,6 +21,10 @@ pub struct LibraryUpdatedEvent {
     pub action: String,
     /// The IDs of affected tracks
     pub track_ids: Vec<i64>,
+    /// Library change sequence after the change, for `library_get_changes_since`
+    ///
+    /// Filled in from the database when the event is emitted if not set.
+    pub change_seq: Option<i64>,
 }
 
 impl LibraryUpdatedEvent {
@@ -27,6 +34,7 @@ impl LibraryUpdatedEvent {
         Self {
             action: "added".to_string(),
             track_ids,
+            change_seq: None,
         }
     }
 
@@ -34,6 +42,7 @@ impl LibraryUpdatedEvent {
         Self {
             action: "modified".to_string(),
             track_ids,
+            change_seq: None,
         }
     }
 
@@ -41,8 +50,14 @@ impl LibraryUpdatedEvent {
         Self {
             action: "deleted".to_string(),
             track_ids,
+            change_seq: None,
         }
     }
+
+    pub fn with_change_seq(mut self, change_seq: i64) -> Self {
+        self.change_seq = Some(change_seq);
+        self
+    }
 }
 
 /// Emitted during library scan to report progress
@@ -60,12 +75,61 @@ pub struct ScanProgressEvent {
     pub errors: u32,
     /// Current file/directory being processed
     pub current_path: Option<String>,
+    /// Files processed per second over the recent window
+    pub rate: Option<f64>,
+    /// Estimated seconds remaining (unknown until inventory finishes)
+    pub eta_secs: Option<u64>,
 }
 
 impl ScanProgressEvent {
     pub const EVENT_NAME: &'static str = "library:scan-progress";
 }
 
+/// Emitted while library tracks are checked for decode errors
+#[derive(Clone, Debug, Serialize)]
+pub struct VerifyProgressEvent {
+    /// Tracks being checked
+    pub total: usize,
+    /// Tracks checked so far
+    pub processed: usize,
+    /// Tracks that failed to decode so far
+    pub failed: usize,
+}
+
+impl VerifyProgressEvent {
+    pub const EVENT_NAME: &'static str = "library:verify-progress";
+}
+
+/// Emitted while every library file is checked for existence
+#[derive(Clone, Debug, Serialize)]
+pub struct ExistenceProgressEvent {
+    /// Tracks being checked
+    pub total: usize,
+    /// Tracks checked so far
+    pub processed: usize,
+    /// Files found missing so far
+    pub missing: usize,
+}
+
+impl ExistenceProgressEvent {
+    pub const EVENT_NAME: &'static str = "library:existence-progress";
+}
+
+/// Emitted while an iTunes / Music.app library is imported
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportProgressEvent {
+    /// Current stage: "tracks" or "playlists"
+    pub stage: String,
+    /// Items in the current stage
+    pub total: usize,
+    /// Items handled so far in the current stage
+    pub processed: usize,
+}
+
+impl ImportProgressEvent {
+    pub const EVENT_NAME: &'static str = "library:import-progress";
+}
+
 /// Emitted when a library scan completes
 #[derive(Clone, Debug, Serialize)]
 pub struct ScanCompleteEvent {
@@ -79,6 +143,10 @@ pub struct ScanCompleteEvent {
     pub errors: u32,
     /// Total scan duration in milliseconds
     pub duration_ms: u64,
+    /// Number of files checked during inventory
+    pub visited: u32,
+    /// True when nothing was added, modified, or deleted (no-op rescan)
+    pub up_to_date: bool,
 }
 
 impl ScanCompleteEvent {
@@ -92,7 +160,8 @@ impl ScanCompleteEvent {
 /// Emitted when the playback queue changes
 #[derive(Clone, Debug, Serialize)]
 pub struct QueueUpdatedEvent {
-    /// The type of change: "added", "removed", "cleared", "reordered", "shuffled"
+    /// The type of change: "added", "removed", "cleared", "reordered", "shuffled",
+    /// "refreshed", "replaced"
     pub action: String,
     /// Affected positions in the queue (if applicable)
     pub positions: Option<Vec<i64>>,
@@ -142,6 +211,38 @@ impl QueueUpdatedEvent {
             queue_length,
         }
     }
+
+    pub fn refreshed(queue_length: i64) -> Self {
+        Self {
+            action: "refreshed".to_string(),
+            positions: None,
+            queue_length,
+        }
+    }
+
+    /// The whole queue was replaced in one step
+    pub fn replaced(queue_length: i64) -> Self {
+        Self {
+            action: "replaced".to_string(),
+            positions: None,
+            queue_length,
+        }
+    }
+}
+
+/// Emitted when advancing the queue passes over entries whose files are missing
+#[derive(Clone, Debug, Serialize)]
+pub struct QueueSkippedMissingEvent {
+    /// Files skipped, in the order they were reached
+    pub filepaths: Vec<String>,
+}
+
+impl QueueSkippedMissingEvent {
+    pub const EVENT_NAME: &'static str = "queue:skipped-missing";
+
+    pub fn new(filepaths: Vec<String>) -> Self {
+        Self { filepaths }
+    }
 }
 
 /// Emitted when queue playback state changes (shuffle, loop, current index)
@@ -164,33 +265,117 @@ impl QueueStateChangedEvent {
     }
 }
 
+// ============================================
+// Playback Events
+// ============================================
+
+/// Emitted when playback changes state, and with the position while playing
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlaybackStateChangedEvent {
+    /// "loading", "playing", "paused", "stopped", or "ended"
+    pub state: String,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub volume: f32,
+}
+
+impl PlaybackStateChangedEvent {
+    pub const EVENT_NAME: &'static str = "playback:state-changed";
+}
+
+/// Emitted once when the current track finishes, so the queue can advance
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PlaybackTrackEndedEvent {
+    /// Library track that ended, if it was loaded from the library
+    pub track_id: Option<i64>,
+    pub path: Option<String>,
+}
+
+impl PlaybackTrackEndedEvent {
+    pub const EVENT_NAME: &'static str = "playback:track-ended";
+}
+
 // ============================================
 // Favorites Events
 // ============================================
 
-/// Emitted when a track is added to or removed from favorites
+/// Emitted when tracks are added to or removed from favorites
+///
+/// A batch change is reported once, with every affected track in `track_ids`.
 #[derive(Clone, Debug, Serialize)]
 pub struct FavoritesUpdatedEvent {
     /// The type of change: "added", "removed"
     pub action: String,
-    /// The track ID that was affected
+    /// The track ID that was affected, the first one for a batch
     pub track_id: i64,
+    /// All track IDs that were affected
+    pub track_ids: Vec<i64>,
+    /// The favorite list that changed
+    pub list_id: i64,
 }
 
 impl FavoritesUpdatedEvent {
     pub const EVENT_NAME: &'static str = "favorites:updated";
 
     pub fn added(track_id: i64) -> Self {
+        Self::batch("added", track_id, vec![track_id])
+    }
+
+    pub fn removed(track_id: i64) -> Self {
+        Self::batch("removed", track_id, vec![track_id])
+    }
+
+    /// Several tracks added at once, or `None` when `track_ids` is empty
+    pub fn added_many(track_ids: Vec<i64>) -> Option<Self> {
+        let first = track_ids.first().copied()?;
+        Some(Self::batch("added", first, track_ids))
+    }
+
+    /// Several tracks removed at once, or `None` when `track_ids` is empty
+    pub fn removed_many(track_ids: Vec<i64>) -> Option<Self> {
+        let first = track_ids.first().copied()?;
+        Some(Self::batch("removed", first, track_ids))
+    }
+
+    fn batch(action: &str, track_id: i64, track_ids: Vec<i64>) -> Self {
         Self {
-            action: "added".to_string(),
+            action: action.to_string(),
             track_id,
+            track_ids,
+            list_id: DEFAULT_FAVORITE_LIST_ID,
         }
     }
 
-    pub fn removed(track_id: i64) -> Self {
+    /// Attribute the change to a specific favorite list
+    pub fn in_list(mut self, list_id: i64) -> Self {
+        self.list_id = list_id;
+        self
+    }
+}
+
+/// Emitted when a favorite list is created or deleted
+#[derive(Clone, Debug, Serialize)]
+pub struct FavoriteListsUpdatedEvent {
+    /// The type of change: "created", "deleted"
+    pub action: String,
+    /// The favorite list that was affected
+    pub list_id: i64,
+}
+
+impl FavoriteListsUpdatedEvent {
+    pub const EVENT_NAME: &'static str = "favorites:lists-updated";
+
+    pub fn created(list_id: i64) -> Self {
         Self {
-            action: "removed".to_string(),
-            track_id,
+            action: "created".to_string(),
+            list_id,
+        }
+    }
+
+    pub fn deleted(list_id: i64) -> Self {
+        Self {
+            action: "deleted".to_string(),
+            list_id,
         }
     }
 }
@@ -202,7 +387,7 @@ impl FavoritesUpdatedEvent {
 /// Emitted when a playlist is created, modified, or deleted
 #[derive(Clone, Debug, Serialize)]
 pub struct PlaylistsUpdatedEvent {
-    /// The type of change: "created", "renamed", "deleted", "tracks_added", "tracks_removed", "reordered"
+    /// The type of change: "created", "renamed", "deleted", "moved", "tracks_added", "tracks_removed", "reordered"
     pub action: String,
     /// The playlist ID that was affected
     pub playlist_id: i64,
@@ -237,6 +422,14 @@ impl PlaylistsUpdatedEvent {
         }
     }
 
+    pub fn moved(playlist_id: i64) -> Self {
+        Self {
+            action: "moved".to_string(),
+            playlist_id,
+            track_ids: None,
+        }
+    }
+
     pub fn tracks_added(playlist_id: i64, track_ids: Vec<i64>) -> Self {
         Self {
             action: "tracks_added".to_string(),
@@ -395,16 +588,31 @@ pub trait EventEmitter {
     fn emit_library_updated(&self, event: LibraryUpdatedEvent) -> Result<(), String>;
     fn emit_scan_progress(&self, event: ScanProgressEvent) -> Result<(), String>;
     fn emit_scan_complete(&self, event: ScanCompleteEvent) -> Result<(), String>;
+    fn emit_verify_progress(&self, event: VerifyProgressEvent) -> Result<(), String>;
+    fn emit_existence_progress(&self, event: ExistenceProgressEvent) -> Result<(), String>;
+    fn emit_import_progress(&self, event: ImportProgressEvent) -> Result<(), String>;
     fn emit_queue_updated(&self, event: QueueUpdatedEvent) -> Result<(), String>;
+    fn emit_queue_skipped_missing(&self, event: QueueSkippedMissingEvent) -> Result<(), String>;
     fn emit_queue_state_changed(&self, event: QueueStateChangedEvent) -> Result<(), String>;
+    fn emit_playback_state_changed(&self, event: PlaybackStateChangedEvent)
+        -> Result<(), String>;
+    fn emit_playback_track_ended(&self, event: PlaybackTrackEndedEvent) -> Result<(), String>;
     fn emit_favorites_updated(&self, event: FavoritesUpdatedEvent) -> Result<(), String>;
+    fn emit_favorite_lists_updated(&self, event: FavoriteListsUpdatedEvent)
+        -> Result<(), String>;
     fn emit_playlists_updated(&self, event: PlaylistsUpdatedEvent) -> Result<(), String>;
     fn emit_settings_updated(&self, event: SettingsUpdatedEvent) -> Result<(), String>;
 }
 
 impl EventEmitter for tauri::AppHandle {
-    fn emit_library_updated(&self, event: LibraryUpdatedEvent) -> Result<(), String> {
-        use tauri::Emitter;
+    fn emit_library_updated(&self, mut event: LibraryUpdatedEvent) -> Result<(), String> {
+        use tauri::{Emitter, Manager};
+        if event.change_seq.is_none()
+            && let Some(db) = self.try_state::<Database>()
+            && let Ok(conn) = db.conn()
+        {
+            event.change_seq = changes::get_change_seq(&conn).ok();
+        }
         self.emit(LibraryUpdatedEvent::EVENT_NAME, event)
             .map_err(|e| e.to_string())
     }
@@ -421,24 +629,72 @@ impl EventEmitter for tauri::AppHandle {
             .map_err(|e| e.to_string())
     }
 
+    fn emit_verify_progress(&self, event: VerifyProgressEvent) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(VerifyProgressEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
+    fn emit_existence_progress(&self, event: ExistenceProgressEvent) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(ExistenceProgressEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
+    fn emit_import_progress(&self, event: ImportProgressEvent) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(ImportProgressEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
     fn emit_queue_updated(&self, event: QueueUpdatedEvent) -> Result<(), String> {
         use tauri::Emitter;
         self.emit(QueueUpdatedEvent::EVENT_NAME, event)
             .map_err(|e| e.to_string())
     }
 
+    fn emit_queue_skipped_missing(&self, event: QueueSkippedMissingEvent) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(QueueSkippedMissingEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
     fn emit_queue_state_changed(&self, event: QueueStateChangedEvent) -> Result<(), String> {
         use tauri::Emitter;
         self.emit(QueueStateChangedEvent::EVENT_NAME, event)
             .map_err(|e| e.to_string())
     }
 
+    fn emit_playback_state_changed(
+        &self,
+        event: PlaybackStateChangedEvent,
+    ) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(PlaybackStateChangedEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
+    fn emit_playback_track_ended(&self, event: PlaybackTrackEndedEvent) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(PlaybackTrackEndedEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
     fn emit_favorites_updated(&self, event: FavoritesUpdatedEvent) -> Result<(), String> {
         use tauri::Emitter;
         self.emit(FavoritesUpdatedEvent::EVENT_NAME, event)
             .map_err(|e| e.to_string())
     }
 
+    fn emit_favorite_lists_updated(
+        &self,
+        event: FavoriteListsUpdatedEvent,
+    ) -> Result<(), String> {
+        use tauri::Emitter;
+        self.emit(FavoriteListsUpdatedEvent::EVENT_NAME, event)
+            .map_err(|e| e.to_string())
+    }
+
     fn emit_playlists_updated(&self, event: PlaylistsUpdatedEvent) -> Result<(), String> {
         use tauri::Emitter;
         self.emit(PlaylistsUpdatedEvent::EVENT_NAME, event)
@@ -487,6 +743,19 @@ mod tests {
         assert_eq!(event.track_ids, vec![5]);
     }
 
+    #[test]
+    fn test_library_updated_event_change_seq() {
+        let event = LibraryUpdatedEvent::modified(vec![7]);
+        assert_eq!(event.change_seq, None);
+        assert!(serde_json::to_string(&event)
+            .unwrap()
+            .contains("\"change_seq\":null"));
+
+        let event = event.with_change_seq(42);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"change_seq\":42"));
+    }
+
     #[test]
     fn test_library_updated_event_empty_ids() {
         let event = LibraryUpdatedEvent::added(vec![]);
@@ -517,12 +786,16 @@ mod tests {
             found: 50,
             errors: 2,
             current_path: Some("/music/album".to_string()),
+            rate: Some(120.5),
+            eta_secs: Some(42),
         };
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("\"job_id\":\"job-123\""));
         assert!(json.contains("\"status\":\"scanning\""));
         assert!(json.contains("\"scanned\":100"));
         assert!(json.contains("\"found\":50"));
+        assert!(json.contains("\"rate\":120.5"));
+        assert!(json.contains("\"eta_secs\":42"));
     }
 
     #[test]
@@ -534,6 +807,8 @@ mod tests {
             found: 500,
             errors: 0,
             current_path: None,
+            rate: None,
+            eta_secs: None,
         };
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("\"current_path\":null"));
@@ -554,9 +829,13 @@ mod tests {
             skipped: 50,
             errors: 5,
             duration_ms: 30000,
+            visited: 305,
+            up_to_date: false,
         };
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("\"added\":250"));
+        assert!(json.contains("\"visited\":305"));
+        assert!(json.contains("\"up_to_date\":false"));
         assert!(json.contains("\"skipped\":50"));
         assert!(json.contains("\"duration_ms\":30000"));
     }
@@ -600,6 +879,14 @@ mod tests {
         assert_eq!(event.queue_length, 0);
     }
 
+    #[test]
+    fn test_queue_updated_event_replaced() {
+        let event = QueueUpdatedEvent::replaced(7);
+        assert_eq!(event.action, "replaced");
+        assert!(event.positions.is_none());
+        assert_eq!(event.queue_length, 7);
+    }
+
     #[test]
     fn test_queue_updated_event_reordered() {
         let event = QueueUpdatedEvent::reordered(2, 5, 10);
@@ -616,11 +903,27 @@ mod tests {
         assert_eq!(event.queue_length, 15);
     }
 
+    #[test]
+    fn test_queue_updated_event_refreshed() {
+        let event = QueueUpdatedEvent::refreshed(4);
+        assert_eq!(event.action, "refreshed");
+        assert!(event.positions.is_none());
+        assert_eq!(event.queue_length, 4);
+    }
+
     #[test]
     fn test_queue_updated_event_name() {
         assert_eq!(QueueUpdatedEvent::EVENT_NAME, "queue:updated");
     }
 
+    #[test]
+    fn test_queue_skipped_missing_event() {
+        let event = QueueSkippedMissingEvent::new(vec!["/music/gone.mp3".to_string()]);
+        assert_eq!(QueueSkippedMissingEvent::EVENT_NAME, "queue:skipped-missing");
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"filepaths":["/music/gone.mp3"]}"#);
+    }
+
     // ==================== QueueStateChangedEvent Tests ====================
 
     #[test]
@@ -653,6 +956,29 @@ mod tests {
         }
     }
 
+    // ==================== Playback Event Tests ====================
+
+    #[test]
+    fn test_playback_state_changed_event_serialization() {
+        let event = PlaybackStateChangedEvent {
+            state: "playing".to_string(),
+            position_ms: 1500,
+            duration_ms: 180_000,
+            volume: 0.5,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"state\":\"playing\""));
+        assert!(json.contains("\"position_ms\":1500"));
+        assert!(json.contains("\"duration_ms\":180000"));
+        assert!(json.contains("\"volume\":0.5"));
+    }
+
+    #[test]
+    fn test_playback_event_names() {
+        assert_eq!(PlaybackStateChangedEvent::EVENT_NAME, "playback:state-changed");
+        assert_eq!(PlaybackTrackEndedEvent::EVENT_NAME, "playback:track-ended");
+    }
+
     // ==================== FavoritesUpdatedEvent Tests ====================
 
     #[test]
@@ -675,6 +1001,26 @@ mod tests {
         let event = FavoritesUpdatedEvent::removed(456);
         assert_eq!(event.action, "removed");
         assert_eq!(event.track_id, 456);
+        assert_eq!(event.list_id, DEFAULT_FAVORITE_LIST_ID);
+    }
+
+    #[test]
+    fn test_favorites_updated_event_in_list() {
+        let event = FavoritesUpdatedEvent::added(7).in_list(3);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"list_id\":3"));
+    }
+
+    #[test]
+    fn test_favorites_updated_event_batch() {
+        let event = FavoritesUpdatedEvent::removed_many(vec![4, 5, 6]).unwrap().in_list(2);
+        assert_eq!(event.action, "removed");
+        assert_eq!(event.track_id, 4);
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.contains("\"track_ids\":[4,5,6]"));
+
+        assert_eq!(FavoritesUpdatedEvent::added(9).track_ids, vec![9]);
+        assert!(FavoritesUpdatedEvent::added_many(Vec::new()).is_none());
     }
 
     #[test]
@@ -682,6 +1028,15 @@ mod tests {
         assert_eq!(FavoritesUpdatedEvent::EVENT_NAME, "favorites:updated");
     }
 
+    #[test]
+    fn test_favorite_lists_updated_event() {
+        let event = FavoriteListsUpdatedEvent::created(2);
+        assert_eq!(event.action, "created");
+        assert_eq!(event.list_id, 2);
+        assert_eq!(FavoriteListsUpdatedEvent::deleted(2).action, "deleted");
+        assert_eq!(FavoriteListsUpdatedEvent::EVENT_NAME, "favorites:lists-updated");
+    }
+
     // ==================== PlaylistsUpdatedEvent Tests ====================
 
     #[test]
@@ -722,6 +1077,14 @@ mod tests {
         assert_eq!(event.track_ids, Some(vec![15, 25]));
     }
 
+    #[test]
+    fn test_playlists_updated_event_moved() {
+        let event = PlaylistsUpdatedEvent::moved(8);
+        assert_eq!(event.action, "moved");
+        assert_eq!(event.playlist_id, 8);
+        assert!(event.track_ids.is_none());
+    }
+
     #[test]
     fn test_playlists_updated_event_reordered() {
         let event = PlaylistsUpdatedEvent::reordered(6);
@@ -903,6 +1266,7 @@ mod tests {
             QueueUpdatedEvent::EVENT_NAME,
             QueueStateChangedEvent::EVENT_NAME,
             FavoritesUpdatedEvent::EVENT_NAME,
+            FavoriteListsUpdatedEvent::EVENT_NAME,
             PlaylistsUpdatedEvent::EVENT_NAME,
             SettingsUpdatedEvent::EVENT_NAME,
             LastfmAuthEvent::EVENT_NAME,
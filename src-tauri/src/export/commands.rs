@@ -0,0 +1,207 @@
+//! Tauri commands for exporting to other players.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use rusqlite::Connection;
+use tauri::State;
+use tracing::info;
+
+use crate::db::library::LibraryQuery;
+use crate::db::{
+    Database, DbResult, LibrarySortColumn, Track, history, library, playlists, settings,
+};
+use crate::export::listening_log::{self, LogEntry, LogRange};
+use crate::export::m3u;
+
+/// File name (before sanitizing) of the whole-library export
+const LIBRARY_EXPORT_NAME: &str = "Library";
+
+/// Collect the named track lists to export
+///
+/// Folders stand for every playlist nested below them. With no IDs, the
+/// whole library and every playlist are exported.
+fn collect_playlists(
+    conn: &Connection,
+    playlist_ids: &[i64],
+) -> Result<Vec<(String, Vec<Track>)>, String> {
+    let db_err = |e: crate::db::DbError| e.to_string();
+    let mut lists = Vec::new();
+
+    let ids: Vec<i64> = if playlist_ids.is_empty() {
+        let query = LibraryQuery {
+            sort_by: LibrarySortColumn::Artist,
+            limit: -1,
+            ..Default::default()
+        };
+        let tracks = library::get_all_tracks(conn, &query).map_err(db_err)?.items;
+        lists.push((LIBRARY_EXPORT_NAME.to_string(), tracks));
+
+        playlists::get_playlists(conn)
+            .map_err(db_err)?
+            .into_iter()
+            .map(|p| p.id)
+            .collect()
+    } else {
+        expand_folders(conn, playlist_ids).map_err(db_err)?
+    };
+
+    for id in ids {
+        let playlist = playlists::get_playlist(conn, id)
+            .map_err(db_err)?
+            .ok_or_else(|| format!("Playlist {} not found", id))?;
+        if !playlist.is_folder {
+            let tracks = playlist.tracks.into_iter().map(|t| t.track).collect();
+            lists.push((playlist.name, tracks));
+        }
+    }
+    Ok(lists)
+}
+
+/// Replace folder IDs with the IDs nested below them, dropping repeats
+fn expand_folders(conn: &Connection, playlist_ids: &[i64]) -> DbResult<Vec<i64>> {
+    let mut seen = HashSet::new();
+    let mut ids = Vec::new();
+    for &id in playlist_ids {
+        let descendants = playlists::get_descendant_ids(conn, id)?;
+        for id in std::iter::once(id).chain(descendants) {
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Export playlists as a folder of `.m3u8` files
+///
+/// Writes one file per playlist into `dir`, named after the playlist and
+/// de-collided with a numeric suffix. Selecting a folder exports the
+/// playlists inside it; an empty `playlist_ids` exports every playlist plus
+/// the whole library as `Library.m3u8`. With `relative`, track paths are
+/// rewritten relative to `dir` where possible. Returns the written files.
+#[tauri::command]
+pub async fn library_export_playlists(
+    db: State<'_, Database>,
+    dir: String,
+    playlist_ids: Vec<i64>,
+    relative: bool,
+) -> Result<Vec<String>, String> {
+    let db = db.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = Path::new(&dir);
+        if !dir.is_absolute() {
+            return Err(format!(
+                "Export folder must be an absolute path: {}",
+                dir.display()
+            ));
+        }
+
+        let lists = {
+            let conn = db.conn().map_err(|e| e.to_string())?;
+            collect_playlists(&conn, &playlist_ids)?
+        };
+        let written = m3u::write_playlists(dir, &lists, relative)
+            .map_err(|e| format!("Failed to write playlists to {}: {}", dir.display(), e))?;
+
+        info!("Wrote {} playlists to {}", written.len(), dir.display());
+        Ok(written
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Write recorded plays to a CSV listening log at `path`
+///
+/// `range` limits the export to plays between two dates (see `LogRange`);
+/// without it every play is written. History doesn't record where a play came
+/// from, so the `source` column is left empty. Returns the number of plays
+/// written.
+#[tauri::command]
+pub async fn listening_log_export(
+    db: State<'_, Database>,
+    path: String,
+    range: Option<LogRange>,
+) -> Result<usize, String> {
+    let (from, to) = range.unwrap_or_default().bounds()?;
+    let db = db.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        if !path.is_absolute() {
+            return Err(format!("Export path must be absolute: {}", path.display()));
+        }
+
+        let entries: Vec<LogEntry> = db
+            .with_conn(|conn| history::get_plays_between(conn, from.as_deref(), to.as_deref()))
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter_map(LogEntry::from_play)
+            .collect();
+        File::create(path)
+            .and_then(|file| listening_log::write_csv(file, &entries))
+            .map_err(|e| format!("Failed to write listening log to {}: {}", path.display(), e))?;
+
+        info!("Wrote {} plays to {}", entries.len(), path.display());
+        Ok(entries.len())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Turn appending eligible plays to the app's `listening_log.csv` on or off
+#[tauri::command]
+pub fn listening_log_set_enabled(db: State<'_, Database>, enabled: bool) -> Result<(), String> {
+    let value = serde_json::json!(enabled);
+    db.with_conn(|conn| settings::set_setting(conn, listening_log::LISTENING_LOG_SETTING, &value))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_collect_expands_folders_and_defaults_to_everything() {
+        let conn = setup_test_db();
+        let a = library::add_track(&conn, "/m/a.mp3", &TrackMetadata::default()).unwrap();
+        let b = library::add_track(&conn, "/m/b.mp3", &TrackMetadata::default()).unwrap();
+        let folder = playlists::create_playlist_folder(&conn, "Sets", None)
+            .unwrap()
+            .unwrap();
+        let warm_up = playlists::create_playlist(&conn, "Warm Up")
+            .unwrap()
+            .unwrap();
+        playlists::move_playlist(&conn, warm_up.id, Some(folder.id)).unwrap();
+        playlists::add_tracks_to_playlist(&conn, warm_up.id, &[b, a], None).unwrap();
+        let other = playlists::create_playlist(&conn, "Other").unwrap().unwrap();
+
+        let lists = collect_playlists(&conn, &[folder.id, warm_up.id]).unwrap();
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].0, "Warm Up");
+        let ids: Vec<i64> = lists[0].1.iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![b, a]);
+
+        let names: Vec<String> = collect_playlists(&conn, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names[0], LIBRARY_EXPORT_NAME);
+        assert!(names.contains(&"Warm Up".to_string()) && names.contains(&"Other".to_string()));
+
+        assert!(collect_playlists(&conn, &[other.id + 100]).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Listening log CSV files.
+//!
+//! One row per play with the columns `timestamp,artist,title,album,duration,source`.
+//! Timestamps are UTC in RFC 3339, durations whole seconds, and fields are
+//! quoted per RFC 4180 only when they need it. With the `listening_log_enabled`
+//! setting on, every scrobble-eligible play is appended to `listening_log.csv`
+//! in the app data dir, whether or not Last.fm is connected. Once the file
+//! passes `MAX_LOG_BYTES` it's rotated to `listening_log.1.csv` and so on,
+//! keeping the last `KEPT_ROTATIONS` files.
+
+use std::borrow::Cow;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use crate::db::{PlayRecord, Track};
+
+/// Settings key for appending eligible plays to the listening log
+pub const LISTENING_LOG_SETTING: &str = "listening_log_enabled";
+
+/// File name of the live log in the app data dir
+pub const LOG_FILE_NAME: &str = "listening_log.csv";
+
+/// Size past which the live log is rotated before the next append
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated logs kept next to the live one
+const KEPT_ROTATIONS: u32 = 3;
+
+const HEADER: [&str; 6] = [
+    "timestamp",
+    "artist",
+    "title",
+    "album",
+    "duration",
+    "source",
+];
+
+/// Format used for `play_history.played_at`
+const PLAYED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Serializes appends, which come from concurrent scrobble tasks
+static APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// One play as written to the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub duration: Option<f64>,
+    /// What the queue was playing from, e.g. `playlist:3`
+    pub source: Option<String>,
+}
+
+impl LogEntry {
+    /// Entry for a play of `track` that started at `timestamp` (Unix seconds)
+    pub fn for_track(track: &Track, timestamp: i64, source: Option<String>) -> Self {
+        Self {
+            timestamp: DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now),
+            artist: track.artist.clone(),
+            title: track.title.clone(),
+            album: track.album.clone(),
+            duration: track.duration,
+            source,
+        }
+    }
+
+    /// Entry for a recorded play; history doesn't keep the source
+    pub fn from_play(play: PlayRecord) -> Option<Self> {
+        let played_at = NaiveDateTime::parse_from_str(&play.played_at, PLAYED_AT_FORMAT).ok()?;
+        Some(Self {
+            timestamp: played_at.and_utc(),
+            artist: play.artist,
+            title: play.title,
+            album: play.album,
+            duration: play.duration,
+            source: None,
+        })
+    }
+
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let timestamp = self.timestamp.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        let duration = self.duration.map(|d| (d.round() as i64).to_string());
+        write_row(
+            w,
+            &[
+                &timestamp,
+                self.artist.as_deref().unwrap_or_default(),
+                self.title.as_deref().unwrap_or_default(),
+                self.album.as_deref().unwrap_or_default(),
+                duration.as_deref().unwrap_or_default(),
+                self.source.as_deref().unwrap_or_default(),
+            ],
+        )
+    }
+}
+
+/// Date range for an export, both ends inclusive and optional
+///
+/// Each end is an RFC 3339 timestamp, a `YYYY-MM-DD HH:MM:SS` UTC time, or a
+/// `YYYY-MM-DD` date, which covers that whole day.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl LogRange {
+    /// Ends of the range in the stored `played_at` format
+    pub fn bounds(&self) -> Result<(Option<String>, Option<String>), String> {
+        let from = self
+            .from
+            .as_deref()
+            .map(|v| parse_bound(v, false))
+            .transpose()?;
+        let to = self
+            .to
+            .as_deref()
+            .map(|v| parse_bound(v, true))
+            .transpose()?;
+        if let (Some(from), Some(to)) = (&from, &to)
+            && from > to
+        {
+            return Err(format!("Range starts after it ends: {} > {}", from, to));
+        }
+        Ok((from, to))
+    }
+}
+
+/// Normalize one end of a range, taking a bare date's last second for the end
+fn parse_bound(value: &str, end: bool) -> Result<String, String> {
+    let utc = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc).naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, PLAYED_AT_FORMAT))
+        .or_else(|_| {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")?;
+            Ok::<_, chrono::ParseError>(if end {
+                date.and_hms_opt(23, 59, 59).unwrap_or_default()
+            } else {
+                date.and_hms_opt(0, 0, 0).unwrap_or_default()
+            })
+        })
+        .map_err(|_| format!("Invalid date: {}", value))?;
+    Ok(utc.format(PLAYED_AT_FORMAT).to_string())
+}
+
+/// Quote a field if it holds a comma, quote, or line break
+pub fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn write_row(w: &mut impl Write, fields: &[&str]) -> io::Result<()> {
+    let line: Vec<Cow<str>> = fields.iter().map(|f| csv_field(f)).collect();
+    write!(w, "{}\r\n", line.join(","))
+}
+
+/// Write a header and one row per entry
+pub fn write_csv(w: impl Write, entries: &[LogEntry]) -> io::Result<()> {
+    let mut w = BufWriter::new(w);
+    write_row(&mut w, &HEADER)?;
+    for entry in entries {
+        entry.write_to(&mut w)?;
+    }
+    w.flush()
+}
+
+fn rotated_path(dir: &Path, n: u32) -> PathBuf {
+    dir.join(format!("listening_log.{}.csv", n))
+}
+
+/// Shift `listening_log.csv` to `.1`, `.1` to `.2`, ..., dropping the oldest
+fn rotate(dir: &Path) -> io::Result<()> {
+    let oldest = rotated_path(dir, KEPT_ROTATIONS);
+    if oldest.exists() {
+        fs::remove_file(&oldest)?;
+    }
+    for n in (1..KEPT_ROTATIONS).rev() {
+        let path = rotated_path(dir, n);
+        if path.exists() {
+            fs::rename(&path, rotated_path(dir, n + 1))?;
+        }
+    }
+    fs::rename(dir.join(LOG_FILE_NAME), rotated_path(dir, 1))
+}
+
+/// Append one play to the log in `dir`, rotating it first when it's too large
+///
+/// The row is flushed and synced before returning, so a crash loses at most
+/// the play being written.
+pub fn append(dir: &Path, entry: &LogEntry) -> io::Result<()> {
+    let _guard = APPEND_LOCK.lock();
+    fs::create_dir_all(dir)?;
+    let path = dir.join(LOG_FILE_NAME);
+    if fs::metadata(&path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        rotate(dir)?;
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let is_new = file.metadata()?.len() == 0;
+    let mut w = BufWriter::new(file);
+    if is_new {
+        write_row(&mut w, &HEADER)?;
+    }
+    entry.write_to(&mut w)?;
+    w.into_inner().map_err(|e| e.into_error())?.sync_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> LogEntry {
+        LogEntry {
+            timestamp: DateTime::from_timestamp(1_767_261_600, 0).unwrap(),
+            artist: Some("Crosby, Stills & Nash".to_string()),
+            title: Some(title.to_string()),
+            album: None,
+            duration: Some(184.6),
+            source: Some("playlist:3".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_csv_field_escaping() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a, b"), "\"a, b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn test_write_csv_rows() {
+        let mut out = Vec::new();
+        write_csv(&mut out, &[entry("Suite: \"Judy\"")]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.split_terminator("\r\n").collect();
+        assert_eq!(
+            lines,
+            [
+                "timestamp,artist,title,album,duration,source",
+                r#"2026-01-01T10:00:00Z,"Crosby, Stills & Nash","Suite: ""Judy""",,185,playlist:3"#,
+            ]
+        );
+        assert!(text.ends_with("\r\n"));
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let range = LogRange {
+            from: Some("2026-01-01".to_string()),
+            to: Some("2026-01-31".to_string()),
+        };
+        assert_eq!(
+            range.bounds().unwrap(),
+            (
+                Some("2026-01-01 00:00:00".to_string()),
+                Some("2026-01-31 23:59:59".to_string())
+            )
+        );
+        let utc = LogRange {
+            from: Some("2026-01-01T12:00:00+02:00".to_string()),
+            to: None,
+        };
+        assert_eq!(
+            utc.bounds().unwrap().0.as_deref(),
+            Some("2026-01-01 10:00:00")
+        );
+
+        let backwards = LogRange {
+            to: Some("2025-12-31".to_string()),
+            ..range
+        };
+        assert!(backwards.bounds().is_err());
+        assert!(
+            LogRange {
+                from: Some("soon".to_string()),
+                to: None
+            }
+            .bounds()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_append_writes_header_once_and_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        append(dir.path(), &entry("One")).unwrap();
+        append(dir.path(), &entry("Two")).unwrap();
+        let log = fs::read_to_string(dir.path().join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(log.matches("timestamp,artist").count(), 1);
+        assert_eq!(log.lines().count(), 3);
+
+        let big = fs::File::options()
+            .append(true)
+            .open(dir.path().join(LOG_FILE_NAME))
+            .unwrap();
+        big.set_len(MAX_LOG_BYTES).unwrap();
+        append(dir.path(), &entry("Three")).unwrap();
+        let log = fs::read_to_string(dir.path().join(LOG_FILE_NAME)).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        assert!(log.contains("Three"));
+        assert!(rotated_path(dir.path(), 1).exists());
+    }
+}
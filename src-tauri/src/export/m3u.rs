@@ -0,0 +1,318 @@
+//! Extended M3U (`.m3u8`) playlist files.
+//!
+//! Each file starts with `#EXTM3U` and lists one track per entry, preceded by
+//! an `#EXTINF:<seconds>,<Artist> - <Title>` line. Paths are UTF-8 and either
+//! absolute or relative to the folder the playlist is written to, which keeps
+//! an exported folder usable when it's copied to another machine alongside the
+//! music.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::db::Track;
+
+/// Extension of exported playlist files
+pub const M3U8_EXTENSION: &str = "m3u8";
+
+/// Name used for playlists whose name sanitizes to nothing
+const FALLBACK_NAME: &str = "Playlist";
+
+/// Longest file stem, in bytes, leaving room for a suffix and the extension
+const MAX_STEM_BYTES: usize = 200;
+
+/// Names Windows reserves for devices, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turn a playlist name into a file stem that's valid on every platform
+///
+/// Path separators, characters Windows forbids, and control characters
+/// become `_`. Leading and trailing dots and spaces are trimmed, reserved
+/// device names such as `CON` get a `_`, and long names are cut at a character
+/// boundary.
+pub fn sanitize_filename(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let mut stem = replaced
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string();
+    if stem.len() > MAX_STEM_BYTES {
+        let mut end = MAX_STEM_BYTES;
+        while !stem.is_char_boundary(end) {
+            end -= 1;
+        }
+        stem.truncate(end);
+        stem = stem.trim_end().to_string();
+    }
+
+    if stem.is_empty() {
+        return FALLBACK_NAME.to_string();
+    }
+    // Windows only looks at the part before the first dot
+    let device = stem.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(device))
+    {
+        let end = device.len();
+        stem.insert(end, '_');
+    }
+    stem
+}
+
+/// Pick a file name for `stem` not yet in `taken`, and record it
+///
+/// Names are compared case-insensitively, since exports often land on
+/// case-insensitive filesystems. Collisions get a ` (2)`, ` (3)`, ... suffix.
+pub fn unique_filename(stem: &str, taken: &mut HashSet<String>) -> String {
+    let mut candidate = format!("{}.{}", stem, M3U8_EXTENSION);
+    let mut suffix = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({}).{}", stem, suffix, M3U8_EXTENSION);
+        suffix += 1;
+    }
+    candidate
+}
+
+/// Express `target` relative to the directory `base`
+///
+/// Both paths must be absolute. Returns `None` when they share no root, such
+/// as files on different Windows drives, where only an absolute path works.
+/// Components are joined with `/`, which every major player accepts.
+pub fn relative_path(target: &Path, base: &Path) -> Option<String> {
+    if !target.is_absolute() || !base.is_absolute() {
+        return None;
+    }
+
+    let target: Vec<Component> = target.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    // The root (and drive prefix on Windows) must match
+    if target.first() != base.first() {
+        return None;
+    }
+
+    let common = target.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = base[common..]
+        .iter()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .map(|_| "..".to_string())
+        .collect();
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    Some(parts.join("/"))
+}
+
+/// The `#EXTINF` line for a track
+///
+/// Unknown durations are written as `-1`. The title falls back to the file
+/// name when the track has no title tag.
+pub fn extinf_line(track: &Track) -> String {
+    let seconds = track.duration.map(|d| d.round() as i64).unwrap_or(-1);
+    let title = match track.title.as_deref() {
+        Some(title) if !title.is_empty() => title.to_string(),
+        _ => Path::new(&track.filepath)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    match track.artist.as_deref() {
+        Some(artist) if !artist.is_empty() => format!("#EXTINF:{},{} - {}", seconds, artist, title),
+        _ => format!("#EXTINF:{},{}", seconds, title),
+    }
+}
+
+/// Render a playlist file for tracks, with paths relative to `dir` if asked
+pub fn render_playlist(tracks: &[Track], dir: &Path, relative: bool) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for track in tracks {
+        let path = if relative {
+            relative_path(Path::new(&track.filepath), dir)
+        } else {
+            None
+        };
+        out.push_str(&extinf_line(track));
+        out.push('\n');
+        out.push_str(path.as_deref().unwrap_or(&track.filepath));
+        out.push('\n');
+    }
+    out
+}
+
+/// Write one `.m3u8` per named track list into `dir`, creating it if needed
+///
+/// File names are sanitized playlist names, numbered when two collide.
+/// Existing files with the same name are overwritten. Returns the written
+/// files in input order.
+pub fn write_playlists(
+    dir: &Path,
+    playlists: &[(String, Vec<Track>)],
+    relative: bool,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+
+    let mut taken = HashSet::new();
+    let mut written = Vec::with_capacity(playlists.len());
+    for (name, tracks) in playlists {
+        let path = dir.join(unique_filename(&sanitize_filename(name), &mut taken));
+        fs::write(&path, render_playlist(tracks, dir, relative))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(
+        filepath: &str,
+        title: Option<&str>,
+        artist: Option<&str>,
+        duration: Option<f64>,
+    ) -> Track {
+        Track {
+            filepath: filepath.to_string(),
+            title: title.map(str::to_string),
+            artist: artist.map(str::to_string),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(
+            sanitize_filename("Warm Up / Peak Time"),
+            "Warm Up _ Peak Time"
+        );
+        assert_eq!(
+            sanitize_filename("What? <Really>: \"yes\" | no*"),
+            "What_ _Really__ _yes_ _ no_"
+        );
+        assert_eq!(sanitize_filename("tab\there"), "tab_here");
+        assert_eq!(sanitize_filename("  ..hidden.. "), "hidden");
+        assert_eq!(sanitize_filename("Café — Ünïcödé"), "Café — Ünïcödé");
+        assert_eq!(sanitize_filename("..."), "Playlist");
+        assert_eq!(sanitize_filename(""), "Playlist");
+        assert_eq!(sanitize_filename("con"), "con_");
+        assert_eq!(sanitize_filename("LPT1.txt"), "LPT1_.txt");
+        assert_eq!(sanitize_filename("Console"), "Console");
+
+        let long = "é".repeat(150);
+        let stem = sanitize_filename(&long);
+        assert!(stem.len() <= MAX_STEM_BYTES);
+        assert!(stem.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn test_unique_filename_decollides() {
+        let mut taken = HashSet::new();
+        assert_eq!(unique_filename("House", &mut taken), "House.m3u8");
+        assert_eq!(unique_filename("house", &mut taken), "house (2).m3u8");
+        assert_eq!(unique_filename("House", &mut taken), "House (3).m3u8");
+        assert_eq!(unique_filename("Techno", &mut taken), "Techno.m3u8");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relative_path() {
+        let rel = |target: &str, base: &str| relative_path(Path::new(target), Path::new(base));
+        assert_eq!(
+            rel("/usb/Music/a b.mp3", "/usb/Playlists").as_deref(),
+            Some("../Music/a b.mp3")
+        );
+        assert_eq!(
+            rel("/usb/Playlists/a.mp3", "/usb/Playlists").as_deref(),
+            Some("a.mp3")
+        );
+        assert_eq!(
+            rel("/usb/Playlists/Sets/a.mp3", "/usb/Playlists/").as_deref(),
+            Some("Sets/a.mp3")
+        );
+        assert_eq!(
+            rel("/home/me/Music/a.flac", "/media/usb/Export").as_deref(),
+            Some("../../../home/me/Music/a.flac")
+        );
+        // Relative inputs can't be rebased
+        assert_eq!(rel("Music/a.mp3", "/usb"), None);
+    }
+
+    #[test]
+    fn test_extinf_line() {
+        let tagged = track(
+            "/m/a.mp3",
+            Some("Strings of Life"),
+            Some("Rhythim Is Rhythim"),
+            Some(437.6),
+        );
+        assert_eq!(
+            extinf_line(&tagged),
+            "#EXTINF:438,Rhythim Is Rhythim - Strings of Life"
+        );
+
+        let untagged = track("/m/Untitled Dub.mp3", None, None, None);
+        assert_eq!(extinf_line(&untagged), "#EXTINF:-1,Untitled Dub");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_playlists_relative_and_absolute() {
+        let root = tempfile::tempdir().unwrap();
+        let music = root.path().join("Music");
+        let export = root.path().join("Export");
+        let a = music.join("Ünïcödé Artist/Track 1.flac");
+        let tracks = vec![
+            track(
+                &a.to_string_lossy(),
+                Some("One"),
+                Some("Ünïcödé Artist"),
+                Some(60.0),
+            ),
+            track("/elsewhere/two.mp3", Some("Two"), None, None),
+        ];
+        let playlists = vec![
+            ("Peak/Time".to_string(), tracks.clone()),
+            ("peak_time".to_string(), tracks),
+        ];
+
+        let written = write_playlists(&export, &playlists, true).unwrap();
+        assert_eq!(
+            written,
+            vec![
+                export.join("Peak_Time.m3u8"),
+                export.join("peak_time (2).m3u8")
+            ]
+        );
+
+        let content = fs::read_to_string(&written[0]).unwrap();
+        let expected_second = relative_path(Path::new("/elsewhere/two.mp3"), &export).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "#EXTM3U\n\
+                 #EXTINF:60,Ünïcödé Artist - One\n../Music/Ünïcödé Artist/Track 1.flac\n\
+                 #EXTINF:-1,Two\n{}\n",
+                expected_second
+            )
+        );
+
+        let written = write_playlists(&export, &playlists[..1], false).unwrap();
+        let content = fs::read_to_string(&written[0]).unwrap();
+        assert!(content.contains(&format!("\n{}\n", a.to_string_lossy())));
+        assert!(content.ends_with("\n/elsewhere/two.mp3\n"));
+    }
+}
@@ -0,0 +1,11 @@
+//! Library export to other players.
+//!
+//! Exports write portable files describing the library, its playlists and
+//! the listening history; the music files themselves are never copied or
+//! changed.
+
+pub mod commands;
+pub mod listening_log;
+pub mod m3u;
+
+pub use commands::*;
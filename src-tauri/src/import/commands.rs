@@ -0,0 +1,72 @@
+//! Tauri commands for importing from other players.
+
+use tauri::{AppHandle, State};
+use tracing::info;
+
+use crate::db::Database;
+use crate::events::{
+    EventEmitter, FavoritesUpdatedEvent, ImportProgressEvent, LibraryUpdatedEvent,
+    PlaylistsUpdatedEvent,
+};
+use crate::import::itunes::{self, ItunesImportSummary};
+
+/// Tracks or playlists handled between two import progress events
+const IMPORT_PROGRESS_INTERVAL: usize = 100;
+
+/// Import play counts, ratings, loved flags, and playlists from an iTunes or
+/// Music.app library export
+///
+/// `path` is the exported `Library.xml`. Tracks are matched to the library by
+/// file path, then by artist and title; unmatched tracks are counted, never
+/// added. The file is read and imported off the main thread in a single
+/// transaction, emitting `library:import-progress` along the way.
+#[tauri::command]
+pub async fn library_import_itunes(
+    app: AppHandle,
+    db: State<'_, Database>,
+    path: String,
+) -> Result<ItunesImportSummary, String> {
+    let db = db.inner().clone();
+    let emitter = app.clone();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let library = itunes::parse_library(&bytes)
+            .map_err(|e| format!("Not an iTunes library file: {}", e))?;
+
+        db.transaction(|conn| {
+            itunes::import_library(conn, &library, |progress| {
+                if progress.processed % IMPORT_PROGRESS_INTERVAL == 0
+                    || progress.processed == progress.total
+                {
+                    let _ = emitter.emit_import_progress(ImportProgressEvent {
+                        stage: progress.stage.to_string(),
+                        total: progress.total,
+                        processed: progress.processed,
+                    });
+                }
+            })
+        })
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    info!(
+        "iTunes import: {} matched by path, {} by name, {} unmatched",
+        summary.matched_by_path, summary.matched_by_name, summary.unmatched
+    );
+
+    if !summary.modified_track_ids.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(
+            summary.modified_track_ids.clone(),
+        ));
+    }
+    for &track_id in &summary.favorited_track_ids {
+        let _ = app.emit_favorites_updated(FavoritesUpdatedEvent::added(track_id));
+    }
+    for &playlist_id in &summary.created_playlist_ids {
+        let _ = app.emit_playlists_updated(PlaylistsUpdatedEvent::created(playlist_id));
+    }
+
+    Ok(summary)
+}
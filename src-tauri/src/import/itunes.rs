@@ -0,0 +1,632 @@
+//! iTunes / Music.app library import.
+//!
+//! Reads the XML library export (`iTunes Library.xml`, or File > Library >
+//! Export Library in Music.app) and copies play counts, ratings, loved flags,
+//! and playlists onto tracks already in the library. Tracks are matched by
+//! file path first, then by artist and title. Nothing is ever added to the
+//! library: tracks without a match are only counted.
+
+use std::collections::{BTreeSet, HashMap};
+
+use percent_encoding::percent_decode_str;
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+
+use crate::db::{DbResult, favorites, library, playlists};
+
+/// Import stage reported while matching tracks
+pub const STAGE_TRACKS: &str = "tracks";
+
+/// Import stage reported while creating playlists
+pub const STAGE_PLAYLISTS: &str = "playlists";
+
+/// Name given to playlists exported without one
+const UNTITLED_PLAYLIST: &str = "Imported playlist";
+
+/// The parts of an iTunes library plist the import uses
+#[derive(Debug, Default, Deserialize)]
+pub struct ItunesLibrary {
+    /// Tracks keyed by their iTunes track ID, as a string
+    #[serde(rename = "Tracks", default)]
+    pub tracks: HashMap<String, ItunesTrack>,
+    #[serde(rename = "Playlists", default)]
+    pub playlists: Vec<ItunesPlaylist>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ItunesTrack {
+    #[serde(rename = "Track ID")]
+    pub track_id: i64,
+    #[serde(rename = "Name")]
+    pub name: Option<String>,
+    #[serde(rename = "Artist")]
+    pub artist: Option<String>,
+    /// `file://` URL of the track; absent for streams and cloud-only tracks
+    #[serde(rename = "Location")]
+    pub location: Option<String>,
+    #[serde(rename = "Play Count")]
+    pub play_count: Option<i64>,
+    /// 0-100, 20 per star
+    #[serde(rename = "Rating")]
+    pub rating: Option<i64>,
+    /// Set when the rating is inherited from the album's rating
+    #[serde(rename = "Rating Computed", default)]
+    pub rating_computed: bool,
+    /// iTunes 12 spelling of a favorite
+    #[serde(rename = "Loved", default)]
+    pub loved: bool,
+    /// Music.app spelling of a favorite
+    #[serde(rename = "Favorited", default)]
+    pub favorited: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ItunesPlaylist {
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "Playlist Persistent ID")]
+    pub persistent_id: Option<String>,
+    /// Persistent ID of the containing folder
+    #[serde(rename = "Parent Persistent ID")]
+    pub parent_persistent_id: Option<String>,
+    /// The whole library, exported as a playlist
+    #[serde(rename = "Master", default)]
+    pub master: bool,
+    /// Built-in playlists such as Music, Podcasts, or Purchased
+    #[serde(rename = "Distinguished Kind")]
+    pub distinguished_kind: Option<i64>,
+    #[serde(rename = "Folder", default)]
+    pub folder: bool,
+    #[serde(rename = "Playlist Items", default)]
+    pub items: Vec<ItunesPlaylistItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ItunesPlaylistItem {
+    #[serde(rename = "Track ID")]
+    pub track_id: i64,
+}
+
+/// Progress of an import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// `STAGE_TRACKS` or `STAGE_PLAYLISTS`
+    pub stage: &'static str,
+    /// Items in the current stage
+    pub total: usize,
+    /// Items handled so far in the current stage
+    pub processed: usize,
+}
+
+/// What an import matched and changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ItunesImportSummary {
+    /// Tracks in the iTunes library
+    pub tracks_total: usize,
+    pub matched_by_path: usize,
+    /// Matched on artist and title after the path didn't match
+    pub matched_by_name: usize,
+    pub unmatched: usize,
+    /// Tracks whose play count was raised to the iTunes count
+    pub play_counts_updated: usize,
+    pub ratings_imported: usize,
+    /// Loved tracks newly added to the default favorites list
+    pub loved_imported: usize,
+    pub playlists_imported: usize,
+    pub folders_imported: usize,
+    /// Master and built-in playlists, which aren't imported
+    pub playlists_skipped: usize,
+    /// Library tracks whose play count or rating changed
+    #[serde(skip)]
+    pub modified_track_ids: Vec<i64>,
+    /// Library tracks added to the default favorites list
+    #[serde(skip)]
+    pub favorited_track_ids: Vec<i64>,
+    /// Playlists and folders created
+    #[serde(skip)]
+    pub created_playlist_ids: Vec<i64>,
+}
+
+/// Parse an XML (or binary) iTunes library plist
+pub fn parse_library(bytes: &[u8]) -> Result<ItunesLibrary, plist::Error> {
+    plist::from_bytes(bytes)
+}
+
+/// Convert a track's `file://` URL into a local path
+///
+/// Percent escapes are decoded, and Windows URLs (`file://localhost/C:/...`)
+/// lose the slash before the drive letter. Returns `None` for other schemes,
+/// remote hosts, and escapes that don't decode to UTF-8.
+pub fn decode_location(location: &str) -> Option<String> {
+    let rest = location.strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let decoded = percent_decode_str(path).decode_utf8().ok()?;
+    let bytes = decoded.as_bytes();
+    let path = if bytes.len() >= 3 && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        decoded[1..].to_string()
+    } else {
+        decoded.into_owned()
+    };
+
+    Some(path)
+}
+
+/// Convert an iTunes rating (0-100) to 1-5 stars, `None` when unrated
+///
+/// Half stars round up.
+pub fn rating_to_stars(rating: i64) -> Option<u8> {
+    if rating <= 0 {
+        return None;
+    }
+    Some(((rating + 10) / 20).clamp(1, 5) as u8)
+}
+
+enum Match {
+    Path(i64),
+    Name(i64),
+}
+
+fn find_by_path(conn: &Connection, filepath: &str) -> DbResult<Option<i64>> {
+    let mut stmt = conn.prepare_cached("SELECT id FROM library WHERE filepath = ?")?;
+    match stmt.query_row([filepath], |row| row.get(0)) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn find_by_name(conn: &Connection, artist: &str, title: &str) -> DbResult<Option<i64>> {
+    // Prefer a track whose file is still present
+    let mut stmt = conn.prepare_cached(
+        "SELECT id FROM library
+         WHERE artist = ?1 COLLATE NOCASE AND title = ?2 COLLATE NOCASE
+         ORDER BY COALESCE(missing, 0), id
+         LIMIT 1",
+    )?;
+    match stmt.query_row(params![artist, title], |row| row.get(0)) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn match_track(conn: &Connection, track: &ItunesTrack) -> DbResult<Option<Match>> {
+    if let Some(path) = track.location.as_deref().and_then(decode_location)
+        && let Some(id) = find_by_path(conn, &path)?
+    {
+        return Ok(Some(Match::Path(id)));
+    }
+
+    // Moved, missing, or cloud-only files fall back to the tags
+    match (track.artist.as_deref(), track.name.as_deref()) {
+        (Some(artist), Some(title)) if !artist.is_empty() && !title.is_empty() => {
+            Ok(find_by_name(conn, artist, title)?.map(Match::Name))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Copy an iTunes track's play count, rating, and loved flag onto `track_id`
+fn apply_track(
+    conn: &Connection,
+    track_id: i64,
+    track: &ItunesTrack,
+    summary: &mut ItunesImportSummary,
+    modified: &mut BTreeSet<i64>,
+) -> DbResult<()> {
+    if let Some(count) = track.play_count
+        && count > 0
+        && library::raise_play_count(conn, track_id, count)?
+    {
+        summary.play_counts_updated += 1;
+        modified.insert(track_id);
+    }
+
+    if !track.rating_computed
+        && let Some(stars) = track.rating.and_then(rating_to_stars)
+        && library::set_track_rating(conn, track_id, Some(stars))?
+    {
+        summary.ratings_imported += 1;
+        modified.insert(track_id);
+    }
+
+    if (track.loved || track.favorited)
+        && favorites::add_favorite(conn, favorites::DEFAULT_FAVORITE_LIST_ID, track_id)?.is_some()
+    {
+        summary.loved_imported += 1;
+        summary.favorited_track_ids.push(track_id);
+    }
+
+    Ok(())
+}
+
+/// Import an iTunes library into the tracks and playlists of `conn`
+///
+/// Run inside a transaction so a failed import leaves nothing behind. Play
+/// counts are only ever raised, so importing twice is harmless for tracks;
+/// playlists are created again under a numbered name. Smart playlists are
+/// imported as plain playlists holding their current tracks. `on_progress`
+/// is called after each track and playlist.
+pub fn import_library(
+    conn: &Connection,
+    itunes: &ItunesLibrary,
+    mut on_progress: impl FnMut(ImportProgress),
+) -> DbResult<ItunesImportSummary> {
+    let mut summary = ItunesImportSummary {
+        tracks_total: itunes.tracks.len(),
+        ..Default::default()
+    };
+
+    // Go through tracks in iTunes ID order so repeated imports match alike
+    let mut tracks: Vec<&ItunesTrack> = itunes.tracks.values().collect();
+    tracks.sort_by_key(|track| track.track_id);
+
+    // iTunes track ID -> library track ID
+    let mut matched: HashMap<i64, i64> = HashMap::new();
+    let mut modified = BTreeSet::new();
+    let total = tracks.len();
+    for (index, track) in tracks.into_iter().enumerate() {
+        let track_id = match match_track(conn, track)? {
+            Some(Match::Path(id)) => {
+                summary.matched_by_path += 1;
+                Some(id)
+            }
+            Some(Match::Name(id)) => {
+                summary.matched_by_name += 1;
+                Some(id)
+            }
+            None => {
+                summary.unmatched += 1;
+                None
+            }
+        };
+        if let Some(track_id) = track_id {
+            matched.insert(track.track_id, track_id);
+            apply_track(conn, track_id, track, &mut summary, &mut modified)?;
+        }
+        on_progress(ImportProgress {
+            stage: STAGE_TRACKS,
+            total,
+            processed: index + 1,
+        });
+    }
+    summary.modified_track_ids = modified.into_iter().collect();
+
+    // Persistent ID -> library folder ID. iTunes lists folders before the
+    // playlists they contain.
+    let mut folders: HashMap<&str, i64> = HashMap::new();
+    let total = itunes.playlists.len();
+    for (index, playlist) in itunes.playlists.iter().enumerate() {
+        if playlist.master || playlist.distinguished_kind.is_some() {
+            summary.playlists_skipped += 1;
+        } else {
+            let base = match playlist.name.trim() {
+                "" => UNTITLED_PLAYLIST,
+                name => name,
+            };
+            let name = playlists::generate_unique_playlist_name(conn, base)?;
+            let parent_id = playlist
+                .parent_persistent_id
+                .as_deref()
+                .and_then(|id| folders.get(id).copied());
+
+            if playlist.folder {
+                if let Some(folder) = playlists::create_playlist_folder(conn, &name, parent_id)? {
+                    if let Some(persistent_id) = playlist.persistent_id.as_deref() {
+                        folders.insert(persistent_id, folder.id);
+                    }
+                    summary.folders_imported += 1;
+                    summary.created_playlist_ids.push(folder.id);
+                }
+            } else if let Some(created) = playlists::create_playlist(conn, &name)? {
+                if parent_id.is_some() {
+                    playlists::move_playlist(conn, created.id, parent_id)?;
+                }
+                let track_ids: Vec<i64> = playlist
+                    .items
+                    .iter()
+                    .filter_map(|item| matched.get(&item.track_id).copied())
+                    .collect();
+                playlists::add_tracks_to_playlist(conn, created.id, &track_ids, None)?;
+                summary.playlists_imported += 1;
+                summary.created_playlist_ids.push(created.id);
+            }
+        }
+        on_progress(ImportProgress {
+            stage: STAGE_PLAYLISTS,
+            total,
+            processed: index + 1,
+        });
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::library::{add_track, get_track_by_id, get_track_rating, set_play_count};
+    use crate::db::{TrackMetadata, schema};
+
+    const SAMPLE_LIBRARY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple Computer//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Major Version</key><integer>1</integer>
+	<key>Application Version</key><string>1.3.5.10</string>
+	<key>Music Folder</key><string>file:///Users/me/Music/Music/Media.localized/</string>
+	<key>Tracks</key>
+	<dict>
+		<key>101</key>
+		<dict>
+			<key>Track ID</key><integer>101</integer>
+			<key>Name</key><string>Glory Box</string>
+			<key>Artist</key><string>Portishead</string>
+			<key>Play Count</key><integer>42</integer>
+			<key>Play Date UTC</key><date>2024-03-01T20:15:00Z</date>
+			<key>Rating</key><integer>80</integer>
+			<key>Loved</key><true/>
+			<key>Location</key><string>file:///Users/me/Music/Portishead/Dummy/11%20Glory%20Box.mp3</string>
+		</dict>
+		<key>102</key>
+		<dict>
+			<key>Track ID</key><integer>102</integer>
+			<key>Name</key><string>Teardrop</string>
+			<key>Artist</key><string>Massive Attack</string>
+			<key>Play Count</key><integer>3</integer>
+			<key>Rating</key><integer>60</integer>
+			<key>Rating Computed</key><true/>
+			<key>Favorited</key><true/>
+			<key>Location</key><string>file:///Volumes/Old%20Drive/Massive%20Attack/Teardrop.m4a</string>
+		</dict>
+		<key>103</key>
+		<dict>
+			<key>Track ID</key><integer>103</integer>
+			<key>Name</key><string>Caf&#233; del Mar</string>
+			<key>Artist</key><string>Energy 52</string>
+			<key>Play Count</key><integer>7</integer>
+			<key>Location</key><string>file:///Users/me/Music/Energy%2052/Caf%C3%A9%20del%20Mar.flac</string>
+		</dict>
+		<key>104</key>
+		<dict>
+			<key>Track ID</key><integer>104</integer>
+			<key>Name</key><string>Not In My Library</string>
+			<key>Artist</key><string>Nobody</string>
+			<key>Play Count</key><integer>99</integer>
+			<key>Loved</key><true/>
+		</dict>
+	</dict>
+	<key>Playlists</key>
+	<array>
+		<dict>
+			<key>Name</key><string>Library</string>
+			<key>Master</key><true/>
+			<key>Playlist Persistent ID</key><string>AAAA000000000001</string>
+			<key>Playlist Items</key>
+			<array>
+				<dict><key>Track ID</key><integer>101</integer></dict>
+				<dict><key>Track ID</key><integer>102</integer></dict>
+			</array>
+		</dict>
+		<dict>
+			<key>Name</key><string>Music</string>
+			<key>Distinguished Kind</key><integer>4</integer>
+			<key>Playlist Persistent ID</key><string>AAAA000000000002</string>
+		</dict>
+		<dict>
+			<key>Name</key><string>Trip Hop</string>
+			<key>Folder</key><true/>
+			<key>Playlist Persistent ID</key><string>AAAA000000000003</string>
+		</dict>
+		<dict>
+			<key>Name</key><string>Late Night</string>
+			<key>Playlist Persistent ID</key><string>AAAA000000000004</string>
+			<key>Parent Persistent ID</key><string>AAAA000000000003</string>
+			<key>Playlist Items</key>
+			<array>
+				<dict><key>Track ID</key><integer>102</integer></dict>
+				<dict><key>Track ID</key><integer>104</integer></dict>
+				<dict><key>Track ID</key><integer>101</integer></dict>
+			</array>
+		</dict>
+		<dict>
+			<key>Name</key><string>Empty</string>
+			<key>Playlist Persistent ID</key><string>AAAA000000000005</string>
+		</dict>
+	</array>
+</dict>
+</plist>
+"#;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add(conn: &Connection, path: &str, title: &str, artist: &str) -> i64 {
+        let metadata = TrackMetadata {
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            ..Default::default()
+        };
+        add_track(conn, path, &metadata).unwrap()
+    }
+
+    #[test]
+    fn test_decode_location() {
+        assert_eq!(
+            decode_location("file:///Users/me/Music/11%20Glory%20Box.mp3").as_deref(),
+            Some("/Users/me/Music/11 Glory Box.mp3")
+        );
+        assert_eq!(
+            decode_location("file://localhost/Users/me/Caf%C3%A9.flac").as_deref(),
+            Some("/Users/me/Café.flac")
+        );
+        assert_eq!(
+            decode_location("file://localhost/C:/Music/A%26B.mp3").as_deref(),
+            Some("C:/Music/A&B.mp3")
+        );
+        assert_eq!(decode_location("http://example.com/stream.mp3"), None);
+        assert_eq!(decode_location("file://server/share/a.mp3"), None);
+        assert_eq!(decode_location("file:///bad%FF.mp3"), None);
+    }
+
+    #[test]
+    fn test_rating_to_stars() {
+        assert_eq!(rating_to_stars(0), None);
+        assert_eq!(rating_to_stars(10), Some(1));
+        assert_eq!(rating_to_stars(20), Some(1));
+        assert_eq!(rating_to_stars(60), Some(3));
+        assert_eq!(rating_to_stars(90), Some(5));
+        assert_eq!(rating_to_stars(100), Some(5));
+    }
+
+    #[test]
+    fn test_parse_sample_library() {
+        let itunes = parse_library(SAMPLE_LIBRARY.as_bytes()).unwrap();
+        assert_eq!(itunes.tracks.len(), 4);
+
+        let glory_box = &itunes.tracks["101"];
+        assert_eq!(glory_box.name.as_deref(), Some("Glory Box"));
+        assert_eq!(glory_box.play_count, Some(42));
+        assert_eq!(glory_box.rating, Some(80));
+        assert!(glory_box.loved && !glory_box.rating_computed);
+        assert!(itunes.tracks["102"].favorited && itunes.tracks["102"].rating_computed);
+        assert_eq!(itunes.tracks["104"].location, None);
+
+        assert_eq!(itunes.playlists.len(), 5);
+        assert!(itunes.playlists[0].master);
+        assert!(itunes.playlists[2].folder);
+        assert_eq!(itunes.playlists[3].items.len(), 3);
+        assert!(itunes.playlists[4].items.is_empty());
+
+        assert!(parse_library(b"<html>not a plist</html>").is_err());
+    }
+
+    #[test]
+    fn test_import_matches_without_adding_tracks() {
+        let conn = setup_test_db();
+        let glory_box = add(
+            &conn,
+            "/Users/me/Music/Portishead/Dummy/11 Glory Box.mp3",
+            "Glory Box",
+            "Portishead",
+        );
+        // Moved since iTunes saw it: only the tags match
+        let teardrop = add(&conn, "/music/Teardrop.m4a", "teardrop", "MASSIVE ATTACK");
+        let cafe = add(
+            &conn,
+            "/Users/me/Music/Energy 52/Café del Mar.flac",
+            "Cafe del Mar",
+            "Energy 52",
+        );
+        // A higher local count is kept
+        set_play_count(&conn, cafe, 20, false).unwrap();
+
+        let itunes = parse_library(SAMPLE_LIBRARY.as_bytes()).unwrap();
+        let mut progress = Vec::new();
+        let summary = import_library(&conn, &itunes, |p| progress.push(p)).unwrap();
+
+        assert_eq!(summary.tracks_total, 4);
+        assert_eq!(summary.matched_by_path, 2);
+        assert_eq!(summary.matched_by_name, 1);
+        assert_eq!(summary.unmatched, 1);
+        assert_eq!(summary.play_counts_updated, 2);
+        assert_eq!(summary.ratings_imported, 1);
+        assert_eq!(summary.loved_imported, 2);
+        assert_eq!(summary.folders_imported, 1);
+        assert_eq!(summary.playlists_imported, 2);
+        assert_eq!(summary.playlists_skipped, 2);
+        assert_eq!(summary.modified_track_ids, vec![glory_box, teardrop]);
+        assert_eq!(summary.favorited_track_ids, vec![glory_box, teardrop]);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM library", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+
+        assert_eq!(
+            get_track_by_id(&conn, glory_box)
+                .unwrap()
+                .unwrap()
+                .play_count,
+            42
+        );
+        assert_eq!(
+            get_track_by_id(&conn, teardrop)
+                .unwrap()
+                .unwrap()
+                .play_count,
+            3
+        );
+        assert_eq!(
+            get_track_by_id(&conn, cafe).unwrap().unwrap().play_count,
+            20
+        );
+        assert_eq!(get_track_rating(&conn, glory_box).unwrap(), Some(4));
+        // Album-derived ratings aren't the track's own
+        assert_eq!(get_track_rating(&conn, teardrop).unwrap(), None);
+        assert!(
+            favorites::is_favorite(&conn, favorites::DEFAULT_FAVORITE_LIST_ID, teardrop)
+                .unwrap()
+                .0
+        );
+
+        // The playlist lands in its folder with the unmatched track left out
+        let all = playlists::get_playlists(&conn).unwrap();
+        let folder = all.iter().find(|p| p.name == "Trip Hop").unwrap();
+        assert!(folder.is_folder);
+        let late_night = all.iter().find(|p| p.name == "Late Night").unwrap();
+        assert_eq!(late_night.parent_id, Some(folder.id));
+        let late_night = playlists::get_playlist(&conn, late_night.id)
+            .unwrap()
+            .unwrap();
+        let ids: Vec<i64> = late_night.tracks.iter().map(|t| t.track.id).collect();
+        assert_eq!(ids, vec![teardrop, glory_box]);
+        assert!(all.iter().any(|p| p.name == "Empty"));
+        assert!(!all.iter().any(|p| p.name == "Library" || p.name == "Music"));
+
+        let last = |stage| progress.iter().rev().find(|p| p.stage == stage).copied();
+        assert_eq!(
+            last(STAGE_TRACKS).map(|p| (p.processed, p.total)),
+            Some((4, 4))
+        );
+        assert_eq!(
+            last(STAGE_PLAYLISTS).map(|p| (p.processed, p.total)),
+            Some((5, 5))
+        );
+    }
+
+    #[test]
+    fn test_reimport_is_idempotent_for_tracks() {
+        let conn = setup_test_db();
+        add(
+            &conn,
+            "/Users/me/Music/Portishead/Dummy/11 Glory Box.mp3",
+            "Glory Box",
+            "Portishead",
+        );
+
+        let itunes = parse_library(SAMPLE_LIBRARY.as_bytes()).unwrap();
+        import_library(&conn, &itunes, |_| {}).unwrap();
+        let again = import_library(&conn, &itunes, |_| {}).unwrap();
+
+        assert_eq!(again.matched_by_path, 1);
+        assert_eq!(again.play_counts_updated, 0);
+        assert_eq!(again.loved_imported, 0);
+        assert!(again.modified_track_ids.is_empty());
+        // Playlists are created again under a new name rather than merged
+        let names: Vec<String> = playlists::get_playlists(&conn)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert!(names.contains(&"Late Night (2)".to_string()));
+    }
+}
@@ -0,0 +1,9 @@
+//! Library import from other players.
+//!
+//! Importers copy listening history and playlists onto tracks that are
+//! already in the library; files are only ever added by the scanner.
+
+pub mod commands;
+pub mod itunes;
+
+pub use commands::*;
@@ -18,7 +18,7 @@ impl LastFmClient {
     pub fn new() -> Self {
         Self {
             config: ApiKeyConfig::load(),
-            rate_limiter: Arc::new(RateLimiter::new()),
+            rate_limiter: RateLimiter::shared(),
             http_client: reqwest::Client::new(),
             base_url: "https://ws.audioscrobbler.com/2.0/".to_string(),
         }
@@ -91,41 +91,13 @@ impl LastFmClient {
                 .map_err(|e| LastFmError::NetworkError(e.to_string()))?
         };
 
-        // Check HTTP status
         let status = response.status();
         let body = response
             .text()
             .await
             .map_err(|e| LastFmError::NetworkError(e.to_string()))?;
 
-        // Parse JSON response
-        let json: serde_json::Value = serde_json::from_str(&body)
-            .map_err(|e| LastFmError::ParseError(e.to_string()))?;
-
-        // Check for Last.fm API errors
-        if let Some(error_code) = json.get("error").and_then(|e| e.as_u64()) {
-            let message = json
-                .get("message")
-                .and_then(|m| m.as_str())
-                .unwrap_or("Unknown error")
-                .to_string();
-
-            return match error_code {
-                4 => Err(LastFmError::AuthenticationFailed),
-                9 => Err(LastFmError::InvalidSession),
-                11 => Err(LastFmError::ServiceOffline),
-                26 => Err(LastFmError::Suspended),
-                29 => Err(LastFmError::RateLimitExceeded),
-                _ => Err(LastFmError::ApiError(error_code as u32, message)),
-            };
-        }
-
-        // Check HTTP status for non-200 responses without error field
-        if !status.is_success() {
-            return Err(LastFmError::HttpError(status.as_u16(), body));
-        }
-
-        Ok(json)
+        parse_api_response(status.as_u16(), &body)
     }
 
     /// Get authentication token and URL
@@ -207,6 +179,50 @@ impl LastFmClient {
         Ok(loved_tracks.lovedtracks.track)
     }
 
+    /// Get tracks similar to a track (track.getSimilar)
+    pub async fn get_similar_tracks(
+        &self,
+        artist: &str,
+        track: &str,
+        limit: u32,
+    ) -> Result<Vec<SimilarTrack>, LastFmError> {
+        let mut params = BTreeMap::new();
+        params.insert("artist".to_string(), artist.to_string());
+        params.insert("track".to_string(), track.to_string());
+        params.insert("limit".to_string(), limit.to_string());
+        params.insert("autocorrect".to_string(), "1".to_string());
+
+        let response = self
+            .api_call("track.getSimilar", params, None, false)
+            .await?;
+
+        let similar: SimilarTracksResponse = serde_json::from_value(response)
+            .map_err(|e| LastFmError::ParseError(e.to_string()))?;
+
+        Ok(similar.similartracks.track)
+    }
+
+    /// Get artists similar to an artist (artist.getSimilar)
+    pub async fn get_similar_artists(
+        &self,
+        artist: &str,
+        limit: u32,
+    ) -> Result<Vec<SimilarArtist>, LastFmError> {
+        let mut params = BTreeMap::new();
+        params.insert("artist".to_string(), artist.to_string());
+        params.insert("limit".to_string(), limit.to_string());
+        params.insert("autocorrect".to_string(), "1".to_string());
+
+        let response = self
+            .api_call("artist.getSimilar", params, None, false)
+            .await?;
+
+        let similar: SimilarArtistsResponse = serde_json::from_value(response)
+            .map_err(|e| LastFmError::ParseError(e.to_string()))?;
+
+        Ok(similar.similarartists.artist)
+    }
+
     /// Update "Now Playing" status
     pub async fn update_now_playing(
         &self,
@@ -234,6 +250,20 @@ impl LastFmClient {
         Ok(())
     }
 
+    /// Get the name of the user a session belongs to (user.getInfo)
+    ///
+    /// A cheap signed call, so it also checks the session is still valid.
+    pub async fn get_user_info(&self, session_key: &str) -> Result<String, LastFmError> {
+        let response = self
+            .api_call("user.getInfo", BTreeMap::new(), Some(session_key), false)
+            .await?;
+
+        let info: UserInfoResponse = serde_json::from_value(response)
+            .map_err(|e| LastFmError::ParseError(e.to_string()))?;
+
+        Ok(info.user.name)
+    }
+
     /// Scrobble a track
     pub async fn scrobble(
         &self,
@@ -263,6 +293,41 @@ impl LastFmClient {
     }
 }
 
+/// Turn a Last.fm response body into JSON, or the error it reports
+///
+/// Error payloads (`{"error": <code>, "message": ...}`) win over the HTTP
+/// status; a failing status without one is reported as `HttpError`.
+pub(crate) fn parse_api_response(
+    status: u16,
+    body: &str,
+) -> Result<serde_json::Value, LastFmError> {
+    let json: serde_json::Value =
+        serde_json::from_str(body).map_err(|e| LastFmError::ParseError(e.to_string()))?;
+
+    if let Some(error_code) = json.get("error").and_then(|e| e.as_u64()) {
+        let message = json
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("Unknown error")
+            .to_string();
+
+        return match error_code {
+            4 => Err(LastFmError::AuthenticationFailed),
+            9 => Err(LastFmError::InvalidSession),
+            11 => Err(LastFmError::ServiceOffline),
+            26 => Err(LastFmError::Suspended),
+            29 => Err(LastFmError::RateLimitExceeded),
+            _ => Err(LastFmError::ApiError(error_code as u32, message)),
+        };
+    }
+
+    if !(200..300).contains(&status) {
+        return Err(LastFmError::HttpError(status, body.to_string()));
+    }
+
+    Ok(json)
+}
+
 impl Default for LastFmClient {
     fn default() -> Self {
         Self::new()
@@ -314,6 +379,40 @@ mod tests {
         assert!(!client.base_url.is_empty());
     }
 
+    #[test]
+    fn test_clients_share_rate_limiter() {
+        let first = LastFmClient::new();
+        let second = LastFmClient::new();
+        assert!(Arc::ptr_eq(&first.rate_limiter, &second.rate_limiter));
+    }
+
+    #[test]
+    fn test_parse_api_response_errors() {
+        let invalid_session = r#"{"error":9,"message":"Invalid session key"}"#;
+        assert!(matches!(
+            parse_api_response(200, invalid_session),
+            Err(LastFmError::InvalidSession)
+        ));
+
+        let other = r#"{"error":6,"message":"User not found"}"#;
+        match parse_api_response(400, other) {
+            Err(LastFmError::ApiError(6, message)) => assert_eq!(message, "User not found"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert!(matches!(
+            parse_api_response(503, r#"{"status":"down"}"#),
+            Err(LastFmError::HttpError(503, _))
+        ));
+        assert!(matches!(
+            parse_api_response(200, "<html>"),
+            Err(LastFmError::ParseError(_))
+        ));
+
+        let ok = parse_api_response(200, r#"{"user":{"name":"rj"}}"#).unwrap();
+        assert_eq!(ok["user"]["name"], "rj");
+    }
+
     #[test]
     fn test_is_configured() {
         let client = LastFmClient::new();
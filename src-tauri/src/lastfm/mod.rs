@@ -7,5 +7,5 @@ pub mod types;
 // Re-export commonly used types
 pub use client::{LastFmClient, LastFmError};
 pub use config::ApiKeyConfig;
-pub use rate_limiter::RateLimiter;
+pub use rate_limiter::{RateLimitStatus, RateLimiter};
 pub use types::*;
@@ -1,6 +1,25 @@
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
+use tracing::debug;
+
+const SECOND_MS: u64 = 1_000;
+const DAY_MS: u64 = 86_400_000;
+
+/// Rate limiter state, for showing "throttled, resuming in Ns"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RateLimitStatus {
+    /// How long the next request would wait, 0 if it can go now
+    pub wait_ms: u64,
+    /// Requests made in the last second
+    pub recent_requests: usize,
+    /// Requests made in the last 24 hours
+    pub daily_requests: usize,
+    pub per_second_limit: usize,
+    pub daily_limit: usize,
+}
 
 /// Rate limiter for Last.fm API calls
 ///
@@ -8,6 +27,7 @@ use tokio::time::sleep;
 /// - 5 requests per second
 /// - 333 requests per 24 hours
 pub struct RateLimiter {
+    /// Request times in Unix milliseconds, oldest first
     requests: Mutex<Vec<u64>>,
     daily_limit: usize,
     per_second_limit: usize,
@@ -23,55 +43,85 @@ impl RateLimiter {
         }
     }
 
+    /// Process-wide rate limiter shared by every Last.fm client
+    ///
+    /// Clients are created per command, so limits only hold if they all
+    /// record requests against the same limiter.
+    pub fn shared() -> Arc<RateLimiter> {
+        static SHARED: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(RateLimiter::new())).clone()
+    }
+
     /// Wait if needed to respect rate limits, then record the request
+    ///
+    /// The wait is re-checked after sleeping, since other callers may have
+    /// taken the free slot in the meantime. Checking and recording happen
+    /// under one lock, so concurrent callers never both take the last slot.
     pub async fn wait_if_needed(&self) {
-        let mut requests = self.requests.lock().await;
+        loop {
+            let mut requests = self.requests.lock().await;
+            let now = Self::current_timestamp();
+            Self::prune(&mut requests, now);
+
+            let wait_ms = self.wait_time_at(&requests, now);
+            if wait_ms == 0 {
+                requests.push(now);
+                return;
+            }
+            drop(requests); // Release lock before sleeping
+            debug!("Rate limit reached, waiting {} ms", wait_ms);
+            sleep(Duration::from_millis(wait_ms)).await;
+        }
+    }
 
+    /// Current wait time and request counts
+    pub async fn status(&self) -> RateLimitStatus {
+        let mut requests = self.requests.lock().await;
         let now = Self::current_timestamp();
-
-        // Clean old requests (keep last 24 hours)
-        requests.retain(|&req_time| now - req_time < 86400);
-
-        // Check daily limit
-        if requests.len() >= self.daily_limit {
-            // Wait until oldest request expires (24 hours)
-            let oldest = requests[0];
-            let wait_time = 86400 - (now - oldest);
-            if wait_time > 0 {
-                drop(requests); // Release lock before sleeping
-                sleep(Duration::from_secs(wait_time)).await;
-                requests = self.requests.lock().await;
-            }
+        Self::prune(&mut requests, now);
+
+        RateLimitStatus {
+            wait_ms: self.wait_time_at(&requests, now),
+            recent_requests: requests
+                .iter()
+                .filter(|&&req_time| now.saturating_sub(req_time) < SECOND_MS)
+                .count(),
+            daily_requests: requests.len(),
+            per_second_limit: self.per_second_limit,
+            daily_limit: self.daily_limit,
         }
+    }
 
-        // Check per-second limit
-        let recent_requests: Vec<u64> = requests
-            .iter()
-            .filter(|&&req_time| now - req_time < 1)
-            .copied()
-            .collect();
-
-        if recent_requests.len() >= self.per_second_limit {
-            // Wait until oldest recent request expires (1 second)
-            let oldest_recent = recent_requests[0];
-            let wait_time = 1 - (now - oldest_recent);
-            if wait_time > 0 {
-                drop(requests); // Release lock before sleeping
-                sleep(Duration::from_secs(wait_time)).await;
-                requests = self.requests.lock().await;
+    /// How long a request at `now` has to wait, in milliseconds
+    ///
+    /// Once a limit is reached, the wait lasts until the request that filled
+    /// it drops out of the window.
+    fn wait_time_at(&self, requests: &[u64], now: u64) -> u64 {
+        let wait_for = |window: &[u64], limit: usize, window_ms: u64| {
+            if limit == 0 || window.len() < limit {
+                return 0;
             }
-        }
+            (window[window.len() - limit] + window_ms).saturating_sub(now)
+        };
+
+        let recent_start =
+            requests.partition_point(|&req_time| now.saturating_sub(req_time) >= SECOND_MS);
+        let per_second = wait_for(&requests[recent_start..], self.per_second_limit, SECOND_MS);
+        let daily = wait_for(requests, self.daily_limit, DAY_MS);
+        per_second.max(daily)
+    }
 
-        // Record this request
-        requests.push(now);
+    /// Drop requests older than 24 hours
+    fn prune(requests: &mut Vec<u64>, now: u64) {
+        requests.retain(|&req_time| now.saturating_sub(req_time) < DAY_MS);
     }
 
-    /// Get current Unix timestamp in seconds
+    /// Get current Unix timestamp in milliseconds
     fn current_timestamp() -> u64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_secs()
+            .as_millis() as u64
     }
 
     /// Get the number of requests made in the last 24 hours (for testing/debugging)
@@ -79,7 +129,10 @@ impl RateLimiter {
     pub async fn request_count(&self) -> usize {
         let requests = self.requests.lock().await;
         let now = Self::current_timestamp();
-        requests.iter().filter(|&&req_time| now - req_time < 86400).count()
+        requests
+            .iter()
+            .filter(|&&req_time| now.saturating_sub(req_time) < DAY_MS)
+            .count()
     }
 }
 
@@ -125,6 +178,14 @@ mod tests {
         assert!(elapsed < Duration::from_millis(1200));
     }
 
+    #[test]
+    fn test_rate_limiter_shared_instance() {
+        let first = RateLimiter::shared();
+        let second = RateLimiter::shared();
+
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
     #[tokio::test]
     async fn test_rate_limiter_cleans_old_requests() {
         let limiter = RateLimiter::new();
@@ -146,4 +207,43 @@ mod tests {
         let count_after = limiter.request_count().await;
         assert_eq!(count_after, 0);
     }
+
+    #[test]
+    fn test_wait_time_grows_with_rapid_requests() {
+        let limiter = RateLimiter::new();
+        let start = 1_700_000_000_000;
+        let mut requests = Vec::new();
+        let mut waits = Vec::new();
+
+        // Six requests 100 ms apart: the sixth has to wait for the first
+        // to leave the one-second window
+        for i in 0..6 {
+            let now = start + i * 100;
+            waits.push(limiter.wait_time_at(&requests, now));
+            requests.push(now);
+        }
+        assert_eq!(waits, [0, 0, 0, 0, 0, 500]);
+        assert_eq!(limiter.wait_time_at(&requests, start + 700), 400);
+        assert_eq!(limiter.wait_time_at(&requests[..5], start + 1_000), 0);
+
+        // At the daily limit the wait runs until the oldest request expires
+        let daily: Vec<u64> = (0..333).map(|i| start + i * 10_000).collect();
+        let now = start + 333 * 10_000;
+        assert_eq!(limiter.wait_time_at(&daily, now), DAY_MS - 333 * 10_000);
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_throttling() {
+        let limiter = RateLimiter::new();
+        let idle = limiter.status().await;
+        assert_eq!((idle.wait_ms, idle.recent_requests, idle.daily_requests), (0, 0, 0));
+
+        for _ in 0..5 {
+            limiter.wait_if_needed().await;
+        }
+        let status = limiter.status().await;
+        assert!(status.wait_ms > 0 && status.wait_ms <= SECOND_MS);
+        assert_eq!((status.recent_requests, status.daily_requests), (5, 5));
+        assert_eq!((status.per_second_limit, status.daily_limit), (5, 333));
+    }
 }
@@ -1,3 +1,4 @@
+use super::client::LastFmError;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -96,6 +97,77 @@ pub struct ImportLovedTracksResponse {
     pub message: String,
 }
 
+/// Result of testing the Last.fm connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTestResponse {
+    /// "ok", "not_configured", "not_authenticated", "network_error",
+    /// "auth_error" or "api_error"
+    pub status: String,
+    /// Last.fm answered; false too when no request was made
+    pub reachable: bool,
+    /// The stored session was accepted
+    pub authenticated: bool,
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl ConnectionTestResponse {
+    pub fn ok(username: String) -> Self {
+        Self {
+            status: "ok".to_string(),
+            reachable: true,
+            authenticated: true,
+            username: Some(username),
+            message: None,
+        }
+    }
+
+    pub fn not_authenticated() -> Self {
+        Self {
+            status: "not_authenticated".to_string(),
+            reachable: false,
+            authenticated: false,
+            username: None,
+            message: Some("Not authenticated with Last.fm".to_string()),
+        }
+    }
+
+    /// Classify a failed call as a network, auth, or API failure
+    pub fn from_error(error: &LastFmError) -> Self {
+        let (status, reachable) = match error {
+            LastFmError::NotConfigured => ("not_configured", false),
+            LastFmError::NetworkError(_) => ("network_error", false),
+            LastFmError::AuthenticationFailed
+            | LastFmError::InvalidSession
+            | LastFmError::Suspended => ("auth_error", true),
+            LastFmError::ServiceOffline
+            | LastFmError::RateLimitExceeded
+            | LastFmError::ParseError(_)
+            | LastFmError::ApiError(..)
+            | LastFmError::HttpError(..) => ("api_error", true),
+        };
+        Self {
+            status: status.to_string(),
+            reachable,
+            authenticated: false,
+            username: None,
+            message: Some(error.to_string()),
+        }
+    }
+}
+
+/// User info response from Last.fm API (user.getInfo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserInfoResponse {
+    pub user: UserInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub name: String,
+}
+
 /// Last.fm API token response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenResponse {
@@ -169,6 +241,41 @@ impl ArtistInfo {
     }
 }
 
+/// Similar tracks response from Last.fm API (track.getSimilar)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarTracksResponse {
+    pub similartracks: SimilarTracksContainer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarTracksContainer {
+    #[serde(default)]
+    pub track: Vec<SimilarTrack>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarTrack {
+    pub name: String,
+    pub artist: ArtistInfo,
+}
+
+/// Similar artists response from Last.fm API (artist.getSimilar)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarArtistsResponse {
+    pub similarartists: SimilarArtistsContainer,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarArtistsContainer {
+    #[serde(default)]
+    pub artist: Vec<SimilarArtist>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarArtist {
+    pub name: String,
+}
+
 /// Scrobble response from Last.fm API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScrobbleApiResponse {
@@ -197,6 +304,7 @@ pub struct NowPlayingApiResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lastfm::client::parse_api_response;
 
     // ==================== LastfmSettings Tests ====================
 
@@ -428,6 +536,35 @@ mod tests {
         assert!(json.contains("\"message\":\"Authentication failed\""));
     }
 
+    // ==================== ConnectionTestResponse Tests ====================
+
+    #[test]
+    fn test_connection_test_classifies_error_response() {
+        let body = r#"{"error":9,"message":"Invalid session key - Please re-authenticate"}"#;
+        let error = parse_api_response(403, body).unwrap_err();
+        let result = ConnectionTestResponse::from_error(&error);
+        assert_eq!(result.status, "auth_error");
+        assert!(result.reachable);
+        assert!(!result.authenticated);
+        assert_eq!(result.message.as_deref(), Some("Invalid or expired session"));
+
+        let body = r#"{"error":11,"message":"Service Offline"}"#;
+        let error = parse_api_response(503, body).unwrap_err();
+        let result = ConnectionTestResponse::from_error(&error);
+        assert_eq!((result.status.as_str(), result.reachable), ("api_error", true));
+
+        let error = LastFmError::NetworkError("connection refused".to_string());
+        let result = ConnectionTestResponse::from_error(&error);
+        assert_eq!((result.status.as_str(), result.reachable), ("network_error", false));
+    }
+
+    #[test]
+    fn test_user_info_response() {
+        let json = r#"{"user":{"name":"rj","playcount":"1234","country":"UK"}}"#;
+        let info: UserInfoResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(info.user.name, "rj");
+    }
+
     // ==================== NowPlayingRequest Tests ====================
 
     #[test]
@@ -472,6 +609,34 @@ mod tests {
         assert!(json.contains("\"totalPages\":\"10\""));
     }
 
+    // ==================== Similar Tracks/Artists Tests ====================
+
+    #[test]
+    fn test_similar_tracks_response_deserialization() {
+        let json = r#"{"similartracks":{"track":[
+            {"name":"Teardrop","match":1.0,"artist":{"name":"Massive Attack","mbid":""}},
+            {"name":"Glory Box","match":0.8,"artist":{"name":"Portishead"}}
+        ],"@attr":{"artist":"Massive Attack"}}}"#;
+        let response: SimilarTracksResponse = serde_json::from_str(json).unwrap();
+
+        let tracks = response.similartracks.track;
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[1].name, "Glory Box");
+        assert_eq!(tracks[1].artist.name(), "Portishead");
+    }
+
+    #[test]
+    fn test_similar_artists_response_deserialization() {
+        let json = r#"{"similarartists":{"artist":[{"name":"Tricky","match":"1"}]}}"#;
+        let response: SimilarArtistsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.similarartists.artist[0].name, "Tricky");
+
+        // An unknown artist yields an empty container
+        let json = r#"{"similarartists":{"@attr":{"artist":"Nobody"}}}"#;
+        let response: SimilarArtistsResponse = serde_json::from_str(json).unwrap();
+        assert!(response.similarartists.artist.is_empty());
+    }
+
     // ==================== QueueStatusResponse Tests ====================
 
     #[test]
@@ -1,53 +1,98 @@
 pub mod audio;
+pub mod background;
 pub mod commands;
 pub mod db;
+pub mod diagnostics;
 pub mod dialog;
 pub mod events;
+pub mod export;
+pub mod import;
 pub mod lastfm;
 pub mod library;
+pub mod logging;
 pub mod media_keys;
 pub mod metadata;
+pub mod playback;
 pub mod scanner;
+pub mod shutdown;
 pub mod watcher;
 
 #[cfg(test)]
 mod concurrency_test;
 
 use commands::{
-    audio_get_status, audio_get_volume, audio_load, audio_pause, audio_play, audio_seek,
-    audio_set_volume, audio_stop, favorites_add, favorites_check, favorites_get,
+    audio_get_device_profile, audio_set_device_profile,
+    audio_get_muted, audio_get_status, audio_get_volume, audio_load, audio_next_chapter,
+    audio_pause, audio_play, audio_prev_chapter, audio_profile_delete_rule, audio_profile_list,
+    audio_profile_set_global, audio_profile_set_rule, audio_seek, audio_seek_commit, audio_seek_preview, audio_seek_to_bookmark, audio_seek_relative, audio_set_buffer_ms, audio_set_fade_ms, audio_set_muted, audio_set_trim_silence, audio_set_volume, audio_stop, db_checkpoint, db_integrity_check, db_optimize, db_relocate, db_vacuum, favorites_add,
+    favorites_add_many, favorites_check, favorites_check_many, favorites_create_list,
+    favorites_delete_list, favorites_get, favorites_remove_many,
     favorites_get_recently_added, favorites_get_recently_played, favorites_get_top25,
-    favorites_remove, lastfm_auth_callback, lastfm_disconnect, lastfm_get_auth_url,
+    favorites_lists, favorites_remove, lastfm_auth_callback, lastfm_disconnect, lastfm_get_auth_url,
     lastfm_get_settings, lastfm_import_loved_tracks, lastfm_now_playing, lastfm_queue_retry,
-    lastfm_queue_status, lastfm_scrobble, lastfm_update_settings, playlist_add_tracks,
-    playlist_create, playlist_delete, playlist_generate_name, playlist_get, playlist_list,
-    playlist_remove_track, playlist_reorder_tracks, playlist_update, playlists_reorder,
-    queue_add, queue_add_files, queue_clear, queue_get, queue_get_playback_state, queue_remove,
-    queue_reorder, queue_set_current_index, queue_set_loop, queue_set_shuffle, queue_shuffle,
-    settings_get, settings_get_all, settings_reset, settings_set, settings_update, AudioState,
+    lastfm_queue_status, lastfm_rate_limit_status, lastfm_scrobble, lastfm_test_connection,
+    lastfm_update_settings, playlist_add_tracks,
+    playlist_create, playlist_create_folder, playlist_delete, playlist_generate_name, playlist_get,
+    playlist_get_virtual, playlist_list, playlist_move, playlist_remove_track,
+    playlist_reorder_tracks, playlist_repair, playlist_repair_all, playlist_update,
+    playlists_reorder, playlists_reorder_many, queue_add, queue_add_files, queue_clear,
+    queue_contains, queue_get,
+    queue_get_context, queue_get_playback_state, queue_get_shuffle_order, queue_get_totals,
+    queue_jump_to_track,
+    queue_peek_next, queue_peek_previous, queue_peek_upcoming, queue_play_folder,
+    queue_play_similar_local, queue_refresh_metadata, queue_remove, queue_replace,
+    queue_reorder, queue_reorder_many, queue_reshuffle, queue_reshuffle_cycle, queue_set_consume,
+    queue_set_current_index,
+    queue_set_loop, queue_set_shuffle, queue_set_shuffle_mode, queue_shuffle,
+    queue_shuffle_library,
+    queue_start_artist_radio, queue_start_similar_radio, settings_get, settings_get_all,
+    settings_reset, settings_set, settings_update, AudioState,
 };
+use background::{background_status, BackgroundExecutor};
+use diagnostics::{AppInfo, Diagnostics};
 use dialog::{open_add_music_dialog, open_file_dialog, open_folder_dialog};
+use logging::Logging;
 use media_keys::{MediaKeyManager, NowPlayingInfo};
 use metadata::{get_track_metadata, save_track_metadata};
+use playback::{playback_play_track, PlaybackManager};
 use scanner::commands::{
-    extract_file_metadata, get_track_artwork, get_track_artwork_url, scan_paths_metadata,
-    scan_paths_to_library,
+    extract_file_metadata, get_track_artwork, get_track_artwork_url, scan_path_sync,
+    scan_paths_metadata, scan_paths_to_library,
 };
+use shutdown::{app_shutdown, Shutdown};
 use library::commands::{
-    library_check_status, library_delete_track, library_get_all, library_get_artwork,
-    library_get_artwork_url, library_get_missing, library_get_stats, library_get_track,
-    library_locate_track, library_mark_missing, library_mark_present, library_reconcile_scan,
-    library_rescan_track, library_update_play_count,
+    artwork_cache_clear, artwork_cache_stats, library_analyze_missing_props,
+    library_analyze_status, library_check_status,
+    library_clear_artwork_misses, library_delete_track, library_find_orphans, library_get_all,
+    library_clear_album_override, library_get_album_artwork, library_get_albums,
+    library_get_artwork, library_get_artwork_patterns, library_set_album_override,
+    library_get_artwork_url,
+    library_get_missing, library_purge_missing, library_add_bookmark, library_delete_bookmark,
+    library_get_bookmarks,
+    library_add_tag, library_get_all_tags, library_get_tags, library_remove_tag,
+    library_find_incomplete_albums, library_get_changes_since, library_get_chapters,
+    library_get_size_breakdown,
+    library_get_stats, library_get_top, library_recompute_stats,
+    library_get_unplayable, library_refresh_artwork, library_set_artwork_patterns,
+    library_get_track, library_locate_track, library_mark_missing, library_mark_orphans_missing,
+    library_mark_present, library_path_status, library_reconcile_scan, library_relocate_prefix,
+    library_replace_track_file, library_rescan_track,
+    library_reset_all_play_counts, library_search_fuzzy, library_set_added_date,
+    library_set_play_count, library_update_play_count, library_verify_all,
+    library_verify_playable, media_read_range,
 };
+use export::commands::{library_export_playlists, listening_log_export, listening_log_set_enabled};
+use import::commands::library_import_itunes;
 use watcher::{
-    watched_folders_add, watched_folders_get, watched_folders_list, watched_folders_remove,
-    watched_folders_rescan, watched_folders_status, watched_folders_update, WatcherManager,
+    watched_folders_add, watched_folders_get, watched_folders_list, watched_folders_pause_all,
+    watched_folders_remove, watched_folders_rescan, watched_folders_resume_all,
+    watched_folders_status, watched_folders_update, WatcherManager,
 };
-use serde::Serialize;
 use std::time::Duration;
 use tokio::io::AsyncWriteExt;
-use tauri::{Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut};
+use tracing::{debug, error, info, warn};
 
 #[tauri::command]
 fn media_set_metadata(
@@ -82,50 +127,19 @@ fn media_set_stopped(state: State<MediaKeyManager>) -> Result<(), String> {
     state.set_stopped()
 }
 
-#[derive(Serialize)]
-struct AppInfo {
-    version: String,
-    build: String,
-    platform: String,
-}
-
 #[tauri::command]
 fn app_get_info() -> AppInfo {
-    let version = env!("CARGO_PKG_VERSION").to_string();
-    let build = option_env!("MT_BUILD_ID")
-        .unwrap_or("dev")
-        .to_string();
-    let platform = format!(
-        "{} {}",
-        std::env::consts::OS,
-        std::env::consts::ARCH
-    );
-    
-    AppInfo {
-        version,
-        build,
-        platform,
-    }
+    AppInfo::current()
 }
 
 #[tauri::command]
-async fn export_diagnostics(path: String) -> Result<(), String> {
-    let mut content = String::new();
-
-    content.push_str("=== mt Diagnostics ===\n\n");
-
-    let info = app_get_info();
-    content.push_str(&format!("Version: {}\n", info.version));
-    content.push_str(&format!("Build: {}\n", info.build));
-    content.push_str(&format!("Platform: {}\n", info.platform));
-    content.push_str(&format!("Timestamp: {}\n", chrono::Utc::now().to_rfc3339()));
-
-    content.push_str("\n=== Environment ===\n\n");
-    content.push_str(&format!("Rust version: {}\n", env!("CARGO_PKG_RUST_VERSION")));
+fn app_get_diagnostics(app: AppHandle) -> Diagnostics {
+    Diagnostics::collect(&app)
+}
 
-    if let Ok(cwd) = std::env::current_dir() {
-        content.push_str(&format!("Working directory: {}\n", cwd.display()));
-    }
+#[tauri::command]
+async fn export_diagnostics(app: AppHandle, path: String) -> Result<(), String> {
+    let content = Diagnostics::collect(&app).to_text_with_log();
 
     let mut file = tokio::fs::File::create(&path)
         .await
@@ -137,6 +151,18 @@ async fn export_diagnostics(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Path of the log file currently written to
+#[tauri::command]
+fn log_get_path(app: AppHandle) -> Result<String, String> {
+    let logging = app
+        .try_state::<Logging>()
+        .ok_or_else(|| "Logging is not initialized".to_string())?;
+    logging
+        .current_file()
+        .map(|path| path.display().to_string())
+        .ok_or_else(|| "No log file has been written yet".to_string())
+}
+
 fn setup_global_shortcuts(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     let app_handle = app.handle().clone();
     
@@ -153,16 +179,16 @@ fn setup_global_shortcuts(app: &tauri::App) -> Result<(), Box<dyn std::error::Er
                 }
                 
                 let event_name = if shortcut == &play_pause {
-                    println!("Media key: Play/Pause");
+                    debug!("Media key: Play/Pause");
                     Some("mediakey://toggle")
                 } else if shortcut == &next_track {
-                    println!("Media key: Next");
+                    debug!("Media key: Next");
                     Some("mediakey://next")
                 } else if shortcut == &prev_track {
-                    println!("Media key: Previous");
+                    debug!("Media key: Previous");
                     Some("mediakey://previous")
                 } else if shortcut == &stop {
-                    println!("Media key: Stop");
+                    debug!("Media key: Stop");
                     Some("mediakey://stop")
                 } else {
                     None
@@ -178,19 +204,19 @@ fn setup_global_shortcuts(app: &tauri::App) -> Result<(), Box<dyn std::error::Er
     let global_shortcut = app.global_shortcut();
     
     if let Err(e) = global_shortcut.register(play_pause) {
-        eprintln!("Failed to register MediaPlayPause: {}", e);
+        warn!("Failed to register MediaPlayPause: {}", e);
     }
     if let Err(e) = global_shortcut.register(next_track) {
-        eprintln!("Failed to register MediaTrackNext: {}", e);
+        warn!("Failed to register MediaTrackNext: {}", e);
     }
     if let Err(e) = global_shortcut.register(prev_track) {
-        eprintln!("Failed to register MediaTrackPrevious: {}", e);
+        warn!("Failed to register MediaTrackPrevious: {}", e);
     }
     if let Err(e) = global_shortcut.register(stop) {
-        eprintln!("Failed to register MediaStop: {}", e);
+        warn!("Failed to register MediaStop: {}", e);
     }
 
-    println!("Global media shortcuts registered");
+    info!("Global media shortcuts registered");
     Ok(())
 }
 
@@ -215,9 +241,27 @@ pub fn run() {
             audio_pause,
             audio_stop,
             audio_seek,
+            audio_seek_preview,
+            audio_seek_commit,
+            audio_seek_relative,
+            audio_seek_to_bookmark,
+            audio_next_chapter,
+            audio_prev_chapter,
             audio_set_volume,
             audio_get_volume,
+            audio_set_muted,
+            audio_get_muted,
+            audio_set_fade_ms,
+            audio_set_buffer_ms,
+            audio_set_trim_silence,
             audio_get_status,
+            audio_profile_list,
+            audio_profile_set_rule,
+            audio_profile_delete_rule,
+            audio_profile_set_global,
+            audio_get_device_profile,
+            audio_set_device_profile,
+            playback_play_track,
             open_file_dialog,
             open_folder_dialog,
             open_add_music_dialog,
@@ -226,7 +270,14 @@ pub fn run() {
             media_set_paused,
             media_set_stopped,
             app_get_info,
+            app_get_diagnostics,
             export_diagnostics,
+            log_get_path,
+            db_vacuum,
+            db_integrity_check,
+            db_optimize,
+            db_checkpoint,
+            db_relocate,
             get_track_metadata,
             save_track_metadata,
             watched_folders_list,
@@ -236,61 +287,141 @@ pub fn run() {
             watched_folders_remove,
             watched_folders_rescan,
             watched_folders_status,
+            watched_folders_pause_all,
+            watched_folders_resume_all,
             scan_paths_to_library,
+            scan_path_sync,
             scan_paths_metadata,
             extract_file_metadata,
             get_track_artwork,
             get_track_artwork_url,
+            library_analyze_missing_props,
+            library_analyze_status,
             library_get_all,
             library_get_stats,
+            library_recompute_stats,
+            library_get_changes_since,
+            library_get_top,
+            library_get_size_breakdown,
+            library_find_incomplete_albums,
+            library_get_chapters,
+            library_add_bookmark,
+            library_get_bookmarks,
+            library_delete_bookmark,
+            library_add_tag,
+            library_remove_tag,
+            library_get_tags,
+            library_get_all_tags,
+            library_search_fuzzy,
             library_get_track,
             library_get_artwork,
             library_get_artwork_url,
+            library_get_album_artwork,
+            library_get_albums,
+            library_set_album_override,
+            library_clear_album_override,
+            library_clear_artwork_misses,
+            library_refresh_artwork,
+            artwork_cache_stats,
+            artwork_cache_clear,
             library_delete_track,
             library_rescan_track,
             library_update_play_count,
+            library_set_play_count,
+            library_set_added_date,
+            library_reset_all_play_counts,
             library_get_missing,
+            library_purge_missing,
             library_locate_track,
+            library_replace_track_file,
+            media_read_range,
             library_check_status,
+            library_path_status,
             library_mark_missing,
             library_mark_present,
             library_reconcile_scan,
+            library_find_orphans,
+            library_mark_orphans_missing,
+            library_verify_playable,
+            library_get_unplayable,
+            library_verify_all,
+            library_relocate_prefix,
+            library_get_artwork_patterns,
+            library_set_artwork_patterns,
+            library_import_itunes,
+            library_export_playlists,
+            listening_log_export,
+            listening_log_set_enabled,
             queue_get,
+            queue_peek_upcoming,
+            queue_peek_next,
+            queue_peek_previous,
+            queue_play_folder,
+            queue_get_totals,
             queue_add,
             queue_add_files,
             queue_remove,
             queue_clear,
+            queue_replace,
+            queue_refresh_metadata,
             queue_reorder,
+            queue_reorder_many,
             queue_shuffle,
+            queue_reshuffle,
+            queue_reshuffle_cycle,
+            queue_shuffle_library,
+            queue_start_artist_radio,
+            queue_start_similar_radio,
+            queue_play_similar_local,
+            queue_get_context,
             queue_get_playback_state,
+            queue_get_shuffle_order,
             queue_set_current_index,
+            queue_jump_to_track,
+            queue_contains,
             queue_set_shuffle,
+            queue_set_shuffle_mode,
             queue_set_loop,
+            queue_set_consume,
             playlist_list,
             playlist_create,
+            playlist_create_folder,
+            playlist_move,
             playlist_get,
+            playlist_get_virtual,
             playlist_update,
             playlist_delete,
             playlist_add_tracks,
             playlist_remove_track,
             playlist_reorder_tracks,
+            playlist_repair,
+            playlist_repair_all,
             playlists_reorder,
+            playlists_reorder_many,
             playlist_generate_name,
             favorites_get,
             favorites_check,
+            favorites_check_many,
             favorites_add,
+            favorites_add_many,
             favorites_remove,
+            favorites_remove_many,
             favorites_get_top25,
             favorites_get_recently_played,
             favorites_get_recently_added,
+            favorites_lists,
+            favorites_create_list,
+            favorites_delete_list,
             lastfm_get_settings,
             lastfm_update_settings,
             lastfm_get_auth_url,
             lastfm_auth_callback,
             lastfm_disconnect,
+            lastfm_test_connection,
             lastfm_now_playing,
             lastfm_scrobble,
             lastfm_queue_status,
+            lastfm_rate_limit_status,
             lastfm_queue_retry,
             lastfm_import_loved_tracks,
             settings_get_all,
@@ -298,76 +429,132 @@ pub fn run() {
             settings_set,
             settings_update,
             settings_reset,
+            app_shutdown,
+            background_status,
         ])
         .setup(|app| {
+            let data_dir = app.path().app_data_dir()
+                .expect("Failed to get app data directory");
+
+            // Initialize logging first so every other subsystem's startup is logged
+            let log_level = commands::stored_log_level(app.handle());
+            match Logging::init(&data_dir.join("logs"), log_level) {
+                Ok(logging) => {
+                    app.manage(logging);
+                    info!("Logging initialized at level {}", log_level);
+                }
+                Err(e) => eprintln!("Failed to initialize logging: {}", e),
+            }
+
             // Initialize database
-            let db_path = app.path().app_data_dir()
-                .expect("Failed to get app data directory")
-                .join("mt.db");
+            let db_path = db::relocate::resolve_db_path(&data_dir);
 
             // Ensure parent directory exists
             if let Some(parent) = db_path.parent() {
                 std::fs::create_dir_all(parent).ok();
             }
 
-            let database = db::Database::new(&db_path)
+            let database = db::Database::with_config(&db_path, &db::DatabaseConfig::from_env())
                 .expect("Failed to initialize database");
             let database_for_watcher = database.clone();
             app.manage(database);
-            println!("Database initialized at: {}", db_path.display());
+            info!("Database initialized at: {}", db_path.display());
 
             // Initialize artwork cache
             let artwork_cache = scanner::artwork_cache::ArtworkCache::new();
+            library::commands::load_artwork_patterns(&database_for_watcher, &artwork_cache);
             app.manage(artwork_cache);
-            println!("Artwork cache initialized (LRU cache size: 100)");
+            app.manage(library::analyze::AnalyzeState::new());
+            let background_threads = commands::stored_background_threads(app.handle());
+            app.manage(BackgroundExecutor::new(background_threads));
+            app.manage(Shutdown::new());
+            info!("Artwork cache initialized (LRU cache size: 100)");
 
             // Pass database clone to watcher manager
             let watcher = WatcherManager::new(app.handle().clone(), database_for_watcher);
             app.manage(watcher);
-            println!("Watcher manager initialized (using native Rust)");
+            info!("Watcher manager initialized (using native Rust)");
 
+            let shutdown = app.state::<Shutdown>();
             let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                tokio::time::sleep(Duration::from_secs(2)).await;
+            shutdown.spawn("watcher startup", |mut signal| async move {
+                if !signal.sleep(Duration::from_secs(2)).await {
+                    return;
+                }
                 if let Some(watcher) = app_handle.try_state::<WatcherManager>() {
                     if let Err(e) = watcher.start().await {
-                        eprintln!("Failed to start watched folder watchers: {}", e);
+                        error!("Failed to start watched folder watchers: {}", e);
                     } else {
-                        println!("Watched folder watchers started ({} active)", watcher.active_watcher_count());
+                        info!("Watched folder watchers started ({} active)", watcher.active_watcher_count());
                     }
                 }
             });
 
+            // Check the whole library for missing files, unless checked recently
+            if commands::stored_verify_on_startup(app.handle()) {
+                let app_handle = app.handle().clone();
+                shutdown.spawn("startup library check", |mut signal| async move {
+                    if !signal.sleep(Duration::from_secs(5)).await {
+                        return;
+                    }
+                    let Some(db) = app_handle.try_state::<db::Database>() else {
+                        return;
+                    };
+                    let db = db.inner().clone();
+                    let now = chrono::Utc::now().timestamp();
+                    match library::existence::startup_check_due(&db, now) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            info!("Skipping startup library check, last one was recent");
+                            return;
+                        }
+                        Err(e) => {
+                            warn!("Failed to read last library check time: {}", e);
+                            return;
+                        }
+                    }
+                    match library::commands::verify_library_files(app_handle, db).await {
+                        Ok(result) => info!(
+                            "Startup library check: {} tracks, {} missing, {} recovered",
+                            result.checked,
+                            result.marked_missing.len(),
+                            result.recovered.len()
+                        ),
+                        Err(e) => error!("Startup library check failed: {}", e),
+                    }
+                });
+            }
+
             app.manage(AudioState::new(app.handle().clone()));
-            println!("Audio engine initialized");
+            info!("Audio engine initialized");
+
+            app.manage(PlaybackManager::new());
 
             match MediaKeyManager::new(app.handle().clone()) {
                 Ok(media_keys) => {
                     app.manage(media_keys);
-                    println!("Media keys (Now Playing) initialized");
+                    info!("Media keys (Now Playing) initialized");
                 }
                 Err(e) => {
-                    eprintln!("Failed to initialize media keys: {}", e);
+                    warn!("Failed to initialize media keys: {}", e);
                 }
             }
 
             if let Err(e) = setup_global_shortcuts(app) {
-                eprintln!("Failed to setup global shortcuts: {}", e);
+                warn!("Failed to setup global shortcuts: {}", e);
             }
 
             // Start Last.fm scrobble retry background task
             let app_handle_lastfm = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                use std::time::Duration;
-
+            shutdown.spawn("scrobble retry", |mut signal| async move {
                 // Wait 30 seconds before starting background retries
-                tokio::time::sleep(Duration::from_secs(30)).await;
-                println!("Last.fm scrobble retry task started (5-minute interval)");
-
-                loop {
-                    // Wait 5 minutes between retry attempts
-                    tokio::time::sleep(Duration::from_secs(300)).await;
+                if !signal.sleep(Duration::from_secs(30)).await {
+                    return;
+                }
+                info!("Last.fm scrobble retry task started (5-minute interval)");
 
+                // Wait 5 minutes between retry attempts
+                while signal.sleep(Duration::from_secs(300)).await {
                     // Attempt to retry queued scrobbles
                     if let Some(db) = app_handle_lastfm.try_state::<db::Database>() {
                         // Check if there are any queued scrobbles
@@ -381,10 +568,10 @@ pub fn run() {
                             // Trigger retry
                             match lastfm_queue_retry(app_handle_lastfm.clone(), db.clone()).await {
                                 Ok(response) => {
-                                    println!("[lastfm] Background retry: {}", response.status);
+                                    info!("Last.fm background retry: {}", response.status);
                                 }
                                 Err(e) => {
-                                    eprintln!("[lastfm] Background retry failed: {}", e);
+                                    warn!("Last.fm background retry failed: {}", e);
                                 }
                             }
                         }
@@ -397,6 +584,12 @@ pub fn run() {
         .on_window_event(|_window, _event| {
             // Window event handler (sidecar removed in migration)
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Does nothing if the frontend already called app_shutdown
+                tauri::async_runtime::block_on(shutdown::shutdown(app));
+            }
+        });
 }
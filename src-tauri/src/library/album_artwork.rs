@@ -0,0 +1,178 @@
+//! Artwork for whole albums.
+//!
+//! The album grid shows one cover per album. Rather than loading artwork for
+//! every track, the first member track with embedded or folder artwork
+//! stands in for its album, and the result is cached under the album key, so
+//! the other tracks of the album are never read. An album override's cover
+//! image, when set and readable, is used before any track.
+
+use crate::db::{Database, DbResult, album_overrides, library};
+use crate::scanner::artwork::{Artwork, get_artwork_from_image, get_artwork_with_patterns};
+use crate::scanner::artwork_cache::ArtworkCache;
+
+/// Artwork for the album with `album_key`, `None` if no member track has any
+///
+/// See `artwork_misses::track_album_key` for how tracks group into albums.
+pub fn get_album_artwork(
+    db: &Database,
+    cache: &ArtworkCache,
+    album_key: &str,
+) -> DbResult<Option<Artwork>> {
+    if let Some(cached) = cache.get_album(album_key) {
+        return Ok(cached);
+    }
+
+    let artwork_path = db
+        .with_conn(|conn| album_overrides::get_album_override(conn, album_key))?
+        .and_then(|o| o.artwork_path);
+    if let Some(artwork) = artwork_path.as_deref().and_then(get_artwork_from_image) {
+        cache.put_album(album_key, Some(artwork.clone()));
+        return Ok(Some(artwork));
+    }
+
+    let candidates = db.with_conn(|conn| library::get_album_artwork_candidates(conn, album_key))?;
+    let patterns = cache.folder_patterns();
+
+    let mut artwork = None;
+    for (track_id, filepath) in candidates {
+        let found = get_artwork_with_patterns(&filepath, &patterns);
+        // Tracks found without artwork are passed over next time
+        let has_artwork = found.is_some();
+        let _ = db.with_conn(|conn| library::set_track_has_artwork(conn, track_id, has_artwork));
+        if found.is_some() {
+            artwork = found;
+            break;
+        }
+    }
+
+    cache.put_album(album_key, artwork.clone());
+    Ok(artwork)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use crate::db::artwork_misses::{album_key, track_album_key};
+    use std::path::Path;
+
+    fn add(db: &Database, path: &Path, artist: &str, album_artist: Option<&str>, album: &str) {
+        std::fs::write(path, b"").unwrap();
+        let metadata = TrackMetadata {
+            artist: Some(artist.to_string()),
+            album_artist: album_artist.map(str::to_string),
+            album: Some(album.to_string()),
+            ..Default::default()
+        };
+        db.with_conn(|conn| library::add_track(conn, &path.to_string_lossy(), &metadata))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_album_tracks_share_one_cache_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let untrue = dir.path().join("untrue");
+        let bare = dir.path().join("bare");
+        std::fs::create_dir_all(&untrue).unwrap();
+        std::fs::create_dir_all(&bare).unwrap();
+        std::fs::write(untrue.join("cover.jpg"), [0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+
+        let db = Database::new_in_memory().unwrap();
+        add(&db, &untrue.join("01.mp3"), "Burial", None, "Untrue");
+        add(&db, &untrue.join("02.mp3"), "burial", None, "UNTRUE");
+        add(&db, &bare.join("01.mp3"), "Burial", None, "Bare");
+        let cache = ArtworkCache::new();
+        let key = album_key("Burial", "Untrue");
+
+        let artwork = get_album_artwork(&db, &cache, &key).unwrap().unwrap();
+        assert_eq!(artwork.filename.as_deref(), Some("cover.jpg"));
+        assert_eq!(cache.album_len(), 1);
+        // No per-track entries are made along the way
+        assert!(cache.is_empty());
+
+        // Later calls come from the cache, even once the file is gone
+        std::fs::remove_file(untrue.join("cover.jpg")).unwrap();
+        let cached = get_album_artwork(&db, &cache, &key).unwrap().unwrap();
+        assert_eq!(cached.data, artwork.data);
+        assert_eq!(cache.album_len(), 1);
+
+        // An album without artwork is cached as having none
+        assert!(
+            get_album_artwork(&db, &cache, &album_key("Burial", "Bare"))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(cache.album_len(), 2);
+        assert!(get_album_artwork(&db, &cache, "unknown").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_override_artwork_takes_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("cover.jpg"), [0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+        let chosen = dir.path().join("chosen.png");
+        std::fs::write(&chosen, b"\x89PNG").unwrap();
+
+        let db = Database::new_in_memory().unwrap();
+        add(&db, &dir.path().join("01.mp3"), "Burial", None, "Untrue");
+        let key = album_key("Burial", "Untrue");
+        let cache = ArtworkCache::new();
+
+        db.with_conn(|conn| {
+            album_overrides::set_album_override(
+                conn,
+                &crate::db::AlbumOverride {
+                    album_key: key.clone(),
+                    album: None,
+                    album_artist: None,
+                    year: None,
+                    artwork_path: Some(chosen.to_string_lossy().into_owned()),
+                },
+            )
+        })
+        .unwrap();
+        let artwork = get_album_artwork(&db, &cache, &key).unwrap().unwrap();
+        assert_eq!(artwork.source, "override");
+        assert_eq!(artwork.mime_type, "image/png");
+
+        // Once cleared, the tracks' artwork is used again
+        db.with_conn(|conn| album_overrides::clear_album_override(conn, &key))
+            .unwrap();
+        cache.invalidate_album(&key);
+        let artwork = get_album_artwork(&db, &cache, &key).unwrap().unwrap();
+        assert_eq!(artwork.filename.as_deref(), Some("cover.jpg"));
+    }
+
+    #[test]
+    fn test_compilation_groups_by_album_artist() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("folder.png"), b"\x89PNG").unwrap();
+
+        let db = Database::new_in_memory().unwrap();
+        let various = Some("Various Artists");
+        add(
+            &db,
+            &dir.path().join("01.mp3"),
+            "Burial",
+            various,
+            "Kompakt 100",
+        );
+        add(
+            &db,
+            &dir.path().join("02.mp3"),
+            "Kode9",
+            various,
+            "Kompakt 100",
+        );
+
+        let key = track_album_key(various, Some("Kode9"), "Kompakt 100");
+        let candidates = db
+            .with_conn(|conn| library::get_album_artwork_candidates(conn, &key))
+            .unwrap();
+        assert_eq!(candidates.len(), 2);
+
+        let cache = ArtworkCache::new();
+        assert!(get_album_artwork(&db, &cache, &key).unwrap().is_some());
+        assert_eq!(cache.album_len(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+//! Background pass that fills in missing audio properties.
+//!
+//! Older library rows can predate duration extraction and have a NULL
+//! duration. This re-reads just the audio properties for those tracks, in
+//! small batches, similar to `update_file_sizes` for file sizes. A second
+//! pass then computes the audio features "play similar" ranks by for tracks
+//! that don't have them yet.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::background::BackgroundExecutor;
+use crate::db::{Database, DbResult, features, library};
+use crate::library::audio_features::extract_features;
+use crate::scanner::metadata::extract_metadata;
+
+/// Tracks analyzed per batch; the DB connection is released between batches
+pub const ANALYZE_BATCH_SIZE: i64 = 50;
+
+/// Progress of the analyze pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AnalyzeStatus {
+    pub running: bool,
+    /// Tracks needing analysis when the pass started
+    pub total: usize,
+    /// Tracks looked at so far
+    pub processed: usize,
+    /// Tracks whose properties were filled in
+    pub updated: usize,
+    /// Tracks skipped because the file is gone or unreadable
+    pub skipped: usize,
+    /// Tracks without audio features when the features pass started
+    pub features_total: usize,
+    /// Tracks whose audio features were computed
+    pub features_updated: usize,
+}
+
+/// Shared analyze progress, managed as Tauri state
+#[derive(Clone, Default)]
+pub struct AnalyzeState {
+    status: Arc<Mutex<AnalyzeStatus>>,
+}
+
+impl AnalyzeState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of the current progress
+    pub fn status(&self) -> AnalyzeStatus {
+        self.status.lock().clone()
+    }
+
+    /// Mark a pass as started, returning false if one is already running
+    pub fn try_start(&self) -> bool {
+        let mut status = self.status.lock();
+        if status.running {
+            return false;
+        }
+        *status = AnalyzeStatus {
+            running: true,
+            ..Default::default()
+        };
+        true
+    }
+
+    fn update(&self, f: impl FnOnce(&mut AnalyzeStatus)) {
+        f(&mut self.status.lock());
+    }
+}
+
+/// Fill in missing durations, then missing audio features, for present tracks
+///
+/// `on_batch` is called with the IDs whose durations were updated in each
+/// batch so callers can emit one coalesced modified event per batch; features
+/// aren't part of the track, so they don't trigger it. The caller must have
+/// called `AnalyzeState::try_start` first; the state is marked finished on
+/// return. Files are read as jobs on `executor`.
+pub fn analyze_missing_props(
+    db: &Database,
+    state: &AnalyzeState,
+    executor: &BackgroundExecutor,
+    mut on_batch: impl FnMut(Vec<i64>),
+) -> DbResult<AnalyzeStatus> {
+    let result = run_batches(db, state, executor, &mut on_batch)
+        .and_then(|_| run_feature_batches(db, state, executor));
+    state.update(|s| s.running = false);
+    result.map(|_| state.status())
+}
+
+fn run_batches(
+    db: &Database,
+    state: &AnalyzeState,
+    executor: &BackgroundExecutor,
+    on_batch: &mut impl FnMut(Vec<i64>),
+) -> DbResult<()> {
+    let total = db.with_conn(library::count_tracks_missing_audio_props)?;
+    state.update(|s| s.total = total as usize);
+
+    let mut after_id = 0;
+    loop {
+        let batch = db.with_conn(|conn| {
+            library::get_tracks_missing_audio_props(conn, after_id, ANALYZE_BATCH_SIZE)
+        })?;
+        let Some(&(last_id, _)) = batch.last() else {
+            break;
+        };
+        after_id = last_id;
+        let batch_len = batch.len();
+
+        // Read properties without holding a connection
+        let durations: Vec<(i64, Option<f64>)> = executor.map(batch, |(id, filepath)| {
+            let duration = Path::new(&filepath)
+                .exists()
+                .then(|| extract_metadata(&filepath).ok())
+                .flatten()
+                .and_then(|m| m.duration);
+            (id, duration)
+        });
+
+        let updated_ids = db.transaction(|conn| {
+            let mut ids = Vec::new();
+            for (id, duration) in &durations {
+                if let Some(duration) = duration
+                    && library::update_track_duration(conn, *id, *duration)?
+                {
+                    ids.push(*id);
+                }
+            }
+            Ok(ids)
+        })?;
+
+        state.update(|s| {
+            s.processed += batch_len;
+            s.updated += updated_ids.len();
+            s.skipped += batch_len - updated_ids.len();
+        });
+
+        if !updated_ids.is_empty() {
+            on_batch(updated_ids);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_feature_batches(
+    db: &Database,
+    state: &AnalyzeState,
+    executor: &BackgroundExecutor,
+) -> DbResult<()> {
+    let total = db.with_conn(features::count_tracks_missing_features)?;
+    state.update(|s| s.features_total = total as usize);
+
+    let mut after_id = 0;
+    loop {
+        let batch = db.with_conn(|conn| {
+            features::get_tracks_missing_features(conn, after_id, ANALYZE_BATCH_SIZE)
+        })?;
+        let Some(&(last_id, _)) = batch.last() else {
+            break;
+        };
+        after_id = last_id;
+
+        // Decoding dominates, so it runs in parallel and without a connection
+        let extracted: Vec<_> = executor
+            .map(batch, |(id, filepath)| {
+                Some((id, extract_features(Path::new(&filepath))?))
+            })
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let updated = db.transaction(|conn| {
+            let mut updated = 0;
+            for (id, track_features) in &extracted {
+                if features::set_track_features(conn, *id, track_features)? {
+                    updated += 1;
+                }
+            }
+            Ok(updated)
+        })?;
+        state.update(|s| s.features_updated += updated);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+
+    /// Write an 8 kHz mono 16-bit PCM WAV file
+    fn write_wav(path: &Path, samples: &[i16]) {
+        let sample_rate: u32 = 8000;
+        let data_len = samples.len() as u32 * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    /// Write a one-second silent WAV file
+    fn write_silent_wav(path: &Path) {
+        write_wav(path, &[0; 8000]);
+    }
+
+    #[test]
+    fn test_analyze_populates_null_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let wav = dir.path().join("silence.wav");
+        write_silent_wav(&wav);
+
+        let db = Database::new_in_memory().unwrap();
+        let track_id = db
+            .with_conn(|conn| {
+                library::add_track(conn, &wav.to_string_lossy(), &TrackMetadata::default())
+            })
+            .unwrap();
+        let gone_id = db
+            .with_conn(|conn| {
+                library::add_track(conn, "/music/gone.wav", &TrackMetadata::default())
+            })
+            .unwrap();
+
+        let state = AnalyzeState::new();
+        assert!(state.try_start());
+
+        let mut batches = Vec::new();
+        let executor = BackgroundExecutor::new(2);
+        let status =
+            analyze_missing_props(&db, &state, &executor, |ids| batches.push(ids)).unwrap();
+
+        assert!(!status.running);
+        assert_eq!(status.total, 2);
+        assert_eq!(status.processed, 2);
+        assert_eq!(status.updated, 1);
+        assert_eq!(status.skipped, 1);
+        assert_eq!(batches, vec![vec![track_id]]);
+
+        let track = db
+            .with_conn(|conn| library::get_track_by_id(conn, track_id))
+            .unwrap()
+            .unwrap();
+        let duration = track.duration.unwrap();
+        assert!((duration - 1.0).abs() < 0.01, "duration was {}", duration);
+
+        let gone = db
+            .with_conn(|conn| library::get_track_by_id(conn, gone_id))
+            .unwrap()
+            .unwrap();
+        assert!(gone.duration.is_none());
+    }
+
+    #[test]
+    fn test_analyze_stores_audio_features() {
+        let dir = tempfile::tempdir().unwrap();
+        let tone = dir.path().join("tone.wav");
+        let samples: Vec<i16> = (0..8000 * 3)
+            .map(|i| (8000.0 * (std::f64::consts::TAU * 440.0 * i as f64 / 8000.0).sin()) as i16)
+            .collect();
+        write_wav(&tone, &samples);
+        let silence = dir.path().join("silence.wav");
+        write_silent_wav(&silence);
+
+        let db = Database::new_in_memory().unwrap();
+        let (tone_id, silence_id) = db
+            .with_conn(|conn| {
+                let metadata = TrackMetadata::default();
+                Ok((
+                    library::add_track(conn, &tone.to_string_lossy(), &metadata)?,
+                    library::add_track(conn, &silence.to_string_lossy(), &metadata)?,
+                ))
+            })
+            .unwrap();
+
+        let state = AnalyzeState::new();
+        assert!(state.try_start());
+        let executor = BackgroundExecutor::new(2);
+        let status = analyze_missing_props(&db, &state, &executor, |_| {}).unwrap();
+
+        assert_eq!(status.features_total, 2);
+        assert_eq!(status.features_updated, 1);
+        let stored = db
+            .with_conn(|conn| features::get_track_features(conn, tone_id))
+            .unwrap()
+            .unwrap();
+        assert!(
+            (stored.centroid_hz - 440.0).abs() < 40.0,
+            "centroid {}",
+            stored.centroid_hz
+        );
+        // Silence has nothing to describe
+        let silent = db.with_conn(|conn| features::get_track_features(conn, silence_id));
+        assert_eq!(silent.unwrap(), None);
+    }
+
+    #[test]
+    fn test_analyze_skips_missing_tracks() {
+        let db = Database::new_in_memory().unwrap();
+        db.with_conn(|conn| {
+            let id = library::add_track(conn, "/music/missing.mp3", &TrackMetadata::default())?;
+            library::mark_track_missing(conn, id)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let state = AnalyzeState::new();
+        assert!(state.try_start());
+        let executor = BackgroundExecutor::new(2);
+        let status = analyze_missing_props(&db, &state, &executor, |_| {}).unwrap();
+
+        assert_eq!(status.total, 0);
+        assert_eq!(status.processed, 0);
+    }
+
+    #[test]
+    fn test_try_start_rejects_concurrent_pass() {
+        let state = AnalyzeState::new();
+        assert!(state.try_start());
+        assert!(!state.try_start());
+    }
+}
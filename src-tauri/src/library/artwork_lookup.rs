@@ -0,0 +1,156 @@
+//! Online artwork lookups, rate limited and backed by a negative cache.
+//!
+//! No online artwork source exists yet; artwork comes only from tags and
+//! folder images. `lookup_album_artwork` is the entry point a future fetcher,
+//! such as Last.fm or the Cover Art Archive, must go through. It skips albums
+//! that recently came back empty, so they don't use up the shared rate limit.
+//! Found and not-found results update the negative cache. Errors such as
+//! timeouts don't, so a flaky network doesn't hide an album's cover for days.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::db::{Database, DbError, artwork_misses};
+use crate::lastfm::rate_limiter::RateLimiter;
+
+/// Result of an online artwork lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupOutcome<T> {
+    Found(T),
+    /// The source had no artwork for the album
+    NotFound,
+    /// Skipped without a request because the album recently had no artwork
+    Skipped {
+        retry_after: i64,
+    },
+}
+
+/// Look up an album's artwork with `fetch`, unless it's in the negative cache
+///
+/// `fetch` resolves to `Ok(None)` when the source has no artwork, which
+/// skips the album for `miss_ttl`. Each call that reaches `fetch` first
+/// waits its turn on `limiter`.
+pub async fn lookup_album_artwork<T, E, F, Fut>(
+    db: &Database,
+    limiter: &RateLimiter,
+    artist: &str,
+    album: &str,
+    miss_ttl: Duration,
+    fetch: F,
+) -> Result<LookupOutcome<T>, E>
+where
+    E: From<DbError>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Option<T>, E>>,
+{
+    let key = artwork_misses::album_key(artist, album);
+    let now = chrono::Utc::now().timestamp();
+    let retry_after = db.with_conn(|conn| artwork_misses::get_retry_after(conn, &key, now))?;
+    if let Some(retry_after) = retry_after {
+        return Ok(LookupOutcome::Skipped { retry_after });
+    }
+
+    limiter.wait_if_needed().await;
+    match fetch().await? {
+        Some(artwork) => {
+            db.with_conn(|conn| artwork_misses::clear_miss(conn, &key))?;
+            Ok(LookupOutcome::Found(artwork))
+        }
+        None => {
+            let now = chrono::Utc::now().timestamp();
+            db.with_conn(|conn| artwork_misses::record_miss(conn, &key, now, miss_ttl))?;
+            Ok(LookupOutcome::NotFound)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug)]
+    enum TestError {
+        Db,
+        Network,
+    }
+
+    impl From<DbError> for TestError {
+        fn from(_: DbError) -> Self {
+            TestError::Db
+        }
+    }
+
+    async fn lookup(
+        db: &Database,
+        limiter: &RateLimiter,
+        calls: &AtomicUsize,
+        ttl: Duration,
+        result: Result<Option<&'static str>, TestError>,
+    ) -> Result<LookupOutcome<&'static str>, TestError> {
+        lookup_album_artwork(db, limiter, "Burial", "Untrue", ttl, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            result
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_cached_miss_suppresses_second_request() {
+        let db = Database::new_in_memory().unwrap();
+        let limiter = RateLimiter::new();
+        let calls = AtomicUsize::new(0);
+        let ttl = Duration::from_secs(3600);
+
+        let first = lookup(&db, &limiter, &calls, ttl, Ok(None)).await.unwrap();
+        assert_eq!(first, LookupOutcome::NotFound);
+
+        let second = lookup(&db, &limiter, &calls, ttl, Ok(Some("cover")))
+            .await
+            .unwrap();
+        assert!(matches!(second, LookupOutcome::Skipped { .. }));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(limiter.request_count().await, 1);
+
+        // Same album under different casing is skipped too
+        let outcome = lookup_album_artwork(&db, &limiter, "burial ", "UNTRUE", ttl, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, TestError>(Some("cover"))
+        })
+        .await
+        .unwrap();
+        assert!(matches!(outcome, LookupOutcome::Skipped { .. }));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Clearing the cache allows a re-check
+        db.with_conn(artwork_misses::clear_misses).unwrap();
+        let third = lookup(&db, &limiter, &calls, ttl, Ok(Some("cover")))
+            .await
+            .unwrap();
+        assert_eq!(third, LookupOutcome::Found("cover"));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_expired_miss_and_errors_are_retried() {
+        let db = Database::new_in_memory().unwrap();
+        let limiter = RateLimiter::new();
+        let calls = AtomicUsize::new(0);
+
+        // A zero TTL expires immediately
+        let first = lookup(&db, &limiter, &calls, Duration::ZERO, Ok(None))
+            .await
+            .unwrap();
+        assert_eq!(first, LookupOutcome::NotFound);
+        let ttl = Duration::from_secs(3600);
+        let err = lookup(&db, &limiter, &calls, ttl, Err(TestError::Network)).await;
+        assert!(matches!(err, Err(TestError::Network)));
+
+        // The failed request didn't record a miss
+        let found = lookup(&db, &limiter, &calls, ttl, Ok(Some("cover")))
+            .await
+            .unwrap();
+        assert_eq!(found, LookupOutcome::Found("cover"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,508 @@
+//! Local audio features and "play similar" ranking.
+//!
+//! Each track gets four descriptors computed from its decoded samples: tempo,
+//! key, average loudness, and spectral centroid. They are stored by the
+//! analyze pass so `queue_play_similar_local` can rank the library by
+//! distance to a seed track without any online service.
+//!
+//! Only the opening `ANALYSIS_SECS` of a track are decoded. That keeps the
+//! pass affordable on large libraries and is usually representative enough
+//! for picking neighbours.
+
+use rodio::{Decoder, Source};
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::db::TrackFeatures;
+use crate::scanner::is_scan_only_file;
+
+/// Seconds of audio decoded per track
+pub const ANALYSIS_SECS: usize = 60;
+
+/// FFT size in samples; must be a power of two
+const FRAME_LEN: usize = 2048;
+
+/// Samples between the starts of consecutive frames
+const HOP_LEN: usize = 1024;
+
+/// Tracks quieter than this on average are treated as silence
+const SILENCE_DB: f64 = -70.0;
+
+/// Tempo search range; tempos outside it are reported at half or double
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 180.0;
+
+/// Tempo the octave-error prior is centred on
+const PREFERRED_BPM: f64 = 120.0;
+
+/// Frequency range whose energy counts towards the key estimate
+const CHROMA_MIN_HZ: f64 = 110.0;
+const CHROMA_MAX_HZ: f64 = 2000.0;
+
+/// Krumhansl-Kessler key profiles, starting at the tonic
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Key distance given to a pair where either key is unknown, half the maximum
+const UNKNOWN_KEY_DISTANCE: f64 = 0.5;
+
+/// Decode the start of a file and compute its features
+///
+/// Returns `None` for files that can't be decoded and for silence.
+pub fn extract_features(path: &Path) -> Option<TrackFeatures> {
+    if is_scan_only_file(path) {
+        return None;
+    }
+    let file = File::open(path).ok()?;
+    let source = Decoder::new(BufReader::new(file)).ok()?;
+    let channels = usize::from(source.channels()).max(1);
+    let sample_rate = source.sample_rate();
+
+    let window = sample_rate as usize * channels * ANALYSIS_SECS;
+    let interleaved: Vec<f32> = source.take(window).collect();
+    let mono: Vec<f32> = interleaved
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect();
+
+    features_from_samples(&mono, sample_rate)
+}
+
+/// Compute features from mono samples
+///
+/// Returns `None` when there's less than one frame of audio or it's silent.
+/// Tempo and key are `None` when nothing rhythmic or tonal stands out.
+pub fn features_from_samples(samples: &[f32], sample_rate: u32) -> Option<TrackFeatures> {
+    if samples.len() < FRAME_LEN || sample_rate == 0 {
+        return None;
+    }
+    let mean_square =
+        samples.iter().map(|&s| f64::from(s).powi(2)).sum::<f64>() / samples.len() as f64;
+    let loudness_db = 10.0 * mean_square.log10();
+    // Digital silence comes out as -inf
+    if loudness_db <= SILENCE_DB {
+        return None;
+    }
+
+    let rate = f64::from(sample_rate);
+    let bin_hz = rate / FRAME_LEN as f64;
+    let window: Vec<f64> = (0..FRAME_LEN)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f64 / FRAME_LEN as f64).cos())
+        .collect();
+
+    let mut re = vec![0.0; FRAME_LEN];
+    let mut im = vec![0.0; FRAME_LEN];
+    let mut previous = vec![0.0; FRAME_LEN / 2];
+    let mut flux = Vec::with_capacity(samples.len() / HOP_LEN);
+    let mut chroma = [0.0; 12];
+    let mut centroid_sum = 0.0;
+    let mut centroid_frames = 0usize;
+
+    for frame in samples.windows(FRAME_LEN).step_by(HOP_LEN) {
+        for (i, (&sample, weight)) in frame.iter().zip(&window).enumerate() {
+            re[i] = f64::from(sample) * weight;
+            im[i] = 0.0;
+        }
+        fft(&mut re, &mut im);
+
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        let mut rise = 0.0;
+        for bin in 1..FRAME_LEN / 2 {
+            let magnitude = re[bin].hypot(im[bin]);
+            let freq = bin as f64 * bin_hz;
+            weighted += freq * magnitude;
+            total += magnitude;
+
+            // Compressed so quiet onsets still register next to loud ones
+            let level = (1.0 + 100.0 * magnitude).ln();
+            rise += (level - previous[bin]).max(0.0);
+            previous[bin] = level;
+
+            if (CHROMA_MIN_HZ..CHROMA_MAX_HZ).contains(&freq) {
+                let midi = 69.0 + 12.0 * (freq / 440.0).log2();
+                chroma[(midi.round() as i64).rem_euclid(12) as usize] += magnitude * magnitude;
+            }
+        }
+        if total > f64::EPSILON {
+            centroid_sum += weighted / total;
+            centroid_frames += 1;
+        }
+        flux.push(rise);
+    }
+    // The first frame rises from nothing, which isn't an onset
+    if let Some(first) = flux.first_mut() {
+        *first = 0.0;
+    }
+
+    if centroid_frames == 0 {
+        return None;
+    }
+    Some(TrackFeatures {
+        bpm: estimate_bpm(&flux, rate / HOP_LEN as f64),
+        key: estimate_key(&chroma),
+        loudness_db,
+        centroid_hz: centroid_sum / centroid_frames as f64,
+    })
+}
+
+/// In-place radix-2 FFT; both slices must have the same power-of-two length
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Tempo from the autocorrelation of the onset envelope
+///
+/// A beat every `n` frames also correlates at `2n`, so lags are weighted
+/// towards `PREFERRED_BPM` to settle on the likelier octave.
+fn estimate_bpm(flux: &[f64], frames_per_sec: f64) -> Option<f64> {
+    let min_lag = ((60.0 * frames_per_sec / MAX_BPM).floor() as usize).max(2);
+    let max_lag = (60.0 * frames_per_sec / MIN_BPM).ceil() as usize;
+    if flux.len() <= 2 * max_lag {
+        return None;
+    }
+    let mean = flux.iter().sum::<f64>() / flux.len() as f64;
+    let onsets: Vec<f64> = flux.iter().map(|f| f - mean).collect();
+    let correlation = |lag: usize| {
+        onsets
+            .iter()
+            .zip(&onsets[lag..])
+            .map(|(a, b)| a * b)
+            .sum::<f64>()
+            / (onsets.len() - lag) as f64
+    };
+    let to_bpm = |lag: f64| 60.0 * frames_per_sec / lag;
+
+    let (lag, score) = (min_lag..=max_lag)
+        .map(|lag| {
+            let octaves = (to_bpm(lag as f64) / PREFERRED_BPM).log2();
+            (lag, correlation(lag) * (-0.5 * octaves * octaves).exp())
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if score <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation between neighbouring lags for sub-frame precision
+    let (before, peak, after) = (correlation(lag - 1), correlation(lag), correlation(lag + 1));
+    let curve = before - 2.0 * peak + after;
+    let offset = if curve < 0.0 {
+        (0.5 * (before - after) / curve).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    Some(to_bpm(lag as f64 + offset).clamp(MIN_BPM, MAX_BPM))
+}
+
+/// Key from a pitch-class energy profile: 0-11 are C to B major, 12-23 minor
+fn estimate_key(chroma: &[f64; 12]) -> Option<u8> {
+    if chroma.iter().sum::<f64>() <= f64::EPSILON {
+        return None;
+    }
+    let mut best = None;
+    let mut best_score = f64::NEG_INFINITY;
+    for (mode, profile) in [MAJOR_PROFILE, MINOR_PROFILE].iter().enumerate() {
+        for tonic in 0..12 {
+            let rotated: Vec<f64> = (0..12).map(|i| chroma[(tonic + i) % 12]).collect();
+            let score = pearson(&rotated, profile);
+            if score > best_score {
+                best_score = score;
+                best = Some((tonic + 12 * mode) as u8);
+            }
+        }
+    }
+    best
+}
+
+fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a <= 0.0 || var_b <= 0.0 {
+        return 0.0;
+    }
+    cov / (var_a * var_b).sqrt()
+}
+
+/// Position of a key on the circle of fifths, as a point on the unit circle
+///
+/// Minor keys sit with their relative major, so A minor and C major share a
+/// point and neighbouring keys are close.
+fn key_point(key: u8) -> (f64, f64) {
+    let tonic = u32::from(key % 12);
+    let major = if key >= 12 { (tonic + 3) % 12 } else { tonic };
+    let angle = 2.0 * PI * f64::from(major * 7 % 12) / 12.0;
+    (angle.cos(), angle.sin())
+}
+
+/// Mean and standard deviation of one feature across the library
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Spread {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Spread {
+    fn new(values: impl Iterator<Item = f64>) -> Self {
+        let values: Vec<f64> = values.collect();
+        if values.is_empty() {
+            return Self {
+                mean: 0.0,
+                std_dev: 1.0,
+            };
+        }
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+        let std_dev = variance.sqrt();
+        Self {
+            mean,
+            // A feature every track shares can't tell them apart anyway
+            std_dev: if std_dev > f64::EPSILON { std_dev } else { 1.0 },
+        }
+    }
+
+    /// The value in standard deviations from the mean; unknown is the mean
+    fn z(&self, value: Option<f64>) -> f64 {
+        value.map_or(0.0, |v| (v - self.mean) / self.std_dev)
+    }
+}
+
+/// Scales that put every feature on a comparable footing
+///
+/// Tempo and centroid are compared on a log scale, since doubling matters
+/// the same at any value, and every continuous feature is z-scored against
+/// the library so no unit dominates the distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureScale {
+    bpm: Spread,
+    loudness: Spread,
+    centroid: Spread,
+}
+
+impl FeatureScale {
+    pub fn new<'a>(features: impl IntoIterator<Item = &'a TrackFeatures> + Clone) -> Self {
+        let all = || features.clone().into_iter();
+        Self {
+            bpm: Spread::new(all().filter_map(|f| f.bpm).map(f64::log2)),
+            loudness: Spread::new(all().map(|f| f.loudness_db)),
+            centroid: Spread::new(all().map(|f| f.centroid_hz.max(1.0).log2())),
+        }
+    }
+
+    /// Distance between two tracks in the normalized feature space
+    pub fn distance(&self, a: &TrackFeatures, b: &TrackFeatures) -> f64 {
+        let bpm = |f: &TrackFeatures| self.bpm.z(f.bpm.map(f64::log2));
+        let loudness = |f: &TrackFeatures| self.loudness.z(Some(f.loudness_db));
+        let centroid = |f: &TrackFeatures| self.centroid.z(Some(f.centroid_hz.max(1.0).log2()));
+
+        // Half the chord between the two points, so 0 to 1
+        let key = match (a.key, b.key) {
+            (Some(a), Some(b)) => {
+                let ((ax, ay), (bx, by)) = (key_point(a), key_point(b));
+                0.5 * (ax - bx).hypot(ay - by)
+            }
+            _ => UNKNOWN_KEY_DISTANCE,
+        };
+
+        ((bpm(a) - bpm(b)).powi(2)
+            + (loudness(a) - loudness(b)).powi(2)
+            + (centroid(a) - centroid(b)).powi(2)
+            + key.powi(2))
+        .sqrt()
+    }
+}
+
+/// IDs of the `count` tracks closest to the seed, nearest first
+///
+/// The seed is never returned, even if it is in `library`. Ties go to the
+/// lower ID so the ranking is stable.
+pub fn rank_similar(
+    seed_id: i64,
+    seed: &TrackFeatures,
+    library: &[(i64, TrackFeatures)],
+    count: usize,
+) -> Vec<i64> {
+    let scale = FeatureScale::new(library.iter().map(|(_, features)| features));
+    let mut ranked: Vec<(f64, i64)> = library
+        .iter()
+        .filter(|(id, _)| *id != seed_id)
+        .map(|(id, features)| (scale.distance(seed, features), *id))
+        .collect();
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    ranked.into_iter().take(count).map(|(_, id)| id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 22050;
+
+    fn features(bpm: f64, key: u8, loudness_db: f64, centroid_hz: f64) -> TrackFeatures {
+        TrackFeatures {
+            bpm: Some(bpm),
+            key: Some(key),
+            loudness_db,
+            centroid_hz,
+        }
+    }
+
+    fn sine(freq: f64, secs: usize) -> Vec<f32> {
+        (0..RATE as usize * secs)
+            .map(|i| (0.5 * (2.0 * PI * freq * i as f64 / f64::from(RATE)).sin()) as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_distance_is_normalized() {
+        let tracks = [
+            features(90.0, 0, -20.0, 1000.0),
+            features(120.0, 0, -14.0, 2000.0),
+            features(150.0, 0, -8.0, 4000.0),
+        ];
+        let scale = FeatureScale::new(&tracks);
+        let [slow, mid, fast] = &tracks;
+
+        assert_eq!(scale.distance(mid, mid), 0.0);
+        assert_eq!(scale.distance(slow, fast), scale.distance(fast, slow));
+        assert!(scale.distance(slow, mid) < scale.distance(slow, fast));
+
+        // A change of one standard deviation weighs the same in any unit:
+        // centroid moves in thousands of Hz, loudness in single decibels
+        let spread = |values: [f64; 3]| Spread::new(values.into_iter());
+        let loud = spread([-20.0, -14.0, -8.0]).std_dev;
+        let bright = spread([1000f64.log2(), 2000f64.log2(), 4000f64.log2()]).std_dev;
+        let louder = features(120.0, 0, -14.0 + loud, 2000.0);
+        let brighter = features(120.0, 0, -14.0, 2000.0 * 2f64.powf(bright));
+        assert!((scale.distance(mid, &louder) - scale.distance(mid, &brighter)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_key_distance_follows_circle_of_fifths() {
+        let tracks = [features(120.0, 0, -14.0, 2000.0)];
+        let scale = FeatureScale::new(&tracks);
+        let in_key = |key| features(120.0, key, -14.0, 2000.0);
+        let c_major = in_key(0);
+
+        // A minor is C major's relative minor
+        assert!(scale.distance(&c_major, &in_key(21)) < 1e-9);
+        // G major is a fifth away, F# major as far as it gets
+        let g_major = scale.distance(&c_major, &in_key(7));
+        let f_sharp_major = scale.distance(&c_major, &in_key(6));
+        assert!(g_major > 0.0 && g_major < f_sharp_major);
+        assert!((f_sharp_major - 1.0).abs() < 1e-9);
+
+        let unknown = TrackFeatures {
+            key: None,
+            ..c_major
+        };
+        assert_eq!(scale.distance(&c_major, &unknown), UNKNOWN_KEY_DISTANCE);
+    }
+
+    #[test]
+    fn test_rank_similar_excludes_seed() {
+        let seed = features(120.0, 0, -14.0, 2000.0);
+        let library = vec![
+            (1, seed),
+            (2, features(170.0, 6, -4.0, 6000.0)),
+            (3, features(122.0, 0, -13.0, 2100.0)),
+            (4, features(100.0, 7, -18.0, 1500.0)),
+        ];
+
+        assert_eq!(rank_similar(1, &seed, &library, 10), vec![3, 4, 2]);
+        assert_eq!(rank_similar(1, &seed, &library, 1), vec![3]);
+        // Not even an identical twin of the seed is the seed
+        assert!(!rank_similar(1, &seed, &library, 10).contains(&1));
+        assert!(rank_similar(1, &seed, &library[..1], 10).is_empty());
+    }
+
+    #[test]
+    fn test_features_of_a_tone() {
+        let tone = features_from_samples(&sine(440.0, 4), RATE).unwrap();
+
+        // Hann leakage spreads a little energy around the tone
+        assert!(
+            (tone.centroid_hz - 440.0).abs() < 40.0,
+            "centroid {}",
+            tone.centroid_hz
+        );
+        // A half-scale sine has an RMS of 0.5 / sqrt(2), about -9 dBFS
+        assert!(
+            (tone.loudness_db + 9.03).abs() < 0.1,
+            "loudness {}",
+            tone.loudness_db
+        );
+        assert_eq!(tone.key, Some(9), "a lone A reads as A major");
+
+        let chord = |freqs: [f64; 3]| -> Vec<f32> {
+            let tones: Vec<Vec<f32>> = freqs.iter().map(|&f| sine(f, 4)).collect();
+            (0..tones[0].len())
+                .map(|i| tones.iter().map(|t| t[i]).sum::<f32>() / 3.0)
+                .collect()
+        };
+        let key = |freqs| features_from_samples(&chord(freqs), RATE).unwrap().key;
+        assert_eq!(key([261.63, 329.63, 392.0]), Some(0), "C major triad");
+        assert_eq!(key([220.0, 261.63, 329.63]), Some(21), "A minor triad");
+
+        assert!(features_from_samples(&vec![0.0; RATE as usize], RATE).is_none());
+        assert!(features_from_samples(&[0.5; 100], RATE).is_none());
+    }
+
+    #[test]
+    fn test_tempo_of_a_click_track() {
+        let mut clicks = vec![0.0f32; RATE as usize * 20];
+        let beat = RATE as usize / 2;
+        for start in (0..clicks.len()).step_by(beat) {
+            for (i, sample) in clicks[start..].iter_mut().take(200).enumerate() {
+                *sample = if i % 2 == 0 { 0.8 } else { -0.8 };
+            }
+        }
+
+        let bpm = features_from_samples(&clicks, RATE).unwrap().bpm.unwrap();
+        assert!((bpm - 120.0).abs() < 3.0, "bpm {}", bpm);
+    }
+}
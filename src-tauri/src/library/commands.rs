@@ -3,17 +3,39 @@
 //! These commands expose library operations to the frontend,
 //! replacing the Python FastAPI library routes.
 
+use rayon::prelude::*;
+use rusqlite::Connection;
+use std::collections::HashSet;
 use std::path::Path;
 use tauri::{AppHandle, State};
+use tracing::{error, info, warn};
 
+use crate::background::BackgroundExecutor;
+use crate::commands::CommandError;
+use crate::db::date_filter::DateFilter;
 use crate::db::{
-    library, Database, LibraryStats, SortOrder, Track, TrackMetadata,
+    album_overrides, artwork_misses, bookmarks, changes, history, library, playlists, settings,
+    tags, Album, AlbumOverride, Bookmark, Chapter, Database, DbResult, IncompleteAlbum,
+    LibraryChanges, LibraryStats, SizeEntry, SizeGroup, SortOrder, TagCount, TopKind, TopList,
+    TopRange, Track, TrackMetadata,
 };
-use crate::events::{EventEmitter, LibraryUpdatedEvent};
-use crate::scanner::artwork::Artwork;
-use crate::scanner::artwork_cache::ArtworkCache;
-use crate::scanner::fingerprint::{compute_content_hash, FileFingerprint};
+use crate::events::{
+    EventEmitter, ExistenceProgressEvent, LibraryUpdatedEvent, VerifyProgressEvent,
+};
+use crate::library::album_artwork;
+use crate::library::analyze::{self, AnalyzeState, AnalyzeStatus};
+use crate::library::existence::{self, ExistenceCheckResult};
+use crate::library::fuzzy;
+use crate::library::media::{self, MediaRange};
+use crate::library::path_status::{self, PathStatus};
+use crate::library::reconcile::{self, ReconcilePlan};
+use crate::library::replace;
+use crate::library::verify::{self, UnplayableTrack};
+use crate::scanner::artwork::{parse_artwork_patterns, Artwork, ARTWORK_PATTERNS_SETTING};
+use crate::scanner::artwork_cache::{ArtworkCache, ArtworkCacheStats};
+use crate::scanner::fingerprint::FileFingerprint;
 use crate::scanner::metadata::extract_metadata;
+use crate::scanner::MAX_FS_CONCURRENCY;
 
 /// Response for paginated library queries
 #[derive(Clone, serde::Serialize)]
@@ -31,6 +53,34 @@ pub struct MissingTracksResponse {
     pub total: i64,
 }
 
+/// Library track whose file no longer exists on disk
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct OrphanTrack {
+    pub id: i64,
+    pub filepath: String,
+}
+
+/// Response for orphan checks
+#[derive(Clone, serde::Serialize)]
+pub struct OrphansResponse {
+    pub orphans: Vec<OrphanTrack>,
+    /// Number of library tracks checked
+    pub checked: i64,
+}
+
+/// Result of relocating every track under a folder prefix
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct RelocatePrefixResult {
+    /// Tracks moved to a file that exists under the new prefix
+    pub relocated: i64,
+    /// Tracks left untouched because no file exists under the new prefix
+    pub still_missing: i64,
+    /// Tracks left untouched because another track already uses the new path
+    pub conflicts: i64,
+    #[serde(skip)]
+    pub relocated_ids: Vec<i64>,
+}
+
 /// Get all tracks with filtering, sorting, and pagination
 #[allow(clippy::too_many_arguments)]
 #[tauri::command]
@@ -39,12 +89,17 @@ pub fn library_get_all(
     search: Option<String>,
     artist: Option<String>,
     album: Option<String>,
+    tags: Option<Vec<String>>,
+    tag_match: Option<String>,
+    favorites_only: Option<bool>,
+    min_rating: Option<u8>,
+    date_filters: Option<Vec<DateFilter>>,
     sort_by: Option<String>,
     sort_order: Option<String>,
     limit: Option<i64>,
     offset: Option<i64>,
-) -> Result<LibraryResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<LibraryResponse, CommandError> {
+    let conn = db.conn()?;
 
     // Update file sizes for tracks that have 0 (background operation)
     let _ = library::update_file_sizes(&conn);
@@ -53,6 +108,14 @@ pub fn library_get_all(
         search,
         artist,
         album,
+        tags: tags.unwrap_or_default(),
+        tag_match: tag_match
+            .as_ref()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default(),
+        favorites_only: favorites_only.unwrap_or(false),
+        min_rating,
+        date_filters: date_filters.unwrap_or_default(),
         sort_by: sort_by
             .as_ref()
             .and_then(|s| s.parse().ok())
@@ -71,7 +134,7 @@ pub fn library_get_all(
         offset: offset.unwrap_or(0),
     };
 
-    let result = library::get_all_tracks(&conn, &query).map_err(|e| e.to_string())?;
+    let result = library::get_all_tracks(&conn, &query)?;
 
     Ok(LibraryResponse {
         tracks: result.items,
@@ -81,18 +144,228 @@ pub fn library_get_all(
     })
 }
 
+/// Search the library with typo-tolerant matching, ranked by relevance
+///
+/// Title matches rank above artist matches, which rank above album matches.
+#[tauri::command]
+pub fn library_search_fuzzy(
+    db: State<'_, Database>,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<Track>, CommandError> {
+    let limit = limit.unwrap_or(50).clamp(1, 500) as usize;
+    let conn = db.conn()?;
+    fuzzy::search(&conn, &query, limit).map_err(CommandError::from)
+}
+
 /// Get library statistics
+///
+/// Cached between library changes, so calling this on every navigation only
+/// recounts after tracks were added, changed, or removed.
+#[tauri::command]
+pub fn library_get_stats(db: State<'_, Database>) -> Result<LibraryStats, CommandError> {
+    let conn = db.conn()?;
+    library::get_library_stats(&conn).map_err(CommandError::from)
+}
+
+/// Recount library statistics from scratch, replacing the cached ones
 #[tauri::command]
-pub fn library_get_stats(db: State<'_, Database>) -> Result<LibraryStats, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    library::get_library_stats(&conn).map_err(|e| e.to_string())
+pub fn library_recompute_stats(db: State<'_, Database>) -> Result<LibraryStats, CommandError> {
+    let conn = db.conn()?;
+    library::recompute_library_stats(&conn).map_err(CommandError::from)
+}
+
+/// Get disk usage grouped by artist, album, or folder, largest first
+///
+/// Folders are the top-level directories under each watched folder, or the
+/// parent directory of files outside them. Sizes not yet recorded are read
+/// from disk first.
+#[tauri::command]
+pub fn library_get_size_breakdown(
+    db: State<'_, Database>,
+    group_by: String,
+) -> Result<Vec<SizeEntry>, CommandError> {
+    let group: SizeGroup = group_by
+        .parse()
+        .map_err(|_| {
+            CommandError::invalid_input(format!(
+                "Invalid group '{}', expected artist, album, or folder",
+                group_by
+            ))
+        })?;
+
+    let conn = db.conn()?;
+    library::update_file_sizes(&conn)?;
+    library::get_size_breakdown(&conn, group).map_err(CommandError::from)
+}
+
+/// Find album discs with gaps in their track numbers
+///
+/// Each entry lists the expected track total, how many tracks are present,
+/// and which track numbers are missing.
+#[tauri::command]
+pub fn library_find_incomplete_albums(
+    db: State<'_, Database>,
+) -> Result<Vec<IncompleteAlbum>, CommandError> {
+    let conn = db.conn()?;
+    library::find_incomplete_albums(&conn).map_err(CommandError::from)
+}
+
+/// Get the tracks changed or deleted since a library change sequence
+///
+/// Pass the `change_seq` of the last `library:updated` event (or the `seq` of
+/// the previous call) to patch the UI instead of reloading the whole page.
+#[tauri::command]
+pub fn library_get_changes_since(
+    db: State<'_, Database>,
+    seq: i64,
+) -> Result<LibraryChanges, CommandError> {
+    let conn = db.conn()?;
+    changes::get_changes_since(&conn, seq).map_err(CommandError::from)
+}
+
+/// Get the most played artists, albums, or genres over a time range
+///
+/// `kind` is artist/album/genre and `range` is 7d/30d/year/all (default all).
+#[tauri::command]
+pub fn library_get_top(
+    db: State<'_, Database>,
+    kind: String,
+    range: Option<String>,
+    limit: Option<i64>,
+) -> Result<TopList, CommandError> {
+    let top_kind: TopKind = kind
+        .parse()
+        .map_err(|_| {
+            CommandError::invalid_input(format!(
+                "Invalid kind '{}', expected artist, album, or genre",
+                kind
+            ))
+        })?;
+    let top_range: TopRange = match range.as_deref() {
+        Some(r) => r
+            .parse()
+            .map_err(|_| {
+                CommandError::invalid_input(format!(
+                    "Invalid range '{}', expected 7d, 30d, year, or all",
+                    r
+                ))
+            })?,
+        None => TopRange::default(),
+    };
+    let limit = limit.unwrap_or(10).clamp(1, 100);
+
+    let conn = db.conn()?;
+    history::get_top(&conn, top_kind, top_range, limit).map_err(CommandError::from)
 }
 
 /// Get a single track by ID
 #[tauri::command]
-pub fn library_get_track(db: State<'_, Database>, track_id: i64) -> Result<Option<Track>, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
-    library::get_track_by_id(&conn, track_id).map_err(|e| e.to_string())
+pub fn library_get_track(
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<Option<Track>, CommandError> {
+    let conn = db.conn()?;
+    library::get_track_by_id(&conn, track_id).map_err(CommandError::from)
+}
+
+/// Get the chapter markers of a track, ordered by start
+#[tauri::command]
+pub fn library_get_chapters(
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<Vec<Chapter>, CommandError> {
+    let conn = db.conn()?;
+    library::get_track_chapters(&conn, track_id).map_err(CommandError::from)
+}
+
+/// Save a named bookmark at a position within a track
+#[tauri::command]
+pub fn library_add_bookmark(
+    db: State<'_, Database>,
+    track_id: i64,
+    position_ms: u64,
+    label: String,
+) -> Result<Bookmark, CommandError> {
+    let conn = db.conn()?;
+    bookmarks::add_bookmark(&conn, track_id, position_ms, label.trim())?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))
+}
+
+/// Get the bookmarks of a track, ordered by position
+#[tauri::command]
+pub fn library_get_bookmarks(
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<Vec<Bookmark>, CommandError> {
+    let conn = db.conn()?;
+    bookmarks::get_bookmarks(&conn, track_id).map_err(CommandError::from)
+}
+
+/// Delete a bookmark
+#[tauri::command]
+pub fn library_delete_bookmark(
+    db: State<'_, Database>,
+    bookmark_id: i64,
+) -> Result<(), CommandError> {
+    let conn = db.conn()?;
+    let deleted = bookmarks::delete_bookmark(&conn, bookmark_id)?;
+    if !deleted {
+        return Err(CommandError::not_found(format!("Bookmark with id {} not found", bookmark_id)));
+    }
+    Ok(())
+}
+
+/// Add a custom tag to a track, returning the track's tags
+///
+/// Tags are trimmed and lowercased; adding one the track already has does
+/// nothing.
+#[tauri::command]
+pub fn library_add_tag(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+    tag: String,
+) -> Result<Vec<String>, CommandError> {
+    let conn = db.conn()?;
+    let tags = tags::add_tag(&conn, track_id, &tag)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+
+    let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+
+    Ok(tags)
+}
+
+/// Remove a custom tag from a track, returning the track's tags
+#[tauri::command]
+pub fn library_remove_tag(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+    tag: String,
+) -> Result<Vec<String>, CommandError> {
+    let conn = db.conn()?;
+    if tags::remove_tag(&conn, track_id, &tag)? {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+    }
+    tags::get_tags(&conn, track_id).map_err(CommandError::from)
+}
+
+/// Get the custom tags of a track, alphabetically
+#[tauri::command]
+pub fn library_get_tags(
+    db: State<'_, Database>,
+    track_id: i64,
+) -> Result<Vec<String>, CommandError> {
+    let conn = db.conn()?;
+    tags::get_tags(&conn, track_id).map_err(CommandError::from)
+}
+
+/// Get every custom tag in use with its track count, alphabetically
+#[tauri::command]
+pub fn library_get_all_tags(db: State<'_, Database>) -> Result<Vec<TagCount>, CommandError> {
+    let conn = db.conn()?;
+    tags::get_all_tags(&conn).map_err(CommandError::from)
 }
 
 /// Get artwork for a track by ID (uses LRU cache)
@@ -101,14 +374,18 @@ pub fn library_get_artwork(
     db: State<'_, Database>,
     cache: State<'_, ArtworkCache>,
     track_id: i64,
-) -> Result<Option<Artwork>, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Option<Artwork>, CommandError> {
+    let conn = db.conn()?;
 
-    let track = library::get_track_by_id(&conn, track_id).map_err(|e| e.to_string())?;
+    let track = library::get_track_by_id(&conn, track_id)?;
 
     match track {
-        Some(t) => Ok(cache.get_or_load(track_id, &t.filepath)),
-        None => Err(format!("Track with id {} not found", track_id)),
+        Some(t) => {
+            let artwork = cache.get_or_load(track_id, &t.filepath);
+            let _ = library::set_track_has_artwork(&conn, track_id, artwork.is_some());
+            Ok(artwork)
+        }
+        None => Err(CommandError::not_found(format!("Track with id {} not found", track_id))),
     }
 }
 
@@ -118,17 +395,158 @@ pub fn library_get_artwork_url(
     db: State<'_, Database>,
     cache: State<'_, ArtworkCache>,
     track_id: i64,
-) -> Result<Option<String>, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Option<String>, CommandError> {
+    let conn = db.conn()?;
 
-    let track = library::get_track_by_id(&conn, track_id).map_err(|e| e.to_string())?;
+    let track = library::get_track_by_id(&conn, track_id)?;
 
     match track {
         Some(t) => {
             let artwork = cache.get_or_load(track_id, &t.filepath);
+            let _ = library::set_track_has_artwork(&conn, track_id, artwork.is_some());
             Ok(artwork.map(|a| format!("data:{};base64,{}", a.mime_type, a.data)))
         }
-        None => Err(format!("Track with id {} not found", track_id)),
+        None => Err(CommandError::not_found(format!("Track with id {} not found", track_id))),
+    }
+}
+
+/// Get one artwork data URL for a whole album, for the album grid
+///
+/// `album_key` is the album artist (or artist) and album, as built by
+/// `artwork_misses::track_album_key`. The first member track with artwork
+/// supplies it, and the result is cached per album. `None` when no track of
+/// the album has artwork.
+#[tauri::command]
+pub fn library_get_album_artwork(
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    album_key: String,
+) -> Result<Option<String>, CommandError> {
+    let artwork = album_artwork::get_album_artwork(&db, &cache, &album_key)?;
+    Ok(artwork.map(|a| format!("data:{};base64,{}", a.mime_type, a.data)))
+}
+
+/// Get every album in the library, with album overrides applied
+#[tauri::command]
+pub fn library_get_albums(db: State<'_, Database>) -> Result<Vec<Album>, CommandError> {
+    let conn = db.conn()?;
+    library::get_albums(&conn).map_err(CommandError::from)
+}
+
+/// Override how an album is shown without changing its files or tracks
+///
+/// Replaces any existing override for `album_key`. Unset or blank fields
+/// fall back to the tags; at least one field must be set.
+#[tauri::command]
+pub fn library_set_album_override(
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    album_key: String,
+    album: Option<String>,
+    album_artist: Option<String>,
+    year: Option<String>,
+    artwork_path: Option<String>,
+) -> Result<AlbumOverride, CommandError> {
+    let value = AlbumOverride {
+        album_key,
+        album,
+        album_artist,
+        year,
+        artwork_path,
+    };
+    let stored = db.with_conn(|conn| album_overrides::set_album_override(conn, &value))?;
+    cache.invalidate_album(&stored.album_key);
+    Ok(stored)
+}
+
+/// Remove an album's override, returning whether it had one
+#[tauri::command]
+pub fn library_clear_album_override(
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    album_key: String,
+) -> Result<bool, CommandError> {
+    let cleared = db.with_conn(|conn| album_overrides::clear_album_override(conn, &album_key))?;
+    cache.invalidate_album(&album_key);
+    Ok(cleared)
+}
+
+/// Size of the in-memory artwork cache, for debugging memory use
+#[tauri::command]
+pub fn artwork_cache_stats(cache: State<'_, ArtworkCache>) -> ArtworkCacheStats {
+    cache.stats()
+}
+
+/// Drop all cached artwork; it's loaded again from the files on demand
+#[tauri::command]
+pub fn artwork_cache_clear(cache: State<'_, ArtworkCache>) {
+    cache.clear();
+}
+
+/// Forget which albums had no online artwork, so they're looked up again
+///
+/// Returns the number of albums cleared.
+#[tauri::command]
+pub fn library_clear_artwork_misses(db: State<'_, Database>) -> Result<usize, CommandError> {
+    db.with_conn(artwork_misses::clear_misses).map_err(CommandError::from)
+}
+
+/// Re-read a track's artwork from its file, replacing the cached copy
+///
+/// Returns the current artwork, or `None` if the track no longer has any.
+#[tauri::command]
+pub fn library_refresh_artwork(
+    app: AppHandle,
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    track_id: i64,
+) -> Result<Option<Artwork>, CommandError> {
+    let conn = db.conn()?;
+
+    let track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+
+    let artwork = cache.reload(track_id, &track.filepath);
+    let _ = library::set_track_has_artwork(&conn, track_id, artwork.is_some());
+
+    let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+
+    Ok(artwork)
+}
+
+/// Get the folder artwork filename patterns, in priority order
+#[tauri::command]
+pub fn library_get_artwork_patterns(cache: State<'_, ArtworkCache>) -> Vec<String> {
+    cache.folder_patterns()
+}
+
+/// Set the folder artwork filename patterns used when a track has no embedded art
+///
+/// Patterns are matched case-insensitively and support `*` and `?` wildcards
+/// (e.g. `cover.*`). An empty list restores the defaults. Returns the patterns
+/// now in effect.
+#[tauri::command]
+pub fn library_set_artwork_patterns(
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    patterns: Vec<String>,
+) -> Result<Vec<String>, CommandError> {
+    let raw = serde_json::to_string(&patterns)?;
+    let patterns = parse_artwork_patterns(Some(&raw));
+
+    db.with_conn(|conn| {
+        settings::set_setting(conn, ARTWORK_PATTERNS_SETTING, &serde_json::json!(patterns))
+    })?;
+
+    cache.set_folder_patterns(patterns.clone());
+    Ok(patterns)
+}
+
+/// Apply the stored folder artwork patterns to the cache (called at startup)
+pub fn load_artwork_patterns(db: &Database, cache: &ArtworkCache) {
+    match db.with_conn(|conn| settings::get_setting(conn, ARTWORK_PATTERNS_SETTING)) {
+        Ok(value) => cache.set_folder_patterns(parse_artwork_patterns(value.as_deref())),
+        Err(e) => warn!("Failed to load folder artwork patterns: {}", e),
     }
 }
 
@@ -138,10 +556,10 @@ pub fn library_delete_track(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
-) -> Result<bool, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<bool, CommandError> {
+    let conn = db.conn()?;
 
-    let deleted = library::delete_track(&conn, track_id).map_err(|e| e.to_string())?;
+    let deleted = library::delete_track(&conn, track_id)?;
 
     if deleted {
         // Emit standardized library updated event
@@ -158,17 +576,16 @@ pub fn library_rescan_track(
     db: State<'_, Database>,
     cache: State<'_, ArtworkCache>,
     track_id: i64,
-) -> Result<Track, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
 
     // Get the existing track
-    let track = library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Track with id {} not found", track_id))?;
+    let track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
 
     // Extract fresh metadata
     let extracted = extract_metadata(&track.filepath)
-        .map_err(|e| format!("Failed to extract metadata: {}", e))?;
+        .map_err(|e| CommandError::from(e).context("Failed to extract metadata"))?;
 
     // Convert to TrackMetadata for update
     let metadata = TrackMetadata {
@@ -178,24 +595,28 @@ pub fn library_rescan_track(
         album_artist: extracted.album_artist,
         track_number: extracted.track_number,
         track_total: extracted.track_total,
+        disc_number: extracted.disc_number,
+        disc_total: extracted.disc_total,
         date: extracted.date,
+        genre: extracted.genre,
         duration: extracted.duration,
         file_size: Some(extracted.file_size),
         file_mtime_ns: extracted.file_mtime_ns,
         file_inode: None,
         content_hash: None,
+        added_date: None,
+        chapters: extracted.chapters,
     };
 
     // Update in database
-    library::update_track_metadata(&conn, track_id, &metadata).map_err(|e| e.to_string())?;
+    library::update_track_metadata(&conn, track_id, &metadata)?;
 
     // Invalidate artwork cache since metadata (and potentially artwork) changed
     cache.invalidate(track_id);
 
     // Get updated track
-    let updated_track = library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Track not found after update".to_string())?;
+    let updated_track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found("Track not found after update"))?;
 
     // Emit standardized library updated event
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -203,18 +624,59 @@ pub fn library_rescan_track(
     Ok(updated_track)
 }
 
+/// Start filling in missing durations in the background
+///
+/// Returns the progress right after starting; if a pass is already running its
+/// current progress is returned instead of starting another. Each finished
+/// batch emits one `library:updated` modified event.
+#[tauri::command]
+pub fn library_analyze_missing_props(
+    app: AppHandle,
+    db: State<'_, Database>,
+    state: State<'_, AnalyzeState>,
+    executor: State<'_, BackgroundExecutor>,
+) -> AnalyzeStatus {
+    if !state.try_start() {
+        return state.status();
+    }
+
+    let db = db.inner().clone();
+    let state = state.inner().clone();
+    let executor = executor.inner().clone();
+    let status = state.status();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = analyze::analyze_missing_props(&db, &state, &executor, |track_ids| {
+            let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(track_ids));
+        });
+        match result {
+            Ok(status) => info!(
+                "Analyze complete: {} updated, {} skipped",
+                status.updated, status.skipped
+            ),
+            Err(e) => error!("Analyze failed: {}", e),
+        }
+    });
+
+    status
+}
+
+/// Get progress of the missing-properties analyze pass
+#[tauri::command]
+pub fn library_analyze_status(state: State<'_, AnalyzeState>) -> AnalyzeStatus {
+    state.status()
+}
+
 /// Increment play count for a track
 #[tauri::command]
 pub fn library_update_play_count(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
-) -> Result<Track, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
 
-    let track = library::update_play_count(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Track with id {} not found", track_id))?;
+    let track = library::update_play_count(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
 
     // Emit standardized library updated event
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -222,17 +684,140 @@ pub fn library_update_play_count(
     Ok(track)
 }
 
+/// Correct a track's play count to an explicit value
+///
+/// Set `clear_last_played` to also forget when the track was last played;
+/// this always happens when the count is set to zero.
+#[tauri::command]
+pub fn library_set_play_count(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+    count: i64,
+    clear_last_played: Option<bool>,
+) -> Result<Track, CommandError> {
+    if count < 0 {
+        return Err(CommandError::invalid_input(format!(
+            "Play count must be non-negative, got {}",
+            count
+        )));
+    }
+
+    let conn = db.conn()?;
+
+    let track = library::set_play_count(&conn, track_id, count, clear_last_played.unwrap_or(false))?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+
+    let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+
+    Ok(track)
+}
+
+/// Correct when a track was added to the library
+///
+/// `date` is an RFC 3339 timestamp, `YYYY-MM-DD HH:MM:SS` in UTC, or a bare
+/// `YYYY-MM-DD` date.
+#[tauri::command]
+pub fn library_set_added_date(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_id: i64,
+    date: String,
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
+
+    let track = library::set_added_date(&conn, track_id, &date)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+
+    let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+
+    Ok(track)
+}
+
+/// Reset play counts for the whole library
+///
+/// Does nothing unless `confirm` is true. Returns the number of tracks reset.
+#[tauri::command]
+pub fn library_reset_all_play_counts(
+    app: AppHandle,
+    db: State<'_, Database>,
+    confirm: bool,
+) -> Result<i64, CommandError> {
+    let reset_ids = db
+        .transaction(|conn| reset_play_counts(conn, confirm))?;
+
+    if !reset_ids.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(reset_ids.clone()));
+    }
+
+    Ok(reset_ids.len() as i64)
+}
+
+/// Reset all play counts only when confirmed
+fn reset_play_counts(conn: &Connection, confirm: bool) -> DbResult<Vec<i64>> {
+    if !confirm {
+        return Ok(Vec::new());
+    }
+    library::reset_all_play_counts(conn)
+}
+
 /// Get all tracks marked as missing
+///
+/// With `older_than_days`, only those missing for longer, i.e. what
+/// `library_purge_missing` with the same cutoff would delete.
 #[tauri::command]
-pub fn library_get_missing(db: State<'_, Database>) -> Result<MissingTracksResponse, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+pub fn library_get_missing(
+    db: State<'_, Database>,
+    older_than_days: Option<i64>,
+) -> Result<MissingTracksResponse, CommandError> {
+    let conn = db.conn()?;
 
-    let tracks = library::get_missing_tracks(&conn).map_err(|e| e.to_string())?;
+    let tracks = match older_than_days {
+        Some(days) => library::get_missing_tracks_older_than(&conn, check_purge_days(days)?)?,
+        None => library::get_missing_tracks(&conn)?,
+    };
     let total = tracks.len() as i64;
 
     Ok(MissingTracksResponse { tracks, total })
 }
 
+/// Delete tracks that have been missing for more than `older_than_days` days
+///
+/// Their favorites, playlist entries, and play history are deleted with
+/// them, and playlists they were in are renumbered. Preview the tracks with
+/// `library_get_missing`. Returns how many were deleted.
+#[tauri::command]
+pub fn library_purge_missing(
+    app: AppHandle,
+    db: State<'_, Database>,
+    older_than_days: i64,
+) -> Result<usize, CommandError> {
+    let days = check_purge_days(older_than_days)?;
+    let purged = db.transaction(|conn| {
+        let purged = library::purge_missing_tracks(conn, days)?;
+        if !purged.is_empty() {
+            playlists::repair_all_positions(conn)?;
+        }
+        Ok(purged)
+    })?;
+
+    let count = purged.len();
+    if count > 0 {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::deleted(purged));
+    }
+    Ok(count)
+}
+
+fn check_purge_days(days: i64) -> Result<i64, CommandError> {
+    if days < 0 {
+        return Err(CommandError::invalid_input(format!(
+            "older_than_days must be non-negative, got {}",
+            days
+        )));
+    }
+    Ok(days)
+}
+
 /// Update a missing track's filepath after user locates the file
 /// If the new path already exists as another track (duplicate), the duplicate is removed
 /// and the original track's path is updated (preserving play history, favorites, etc.)
@@ -242,18 +827,17 @@ pub fn library_locate_track(
     db: State<'_, Database>,
     track_id: i64,
     new_path: String,
-) -> Result<Track, String> {
+) -> Result<Track, CommandError> {
     // Verify the new path exists
     if !Path::new(&new_path).exists() {
-        return Err(format!("File not found: {}", new_path));
+        return Err(CommandError::not_found(format!("File not found: {}", new_path)));
     }
 
-    let conn = db.conn().map_err(|e| e.to_string())?;
+    let conn = db.conn()?;
 
     // Verify the track exists
-    library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Track with id {} not found", track_id))?;
+    library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
 
     // Check if another track already exists at the new path (duplicate scenario)
     // This can happen when:
@@ -265,21 +849,20 @@ pub fn library_locate_track(
         && existing_track.id != track_id {
             // There's a duplicate track at this path - remove it
             // The original track (being located) takes precedence to preserve play history
-            println!(
-                "[locate] Removing duplicate track {} at path {} (keeping original track {})",
+            info!(
+                "Removing duplicate track {} at path {} (keeping original track {})",
                 existing_track.id, new_path, track_id
             );
-            library::delete_track(&conn, existing_track.id).map_err(|e| e.to_string())?;
+            library::delete_track(&conn, existing_track.id)?;
             deleted_duplicate_id = Some(existing_track.id);
         }
 
     // Update the filepath (also clears missing flag and updates last_seen_at)
-    library::update_track_filepath(&conn, track_id, &new_path).map_err(|e| e.to_string())?;
+    library::update_track_filepath(&conn, track_id, &new_path)?;
 
     // Get updated track
-    let updated_track = library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Track not found after update".to_string())?;
+    let updated_track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found("Track not found after update"))?;
 
     // Emit library updated events
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -292,18 +875,204 @@ pub fn library_locate_track(
     Ok(updated_track)
 }
 
+/// Point a track at a different file of the same song, such as a new format
+///
+/// Unlike `library_locate_track` this is for present tracks too, and it
+/// rereads the tags, audio properties and fingerprints from the new file.
+/// Play count, rating, favorites and playlist membership are kept.
+#[tauri::command]
+pub fn library_replace_track_file(
+    app: AppHandle,
+    db: State<'_, Database>,
+    cache: State<'_, ArtworkCache>,
+    track_id: i64,
+    new_path: String,
+) -> Result<Track, CommandError> {
+    let replaced = replace::replace_track_file(&db, track_id, &new_path)?;
+
+    // Artwork may differ in the new file
+    cache.invalidate(track_id);
+
+    let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
+    if let Some(dup_id) = replaced.merged_duplicate {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::deleted(vec![dup_id]));
+    }
+
+    Ok(replaced.track)
+}
+
+/// Read up to `len` bytes of a track's file from `start`
+///
+/// Only serves the file of a track in the library, never an arbitrary path.
+/// The length is clamped to the end of the file and to `media::MAX_RANGE_LEN`.
+#[tauri::command]
+pub fn media_read_range(
+    db: State<'_, Database>,
+    track_id: i64,
+    start: u64,
+    len: u64,
+) -> Result<MediaRange, CommandError> {
+    let track = db
+        .with_conn(|conn| library::get_track_by_id(conn, track_id))?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+    if track.missing {
+        return Err(CommandError::not_found(format!("File not found: {}", track.filepath)));
+    }
+
+    media::read_range(Path::new(&track.filepath), start, len)
+        .map_err(|e| CommandError::from(e).context(&format!("Failed to read {}", track.filepath)))
+}
+
+/// Relocate every track under `old_prefix` to the same relative path under `new_prefix`
+///
+/// Used when a whole album or library root has moved. Missing and present
+/// tracks are both considered; a track is only updated when its file exists at
+/// the new location, so unmatched tracks are left untouched.
+#[tauri::command]
+pub fn library_relocate_prefix(
+    app: AppHandle,
+    db: State<'_, Database>,
+    old_prefix: String,
+    new_prefix: String,
+) -> Result<RelocatePrefixResult, CommandError> {
+    let result = db
+        .transaction(|conn| relocate_prefix(conn, &old_prefix, &new_prefix))?;
+
+    info!(
+        "Relocated prefix {} to {}: {} relocated, {} still missing, {} conflicts",
+        old_prefix, new_prefix, result.relocated, result.still_missing, result.conflicts
+    );
+
+    if !result.relocated_ids.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(
+            result.relocated_ids.clone(),
+        ));
+    }
+
+    Ok(result)
+}
+
+/// Rewrite the prefix of all matching tracks whose file exists at the new path
+fn relocate_prefix(
+    conn: &Connection,
+    old_prefix: &str,
+    new_prefix: &str,
+) -> DbResult<RelocatePrefixResult> {
+    let mut result = RelocatePrefixResult::default();
+
+    let old_trimmed = trim_trailing_separators(old_prefix);
+    if old_trimmed.is_empty() {
+        return Ok(result);
+    }
+
+    // Stored paths may use either separator style
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    for variant in [old_trimmed.replace('\\', "/"), old_trimmed.replace('/', "\\")] {
+        for (id, filepath) in library::get_track_paths_with_prefix(conn, &variant)? {
+            if seen.insert(id) {
+                candidates.push((id, filepath));
+            }
+        }
+    }
+
+    for (track_id, filepath) in candidates {
+        let Some(new_path) = rebase_path(&filepath, old_prefix, new_prefix) else {
+            continue;
+        };
+
+        let path = Path::new(&new_path);
+        if !path.is_file() {
+            result.still_missing += 1;
+            continue;
+        }
+
+        if let Some(existing) = library::get_track_by_filepath(conn, &new_path)?
+            && existing.id != track_id
+        {
+            result.conflicts += 1;
+            continue;
+        }
+
+        let inode = FileFingerprint::from_path(path).ok().and_then(|fp| fp.inode);
+        if library::relocate_track(conn, track_id, &new_path, inode)? {
+            result.relocated += 1;
+            result.relocated_ids.push(track_id);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Strip trailing path separators from a prefix, keeping a bare root intact
+fn trim_trailing_separators(prefix: &str) -> &str {
+    let trimmed = prefix.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() && !prefix.is_empty() {
+        &prefix[..1]
+    } else {
+        trimmed
+    }
+}
+
+/// Map `filepath` from under `old_prefix` to the same relative path under `new_prefix`
+///
+/// Matching ignores trailing separators and separator style, and only
+/// matches whole path components (`/music/a` does not match `/music/ab`).
+/// The relative part is rewritten using the new prefix's separator style.
+fn rebase_path(filepath: &str, old_prefix: &str, new_prefix: &str) -> Option<String> {
+    let old = trim_trailing_separators(old_prefix);
+    let new = trim_trailing_separators(new_prefix);
+    if old.is_empty() || filepath.len() < old.len() || !filepath.is_char_boundary(old.len()) {
+        return None;
+    }
+
+    let (head, rest) = filepath.split_at(old.len());
+    let normalize = |s: &str| s.replace('\\', "/");
+    if normalize(head) != normalize(old) {
+        return None;
+    }
+
+    let old_is_root = matches!(old, "/" | "\\");
+    if !rest.is_empty() && !old_is_root && !rest.starts_with(['/', '\\']) {
+        return None;
+    }
+
+    let separator = separator_style(new)
+        .or_else(|| separator_style(filepath))
+        .unwrap_or('/');
+    let rest = rest
+        .trim_start_matches(['/', '\\'])
+        .replace(['/', '\\'], &separator.to_string());
+
+    if rest.is_empty() {
+        Some(new.to_string())
+    } else if new.ends_with(['/', '\\']) {
+        Some(format!("{}{}", new, rest))
+    } else {
+        Some(format!("{}{}{}", new, separator, rest))
+    }
+}
+
+/// Separator used by a path: backslash only for purely Windows-style paths
+fn separator_style(path: &str) -> Option<char> {
+    match (path.contains('/'), path.contains('\\')) {
+        (false, true) => Some('\\'),
+        (true, _) => Some('/'),
+        (false, false) => None,
+    }
+}
+
 /// Check if a track's file exists and update its missing status
 #[tauri::command]
 pub fn library_check_status(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
-) -> Result<Track, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
 
-    let track = library::check_and_update_track_status(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Track with id {} not found", track_id))?;
+    let track = library::check_and_update_track_status(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
 
     // Emit standardized library updated event
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -311,24 +1080,42 @@ pub fn library_check_status(
     Ok(track)
 }
 
+/// Check whether a file or folder is already in the library or watched
+///
+/// Reports whether the exact path is a track (and marked missing), the
+/// watched folder it falls under, and for a directory how many of its audio
+/// files are already indexed versus new. Directories are walked off the
+/// main thread.
+#[tauri::command]
+pub async fn library_path_status(
+    db: State<'_, Database>,
+    path: String,
+) -> Result<PathStatus, CommandError> {
+    let db = db.inner().clone();
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        db.with_conn(|conn| path_status::path_status(conn, &path))
+    })
+    .await??;
+    Ok(status)
+}
+
 /// Manually mark a track as missing
 #[tauri::command]
 pub fn library_mark_missing(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
-) -> Result<Track, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
 
-    let marked = library::mark_track_missing(&conn, track_id).map_err(|e| e.to_string())?;
+    let marked = library::mark_track_missing(&conn, track_id)?;
 
     if !marked {
-        return Err(format!("Track with id {} not found", track_id));
+        return Err(CommandError::not_found(format!("Track with id {} not found", track_id)));
     }
 
-    let track = library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Track not found after marking".to_string())?;
+    let track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found("Track not found after marking"))?;
 
     // Emit standardized library updated event
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -342,18 +1129,17 @@ pub fn library_mark_present(
     app: AppHandle,
     db: State<'_, Database>,
     track_id: i64,
-) -> Result<Track, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+) -> Result<Track, CommandError> {
+    let conn = db.conn()?;
 
-    let marked = library::mark_track_present(&conn, track_id).map_err(|e| e.to_string())?;
+    let marked = library::mark_track_present(&conn, track_id)?;
 
     if !marked {
-        return Err(format!("Track with id {} not found", track_id));
+        return Err(CommandError::not_found(format!("Track with id {} not found", track_id)));
     }
 
-    let track = library::get_track_by_id(&conn, track_id)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| "Track not found after marking".to_string())?;
+    let track = library::get_track_by_id(&conn, track_id)?
+        .ok_or_else(|| CommandError::not_found("Track not found after marking"))?;
 
     // Emit standardized library updated event
     let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![track_id]));
@@ -361,109 +1147,224 @@ pub fn library_mark_present(
     Ok(track)
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct ReconcileScanResult {
-    pub backfilled: u32,
-    pub duplicates_merged: u32,
-    pub errors: u32,
-}
+/// Tracks probed between two verify progress events
+const VERIFY_PROGRESS_INTERVAL: usize = 10;
 
+/// Check that tracks decode, flagging the ones that don't
+///
+/// Each file's first frames are decoded off the main thread, with at most
+/// `MAX_FS_CONCURRENCY` probes in flight; nothing is played. Progress is
+/// emitted as `library:verify-progress`. The outcome is stored in
+/// `last_verify_failed`. Returns the tracks that failed, with the reason.
 #[tauri::command]
-pub fn library_reconcile_scan(
+pub async fn library_verify_playable(
     app: AppHandle,
     db: State<'_, Database>,
-) -> Result<ReconcileScanResult, String> {
-    let conn = db.conn().map_err(|e| e.to_string())?;
+    track_ids: Vec<i64>,
+) -> Result<Vec<UnplayableTrack>, CommandError> {
+    let db = db.inner().clone();
+    let emitter = app.clone();
+    let verified = track_ids.clone();
+    let unplayable = tauri::async_runtime::spawn_blocking(move || {
+        verify::verify_playable(&db, &track_ids, MAX_FS_CONCURRENCY, |progress| {
+            if progress.processed % VERIFY_PROGRESS_INTERVAL == 0
+                || progress.processed == progress.total
+            {
+                let _ = emitter.emit_verify_progress(VerifyProgressEvent {
+                    total: progress.total,
+                    processed: progress.processed,
+                    failed: progress.failed,
+                });
+            }
+        })
+    })
+    .await??;
 
-    let mut backfilled = 0u32;
-    let mut errors = 0u32;
+    if !verified.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(verified));
+    }
 
-    let tracks = library::get_tracks_needing_fingerprints(&conn).map_err(|e| e.to_string())?;
+    Ok(unplayable)
+}
 
-    for track in tracks {
-        let path = std::path::Path::new(&track.filepath);
-        if !path.exists() {
-            continue;
-        }
+/// Get the IDs of tracks whose last decode check failed
+#[tauri::command]
+pub fn library_get_unplayable(db: State<'_, Database>) -> Result<Vec<i64>, CommandError> {
+    db.with_conn(library::get_verify_failed_track_ids).map_err(CommandError::from)
+}
 
-        let fingerprint = match FileFingerprint::from_path(path) {
-            Ok(fp) => fp,
-            Err(_) => {
-                errors += 1;
-                continue;
-            }
-        };
+/// Tracks stat-ed between two existence check progress events
+const EXISTENCE_PROGRESS_INTERVAL: usize = 100;
 
-        let content_hash = match compute_content_hash(path) {
-            Ok(h) => Some(h),
-            Err(_) => {
-                errors += 1;
-                None
-            }
-        };
+/// Check that every library file still exists and update missing flags
+///
+/// Files that are gone are marked missing and missing files that are back
+/// are marked present. Progress is emitted as `library:existence-progress`.
+#[tauri::command]
+pub async fn library_verify_all(
+    app: AppHandle,
+    db: State<'_, Database>,
+) -> Result<ExistenceCheckResult, CommandError> {
+    verify_library_files(app, db.inner().clone()).await
+}
 
-        match library::update_track_fingerprints(
-            &conn,
-            track.id,
-            fingerprint.inode,
-            content_hash.as_deref(),
-        ) {
-            Ok(true) => backfilled += 1,
-            Ok(false) => {}
-            Err(_) => errors += 1,
-        }
+/// Run the existence check off the main thread, shared by
+/// `library_verify_all` and the startup check
+pub async fn verify_library_files(
+    app: AppHandle,
+    db: Database,
+) -> Result<ExistenceCheckResult, CommandError> {
+    let emitter = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        existence::verify_all(&db, MAX_FS_CONCURRENCY, |progress| {
+            if progress.processed % EXISTENCE_PROGRESS_INTERVAL == 0
+                || progress.processed == progress.total
+            {
+                let _ = emitter.emit_existence_progress(ExistenceProgressEvent {
+                    total: progress.total,
+                    processed: progress.processed,
+                    missing: progress.missing,
+                });
+            }
+        })
+    })
+    .await??;
+
+    let changed: Vec<i64> = result
+        .marked_missing
+        .iter()
+        .chain(&result.recovered)
+        .copied()
+        .collect();
+    if !changed.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(changed));
     }
 
-    let mut duplicates_merged = 0u32;
-    let mut deleted_ids = Vec::new();
+    Ok(result)
+}
 
-    let inode_dups = library::find_duplicates_by_inode(&conn).map_err(|e| e.to_string())?;
-    for group in inode_dups {
-        if group.len() < 2 {
-            continue;
-        }
-        let keep = &group[0];
-        for dup in &group[1..] {
-            match library::merge_duplicate_tracks(&conn, keep.id, dup.id) {
-                Ok(true) => {
-                    duplicates_merged += 1;
-                    deleted_ids.push(dup.id);
+/// Find library tracks whose files no longer exist, without modifying anything
+///
+/// Faster than a full scan when only existence matters: each path is stat-ed
+/// once, with at most `MAX_FS_CONCURRENCY` stats in flight. Tracks already
+/// marked missing are skipped. Pass the result to `library_mark_orphans_missing`
+/// to flag them.
+#[tauri::command]
+pub fn library_find_orphans(db: State<'_, Database>) -> Result<OrphansResponse, CommandError> {
+    let paths = {
+        let conn = db.conn()?;
+        library::get_present_track_paths(&conn)?
+    };
+
+    let checked = paths.len() as i64;
+    let orphans = find_orphans(paths, MAX_FS_CONCURRENCY)?;
+
+    Ok(OrphansResponse { orphans, checked })
+}
+
+/// Mark confirmed orphan tracks as missing
+///
+/// Each file is checked again before flagging, so tracks that reappeared
+/// since `library_find_orphans` ran are left alone. Returns the IDs marked.
+#[tauri::command]
+pub fn library_mark_orphans_missing(
+    app: AppHandle,
+    db: State<'_, Database>,
+    track_ids: Vec<i64>,
+) -> Result<Vec<i64>, CommandError> {
+    let marked = db
+        .transaction(|conn| {
+            let mut marked = Vec::new();
+            for track_id in track_ids {
+                let Some(track) = library::get_track_by_id(conn, track_id)? else {
+                    continue;
+                };
+                if !Path::new(&track.filepath).exists()
+                    && library::mark_track_missing(conn, track_id)?
+                {
+                    marked.push(track_id);
                 }
-                Ok(false) => {}
-                Err(_) => errors += 1,
             }
-        }
+            Ok(marked)
+        })?;
+
+    if !marked.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(marked.clone()));
     }
 
-    let hash_dups = library::find_duplicates_by_content_hash(&conn).map_err(|e| e.to_string())?;
-    for group in hash_dups {
-        if group.len() < 2 {
-            continue;
-        }
-        let keep = &group[0];
-        for dup in &group[1..] {
-            if deleted_ids.contains(&dup.id) {
-                continue;
-            }
-            match library::merge_duplicate_tracks(&conn, keep.id, dup.id) {
-                Ok(true) => {
-                    duplicates_merged += 1;
-                    deleted_ids.push(dup.id);
-                }
-                Ok(false) => {}
-                Err(_) => errors += 1,
-            }
-        }
+    Ok(marked)
+}
+
+/// Stat each path on a bounded pool and return the ones missing from disk
+fn find_orphans(
+    paths: Vec<(i64, String)>,
+    concurrency: usize,
+) -> Result<Vec<OrphanTrack>, CommandError> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency.max(1))
+        .build()
+        .map_err(|e| {
+            CommandError::new(CommandError::INTERNAL, e.to_string())
+                .context("Failed to start file check threads")
+        })?;
+
+    Ok(pool.install(|| {
+        paths
+            .into_par_iter()
+            .filter(|(_, filepath)| !Path::new(filepath).exists())
+            .map(|(id, filepath)| OrphanTrack { id, filepath })
+            .collect()
+    }))
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ReconcileScanResult {
+    /// Whether this only planned the changes; nothing was written if so
+    pub dry_run: bool,
+    pub backfilled: u32,
+    pub duplicates_merged: u32,
+    pub errors: u32,
+    /// The planned changes, so the UI can confirm them before a real run
+    pub plan: ReconcilePlan,
+}
+
+/// Backfill missing fingerprints and merge duplicate tracks
+///
+/// With `dry_run` set, returns the fingerprints that would be stored and
+/// which tracks would be merged into which, without changing anything; the
+/// counts are then what a real run would do.
+#[tauri::command]
+pub fn library_reconcile_scan(
+    app: AppHandle,
+    db: State<'_, Database>,
+    executor: State<'_, BackgroundExecutor>,
+    dry_run: Option<bool>,
+) -> Result<ReconcileScanResult, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    let conn = db.conn()?;
+
+    let plan = reconcile::plan_reconcile(&conn, &executor)?;
+    if dry_run {
+        return Ok(ReconcileScanResult {
+            dry_run,
+            backfilled: plan.backfills.len() as u32,
+            duplicates_merged: plan.merges.len() as u32,
+            errors: plan.errors,
+            plan,
+        });
     }
 
-    if !deleted_ids.is_empty() {
-        let _ = app.emit_library_updated(LibraryUpdatedEvent::deleted(deleted_ids));
+    let outcome = reconcile::apply_reconcile(&conn, &plan);
+    if !outcome.deleted_ids.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::deleted(outcome.deleted_ids.clone()));
     }
 
     Ok(ReconcileScanResult {
-        backfilled,
-        duplicates_merged,
-        errors,
+        dry_run,
+        backfilled: outcome.backfilled,
+        duplicates_merged: outcome.deleted_ids.len() as u32,
+        errors: outcome.errors,
+        plan,
     })
 }
 
@@ -578,9 +1479,11 @@ mod tests {
     #[test]
     fn test_reconcile_scan_result_serialization() {
         let result = ReconcileScanResult {
+            dry_run: false,
             backfilled: 10,
             duplicates_merged: 5,
             errors: 2,
+            plan: ReconcilePlan::default(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -592,9 +1495,11 @@ mod tests {
     #[test]
     fn test_reconcile_scan_result_zero_values() {
         let result = ReconcileScanResult {
+            dry_run: false,
             backfilled: 0,
             duplicates_merged: 0,
             errors: 0,
+            plan: ReconcilePlan::default(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -606,9 +1511,11 @@ mod tests {
     #[test]
     fn test_reconcile_scan_result_clone() {
         let result = ReconcileScanResult {
+            dry_run: false,
             backfilled: 100,
             duplicates_merged: 20,
             errors: 3,
+            plan: ReconcilePlan::default(),
         };
 
         let cloned = result.clone();
@@ -620,9 +1527,11 @@ mod tests {
     #[test]
     fn test_reconcile_scan_result_large_values() {
         let result = ReconcileScanResult {
+            dry_run: false,
             backfilled: 10_000,
             duplicates_merged: 5_000,
             errors: 100,
+            plan: ReconcilePlan::default(),
         };
 
         let json = serde_json::to_string(&result).unwrap();
@@ -757,4 +1666,201 @@ mod tests {
         let path = Path::new(path_str);
         assert_eq!(path.to_str(), Some(path_str));
     }
+
+    // =========================================================================
+    // Orphan detection tests
+    // =========================================================================
+
+    #[test]
+    fn test_find_orphans_returns_removed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for i in 1..=4 {
+            let path = dir.path().join(format!("track{}.mp3", i));
+            std::fs::write(&path, b"audio").unwrap();
+            paths.push((i as i64, path.to_string_lossy().to_string()));
+        }
+
+        std::fs::remove_file(&paths[1].1).unwrap();
+        std::fs::remove_file(&paths[3].1).unwrap();
+
+        let orphans = find_orphans(paths.clone(), 2).unwrap();
+        assert_eq!(
+            orphans,
+            vec![
+                OrphanTrack { id: 2, filepath: paths[1].1.clone() },
+                OrphanTrack { id: 4, filepath: paths[3].1.clone() },
+            ]
+        );
+
+        // Checking must not touch the remaining files
+        assert!(Path::new(&paths[0].1).exists());
+        assert!(Path::new(&paths[2].1).exists());
+    }
+
+    #[test]
+    fn test_find_orphans_empty() {
+        let orphans = find_orphans(Vec::new(), MAX_FS_CONCURRENCY).unwrap();
+        assert!(orphans.is_empty());
+    }
+
+    #[test]
+    fn test_orphans_response_serialization() {
+        let response = OrphansResponse {
+            orphans: vec![OrphanTrack {
+                id: 7,
+                filepath: "/music/gone.mp3".to_string(),
+            }],
+            checked: 10,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"checked\":10"));
+        assert!(json.contains("\"filepath\":\"/music/gone.mp3\""));
+    }
+
+    // =========================================================================
+    // Prefix relocation tests
+    // =========================================================================
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::db::schema::create_tables(&conn).unwrap();
+        crate::db::schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_rebase_path_nested() {
+        assert_eq!(
+            rebase_path("/old/music/Artist/Album/01.mp3", "/old/music", "/new/lib"),
+            Some("/new/lib/Artist/Album/01.mp3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rebase_path_trailing_slashes() {
+        let expected = Some("/new/Album/01.mp3".to_string());
+        assert_eq!(rebase_path("/old/Album/01.mp3", "/old/", "/new"), expected);
+        assert_eq!(rebase_path("/old/Album/01.mp3", "/old", "/new/"), expected);
+        assert_eq!(rebase_path("/old/Album/01.mp3", "/old//", "/new//"), expected);
+    }
+
+    #[test]
+    fn test_rebase_path_requires_component_boundary() {
+        assert_eq!(rebase_path("/music/ab/01.mp3", "/music/a", "/new"), None);
+        assert_eq!(rebase_path("/other/01.mp3", "/music", "/new"), None);
+    }
+
+    #[test]
+    fn test_rebase_path_separator_styles() {
+        assert_eq!(
+            rebase_path("C:\\Music\\Album\\01.mp3", "C:/Music/", "D:\\Lib"),
+            Some("D:\\Lib\\Album\\01.mp3".to_string())
+        );
+        assert_eq!(
+            rebase_path("C:\\Music\\Album\\01.mp3", "C:\\Music", "/mnt/lib"),
+            Some("/mnt/lib/Album/01.mp3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rebase_path_root_prefix() {
+        assert_eq!(
+            rebase_path("/Album/01.mp3", "/", "/mnt/usb"),
+            Some("/mnt/usb/Album/01.mp3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_relocate_prefix_nested_paths() {
+        let conn = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let new_root = dir.path().join("new");
+
+        // Only two of the three files exist at the new location
+        for rel in ["A/Album 1/01.mp3", "A/Album 1/Disc 2/01.mp3"] {
+            let path = new_root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"audio").unwrap();
+        }
+
+        let metadata = TrackMetadata::default();
+        let moved = library::add_track(&conn, "/gone/music/A/Album 1/01.mp3", &metadata).unwrap();
+        let nested =
+            library::add_track(&conn, "/gone/music/A/Album 1/Disc 2/01.mp3", &metadata).unwrap();
+        let absent = library::add_track(&conn, "/gone/music/B/02.mp3", &metadata).unwrap();
+        let unrelated = library::add_track(&conn, "/gone/musicals/03.mp3", &metadata).unwrap();
+        library::mark_track_missing(&conn, moved).unwrap();
+        library::mark_track_missing(&conn, absent).unwrap();
+
+        let new_prefix = format!("{}/", new_root.to_string_lossy());
+        let result = relocate_prefix(&conn, "/gone/music/", &new_prefix).unwrap();
+
+        assert_eq!(result.relocated, 2);
+        assert_eq!(result.still_missing, 1);
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.relocated_ids, vec![moved, nested]);
+
+        let track = library::get_track_by_id(&conn, moved).unwrap().unwrap();
+        assert_eq!(
+            Path::new(&track.filepath),
+            new_root.join("A/Album 1/01.mp3").as_path()
+        );
+        assert!(!track.missing);
+
+        let track = library::get_track_by_id(&conn, nested).unwrap().unwrap();
+        assert_eq!(
+            Path::new(&track.filepath),
+            new_root.join("A/Album 1/Disc 2/01.mp3").as_path()
+        );
+
+        let track = library::get_track_by_id(&conn, absent).unwrap().unwrap();
+        assert_eq!(track.filepath, "/gone/music/B/02.mp3");
+        assert!(track.missing);
+
+        let track = library::get_track_by_id(&conn, unrelated).unwrap().unwrap();
+        assert_eq!(track.filepath, "/gone/musicals/03.mp3");
+    }
+
+    #[test]
+    fn test_relocate_prefix_skips_conflicts() {
+        let conn = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let existing_path = dir.path().join("01.mp3");
+        std::fs::write(&existing_path, b"audio").unwrap();
+        let existing_path = existing_path.to_string_lossy().to_string();
+
+        let metadata = TrackMetadata::default();
+        let old = library::add_track(&conn, "/gone/01.mp3", &metadata).unwrap();
+        library::add_track(&conn, &existing_path, &metadata).unwrap();
+
+        let result = relocate_prefix(&conn, "/gone", &dir.path().to_string_lossy()).unwrap();
+
+        assert_eq!(result.relocated, 0);
+        assert_eq!(result.conflicts, 1);
+        let track = library::get_track_by_id(&conn, old).unwrap().unwrap();
+        assert_eq!(track.filepath, "/gone/01.mp3");
+    }
+
+    // =========================================================================
+    // Play count reset tests
+    // =========================================================================
+
+    #[test]
+    fn test_reset_play_counts_requires_confirm() {
+        let conn = setup_test_db();
+
+        let metadata = TrackMetadata::default();
+        let id = library::add_track(&conn, "/music/track.mp3", &metadata).unwrap();
+        library::set_play_count(&conn, id, 7, false).unwrap();
+
+        assert!(reset_play_counts(&conn, false).unwrap().is_empty());
+        let track = library::get_track_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!(track.play_count, 7);
+
+        assert_eq!(reset_play_counts(&conn, true).unwrap(), vec![id]);
+        let track = library::get_track_by_id(&conn, id).unwrap().unwrap();
+        assert_eq!(track.play_count, 0);
+    }
 }
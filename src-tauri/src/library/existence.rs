@@ -0,0 +1,186 @@
+//! Existence check across the whole library.
+//!
+//! Unlike a watched-folder scan, this only stats each library filepath: a
+//! track whose file is gone is marked missing, and a missing track whose file
+//! is back is marked present. No file is opened. It runs on launch when the
+//! `verify_library_on_startup` setting is on, at most once per
+//! `STARTUP_COOLDOWN`, and on demand through `library_verify_all`.
+
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+use crate::db::{Database, DbResult, library, settings};
+
+/// Settings store key for running the check on launch
+pub const VERIFY_ON_STARTUP_SETTING: &str = "verify_library_on_startup";
+
+/// Database setting holding when the last check finished, in Unix seconds
+const LAST_VERIFIED_SETTING: &str = "library_verified_at";
+
+/// Shortest time between two startup checks, so quick restarts skip it
+pub const STARTUP_COOLDOWN: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Progress of an existence check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExistenceProgress {
+    pub total: usize,
+    /// Tracks checked so far
+    pub processed: usize,
+    /// Files found missing so far
+    pub missing: usize,
+}
+
+/// Outcome of an existence check
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExistenceCheckResult {
+    /// Tracks checked
+    pub checked: usize,
+    /// Tracks newly marked missing
+    pub marked_missing: Vec<i64>,
+    /// Missing tracks whose file is back
+    pub recovered: Vec<i64>,
+}
+
+/// Whether the startup check is due at `now`, Unix seconds
+pub fn startup_check_due(db: &Database, now: i64) -> DbResult<bool> {
+    let last = db
+        .with_conn(|conn| settings::get_setting(conn, LAST_VERIFIED_SETTING))?
+        .and_then(|value| value.parse::<i64>().ok());
+    Ok(last.is_none_or(|last| now - last >= STARTUP_COOLDOWN.as_secs() as i64))
+}
+
+/// Stat every library file, at most `concurrency` at a time, and update the
+/// missing flags
+///
+/// `on_progress` is called from the worker threads after each stat. The
+/// finish time is recorded for the startup cooldown.
+pub fn verify_all(
+    db: &Database,
+    concurrency: usize,
+    on_progress: impl Fn(ExistenceProgress) + Sync,
+) -> DbResult<ExistenceCheckResult> {
+    let tracks = db.with_conn(library::get_track_paths_with_status)?;
+
+    let total = tracks.len();
+    let processed = AtomicUsize::new(0);
+    let missing = AtomicUsize::new(0);
+
+    // Stat without holding a connection
+    let check = |(track_id, filepath, was_missing): (i64, String, bool)| {
+        let exists = Path::new(&filepath).exists();
+        if !exists {
+            missing.fetch_add(1, Ordering::Relaxed);
+        }
+        on_progress(ExistenceProgress {
+            total,
+            processed: processed.fetch_add(1, Ordering::Relaxed) + 1,
+            missing: missing.load(Ordering::Relaxed),
+        });
+        (track_id, was_missing, exists)
+    };
+    let results: Vec<_> = match rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency.max(1))
+        .build()
+    {
+        Ok(pool) => pool.install(|| {
+            use rayon::prelude::*;
+            tracks.into_par_iter().map(check).collect()
+        }),
+        Err(e) => {
+            warn!("Falling back to sequential existence checks: {}", e);
+            tracks.into_iter().map(check).collect()
+        }
+    };
+
+    db.transaction(|conn| {
+        let mut result = ExistenceCheckResult {
+            checked: total,
+            ..Default::default()
+        };
+        for (track_id, was_missing, exists) in results {
+            if exists && was_missing && library::mark_track_present(conn, track_id)? {
+                result.recovered.push(track_id);
+            } else if !exists && !was_missing && library::mark_track_missing(conn, track_id)? {
+                result.marked_missing.push(track_id);
+            }
+        }
+        let now = chrono::Utc::now().timestamp();
+        settings::set_setting(conn, LAST_VERIFIED_SETTING, &json!(now))?;
+        Ok(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn test_verify_all_flags_removed_and_restored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<String> = ["a.mp3", "b.mp3", "c.mp3"]
+            .iter()
+            .map(|name| dir.path().join(name).to_string_lossy().to_string())
+            .collect();
+        for path in &paths {
+            std::fs::write(path, b"not really audio").unwrap();
+        }
+
+        let db = Database::new_in_memory().unwrap();
+        let ids: Vec<i64> = paths
+            .iter()
+            .map(|path| {
+                db.with_conn(|conn| library::add_track(conn, path, &TrackMetadata::default()))
+                    .unwrap()
+            })
+            .collect();
+        // c.mp3 was marked missing earlier but is on disk again
+        db.with_conn(|conn| library::mark_track_missing(conn, ids[2]))
+            .unwrap();
+        std::fs::remove_file(&paths[1]).unwrap();
+
+        let progress = Mutex::new(Vec::new());
+        let result = verify_all(&db, 2, |p| progress.lock().push(p)).unwrap();
+
+        assert_eq!(result.checked, 3);
+        assert_eq!(result.marked_missing, [ids[1]]);
+        assert_eq!(result.recovered, [ids[2]]);
+        let missing: Vec<bool> = ids
+            .iter()
+            .map(|&id| {
+                db.with_conn(|conn| library::get_track_by_id(conn, id))
+                    .unwrap()
+                    .unwrap()
+                    .missing
+            })
+            .collect();
+        assert_eq!(missing, [false, true, false]);
+
+        let progress = progress.into_inner();
+        assert_eq!(progress.len(), 3);
+        assert!(progress.iter().any(|p| p.processed == 3));
+        assert!(progress.iter().all(|p| p.total == 3 && p.missing <= 1));
+
+        // Nothing changed since, so a second run updates nothing
+        let again = verify_all(&db, 2, |_| {}).unwrap();
+        assert!(again.marked_missing.is_empty() && again.recovered.is_empty());
+    }
+
+    #[test]
+    fn test_startup_check_respects_cooldown() {
+        let db = Database::new_in_memory().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        assert!(startup_check_due(&db, now).unwrap());
+
+        verify_all(&db, 1, |_| {}).unwrap();
+        assert!(!startup_check_due(&db, now).unwrap());
+
+        let later = now + STARTUP_COOLDOWN.as_secs() as i64 + 1;
+        assert!(startup_check_due(&db, later).unwrap());
+    }
+}
@@ -0,0 +1,325 @@
+//! Fuzzy library search.
+//!
+//! Ranks tracks by similarity to a query so typos ("beatls") and
+//! transpositions still match. Candidates are prefiltered in SQL by query
+//! trigrams, then scored in Rust with trigram and edit-distance similarity.
+
+use rusqlite::Connection;
+use std::collections::HashSet;
+
+use crate::db::{DbResult, Track, library};
+
+/// Maximum number of rows pulled from the database for scoring
+const MAX_CANDIDATES: i64 = 2000;
+
+/// Maximum number of trigram fragments used in the SQL prefilter
+const MAX_PREFILTER_FRAGMENTS: usize = 24;
+
+/// Minimum combined score for a track to be returned
+const MIN_SCORE: f64 = 0.6;
+
+/// Field weights: title matches rank above artist, artist above album
+const TITLE_WEIGHT: f64 = 1.0;
+const ARTIST_WEIGHT: f64 = 0.9;
+const ALBUM_WEIGHT: f64 = 0.8;
+
+/// Search the library, returning non-missing tracks ordered by relevance
+pub fn search(conn: &Connection, query: &str, limit: usize) -> DbResult<Vec<Track>> {
+    let normalized = normalize(query);
+    if normalized.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let fragments = prefilter_fragments(&normalized);
+    let candidates = library::get_search_candidates(conn, &fragments, MAX_CANDIDATES)?;
+
+    let mut scored: Vec<(f64, Track)> = candidates
+        .into_iter()
+        .map(|track| (score_track(&normalized, &track), track))
+        .filter(|(score, _)| *score >= MIN_SCORE)
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.1.id.cmp(&b.1.id))
+    });
+
+    Ok(scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, track)| track)
+        .collect())
+}
+
+/// Combined relevance of a track for a normalized query
+fn score_track(query: &str, track: &Track) -> f64 {
+    let field = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(|v| field_similarity(query, &normalize(v)))
+            .unwrap_or(0.0)
+    };
+
+    let title = field(&track.title) * TITLE_WEIGHT;
+    let artist = field(&track.artist) * ARTIST_WEIGHT;
+    let album = field(&track.album) * ALBUM_WEIGHT;
+
+    // Best single field dominates; matching several fields breaks ties
+    let best = title.max(artist).max(album);
+    best + (title + artist + album - best) * 0.05
+}
+
+/// Similarity (0.0-1.0) between a normalized query and a normalized field
+fn field_similarity(query: &str, field: &str) -> f64 {
+    if field.is_empty() {
+        return 0.0;
+    }
+    if field == query {
+        return 1.0;
+    }
+    if field.contains(query) {
+        return 0.95;
+    }
+
+    let trigram = trigram_similarity(query, field);
+
+    // Each query word is matched against its closest field word
+    let field_words: Vec<&str> = field.split(' ').collect();
+    let query_words: Vec<&str> = query.split(' ').collect();
+    let word_score = query_words
+        .iter()
+        .map(|q| {
+            field_words
+                .iter()
+                .map(|f| word_similarity(q, f))
+                .fold(0.0, f64::max)
+        })
+        .sum::<f64>()
+        / query_words.len() as f64;
+
+    trigram.max(word_score * 0.9)
+}
+
+/// Similarity between two words based on edit distance with transpositions
+fn word_similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    // Prefix of a longer word counts as a match (search-as-you-type)
+    if a.len() >= 3 && b.starts_with(&a) {
+        return 0.9;
+    }
+
+    1.0 - edit_distance(&a, &b) as f64 / max_len as f64
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent transpositions)
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    let mut d = vec![vec![0usize; m + 1]; n + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=n {
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[n][m]
+}
+
+/// Jaccard similarity of the padded word trigrams of two strings
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let a = trigrams(a);
+    let b = trigrams(b);
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let shared = a.intersection(&b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+fn trigrams(s: &str) -> HashSet<String> {
+    let mut result = HashSet::new();
+    for word in s.split(' ').filter(|w| !w.is_empty()) {
+        let padded: Vec<char> = format!("  {} ", word).chars().collect();
+        for window in padded.windows(3) {
+            result.insert(window.iter().collect());
+        }
+    }
+    result
+}
+
+/// Substrings used by the SQL prefilter so typo'd queries still reach scoring
+fn prefilter_fragments(query: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut fragments = Vec::new();
+
+    for word in query.split(' ') {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < 3 {
+            if seen.insert(word.to_string()) {
+                fragments.push(word.to_string());
+            }
+            continue;
+        }
+        for window in chars.windows(3) {
+            let fragment: String = window.iter().collect();
+            if seen.insert(fragment.clone()) {
+                fragments.push(fragment);
+            }
+        }
+    }
+
+    fragments.truncate(MAX_PREFILTER_FRAGMENTS);
+    fragments
+}
+
+/// Lowercase, strip punctuation, and drop a leading or trailing "the"
+///
+/// "The Beatles" and "Beatles, The" both normalize to "beatles".
+pub fn normalize(s: &str) -> String {
+    let cleaned: String = s
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+
+    let mut words: Vec<&str> = cleaned.split_whitespace().collect();
+    if words.len() > 1 && words.first() == Some(&"the") {
+        words.remove(0);
+    } else if words.len() > 1 && words.last() == Some(&"the") {
+        words.pop();
+    }
+
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use crate::db::schema::{create_tables, run_migrations};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+
+        let tracks = [
+            ("/music/1.mp3", "Let It Be", "The Beatles", "Let It Be"),
+            ("/music/2.mp3", "Yesterday", "The Beatles", "Help!"),
+            (
+                "/music/3.mp3",
+                "Paranoid Android",
+                "Radiohead",
+                "OK Computer",
+            ),
+            ("/music/4.mp3", "Beat It", "Michael Jackson", "Thriller"),
+            ("/music/5.mp3", "Hey Jude", "Beatles, The", "Hey Jude"),
+        ];
+        for (path, title, artist, album) in tracks {
+            let metadata = TrackMetadata {
+                title: Some(title.to_string()),
+                artist: Some(artist.to_string()),
+                album: Some(album.to_string()),
+                ..Default::default()
+            };
+            library::add_track(&conn, path, &metadata).unwrap();
+        }
+
+        conn
+    }
+
+    fn titles(tracks: &[Track]) -> Vec<&str> {
+        tracks.iter().filter_map(|t| t.title.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_normalize_articles() {
+        assert_eq!(normalize("The Beatles"), "beatles");
+        assert_eq!(normalize("Beatles, The"), "beatles");
+        assert_eq!(normalize("  AC/DC "), "ac dc");
+        assert_eq!(normalize("The"), "the");
+    }
+
+    #[test]
+    fn test_edit_distance_transposition() {
+        let a: Vec<char> = "beatles".chars().collect();
+        let b: Vec<char> = "baetles".chars().collect();
+        assert_eq!(edit_distance(&a, &b), 1);
+
+        let c: Vec<char> = "beatls".chars().collect();
+        assert_eq!(edit_distance(&a, &c), 1);
+    }
+
+    #[test]
+    fn test_search_typo() {
+        let conn = setup_test_db();
+        let results = search(&conn, "beatls", 10).unwrap();
+
+        let found = titles(&results);
+        assert!(found.contains(&"Let It Be"));
+        assert!(found.contains(&"Yesterday"));
+        assert!(found.contains(&"Hey Jude"));
+        assert!(!found.contains(&"Paranoid Android"));
+    }
+
+    #[test]
+    fn test_search_transposition() {
+        let conn = setup_test_db();
+        let results = search(&conn, "paranodi andriod", 10).unwrap();
+        assert_eq!(titles(&results).first(), Some(&"Paranoid Android"));
+    }
+
+    #[test]
+    fn test_search_article_equivalence() {
+        let conn = setup_test_db();
+        let a = search(&conn, "the beatles", 10).unwrap();
+        let b = search(&conn, "beatles, the", 10).unwrap();
+
+        let ids_a: Vec<i64> = a.iter().map(|t| t.id).collect();
+        let ids_b: Vec<i64> = b.iter().map(|t| t.id).collect();
+        assert_eq!(ids_a, ids_b);
+        assert_eq!(ids_a.len(), 3);
+    }
+
+    #[test]
+    fn test_search_title_ranks_above_album() {
+        let conn = setup_test_db();
+        let results = search(&conn, "beat it", 10).unwrap();
+        assert_eq!(titles(&results).first(), Some(&"Beat It"));
+    }
+
+    #[test]
+    fn test_search_empty_query() {
+        let conn = setup_test_db();
+        assert!(search(&conn, "  ", 10).unwrap().is_empty());
+        assert!(search(&conn, "beatles", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_respects_limit() {
+        let conn = setup_test_db();
+        assert_eq!(search(&conn, "beatles", 2).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,214 @@
+//! Byte ranges of library audio files.
+//!
+//! Backs `media_read_range`, so the webview `<audio>` element or a cast
+//! target can stream a track through HTTP-style range requests. Only files
+//! the library knows about are served: callers name a track, never a path.
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Most bytes one read returns; longer requests are cut short
+pub const MAX_RANGE_LEN: u64 = 4 * 1024 * 1024;
+
+/// Content type for files of no recognized audio format
+const FALLBACK_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// A slice of an audio file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MediaRange {
+    /// The bytes, base64 encoded
+    pub data: String,
+    /// Offset of the first byte
+    pub start: u64,
+    /// Number of bytes returned, after clamping
+    pub len: u64,
+    /// Size of the whole file
+    pub total_size: u64,
+    pub content_type: String,
+}
+
+/// Read up to `len` bytes from `start`
+///
+/// The length is clamped to the end of the file and to `MAX_RANGE_LEN`. A
+/// start past the end is an error, like an unsatisfiable HTTP range; reading
+/// from offset 0 of an empty file returns no bytes.
+pub fn read_range(path: &Path, start: u64, len: u64) -> io::Result<MediaRange> {
+    let mut file = File::open(path)?;
+    let total_size = file.metadata()?.len();
+    if start > total_size || (start == total_size && total_size > 0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Range start {} is past the end of the file ({} bytes)",
+                start, total_size
+            ),
+        ));
+    }
+
+    let mut header = [0u8; 12];
+    let header_len = file.read(&mut header)?;
+    let content_type = content_type(path, &header[..header_len]);
+
+    let len = len.min(MAX_RANGE_LEN).min(total_size - start);
+    file.seek(SeekFrom::Start(start))?;
+    let mut data = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut data)?;
+
+    Ok(MediaRange {
+        data: BASE64.encode(&data),
+        start,
+        len: data.len() as u64,
+        total_size,
+        content_type: content_type.to_string(),
+    })
+}
+
+/// Content type of an audio file, from its first bytes or else its extension
+///
+/// The container wins over the extension, so a mislabeled file still gets
+/// a type the player can decode.
+pub fn content_type(path: &Path, header: &[u8]) -> &'static str {
+    sniff_content_type(header).unwrap_or_else(|| {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        match ext.as_str() {
+            "mp3" => "audio/mpeg",
+            "m4a" => "audio/mp4",
+            "aac" => "audio/aac",
+            "flac" => "audio/flac",
+            "ogg" | "opus" => "audio/ogg",
+            "wav" => "audio/wav",
+            "aiff" => "audio/aiff",
+            "wma" => "audio/x-ms-wma",
+            "ape" => "audio/x-ape",
+            "dsf" => "audio/x-dsf",
+            "dff" => "audio/x-dff",
+            "mpc" => "audio/x-musepack",
+            "wv" => "audio/x-wavpack",
+            "tta" => "audio/x-tta",
+            _ => FALLBACK_CONTENT_TYPE,
+        }
+    })
+}
+
+/// Content type from the container signature at the start of a file
+fn sniff_content_type(header: &[u8]) -> Option<&'static str> {
+    match header {
+        [b'f', b'L', b'a', b'C', ..] => Some("audio/flac"),
+        [b'O', b'g', b'g', b'S', ..] => Some("audio/ogg"),
+        [
+            b'R',
+            b'I',
+            b'F',
+            b'F',
+            _,
+            _,
+            _,
+            _,
+            b'W',
+            b'A',
+            b'V',
+            b'E',
+            ..,
+        ] => Some("audio/wav"),
+        [b'F', b'O', b'R', b'M', _, _, _, _, b'A', b'I', b'F', ..] => Some("audio/aiff"),
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => Some("audio/mp4"),
+        [b'I', b'D', b'3', ..] => Some("audio/mpeg"),
+        // Frame sync: ADTS AAC has layer bits 00, MPEG audio layers I-III don't
+        [0xFF, second, ..] if second & 0xF6 == 0xF0 => Some("audio/aac"),
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 && second & 0x06 != 0 => Some("audio/mpeg"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decoded(range: &MediaRange) -> Vec<u8> {
+        BASE64.decode(&range.data).unwrap()
+    }
+
+    #[test]
+    fn test_read_range_clamps_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("song.flac");
+        let bytes: Vec<u8> = b"fLaC".iter().copied().chain(0..96).collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let range = read_range(&path, 10, 20).unwrap();
+        assert_eq!((range.start, range.len, range.total_size), (10, 20, 100));
+        assert_eq!(decoded(&range), &bytes[10..30]);
+        assert_eq!(range.content_type, "audio/flac");
+
+        // Runs past the end are cut at the last byte
+        let tail = read_range(&path, 90, 1000).unwrap();
+        assert_eq!(tail.len, 10);
+        assert_eq!(decoded(&tail), &bytes[90..]);
+
+        assert_eq!(read_range(&path, 99, 0).unwrap().len, 0);
+        assert!(read_range(&path, 100, 10).is_err());
+        assert!(read_range(&path, 5000, 10).is_err());
+        assert!(read_range(&dir.path().join("gone.flac"), 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_read_range_caps_length() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("long.wav");
+        std::fs::write(&path, vec![0u8; MAX_RANGE_LEN as usize + 10]).unwrap();
+
+        let range = read_range(&path, 0, u64::MAX).unwrap();
+        assert_eq!(range.len, MAX_RANGE_LEN);
+
+        let empty = dir.path().join("empty.mp3");
+        std::fs::write(&empty, b"").unwrap();
+        let range = read_range(&empty, 0, 10).unwrap();
+        assert_eq!((range.len, range.total_size), (0, 0));
+        assert_eq!(range.content_type, "audio/mpeg");
+    }
+
+    #[test]
+    fn test_content_type_prefers_container() {
+        let path = |name: &str| Path::new("/music").join(name);
+
+        assert_eq!(
+            content_type(&path("a.flac"), b"fLaC\0\0\0\x22"),
+            "audio/flac"
+        );
+        assert_eq!(content_type(&path("a.mp3"), b"ID3\x04\0\0"), "audio/mpeg");
+        assert_eq!(
+            content_type(&path("a.mp3"), &[0xFF, 0xFB, 0x90, 0x00]),
+            "audio/mpeg"
+        );
+        assert_eq!(
+            content_type(&path("a.aac"), &[0xFF, 0xF1, 0x50, 0x80]),
+            "audio/aac"
+        );
+        assert_eq!(
+            content_type(&path("a.m4a"), b"\0\0\0\x20ftypM4A "),
+            "audio/mp4"
+        );
+        assert_eq!(
+            content_type(&path("a.wav"), b"RIFF\x24\0\0\0WAVE"),
+            "audio/wav"
+        );
+        assert_eq!(content_type(&path("a.opus"), b"OggS\0\x02"), "audio/ogg");
+        // A FLAC stream saved as .mp3 is served as FLAC
+        assert_eq!(content_type(&path("mislabeled.mp3"), b"fLaC"), "audio/flac");
+
+        // Unknown headers fall back to the extension
+        assert_eq!(content_type(&path("a.WV"), b"wvpk"), "audio/x-wavpack");
+        assert_eq!(content_type(&path("a.aac"), b""), "audio/aac");
+        assert_eq!(
+            content_type(&path("notes.txt"), b"hello"),
+            FALLBACK_CONTENT_TYPE
+        );
+    }
+}
@@ -3,6 +3,17 @@
 //! Provides Tauri commands for library browsing and management,
 //! replacing the Python FastAPI library routes.
 
+pub mod album_artwork;
+pub mod analyze;
+pub mod artwork_lookup;
+pub mod audio_features;
 pub mod commands;
+pub mod existence;
+pub mod fuzzy;
+pub mod media;
+pub mod path_status;
+pub mod reconcile;
+pub mod replace;
+pub mod verify;
 
 pub use commands::*;
@@ -0,0 +1,160 @@
+//! Whether a file or folder is already managed by the library.
+//!
+//! The "Add Music" dialog checks a path before adding it, so it can warn
+//! about files that are already indexed or folders that are already watched.
+//! Directories are walked the same way a scan walks them, and their audio
+//! files are looked up in batches with `library::get_existing_filepaths`.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::db::{DbResult, library, watched};
+use crate::scanner::is_audio_file;
+
+/// Filepaths looked up per `get_existing_filepaths` query, well under
+/// SQLite's bound parameter limit
+const LOOKUP_BATCH_SIZE: usize = 500;
+
+/// How a path relates to the library
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PathStatus {
+    pub path: String,
+    pub is_directory: bool,
+    /// The exact path is a library track
+    pub in_library: bool,
+    /// The track at the exact path is marked missing
+    pub missing: bool,
+    /// The deepest watched folder containing the path, or the path itself
+    pub watched_folder: Option<String>,
+    /// For a directory, audio files inside it that are already in the library
+    pub indexed_files: usize,
+    /// For a directory, audio files inside it that aren't in the library yet
+    pub new_files: usize,
+}
+
+/// Check whether `path` is a library track, lies under a watched folder, and,
+/// for a directory, how many of its audio files are already indexed
+pub fn path_status(conn: &Connection, path: &str) -> DbResult<PathStatus> {
+    let fs_path = Path::new(path);
+    let track = library::get_track_by_filepath(conn, path)?;
+    let watched_folder = watched::get_watched_folders(conn)?
+        .into_iter()
+        .map(|folder| folder.path)
+        .filter(|folder| fs_path.starts_with(folder))
+        .max_by_key(|folder| Path::new(folder).components().count());
+
+    let mut status = PathStatus {
+        path: path.to_string(),
+        is_directory: fs_path.is_dir(),
+        in_library: track.is_some(),
+        missing: track.is_some_and(|t| t.missing),
+        watched_folder,
+        ..Default::default()
+    };
+
+    if status.is_directory {
+        let files: Vec<String> = WalkDir::new(fs_path)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file() && is_audio_file(e.path()))
+            .map(|e| e.path().to_string_lossy().to_string())
+            .collect();
+
+        for batch in files.chunks(LOOKUP_BATCH_SIZE) {
+            let existing = library::get_existing_filepaths(conn, batch)?.len();
+            status.indexed_files += existing;
+            status.new_files += batch.len() - existing;
+        }
+    }
+
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TrackMetadata, schema};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::create_tables(&conn).unwrap();
+        schema::run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn add(conn: &Connection, path: &Path) -> i64 {
+        let filepath = path.to_string_lossy();
+        library::add_track(conn, &filepath, &TrackMetadata::default()).unwrap()
+    }
+
+    #[test]
+    fn test_file_in_library() {
+        let conn = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let song = dir.path().join("song.flac");
+        std::fs::write(&song, b"").unwrap();
+        let track_id = add(&conn, &song);
+
+        let status = path_status(&conn, &song.to_string_lossy()).unwrap();
+        assert!(status.in_library);
+        assert!(!status.missing);
+        assert!(!status.is_directory);
+        assert_eq!(status.watched_folder, None);
+
+        library::mark_track_missing(&conn, track_id).unwrap();
+        assert!(path_status(&conn, &song.to_string_lossy()).unwrap().missing);
+    }
+
+    #[test]
+    fn test_file_under_watched_folder() {
+        let conn = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let nested = dir.path().join("Low");
+        std::fs::create_dir_all(&nested).unwrap();
+        watched::add_watched_folder(&conn, &root, "continuous", 10, true).unwrap();
+        let nested_root = nested.to_string_lossy().to_string();
+        watched::add_watched_folder(&conn, &nested_root, "continuous", 10, true).unwrap();
+
+        let song = nested.join("song.flac");
+        std::fs::write(&song, b"").unwrap();
+        let status = path_status(&conn, &song.to_string_lossy()).unwrap();
+        assert!(!status.in_library);
+        assert_eq!(status.watched_folder, Some(nested_root));
+
+        // A sibling whose name merely starts with the folder's isn't under it
+        let status = path_status(&conn, &format!("{}-other/song.flac", root)).unwrap();
+        assert_eq!(status.watched_folder, None);
+    }
+
+    #[test]
+    fn test_new_path_and_directory_counts() {
+        let conn = setup_test_db();
+        let dir = tempfile::tempdir().unwrap();
+
+        let status = path_status(&conn, "/nowhere/new.flac").unwrap();
+        assert_eq!(
+            status,
+            PathStatus {
+                path: "/nowhere/new.flac".to_string(),
+                ..Default::default()
+            }
+        );
+
+        let album = dir.path().join("album");
+        std::fs::create_dir_all(album.join("disc2")).unwrap();
+        for name in ["01.flac", "02.flac", "cover.jpg", "disc2/01.flac"] {
+            std::fs::write(album.join(name), b"").unwrap();
+        }
+        add(&conn, &album.join("01.flac"));
+
+        let status = path_status(&conn, &album.to_string_lossy()).unwrap();
+        assert!(status.is_directory);
+        assert!(!status.in_library);
+        assert_eq!(status.indexed_files, 1);
+        assert_eq!(status.new_files, 2);
+    }
+}
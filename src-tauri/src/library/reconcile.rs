@@ -0,0 +1,304 @@
+//! Fingerprint backfill and duplicate merging for the reconcile scan.
+//!
+//! The scan is split into a plan, which reads files and the database but
+//! writes nothing, and an apply step that carries a plan out. A dry run
+//! stops at the plan, so the UI can show exactly which tracks would be
+//! merged into which before anything irreversible happens.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::background::BackgroundExecutor;
+use crate::db::DbResult;
+use crate::db::library::{self, FingerprintedTrack, TrackForBackfill};
+use crate::scanner::fingerprint::{FileFingerprint, compute_content_hash};
+
+/// Fingerprints to store for a track that lacks them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FingerprintBackfill {
+    pub track_id: i64,
+    pub filepath: String,
+    pub file_inode: Option<u64>,
+    /// `None` if the file couldn't be hashed; the stored hash is cleared
+    pub content_hash: Option<String>,
+}
+
+/// Which fingerprint two duplicate tracks share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateMatch {
+    Inode,
+    ContentHash,
+}
+
+/// One duplicate folded into the track it duplicates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedMerge {
+    pub keep_id: i64,
+    pub keep_filepath: String,
+    /// Track that is deleted once its plays, tags, favorites and playlist
+    /// entries move to the kept one
+    pub delete_id: i64,
+    pub delete_filepath: String,
+    pub matched_by: DuplicateMatch,
+}
+
+/// Everything a reconcile scan would change, in the order it is applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ReconcilePlan {
+    pub backfills: Vec<FingerprintBackfill>,
+    pub merges: Vec<PlannedMerge>,
+    /// Files that couldn't be fingerprinted or hashed while planning
+    pub errors: u32,
+}
+
+/// What applying a plan changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReconcileOutcome {
+    pub backfilled: u32,
+    /// IDs of the duplicates that were merged away
+    pub deleted_ids: Vec<i64>,
+    /// Planning errors plus any writes that failed
+    pub errors: u32,
+}
+
+/// Work out the fingerprints to backfill and the duplicates to merge
+///
+/// Duplicates are grouped as if the backfill had already happened, first by
+/// inode and then by content hash among the tracks the inode pass keeps. In
+/// each group the present, earliest-added track is kept. Files are hashed as
+/// jobs on `executor`.
+pub fn plan_reconcile(conn: &Connection, executor: &BackgroundExecutor) -> DbResult<ReconcilePlan> {
+    let mut plan = ReconcilePlan::default();
+
+    let needing = library::get_tracks_needing_fingerprints(conn)?;
+    for (backfill, errors) in executor.map(needing, read_backfill) {
+        plan.errors += errors;
+        plan.backfills.extend(backfill);
+    }
+
+    let mut tracks = library::get_fingerprinted_tracks(conn)?;
+    let backfills: HashMap<i64, &FingerprintBackfill> =
+        plan.backfills.iter().map(|b| (b.track_id, b)).collect();
+    for track in &mut tracks {
+        if let Some(backfill) = backfills.get(&track.candidate.id) {
+            track.file_inode = backfill.file_inode.map(|inode| inode as i64);
+            track.content_hash = backfill.content_hash.clone();
+        }
+    }
+    plan.merges = plan_merges(tracks);
+
+    Ok(plan)
+}
+
+/// Group tracks by inode, then by content hash, into merges
+fn plan_merges(mut tracks: Vec<FingerprintedTrack>) -> Vec<PlannedMerge> {
+    tracks.sort_by(|a, b| {
+        let (a, b) = (&a.candidate, &b.candidate);
+        (a.missing, &a.added_date, a.id).cmp(&(b.missing, &b.added_date, b.id))
+    });
+
+    let mut merges = Vec::new();
+    let mut deleted = HashSet::new();
+    for matched_by in [DuplicateMatch::Inode, DuplicateMatch::ContentHash] {
+        let mut groups: BTreeMap<String, Vec<&FingerprintedTrack>> = BTreeMap::new();
+        for track in tracks.iter().filter(|t| !deleted.contains(&t.candidate.id)) {
+            let key = match matched_by {
+                DuplicateMatch::Inode => track.file_inode.map(|inode| inode.to_string()),
+                DuplicateMatch::ContentHash => track.content_hash.clone(),
+            };
+            if let Some(key) = key {
+                groups.entry(key).or_default().push(track);
+            }
+        }
+
+        for group in groups.values().filter(|group| group.len() > 1) {
+            let keep = &group[0].candidate;
+            for dup in &group[1..] {
+                deleted.insert(dup.candidate.id);
+                merges.push(PlannedMerge {
+                    keep_id: keep.id,
+                    keep_filepath: keep.filepath.clone(),
+                    delete_id: dup.candidate.id,
+                    delete_filepath: dup.candidate.filepath.clone(),
+                    matched_by,
+                });
+            }
+        }
+    }
+    merges
+}
+
+/// Read a track's fingerprints from disk, with the number of reads that failed
+///
+/// Files that are gone are left out. A file that can't be hashed still gets
+/// its inode stored.
+fn read_backfill(track: TrackForBackfill) -> (Option<FingerprintBackfill>, u32) {
+    let path = Path::new(&track.filepath);
+    if !path.exists() {
+        return (None, 0);
+    }
+    let Ok(fingerprint) = FileFingerprint::from_path(path) else {
+        return (None, 1);
+    };
+    let content_hash = compute_content_hash(path).ok();
+    let errors = u32::from(content_hash.is_none());
+    let backfill = FingerprintBackfill {
+        track_id: track.id,
+        filepath: track.filepath,
+        file_inode: fingerprint.inode,
+        content_hash,
+    };
+    (Some(backfill), errors)
+}
+
+/// Store the planned fingerprints, then merge the planned duplicates
+///
+/// A failed write is counted and skipped rather than stopping the rest.
+pub fn apply_reconcile(conn: &Connection, plan: &ReconcilePlan) -> ReconcileOutcome {
+    let mut outcome = ReconcileOutcome {
+        errors: plan.errors,
+        ..Default::default()
+    };
+
+    for backfill in &plan.backfills {
+        match library::update_track_fingerprints(
+            conn,
+            backfill.track_id,
+            backfill.file_inode,
+            backfill.content_hash.as_deref(),
+        ) {
+            Ok(true) => outcome.backfilled += 1,
+            Ok(false) => {}
+            Err(_) => outcome.errors += 1,
+        }
+    }
+
+    for merge in &plan.merges {
+        match library::merge_duplicate_tracks(conn, merge.keep_id, merge.delete_id) {
+            Ok(true) => outcome.deleted_ids.push(merge.delete_id),
+            Ok(false) => {}
+            Err(_) => outcome.errors += 1,
+        }
+    }
+
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use crate::db::library::add_track;
+    use crate::db::schema::{create_tables, run_migrations};
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        let sql = format!("SELECT COUNT(*) FROM {}", table);
+        conn.query_row(&sql, [], |row| row.get(0)).unwrap()
+    }
+
+    fn add(conn: &Connection, path: &Path, added_date: &str) -> i64 {
+        let metadata = TrackMetadata {
+            added_date: Some(added_date.to_string()),
+            ..Default::default()
+        };
+        add_track(conn, &path.to_string_lossy(), &metadata).unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_plan_changes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.flac");
+        let copy = dir.path().join("copy.flac");
+        std::fs::write(&original, b"fLaC same audio").unwrap();
+        std::fs::write(&copy, b"fLaC same audio").unwrap();
+
+        let conn = setup_test_db();
+        let executor = BackgroundExecutor::new(2);
+        let keep = add(&conn, &original, "2020-01-01");
+        let dup = add(&conn, &copy, "2024-01-01");
+        conn.execute("UPDATE library SET play_count = 3 WHERE id = ?", [dup])
+            .unwrap();
+
+        let plan = plan_reconcile(&conn, &executor).unwrap();
+        assert_eq!(plan.backfills.len(), 2);
+        assert_eq!(plan.errors, 0);
+        assert_eq!(plan.merges.len(), 1);
+        let merge = &plan.merges[0];
+        assert_eq!((merge.keep_id, merge.delete_id), (keep, dup));
+        assert_eq!(merge.matched_by, DuplicateMatch::ContentHash);
+
+        // Planning wrote nothing: both rows remain, still unfingerprinted
+        assert_eq!(count(&conn, "library"), 2);
+        let hashed: i64 = conn
+            .query_row("SELECT COUNT(content_hash) FROM library", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(hashed, 0);
+        assert_eq!(plan_reconcile(&conn, &executor).unwrap(), plan);
+
+        let outcome = apply_reconcile(&conn, &plan);
+        assert_eq!(outcome.backfilled, 2);
+        assert_eq!(outcome.deleted_ids, vec![dup]);
+        assert_eq!(count(&conn, "library"), 1);
+        let play_count: i64 = conn
+            .query_row(
+                "SELECT play_count FROM library WHERE id = ?",
+                [keep],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(play_count, 3);
+
+        // Once applied there is nothing left to do
+        let replanned = plan_reconcile(&conn, &executor).unwrap();
+        assert!(replanned.backfills.is_empty() && replanned.merges.is_empty());
+    }
+
+    #[test]
+    fn test_plan_merges_inode_before_hash() {
+        let track = |id: i64, missing: bool, added: &str, inode: Option<i64>, hash: &str| {
+            FingerprintedTrack {
+                candidate: library::DuplicateCandidate {
+                    id,
+                    filepath: format!("/music/{}.mp3", id),
+                    missing,
+                    play_count: 0,
+                    added_date: Some(added.to_string()),
+                },
+                file_inode: inode,
+                content_hash: Some(hash.to_string()),
+            }
+        };
+        let merges = plan_merges(vec![
+            // A missing track loses to a present one even if added earlier
+            track(1, true, "2019-01-01", Some(7), "sha256:a"),
+            track(2, false, "2021-01-01", Some(7), "sha256:a"),
+            track(3, false, "2020-01-01", None, "sha256:a"),
+            track(4, false, "2018-01-01", None, "sha256:b"),
+        ]);
+
+        let pairs: Vec<(i64, i64, DuplicateMatch)> = merges
+            .iter()
+            .map(|m| (m.keep_id, m.delete_id, m.matched_by))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (2, 1, DuplicateMatch::Inode),
+                // Track 1 is merged away by then, and 3 was added before 2
+                (3, 2, DuplicateMatch::ContentHash),
+            ]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Pointing a library track at a different file.
+//!
+//! For swapping in a new copy of the same song, say a FLAC for an old MP3.
+//! Unlike locate, which repairs the path of a missing track, replacing also
+//! works on present tracks and rereads everything that comes from the file:
+//! tags, duration, size, fingerprints and chapters. What the library holds
+//! about the track itself stays: play count and history, rating, tags,
+//! favorites, and playlist membership. The old file is left alone.
+
+use std::path::Path;
+
+use crate::audio::RodioBackend;
+use crate::commands::CommandError;
+use crate::db::{Database, DbError, Track, TrackMetadata, library};
+use crate::scanner::fingerprint::compute_content_hash;
+use crate::scanner::is_audio_file;
+use crate::scanner::metadata::extract_metadata;
+
+/// Outcome of a file replacement
+#[derive(Debug, Clone)]
+pub struct ReplacedTrack {
+    pub track: Track,
+    /// Track already in the library at the new path, merged into this one
+    pub merged_duplicate: Option<i64>,
+}
+
+/// Point `track_id` at `new_path` and reread its file properties
+///
+/// The new file must exist, have an audio extension, and decode. If it was
+/// already in the library as another track, that track is merged in, so its
+/// plays and memberships carry over too.
+pub fn replace_track_file(
+    db: &Database,
+    track_id: i64,
+    new_path: &str,
+) -> Result<ReplacedTrack, CommandError> {
+    let path = Path::new(new_path);
+    if !path.is_file() {
+        return Err(CommandError::not_found(format!(
+            "File not found: {}",
+            new_path
+        )));
+    }
+    if !is_audio_file(path) {
+        return Err(CommandError::invalid_input(format!(
+            "Not an audio file: {}",
+            new_path
+        )));
+    }
+
+    db.with_conn(|conn| library::get_track_by_id(conn, track_id))?
+        .ok_or_else(|| CommandError::not_found(format!("Track with id {} not found", track_id)))?;
+
+    let extracted = extract_metadata(new_path)
+        .map_err(|e| CommandError::from(e).context("Failed to extract metadata"))?;
+    RodioBackend::probe(new_path)
+        .map_err(|e| CommandError::from(e).context("Not playable audio"))?;
+    let content_hash = compute_content_hash(path).ok();
+
+    let metadata = TrackMetadata {
+        title: extracted.title,
+        artist: extracted.artist,
+        album: extracted.album,
+        album_artist: extracted.album_artist,
+        track_number: extracted.track_number,
+        track_total: extracted.track_total,
+        disc_number: extracted.disc_number,
+        disc_total: extracted.disc_total,
+        date: extracted.date,
+        genre: extracted.genre,
+        duration: extracted.duration,
+        file_size: Some(extracted.file_size),
+        file_mtime_ns: extracted.file_mtime_ns,
+        file_inode: extracted.file_inode,
+        content_hash,
+        added_date: None,
+        chapters: extracted.chapters,
+    };
+
+    let replaced = db.transaction(|conn| {
+        let mut merged_duplicate = None;
+        if let Some(existing) = library::get_track_by_filepath(conn, new_path)?
+            && existing.id != track_id
+            && library::merge_duplicate_tracks(conn, track_id, existing.id)?
+        {
+            merged_duplicate = Some(existing.id);
+        }
+
+        library::relocate_track(conn, track_id, new_path, metadata.file_inode)?;
+        library::update_track_metadata(conn, track_id, &metadata)?;
+        library::update_track_fingerprints(
+            conn,
+            track_id,
+            metadata.file_inode,
+            metadata.content_hash.as_deref(),
+        )?;
+        // Checks made against the old file no longer apply
+        library::set_track_verify_failed(conn, track_id, false)?;
+
+        let track = library::get_track_by_id(conn, track_id)?
+            .ok_or_else(|| DbError::NotFound(format!("track {}", track_id)))?;
+        Ok(ReplacedTrack {
+            track,
+            merged_duplicate,
+        })
+    })?;
+    Ok(replaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::favorites::{self, DEFAULT_FAVORITE_LIST_ID};
+
+    /// Write a one-second silent 8 kHz mono 16-bit PCM WAV file
+    fn write_silent_wav(path: &Path) {
+        let sample_rate: u32 = 8000;
+        let data_len: u32 = sample_rate * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + data_len as usize, 0);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_replace_keeps_play_count_across_format_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let mp3 = dir.path().join("song.mp3");
+        std::fs::write(&mp3, b"an old lossy encode").unwrap();
+        let wav = dir.path().join("song.wav");
+        write_silent_wav(&wav);
+        let wav_path = wav.to_string_lossy().to_string();
+
+        let db = Database::new_in_memory().unwrap();
+        let metadata = TrackMetadata {
+            title: Some("Song".to_string()),
+            duration: Some(200.0),
+            file_size: Some(19),
+            ..Default::default()
+        };
+        let track_id = db
+            .with_conn(|conn| {
+                let id = library::add_track(conn, &mp3.to_string_lossy(), &metadata)?;
+                library::set_play_count(conn, id, 12, false)?;
+                favorites::add_favorite(conn, DEFAULT_FAVORITE_LIST_ID, id)?;
+                Ok(id)
+            })
+            .unwrap();
+
+        let replaced = replace_track_file(&db, track_id, &wav_path).unwrap();
+        assert_eq!(replaced.merged_duplicate, None);
+        let track = replaced.track;
+        assert_eq!(track.id, track_id);
+        assert_eq!(track.filepath, wav_path);
+        assert_eq!(track.play_count, 12);
+        assert!(!track.missing);
+        assert!(track.content_hash.is_some());
+        assert!((track.duration.unwrap() - 1.0).abs() < 0.01);
+        assert_eq!(
+            track.file_size,
+            std::fs::metadata(&wav).unwrap().len() as i64
+        );
+        let (favorite, _) = db
+            .with_conn(|conn| favorites::is_favorite(conn, DEFAULT_FAVORITE_LIST_ID, track_id))
+            .unwrap();
+        assert!(favorite);
+        // The old file is kept
+        assert!(mp3.exists());
+    }
+
+    #[test]
+    fn test_replace_rejects_missing_and_non_audio_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, b"not audio").unwrap();
+        let fake = dir.path().join("fake.flac");
+        std::fs::write(&fake, b"not audio either").unwrap();
+
+        let db = Database::new_in_memory().unwrap();
+        let track_id = db
+            .with_conn(|conn| library::add_track(conn, "/music/a.mp3", &TrackMetadata::default()))
+            .unwrap();
+
+        let replace = |path: &Path| {
+            replace_track_file(&db, track_id, &path.to_string_lossy())
+                .unwrap_err()
+                .code
+        };
+        assert_eq!(
+            replace(&dir.path().join("gone.flac")),
+            CommandError::NOT_FOUND
+        );
+        assert_eq!(replace(&text), CommandError::INVALID_INPUT);
+        assert_ne!(replace(&fake), CommandError::INTERNAL);
+        let missing = replace_track_file(&db, track_id + 1, &fake.to_string_lossy());
+        assert_eq!(missing.unwrap_err().code, CommandError::NOT_FOUND);
+
+        let track = db
+            .with_conn(|conn| library::get_track_by_id(conn, track_id))
+            .unwrap()
+            .unwrap();
+        assert_eq!(track.filepath, "/music/a.mp3");
+    }
+}
@@ -0,0 +1,178 @@
+//! Decode checks for library tracks.
+//!
+//! Corrupt files usually only show up when playback fails. This probes each
+//! file by decoding its first frames with the playback decoder, without
+//! opening an audio output, and records the outcome in `last_verify_failed`
+//! so the UI can flag bad files.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::warn;
+
+use crate::audio::RodioBackend;
+use crate::db::{Database, DbResult, library};
+
+/// A track whose file could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnplayableTrack {
+    pub track_id: i64,
+    pub filepath: String,
+    pub error: String,
+}
+
+/// Progress of a decode check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct VerifyProgress {
+    pub total: usize,
+    /// Tracks probed so far
+    pub processed: usize,
+    /// Tracks that failed so far
+    pub failed: usize,
+}
+
+/// Probe the given tracks, at most `concurrency` at a time
+///
+/// Unknown track IDs are ignored. `on_progress` is called from the worker
+/// threads after each probe. Returns the tracks that failed, in ID order.
+pub fn verify_playable(
+    db: &Database,
+    track_ids: &[i64],
+    concurrency: usize,
+    on_progress: impl Fn(VerifyProgress) + Sync,
+) -> DbResult<Vec<UnplayableTrack>> {
+    let tracks: Vec<(i64, String)> = db.with_conn(|conn| {
+        let mut tracks = Vec::new();
+        for &track_id in track_ids {
+            if let Some(track) = library::get_track_by_id(conn, track_id)? {
+                tracks.push((track.id, track.filepath));
+            }
+        }
+        Ok(tracks)
+    })?;
+
+    let total = tracks.len();
+    let processed = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+
+    // Probe without holding a connection
+    let probe = |(track_id, filepath): (i64, String)| {
+        let result = RodioBackend::probe(&filepath);
+        if result.is_err() {
+            failed.fetch_add(1, Ordering::Relaxed);
+        }
+        on_progress(VerifyProgress {
+            total,
+            processed: processed.fetch_add(1, Ordering::Relaxed) + 1,
+            failed: failed.load(Ordering::Relaxed),
+        });
+        (track_id, filepath, result)
+    };
+    let results: Vec<_> = match rayon::ThreadPoolBuilder::new()
+        .num_threads(concurrency.max(1))
+        .build()
+    {
+        Ok(pool) => pool.install(|| {
+            use rayon::prelude::*;
+            tracks.into_par_iter().map(probe).collect()
+        }),
+        Err(e) => {
+            warn!("Falling back to sequential probes: {}", e);
+            tracks.into_iter().map(probe).collect()
+        }
+    };
+
+    db.transaction(|conn| {
+        let mut unplayable = Vec::new();
+        for (track_id, filepath, result) in results {
+            library::set_track_verify_failed(conn, track_id, result.is_err())?;
+            if let Err(e) = result {
+                unplayable.push(UnplayableTrack {
+                    track_id,
+                    filepath,
+                    error: e.to_string(),
+                });
+            }
+        }
+        Ok(unplayable)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use parking_lot::Mutex;
+    use std::path::Path;
+
+    /// Write an 8 kHz mono 16-bit PCM WAV header claiming `data_len` bytes,
+    /// followed by `written` bytes of silence
+    fn write_wav(path: &Path, data_len: u32, written: u32) {
+        let sample_rate: u32 = 8000;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+        bytes.extend_from_slice(&2u16.to_le_bytes()); // block align
+        bytes.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        bytes.resize(bytes.len() + written as usize, 0);
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_verify_flags_truncated_and_garbage_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.wav");
+        write_wav(&good, 16000, 16000);
+        let truncated = dir.path().join("truncated.wav");
+        write_wav(&truncated, 16000, 0);
+        let garbage = dir.path().join("garbage.mp3");
+        std::fs::write(&garbage, b"this is not audio at all").unwrap();
+
+        let db = Database::new_in_memory().unwrap();
+        let add = |path: &Path| {
+            db.with_conn(|conn| {
+                library::add_track(conn, &path.to_string_lossy(), &TrackMetadata::default())
+            })
+            .unwrap()
+        };
+        let good_id = add(&good);
+        let truncated_id = add(&truncated);
+        let garbage_id = add(&garbage);
+        let gone_id = add(&dir.path().join("gone.flac"));
+
+        let progress = Mutex::new(Vec::new());
+        let ids = [good_id, truncated_id, garbage_id, gone_id, 9999];
+        let unplayable = verify_playable(&db, &ids, 2, |p| progress.lock().push(p)).unwrap();
+
+        let failed: Vec<i64> = unplayable.iter().map(|t| t.track_id).collect();
+        assert_eq!(failed, vec![truncated_id, garbage_id, gone_id]);
+        assert!(unplayable.iter().all(|t| !t.error.is_empty()));
+        assert_eq!(unplayable[1].filepath, garbage.to_string_lossy());
+
+        let progress = progress.into_inner();
+        assert_eq!(progress.len(), 4);
+        let last = progress.iter().max_by_key(|p| p.processed).unwrap();
+        assert_eq!((last.total, last.processed), (4, 4));
+
+        assert_eq!(
+            db.with_conn(library::get_verify_failed_track_ids).unwrap(),
+            vec![truncated_id, garbage_id, gone_id]
+        );
+
+        // A repaired file clears its flag on the next check
+        write_wav(&garbage, 16000, 16000);
+        let unplayable = verify_playable(&db, &[garbage_id], 1, |_| {}).unwrap();
+        assert!(unplayable.is_empty());
+        assert_eq!(
+            db.with_conn(library::get_verify_failed_track_ids).unwrap(),
+            vec![truncated_id, gone_id]
+        );
+    }
+}
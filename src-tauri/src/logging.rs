@@ -0,0 +1,180 @@
+//! Leveled logging to stderr and a rotating log file.
+//!
+//! `Logging::init` runs first in `setup`, ahead of the database and the other
+//! subsystems, so their startup is logged too. Records at or above the level
+//! go to stderr and to a daily file in the `logs` folder of the app data dir,
+//! keeping the last `MAX_LOG_FILES` days. The level comes from the `log_level`
+//! setting and changes take effect without a restart.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Registry, fmt, reload};
+
+/// Settings key for the log level
+pub const LOG_LEVEL_SETTING: &str = "log_level";
+
+/// Level used when the setting is unset or invalid
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/// Log files are named `mt.YYYY-MM-DD.log`
+const LOG_FILE_PREFIX: &str = "mt";
+const LOG_FILE_SUFFIX: &str = "log";
+
+/// Daily log files kept before the oldest is deleted
+const MAX_LOG_FILES: usize = 7;
+
+/// Parse a level name such as "debug", `None` if it isn't one
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "off" => Some(LevelFilter::OFF),
+        "error" => Some(LevelFilter::ERROR),
+        "warn" | "warning" => Some(LevelFilter::WARN),
+        "info" => Some(LevelFilter::INFO),
+        "debug" => Some(LevelFilter::DEBUG),
+        "trace" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+/// The installed logger, managed as Tauri state
+pub struct Logging {
+    dir: PathBuf,
+    level: reload::Handle<LevelFilter, Registry>,
+    /// Flushes the file writer when the app exits
+    _guard: WorkerGuard,
+}
+
+impl Logging {
+    /// Install the global logger, writing files into `dir`
+    pub fn init(dir: &Path, level: LevelFilter) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create log dir: {}", e))?;
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(|e| format!("Failed to open log file: {}", e))?;
+        let (file_writer, guard) = tracing_appender::non_blocking(appender);
+
+        let (filter, handle) = reload::Layer::new(level);
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer().with_writer(io::stderr))
+            .with(fmt::layer().with_ansi(false).with_writer(file_writer))
+            .try_init()
+            .map_err(|e| format!("Failed to install logger: {}", e))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            level: handle,
+            _guard: guard,
+        })
+    }
+
+    /// Change the level of everything logged from now on
+    pub fn set_level(&self, level: LevelFilter) -> Result<(), String> {
+        self.level.reload(level).map_err(|e| e.to_string())
+    }
+
+    /// The file currently written to
+    pub fn current_file(&self) -> Option<PathBuf> {
+        newest_log_file(&self.dir)
+    }
+}
+
+/// Newest log file in `dir`
+///
+/// The date in the name sorts chronologically, so the newest is the last.
+pub fn newest_log_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|ext| ext == LOG_FILE_SUFFIX)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&format!("{}.", LOG_FILE_PREFIX)))
+        })
+        .max()
+}
+
+/// The last `max_bytes` of a log file, starting on a whole line
+pub fn read_tail(path: &Path, max_bytes: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    if start == 0 {
+        return Ok(text.into_owned());
+    }
+    // Drop the partial first line
+    Ok(text
+        .split_once('\n')
+        .map(|(_, rest)| rest)
+        .unwrap_or("")
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("DEBUG"), Some(LevelFilter::DEBUG));
+        assert_eq!(parse_level(" warning "), Some(LevelFilter::WARN));
+        assert_eq!(parse_level("off"), Some(LevelFilter::OFF));
+        assert_eq!(parse_level(DEFAULT_LOG_LEVEL), Some(LevelFilter::INFO));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn test_newest_log_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(newest_log_file(dir.path()), None);
+
+        for name in [
+            "mt.2026-10-14.log",
+            "mt.2026-10-16.log",
+            "mt.2026-10-15.log",
+            "other.log",
+        ] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        std::fs::write(dir.path().join("mt.2026-10-17.txt"), b"").unwrap();
+
+        assert_eq!(
+            newest_log_file(dir.path()),
+            Some(dir.path().join("mt.2026-10-16.log"))
+        );
+    }
+
+    #[test]
+    fn test_read_tail_starts_on_a_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mt.2026-10-16.log");
+        std::fs::write(&path, "first line\nsecond line\nthird\n").unwrap();
+
+        assert_eq!(
+            read_tail(&path, 1024).unwrap(),
+            "first line\nsecond line\nthird\n"
+        );
+        assert_eq!(read_tail(&path, 15).unwrap(), "third\n");
+        assert!(read_tail(&dir.path().join("missing.log"), 10).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+//! Tauri commands for coordinated playback.
+//!
+//! These commands replace the frontend coordinating `audio_*`, `queue_*`,
+//! and `media_set_*` calls separately, which could drift out of sync.
+
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tracing::warn;
+
+use crate::audio::TrackInfo;
+use crate::commands::{AudioState, lastfm_now_playing};
+use crate::db::{Database, ResolvedProfile, Track, audio_profiles, library, queue};
+use crate::events::{EventEmitter, QueueStateChangedEvent};
+use crate::lastfm::NowPlayingRequest;
+use crate::media_keys::{MediaKeyManager, NowPlayingInfo};
+use crate::playback::{PlaybackManager, PlaybackSession};
+
+/// How long a track must keep playing before Last.fm is told it's now playing,
+/// so skipping rapidly through tracks doesn't send an update for each one
+const NOW_PLAYING_DELAY: Duration = Duration::from_secs(1);
+
+/// Combined status returned after starting a track
+#[derive(Clone, serde::Serialize)]
+pub struct PlayTrackResponse {
+    /// Library metadata for the track
+    pub track: Track,
+    /// Stream info reported by the audio engine
+    pub track_info: TrackInfo,
+    /// Queue position now marked as current (None if the track is not queued)
+    pub queue_index: Option<i64>,
+    /// Whether the OS Now Playing widget was updated
+    pub media_keys_updated: bool,
+    /// Whether a Last.fm now-playing update was scheduled
+    pub now_playing_requested: bool,
+    /// EQ/normalization profile selected for the track (None if lookup failed)
+    ///
+    /// Only reported for the frontend to show; the engine has no EQ or
+    /// normalization yet, so nothing is applied to playback.
+    pub audio_profile: Option<ResolvedProfile>,
+}
+
+/// Load and play a library track, updating every dependent subsystem.
+///
+/// Loading into the audio engine is the only required step. Queue index,
+/// media keys, and Last.fm updates are best-effort: failures are logged and
+/// reported in the response without interrupting playback. Play count is
+/// armed on load and incremented by the audio thread once the track passes
+/// the play-count threshold, so skipped tracks are not counted. The audio
+/// profile is chosen from the first matching profile rule, falling back to
+/// the active output device's profile and then the global one, and only
+/// reported in the response until the engine
+/// supports EQ and normalization.
+#[tauri::command]
+pub fn playback_play_track(
+    app: AppHandle,
+    db: State<'_, Database>,
+    audio: State<'_, AudioState>,
+    playback: State<'_, PlaybackManager>,
+    track_id: i64,
+) -> Result<PlayTrackResponse, String> {
+    let mut session = playback.lock();
+    play_track(&app, &db, &audio, &mut session, track_id)
+}
+
+/// Body of `playback_play_track`, for callers that already hold the session
+pub(crate) fn play_track(
+    app: &AppHandle,
+    db: &Database,
+    audio: &AudioState,
+    session: &mut PlaybackSession,
+    track_id: i64,
+) -> Result<PlayTrackResponse, String> {
+    let track = {
+        let conn = db.conn().map_err(|e| e.to_string())?;
+        library::get_track_by_id(&conn, track_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track with id {} not found", track_id))?
+    };
+
+    let track_info = audio.load(track.filepath.clone(), Some(track_id))?;
+    audio.play()?;
+    session.track_id = Some(track_id);
+
+    let audio_profile = track_audio_profile(db, track_id);
+
+    let queue_index = match sync_queue_index(app, db, &track.filepath) {
+        Ok(index) => index,
+        Err(e) => {
+            warn!("Failed to update queue index: {}", e);
+            None
+        }
+    };
+
+    let media_keys_updated = match app.try_state::<MediaKeyManager>() {
+        Some(media_keys) => match update_media_keys(&media_keys, &track, &track_info) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to update media keys: {}", e);
+                false
+            }
+        },
+        None => false,
+    };
+
+    let now_playing_requested = request_now_playing(app, &track, &track_info);
+
+    Ok(PlayTrackResponse {
+        track,
+        track_info,
+        queue_index,
+        media_keys_updated,
+        now_playing_requested,
+        audio_profile,
+    })
+}
+
+/// Profile for a track on the active output device, `None` if lookup failed
+fn track_audio_profile(db: &Database, track_id: i64) -> Option<ResolvedProfile> {
+    match db.with_conn(|conn| audio_profiles::resolve_profile(conn, track_id)) {
+        Ok(profile) => profile,
+        Err(e) => {
+            warn!("Failed to resolve audio profile: {}", e);
+            None
+        }
+    }
+}
+
+/// Point the queue's current index at the track and emit a state change
+fn sync_queue_index(app: &AppHandle, db: &Database, filepath: &str) -> Result<Option<i64>, String> {
+    let conn = db.conn().map_err(|e| e.to_string())?;
+
+    let Some(position) = queue::find_queue_position(&conn, filepath).map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+
+    queue::set_current_index(&conn, position).map_err(|e| e.to_string())?;
+
+    let state = queue::get_queue_state(&conn).map_err(|e| e.to_string())?;
+    let _ = app.emit_queue_state_changed(QueueStateChangedEvent::new(
+        state.current_index,
+        state.shuffle_enabled,
+        state.loop_mode,
+    ));
+
+    Ok(Some(position))
+}
+
+/// Publish track metadata and playing state to the OS media controls
+fn update_media_keys(
+    media_keys: &MediaKeyManager,
+    track: &Track,
+    track_info: &TrackInfo,
+) -> Result<(), String> {
+    media_keys.set_metadata(NowPlayingInfo {
+        title: track.title.clone(),
+        artist: track.artist.clone(),
+        album: track.album.clone(),
+        duration: Some(Duration::from_millis(track_info.duration_ms)),
+        cover_url: None,
+    })?;
+    media_keys.set_playing(Some(Duration::ZERO))
+}
+
+/// Schedule a Last.fm now-playing update for a track just loaded through
+/// `audio_load`
+///
+/// Like `playback_play_track`, so the frontend's own load path gets the same
+/// delay and doesn't send an update for each track skipped past.
+pub(crate) fn request_now_playing_for(app: &AppHandle, track_id: i64, track_info: &TrackInfo) {
+    let db = app.state::<Database>();
+    match db.with_conn(|conn| library::get_track_by_id(conn, track_id)) {
+        Ok(Some(track)) => {
+            request_now_playing(app, &track, track_info);
+        }
+        Ok(None) => {}
+        Err(e) => warn!(
+            "Failed to look up track {} for now playing: {}",
+            track_id, e
+        ),
+    }
+}
+
+/// Schedule a Last.fm now-playing update in the background
+///
+/// The update is sent after `NOW_PLAYING_DELAY` and only if no other track
+/// was loaded meanwhile. `lastfm_now_playing` skips silently when scrobbling
+/// is disabled or not authenticated. Returns false when the track lacks the
+/// artist/title Last.fm requires.
+fn request_now_playing(app: &AppHandle, track: &Track, track_info: &TrackInfo) -> bool {
+    let token = app.state::<PlaybackManager>().begin_now_playing();
+    let Some(request) = build_now_playing_request(track, track_info) else {
+        return false;
+    };
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(NOW_PLAYING_DELAY).await;
+
+        if !app.state::<PlaybackManager>().is_latest_now_playing(token) {
+            return;
+        }
+
+        let db = app.state::<Database>();
+        if let Err(e) = lastfm_now_playing(db, request).await {
+            warn!("Last.fm now playing failed: {}", e);
+        }
+    });
+
+    true
+}
+
+/// Build a now-playing request, preferring the decoded stream duration
+/// over the library's tagged duration
+fn build_now_playing_request(track: &Track, track_info: &TrackInfo) -> Option<NowPlayingRequest> {
+    let (Some(artist), Some(title)) = (track.artist.clone(), track.title.clone()) else {
+        return None;
+    };
+
+    let duration = if track_info.duration_ms > 0 {
+        (track_info.duration_ms / 1000) as u32
+    } else {
+        track.duration.unwrap_or(0.0) as u32
+    };
+
+    Some(NowPlayingRequest {
+        artist,
+        track: title,
+        album: track.album.clone(),
+        duration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_track_response_serialization() {
+        let response = PlayTrackResponse {
+            track: Track {
+                id: 7,
+                filepath: "/music/song.mp3".to_string(),
+                title: Some("Song".to_string()),
+                ..Default::default()
+            },
+            track_info: TrackInfo {
+                path: "/music/song.mp3".to_string(),
+                duration_ms: 180000,
+                sample_rate: 44100,
+                channels: 2,
+            },
+            queue_index: Some(3),
+            media_keys_updated: true,
+            now_playing_requested: false,
+            audio_profile: None,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"queue_index\":3"));
+        assert!(json.contains("\"media_keys_updated\":true"));
+        assert!(json.contains("\"now_playing_requested\":false"));
+        assert!(json.contains("\"duration_ms\":180000"));
+    }
+
+    #[test]
+    fn test_play_track_response_not_queued() {
+        let response = PlayTrackResponse {
+            track: Track::default(),
+            track_info: TrackInfo {
+                path: String::new(),
+                duration_ms: 0,
+                sample_rate: 0,
+                channels: 0,
+            },
+            queue_index: None,
+            media_keys_updated: false,
+            now_playing_requested: false,
+            audio_profile: None,
+        };
+
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(json.contains("\"queue_index\":null"));
+    }
+
+    #[test]
+    fn test_audio_profile_follows_active_device() {
+        use crate::db::{AudioProfile, NormalizationMode, TrackMetadata, settings};
+
+        let db = Database::new_in_memory().unwrap();
+        let headphones = AudioProfile {
+            eq_preset: "bass_boost".to_string(),
+            normalization: NormalizationMode::Track,
+        };
+        let track_id = db
+            .with_conn(|conn| {
+                audio_profiles::set_device_profile(conn, "Headphones", &headphones)?;
+                library::add_track(conn, "/music/song.mp3", &TrackMetadata::default())
+            })
+            .unwrap();
+        let switch_to = |device_id: &str| {
+            db.with_conn(|conn| {
+                let device = serde_json::json!(device_id);
+                settings::set_setting(conn, audio_profiles::ACTIVE_DEVICE_SETTING, &device)
+            })
+            .unwrap();
+            track_audio_profile(&db, track_id).unwrap().profile
+        };
+
+        assert_eq!(switch_to("Headphones"), headphones);
+        let speakers = switch_to("Built-in Speakers");
+        assert_ne!(speakers, headphones);
+        assert_eq!(
+            speakers,
+            db.with_conn(audio_profiles::get_global_profile).unwrap()
+        );
+
+        assert!(track_audio_profile(&db, track_id + 1).is_none());
+    }
+
+    #[test]
+    fn test_build_now_playing_request_uses_stream_duration() {
+        let track = Track {
+            artist: Some("Artist".to_string()),
+            title: Some("Song".to_string()),
+            album: Some("Album".to_string()),
+            duration: Some(200.0),
+            ..Default::default()
+        };
+        let track_info = TrackInfo {
+            path: String::new(),
+            duration_ms: 215_500,
+            sample_rate: 44100,
+            channels: 2,
+        };
+
+        let request = build_now_playing_request(&track, &track_info).unwrap();
+        assert_eq!(request.artist, "Artist");
+        assert_eq!(request.track, "Song");
+        assert_eq!(request.album.as_deref(), Some("Album"));
+        assert_eq!(request.duration, 215);
+    }
+
+    #[test]
+    fn test_build_now_playing_request_falls_back_to_library_duration() {
+        let track = Track {
+            artist: Some("Artist".to_string()),
+            title: Some("Song".to_string()),
+            duration: Some(180.4),
+            ..Default::default()
+        };
+        let track_info = TrackInfo {
+            path: String::new(),
+            duration_ms: 0,
+            sample_rate: 0,
+            channels: 0,
+        };
+
+        let request = build_now_playing_request(&track, &track_info).unwrap();
+        assert_eq!(request.duration, 180);
+    }
+
+    #[test]
+    fn test_build_now_playing_request_requires_artist_and_title() {
+        let track = Track {
+            title: Some("Song".to_string()),
+            ..Default::default()
+        };
+        let track_info = TrackInfo {
+            path: String::new(),
+            duration_ms: 180000,
+            sample_rate: 44100,
+            channels: 2,
+        };
+
+        assert!(build_now_playing_request(&track, &track_info).is_none());
+    }
+}
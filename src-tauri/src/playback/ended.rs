@@ -0,0 +1,430 @@
+//! Moving the queue on when a track plays to its end.
+//!
+//! The audio thread reports every track that finishes, or that a relative
+//! seek skipped past. For a track the orchestrator started, the queue picks
+//! what comes next following the loop mode and shuffle order, and that track
+//! starts through the same path as `playback_play_track`. At the end of the
+//! queue playback stops, unless radio top-up is on and finds more tracks.
+//! In consume mode each ended entry is removed from the queue on the way.
+//! With `skip_missing_in_queue` on, entries whose files are missing are
+//! passed over, and playback stops when only missing ones are left.
+//! Tracks the frontend loaded itself are left to the frontend, which hears
+//! about their end through `audio://track-ended`.
+
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info, warn};
+
+use crate::commands::AudioState;
+use crate::db::queue::{self, QueueAdvance};
+use crate::db::{Database, Track, library, settings};
+use crate::events::{EventEmitter, QueueSkippedMissingEvent, QueueUpdatedEvent};
+use crate::media_keys::MediaKeyManager;
+use crate::playback::{EndedOwner, PlaybackManager, PlaybackSession, play_track};
+
+/// Settings key for whether an exhausted queue is topped up with radio tracks
+const RADIO_TOP_UP_SETTING: &str = "queue_radio_top_up";
+
+/// Starts and stops tracks for `advance_queue`
+trait QueuePlayer {
+    fn play(&mut self, session: &mut PlaybackSession, track: &Track) -> Result<(), String>;
+    fn stop(&mut self, session: &mut PlaybackSession) -> Result<(), String>;
+}
+
+/// Plays through the audio thread and updates every dependent subsystem
+struct AppPlayer<'a> {
+    app: &'a AppHandle,
+    db: &'a Database,
+    audio: &'a AudioState,
+}
+
+impl QueuePlayer for AppPlayer<'_> {
+    fn play(&mut self, session: &mut PlaybackSession, track: &Track) -> Result<(), String> {
+        play_track(self.app, self.db, self.audio, session, track.id).map(|_| ())
+    }
+
+    fn stop(&mut self, session: &mut PlaybackSession) -> Result<(), String> {
+        self.audio.stop()?;
+        session.clear();
+        if let Some(media_keys) = self.app.try_state::<MediaKeyManager>() {
+            media_keys.set_stopped()?;
+        }
+        Ok(())
+    }
+}
+
+/// Handle the end of load `load_seq` of `track_id` on the audio thread
+///
+/// Holds the playback session for the whole transition, so ended signals
+/// that arrive together are handled one after the other, and any after the
+/// first for the same load are ignored. Either the queue advances here or
+/// the frontend is told to advance it, never both.
+pub fn handle_track_ended(app: &AppHandle, track_id: Option<i64>, load_seq: u64) {
+    let (Some(db), Some(audio), Some(playback)) = (
+        app.try_state::<Database>(),
+        app.try_state::<AudioState>(),
+        app.try_state::<PlaybackManager>(),
+    ) else {
+        return;
+    };
+
+    let radio_top_up = db
+        .with_conn(|conn| settings::get_setting(conn, RADIO_TOP_UP_SETTING))
+        .ok()
+        .flatten()
+        .is_some_and(|value| value == "1" || value == "true");
+
+    let mut session = playback.lock();
+    match session.claim_ended(track_id, load_seq) {
+        EndedOwner::Backend => {}
+        EndedOwner::Frontend => {
+            let _ = app.emit("audio://track-ended", ());
+            return;
+        }
+        EndedOwner::Handled => return,
+    }
+
+    let mut player = AppPlayer {
+        app,
+        db: &db,
+        audio: &audio,
+    };
+    let step = advance_queue(&db, &mut session, &mut player, rand::random(), radio_top_up);
+
+    match step {
+        Ok(step) => {
+            if matches!(step, QueueAdvance::End { .. }) {
+                info!("Reached the end of the queue");
+            }
+            emit_queue_changes(app, &db, &step);
+        }
+        Err(e) => error!("Failed to advance the queue: {}", e),
+    }
+}
+
+/// Tell the frontend about entries the advance skipped, consumed, added, or
+/// reshuffled
+fn emit_queue_changes(app: &AppHandle, db: &Database, step: &QueueAdvance) {
+    let (reshuffled, topped_up, consumed, skipped) = match step {
+        QueueAdvance::Play {
+            reshuffled,
+            topped_up,
+            consumed,
+            skipped,
+            ..
+        } => (*reshuffled, *topped_up as i64, *consumed, skipped),
+        QueueAdvance::End { consumed, skipped } => (false, 0, *consumed, skipped),
+    };
+    if !skipped.is_empty() {
+        warn!("Skipped missing queue entries: {}", skipped.join(", "));
+        let event = QueueSkippedMissingEvent::new(skipped.clone());
+        let _ = app.emit_queue_skipped_missing(event);
+    }
+    if !reshuffled && topped_up == 0 && consumed.is_none() {
+        return;
+    }
+    let Ok(queue_length) = db.with_conn(queue::get_queue_length) else {
+        return;
+    };
+
+    if let Some(position) = consumed {
+        let remaining = queue_length - topped_up;
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::removed(position, remaining));
+    }
+    if topped_up > 0 {
+        let first = queue_length - topped_up;
+        let positions = (first..queue_length).collect();
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::added(positions, queue_length));
+    } else if reshuffled {
+        let _ = app.emit_queue_updated(QueueUpdatedEvent::shuffled(queue_length));
+    }
+}
+
+/// Advance the queue past a claimed ended load and start or stop playback
+/// to match
+fn advance_queue(
+    db: &Database,
+    session: &mut PlaybackSession,
+    player: &mut impl QueuePlayer,
+    seed: u64,
+    radio_top_up: bool,
+) -> Result<QueueAdvance, String> {
+    let step = db
+        .transaction(|conn| queue::advance_after_ended(conn, seed, radio_top_up))
+        .map_err(|e| e.to_string())?;
+
+    match &step {
+        QueueAdvance::Play { filepath, .. } => {
+            let track = db
+                .with_conn(|conn| library::get_track_by_filepath(conn, filepath))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("Queued file {} is not in the library", filepath))?;
+            player.play(session, &track)?;
+        }
+        QueueAdvance::End { .. } => player.stop(session)?,
+    }
+
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::mock::{MockBackend, MockHandle};
+    use crate::audio::{AudioEngine, PlaybackNotice, PlaybackNotifier, PlaybackState};
+    use crate::db::{QueueState, TrackMetadata};
+    use std::time::Instant;
+
+    /// Mock-backed engine standing in for the audio thread
+    struct EnginePlayer {
+        engine: AudioEngine,
+        handle: MockHandle,
+        load_seq: u64,
+        played: Vec<String>,
+    }
+
+    impl QueuePlayer for EnginePlayer {
+        fn play(&mut self, session: &mut PlaybackSession, track: &Track) -> Result<(), String> {
+            self.engine
+                .load(&track.filepath)
+                .map_err(|e| e.to_string())?;
+            self.engine.play().map_err(|e| e.to_string())?;
+            self.load_seq += 1;
+            self.played.push(track.filepath.clone());
+            session.track_id = Some(track.id);
+            Ok(())
+        }
+
+        fn stop(&mut self, session: &mut PlaybackSession) -> Result<(), String> {
+            self.engine.stop();
+            session.track_id = None;
+            Ok(())
+        }
+    }
+
+    fn setup_queue(loop_mode: &str, shuffle: bool) -> (Database, Vec<Track>) {
+        let db = Database::new_in_memory().unwrap();
+        let tracks = db
+            .with_conn(|conn| {
+                let mut tracks = Vec::new();
+                for i in 1..=3 {
+                    let filepath = format!("/music/track{}.mp3", i);
+                    library::add_track(conn, &filepath, &TrackMetadata::default())?;
+                    tracks.push(library::get_track_by_filepath(conn, &filepath)?.unwrap());
+                }
+                let ids: Vec<i64> = tracks.iter().map(|t| t.id).collect();
+                queue::add_to_queue(conn, &ids, None)?;
+                queue::set_queue_state(
+                    conn,
+                    &QueueState {
+                        current_index: 0,
+                        shuffle_enabled: shuffle,
+                        loop_mode: loop_mode.to_string(),
+                        original_order_json: None,
+                        shuffle_seed: None,
+                        consume_enabled: false,
+                        shuffle_mode: "tracks".to_string(),
+                    },
+                )?;
+                Ok(tracks)
+            })
+            .unwrap();
+        (db, tracks)
+    }
+
+    /// Handle an ended signal for the current load like `handle_track_ended`
+    fn report_end(
+        db: &Database,
+        session: &mut PlaybackSession,
+        player: &mut EnginePlayer,
+        ended: (Option<i64>, u64),
+        seed: u64,
+    ) -> (EndedOwner, Option<QueueAdvance>) {
+        let owner = session.claim_ended(ended.0, ended.1);
+        let step = (owner == EndedOwner::Backend)
+            .then(|| advance_queue(db, session, player, seed, false).unwrap());
+        (owner, step)
+    }
+
+    /// Play the queue from the top, letting each track run to its end, until
+    /// playback stops or `max_ends` tracks have ended
+    fn play_through(db: &Database, first: &Track, max_ends: usize) -> EnginePlayer {
+        let (backend, handle) = MockBackend::with_handle();
+        let mut player = EnginePlayer {
+            engine: AudioEngine::with_backend(Box::new(backend)),
+            handle,
+            load_seq: 0,
+            played: Vec::new(),
+        };
+        let mut session = PlaybackSession::default();
+        let mut notifier = PlaybackNotifier::new();
+        player.play(&mut session, first).unwrap();
+
+        for seed in 0..max_ends as u64 {
+            notifier.observe(&player.engine, Instant::now());
+            player.handle.lock().drained = true;
+            let notices = notifier.observe(&player.engine, Instant::now());
+            assert!(notices.contains(&PlaybackNotice::TrackEnded));
+
+            // The ended signal arrives twice; only the first advances
+            let ended = (session.track_id, player.load_seq);
+            let (owner, step) = report_end(db, &mut session, &mut player, ended, seed);
+            assert_eq!(owner, EndedOwner::Backend);
+            let (owner, _) = report_end(db, &mut session, &mut player, ended, seed);
+            assert_eq!(owner, EndedOwner::Handled);
+
+            if matches!(step, Some(QueueAdvance::End { .. })) {
+                break;
+            }
+        }
+        player
+    }
+
+    fn names(played: &[String]) -> Vec<&str> {
+        played
+            .iter()
+            .map(|path| path.trim_start_matches("/music/").trim_end_matches(".mp3"))
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_none_stops_at_end_of_queue() {
+        let (db, tracks) = setup_queue("none", false);
+        let player = play_through(&db, &tracks[0], 10);
+
+        assert_eq!(names(&player.played), ["track1", "track2", "track3"]);
+        assert_eq!(player.engine.get_state(), PlaybackState::Stopped);
+        assert!(player.handle.lock().track.is_none());
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, 2);
+    }
+
+    #[test]
+    fn test_loop_all_wraps_to_start() {
+        let (db, tracks) = setup_queue("all", false);
+        let player = play_through(&db, &tracks[0], 5);
+
+        assert_eq!(
+            names(&player.played),
+            ["track1", "track2", "track3", "track1", "track2", "track3"]
+        );
+        assert_eq!(player.engine.get_state(), PlaybackState::Playing);
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, 2);
+    }
+
+    #[test]
+    fn test_loop_one_repeats_current_track() {
+        let (db, tracks) = setup_queue("one", false);
+        let player = play_through(&db, &tracks[0], 3);
+
+        assert_eq!(names(&player.played), ["track1"; 4]);
+        assert_eq!(player.load_seq, 4);
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, 0);
+    }
+
+    #[test]
+    fn test_loop_all_with_shuffle_plays_each_track_per_cycle() {
+        let (db, tracks) = setup_queue("all", true);
+        let player = play_through(&db, &tracks[0], 8);
+        let played = names(&player.played);
+
+        assert_eq!(played.len(), 9);
+        for cycle in played.chunks(3) {
+            let mut cycle = cycle.to_vec();
+            cycle.sort();
+            assert_eq!(cycle, ["track1", "track2", "track3"]);
+        }
+        // The last track of a cycle never opens the next one
+        assert_ne!(played[3], played[2]);
+        assert_ne!(played[6], played[5]);
+    }
+
+    #[test]
+    fn test_consume_empties_queue_and_stops() {
+        let (db, tracks) = setup_queue("all", false);
+        db.with_conn(|conn| queue::set_consume_enabled(conn, true))
+            .unwrap();
+        let player = play_through(&db, &tracks[0], 10);
+
+        // Consume wins over loop all, so nothing plays twice
+        assert_eq!(names(&player.played), ["track1", "track2", "track3"]);
+        assert_eq!(player.engine.get_state(), PlaybackState::Stopped);
+        assert_eq!(db.with_conn(queue::get_queue_length).unwrap(), 0);
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, -1);
+    }
+
+    #[test]
+    fn test_skip_missing_plays_available_tracks_only() {
+        let (db, tracks) = setup_queue("all", false);
+        db.with_conn(|conn| {
+            library::mark_track_missing(conn, tracks[1].id)?;
+            settings::set_setting(conn, queue::SKIP_MISSING_SETTING, &serde_json::json!(true))
+        })
+        .unwrap();
+        let player = play_through(&db, &tracks[0], 4);
+
+        assert_eq!(
+            names(&player.played),
+            ["track1", "track3", "track1", "track3", "track1"]
+        );
+
+        // Once nothing left is available, playback stops
+        db.with_conn(|conn| {
+            library::mark_track_missing(conn, tracks[2].id)?;
+            queue::set_loop_mode(conn, "none")
+        })
+        .unwrap();
+        let player = play_through(&db, &tracks[0], 4);
+        assert_eq!(names(&player.played), ["track1"]);
+        assert_eq!(player.engine.get_state(), PlaybackState::Stopped);
+    }
+
+    #[test]
+    fn test_one_track_end_advances_once() {
+        let (db, tracks) = setup_queue("none", false);
+        let (backend, handle) = MockBackend::with_handle();
+        let mut player = EnginePlayer {
+            engine: AudioEngine::with_backend(Box::new(backend)),
+            handle,
+            load_seq: 0,
+            played: Vec::new(),
+        };
+        let mut session = PlaybackSession::default();
+        player.play(&mut session, &tracks[0]).unwrap();
+
+        // A relative seek past the end and the drained stream both report it
+        let ended = (session.track_id, player.load_seq);
+        let owners: Vec<EndedOwner> = (0..2)
+            .map(|seed| report_end(&db, &mut session, &mut player, ended, seed).0)
+            .collect();
+
+        assert_eq!(owners, [EndedOwner::Backend, EndedOwner::Handled]);
+        assert_eq!(names(&player.played), ["track1", "track2"]);
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, 1);
+    }
+
+    #[test]
+    fn test_untracked_load_is_left_alone() {
+        let (db, tracks) = setup_queue("none", false);
+        let (backend, handle) = MockBackend::with_handle();
+        let mut player = EnginePlayer {
+            engine: AudioEngine::with_backend(Box::new(backend)),
+            handle,
+            load_seq: 1,
+            played: Vec::new(),
+        };
+        // The frontend loaded this track, so the session isn't driving it
+        let mut session = PlaybackSession::default();
+
+        let ended = (Some(tracks[0].id), player.load_seq);
+        let (owner, step) = report_end(&db, &mut session, &mut player, ended, 0);
+        assert_eq!(owner, EndedOwner::Frontend);
+        assert!(step.is_none());
+        assert!(player.played.is_empty());
+        let state = db.with_conn(queue::get_queue_state).unwrap();
+        assert_eq!(state.current_index, 0);
+    }
+}
@@ -0,0 +1,147 @@
+//! Playback orchestration module.
+//!
+//! Coordinates the audio engine, queue state, media keys, and Last.fm so a
+//! track change is a single backend transition instead of several frontend calls.
+
+pub mod commands;
+mod ended;
+
+pub use commands::*;
+pub use ended::handle_track_ended;
+
+use parking_lot::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// State of the track currently driven by the playback orchestrator
+#[derive(Debug, Default)]
+pub struct PlaybackSession {
+    /// Library ID of the track currently loaded through the orchestrator
+    pub track_id: Option<i64>,
+    /// Audio thread load whose end has already been handled
+    pub advanced_load: Option<u64>,
+}
+
+/// Which side moves the queue on when a load ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndedOwner {
+    /// The orchestrator started the track and advances the queue itself
+    Backend,
+    /// The frontend loaded the track and advances on `audio://track-ended`
+    Frontend,
+    /// The end of this load was already handled
+    Handled,
+}
+
+impl PlaybackSession {
+    /// Claim the end of a load for advancing the queue
+    ///
+    /// Each load is handed to exactly one side, once, so repeated ended
+    /// signals for one load are ignored.
+    pub fn claim_ended(&mut self, track_id: Option<i64>, load_seq: u64) -> EndedOwner {
+        if self.advanced_load == Some(load_seq) {
+            return EndedOwner::Handled;
+        }
+        self.advanced_load = Some(load_seq);
+        if track_id.is_some() && track_id == self.track_id {
+            EndedOwner::Backend
+        } else {
+            EndedOwner::Frontend
+        }
+    }
+
+    /// Forget the driven track, once playback stops or the frontend loads
+    /// a track itself
+    pub fn clear(&mut self) {
+        self.track_id = None;
+    }
+}
+
+/// Serializes playback transitions so audio, queue, and media key state
+/// are always updated together.
+#[derive(Default)]
+pub struct PlaybackManager {
+    session: Mutex<PlaybackSession>,
+    /// Last Last.fm now-playing request started, by any load path
+    now_playing_seq: AtomicU64,
+}
+
+impl PlaybackManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock the session for the duration of a playback transition
+    pub fn lock(&self) -> MutexGuard<'_, PlaybackSession> {
+        self.session.lock()
+    }
+
+    /// Library ID of the track currently loaded through the orchestrator
+    pub fn current_track_id(&self) -> Option<i64> {
+        self.session.lock().track_id
+    }
+
+    /// Start a now-playing request, superseding any still waiting to be sent
+    pub fn begin_now_playing(&self) -> u64 {
+        self.now_playing_seq.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Whether no track has been loaded since the request `token` started
+    pub fn is_latest_now_playing(&self, token: u64) -> bool {
+        self.now_playing_seq.load(Ordering::SeqCst) == token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playback_manager_starts_empty() {
+        let manager = PlaybackManager::new();
+        assert!(manager.current_track_id().is_none());
+    }
+
+    #[test]
+    fn test_playback_manager_tracks_current_track() {
+        let manager = PlaybackManager::new();
+
+        {
+            let mut session = manager.lock();
+            session.track_id = Some(42);
+        }
+
+        assert_eq!(manager.current_track_id(), Some(42));
+    }
+
+    #[test]
+    fn test_claim_ended_once_per_load() {
+        let mut session = PlaybackSession {
+            track_id: Some(7),
+            ..Default::default()
+        };
+
+        assert_eq!(session.claim_ended(Some(7), 1), EndedOwner::Backend);
+        assert_eq!(session.claim_ended(Some(7), 1), EndedOwner::Handled);
+        // The same track loaded again (loop one) ends as a new load
+        assert_eq!(session.claim_ended(Some(7), 2), EndedOwner::Backend);
+        assert_eq!(session.claim_ended(Some(8), 3), EndedOwner::Frontend);
+        assert_eq!(session.claim_ended(Some(8), 3), EndedOwner::Handled);
+
+        // A frontend load of the same track is the frontend's to advance
+        session.clear();
+        assert_eq!(session.claim_ended(Some(7), 4), EndedOwner::Frontend);
+        assert_eq!(session.claim_ended(None, 5), EndedOwner::Frontend);
+    }
+
+    #[test]
+    fn test_later_load_supersedes_now_playing() {
+        let manager = PlaybackManager::new();
+
+        let first = manager.begin_now_playing();
+        assert!(manager.is_latest_now_playing(first));
+
+        let second = manager.begin_now_playing();
+        assert!(!manager.is_latest_now_playing(first));
+        assert!(manager.is_latest_now_playing(second));
+    }
+}
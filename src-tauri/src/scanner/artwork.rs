@@ -10,24 +10,16 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
-/// Standard filenames to look for folder artwork (case-insensitive)
-pub const ARTWORK_FILENAMES: &[&str] = &[
-    "cover.jpg",
-    "cover.jpeg",
-    "cover.png",
-    "folder.jpg",
-    "folder.jpeg",
-    "folder.png",
-    "album.jpg",
-    "album.jpeg",
-    "album.png",
-    "front.jpg",
-    "front.jpeg",
-    "front.png",
-    "artwork.jpg",
-    "artwork.jpeg",
-    "artwork.png",
-];
+/// Default folder artwork patterns, in priority order (case-insensitive)
+pub const DEFAULT_ARTWORK_PATTERNS: &[&str] =
+    &["cover.*", "folder.*", "album.*", "front.*", "artwork.*"];
+
+/// Image extensions accepted for folder artwork; earlier wins when one
+/// pattern matches several files (e.g. cover.jpg over cover.png)
+pub const ARTWORK_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "bmp", "webp"];
+
+/// Setting key holding the folder artwork patterns as a JSON array
+pub const ARTWORK_PATTERNS_SETTING: &str = "artwork_folder_patterns";
 
 /// Extracted artwork data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,7 +28,7 @@ pub struct Artwork {
     pub data: String,
     /// MIME type (e.g., "image/jpeg", "image/png")
     pub mime_type: String,
-    /// Source of the artwork ("embedded" or "folder")
+    /// Source of the artwork ("embedded", "folder", or "override")
     pub source: String,
     /// For folder artwork, the filename found
     pub filename: Option<String>,
@@ -60,98 +52,200 @@ pub fn get_embedded_artwork(filepath: &str) -> Option<Artwork> {
     // Use the first picture (typically front cover)
     let picture = &pictures[0];
 
-    let mime_type = match picture.mime_type() {
+    Some(embedded_artwork_from_bytes(picture.mime_type(), picture.data()))
+}
+
+/// Build embedded artwork from a tag picture's full data
+///
+/// The complete picture is always encoded; high-res covers (often 50-500KB)
+/// must not be truncated. When the tag's MIME type is missing or unknown,
+/// the type is sniffed from the image header instead of assuming JPEG.
+fn embedded_artwork_from_bytes(mime: Option<&lofty::picture::MimeType>, data: &[u8]) -> Artwork {
+    let mime_type = match mime {
         Some(lofty::picture::MimeType::Jpeg) => "image/jpeg",
         Some(lofty::picture::MimeType::Png) => "image/png",
         Some(lofty::picture::MimeType::Gif) => "image/gif",
         Some(lofty::picture::MimeType::Bmp) => "image/bmp",
         Some(lofty::picture::MimeType::Tiff) => "image/tiff",
-        _ => "image/jpeg", // Default
+        _ => sniff_image_mime(data).unwrap_or("image/jpeg"),
     };
 
-    Some(Artwork {
-        data: BASE64.encode(picture.data()),
+    Artwork {
+        data: BASE64.encode(data),
         mime_type: mime_type.to_string(),
         source: "embedded".to_string(),
         filename: None,
-    })
+    }
+}
+
+/// Detect an image MIME type from its magic bytes
+fn sniff_image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.starts_with(b"BM") {
+        Some("image/bmp")
+    } else if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        Some("image/tiff")
+    } else {
+        None
+    }
 }
 
 /// Find folder-based artwork in the same directory as the audio file
 pub fn get_folder_artwork(filepath: &str) -> Option<Artwork> {
-    let path = Path::new(filepath);
-    let folder = path.parent()?;
-
-    // Try exact filenames first
-    for filename in ARTWORK_FILENAMES {
-        let artwork_path = folder.join(filename);
-        if artwork_path.exists()
-            && let Ok(data) = fs::read(&artwork_path) {
-                let ext = artwork_path.extension()?.to_str()?.to_lowercase();
-                let mime_type = match ext.as_str() {
-                    "jpg" | "jpeg" => "image/jpeg",
-                    "png" => "image/png",
-                    "gif" => "image/gif",
-                    "bmp" => "image/bmp",
-                    _ => "image/jpeg",
-                };
-
-                return Some(Artwork {
-                    data: BASE64.encode(&data),
-                    mime_type: mime_type.to_string(),
-                    source: "folder".to_string(),
-                    filename: Some(filename.to_string()),
-                });
-            }
-    }
-
-    // Try case-insensitive search
-    let folder_entries: Vec<_> = fs::read_dir(folder).ok()?.filter_map(|e| e.ok()).collect();
-
-    for entry in &folder_entries {
-        let entry_name = entry.file_name();
-        let entry_name_lower = entry_name.to_string_lossy().to_lowercase();
-
-        for filename in ARTWORK_FILENAMES {
-            if entry_name_lower == *filename {
-                let artwork_path = entry.path();
-                if let Ok(data) = fs::read(&artwork_path) {
-                    let ext = artwork_path
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .unwrap_or("")
-                        .to_lowercase();
-                    let mime_type = match ext.as_str() {
-                        "jpg" | "jpeg" => "image/jpeg",
-                        "png" => "image/png",
-                        "gif" => "image/gif",
-                        "bmp" => "image/bmp",
-                        _ => "image/jpeg",
-                    };
-
-                    return Some(Artwork {
-                        data: BASE64.encode(&data),
-                        mime_type: mime_type.to_string(),
-                        source: "folder".to_string(),
-                        filename: Some(entry_name.to_string_lossy().to_string()),
-                    });
-                }
-            }
+    get_folder_artwork_with_patterns(filepath, DEFAULT_ARTWORK_PATTERNS)
+}
+
+/// Find folder-based artwork using an ordered list of filename patterns
+///
+/// Patterns support `*` and `?` wildcards and are matched case-insensitively
+/// against image files in the audio file's directory. The first pattern with
+/// a match wins.
+pub fn get_folder_artwork_with_patterns<S: AsRef<str>>(
+    filepath: &str,
+    patterns: &[S],
+) -> Option<Artwork> {
+    let folder = Path::new(filepath).parent()?;
+
+    // (lowercase name, original name, extension rank) for each image file
+    let images: Vec<(String, String, usize)> = fs::read_dir(folder)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let lower = name.to_lowercase();
+            let ext = Path::new(&lower).extension()?.to_str()?.to_string();
+            let rank = ARTWORK_IMAGE_EXTENSIONS.iter().position(|x| *x == ext)?;
+            Some((lower, name, rank))
+        })
+        .collect();
+
+    for pattern in patterns {
+        let pattern = pattern.as_ref().to_lowercase();
+        let best = images
+            .iter()
+            .filter(|(lower, _, _)| pattern_matches(&pattern, lower))
+            .min_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+
+        if let Some((lower, name, _)) = best
+            && let Ok(data) = fs::read(folder.join(name))
+        {
+            return Some(Artwork {
+                data: BASE64.encode(&data),
+                mime_type: image_mime_type(lower).to_string(),
+                source: "folder".to_string(),
+                filename: Some(name.clone()),
+            });
         }
     }
 
     None
 }
 
+/// Load an image file chosen directly, such as an album override's cover
+///
+/// `None` if the file can't be read or isn't one of `ARTWORK_IMAGE_EXTENSIONS`.
+pub fn get_artwork_from_image(image_path: &str) -> Option<Artwork> {
+    let path = Path::new(image_path);
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let lower = name.to_lowercase();
+    let ext = Path::new(&lower).extension()?.to_str()?;
+    if !ARTWORK_IMAGE_EXTENSIONS.contains(&ext) {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+
+    Some(Artwork {
+        data: BASE64.encode(&data),
+        mime_type: image_mime_type(&lower).to_string(),
+        source: "override".to_string(),
+        filename: Some(name),
+    })
+}
+
+/// MIME type of an image file by its lowercase name
+fn image_mime_type(lower_name: &str) -> &'static str {
+    let ext = Path::new(lower_name).extension().and_then(|e| e.to_str()).unwrap_or("");
+    match ext {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+}
+
+/// Match a lowercase filename against a lowercase pattern with `*` and `?` wildcards
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Parse the stored folder artwork patterns setting
+///
+/// Falls back to `DEFAULT_ARTWORK_PATTERNS` when unset, invalid, or empty.
+pub fn parse_artwork_patterns(value: Option<&str>) -> Vec<String> {
+    let patterns: Vec<String> = value
+        .and_then(|v| serde_json::from_str::<Vec<String>>(v).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    if patterns.is_empty() {
+        DEFAULT_ARTWORK_PATTERNS.iter().map(|p| p.to_string()).collect()
+    } else {
+        patterns
+    }
+}
+
 /// Get artwork for an audio file, trying embedded first then folder-based
 pub fn get_artwork(filepath: &str) -> Option<Artwork> {
+    get_artwork_with_patterns(filepath, DEFAULT_ARTWORK_PATTERNS)
+}
+
+/// Get artwork for an audio file, falling back to folder artwork matched by `patterns`
+pub fn get_artwork_with_patterns<S: AsRef<str>>(
+    filepath: &str,
+    patterns: &[S],
+) -> Option<Artwork> {
     // Try embedded artwork first
     if let Some(artwork) = get_embedded_artwork(filepath) {
         return Some(artwork);
     }
 
     // Fall back to folder-based artwork
-    get_folder_artwork(filepath)
+    get_folder_artwork_with_patterns(filepath, patterns)
 }
 
 /// Get artwork data URL for use in HTML/CSS
@@ -240,8 +334,105 @@ mod tests {
         let artwork = get_folder_artwork(audio_path.to_str().unwrap());
         assert!(artwork.is_some());
 
-        // Should find cover.jpg first (earlier in ARTWORK_FILENAMES)
+        // Should find cover.jpg first (earlier in DEFAULT_ARTWORK_PATTERNS)
         let art = artwork.unwrap();
         assert_eq!(art.filename.unwrap(), "cover.jpg");
     }
+
+    fn write_images(dir: &Path, names: &[&str]) {
+        for name in names {
+            let mut file = File::create(dir.join(name)).unwrap();
+            file.write_all(name.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_folder_artwork_custom_pattern_priority() {
+        let dir = tempdir().unwrap();
+        write_images(dir.path(), &["cover.jpg", "Front.PNG", "albumart.jpg"]);
+
+        let audio_path = dir.path().join("song.mp3");
+        File::create(&audio_path).unwrap();
+        let audio_path = audio_path.to_str().unwrap();
+
+        let art = get_folder_artwork_with_patterns(audio_path, &["front.*", "cover.*"]).unwrap();
+        assert_eq!(art.filename.as_deref(), Some("Front.PNG"));
+        assert_eq!(art.mime_type, "image/png");
+        assert_eq!(art.source, "folder");
+
+        let art = get_folder_artwork_with_patterns(audio_path, &["albumart.*", "front.*"]).unwrap();
+        assert_eq!(art.filename.as_deref(), Some("albumart.jpg"));
+    }
+
+    #[test]
+    fn test_folder_artwork_extension_order_within_pattern() {
+        let dir = tempdir().unwrap();
+        write_images(dir.path(), &["cover.png", "cover.jpg", "cover.txt"]);
+
+        let audio_path = dir.path().join("song.mp3");
+        File::create(&audio_path).unwrap();
+
+        let art = get_folder_artwork_with_patterns(audio_path.to_str().unwrap(), &["cover.*"])
+            .unwrap();
+        assert_eq!(art.filename.as_deref(), Some("cover.jpg"));
+    }
+
+    #[test]
+    fn test_folder_artwork_ignores_non_images() {
+        let dir = tempdir().unwrap();
+        write_images(dir.path(), &["cover.txt"]);
+
+        let audio_path = dir.path().join("song.mp3");
+        File::create(&audio_path).unwrap();
+
+        assert!(get_folder_artwork_with_patterns(audio_path.to_str().unwrap(), &["cover.*"])
+            .is_none());
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("cover.*", "cover.jpg"));
+        assert!(pattern_matches("*cover*", "album cover art.png"));
+        assert!(pattern_matches("cd?.jpg", "cd1.jpg"));
+        assert!(!pattern_matches("cover.*", "backcover.jpg"));
+        assert!(!pattern_matches("cd?.jpg", "cd10.jpg"));
+    }
+
+    #[test]
+    fn test_parse_artwork_patterns() {
+        assert_eq!(
+            parse_artwork_patterns(Some(r#"["Front.*", " cover.* ", ""]"#)),
+            vec!["front.*".to_string(), "cover.*".to_string()]
+        );
+        assert_eq!(parse_artwork_patterns(None), DEFAULT_ARTWORK_PATTERNS.to_vec());
+        assert_eq!(parse_artwork_patterns(Some("not json")), DEFAULT_ARTWORK_PATTERNS.to_vec());
+        assert_eq!(parse_artwork_patterns(Some("[]")), DEFAULT_ARTWORK_PATTERNS.to_vec());
+    }
+
+    #[test]
+    fn test_embedded_artwork_large_cover_not_truncated() {
+        // Larger than the 8192-byte buffer older artwork paths were limited to
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0];
+        data.extend((0..300_000u32).map(|i| (i % 251) as u8));
+
+        let artwork = embedded_artwork_from_bytes(Some(&lofty::picture::MimeType::Jpeg), &data);
+        let decoded = BASE64.decode(&artwork.data).unwrap();
+
+        assert_eq!(decoded.len(), data.len());
+        assert_eq!(decoded, data);
+        assert_eq!(artwork.mime_type, "image/jpeg");
+        assert_eq!(artwork.source, "embedded");
+    }
+
+    #[test]
+    fn test_embedded_artwork_sniffs_unknown_mime() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        assert_eq!(embedded_artwork_from_bytes(None, &png).mime_type, "image/png");
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8 ".to_vec();
+        webp.extend([0u8; 16]);
+        assert_eq!(embedded_artwork_from_bytes(None, &webp).mime_type, "image/webp");
+
+        assert_eq!(embedded_artwork_from_bytes(None, b"????").mime_type, "image/jpeg");
+    }
 }
@@ -4,17 +4,31 @@
 //! extracting artwork from files when navigating prev/next in queue.
 
 use lru::LruCache;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
 use std::num::NonZeroUsize;
 
-use super::artwork::{get_artwork, Artwork};
+use super::artwork::{get_artwork_with_patterns, Artwork, DEFAULT_ARTWORK_PATTERNS};
 
-/// Default cache size (number of tracks)
+/// Default cache size (number of tracks, and separately of albums)
 const DEFAULT_CACHE_SIZE: usize = 100;
 
+/// Snapshot of the cache's size for debugging memory use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ArtworkCacheStats {
+    /// Tracks with a cached result, including cached "no artwork" results
+    pub len: usize,
+    pub capacity: usize,
+    /// Size of the cached image data (base64), tracks and albums together
+    pub bytes: usize,
+}
+
 /// Thread-safe LRU cache for artwork
 pub struct ArtworkCache {
     cache: Mutex<LruCache<i64, Option<Artwork>>>,
+    /// Album artwork by album key, for the album grid
+    albums: Mutex<LruCache<String, Option<Artwork>>>,
+    folder_patterns: RwLock<Vec<String>>,
 }
 
 impl ArtworkCache {
@@ -28,11 +42,25 @@ impl ArtworkCache {
         let size = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(100).unwrap());
         Self {
             cache: Mutex::new(LruCache::new(size)),
+            albums: Mutex::new(LruCache::new(size)),
+            folder_patterns: RwLock::new(
+                DEFAULT_ARTWORK_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            ),
         }
     }
 
     /// Get artwork for a track, using cache if available
     pub fn get_or_load(&self, track_id: i64, filepath: &str) -> Option<Artwork> {
+        self.get_or_load_with(track_id, || {
+            get_artwork_with_patterns(filepath, &self.folder_patterns())
+        })
+    }
+
+    /// Get artwork for a track, calling `load` on a cache miss
+    pub fn get_or_load_with<F>(&self, track_id: i64, load: F) -> Option<Artwork>
+    where
+        F: FnOnce() -> Option<Artwork>,
+    {
         // Check cache first
         {
             let mut cache = self.cache.lock();
@@ -41,8 +69,8 @@ impl ArtworkCache {
             }
         }
 
-        // Not in cache, load from file
-        let artwork = get_artwork(filepath);
+        // Not in cache, load it
+        let artwork = load();
 
         // Store in cache
         {
@@ -53,6 +81,40 @@ impl ArtworkCache {
         artwork
     }
 
+    /// Cached artwork for an album
+    ///
+    /// `None` when the album isn't cached, `Some(None)` when it is known to
+    /// have no artwork.
+    pub fn get_album(&self, album_key: &str) -> Option<Option<Artwork>> {
+        self.albums.lock().get(album_key).cloned()
+    }
+
+    /// Cache the artwork found for an album
+    pub fn put_album(&self, album_key: &str, artwork: Option<Artwork>) {
+        self.albums.lock().put(album_key.to_string(), artwork);
+    }
+
+    /// Drop an album's cached artwork so it is looked up again
+    pub fn invalidate_album(&self, album_key: &str) {
+        self.albums.lock().pop(album_key);
+    }
+
+    /// Number of albums with a cached result
+    pub fn album_len(&self) -> usize {
+        self.albums.lock().len()
+    }
+
+    /// Folder artwork patterns currently in use, in priority order
+    pub fn folder_patterns(&self) -> Vec<String> {
+        self.folder_patterns.read().clone()
+    }
+
+    /// Replace the folder artwork patterns and drop cached results
+    pub fn set_folder_patterns(&self, patterns: Vec<String>) {
+        *self.folder_patterns.write() = patterns;
+        self.clear();
+    }
+
     /// Invalidate cache entry for a specific track
     /// Called when track metadata is updated
     pub fn invalidate(&self, track_id: i64) {
@@ -60,10 +122,19 @@ impl ArtworkCache {
         cache.pop(&track_id);
     }
 
-    /// Clear all cache entries
+    /// Drop a track's cached artwork and load it again from its file
+    ///
+    /// Picks up embedded or folder artwork that changed since it was cached.
+    pub fn reload(&self, track_id: i64, filepath: &str) -> Option<Artwork> {
+        self.invalidate(track_id);
+        self.get_or_load(track_id, filepath)
+    }
+
+    /// Clear all cache entries, albums included
     pub fn clear(&self) {
         let mut cache = self.cache.lock();
         cache.clear();
+        self.albums.lock().clear();
     }
 
     /// Get current cache size
@@ -77,6 +148,26 @@ impl ArtworkCache {
         let cache = self.cache.lock();
         cache.is_empty()
     }
+
+    /// Most entries kept before the least recently used is dropped
+    pub fn capacity(&self) -> usize {
+        self.cache.lock().cap().get()
+    }
+
+    /// Current size, capacity, and image bytes held
+    pub fn stats(&self) -> ArtworkCacheStats {
+        let cache = self.cache.lock();
+        let albums = self.albums.lock();
+        let track_bytes: usize =
+            cache.iter().flat_map(|(_, artwork)| artwork).map(|a| a.data.len()).sum();
+        let album_bytes: usize =
+            albums.iter().flat_map(|(_, artwork)| artwork).map(|a| a.data.len()).sum();
+        ArtworkCacheStats {
+            len: cache.len(),
+            capacity: cache.cap().get(),
+            bytes: track_bytes + album_bytes,
+        }
+    }
 }
 
 impl Default for ArtworkCache {
@@ -152,6 +243,33 @@ mod tests {
         assert_eq!(cache.len(), 0);
     }
 
+    #[test]
+    fn test_cache_reload_rereads_changed_artwork() {
+        let cache = ArtworkCache::new();
+        let dir = tempdir().unwrap();
+
+        let cover_path = dir.path().join("cover.jpg");
+        std::fs::write(&cover_path, b"old cover").unwrap();
+        let audio_path = dir.path().join("song.mp3");
+        File::create(&audio_path).unwrap();
+        let audio_path = audio_path.to_str().unwrap();
+
+        let original = cache.get_or_load(1, audio_path).unwrap();
+
+        // The cached copy hides the changed file
+        std::fs::write(&cover_path, b"new cover").unwrap();
+        assert_eq!(cache.get_or_load(1, audio_path).unwrap().data, original.data);
+
+        let refreshed = cache.reload(1, audio_path).unwrap();
+        assert_ne!(refreshed.data, original.data);
+        assert_eq!(cache.get_or_load(1, audio_path).unwrap().data, refreshed.data);
+
+        // Removed artwork is cached as missing
+        std::fs::remove_file(&cover_path).unwrap();
+        assert!(cache.reload(1, audio_path).is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
     #[test]
     fn test_cache_clear() {
         let cache = ArtworkCache::new();
@@ -212,4 +330,62 @@ mod tests {
         // Should still cache the "None" result
         assert_eq!(cache.len(), 1);
     }
+
+    #[test]
+    fn test_cache_set_folder_patterns_reloads() {
+        let cache = ArtworkCache::new();
+        let dir = tempdir().unwrap();
+
+        for name in ["cover.jpg", "front.jpg"] {
+            let mut file = File::create(dir.path().join(name)).unwrap();
+            file.write_all(name.as_bytes()).unwrap();
+        }
+
+        let audio_path = dir.path().join("song.mp3");
+        File::create(&audio_path).unwrap();
+        let audio_path = audio_path.to_str().unwrap();
+
+        let artwork = cache.get_or_load(1, audio_path).unwrap();
+        assert_eq!(artwork.filename.as_deref(), Some("cover.jpg"));
+
+        cache.set_folder_patterns(vec!["front.*".to_string()]);
+        assert!(cache.is_empty());
+        assert_eq!(cache.folder_patterns(), vec!["front.*".to_string()]);
+
+        let artwork = cache.get_or_load(1, audio_path).unwrap();
+        assert_eq!(artwork.filename.as_deref(), Some("front.jpg"));
+    }
+
+    fn stub_artwork(data: &str) -> Option<Artwork> {
+        Some(Artwork {
+            data: data.to_string(),
+            mime_type: "image/jpeg".to_string(),
+            source: "embedded".to_string(),
+            filename: None,
+        })
+    }
+
+    #[test]
+    fn test_stats_and_clear_with_stub_loader() {
+        let cache = ArtworkCache::with_capacity(10);
+        cache.get_or_load_with(1, || stub_artwork("abcd"));
+        cache.get_or_load_with(2, || stub_artwork("ef"));
+        cache.get_or_load_with(3, || None);
+        // A hit doesn't call the loader again
+        cache.get_or_load_with(1, || panic!("cached artwork was reloaded"));
+
+        assert_eq!(
+            cache.stats(),
+            ArtworkCacheStats {
+                len: 3,
+                capacity: 10,
+                bytes: 6,
+            }
+        );
+
+        cache.clear();
+        assert_eq!(cache.stats().len, 0);
+        assert_eq!(cache.stats().bytes, 0);
+        assert_eq!(cache.capacity(), 10);
+    }
 }
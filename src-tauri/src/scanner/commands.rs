@@ -3,18 +3,25 @@
 //! These commands expose the scanner functionality to the frontend
 //! with progress events emitted during scanning.
 
+use parking_lot::Mutex;
+use rusqlite::Connection;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 use tauri::{AppHandle, Emitter, State};
+use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use crate::db::maintenance::{self, CheckpointMode};
 use crate::db::{library, Database};
 use crate::events::{EventEmitter, LibraryUpdatedEvent, ScanCompleteEvent, ScanProgressEvent};
-use crate::scanner::artwork::{get_artwork, Artwork};
+use crate::scanner::artwork::{get_artwork_with_patterns, Artwork};
+use crate::scanner::artwork_cache::ArtworkCache;
 use crate::scanner::fingerprint::{compute_content_hash, FileFingerprint};
 use crate::scanner::metadata::extract_metadata;
 use crate::scanner::scan::{scan_2phase, ProgressCallback, ScanResult2Phase};
+use crate::scanner::throughput::ThroughputTracker;
 use crate::scanner::{ExtractedMetadata, ScanProgress, ScanStats};
 
 /// Internal scan progress event for metadata-only scans
@@ -26,6 +33,9 @@ struct MetadataScanProgress {
     message: Option<String>,
 }
 
+/// Scans changing at least this many files are followed by a WAL checkpoint
+const LARGE_SCAN_CHECKPOINT_FILES: usize = 1000;
+
 /// Job ID counter for generating unique scan job IDs
 static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -59,6 +69,16 @@ impl From<&ScanResult2Phase> for ScanResultResponse {
     }
 }
 
+/// Result of a blocking single-path scan, with the affected file paths
+#[derive(Clone, serde::Serialize)]
+pub struct PathScanResponse {
+    pub stats: ScanStats,
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    pub duration_ms: u64,
+}
+
 /// Get fingerprints from the database for comparison
 fn get_db_fingerprints(db: &Database) -> Result<HashMap<String, FileFingerprint>, String> {
     let conn = db.conn().map_err(|e| e.to_string())?;
@@ -82,6 +102,127 @@ fn get_db_fingerprints(db: &Database) -> Result<HashMap<String, FileFingerprint>
     Ok(fingerprints)
 }
 
+/// Counts from writing a scan result to the library
+pub(crate) struct AppliedScan {
+    pub(crate) added: usize,
+    pub(crate) reconciled: usize,
+    pub(crate) recovered: usize,
+}
+
+/// Find the missing library track a newly found file was moved from, and
+/// point it at the file's new path
+///
+/// Matches by inode first, then by content hash, so the moved track keeps its
+/// ID, play count, and favorites. Returns the reconciled track's ID.
+fn reconcile_moved_file(conn: &Connection, m: &ExtractedMetadata) -> Option<i64> {
+    let by_inode = m
+        .file_inode
+        .and_then(|inode| library::find_missing_track_by_inode(conn, inode).ok().flatten());
+    let (track, method) = match by_inode {
+        Some(track) => (track, "inode"),
+        None => {
+            let hash = compute_content_hash(Path::new(&m.filepath)).ok()?;
+            let track = library::find_missing_track_by_content_hash(conn, &hash).ok()??;
+            (track, "content hash")
+        }
+    };
+
+    library::reconcile_moved_track(conn, track.id, &m.filepath, m.file_inode).ok()?;
+    debug!(
+        "Reconciled moved track {} by {}: {} -> {}",
+        track.id, method, track.filepath, m.filepath
+    );
+    Some(track.id)
+}
+
+/// Write a scan result to the library
+///
+/// Shared by manual scans and the folder watcher. Marks deleted files
+/// missing, reconciles moved files, adds new files, updates modified ones,
+/// and clears the missing flag on files that came back.
+pub(crate) fn apply_scan_result(
+    conn: &Connection,
+    scan_result: &ScanResult2Phase,
+) -> Result<AppliedScan, String> {
+    // IMPORTANT: Mark deleted tracks as missing FIRST
+    // This is required because reconciliation of "added" tracks looks for tracks
+    // where missing=1. If a file is moved (delete + add in same scan), we need to
+    // mark the old path as missing before we can reconcile it with the new path.
+    for filepath in &scan_result.deleted {
+        if let Err(e) = library::mark_track_missing_by_filepath(conn, filepath) {
+            warn!("Failed to mark track missing: {}", e);
+        }
+    }
+
+    // Process "added" tracks - check for moves first, then add truly new tracks
+    // Now that deleted tracks are marked missing, reconciliation by inode/hash will work
+    let mut reconciled_count = 0;
+    let mut truly_new: Vec<(String, crate::db::TrackMetadata)> = Vec::new();
+    for m in &scan_result.added {
+        if reconcile_moved_file(conn, m).is_some() {
+            reconciled_count += 1;
+        } else {
+            truly_new.push((m.filepath.clone(), to_db_metadata(m)));
+        }
+    }
+
+    // Add truly new tracks to database
+    let added_count = truly_new.len();
+    if !truly_new.is_empty() {
+        library::add_tracks_bulk(conn, &truly_new).map_err(|e| e.to_string())?;
+    }
+
+    // Update modified tracks
+    if !scan_result.modified.is_empty() {
+        let updates: Vec<(String, crate::db::TrackMetadata)> = scan_result
+            .modified
+            .iter()
+            .map(|m| (m.filepath.clone(), to_db_metadata(m)))
+            .collect();
+
+        library::update_tracks_bulk(conn, &updates).map_err(|e| e.to_string())?;
+    }
+
+    // Clear missing flag for unchanged files that were previously missing but have reappeared
+    // This handles the case where a file is moved out and then moved back to the same location
+    let mut recovered_count = 0;
+    if !scan_result.unchanged.is_empty() {
+        match library::mark_tracks_present_by_filepaths(conn, &scan_result.unchanged) {
+            Ok(count) => {
+                recovered_count = count;
+                if count > 0 {
+                    info!("Recovered {} previously missing track(s) that reappeared", count);
+                }
+            }
+            Err(e) => error!("Failed to mark recovered tracks present: {}", e),
+        }
+    }
+
+    Ok(AppliedScan {
+        added: added_count,
+        reconciled: reconciled_count,
+        recovered: recovered_count,
+    })
+}
+
+/// Empty the WAL after a scan that changed `changed` files, if that's a lot
+///
+/// SQLite's automatic checkpoints never shrink the WAL file, so after a big
+/// import it would otherwise stay as large as the import. Blocks while
+/// waiting for other writers, so call it off the async runtime.
+pub(crate) fn checkpoint_after_scan(db: &Database, changed: usize) {
+    if changed < LARGE_SCAN_CHECKPOINT_FILES {
+        return;
+    }
+    match db.with_maintenance_conn(|conn| maintenance::checkpoint(conn, CheckpointMode::Truncate)) {
+        Ok(result) if result.busy => {
+            warn!("WAL checkpoint after {} changes was blocked", changed);
+        }
+        Ok(_) => {}
+        Err(e) => error!("WAL checkpoint after {} changes failed: {}", changed, e),
+    }
+}
+
 /// Scan paths and add/update tracks in the database
 #[tauri::command]
 pub async fn scan_paths_to_library(
@@ -99,7 +240,11 @@ pub async fn scan_paths_to_library(
     // Create progress callback that emits standardized Tauri events
     let app_handle = app.clone();
     let job_id_clone = job_id.clone();
+    let throughput = Mutex::new(ThroughputTracker::default());
     let progress_callback: ProgressCallback = Box::new(move |progress: ScanProgress| {
+        let Some(estimate) = throughput.lock().record(&progress, Instant::now()) else {
+            return;
+        };
         let _ = app_handle.emit_scan_progress(ScanProgressEvent {
             job_id: job_id_clone.clone(),
             status: progress.phase.clone(),
@@ -107,6 +252,8 @@ pub async fn scan_paths_to_library(
             found: 0, // Will be updated in final event
             errors: 0,
             current_path: progress.message.clone(),
+            rate: estimate.rate,
+            eta_secs: estimate.eta_secs,
         });
     });
 
@@ -116,80 +263,19 @@ pub async fn scan_paths_to_library(
 
     // Get database connection for updates
     let conn = db.conn().map_err(|e| e.to_string())?;
-
-    let mut added_count = 0;
-    let mut reconciled_count = 0;
+    let AppliedScan {
+        added: added_count,
+        reconciled: reconciled_count,
+        recovered: recovered_count,
+    } = apply_scan_result(&conn, &scan_result)?;
+    drop(conn);
     let modified_count = scan_result.modified.len();
-
-    // IMPORTANT: Mark deleted tracks as missing FIRST
-    // This is required because reconciliation of "added" tracks looks for tracks
-    // where missing=1. If a file is moved (delete + add in same scan), we need to
-    // mark the old path as missing before we can reconcile it with the new path.
-    for filepath in &scan_result.deleted {
-        let _ = library::mark_track_missing_by_filepath(&conn, filepath);
-    }
-
-    // Process "added" tracks - check for moves first, then add truly new tracks
-    // Now that deleted tracks are marked missing, reconciliation by inode/hash will work
-    if !scan_result.added.is_empty() {
-        let mut truly_new: Vec<(String, crate::db::TrackMetadata)> = Vec::new();
-
-        for m in &scan_result.added {
-            let mut was_reconciled = false;
-
-            if let Some(inode) = m.file_inode {
-                let track_result = library::find_missing_track_by_inode(&conn, inode);
-                if let Ok(Some(track)) = track_result {
-                    let reconcile_result = library::reconcile_moved_track(&conn, track.id, &m.filepath, Some(inode));
-                    if reconcile_result.is_ok() {
-                        reconciled_count += 1;
-                        was_reconciled = true;
-                    }
-                }
-            }
-
-            if !was_reconciled
-                && let Ok(hash) = compute_content_hash(std::path::Path::new(&m.filepath)) {
-                    let track_result = library::find_missing_track_by_content_hash(&conn, &hash);
-                    if let Ok(Some(track)) = track_result {
-                        let reconcile_result = library::reconcile_moved_track(&conn, track.id, &m.filepath, m.file_inode);
-                        if reconcile_result.is_ok() {
-                            reconciled_count += 1;
-                            was_reconciled = true;
-                        }
-                    }
-                }
-
-            if !was_reconciled {
-                truly_new.push((m.filepath.clone(), to_db_metadata(m)));
-            }
-        }
-
-        // Add truly new tracks to database
-        if !truly_new.is_empty() {
-            added_count = truly_new.len();
-            library::add_tracks_bulk(&conn, &truly_new).map_err(|e| e.to_string())?;
-        }
-    }
-
-    // Update modified tracks
-    if !scan_result.modified.is_empty() {
-        let updates: Vec<(String, crate::db::TrackMetadata)> = scan_result
-            .modified
-            .iter()
-            .map(|m| (m.filepath.clone(), to_db_metadata(m)))
-            .collect();
-
-        library::update_tracks_bulk(&conn, &updates).map_err(|e| e.to_string())?;
-    }
-
-    // Clear missing flag for unchanged files that were previously missing but have reappeared
-    // This handles the case where a file is moved out and then moved back to the same location
-    let mut recovered_count = 0;
-    if !scan_result.unchanged.is_empty()
-        && let Ok(count) = library::mark_tracks_present_by_filepaths(&conn, &scan_result.unchanged) {
-            recovered_count = count;
-        }
+    let changed = scan_result.change_count();
+    let checkpoint_db = db.inner().clone();
+    let _ = tauri::async_runtime::spawn_blocking(move || {
+        checkpoint_after_scan(&checkpoint_db, changed)
+    })
+    .await;
 
     let duration_ms = start_time.elapsed().as_millis() as u64;
 
@@ -200,6 +286,8 @@ pub async fn scan_paths_to_library(
         skipped: scan_result.unchanged.len() as u32,
         errors: scan_result.stats.errors as u32,
         duration_ms,
+        visited: scan_result.stats.visited as u32,
+        up_to_date: !scan_result.has_changes() && recovered_count == 0,
     });
 
     // Emit library updated events (empty track_ids signals a bulk change - frontend should refresh)
@@ -213,6 +301,103 @@ pub async fn scan_paths_to_library(
     Ok(ScanResultResponse::from(&scan_result))
 }
 
+/// DB fingerprints for the files a scan of `root` covers
+///
+/// Library files outside the scanned path are left out, so they aren't
+/// reported as deleted.
+fn fingerprints_in_scope(
+    fingerprints: HashMap<String, FileFingerprint>,
+    root: &Path,
+    recursive: bool,
+) -> HashMap<String, FileFingerprint> {
+    let root_is_file = root.is_file();
+    fingerprints
+        .into_iter()
+        .filter(|(filepath, _)| {
+            let path = Path::new(filepath);
+            if root_is_file {
+                path == root
+            } else if recursive {
+                path.starts_with(root)
+            } else {
+                path.parent() == Some(root)
+            }
+        })
+        .collect()
+}
+
+/// Scan one path into the library and report what changed
+pub(crate) fn scan_path_into_library(
+    db: &Database,
+    path: &str,
+    recursive: bool,
+) -> Result<(PathScanResponse, AppliedScan), String> {
+    let start_time = Instant::now();
+    let root = Path::new(path);
+    if !root.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path));
+    }
+    if !root.exists() {
+        return Err(format!("Path not found: {}", path));
+    }
+
+    let db_fingerprints = fingerprints_in_scope(get_db_fingerprints(db)?, root, recursive);
+    let scan_result = scan_2phase(&[path.to_string()], &db_fingerprints, recursive, None)
+        .map_err(|e| e.to_string())?;
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let applied = apply_scan_result(&conn, &scan_result)?;
+    drop(conn);
+    checkpoint_after_scan(db, scan_result.change_count());
+
+    let sorted = |mut paths: Vec<String>| {
+        paths.sort();
+        paths
+    };
+    let response = PathScanResponse {
+        stats: scan_result.stats.clone(),
+        added: sorted(scan_result.added.iter().map(|m| m.filepath.clone()).collect()),
+        modified: sorted(scan_result.modified.iter().map(|m| m.filepath.clone()).collect()),
+        deleted: sorted(scan_result.deleted.clone()),
+        duration_ms: start_time.elapsed().as_millis() as u64,
+    };
+    Ok((response, applied))
+}
+
+/// Scan a single path into the library and resolve with what changed
+///
+/// A blocking counterpart to `scan_paths_to_library` for scripts and
+/// automation: no progress events, just the stats and the added, modified,
+/// and deleted file paths. Only library files under `path` are compared, so
+/// the rest of the library is never marked missing.
+#[tauri::command]
+pub async fn scan_path_sync(
+    app: AppHandle,
+    db: State<'_, Database>,
+    path: String,
+    recursive: Option<bool>,
+) -> Result<PathScanResponse, String> {
+    let db = db.inner().clone();
+    let recursive = recursive.unwrap_or(true);
+    let (response, applied) = tauri::async_runtime::spawn_blocking(move || {
+        scan_path_into_library(&db, &path, recursive)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if applied.added > 0 || applied.reconciled > 0 || applied.recovered > 0 {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::added(vec![]));
+    }
+    if !response.modified.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::modified(vec![]));
+    }
+    if !response.deleted.is_empty() {
+        let _ = app.emit_library_updated(LibraryUpdatedEvent::deleted(vec![]));
+    }
+
+    Ok(response)
+}
+
 /// Scan a single path (file or directory) without database integration
 #[tauri::command]
 pub async fn scan_paths_metadata(
@@ -251,14 +436,15 @@ pub fn extract_file_metadata(filepath: String) -> Result<ExtractedMetadata, Stri
 
 /// Get artwork for a track
 #[tauri::command]
-pub fn get_track_artwork(filepath: String) -> Option<Artwork> {
-    get_artwork(&filepath)
+pub fn get_track_artwork(cache: State<'_, ArtworkCache>, filepath: String) -> Option<Artwork> {
+    get_artwork_with_patterns(&filepath, &cache.folder_patterns())
 }
 
 /// Get artwork as a data URL for use in img src
 #[tauri::command]
-pub fn get_track_artwork_url(filepath: String) -> Option<String> {
-    crate::scanner::artwork::get_artwork_data_url(&filepath)
+pub fn get_track_artwork_url(cache: State<'_, ArtworkCache>, filepath: String) -> Option<String> {
+    let artwork = get_artwork_with_patterns(&filepath, &cache.folder_patterns())?;
+    Some(format!("data:{};base64,{}", artwork.mime_type, artwork.data))
 }
 
 /// Convert ExtractedMetadata to database TrackMetadata
@@ -271,11 +457,108 @@ fn to_db_metadata(m: &ExtractedMetadata) -> crate::db::TrackMetadata {
         album_artist: m.album_artist.clone(),
         track_number: m.track_number.clone(),
         track_total: m.track_total.clone(),
+        disc_number: m.disc_number,
+        disc_total: m.disc_total,
         date: m.date.clone(),
+        genre: m.genre.clone(),
         duration: m.duration,
         file_size: Some(m.file_size),
         file_mtime_ns: m.file_mtime_ns,
         file_inode: m.file_inode,
         content_hash,
+        added_date: None,
+        chapters: m.chapters.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+
+    fn library_track(db: &Database, filepath: &str) -> Option<crate::db::Track> {
+        db.with_conn(|conn| library::get_track_by_filepath(conn, filepath)).unwrap()
+    }
+
+    #[test]
+    fn test_scan_path_into_library_reports_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        let files = [
+            dir.path().join("a.mp3"),
+            dir.path().join("b.flac"),
+            sub.join("c.m4a"),
+        ];
+        for file in &files {
+            std::fs::write(file, b"not really audio").unwrap();
+        }
+        std::fs::write(dir.path().join("cover.jpg"), b"image").unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let name = |p: &Path| p.to_string_lossy().to_string();
+
+        let db = Database::new_in_memory().unwrap();
+        // A library track elsewhere must not be reported as deleted
+        db.with_conn(|conn| {
+            library::add_track(conn, "/elsewhere/x.mp3", &TrackMetadata::default())
+        })
+        .unwrap();
+
+        let (first, applied) = scan_path_into_library(&db, &root, true).unwrap();
+        let mut expected: Vec<String> = files.iter().map(|f| name(f.as_path())).collect();
+        expected.sort();
+        assert_eq!(first.added, expected);
+        assert!(first.modified.is_empty());
+        assert!(first.deleted.is_empty());
+        assert_eq!((first.stats.visited, first.stats.added), (3, 3));
+        assert_eq!(applied.added, 3);
+        assert!(library_track(&db, &name(&files[2])).is_some());
+
+        // Non-recursive scans only see the top level
+        let (shallow, _) = scan_path_into_library(&db, &root, false).unwrap();
+        assert_eq!(shallow.stats.visited, 2);
+        assert_eq!(shallow.stats.unchanged, 2);
+        assert!(shallow.deleted.is_empty());
+
+        std::fs::remove_file(&files[1]).unwrap();
+        std::fs::write(&files[0], b"re-tagged and now longer").unwrap();
+        let (second, _) = scan_path_into_library(&db, &root, true).unwrap();
+        assert!(second.added.is_empty());
+        assert_eq!(second.modified, vec![name(&files[0])]);
+        assert_eq!(second.deleted, vec![name(&files[1])]);
+        assert_eq!(second.stats.unchanged, 1);
+        assert!(library_track(&db, "/elsewhere/x.mp3").is_some_and(|t| !t.missing));
+
+        assert!(scan_path_into_library(&db, "relative/dir", true).is_err());
+        let gone = dir.path().join("gone");
+        assert!(scan_path_into_library(&db, &name(&gone), true).is_err());
+    }
+
+    #[test]
+    fn test_manual_scan_detects_moved_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let (old_dir, new_dir) = (dir.path().join("old"), dir.path().join("new"));
+        std::fs::create_dir(&old_dir).unwrap();
+        std::fs::create_dir(&new_dir).unwrap();
+        let (old_path, new_path) = (old_dir.join("a.mp3"), new_dir.join("a.mp3"));
+        std::fs::write(&old_path, b"not really audio").unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+        let name = |p: &Path| p.to_string_lossy().to_string();
+
+        let db = Database::new_in_memory().unwrap();
+        scan_path_into_library(&db, &root, true).unwrap();
+        let track = library_track(&db, &name(&old_path)).unwrap();
+        db.with_conn(|conn| library::set_play_count(conn, track.id, 7, false))
+            .unwrap();
+
+        std::fs::rename(&old_path, &new_path).unwrap();
+        let (_, applied) = scan_path_into_library(&db, &root, true).unwrap();
+
+        assert_eq!((applied.added, applied.reconciled), (0, 1));
+        assert!(library_track(&db, &name(&old_path)).is_none());
+        let moved = library_track(&db, &name(&new_path)).unwrap();
+        assert_eq!(moved.id, track.id);
+        assert_eq!(moved.play_count, 7);
+        assert!(!moved.missing);
     }
 }
@@ -0,0 +1,159 @@
+//! Resolving a folder to library tracks for "play folder".
+//!
+//! The folder is scanned into the library first, so files that aren't in it
+//! yet are added and a folder that's only partly in the library plays in
+//! full. The audio files found by the inventory walk are then looked up and
+//! put in album order: disc number, then track number, then filename.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::db::{Database, Track, library};
+use crate::scanner::commands::scan_path_into_library;
+use crate::scanner::inventory::inventory_path;
+use crate::scanner::metadata::extract_metadata;
+
+/// A folder's tracks in play order
+#[derive(Debug, Default)]
+pub struct FolderTracks {
+    pub tracks: Vec<Track>,
+    /// Audio files in the folder that aren't in the library after the scan
+    pub skipped: Vec<String>,
+    /// Whether the scan added, moved, or restored any library tracks
+    pub library_changed: bool,
+}
+
+/// Leading number of a track number tag such as "3" or "03/12"
+pub fn parse_track_number(value: &str) -> Option<u32> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Album order: disc, then folder, then numbered tracks before unnumbered
+/// ones, then filename
+///
+/// Discs without a tag count as the first. Folders come before track numbers
+/// so "CD1" and "CD2" folders with untagged discs don't interleave.
+fn play_order(a: &(Option<u32>, Track), b: &(Option<u32>, Track)) -> Ordering {
+    let key = |(disc, track): &(Option<u32>, Track)| {
+        let path = Path::new(&track.filepath);
+        let number = track.track_number.as_deref().and_then(parse_track_number);
+        (
+            disc.unwrap_or(1),
+            path.parent().map(Path::to_path_buf),
+            number.is_none(),
+            number,
+            path.file_name().map(|n| n.to_string_lossy().to_lowercase()),
+        )
+    };
+    key(a).cmp(&key(b))
+}
+
+/// Scan a folder into the library and get its tracks in album order
+///
+/// Disc numbers aren't stored in the library, so they're read from each
+/// file's tags.
+pub fn resolve_folder(db: &Database, path: &str, recursive: bool) -> Result<FolderTracks, String> {
+    let (_, applied) = scan_path_into_library(db, path, recursive)?;
+
+    let inventory = inventory_path(path, &HashMap::new(), recursive, None::<fn(usize)>)
+        .map_err(|e| e.to_string())?;
+
+    let conn = db.conn().map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for (filepath, _) in inventory.added {
+        match library::get_track_by_filepath(&conn, &filepath).map_err(|e| e.to_string())? {
+            Some(track) => {
+                let disc = extract_metadata(&filepath).ok().and_then(|m| m.disc_number);
+                entries.push((disc, track));
+            }
+            None => skipped.push(filepath),
+        }
+    }
+    entries.sort_by(play_order);
+    skipped.sort();
+
+    Ok(FolderTracks {
+        tracks: entries.into_iter().map(|(_, track)| track).collect(),
+        skipped,
+        library_changed: applied.added > 0 || applied.reconciled > 0 || applied.recovered > 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TrackMetadata;
+    use crate::scanner::fingerprint::FileFingerprint;
+
+    /// Add a file to the library as the scanner would, with a track number
+    fn add_numbered(db: &Database, filepath: &Path, track_number: &str) {
+        let fingerprint = FileFingerprint::from_path(filepath).unwrap();
+        let metadata = TrackMetadata {
+            track_number: Some(track_number.to_string()),
+            file_size: Some(fingerprint.size),
+            file_mtime_ns: fingerprint.mtime_ns,
+            ..Default::default()
+        };
+        let filepath = filepath.to_string_lossy();
+        db.with_conn(|conn| library::add_track(conn, &filepath, &metadata))
+            .unwrap();
+    }
+
+    fn file_names(folder: &FolderTracks) -> Vec<String> {
+        folder
+            .tracks
+            .iter()
+            .map(|t| {
+                Path::new(&t.filepath)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_track_number() {
+        assert_eq!(parse_track_number("3"), Some(3));
+        assert_eq!(parse_track_number(" 03/12"), Some(3));
+        assert_eq!(parse_track_number("A1"), None);
+        assert_eq!(parse_track_number(""), None);
+    }
+
+    #[test]
+    fn test_folder_queues_in_track_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["c.mp3", "a.mp3", "b.mp3", "intro.mp3", "cover.jpg"] {
+            std::fs::write(dir.path().join(name), b"not really audio").unwrap();
+        }
+        let db = Database::new_in_memory().unwrap();
+        add_numbered(&db, &dir.path().join("c.mp3"), "1");
+        add_numbered(&db, &dir.path().join("a.mp3"), "2/10");
+        add_numbered(&db, &dir.path().join("b.mp3"), "10");
+        let root = dir.path().to_string_lossy().to_string();
+
+        // intro.mp3 isn't in the library yet; it's added and, having no
+        // track number, goes after the numbered tracks
+        let folder = resolve_folder(&db, &root, true).unwrap();
+        assert_eq!(
+            file_names(&folder),
+            ["c.mp3", "a.mp3", "b.mp3", "intro.mp3"]
+        );
+        assert!(folder.skipped.is_empty());
+        assert!(folder.library_changed);
+
+        let again = resolve_folder(&db, &root, true).unwrap();
+        assert_eq!(file_names(&again), file_names(&folder));
+        assert!(!again.library_changed);
+
+        assert!(resolve_folder(&db, "relative/dir", true).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Audio properties from the headers of formats lofty can't read.
+//!
+//! DSD (`.dsf`, `.dff`) and True Audio (`.tta`) files are scanned like any
+//! other format, but lofty has no parser for them. Their headers are simple
+//! enough to give the duration, sample rate, and channel count directly, so
+//! the tracks show up with a proper length. Tags in these files aren't read.
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Most DSDIFF chunks walked before giving up on finding the audio
+const MAX_DFF_CHUNKS: usize = 64;
+
+/// Properties read from a file header
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeaderProps {
+    /// Duration in seconds
+    pub duration: f64,
+    pub sample_rate: u32,
+    pub channels: u8,
+}
+
+/// Read the header of a DSF, DFF, or TTA file, chosen by extension
+///
+/// Returns `None` for other formats and for headers that don't parse.
+pub fn read_header_props(path: &Path) -> Option<HeaderProps> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    match ext.as_str() {
+        "dsf" => read_dsf(&mut reader),
+        "dff" => read_dff(&mut reader),
+        "tta" => read_tta(&mut reader),
+        _ => None,
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Option<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn props(duration: f64, sample_rate: u32, channels: u32) -> Option<HeaderProps> {
+    if sample_rate == 0 || channels == 0 || !duration.is_finite() {
+        return None;
+    }
+    Some(HeaderProps {
+        duration,
+        sample_rate,
+        channels: u8::try_from(channels).ok()?,
+    })
+}
+
+/// DSF: a little-endian 28-byte `DSD ` chunk, then the `fmt ` chunk
+fn read_dsf(reader: &mut impl Read) -> Option<HeaderProps> {
+    let header: [u8; 72] = read_array(reader)?;
+    if &header[0..4] != b"DSD " || &header[28..32] != b"fmt " {
+        return None;
+    }
+    let channels = u32::from_le_bytes(header[52..56].try_into().ok()?);
+    let sample_rate = u32::from_le_bytes(header[56..60].try_into().ok()?);
+    // Samples per channel, one bit each
+    let samples = u64::from_le_bytes(header[64..72].try_into().ok()?);
+    props(
+        samples as f64 / f64::from(sample_rate),
+        sample_rate,
+        channels,
+    )
+}
+
+/// DSDIFF chunk header: a 4-byte ID and a big-endian 64-bit size
+fn read_chunk_header(reader: &mut impl Read) -> Option<([u8; 4], u64)> {
+    let id = read_array(reader)?;
+    let size = u64::from_be_bytes(read_array(reader)?);
+    Some((id, size))
+}
+
+/// DSDIFF: a big-endian `FRM8` form holding `PROP` (sample rate, channels)
+/// and then the `DSD ` (raw) or `DST ` (compressed) audio chunk
+fn read_dff<R: Read + Seek>(reader: &mut R) -> Option<HeaderProps> {
+    let (id, _) = read_chunk_header(reader)?;
+    if &id != b"FRM8" || read_array(reader)? != *b"DSD " {
+        return None;
+    }
+
+    let mut sample_rate = 0u32;
+    let mut channels = 0u16;
+    for _ in 0..MAX_DFF_CHUNKS {
+        let (id, size) = read_chunk_header(reader)?;
+        // Chunks are padded to an even length
+        let padded = size.checked_add(size & 1)?;
+        match &id {
+            b"PROP" => {
+                let end = reader.stream_position().ok()?.checked_add(padded)?;
+                if read_array(reader)? != *b"SND " {
+                    return None;
+                }
+                while reader.stream_position().ok()?.checked_add(12)? <= end {
+                    let (sub_id, sub_size) = read_chunk_header(reader)?;
+                    let next = reader
+                        .stream_position()
+                        .ok()?
+                        .checked_add(sub_size.checked_add(sub_size & 1)?)?;
+                    match &sub_id {
+                        b"FS  " => sample_rate = u32::from_be_bytes(read_array(reader)?),
+                        b"CHNL" => channels = u16::from_be_bytes(read_array(reader)?),
+                        _ => {}
+                    }
+                    reader.seek(SeekFrom::Start(next)).ok()?;
+                }
+                reader.seek(SeekFrom::Start(end)).ok()?;
+            }
+            b"DSD " => {
+                let bits = size as f64 * 8.0;
+                let duration = bits / f64::from(channels) / f64::from(sample_rate);
+                return props(duration, sample_rate, channels.into());
+            }
+            b"DST " => {
+                // Starts with the frame count and frames per second
+                let (sub_id, _) = read_chunk_header(reader)?;
+                if &sub_id != b"FRTE" {
+                    return None;
+                }
+                let frames = u32::from_be_bytes(read_array(reader)?);
+                let frame_rate = u16::from_be_bytes(read_array(reader)?);
+                let duration = f64::from(frames) / f64::from(frame_rate);
+                return props(duration, sample_rate, channels.into());
+            }
+            _ => {
+                reader
+                    .seek(SeekFrom::Current(i64::try_from(padded).ok()?))
+                    .ok()?;
+            }
+        }
+    }
+    None
+}
+
+/// TTA: an optional ID3v2 tag, then the little-endian `TTA1` header
+fn read_tta<R: Read + Seek>(reader: &mut R) -> Option<HeaderProps> {
+    let start: [u8; 10] = read_array(reader)?;
+    let offset = if &start[0..3] == b"ID3" {
+        // Syncsafe size, plus a 10-byte footer when flagged
+        let size = start[6..10]
+            .iter()
+            .fold(0u64, |acc, b| (acc << 7) | u64::from(b & 0x7f));
+        let footer = if start[5] & 0x10 != 0 { 10 } else { 0 };
+        10 + size + footer
+    } else {
+        0
+    };
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+
+    let header: [u8; 18] = read_array(reader)?;
+    if &header[0..4] != b"TTA1" {
+        return None;
+    }
+    let channels = u16::from_le_bytes([header[6], header[7]]);
+    let sample_rate = u32::from_le_bytes(header[10..14].try_into().ok()?);
+    let samples = u32::from_le_bytes(header[14..18].try_into().ok()?);
+    props(
+        f64::from(samples) / f64::from(sample_rate),
+        sample_rate,
+        channels.into(),
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// A DSF header for `seconds` of DSD64 audio
+    pub(crate) fn dsf_header(channels: u32, seconds: u64) -> Vec<u8> {
+        let sample_rate: u32 = 2_822_400;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"DSD ");
+        bytes.extend_from_slice(&28u64.to_le_bytes());
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // file size
+        bytes.extend_from_slice(&0u64.to_le_bytes()); // metadata offset
+        bytes.extend_from_slice(b"fmt ");
+        bytes.extend_from_slice(&52u64.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // version
+        bytes.extend_from_slice(&0u32.to_le_bytes()); // raw DSD
+        bytes.extend_from_slice(&2u32.to_le_bytes()); // stereo layout
+        bytes.extend_from_slice(&channels.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes()); // bits per sample
+        bytes.extend_from_slice(&(u64::from(sample_rate) * seconds).to_le_bytes());
+        bytes.extend_from_slice(&4096u32.to_le_bytes()); // block size
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(body.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(body);
+        if body.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn dff_file(audio: Vec<u8>) -> Vec<u8> {
+        let mut prop = b"SND ".to_vec();
+        prop.extend(chunk(b"FS  ", &2_822_400u32.to_be_bytes()));
+        let mut chnl = 2u16.to_be_bytes().to_vec();
+        chnl.extend_from_slice(b"SLFTSRGT");
+        prop.extend(chunk(b"CHNL", &chnl));
+        // An odd-sized chunk exercises the padding
+        prop.extend(chunk(b"CMPR", b"DSD \x0enot compressed"));
+
+        let mut form = b"DSD ".to_vec();
+        form.extend(chunk(b"FVER", &0x0105_0000u32.to_be_bytes()));
+        form.extend(chunk(b"PROP", &prop));
+        form.extend(audio);
+        chunk(b"FRM8", &form)
+    }
+
+    #[test]
+    fn test_read_dsf() {
+        let props = read_dsf(&mut Cursor::new(dsf_header(2, 3))).unwrap();
+        assert_eq!(props.duration, 3.0);
+        assert_eq!(props.sample_rate, 2_822_400);
+        assert_eq!(props.channels, 2);
+
+        assert!(read_dsf(&mut Cursor::new(dsf_header(0, 3))).is_none());
+        assert!(read_dsf(&mut Cursor::new(b"RIFF".to_vec())).is_none());
+    }
+
+    #[test]
+    fn test_read_dff_raw_and_dst() {
+        // 2 seconds of stereo DSD64: 2.8224 Mbit per channel per second
+        let raw = chunk(b"DSD ", &vec![0x69; 2 * 2 * 2_822_400 / 8]);
+        let props = read_dff(&mut Cursor::new(dff_file(raw))).unwrap();
+        assert_eq!(props.duration, 2.0);
+        assert_eq!((props.sample_rate, props.channels), (2_822_400, 2));
+
+        // 150 frames at 75 per second
+        let mut frte = 150u32.to_be_bytes().to_vec();
+        frte.extend_from_slice(&75u16.to_be_bytes());
+        let dst = chunk(b"DST ", &chunk(b"FRTE", &frte));
+        let props = read_dff(&mut Cursor::new(dff_file(dst))).unwrap();
+        assert_eq!(props.duration, 2.0);
+
+        assert!(read_dff(&mut Cursor::new(b"FORM".to_vec())).is_none());
+    }
+
+    #[test]
+    fn test_read_tta_with_and_without_id3() {
+        let mut header = b"TTA1".to_vec();
+        header.extend_from_slice(&1u16.to_le_bytes()); // format
+        header.extend_from_slice(&2u16.to_le_bytes()); // channels
+        header.extend_from_slice(&16u16.to_le_bytes()); // bits
+        header.extend_from_slice(&44_100u32.to_le_bytes());
+        header.extend_from_slice(&(44_100u32 * 5).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes()); // CRC
+
+        let props = read_tta(&mut Cursor::new(header.clone())).unwrap();
+        assert_eq!(props.duration, 5.0);
+        assert_eq!((props.sample_rate, props.channels), (44_100, 2));
+
+        // A 200-byte tag: syncsafe 0x01 0x48
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x01\x48".to_vec();
+        tagged.resize(10 + 200, 0);
+        tagged.extend(header);
+        assert_eq!(read_tta(&mut Cursor::new(tagged)).unwrap().duration, 5.0);
+    }
+}
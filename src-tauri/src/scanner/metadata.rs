@@ -3,14 +3,20 @@
 //! Extracts audio metadata from files using the lofty crate.
 //! Supports parallel extraction using rayon.
 
+use lofty::file::FileType;
 use lofty::prelude::*;
 use lofty::probe::Probe;
+use lofty::tag::Tag;
 use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use crate::db::Chapter;
 use crate::scanner::fingerprint::FileFingerprint;
+use crate::scanner::header_props::read_header_props;
 use crate::scanner::{ExtractedMetadata, ScanResult};
 
 /// Extract metadata from a single audio file
@@ -32,6 +38,24 @@ pub fn extract_metadata(filepath: &str) -> ScanResult<ExtractedMetadata> {
         ..Default::default()
     };
 
+    // lofty can't read DSD or TTA, but their headers give the length
+    if let Some(props) = read_header_props(path) {
+        metadata.duration = Some(props.duration);
+        metadata.sample_rate = Some(props.sample_rate);
+        metadata.channels = Some(props.channels);
+        if props.duration > 0.0 {
+            let kbps = fingerprint.size as f64 * 8.0 / props.duration / 1000.0;
+            metadata.bitrate = Some(kbps.round() as u32);
+        }
+        metadata.title = Some(
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+        );
+        return Ok(metadata);
+    }
+
     // Try to read audio file
     let tagged_file = match Probe::open(path) {
         Ok(probe) => match probe.read() {
@@ -92,6 +116,13 @@ pub fn extract_metadata(filepath: &str) -> ScanResult<ExtractedMetadata> {
         metadata.date = tag.year().map(|y| y.to_string());
 
         metadata.genre = tag.genre().map(|s| s.to_string());
+
+        metadata.chapters = vorbis_chapters(tag);
+    }
+
+    // lofty doesn't parse Nero chapters, so read the atom directly
+    if metadata.chapters.is_empty() && tagged_file.file_type() == FileType::Mp4 {
+        metadata.chapters = read_mp4_chapters(path).unwrap_or_default();
     }
 
     // Use filename as title if no title found
@@ -245,6 +276,179 @@ where
     }
 }
 
+/// Largest `chpl` atom read; 255 chapters with maximal titles fit well within
+const MAX_CHPL_SIZE: u64 = 1024 * 1024;
+
+/// Chapters from Vorbis comment `CHAPTERnnn` / `CHAPTERnnnNAME` tags
+fn vorbis_chapters(tag: &Tag) -> Vec<Chapter> {
+    let fields = tag.items().filter_map(|item| match (item.key(), item.value().text()) {
+        (ItemKey::Unknown(key), Some(value)) => Some((key.as_str(), value)),
+        _ => None,
+    });
+    parse_vorbis_chapters(fields)
+}
+
+/// Parse Vorbis chapter fields (`CHAPTER001=00:01:30.000`,
+/// `CHAPTER001NAME=Title`) into chapters ordered by start
+pub fn parse_vorbis_chapters<'a>(
+    fields: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<Chapter> {
+    let mut starts: Vec<(String, u64)> = Vec::new();
+    let mut names: Vec<(String, String)> = Vec::new();
+
+    for (key, value) in fields {
+        let key = key.to_ascii_uppercase();
+        let Some(rest) = key.strip_prefix("CHAPTER") else {
+            continue;
+        };
+        if let Some(number) = rest.strip_suffix("NAME") {
+            names.push((number.to_string(), value.to_string()));
+        } else if !rest.is_empty()
+            && rest.bytes().all(|b| b.is_ascii_digit())
+            && let Some(start_ms) = parse_chapter_time(value)
+        {
+            starts.push((rest.to_string(), start_ms));
+        }
+    }
+
+    let mut chapters: Vec<Chapter> = starts
+        .into_iter()
+        .map(|(number, start_ms)| Chapter {
+            start_ms,
+            title: names
+                .iter()
+                .find(|(n, _)| *n == number)
+                .map(|(_, name)| name.clone()),
+        })
+        .collect();
+    chapters.sort_by_key(|c| c.start_ms);
+    chapters
+}
+
+/// Parse a chapter timestamp (`HH:MM:SS.mmm`, fraction optional) to ms
+pub fn parse_chapter_time(value: &str) -> Option<u64> {
+    let (hms, fraction) = match value.trim().split_once('.') {
+        Some((hms, fraction)) => (hms, fraction),
+        None => (value.trim(), ""),
+    };
+    let mut parts = hms.split(':').map(|p| p.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+
+    // Only millisecond precision is kept
+    let millis = if fraction.is_empty() {
+        0
+    } else {
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse::<u64>().ok()?
+    };
+
+    Some((hours * 3600 + minutes * 60 + seconds) * 1000 + millis)
+}
+
+/// Read Nero chapters from the `moov/udta/chpl` atom of an MP4 file
+fn read_mp4_chapters(path: &Path) -> std::io::Result<Vec<Chapter>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let file_end = reader.seek(SeekFrom::End(0))?;
+
+    let mut range = (0, file_end);
+    for name in [b"moov", b"udta", b"chpl"] {
+        match find_mp4_box(&mut reader, range, name)? {
+            Some(found) => range = found,
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    let (start, end) = range;
+    if end - start > MAX_CHPL_SIZE {
+        return Ok(Vec::new());
+    }
+    let mut data = vec![0; (end - start) as usize];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut data)?;
+
+    Ok(parse_chpl(&data))
+}
+
+/// Find a child box within `(start, end)`, returning its payload range
+fn find_mp4_box<R: Read + Seek>(
+    reader: &mut R,
+    (start, end): (u64, u64),
+    name: &[u8; 4],
+) -> std::io::Result<Option<(u64, u64)>> {
+    let mut pos = start;
+    while pos + 8 <= end {
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+
+        let (header_len, box_len) = match size {
+            // Size 0 means the box runs to the end of its parent
+            0 => (8, end - pos),
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large)?;
+                (16, u64::from_be_bytes(large))
+            }
+            _ => (8, size),
+        };
+        if box_len < header_len || pos + box_len > end {
+            return Ok(None);
+        }
+
+        if &header[4..8] == name {
+            return Ok(Some((pos + header_len, pos + box_len)));
+        }
+        pos += box_len;
+    }
+    Ok(None)
+}
+
+/// Parse a Nero `chpl` payload into chapters ordered by start
+///
+/// Layout: version (1), flags (3), reserved (4, version 1 only), count (1),
+/// then per chapter a start in 100ns units (8) and a length-prefixed title.
+pub fn parse_chpl(data: &[u8]) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    let Some(&version) = data.first() else {
+        return chapters;
+    };
+    let mut pos = if version == 1 { 8 } else { 4 };
+    let Some(&count) = data.get(pos) else {
+        return chapters;
+    };
+    pos += 1;
+
+    for _ in 0..count {
+        let Some(start) = data.get(pos..pos + 8) else {
+            break;
+        };
+        let start_100ns = u64::from_be_bytes(start.try_into().unwrap_or_default());
+        let Some(&title_len) = data.get(pos + 8) else {
+            break;
+        };
+        let title_start = pos + 9;
+        let Some(title) = data.get(title_start..title_start + title_len as usize) else {
+            break;
+        };
+        pos = title_start + title_len as usize;
+
+        let title = String::from_utf8_lossy(title).trim().to_string();
+        chapters.push(Chapter {
+            start_ms: start_100ns / 10_000,
+            title: (!title.is_empty()).then_some(title),
+        });
+    }
+
+    chapters.sort_by_key(|c| c.start_ms);
+    chapters
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -264,9 +468,122 @@ mod tests {
         assert_eq!(metadata.title.unwrap(), "file");
     }
 
+    #[test]
+    fn test_extract_metadata_reads_dsf_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Side A.dsf");
+        std::fs::write(&path, crate::scanner::header_props::tests::dsf_header(2, 4)).unwrap();
+
+        let metadata = extract_metadata(&path.to_string_lossy()).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Side A"));
+        assert_eq!(metadata.duration, Some(4.0));
+        assert_eq!(metadata.sample_rate, Some(2_822_400));
+        assert_eq!(metadata.channels, Some(2));
+    }
+
     #[test]
     fn test_extract_metadata_parallel_empty() {
         let results = extract_metadata_parallel(&[], None::<fn(usize, usize)>);
         assert!(results.is_empty());
     }
+
+    fn chapter(start_ms: u64, title: Option<&str>) -> Chapter {
+        Chapter {
+            start_ms,
+            title: title.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_chapter_time() {
+        assert_eq!(parse_chapter_time("00:00:00.000"), Some(0));
+        assert_eq!(parse_chapter_time("01:02:03.5"), Some(3_723_500));
+        assert_eq!(parse_chapter_time("00:10:00"), Some(600_000));
+        assert_eq!(parse_chapter_time("00:00:01.123456"), Some(1123));
+        assert_eq!(parse_chapter_time("00:61:00"), None);
+        assert_eq!(parse_chapter_time("1:30"), None);
+        assert_eq!(parse_chapter_time("garbage"), None);
+    }
+
+    #[test]
+    fn test_parse_vorbis_chapters() {
+        let fields = [
+            ("CHAPTER002", "00:05:00.000"),
+            ("CHAPTER002NAME", "Second"),
+            ("chapter001", "00:00:00.000"),
+            ("chapter001name", "First"),
+            ("CHAPTER003", "00:09:30.250"),
+            ("CHAPTER004", "not a time"),
+            ("CHAPTERS", "ignored"),
+            ("TITLE", "ignored"),
+        ];
+
+        assert_eq!(
+            parse_vorbis_chapters(fields),
+            vec![
+                chapter(0, Some("First")),
+                chapter(300_000, Some("Second")),
+                chapter(570_250, None),
+            ]
+        );
+    }
+
+    fn chpl_entry(data: &mut Vec<u8>, start_ms: u64, title: &str) {
+        data.extend_from_slice(&(start_ms * 10_000).to_be_bytes());
+        data.push(title.len() as u8);
+        data.extend_from_slice(title.as_bytes());
+    }
+
+    #[test]
+    fn test_parse_chpl() {
+        // Version 1 has 4 reserved bytes after the flags
+        let mut data = vec![1, 0, 0, 0, 0, 0, 0, 0, 3];
+        chpl_entry(&mut data, 0, "Intro");
+        chpl_entry(&mut data, 61_500, "Chapter 1");
+        chpl_entry(&mut data, 125_000, "");
+
+        assert_eq!(
+            parse_chpl(&data),
+            vec![
+                chapter(0, Some("Intro")),
+                chapter(61_500, Some("Chapter 1")),
+                chapter(125_000, None),
+            ]
+        );
+
+        let mut v0 = vec![0, 0, 0, 0, 1];
+        chpl_entry(&mut v0, 1000, "Only");
+        assert_eq!(parse_chpl(&v0), vec![chapter(1000, Some("Only"))]);
+    }
+
+    #[test]
+    fn test_parse_chpl_truncated() {
+        let mut data = vec![0, 0, 0, 0, 2];
+        chpl_entry(&mut data, 0, "Complete");
+        data.extend_from_slice(&[0, 0, 0]);
+
+        assert_eq!(parse_chpl(&data), vec![chapter(0, Some("Complete"))]);
+        assert!(parse_chpl(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_mp4_box_nested() {
+        let mut chpl = vec![0, 0, 0, 0, 1];
+        chpl_entry(&mut chpl, 2000, "Nested");
+
+        let wrap = |name: &[u8; 4], payload: &[u8]| {
+            let mut b = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+            b.extend_from_slice(name);
+            b.extend_from_slice(payload);
+            b
+        };
+        let mut file = wrap(b"ftyp", b"M4A ");
+        file.extend(wrap(b"moov", &wrap(b"udta", &wrap(b"chpl", &chpl))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.m4b");
+        std::fs::write(&path, file).unwrap();
+
+        assert_eq!(read_mp4_chapters(&path).unwrap(), vec![chapter(2000, Some("Nested"))]);
+    }
 }
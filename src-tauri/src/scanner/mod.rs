@@ -12,20 +12,40 @@ pub mod artwork_cache;
 mod benchmarks;
 pub mod commands;
 pub mod fingerprint;
+pub mod folder;
+pub mod header_props;
 pub mod inventory;
 pub mod metadata;
 pub mod scan;
+pub mod throughput;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::Path;
 use thiserror::Error;
 
+use crate::db::Chapter;
+
 /// Supported audio file extensions
+///
+/// The one list the scanner, folder watcher, and file picker all match against.
 pub const AUDIO_EXTENSIONS: &[&str] = &[
-    ".mp3", ".m4a", ".flac", ".ogg", ".wav", ".aac", ".wma", ".opus", ".ape", ".aiff",
+    ".mp3", ".m4a", ".flac", ".ogg", ".wav", ".aac", ".wma", ".opus", ".ape", ".aiff", ".dsf",
+    ".dff", ".mpc", ".wv", ".tta",
 ];
 
+/// Extensions that are scanned and tagged but have no playback decoder
+///
+/// DSD (`.dsf`, `.dff`) would need conversion to PCM first. Musepack,
+/// WavPack, and True Audio have no decoder in the audio engine.
+pub const SCAN_ONLY_EXTENSIONS: &[&str] = &[".dsf", ".dff", ".mpc", ".wv", ".tta"];
+
+/// Maximum number of concurrent filesystem stat calls.
+///
+/// Kept low so libraries on network drives (SMB/NFS) aren't flooded with
+/// requests, which is slower than stat-ing sequentially on most servers.
+pub const MAX_FS_CONCURRENCY: usize = 8;
+
 /// Scanner error types
 #[derive(Error, Debug)]
 pub enum ScanError {
@@ -82,6 +102,8 @@ pub struct ExtractedMetadata {
     pub bitrate: Option<u32>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    /// Chapter markers from MP4 `chpl` or Vorbis CHAPTER tags
+    pub chapters: Vec<Chapter>,
 }
 
 /// Progress event for scan operations
@@ -93,15 +115,37 @@ pub struct ScanProgress {
     pub message: Option<String>,
 }
 
-/// Check if a path has a supported audio extension
-pub fn is_audio_file(path: &Path) -> bool {
+/// Whether `ext`, with or without its leading dot and in any case, is in `extensions`
+fn extension_in(ext: &str, extensions: &[&str]) -> bool {
+    let ext = ext.strip_prefix('.').unwrap_or(ext);
+    extensions
+        .iter()
+        .any(|known| known.trim_start_matches('.').eq_ignore_ascii_case(ext))
+}
+
+/// Whether a path's extension is in `extensions`
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
     path.extension()
         .and_then(|ext| ext.to_str())
-        .map(|ext| {
-            let ext_lower = format!(".{}", ext.to_lowercase());
-            AUDIO_EXTENSIONS.contains(&ext_lower.as_str())
-        })
-        .unwrap_or(false)
+        .is_some_and(|ext| extension_in(ext, extensions))
+}
+
+/// Check if an extension such as `"flac"` or `".FLAC"` is a supported audio format
+///
+/// Anything that filters audio files by extension should go through this or
+/// `is_audio_file`, so the scanner and watcher can't disagree.
+pub fn is_supported_extension(ext: &str) -> bool {
+    extension_in(ext, AUDIO_EXTENSIONS)
+}
+
+/// Check if a path has a supported audio extension
+pub fn is_audio_file(path: &Path) -> bool {
+    has_extension(path, AUDIO_EXTENSIONS)
+}
+
+/// Check if a path is an audio format that can be scanned but not played
+pub fn is_scan_only_file(path: &Path) -> bool {
+    has_extension(path, SCAN_ONLY_EXTENSIONS)
 }
 
 /// Get supported audio extensions as a HashSet for fast lookup
@@ -128,6 +172,29 @@ mod tests {
         assert!(!is_audio_file(Path::new("noext")));
     }
 
+    #[test]
+    fn test_is_supported_extension_normalizes() {
+        for ext in ["flac", ".flac", "FLAC", ".Flac", "aiff", ".ape", "WV"] {
+            assert!(is_supported_extension(ext), "{} should be supported", ext);
+        }
+        for ext in ["", ".", "..flac", "flac ", "jpg", ".mp4"] {
+            assert!(!is_supported_extension(ext), "{:?} should not be supported", ext);
+        }
+    }
+
+    #[test]
+    fn test_is_audio_file_dsd_and_lossless_formats() {
+        for name in ["a.dsf", "a.dff", "a.mpc", "a.wv", "a.tta", "A.DSF", "b.Wv"] {
+            assert!(is_audio_file(Path::new(name)), "{} should be audio", name);
+            assert!(is_scan_only_file(Path::new(name)), "{} should be scan-only", name);
+        }
+        assert!(!is_scan_only_file(Path::new("a.flac")));
+        // Near misses aren't audio
+        for name in ["a.wvc", "a.dsd", "a.mpcx", "dsf", "a.tt"] {
+            assert!(!is_audio_file(Path::new(name)), "{} should not be audio", name);
+        }
+    }
+
     #[test]
     fn test_is_audio_file_with_path() {
         assert!(is_audio_file(&PathBuf::from("/music/artist/album/track.mp3")));
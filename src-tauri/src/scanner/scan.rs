@@ -25,6 +25,18 @@ pub struct ScanResult2Phase {
     pub stats: ScanStats,
 }
 
+impl ScanResult2Phase {
+    /// Whether the scan found anything to add, update, or remove
+    pub fn has_changes(&self) -> bool {
+        !self.added.is_empty() || !self.modified.is_empty() || !self.deleted.is_empty()
+    }
+
+    /// Number of files added, updated, or removed
+    pub fn change_count(&self) -> usize {
+        self.added.len() + self.modified.len() + self.deleted.len()
+    }
+}
+
 /// Progress callback type for scan operations
 pub type ProgressCallback = Box<dyn Fn(ScanProgress) + Send + Sync>;
 
@@ -256,6 +268,33 @@ mod tests {
         assert_eq!(result.stats.added, 1);
     }
 
+    #[test]
+    fn test_scan_2phase_rescan_unchanged_has_no_changes() {
+        let dir = tempdir().unwrap();
+        for name in ["track1.mp3", "track2.flac"] {
+            std::fs::write(dir.path().join(name), format!("content of {}", name)).unwrap();
+        }
+        let paths = [dir.path().to_string_lossy().to_string()];
+
+        let first = scan_2phase(&paths, &HashMap::new(), true, None).unwrap();
+        assert!(first.has_changes());
+
+        // Fingerprints as they'd be stored after the first scan
+        let db_fingerprints: HashMap<String, FileFingerprint> = first
+            .added
+            .iter()
+            .map(|m| {
+                let fp = FileFingerprint::from_path(std::path::Path::new(&m.filepath)).unwrap();
+                (m.filepath.clone(), fp)
+            })
+            .collect();
+
+        let second = scan_2phase(&paths, &db_fingerprints, true, None).unwrap();
+        assert!(!second.has_changes());
+        assert_eq!(second.unchanged.len(), 2);
+        assert_eq!(second.stats.visited, 2);
+    }
+
     #[test]
     fn test_scan_inventory_only() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,246 @@
+//! Scan throughput tracking.
+//!
+//! Turns the raw progress callbacks from a scan into a files-per-second rate
+//! and an ETA, and throttles how often progress is forwarded to the frontend.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::scanner::ScanProgress;
+
+/// Span of recent samples used to compute the rate
+pub const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Minimum time between forwarded progress updates within a phase
+pub const MIN_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Rate and time estimate for a progress update
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Throughput {
+    /// Files processed per second over the recent window
+    pub rate: Option<f64>,
+    /// Estimated seconds until the current phase finishes
+    pub eta_secs: Option<u64>,
+}
+
+/// Tracks scan progress samples over a sliding window
+#[derive(Debug)]
+pub struct ThroughputTracker {
+    window: Duration,
+    min_interval: Duration,
+    phase: Option<String>,
+    samples: VecDeque<(Instant, usize)>,
+    last_emit: Option<Instant>,
+}
+
+impl Default for ThroughputTracker {
+    fn default() -> Self {
+        Self::new(RATE_WINDOW, MIN_EMIT_INTERVAL)
+    }
+}
+
+impl ThroughputTracker {
+    pub fn new(window: Duration, min_interval: Duration) -> Self {
+        Self {
+            window,
+            min_interval,
+            phase: None,
+            samples: VecDeque::new(),
+            last_emit: None,
+        }
+    }
+
+    /// Record a progress update observed at `now`
+    ///
+    /// Returns the current throughput when the update should be forwarded, or
+    /// `None` when it falls inside the throttle interval. Phase changes and
+    /// the final update of a phase are always forwarded.
+    pub fn record(&mut self, progress: &ScanProgress, now: Instant) -> Option<Throughput> {
+        let phase_changed = self.phase.as_deref() != Some(progress.phase.as_str());
+        if phase_changed {
+            self.phase = Some(progress.phase.clone());
+            self.samples.clear();
+        }
+
+        self.samples.push_back((now, progress.current));
+        while let Some(&(t, _)) = self.samples.front() {
+            if now.duration_since(t) > self.window && self.samples.len() > 2 {
+                self.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let finished = progress.total > 0 && progress.current >= progress.total;
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= self.min_interval);
+        if !(phase_changed || finished || due) {
+            return None;
+        }
+        self.last_emit = Some(now);
+
+        let rate = self.rate();
+        // Total is unknown until inventory finishes walking, so no ETA before then
+        let eta_secs = match (rate, progress.total) {
+            (Some(rate), total) if total > 0 && rate > 0.0 => {
+                let remaining = total.saturating_sub(progress.current) as f64;
+                Some((remaining / rate).ceil() as u64)
+            }
+            _ => None,
+        };
+
+        Some(Throughput { rate, eta_secs })
+    }
+
+    /// Files per second across the samples in the window
+    fn rate(&self) -> Option<f64> {
+        let (first_t, first_n) = *self.samples.front()?;
+        let (last_t, last_n) = *self.samples.back()?;
+        let elapsed = last_t.duration_since(first_t).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        Some(last_n.saturating_sub(first_n) as f64 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(phase: &str, current: usize, total: usize) -> ScanProgress {
+        ScanProgress {
+            phase: phase.to_string(),
+            current,
+            total,
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_rate_and_eta_during_parse() {
+        let mut tracker = ThroughputTracker::new(Duration::from_secs(5), Duration::ZERO);
+        let start = Instant::now();
+
+        let first = tracker.record(&progress("parse", 0, 1000), start).unwrap();
+        assert_eq!(first.rate, None);
+        assert_eq!(first.eta_secs, None);
+
+        // 200 files in 2 seconds = 100 files/sec, 800 remaining = 8 seconds
+        let later = tracker
+            .record(
+                &progress("parse", 200, 1000),
+                start + Duration::from_secs(2),
+            )
+            .unwrap();
+        assert_eq!(later.rate, Some(100.0));
+        assert_eq!(later.eta_secs, Some(8));
+    }
+
+    #[test]
+    fn test_no_eta_during_inventory() {
+        let mut tracker = ThroughputTracker::new(Duration::from_secs(5), Duration::ZERO);
+        let start = Instant::now();
+
+        tracker.record(&progress("inventory", 0, 0), start);
+        let update = tracker
+            .record(
+                &progress("inventory", 500, 0),
+                start + Duration::from_secs(1),
+            )
+            .unwrap();
+
+        assert_eq!(update.rate, Some(500.0));
+        assert_eq!(update.eta_secs, None);
+    }
+
+    #[test]
+    fn test_rate_uses_sliding_window() {
+        let mut tracker = ThroughputTracker::new(Duration::from_secs(2), Duration::ZERO);
+        let start = Instant::now();
+
+        // Slow start: 10 files/sec for 4 seconds
+        for s in 0..=4 {
+            tracker.record(
+                &progress("parse", s * 10, 1000),
+                start + Duration::from_secs(s as u64),
+            );
+        }
+        // Then 100 files/sec
+        tracker.record(
+            &progress("parse", 140, 1000),
+            start + Duration::from_secs(5),
+        );
+        let update = tracker
+            .record(
+                &progress("parse", 240, 1000),
+                start + Duration::from_secs(6),
+            )
+            .unwrap();
+
+        // Window covers t=4..6: (240 - 40) / 2
+        assert_eq!(update.rate, Some(100.0));
+    }
+
+    #[test]
+    fn test_phase_change_resets_samples() {
+        let mut tracker = ThroughputTracker::new(Duration::from_secs(5), Duration::ZERO);
+        let start = Instant::now();
+
+        tracker.record(&progress("inventory", 0, 0), start);
+        tracker.record(
+            &progress("inventory", 1000, 0),
+            start + Duration::from_secs(1),
+        );
+
+        let update = tracker
+            .record(&progress("parse", 0, 10), start + Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(update.rate, None);
+    }
+
+    #[test]
+    fn test_throttles_updates_within_interval() {
+        let mut tracker =
+            ThroughputTracker::new(Duration::from_secs(5), Duration::from_millis(250));
+        let start = Instant::now();
+
+        assert!(tracker.record(&progress("parse", 0, 100), start).is_some());
+        assert!(
+            tracker
+                .record(
+                    &progress("parse", 5, 100),
+                    start + Duration::from_millis(100)
+                )
+                .is_none()
+        );
+        assert!(
+            tracker
+                .record(
+                    &progress("parse", 10, 100),
+                    start + Duration::from_millis(300)
+                )
+                .is_some()
+        );
+
+        // The final update of a phase is never dropped
+        assert!(
+            tracker
+                .record(
+                    &progress("parse", 100, 100),
+                    start + Duration::from_millis(350)
+                )
+                .is_some()
+        );
+        // Nor is a phase change
+        assert!(
+            tracker
+                .record(
+                    &progress("complete", 100, 100),
+                    start + Duration::from_millis(360)
+                )
+                .is_some()
+        );
+    }
+}
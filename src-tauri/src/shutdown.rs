@@ -0,0 +1,212 @@
+//! Coordinated shutdown.
+//!
+//! Background tasks are spawned through `Shutdown::spawn`, which hands each a
+//! `ShutdownSignal` to stop on. `shutdown` runs once, from `app_shutdown` or
+//! the exit event, whichever comes first: it stops the folder watchers,
+//! signals the tasks and waits up to `TASK_TIMEOUT` for them, then
+//! checkpoints the database. Queue changes are written as they happen, so the
+//! checkpoint carries them into the main database file. The queue is cleared
+//! on the next launch anyway, so the playback position isn't kept.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::db::{self, Database};
+use crate::watcher::WatcherManager;
+
+/// Longest wait for background tasks to stop
+pub const TASK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Lets a background task find out the app is shutting down
+#[derive(Clone)]
+pub struct ShutdownSignal(watch::Receiver<bool>);
+
+impl ShutdownSignal {
+    pub fn is_shutting_down(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Wait until shutdown starts
+    pub async fn cancelled(&mut self) {
+        // The sender only goes away on shutdown, so an error means the same
+        let _ = self.0.wait_for(|stop| *stop).await;
+    }
+
+    /// Sleep for `duration`, returning false if shutdown starts first
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => true,
+            _ = self.cancelled() => false,
+        }
+    }
+}
+
+/// Background tasks to stop on shutdown, managed as Tauri state
+pub struct Shutdown {
+    stop_tx: watch::Sender<bool>,
+    tasks: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+    started: AtomicBool,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (stop_tx, _) = watch::channel(false);
+        Self {
+            stop_tx,
+            tasks: Mutex::new(Vec::new()),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal(self.stop_tx.subscribe())
+    }
+
+    /// Spawn a background task that shutdown signals and waits for
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F)
+    where
+        F: FnOnce(ShutdownSignal) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tauri::async_runtime::spawn(task(self.signal()));
+        self.tasks.lock().push((name, handle));
+    }
+
+    /// Signal every task to stop and wait up to `timeout` for them all
+    ///
+    /// Tasks still running when the wait runs out are aborted, and their
+    /// names returned.
+    pub async fn stop_tasks(&self, timeout: Duration) -> Vec<&'static str> {
+        self.stop_tx.send_replace(true);
+
+        let tasks = std::mem::take(&mut *self.tasks.lock());
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut stuck = Vec::new();
+        for (name, mut handle) in tasks {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!("Background task {} failed: {}", name, e),
+                Err(_) => {
+                    handle.abort();
+                    stuck.push(name);
+                }
+            }
+        }
+        stuck
+    }
+
+    /// Claim the shutdown, false if it already ran
+    fn begin(&self) -> bool {
+        !self.started.swap(true, Ordering::SeqCst)
+    }
+}
+
+/// Stop background work and flush state to the database
+///
+/// Only the first call does anything.
+pub async fn shutdown(app: &AppHandle) {
+    let Some(shutdown) = app.try_state::<Shutdown>() else {
+        return;
+    };
+    if !shutdown.begin() {
+        return;
+    }
+    info!("Shutting down");
+
+    if let Some(watcher) = app.try_state::<WatcherManager>() {
+        watcher.stop().await;
+    }
+    let stuck = shutdown.stop_tasks(TASK_TIMEOUT).await;
+    if !stuck.is_empty() {
+        warn!(
+            "Aborted background tasks still running at shutdown: {}",
+            stuck.join(", ")
+        );
+    }
+
+    if let Some(db) = app.try_state::<Database>() {
+        flush_database(&db);
+    }
+}
+
+/// Stop background tasks and flush state before the window closes
+///
+/// The exit handler runs the same shutdown when this wasn't called, and does
+/// nothing more when it was.
+#[tauri::command]
+pub async fn app_shutdown(app: AppHandle) -> Result<(), String> {
+    shutdown(&app).await;
+    Ok(())
+}
+
+fn flush_database(db: &Database) {
+    // Keep query planner statistics fresh for the next launch
+    if let Err(e) = db.with_maintenance_conn(db::maintenance::optimize_on_close) {
+        error!("Database PRAGMA optimize on shutdown failed: {}", e);
+    }
+    // Leave an empty WAL behind instead of one sized by the session's writes
+    let truncate = |conn: &rusqlite::Connection| {
+        db::maintenance::checkpoint(conn, db::maintenance::CheckpointMode::Truncate)
+    };
+    if let Err(e) = db.with_maintenance_conn(truncate) {
+        error!("Database WAL checkpoint on shutdown failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_stop_tasks_ends_background_loop() {
+        let shutdown = Shutdown::new();
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let counter = ticks.clone();
+        shutdown.spawn("ticker", |mut signal| async move {
+            while signal.sleep(Duration::from_millis(10)).await {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        shutdown.spawn("stuck", |_| std::future::pending::<()>());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let started = Instant::now();
+        let stuck = shutdown.stop_tasks(Duration::from_millis(200)).await;
+        assert_eq!(stuck, ["stuck"]);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // The loop has exited, so it stops ticking
+        let after = ticks.load(Ordering::SeqCst);
+        assert!(after > 0);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), after);
+
+        // Tasks subscribing late still see the shutdown
+        let mut late = shutdown.signal();
+        assert!(late.is_shutting_down());
+        assert!(!late.sleep(Duration::from_secs(10)).await);
+    }
+
+    #[test]
+    fn test_shutdown_runs_once() {
+        let shutdown = Shutdown::new();
+        assert!(shutdown.begin());
+        assert!(!shutdown.begin());
+    }
+}
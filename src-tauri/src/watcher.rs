@@ -8,20 +8,78 @@ use notify_debouncer_full::{
     notify::{self, RecursiveMode},
     DebounceEventResult, Debouncer, RecommendedCache,
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, State};
 use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
 
-use crate::db::{library, watched, Database, TrackMetadata, WatchedFolder as DbWatchedFolder};
+use crate::db::{settings, watched, Database, WatchedFolder as DbWatchedFolder};
 use crate::events::{EventEmitter, LibraryUpdatedEvent, ScanCompleteEvent, ScanProgressEvent};
-use crate::scanner::fingerprint::{compute_content_hash, FileFingerprint};
+use crate::scanner::fingerprint::FileFingerprint;
+use crate::scanner::commands::{apply_scan_result, checkpoint_after_scan};
 use crate::scanner::scan::{scan_2phase, ProgressCallback};
-use crate::scanner::ExtractedMetadata;
+use crate::scanner::throughput::ThroughputTracker;
+use crate::scanner::is_supported_extension;
+
+/// Whether a filesystem event on `path` concerns a file the scanner would pick up
+fn is_watched_audio_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(is_supported_extension)
+}
+
+/// How a watched folder is kept up to date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Rescanned once each time watching starts
+    Startup,
+    /// Filesystem watcher plus a rescan every `cadence_minutes`
+    Continuous,
+    /// Scanned once when added, then only by `watched_folders_rescan`
+    Manual,
+}
+
+impl WatchMode {
+    /// Whether the folder gets a watcher task, counted in `active_watchers`
+    pub fn has_watcher(self) -> bool {
+        self != WatchMode::Manual
+    }
+}
+
+impl FromStr for WatchMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "startup" => Ok(WatchMode::Startup),
+            "continuous" => Ok(WatchMode::Continuous),
+            "manual" => Ok(WatchMode::Manual),
+            _ => Err(()),
+        }
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<WatchMode, String> {
+    mode.parse().map_err(|_| {
+        format!("Invalid mode '{}', expected 'startup', 'continuous' or 'manual'", mode)
+    })
+}
+
+/// The folders that get a watcher: enabled, in a mode other than manual
+fn folders_with_watchers(folders: Vec<WatchedFolder>) -> Vec<WatchedFolder> {
+    folders
+        .into_iter()
+        .filter(|f| f.enabled && f.mode.parse().is_ok_and(WatchMode::has_watcher))
+        .collect()
+}
 
 /// Watched folder response for frontend (matches existing API contract)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,11 +112,24 @@ pub struct WatcherStatus {
     pub message: Option<String>,
 }
 
+impl WatcherStatus {
+    /// Status for a rescan that found no added, modified, or deleted files
+    pub fn up_to_date(folder_id: i64, visited: usize) -> Self {
+        Self {
+            folder_id,
+            status: "up-to-date".to_string(),
+            message: Some(format!("Checked {} files, no changes", visited)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct ScanProgress {
     pub folder_id: i64,
     pub percent: Option<u8>,
     pub stage: Option<String>,
+    pub rate: Option<f64>,
+    pub eta_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -76,11 +147,174 @@ pub struct FsEvent {
     pub paths: Vec<String>,
 }
 
+/// What asked for a rescan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RescanTrigger {
+    /// Watcher start-up and the periodic cadence
+    Scheduled,
+    /// Filesystem changes reported by a continuous watcher
+    FsEvent,
+    /// The user asked for it
+    Manual,
+}
+
+/// Setting holding how far, in percent of the cadence, a scheduled rescan may
+/// drift either way
+pub const CADENCE_JITTER_SETTING: &str = "watcher_cadence_jitter_percent";
+
+/// Setting holding the least time, in seconds, between the starts of any two
+/// scheduled rescans; 0 turns spacing off
+pub const MIN_RESCAN_GAP_SETTING: &str = "watcher_min_rescan_gap_secs";
+
+/// Jitter used when the setting is unset, enough to spread folders added
+/// together
+const DEFAULT_CADENCE_JITTER_PERCENT: u32 = 10;
+
+/// Jitter is capped so a rescan never comes sooner than half its cadence
+const MAX_CADENCE_JITTER_PERCENT: u32 = 50;
+
+/// Read a numeric watcher setting, `None` when unset or unparseable
+fn numeric_setting(db: &Database, key: &str) -> Option<u64> {
+    let conn = db.conn().ok()?;
+    settings::get_setting(&conn, key)
+        .ok()
+        .flatten()
+        .and_then(|v| v.trim_matches('"').parse().ok())
+}
+
+/// Delay until a folder's next scheduled rescan
+///
+/// The cadence moves by a uniformly random amount of up to `jitter_percent`
+/// either way, so folders sharing a cadence drift apart while each still
+/// rescans at its nominal cadence on average.
+fn jittered_cadence(cadence: Duration, jitter_percent: u32, rng: &mut impl Rng) -> Duration {
+    let spread = f64::from(jitter_percent.min(MAX_CADENCE_JITTER_PERCENT)) / 100.0;
+    if spread == 0.0 {
+        return cadence;
+    }
+    cadence.mul_f64(1.0 + rng.random_range(-spread..=spread))
+}
+
+/// How long a rescan must wait to start `gap` after the previous one
+fn rescan_gap_wait(last_start: Option<Instant>, now: Instant, gap: Duration) -> Duration {
+    last_start.map_or(Duration::ZERO, |last| (last + gap).saturating_duration_since(now))
+}
+
+/// Keeps scheduled rescans of different folders a minimum gap apart
+///
+/// A rescan that would start too soon after the previous one waits; rescans
+/// that arrive meanwhile queue behind it in order.
+#[derive(Debug, Default)]
+pub struct RescanSpacer {
+    last_start: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl RescanSpacer {
+    /// Wait until a rescan may start, and record that it has
+    pub async fn wait_turn(&self, gap: Duration) {
+        if gap.is_zero() {
+            return;
+        }
+        let mut last_start = self.last_start.lock().await;
+        let wait = rescan_gap_wait(*last_start, Instant::now(), gap);
+        if !wait.is_zero() {
+            debug!("Delaying scheduled rescan by {:?} to keep rescans apart", wait);
+            tokio::time::sleep(wait).await;
+        }
+        *last_start = Some(Instant::now());
+    }
+}
+
+/// Global pause switch shared by every watcher
+///
+/// While paused, scheduled and FS-triggered rescans are skipped, leaving
+/// only manual rescans. FS changes are dropped, or with buffering on, kept
+/// per folder until resume. The folders' `enabled` flags are untouched.
+#[derive(Debug, Default)]
+pub struct PauseGate {
+    inner: Mutex<PauseInner>,
+}
+
+#[derive(Debug, Default)]
+struct PauseInner {
+    paused: bool,
+    buffer_events: bool,
+    /// Changed paths seen while paused, by folder
+    buffered: HashMap<i64, BTreeSet<String>>,
+}
+
+impl PauseGate {
+    /// Pause, returning false if already paused
+    pub fn pause(&self, buffer_events: bool) -> bool {
+        let mut inner = self.inner.lock();
+        if inner.paused {
+            return false;
+        }
+        inner.paused = true;
+        inner.buffer_events = buffer_events;
+        true
+    }
+
+    /// Resume, returning the buffered changed paths by folder, or `None` if
+    /// not paused
+    pub fn resume(&self) -> Option<HashMap<i64, Vec<String>>> {
+        let mut inner = self.inner.lock();
+        if !inner.paused {
+            return None;
+        }
+        inner.paused = false;
+        inner.buffer_events = false;
+        let buffered = std::mem::take(&mut inner.buffered);
+        Some(
+            buffered
+                .into_iter()
+                .map(|(folder_id, paths)| (folder_id, paths.into_iter().collect()))
+                .collect(),
+        )
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.inner.lock().paused
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.paused && inner.buffer_events
+    }
+
+    /// Whether a rescan from `trigger` may run now
+    pub fn allows(&self, trigger: RescanTrigger) -> bool {
+        trigger == RescanTrigger::Manual || !self.is_paused()
+    }
+
+    /// Take FS changes for a folder while paused
+    ///
+    /// Returns false when not paused, meaning the caller should handle them
+    /// as usual. Otherwise they are buffered or dropped and the caller skips
+    /// them.
+    pub fn hold_fs_event(&self, folder_id: i64, paths: &[String]) -> bool {
+        let mut inner = self.inner.lock();
+        if !inner.paused {
+            return false;
+        }
+        if inner.buffer_events {
+            inner
+                .buffered
+                .entry(folder_id)
+                .or_default()
+                .extend(paths.iter().cloned());
+        }
+        true
+    }
+}
+
 /// Manages filesystem watchers for watched folders
 pub struct WatcherManager {
     app: AppHandle,
     db: Database,
     active_watchers: Arc<RwLock<HashMap<i64, WatcherHandle>>>,
+    pause: Arc<PauseGate>,
+    spacer: Arc<RescanSpacer>,
 }
 
 struct WatcherHandle {
@@ -97,6 +331,8 @@ impl WatcherManager {
             app,
             db,
             active_watchers: Arc::new(RwLock::new(HashMap::new())),
+            pause: Arc::new(PauseGate::default()),
+            spacer: Arc::new(RescanSpacer::default()),
         }
     }
 
@@ -107,10 +343,8 @@ impl WatcherManager {
     pub async fn start(&self) -> Result<(), String> {
         let folders = self.fetch_enabled_folders()?;
 
-        for folder in folders {
-            if folder.enabled {
-                self.start_watching(folder).await?;
-            }
+        for folder in folders_with_watchers(folders) {
+            self.start_watching(folder).await?;
         }
 
         Ok(())
@@ -130,6 +364,76 @@ impl WatcherManager {
         self.active_watchers.read().len()
     }
 
+    /// IDs of the folders with a running watcher, in order
+    pub fn watching_folder_ids(&self) -> Vec<i64> {
+        let mut ids: Vec<i64> = self.active_watchers.read().keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_paused()
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        self.pause.is_buffering()
+    }
+
+    /// Stop all automatic rescans until `resume_all`
+    ///
+    /// Watcher tasks are stopped. FS watchers are dropped too, unless
+    /// `buffer_events` is set, in which case they keep recording changes to
+    /// report on resume. Returns false if already paused.
+    pub async fn pause_all(&self, buffer_events: bool) -> bool {
+        if !self.pause.pause(buffer_events) {
+            return false;
+        }
+        info!("Pausing all watchers (buffer_events={})", buffer_events);
+
+        let cancel_txs: Vec<_> = if buffer_events {
+            let watchers = self.active_watchers.read();
+            watchers.values().map(|h| h.cancel_tx.clone()).collect()
+        } else {
+            let mut watchers = self.active_watchers.write();
+            watchers.drain().map(|(_, h)| h.cancel_tx).collect()
+        };
+        for tx in cancel_txs {
+            let _ = tx.send(()).await;
+        }
+        true
+    }
+
+    /// Restart every enabled folder's watcher, rescanning each once
+    ///
+    /// Changes buffered while paused are reported as `watched-folder:fs-event`
+    /// first. Returns false if not paused.
+    pub async fn resume_all(&self) -> Result<bool, String> {
+        let Some(buffered) = self.pause.resume() else {
+            return Ok(false);
+        };
+        info!("Resuming all watchers");
+
+        for (folder_id, paths) in buffered {
+            let _ = self.app.emit(
+                "watched-folder:fs-event",
+                FsEvent {
+                    folder_id,
+                    event_type: "change".to_string(),
+                    paths,
+                },
+            );
+        }
+
+        // Drop whatever is left so every folder restarts from the current settings
+        let stale: Vec<i64> = self.active_watchers.read().keys().copied().collect();
+        for folder_id in stale {
+            self.remove_folder(folder_id).await;
+        }
+        // Starting a watcher runs its catch-up rescan
+        self.start().await?;
+        Ok(true)
+    }
+
     fn fetch_enabled_folders(&self) -> Result<Vec<WatchedFolder>, String> {
         let conn = self.db.conn().map_err(|e| e.to_string())?;
         let folders = watched::get_enabled_watched_folders(&conn)
@@ -137,22 +441,51 @@ impl WatcherManager {
         Ok(folders.into_iter().map(WatchedFolder::from).collect())
     }
 
+    /// Start a folder's watcher; a manual folder gets no watcher, only its
+    /// first scan if it has never been scanned
+    async fn start_folder(&self, folder: WatchedFolder) -> Result<(), String> {
+        if folder.mode.parse() != Ok(WatchMode::Manual) {
+            return self.start_watching(folder).await;
+        }
+        if folder.last_scanned_at.is_none() {
+            info!("Indexing manual folder {} for the first time", folder.id);
+            let (app, db, pause) = (self.app.clone(), self.db.clone(), self.pause.clone());
+            tokio::spawn(async move {
+                Self::trigger_rescan(&app, &db, &pause, folder.id, RescanTrigger::Manual).await;
+            });
+        }
+        Ok(())
+    }
+
+    /// Start the watcher task, plus an FS watcher for continuous folders
+    ///
+    /// Manual folders, and modes this version doesn't know, are skipped.
     async fn start_watching(&self, folder: WatchedFolder) -> Result<(), String> {
+        let mode = match folder.mode.parse::<WatchMode>() {
+            Ok(mode) if mode.has_watcher() => mode,
+            _ => {
+                debug!("No watcher for folder {} (mode={})", folder.id, folder.mode);
+                return Ok(());
+            }
+        };
         let (cancel_tx, mut cancel_rx) = mpsc::channel::<()>(1);
 
         let app = self.app.clone();
         let db = self.db.clone();
+        let pause = self.pause.clone();
+        let spacer = self.spacer.clone();
         let folder_id = folder.id;
-        let mode = folder.mode.clone();
         let cadence_minutes = folder.cadence_minutes.unwrap_or(10) as u64;
         let folder_path = folder.path.clone();
+        let jitter_percent = numeric_setting(&self.db, CADENCE_JITTER_SETTING)
+            .map_or(DEFAULT_CADENCE_JITTER_PERCENT, |v| v.min(100) as u32);
 
-        println!(
-            "[watcher] Starting watcher for folder {} (mode={}, cadence={}min)",
-            folder_id, mode, cadence_minutes
+        info!(
+            "Starting watcher for folder {} (mode={}, cadence={}min, jitter={}%)",
+            folder_id, folder.mode, cadence_minutes, jitter_percent
         );
 
-        let fs_watcher = if mode == "continuous" {
+        let fs_watcher = if mode == WatchMode::Continuous {
             self.create_fs_watcher(folder_id, &folder_path)
         } else {
             None
@@ -166,23 +499,30 @@ impl WatcherManager {
 
         self.active_watchers.write().insert(folder.id, handle);
 
+        // Seeded per folder so watchers started together get different delays
+        let mut rng = StdRng::from_rng(&mut rand::rng());
+
         tokio::spawn(async move {
-            if mode == "startup" {
-                Self::trigger_rescan(&app, &db, folder_id).await;
-            } else if mode == "continuous" {
-                Self::trigger_rescan(&app, &db, folder_id).await;
+            if mode == WatchMode::Startup {
+                Self::scheduled_rescan(&app, &db, &pause, &spacer, folder_id).await;
+            } else if mode == WatchMode::Continuous {
+                Self::scheduled_rescan(&app, &db, &pause, &spacer, folder_id).await;
 
-                let mut interval =
-                    tokio::time::interval(Duration::from_secs(cadence_minutes * 60));
-                interval.tick().await;
+                // Deadlines advance from the previous deadline rather than from
+                // when the rescan finished, so the cadence holds on average
+                let cadence = Duration::from_secs(cadence_minutes * 60);
+                let mut next_run = tokio::time::Instant::now();
 
                 loop {
+                    next_run = (next_run + jittered_cadence(cadence, jitter_percent, &mut rng))
+                        .max(tokio::time::Instant::now());
                     tokio::select! {
-                        _ = interval.tick() => {
-                            Self::trigger_rescan(&app, &db, folder_id).await;
+                        _ = tokio::time::sleep_until(next_run) => {
+                            Self::scheduled_rescan(&app, &db, &pause, &spacer, folder_id)
+                                .await;
                         }
                         _ = cancel_rx.recv() => {
-                            println!("[watcher] Stopping watcher for folder {}", folder_id);
+                            info!("Stopping watcher for folder {}", folder_id);
                             break;
                         }
                     }
@@ -200,11 +540,12 @@ impl WatcherManager {
     ) -> Option<Debouncer<notify::RecommendedWatcher, RecommendedCache>> {
         let app = self.app.clone();
         let db = self.db.clone();
+        let pause = self.pause.clone();
         let path = PathBuf::from(folder_path);
 
         if !path.exists() {
-            eprintln!(
-                "[watcher] Cannot watch folder {}: path does not exist",
+            warn!(
+                "Cannot watch folder {}: path does not exist",
                 folder_path
             );
             return None;
@@ -229,20 +570,8 @@ impl WatcherManager {
                                 | notify::EventKind::Remove(_) => {
                                     has_changes = true;
                                     for p in &event.paths {
-                                        if let Some(ext) = p.extension() {
-                                            let ext_lower = ext.to_string_lossy().to_lowercase();
-                                            if matches!(
-                                                ext_lower.as_str(),
-                                                "mp3" | "flac"
-                                                    | "m4a"
-                                                    | "ogg"
-                                                    | "wav"
-                                                    | "aac"
-                                                    | "wma"
-                                                    | "opus"
-                                            ) {
-                                                event_paths.push(p.to_string_lossy().to_string());
-                                            }
+                                        if is_watched_audio_path(p) {
+                                            event_paths.push(p.to_string_lossy().to_string());
                                         }
                                     }
                                 }
@@ -251,8 +580,13 @@ impl WatcherManager {
                         }
 
                         if has_changes && !event_paths.is_empty() {
-                            println!(
-                                "[watcher] FS events detected for folder {}: {} files changed",
+                            // Buffered or dropped while all watchers are paused
+                            if pause.hold_fs_event(folder_id, &event_paths) {
+                                return;
+                            }
+
+                            debug!(
+                                "FS events detected for folder {}: {} files changed",
                                 folder_id,
                                 event_paths.len()
                             );
@@ -268,15 +602,23 @@ impl WatcherManager {
 
                             let app_clone = app.clone();
                             let db_clone = db.clone();
+                            let pause_clone = pause.clone();
                             runtime_handle.spawn(async move {
-                                Self::trigger_rescan(&app_clone, &db_clone, folder_id).await;
+                                Self::trigger_rescan(
+                                    &app_clone,
+                                    &db_clone,
+                                    &pause_clone,
+                                    folder_id,
+                                    RescanTrigger::FsEvent,
+                                )
+                                .await;
                             });
                         }
                     }
                     Err(errors) => {
                         for error in errors {
-                            eprintln!(
-                                "[watcher] FS watcher error for folder {}: {:?}",
+                            warn!(
+                                "FS watcher error for folder {}: {:?}",
                                 folder_id, error
                             );
                         }
@@ -287,21 +629,21 @@ impl WatcherManager {
         match debouncer_result {
             Ok(mut debouncer) => {
                 if let Err(e) = debouncer.watch(&path, RecursiveMode::Recursive) {
-                    eprintln!(
-                        "[watcher] Failed to start watching folder {}: {:?}",
+                    error!(
+                        "Failed to start watching folder {}: {:?}",
                         folder_path, e
                     );
                     return None;
                 }
-                println!(
-                    "[watcher] FS watcher active for folder {} at {}",
+                info!(
+                    "FS watcher active for folder {} at {}",
                     folder_id, folder_path
                 );
                 Some(debouncer)
             }
             Err(e) => {
-                eprintln!(
-                    "[watcher] Failed to create debouncer for folder {}: {:?}",
+                error!(
+                    "Failed to create debouncer for folder {}: {:?}",
                     folder_path, e
                 );
                 None
@@ -309,9 +651,37 @@ impl WatcherManager {
         }
     }
 
+    /// Run a start-up or cadence rescan once the spacer allows it
+    async fn scheduled_rescan(
+        app: &AppHandle,
+        db: &Database,
+        pause: &PauseGate,
+        spacer: &RescanSpacer,
+        folder_id: i64,
+    ) {
+        let gap = numeric_setting(db, MIN_RESCAN_GAP_SETTING).unwrap_or(0);
+        spacer.wait_turn(Duration::from_secs(gap)).await;
+        Self::trigger_rescan(app, db, pause, folder_id, RescanTrigger::Scheduled).await;
+    }
+
     /// Trigger a rescan for a watched folder using native Rust scanner
-    async fn trigger_rescan(app: &AppHandle, db: &Database, folder_id: i64) {
-        println!("[watcher] Triggering rescan for folder {}", folder_id);
+    ///
+    /// Does nothing while watchers are paused, unless the user asked for it.
+    async fn trigger_rescan(
+        app: &AppHandle,
+        db: &Database,
+        pause: &PauseGate,
+        folder_id: i64,
+        trigger: RescanTrigger,
+    ) {
+        if !pause.allows(trigger) {
+            debug!(
+                "Skipping {:?} rescan for folder {}: watchers paused",
+                trigger, folder_id
+            );
+            return;
+        }
+        info!("Triggering rescan for folder {}", folder_id);
 
         let _ = app.emit(
             "watched-folder:status",
@@ -327,7 +697,7 @@ impl WatcherManager {
             let conn = match db.conn() {
                 Ok(c) => c,
                 Err(e) => {
-                    eprintln!("[watcher] Failed to get DB connection: {}", e);
+                    error!("Failed to get DB connection: {}", e);
                     return;
                 }
             };
@@ -336,11 +706,11 @@ impl WatcherManager {
             match folder_result {
                 Ok(Some(f)) => f,
                 Ok(None) => {
-                    eprintln!("[watcher] Folder {} not found", folder_id);
+                    warn!("Folder {} not found", folder_id);
                     return;
                 }
                 Err(e) => {
-                    eprintln!("[watcher] Failed to get folder {}: {}", folder_id, e);
+                    error!("Failed to get folder {}: {}", folder_id, e);
                     return;
                 }
             }
@@ -356,7 +726,7 @@ impl WatcherManager {
             let conn = match db.conn() {
                 Ok(c) => c,
                 Err(e) => {
-                    eprintln!("[watcher] Failed to get DB connection: {}", e);
+                    error!("Failed to get DB connection: {}", e);
                     return;
                 }
             };
@@ -366,7 +736,7 @@ impl WatcherManager {
             {
                 Ok(s) => s,
                 Err(e) => {
-                    eprintln!("[watcher] Failed to prepare fingerprint query: {}", e);
+                    error!("Failed to prepare fingerprint query: {}", e);
                     return;
                 }
             };
@@ -384,8 +754,12 @@ impl WatcherManager {
         // Create progress callback
         let app_for_progress = app.clone();
         let job_id_for_progress = job_id.clone();
+        let throughput = Mutex::new(ThroughputTracker::default());
         let progress_callback: ProgressCallback =
             Box::new(move |progress: crate::scanner::ScanProgress| {
+                let Some(estimate) = throughput.lock().record(&progress, Instant::now()) else {
+                    return;
+                };
                 let _ = app_for_progress.emit_scan_progress(ScanProgressEvent {
                     job_id: job_id_for_progress.clone(),
                     status: progress.phase.clone(),
@@ -393,6 +767,8 @@ impl WatcherManager {
                     found: 0,
                     errors: 0,
                     current_path: progress.message.clone(),
+                    rate: estimate.rate,
+                    eta_secs: estimate.eta_secs,
                 });
             });
 
@@ -410,7 +786,7 @@ impl WatcherManager {
         {
             Ok(Ok(r)) => r,
             Ok(Err(e)) => {
-                eprintln!("[watcher] Scan failed for folder {}: {}", folder_id, e);
+                error!("Scan failed for folder {}: {}", folder_id, e);
                 let _ = app.emit(
                     "watched-folder:status",
                     WatcherStatus {
@@ -422,7 +798,7 @@ impl WatcherManager {
                 return;
             }
             Err(e) => {
-                eprintln!("[watcher] Scan task panicked for folder {}: {}", folder_id, e);
+                error!("Scan task panicked for folder {}: {}", folder_id, e);
                 let _ = app.emit(
                     "watched-folder:status",
                     WatcherStatus {
@@ -440,7 +816,7 @@ impl WatcherManager {
             let conn = match db.conn() {
                 Ok(c) => c,
                 Err(e) => {
-                    eprintln!("[watcher] Failed to get DB connection: {}", e);
+                    error!("Failed to get DB connection: {}", e);
                     return;
                 }
             };
@@ -448,131 +824,56 @@ impl WatcherManager {
             let modified_count = scan_result.modified.len();
             let deleted_count = scan_result.deleted.len();
 
-            // IMPORTANT: Mark deleted tracks as missing FIRST
-            // This is required because reconciliation of "added" tracks looks for tracks
-            // where missing=1. If a file is moved (delete + add in same scan), we need to
-            // mark the old path as missing before we can reconcile it with the new path.
-            if !scan_result.deleted.is_empty() {
-                for filepath in &scan_result.deleted {
-                    let mark_result = library::mark_track_missing_by_filepath(&conn, filepath);
-                    if let Err(e) = mark_result {
-                        eprintln!("[watcher] Failed to mark track missing: {}", e);
-                    }
-                }
-            }
-
-            // Process "added" tracks - check for moves first, then add truly new tracks
-            // Now that deleted tracks are marked missing, reconciliation by inode/hash will work
-            let mut added_count = 0;
-            let mut reconciled_count = 0;
-            if !scan_result.added.is_empty() {
-                let mut truly_new: Vec<(String, TrackMetadata)> = Vec::new();
-
-                for m in &scan_result.added {
-                    let mut was_reconciled = false;
-
-                    if let Some(inode) = m.file_inode {
-                        let track_result = library::find_missing_track_by_inode(&conn, inode);
-                        if let Ok(Some(track)) = track_result {
-                            let reconcile_result = library::reconcile_moved_track(
-                                &conn,
-                                track.id,
-                                &m.filepath,
-                                Some(inode),
-                            );
-                            if reconcile_result.is_ok() {
-                                reconciled_count += 1;
-                                was_reconciled = true;
-                                println!(
-                                    "[watcher] Reconciled moved track {} by inode: {} -> {}",
-                                    track.id, track.filepath, m.filepath
-                                );
-                            }
-                        }
-                    }
-
-                    if !was_reconciled
-                        && let Ok(hash) = compute_content_hash(std::path::Path::new(&m.filepath)) {
-                            let track_result = library::find_missing_track_by_content_hash(&conn, &hash);
-                            if let Ok(Some(track)) = track_result {
-                                let reconcile_result = library::reconcile_moved_track(
-                                    &conn,
-                                    track.id,
-                                    &m.filepath,
-                                    m.file_inode,
-                                );
-                                if reconcile_result.is_ok() {
-                                    reconciled_count += 1;
-                                    was_reconciled = true;
-                                    println!(
-                                        "[watcher] Reconciled moved track {} by content hash: {} -> {}",
-                                        track.id, track.filepath, m.filepath
-                                    );
-                                }
-                            }
-                        }
-
-                    if !was_reconciled {
-                        truly_new.push((m.filepath.clone(), to_track_metadata(m)));
-                    }
-                }
-
-                // Add truly new tracks to database
-                if !truly_new.is_empty() {
-                    added_count = truly_new.len();
-                    if let Err(e) = library::add_tracks_bulk(&conn, &truly_new) {
-                        eprintln!("[watcher] Failed to add tracks: {}", e);
-                    }
-                }
-            }
-
-            // Update modified tracks
-            if !scan_result.modified.is_empty() {
-                let updates: Vec<(String, TrackMetadata)> = scan_result
-                    .modified
-                    .iter()
-                    .map(|m| (m.filepath.clone(), to_track_metadata(m)))
-                    .collect();
-
-                if let Err(e) = library::update_tracks_bulk(&conn, &updates) {
-                    eprintln!("[watcher] Failed to update tracks: {}", e);
-                }
-            }
-
-            // Clear missing flag for unchanged files that were previously missing but have reappeared
-            // This handles the case where a file is moved out and then moved back to the same location
-            let mut recovered_count = 0;
-            if !scan_result.unchanged.is_empty() {
-                match library::mark_tracks_present_by_filepaths(&conn, &scan_result.unchanged) {
-                    Ok(count) => {
-                        if count > 0 {
-                            recovered_count = count;
-                            println!(
-                                "[watcher] Recovered {} previously missing track(s) that reappeared",
-                                count
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[watcher] Failed to mark recovered tracks present: {}", e);
-                    }
+            let applied = match apply_scan_result(&conn, &scan_result) {
+                Ok(applied) => applied,
+                Err(e) => {
+                    error!("Failed to apply scan for folder {}: {}", folder_id, e);
+                    return;
                 }
-            }
+            };
 
             // Update last_scanned_at timestamp
             if let Err(e) = watched::update_watched_folder_last_scanned(&conn, folder_id) {
-                eprintln!("[watcher] Failed to update last_scanned_at: {}", e);
+                error!("Failed to update last_scanned_at: {}", e);
             }
 
             (
-                (added_count + reconciled_count + recovered_count) as i32,
+                (applied.added + applied.reconciled + applied.recovered) as i32,
                 modified_count as i32,
                 deleted_count as i32,
             )
         };
 
-        println!(
-            "[watcher] Folder {} scan complete: +{} ~{} -{}",
+        let changed = scan_result.change_count();
+        let checkpoint_db = db.clone();
+        let _ = tokio::task::spawn_blocking(move || checkpoint_after_scan(&checkpoint_db, changed))
+            .await;
+
+        let visited = scan_result.stats.visited;
+
+        // Nothing changed: signal "up to date" so the UI can drop its spinner,
+        // and skip the results and library-updated emissions entirely
+        if added == 0 && updated == 0 && deleted == 0 {
+            debug!(
+                "Folder {} up to date ({} files checked)",
+                folder_id, visited
+            );
+
+            let _ = app.emit_scan_complete(ScanCompleteEvent {
+                job_id: job_id.clone(),
+                added: 0,
+                skipped: scan_result.unchanged.len() as u32,
+                errors: scan_result.stats.errors as u32,
+                duration_ms: 0,
+                visited: visited as u32,
+                up_to_date: true,
+            });
+            let _ = app.emit("watched-folder:status", WatcherStatus::up_to_date(folder_id, visited));
+            return;
+        }
+
+        info!(
+            "Folder {} scan complete: +{} ~{} -{}",
             folder_id, added, updated, deleted
         );
 
@@ -583,6 +884,8 @@ impl WatcherManager {
             skipped: scan_result.unchanged.len() as u32,
             errors: scan_result.stats.errors as u32,
             duration_ms: 0,
+            visited: visited as u32,
+            up_to_date: false,
         });
 
         // Emit results event
@@ -619,7 +922,7 @@ impl WatcherManager {
 
     pub async fn add_folder(&self, folder: WatchedFolder) -> Result<(), String> {
         if folder.enabled {
-            self.start_watching(folder).await?;
+            self.start_folder(folder).await?;
         }
         Ok(())
     }
@@ -638,33 +941,15 @@ impl WatcherManager {
     pub async fn update_folder(&self, folder: WatchedFolder) -> Result<(), String> {
         self.remove_folder(folder.id).await;
         if folder.enabled {
-            self.start_watching(folder).await?;
+            self.start_folder(folder).await?;
         }
         Ok(())
     }
 
-    /// Trigger a manual rescan for a specific folder
+    /// Trigger a manual rescan for a specific folder, even while paused
     pub async fn rescan_folder(&self, folder_id: i64) {
-        Self::trigger_rescan(&self.app, &self.db, folder_id).await;
-    }
-}
-
-/// Convert ExtractedMetadata to database TrackMetadata
-fn to_track_metadata(m: &ExtractedMetadata) -> TrackMetadata {
-    let content_hash = compute_content_hash(std::path::Path::new(&m.filepath)).ok();
-    TrackMetadata {
-        title: m.title.clone(),
-        artist: m.artist.clone(),
-        album: m.album.clone(),
-        album_artist: m.album_artist.clone(),
-        track_number: m.track_number.clone(),
-        track_total: m.track_total.clone(),
-        date: m.date.clone(),
-        duration: m.duration,
-        file_size: Some(m.file_size),
-        file_mtime_ns: m.file_mtime_ns,
-        file_inode: m.file_inode,
-        content_hash,
+        Self::trigger_rescan(&self.app, &self.db, &self.pause, folder_id, RescanTrigger::Manual)
+            .await;
     }
 }
 
@@ -720,6 +1005,7 @@ pub async fn watched_folders_add(
     }
 
     let mode = request.mode.unwrap_or_else(|| "continuous".to_string());
+    parse_mode(&mode)?;
     let cadence_minutes = request.cadence_minutes.unwrap_or(10);
     let enabled = request.enabled.unwrap_or(true);
 
@@ -754,6 +1040,10 @@ pub async fn watched_folders_update(
     request: UpdateWatchedFolderRequest,
     state: State<'_, WatcherManager>,
 ) -> Result<WatchedFolder, String> {
+    if let Some(mode) = &request.mode {
+        parse_mode(mode)?;
+    }
+
     let folder = {
         let conn = state.get_db().conn().map_err(|e| e.to_string())?;
 
@@ -821,14 +1111,41 @@ pub async fn watched_folders_rescan(
     Ok(())
 }
 
-/// Get the current watcher status (number of active watchers)
+/// Get the current watcher status (active watchers, global pause)
+///
+/// `watching` lists the folders with a watcher; manual folders never have one.
 #[tauri::command]
 pub fn watched_folders_status(state: State<'_, WatcherManager>) -> serde_json::Value {
     serde_json::json!({
         "active_watchers": state.active_watcher_count(),
+        "watching": state.watching_folder_ids(),
+        "paused": state.is_paused(),
+        "buffer_events": state.is_buffering(),
     })
 }
 
+/// Pause automatic rescans for all watched folders
+///
+/// With `buffer_events`, filesystem changes seen while paused are reported
+/// on resume instead of being dropped. Returns false if already paused.
+#[tauri::command]
+pub async fn watched_folders_pause_all(
+    buffer_events: Option<bool>,
+    state: State<'_, WatcherManager>,
+) -> Result<bool, String> {
+    Ok(state.pause_all(buffer_events.unwrap_or(false)).await)
+}
+
+/// Resume all watchers and rescan each folder once to catch up
+///
+/// Returns false if watchers weren't paused.
+#[tauri::command]
+pub async fn watched_folders_resume_all(
+    state: State<'_, WatcherManager>,
+) -> Result<bool, String> {
+    state.resume_all().await
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -929,6 +1246,15 @@ mod tests {
         assert!(json.contains("\"message\":null"));
     }
 
+    #[test]
+    fn test_watcher_status_up_to_date() {
+        let status = WatcherStatus::up_to_date(7, 12000);
+
+        assert_eq!(status.folder_id, 7);
+        assert_eq!(status.status, "up-to-date");
+        assert_eq!(status.message.as_deref(), Some("Checked 12000 files, no changes"));
+    }
+
     // -------------------------------------------------------------------------
     // ScanProgress tests
     // -------------------------------------------------------------------------
@@ -939,12 +1265,15 @@ mod tests {
             folder_id: 5,
             percent: Some(75),
             stage: Some("Scanning metadata".to_string()),
+            rate: Some(80.0),
+            eta_secs: Some(12),
         };
 
         let json = serde_json::to_string(&progress).unwrap();
         assert!(json.contains("\"folder_id\":5"));
         assert!(json.contains("\"percent\":75"));
         assert!(json.contains("\"stage\":\"Scanning metadata\""));
+        assert!(json.contains("\"eta_secs\":12"));
     }
 
     #[test]
@@ -953,6 +1282,8 @@ mod tests {
             folder_id: 1,
             percent: None,
             stage: None,
+            rate: None,
+            eta_secs: None,
         };
 
         let json = serde_json::to_string(&progress).unwrap();
@@ -1029,6 +1360,56 @@ mod tests {
         assert!(json.contains("\"paths\":[]"));
     }
 
+    // -------------------------------------------------------------------------
+    // PauseGate tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_pause_gate_blocks_automatic_rescans() {
+        let gate = PauseGate::default();
+        assert!(gate.allows(RescanTrigger::Scheduled));
+        assert!(gate.allows(RescanTrigger::FsEvent));
+
+        assert!(gate.pause(false));
+        assert!(!gate.pause(true), "second pause is a no-op");
+        assert!(gate.is_paused());
+        assert!(!gate.is_buffering());
+        assert!(!gate.allows(RescanTrigger::Scheduled));
+        assert!(!gate.allows(RescanTrigger::FsEvent));
+        assert!(gate.allows(RescanTrigger::Manual));
+
+        assert!(gate.resume().is_some());
+        assert!(gate.resume().is_none(), "second resume is a no-op");
+        assert!(gate.allows(RescanTrigger::Scheduled));
+        assert!(gate.allows(RescanTrigger::FsEvent));
+    }
+
+    #[test]
+    fn test_pause_gate_drops_or_buffers_fs_events() {
+        let gate = PauseGate::default();
+        let paths = vec!["/music/a.mp3".to_string(), "/music/b.flac".to_string()];
+
+        // Not paused: the caller handles events itself
+        assert!(!gate.hold_fs_event(1, &paths));
+
+        gate.pause(false);
+        assert!(gate.hold_fs_event(1, &paths));
+        assert!(gate.resume().unwrap().is_empty());
+
+        gate.pause(true);
+        assert!(gate.is_buffering());
+        assert!(gate.hold_fs_event(1, &paths));
+        assert!(gate.hold_fs_event(1, &paths[..1]));
+        assert!(gate.hold_fs_event(2, &paths[1..]));
+        let buffered = gate.resume().unwrap();
+        assert_eq!(buffered[&1], paths);
+        assert_eq!(buffered[&2], vec!["/music/b.flac".to_string()]);
+
+        // Buffering ends with the pause
+        assert!(!gate.is_buffering());
+        assert!(!gate.hold_fs_event(1, &paths));
+    }
+
     // -------------------------------------------------------------------------
     // AddWatchedFolderRequest tests
     // -------------------------------------------------------------------------
@@ -1115,6 +1496,43 @@ mod tests {
         assert_eq!(continuous_folder.mode, "continuous");
     }
 
+    #[test]
+    fn test_watch_mode_parsing() {
+        assert_eq!("startup".parse(), Ok(WatchMode::Startup));
+        assert_eq!("continuous".parse(), Ok(WatchMode::Continuous));
+        assert_eq!("manual".parse(), Ok(WatchMode::Manual));
+        assert!(parse_mode("watch").unwrap_err().contains("'manual'"));
+
+        assert!(WatchMode::Startup.has_watcher());
+        assert!(WatchMode::Continuous.has_watcher());
+        assert!(!WatchMode::Manual.has_watcher());
+    }
+
+    #[test]
+    fn test_manual_folder_persisted_without_watcher() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        crate::db::schema::create_tables(&conn).unwrap();
+        crate::db::schema::run_migrations(&conn).unwrap();
+        let archive = watched::add_watched_folder(&conn, "/music/archive", "manual", 10, true)
+            .unwrap()
+            .unwrap();
+
+        let folders: Vec<WatchedFolder> = watched::get_enabled_watched_folders(&conn)
+            .unwrap()
+            .into_iter()
+            .map(WatchedFolder::from)
+            .collect();
+        assert_eq!(folders.len(), 1);
+        assert_eq!((folders[0].id, folders[0].mode.as_str()), (archive.id, "manual"));
+        assert!(folders_with_watchers(folders.clone()).is_empty());
+
+        let mut continuous = folders[0].clone();
+        continuous.mode = "continuous".to_string();
+        let started = folders_with_watchers(vec![continuous, folders[0].clone()]);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].mode, "continuous");
+    }
+
     // -------------------------------------------------------------------------
     // Cadence validation tests
     // -------------------------------------------------------------------------
@@ -1158,14 +1576,13 @@ mod tests {
 
     #[test]
     fn test_supported_audio_extensions() {
-        let supported = ["mp3", "flac", "m4a", "ogg", "wav", "aac", "wma", "opus"];
+        let supported = [
+            "mp3", "flac", "m4a", "ogg", "wav", "aac", "wma", "opus", "dsf", "dff", "mpc", "wv",
+            "tta",
+        ];
 
         for ext in supported.iter() {
-            let ext_lower = ext.to_lowercase();
-            let is_audio = matches!(
-                ext_lower.as_str(),
-                "mp3" | "flac" | "m4a" | "ogg" | "wav" | "aac" | "wma" | "opus"
-            );
+            let is_audio = is_watched_audio_path(&PathBuf::from(format!("/music/track.{}", ext)));
             assert!(is_audio, "Extension {} should be supported", ext);
         }
     }
@@ -1175,25 +1592,17 @@ mod tests {
         let unsupported = ["txt", "jpg", "png", "pdf", "doc", "mp4", "avi"];
 
         for ext in unsupported.iter() {
-            let ext_lower = ext.to_lowercase();
-            let is_audio = matches!(
-                ext_lower.as_str(),
-                "mp3" | "flac" | "m4a" | "ogg" | "wav" | "aac" | "wma" | "opus"
-            );
+            let is_audio = is_watched_audio_path(&PathBuf::from(format!("/music/file.{}", ext)));
             assert!(!is_audio, "Extension {} should not be supported", ext);
         }
     }
 
     #[test]
     fn test_case_insensitive_extensions() {
-        let extensions = ["MP3", "FLAC", "M4A", "Ogg", "WAV", "AAC", "WMA", "OPUS"];
+        let extensions = ["MP3", "FLAC", "M4A", "Ogg", "WAV", "AAC", "WMA", "OPUS", "DSF", "Wv"];
 
         for ext in extensions.iter() {
-            let ext_lower = ext.to_lowercase();
-            let is_audio = matches!(
-                ext_lower.as_str(),
-                "mp3" | "flac" | "m4a" | "ogg" | "wav" | "aac" | "wma" | "opus"
-            );
+            let is_audio = is_watched_audio_path(&PathBuf::from(format!("/music/track.{}", ext)));
             assert!(
                 is_audio,
                 "Extension {} (case insensitive) should be supported",
@@ -1201,4 +1610,101 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_watcher_and_scanner_accept_same_extensions() {
+        use crate::scanner::{is_audio_file, AUDIO_EXTENSIONS};
+
+        let mut candidates: Vec<String> = AUDIO_EXTENSIONS
+            .iter()
+            .map(|ext| ext.trim_start_matches('.'))
+            .flat_map(|ext| [ext.to_string(), ext.to_uppercase()])
+            .collect();
+        candidates.extend(["txt", "jpg", "mp4", "cue", "m3u", "wvc", "aif"].map(String::from));
+
+        for ext in &candidates {
+            let path = PathBuf::from(format!("/music/track.{}", ext));
+            assert_eq!(
+                is_watched_audio_path(&path),
+                is_audio_file(&path),
+                "watcher and scanner disagree on {}",
+                path.display()
+            );
+        }
+        for ext in AUDIO_EXTENSIONS {
+            let path = PathBuf::from(format!("/music/track{}", ext));
+            assert!(is_watched_audio_path(&path), "watcher ignores {}", ext);
+        }
+    }
+
+    // -------------------------------------------------------------------------
+    // Rescan jitter and spacing tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_same_cadence_gets_distinct_jittered_delays() {
+        let cadence = Duration::from_secs(10 * 60);
+        let mut first = StdRng::seed_from_u64(1);
+        let mut second = StdRng::seed_from_u64(2);
+
+        let a = jittered_cadence(cadence, 10, &mut first);
+        let b = jittered_cadence(cadence, 10, &mut second);
+        assert_ne!(a, b);
+        for delay in [a, b] {
+            assert!(delay >= Duration::from_secs(540) && delay <= Duration::from_secs(660));
+        }
+
+        assert_eq!(jittered_cadence(cadence, 0, &mut first), cadence);
+    }
+
+    #[test]
+    fn test_jittered_cadence_averages_to_nominal() {
+        let cadence = Duration::from_secs(600);
+        let mut rng = StdRng::seed_from_u64(7);
+        let samples = 10_000;
+        let total: f64 = (0..samples)
+            .map(|_| jittered_cadence(cadence, 100, &mut rng).as_secs_f64())
+            .sum();
+        assert!((total / samples as f64 - 600.0).abs() < 6.0);
+
+        // Jitter is capped at 50%, so never below half the cadence
+        let min = (0..samples)
+            .map(|_| jittered_cadence(cadence, 100, &mut rng))
+            .min()
+            .unwrap();
+        assert!(min >= Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_rescan_gap_wait() {
+        let now = Instant::now();
+        let gap = Duration::from_secs(30);
+        assert_eq!(rescan_gap_wait(None, now, gap), Duration::ZERO);
+        assert_eq!(
+            rescan_gap_wait(Some(now - Duration::from_secs(10)), now, gap),
+            Duration::from_secs(20)
+        );
+        assert_eq!(
+            rescan_gap_wait(Some(now - Duration::from_secs(45)), now, gap),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spacer_queues_overlapping_rescans() {
+        let spacer = Arc::new(RescanSpacer::default());
+        let gap = Duration::from_millis(50);
+        let start = Instant::now();
+
+        let turns: Vec<_> = (0..3)
+            .map(|_| {
+                let spacer = spacer.clone();
+                tokio::spawn(async move { spacer.wait_turn(gap).await })
+            })
+            .collect();
+        for turn in turns {
+            turn.await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
 }